@@ -22,6 +22,7 @@ fn bench_index_full(c: &mut Criterion) {
         watch_once_paths: None,
         db_path,
         data_dir: data_dir.clone(),
+        gc: false,
         progress: None,
     };
 
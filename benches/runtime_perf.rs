@@ -19,6 +19,7 @@ fn sample_conv(i: i64, msgs: i64) -> NormalizedConversation {
             content: format!("conversation {i} message {m} lorem ipsum dolor sit amet"),
             extra: serde_json::json!({}),
             snippets: Vec::new(),
+            source: None,
         });
     }
     NormalizedConversation {
@@ -152,6 +153,7 @@ fn wildcard_sample_conv(i: i64, msgs: i64) -> NormalizedConversation {
             ),
             extra: serde_json::json!({}),
             snippets: Vec::new(),
+            source: None,
         });
     }
     NormalizedConversation {
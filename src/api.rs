@@ -0,0 +1,328 @@
+//! Read-only queries behind `cass serve` (HTTP) and `cass mcp`.
+//!
+//! Both servers take the same parameters and answer with the same JSON, so an
+//! editor plugin and an agent asking for the same thing see the same result.
+//! Parameters deserialize from query strings and from JSON tool arguments.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use thiserror::Error;
+
+use crate::search::query::{SearchClient, SearchFilters};
+use crate::sources::provenance::SourceFilter;
+use crate::storage::sqlite::SqliteStorage;
+
+/// Hits returned by a search unless the caller asks for a number
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+const MAX_SEARCH_LIMIT: usize = 100;
+/// Hits counted for facets, as `cass search --aggregate` does
+const FACET_SAMPLE: usize = 1000;
+/// Messages returned per conversation page unless asked otherwise
+const DEFAULT_MESSAGE_LIMIT: usize = 100;
+const DEFAULT_WORKSPACE_LIMIT: usize = 50;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    /// No index or database yet
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("{0:#}")]
+    Internal(#[from] anyhow::Error),
+}
+
+pub type ApiResult<T = Value> = std::result::Result<T, ApiError>;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchParams {
+    #[serde(alias = "q")]
+    pub query: String,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub agent: Option<String>,
+    pub workspace: Option<String>,
+    /// `local`, `remote`, or a source name
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FacetParams {
+    /// Defaults to every message
+    #[serde(alias = "q")]
+    pub query: Option<String>,
+    /// Comma-separated: agent, workspace, date, match_type
+    pub fields: Option<String>,
+    pub agent: Option<String>,
+    pub workspace: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ConversationParams {
+    /// Conversation id or the agent's session id
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub id: String,
+    pub agent: Option<String>,
+    pub source: Option<String>,
+    pub from_idx: Option<i64>,
+    /// 0 for all messages
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WorkspaceParams {
+    /// 0 for all workspaces
+    pub limit: Option<usize>,
+}
+
+/// Conversation ids are numbers in JSON but strings in URLs and session ids
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a string or number, got {other}"
+        ))),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|s| !s.is_empty())
+}
+
+fn filters(
+    agent: &Option<String>,
+    workspace: &Option<String>,
+    source: &Option<String>,
+) -> SearchFilters {
+    let mut filters = SearchFilters::default();
+    if let Some(agent) = non_empty(agent) {
+        filters.agents = HashSet::from([agent.to_string()]);
+    }
+    if let Some(workspace) = non_empty(workspace) {
+        filters.workspaces = HashSet::from([workspace.to_string()]);
+    }
+    if let Some(source) = non_empty(source) {
+        filters.source_filter = SourceFilter::parse(source);
+    }
+    filters
+}
+
+pub struct Api {
+    data_dir: PathBuf,
+    db_path: PathBuf,
+    /// Opened on the first search, so servers start before an index exists.
+    /// The client isn't `Sync`, so concurrent searches take turns.
+    client: Mutex<Option<SearchClient>>,
+}
+
+impl Api {
+    pub fn new(data_dir: PathBuf, db_path: PathBuf) -> Self {
+        Self {
+            data_dir,
+            db_path,
+            client: Mutex::new(None),
+        }
+    }
+
+    fn with_client<T>(&self, f: impl FnOnce(&SearchClient) -> anyhow::Result<T>) -> ApiResult<T> {
+        let mut client = self.client.lock().unwrap_or_else(|e| e.into_inner());
+        if client.is_none() {
+            let index_path = crate::search::tantivy::index_dir(&self.data_dir)?;
+            *client = Some(
+                SearchClient::open(&index_path, Some(&self.db_path))?.ok_or_else(|| {
+                    ApiError::Unavailable(format!(
+                        "index not found at {}; run 'cass index --full' first",
+                        index_path.display()
+                    ))
+                })?,
+            );
+        }
+        Ok(f(client.as_ref().expect("opened above"))?)
+    }
+
+    fn storage(&self) -> ApiResult<SqliteStorage> {
+        if !self.db_path.exists() {
+            return Err(ApiError::Unavailable(format!(
+                "database not found at {}; run 'cass index --full' first",
+                self.db_path.display()
+            )));
+        }
+        Ok(SqliteStorage::open_readonly(&self.db_path)?)
+    }
+
+    /// Matching messages, best first, each with the `conversation_id` to fetch it by
+    /// (null when the hit's file holds several conversations it can't be told apart in)
+    pub fn search(&self, params: &SearchParams) -> ApiResult {
+        let query = params.query.trim();
+        if query.is_empty() {
+            return Err(ApiError::BadRequest("'query' is required".to_string()));
+        }
+        let limit = params
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT);
+        let offset = params.offset.unwrap_or(0);
+        let filters = filters(&params.agent, &params.workspace, &params.source);
+        let hits = self.with_client(|c| c.search(query, filters, limit, offset))?;
+
+        // Hits carry no conversation id; resolve it so callers can follow up
+        let storage = self.storage().ok();
+        let hits: Vec<Value> = hits
+            .iter()
+            .map(|hit| {
+                let conversation_id = storage.as_ref().and_then(|s| {
+                    s.hit_conversation_id(
+                        &hit.source_id,
+                        &hit.agent,
+                        &hit.source_path,
+                        hit.created_at,
+                    )
+                    .ok()
+                    .flatten()
+                });
+                json!({
+                    "conversation_id": conversation_id,
+                    "title": hit.title,
+                    "agent": hit.agent,
+                    "workspace": hit.workspace,
+                    "source_path": hit.source_path,
+                    "source_id": hit.source_id,
+                    "line_number": hit.line_number,
+                    "created_at": hit.created_at,
+                    "score": hit.score,
+                    "snippet": hit.snippet,
+                })
+            })
+            .collect();
+        Ok(json!({
+            "query": query,
+            "offset": offset,
+            "count": hits.len(),
+            "hits": hits,
+        }))
+    }
+
+    /// Hit counts by agent, workspace, date or match type, like `cass search --aggregate`
+    pub fn facets(&self, params: &FacetParams) -> ApiResult {
+        let query = non_empty(&params.query).unwrap_or("*");
+        let fields = params
+            .fields
+            .as_deref()
+            .unwrap_or("agent,workspace,date")
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(|f| {
+                crate::AggregateField::from_str(f).ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "unknown facet '{f}'; use agent, workspace, date or match_type"
+                    ))
+                })
+            })
+            .collect::<ApiResult<Vec<_>>>()?;
+        let filters = filters(&params.agent, &params.workspace, &params.source);
+        let hits = self.with_client(|c| c.search(query, filters, FACET_SAMPLE, 0))?;
+        Ok(json!({
+            "query": query,
+            "sampled_hits": hits.len(),
+            "facets": crate::compute_aggregations(&hits, &fields),
+        }))
+    }
+
+    /// One conversation with a page of its messages, like `cass show --json`
+    pub fn conversation(&self, params: &ConversationParams) -> ApiResult {
+        let id = params.id.trim();
+        if id.is_empty() {
+            return Err(ApiError::BadRequest("'id' is required".to_string()));
+        }
+        let source = non_empty(&params.source)
+            .map(SourceFilter::parse)
+            .unwrap_or_default();
+        let limit = params.limit.unwrap_or(DEFAULT_MESSAGE_LIMIT);
+
+        let storage = self.storage()?;
+        let mut conv =
+            crate::find_conversation_ref(&storage, id, non_empty(&params.agent), &source).map_err(
+                |e| match e.code {
+                    3 => ApiError::NotFound(e.message),
+                    _ => ApiError::Internal(anyhow::anyhow!(e.message)),
+                },
+            )?;
+        let page = storage.fetch_messages_page(
+            conv.id.unwrap_or_default(),
+            params.from_idx.unwrap_or(0),
+            if limit == 0 { u32::MAX as usize } else { limit },
+        )?;
+        conv.messages = page.messages;
+        let mut payload = serde_json::to_value(&conv).map_err(anyhow::Error::from)?;
+        payload["total_messages"] = page.total.into();
+        payload["next_idx"] = page.next_idx.into();
+        Ok(payload)
+    }
+
+    /// Workspaces by recent activity, like `cass workspaces --json`
+    pub fn workspaces(&self, params: &WorkspaceParams) -> ApiResult {
+        let limit = params.limit.unwrap_or(DEFAULT_WORKSPACE_LIMIT);
+        let workspaces = self.storage()?.workspace_activity(limit)?;
+        Ok(json!({
+            "workspaces": workspaces.iter().map(|w| json!({
+                "path": w.path,
+                "conversations": w.conversations,
+                "agents": w.agents,
+                "last_activity": w.last_activity
+                    .and_then(chrono::DateTime::from_timestamp_millis)
+                    .map(|d| d.to_rfc3339()),
+            })).collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Index totals with per-agent and per-source conversation counts
+    pub fn stats(&self) -> ApiResult {
+        let storage = self.storage()?;
+        let conn = storage.raw();
+        let count =
+            |sql: &str| -> anyhow::Result<i64> { Ok(conn.query_row(sql, [], |r| r.get(0))?) };
+        let grouped = |sql: &str| -> anyhow::Result<Value> {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt
+                .query_map([], |r| {
+                    Ok(json!({ "name": r.get::<_, String>(0)?, "conversations": r.get::<_, i64>(1)? }))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(Value::Array(rows))
+        };
+        let (oldest, newest): (Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT MIN(started_at), MAX(COALESCE(ended_at, started_at)) FROM conversations",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .map_err(anyhow::Error::from)?;
+        let time = |ms: Option<i64>| {
+            ms.and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|d| d.to_rfc3339())
+        };
+        Ok(json!({
+            "conversations": count("SELECT COUNT(*) FROM conversations")?,
+            "messages": count("SELECT COUNT(*) FROM messages")?,
+            "workspaces": count("SELECT COUNT(DISTINCT workspace_id) FROM conversations")?,
+            "oldest": time(oldest),
+            "newest": time(newest),
+            "agents": grouped(
+                "SELECT a.slug, COUNT(*) FROM conversations c JOIN agents a ON c.agent_id = a.id
+                 GROUP BY a.slug ORDER BY 2 DESC"
+            )?,
+            "sources": grouped(
+                "SELECT source_id, COUNT(*) FROM conversations GROUP BY source_id ORDER BY 2 DESC"
+            )?,
+        }))
+    }
+}
@@ -0,0 +1,570 @@
+//! Tamper-evident audit log of agent actions (`cass audit`).
+//!
+//! Every tool call recorded in the matching sessions becomes one entry: shell
+//! commands, file edits and writes (with line counts), and other tool uses
+//! with the file they touched. Entries are emitted as JSON Lines in
+//! chronological order and chained: each carries the previous entry's hash,
+//! and its own `hash` is the HMAC-SHA256 of `prev_hash` followed by the
+//! entry's JSON without the `hash` field. The key is `audit.key` in the data
+//! dir, created on the first export, so only a holder of the key can
+//! re-chain an edited log. Editing, dropping or reordering any entry breaks
+//! every later hash, which `cass audit --verify` detects with the same key.
+//! Recording the final hash elsewhere also makes truncation detectable.
+
+use std::io::BufRead;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use ring::hmac;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::connectors::tool_call_file_path;
+use crate::diff::diff_stats;
+use crate::model::tool_calls::{ToolCall, tool_calls};
+
+/// `prev_hash` of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// File in the data dir holding the chain key (hex)
+pub const KEY_FILE: &str = "audit.key";
+
+/// The chain key in `data_dir`, created (readable by the owner only) if missing.
+pub fn load_or_create_key(data_dir: &Path) -> Result<hmac::Key> {
+    let path = data_dir.join(KEY_FILE);
+    if !path.exists() {
+        use ring::rand::SecureRandom;
+        let mut bytes = [0u8; 32];
+        ring::rand::SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow::anyhow!("system random number generator unavailable"))?;
+        let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        std::fs::create_dir_all(data_dir)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("creating {}", path.display()))?;
+        std::io::Write::write_all(&mut file, hex.as_bytes())?;
+    }
+    load_key(data_dir)
+}
+
+/// The chain key in `data_dir`; verifying needs the key the log was exported with.
+pub fn load_key(data_dir: &Path) -> Result<hmac::Key> {
+    let path = data_dir.join(KEY_FILE);
+    let hex = std::fs::read_to_string(&path)
+        .with_context(|| format!("reading audit key {}", path.display()))?;
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("{} is not a 64-digit hex key", path.display());
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default())
+        .collect();
+    Ok(hmac::Key::new(hmac::HMAC_SHA256, &bytes))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// Workspace path; subdirectories match too
+    pub workspace: Option<String>,
+    pub agents: Vec<String>,
+    /// Only actions at or after this timestamp (ms)
+    pub since: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Command,
+    FileEdit,
+    FileWrite,
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp: Option<i64>,
+    pub workspace: String,
+    pub agent: String,
+    pub conversation_id: i64,
+    pub source_path: String,
+    pub message_idx: i64,
+    pub action: ActionKind,
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines_added: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines_removed: Option<usize>,
+    pub prev_hash: String,
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl AuditEntry {
+    /// HMAC-SHA256 of `prev_hash` followed by this entry's JSON without `hash`.
+    pub fn digest(&self, key: &hmac::Key) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("hash");
+        }
+        let mut ctx = hmac::Context::with_key(key);
+        ctx.update(self.prev_hash.as_bytes());
+        ctx.update(value.to_string().as_bytes());
+        ctx.sign()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+/// One action before it is placed in the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Action {
+    kind: ActionKind,
+    tool: String,
+    path: Option<String>,
+    command: Option<String>,
+    lines: Option<(usize, usize)>,
+}
+
+/// Chained actions from every message matching `query`, oldest first.
+pub fn collect_actions(
+    conn: &Connection,
+    query: &AuditQuery,
+    key: &hmac::Key,
+) -> Result<Vec<AuditEntry>> {
+    let mut sql = String::from(
+        "SELECT COALESCE(w.path, ''), a.slug, c.id, c.source_path, m.idx,
+                COALESCE(m.created_at, c.started_at), cass_text(m.content), cass_text(m.extra_json)
+         FROM messages m
+         JOIN conversations c ON m.conversation_id = c.id
+         JOIN agents a ON c.agent_id = a.id
+         LEFT JOIN workspaces w ON c.workspace_id = w.id
+         WHERE 1 = 1",
+    );
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(ws) = &query.workspace {
+        let ws = ws.trim_end_matches('/').to_string();
+        // `%` and `_` in the path itself must match literally
+        let pattern = format!(
+            "{}/%",
+            ws.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        sql.push_str(&format!(
+            " AND (w.path = ?{} OR w.path LIKE ?{} ESCAPE '\\')",
+            params.len() + 1,
+            params.len() + 2
+        ));
+        params.push(Box::new(ws));
+        params.push(Box::new(pattern));
+    }
+    if !query.agents.is_empty() {
+        let placeholders: Vec<String> = query
+            .agents
+            .iter()
+            .map(|agent| {
+                params.push(Box::new(agent.clone()));
+                format!("?{}", params.len())
+            })
+            .collect();
+        sql.push_str(&format!(" AND a.slug IN ({})", placeholders.join(", ")));
+    }
+    if let Some(since) = query.since {
+        sql.push_str(&format!(
+            " AND COALESCE(m.created_at, c.started_at) >= ?{}",
+            params.len() + 1
+        ));
+        params.push(Box::new(since));
+    }
+    sql.push_str(" ORDER BY COALESCE(m.created_at, c.started_at), c.id, m.idx");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(
+        rusqlite::params_from_iter(params.iter().map(|p| &**p)),
+        |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, i64>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, i64>(4)?,
+                r.get::<_, Option<i64>>(5)?,
+                r.get::<_, String>(6)?,
+                r.get::<_, Option<String>>(7)?,
+            ))
+        },
+    )?;
+
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    let mut prev_hash = GENESIS_HASH.to_string();
+    for row in rows {
+        let (workspace, agent, conversation_id, source_path, idx, timestamp, content, extra) = row?;
+        let extra: Value = extra
+            .and_then(|e| serde_json::from_str(&e).ok())
+            .unwrap_or(Value::Null);
+        for action in message_actions(&agent, &content, &extra) {
+            let mut entry = AuditEntry {
+                seq: entries.len() as u64 + 1,
+                timestamp,
+                workspace: workspace.clone(),
+                agent: agent.clone(),
+                conversation_id,
+                source_path: source_path.clone(),
+                message_idx: idx,
+                action: action.kind,
+                tool: action.tool,
+                path: action.path,
+                command: action.command,
+                lines_added: action.lines.map(|(added, _)| added),
+                lines_removed: action.lines.map(|(_, removed)| removed),
+                prev_hash: std::mem::take(&mut prev_hash),
+                hash: String::new(),
+            };
+            entry.hash = entry.digest(key);
+            prev_hash = entry.hash.clone();
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Actions in one message: recorded tool calls for agents the viewer understands,
+/// otherwise the flattened `[Tool: name - detail]` markers in the content.
+fn message_actions(agent: &str, content: &str, extra: &Value) -> Vec<Action> {
+    let calls = tool_calls(agent, extra);
+    if calls.is_empty() {
+        return content.lines().filter_map(marker_action).collect();
+    }
+    calls
+        .into_iter()
+        .map(|call| match call {
+            ToolCall::Edit {
+                name,
+                path,
+                changes,
+                ..
+            } => {
+                let write = changes.iter().all(|(old, _)| old.is_empty())
+                    && name.to_lowercase().contains("write");
+                let (added, removed) = changes
+                    .iter()
+                    .map(|(old, new)| diff_stats(old, new))
+                    .fold((0, 0), |(a, r), (da, dr)| (a + da, r + dr));
+                Action {
+                    kind: if write {
+                        ActionKind::FileWrite
+                    } else {
+                        ActionKind::FileEdit
+                    },
+                    tool: name,
+                    path: Some(path),
+                    command: None,
+                    lines: Some((added, removed)),
+                }
+            }
+            ToolCall::Shell { command, workdir } => Action {
+                kind: ActionKind::Command,
+                tool: "shell".to_string(),
+                path: workdir,
+                command: Some(command),
+                lines: None,
+            },
+            ToolCall::Panel { name, input: args } | ToolCall::Function { name, args, .. } => {
+                tool_action(name, &args)
+            }
+        })
+        .collect()
+}
+
+/// A generic tool use: a command if it carries one, else the file it touched.
+fn tool_action(tool: String, args: &Value) -> Action {
+    let command = args
+        .get("command")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Action {
+        kind: if command.is_some() {
+            ActionKind::Command
+        } else {
+            ActionKind::Tool
+        },
+        path: tool_call_file_path(args).map(|p| p.to_string_lossy().into_owned()),
+        tool,
+        command,
+        lines: None,
+    }
+}
+
+/// `[Tool: name - detail]` as flattened by connectors without structured calls.
+fn marker_action(line: &str) -> Option<Action> {
+    let inner = line
+        .trim()
+        .strip_prefix("[Tool:")?
+        .strip_suffix(']')?
+        .trim();
+    let (tool, detail) = match inner.split_once(" - ") {
+        Some((tool, detail)) => (tool.trim(), Some(detail.trim().to_string())),
+        None => (inner, None),
+    };
+    Some(Action {
+        kind: ActionKind::Tool,
+        tool: tool.to_string(),
+        path: None,
+        command: detail,
+        lines: None,
+    })
+}
+
+/// Why an exported log failed verification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainBreak {
+    /// 1-based line of the first entry that doesn't verify
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Result of checking an exported log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    pub entries: usize,
+    /// Hash of the last entry; compare with a separately recorded copy to detect truncation
+    pub head_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken: Option<ChainBreak>,
+}
+
+/// Re-compute the chain of a JSON Lines audit log.
+pub fn verify_chain(reader: impl BufRead, key: &hmac::Key) -> Result<Verification> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut entries = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.context("reading audit log")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let broken = |reason: String| Verification {
+            entries,
+            head_hash: prev_hash.clone(),
+            broken: Some(ChainBreak {
+                line: i + 1,
+                reason,
+            }),
+        };
+        let entry: AuditEntry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(e) => return Ok(broken(format!("not an audit entry: {e}"))),
+        };
+        if entry.prev_hash != prev_hash {
+            return Ok(broken(
+                "prev_hash doesn't match the previous entry".to_string(),
+            ));
+        }
+        if entry.digest(key) != entry.hash {
+            return Ok(broken("entry was modified after export".to_string()));
+        }
+        prev_hash = entry.hash;
+        entries += 1;
+    }
+    Ok(Verification {
+        entries,
+        head_hash: prev_hash,
+        broken: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key() -> hmac::Key {
+        hmac::Key::new(hmac::HMAC_SHA256, b"test key")
+    }
+
+    fn chain(actions: Vec<Action>) -> Vec<AuditEntry> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let mut entry = AuditEntry {
+                    seq: i as u64 + 1,
+                    timestamp: Some(1_700_000_000_000 + i as i64),
+                    workspace: "/repo".into(),
+                    agent: "claude_code".into(),
+                    conversation_id: 1,
+                    source_path: "/s.jsonl".into(),
+                    message_idx: i as i64,
+                    action: action.kind,
+                    tool: action.tool,
+                    path: action.path,
+                    command: action.command,
+                    lines_added: action.lines.map(|l| l.0),
+                    lines_removed: action.lines.map(|l| l.1),
+                    prev_hash: std::mem::take(&mut prev_hash),
+                    hash: String::new(),
+                };
+                entry.hash = entry.digest(&key());
+                prev_hash = entry.hash.clone();
+                entry
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extracts_commands_edits_and_markers() {
+        let extra = json!({"message": {"content": [
+            {"type": "tool_use", "name": "Bash", "input": {"command": "cargo test"}},
+            {"type": "tool_use", "name": "Edit", "input": {
+                "file_path": "/repo/src/lib.rs", "old_string": "a\nb", "new_string": "a\nc\nd"}},
+            {"type": "tool_use", "name": "Write", "input": {
+                "file_path": "/repo/NEW.md", "content": "hi"}},
+            {"type": "tool_use", "name": "Read", "input": {"file_path": "/repo/Cargo.toml"}}
+        ]}});
+        let actions = message_actions("claude_code", "", &extra);
+        let kinds: Vec<&ActionKind> = actions.iter().map(|a| &a.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &ActionKind::Command,
+                &ActionKind::FileEdit,
+                &ActionKind::FileWrite,
+                &ActionKind::Tool
+            ]
+        );
+        assert_eq!(actions[0].command.as_deref(), Some("cargo test"));
+        assert_eq!(actions[1].lines, Some((2, 1)));
+        assert_eq!(actions[3].path.as_deref(), Some("/repo/Cargo.toml"));
+
+        let flattened = message_actions("aider", "ok\n[Tool: run - make build]\n", &Value::Null);
+        assert_eq!(flattened.len(), 1);
+        assert_eq!(flattened[0].tool, "run");
+        assert_eq!(flattened[0].command.as_deref(), Some("make build"));
+    }
+
+    #[test]
+    fn verify_detects_edits_and_reordering() {
+        let entries = chain(
+            ["ls", "rm -rf build", "cargo build"]
+                .into_iter()
+                .map(|c| tool_action("Bash".into(), &json!({ "command": c })))
+                .collect(),
+        );
+        let log = |entries: &[AuditEntry]| {
+            entries
+                .iter()
+                .map(|e| serde_json::to_string(e).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let ok = verify_chain(log(&entries).as_bytes(), &key()).unwrap();
+        assert_eq!(ok.entries, 3);
+        assert_eq!(ok.head_hash, entries[2].hash);
+        assert!(ok.broken.is_none());
+
+        let mut tampered = entries.clone();
+        tampered[1].command = Some("echo harmless".into());
+        let result = verify_chain(log(&tampered).as_bytes(), &key()).unwrap();
+        assert_eq!(result.broken.unwrap().line, 2);
+
+        let mut reordered = entries.clone();
+        reordered.swap(1, 2);
+        let result = verify_chain(log(&reordered).as_bytes(), &key()).unwrap();
+        assert_eq!(result.entries, 1);
+        assert_eq!(result.broken.unwrap().line, 2);
+    }
+
+    #[test]
+    fn a_log_rechained_without_the_key_fails() {
+        let entries = chain(vec![tool_action(
+            "Bash".into(),
+            &json!({ "command": "ls" }),
+        )]);
+        let mut forged = entries[0].clone();
+        forged.command = Some("echo harmless".into());
+        forged.hash = forged.digest(&hmac::Key::new(hmac::HMAC_SHA256, b"guessed"));
+        let log = serde_json::to_string(&forged).unwrap();
+        let result = verify_chain(log.as_bytes(), &key()).unwrap();
+        assert_eq!(result.broken.unwrap().line, 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let created = load_or_create_key(dir.path()).unwrap();
+        let reloaded = load_or_create_key(dir.path()).unwrap();
+        assert_eq!(
+            hmac::sign(&created, b"x").as_ref(),
+            hmac::sign(&reloaded, b"x").as_ref()
+        );
+    }
+
+    #[test]
+    fn workspace_filter_matches_wildcards_literally() {
+        use crate::model::types::{Agent, AgentKind, Conversation, Message, MessageRole};
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage =
+            crate::storage::sqlite::SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let agent_id = storage
+            .ensure_agent(&Agent {
+                id: None,
+                slug: "aider".into(),
+                name: "Aider".into(),
+                version: None,
+                kind: AgentKind::Cli,
+            })
+            .unwrap();
+        for (i, ws) in ["/src/my_app", "/src/myXapp/sub"].into_iter().enumerate() {
+            let ws_id = storage.ensure_workspace(Path::new(ws), None).unwrap();
+            let conv = Conversation {
+                id: None,
+                agent_slug: "aider".into(),
+                workspace: Some(ws.into()),
+                external_id: Some(format!("s{i}")),
+                title: None,
+                source_path: format!("/logs/{i}.md").into(),
+                started_at: Some(1),
+                ended_at: Some(1),
+                approx_tokens: None,
+                metadata_json: Value::Null,
+                messages: vec![Message {
+                    id: None,
+                    idx: 0,
+                    role: MessageRole::Agent,
+                    author: None,
+                    created_at: Some(1),
+                    content: "[Tool: run - make]".into(),
+                    extra_json: Value::Null,
+                    snippets: vec![],
+                    approx_tokens: None,
+                    source_path: None,
+                    source_line: None,
+                }],
+                source_id: "local".into(),
+                origin_host: None,
+            };
+            storage
+                .insert_conversation_tree(agent_id, Some(ws_id), &conv)
+                .unwrap();
+        }
+        let query = AuditQuery {
+            workspace: Some("/src/my_app".into()),
+            ..Default::default()
+        };
+        let entries = collect_actions(storage.raw(), &query, &key()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].workspace, "/src/my_app");
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! Provides persistent storage for bookmarked search results with user notes
 //! and tags. Uses a separate `SQLite` database file to avoid schema conflicts.
+//!
+//! Destructive edits (bookmark removal, bookmark and conversation tag removal,
+//! bulk deletes from the index) are recorded in an undo journal so they can be
+//! reverted with `cass undo` or `u` in the TUI.
 
 use anyhow::{Context, Result};
 use rusqlite::{Connection, OptionalExtension, params};
@@ -9,6 +13,8 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::storage::sqlite::SqliteStorage;
+
 /// A bookmarked search result with optional note and tags
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bookmark {
@@ -103,6 +109,64 @@ impl Bookmark {
     }
 }
 
+/// How long a destructive action stays undoable (24 hours).
+pub const UNDO_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Kind of destructive action recorded in the undo journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoAction {
+    /// A bookmark was deleted
+    RemoveBookmark,
+    /// A tag was removed from a bookmark
+    RemoveTag,
+    /// A tag was removed from an indexed conversation
+    RemoveConversationTag,
+    /// Sessions were deleted from the index
+    DeleteSessions,
+}
+
+impl UndoAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RemoveBookmark => "remove_bookmark",
+            Self::RemoveTag => "remove_tag",
+            Self::RemoveConversationTag => "remove_conversation_tag",
+            Self::DeleteSessions => "delete_sessions",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "remove_bookmark" => Some(Self::RemoveBookmark),
+            "remove_tag" => Some(Self::RemoveTag),
+            "remove_conversation_tag" => Some(Self::RemoveConversationTag),
+            "delete_sessions" => Some(Self::DeleteSessions),
+            _ => None,
+        }
+    }
+}
+
+/// A journaled destructive action that can still be undone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoEntry {
+    /// Journal entry ID
+    pub id: i64,
+    /// What was done
+    pub action: UndoAction,
+    /// Human-readable description (e.g. "Removed bookmark 'Fix auth'")
+    pub summary: String,
+    /// When the action happened (unix millis)
+    pub created_at: i64,
+}
+
+/// Journal payload for a tag removed from an indexed conversation
+#[derive(Debug, Serialize, Deserialize)]
+struct ConversationTagSnapshot {
+    conversation_id: i64,
+    tag: String,
+}
+
 /// Storage backend for bookmarks using `SQLite`
 pub struct BookmarkStore {
     conn: Connection,
@@ -181,14 +245,204 @@ impl BookmarkStore {
         Ok(rows > 0)
     }
 
-    /// Remove a bookmark by ID
+    /// Remove a bookmark by ID. The removed bookmark is journaled for undo.
     pub fn remove(&self, id: i64) -> Result<bool> {
-        let rows = self
-            .conn
-            .execute("DELETE FROM bookmarks WHERE id = ?1", [id])?;
+        let Some(existing) = self.get(id)? else {
+            return Ok(false);
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        journal(
+            &tx,
+            UndoAction::RemoveBookmark,
+            &format!("Removed bookmark '{}'", existing.title),
+            &existing,
+        )?;
+        let rows = tx.execute("DELETE FROM bookmarks WHERE id = ?1", [id])?;
+        tx.commit()?;
         Ok(rows > 0)
     }
 
+    /// Remove a single tag from a bookmark. The previous tags are journaled for undo.
+    /// Returns false if the bookmark doesn't exist or doesn't carry the tag.
+    pub fn remove_tag(&self, id: i64, tag: &str) -> Result<bool> {
+        let Some(existing) = self.get(id)? else {
+            return Ok(false);
+        };
+        if !existing.has_tag(tag) {
+            return Ok(false);
+        }
+        let remaining = existing
+            .tag_list()
+            .into_iter()
+            .filter(|t| !t.eq_ignore_ascii_case(tag))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let tx = self.conn.unchecked_transaction()?;
+        journal(
+            &tx,
+            UndoAction::RemoveTag,
+            &format!("Removed tag '{tag}' from '{}'", existing.title),
+            &existing,
+        )?;
+        tx.execute(
+            "UPDATE bookmarks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+            params![remaining, now_millis(), id],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Journal a tag about to be removed from an indexed conversation, so undo
+    /// can re-add it. The index database itself lives outside this store.
+    pub fn journal_conversation_tag(&self, conversation_id: i64, tag: &str) -> Result<()> {
+        journal(
+            &self.conn,
+            UndoAction::RemoveConversationTag,
+            &format!("Removed tag '{tag}' from conversation {conversation_id}"),
+            &ConversationTagSnapshot {
+                conversation_id,
+                tag: tag.to_string(),
+            },
+        )
+    }
+
+    /// Journal sessions, as `(source_id, source_path)`, just deleted from the
+    /// index. Undo forgets their deletion so the next full index re-adds them.
+    pub fn journal_deleted_sessions(
+        &self,
+        sessions: &[(String, String)],
+        removed: usize,
+    ) -> Result<()> {
+        journal(
+            &self.conn,
+            UndoAction::DeleteSessions,
+            &format!("Deleted {removed} conversations from the index"),
+            &sessions,
+        )
+    }
+
+    /// Revert the most recent destructive action still inside the undo window.
+    /// Actions on indexed conversations are reverted in the database at `index_db`.
+    /// Returns the entry that was undone, or None if there is nothing to undo.
+    pub fn undo_last(&self, index_db: &Path) -> Result<Option<UndoEntry>> {
+        self.prune_undo_journal()?;
+        let row: Option<(i64, String, String, String, i64)> = self
+            .conn
+            .query_row(
+                "SELECT id, action, summary, payload, created_at FROM undo_journal
+                 ORDER BY id DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .optional()
+            .context("reading undo journal")?;
+        let Some((id, action, summary, payload, created_at)) = row else {
+            return Ok(None);
+        };
+        let action = UndoAction::parse(&action)
+            .ok_or_else(|| anyhow::anyhow!("unknown undo action '{action}'"))?;
+        let bookmark = || -> Result<Bookmark> {
+            serde_json::from_str(&payload).context("parsing undo journal payload")
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        match action {
+            UndoAction::RemoveBookmark => {
+                let snapshot = bookmark()?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO bookmarks (id, title, source_path, line_number, agent, workspace, note, tags, created_at, updated_at, snippet)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        snapshot.id,
+                        snapshot.title,
+                        snapshot.source_path,
+                        snapshot.line_number.map(|n| n as i64),
+                        snapshot.agent,
+                        snapshot.workspace,
+                        snapshot.note,
+                        snapshot.tags,
+                        snapshot.created_at,
+                        snapshot.updated_at,
+                        snapshot.snippet,
+                    ],
+                )?;
+            }
+            UndoAction::RemoveTag => {
+                let snapshot = bookmark()?;
+                tx.execute(
+                    "UPDATE bookmarks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![snapshot.tags, snapshot.updated_at, snapshot.id],
+                )?;
+            }
+            UndoAction::RemoveConversationTag => {
+                let snapshot: ConversationTagSnapshot =
+                    serde_json::from_str(&payload).context("parsing undo journal payload")?;
+                SqliteStorage::open(index_db)?.add_tag(snapshot.conversation_id, &snapshot.tag)?;
+            }
+            UndoAction::DeleteSessions => {
+                let sessions: Vec<(String, String)> =
+                    serde_json::from_str(&payload).context("parsing undo journal payload")?;
+                SqliteStorage::open(index_db)?.forget_deleted_sessions(&sessions)?;
+            }
+        }
+        tx.execute("DELETE FROM undo_journal WHERE id = ?1", [id])?;
+        tx.commit()?;
+
+        Ok(Some(UndoEntry {
+            id,
+            action,
+            summary,
+            created_at,
+        }))
+    }
+
+    /// List undoable actions, newest first
+    pub fn undo_history(&self, limit: usize) -> Result<Vec<UndoEntry>> {
+        self.prune_undo_journal()?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, action, summary, created_at FROM undo_journal ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, action, summary, created_at) = row?;
+            if let Some(action) = UndoAction::parse(&action) {
+                entries.push(UndoEntry {
+                    id,
+                    action,
+                    summary,
+                    created_at,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Drop journal entries that fell out of the undo window
+    fn prune_undo_journal(&self) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM undo_journal WHERE created_at < ?1",
+            [now_millis() - UNDO_WINDOW_MS],
+        )?;
+        Ok(())
+    }
+
     /// Get a bookmark by ID
     pub fn get(&self, id: i64) -> Result<Option<Bookmark>> {
         self.conn
@@ -274,6 +528,22 @@ impl BookmarkStore {
         Ok(exists)
     }
 
+    /// Find the bookmark ID for a source location, if bookmarked
+    pub fn find_by_location(
+        &self,
+        source_path: &str,
+        line_number: Option<usize>,
+    ) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT id FROM bookmarks WHERE source_path = ?1 AND line_number IS ?2",
+                params![source_path, line_number.map(|n| n as i64)],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("looking up bookmark by location")
+    }
+
     /// Export all bookmarks to JSON
     pub fn export_json(&self) -> Result<String> {
         let bookmarks = self.list(None)?;
@@ -299,6 +569,32 @@ impl BookmarkStore {
     }
 }
 
+/// Record a snapshot taken before a destructive change
+fn journal(
+    conn: &Connection,
+    action: UndoAction,
+    summary: &str,
+    snapshot: &impl Serialize,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO undo_journal (action, summary, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            action.as_str(),
+            summary,
+            serde_json::to_string(snapshot)?,
+            now_millis()
+        ],
+    )?;
+    Ok(())
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
 /// Convert a database row to a Bookmark
 fn row_to_bookmark(row: &rusqlite::Row) -> Bookmark {
     Bookmark {
@@ -320,8 +616,12 @@ fn row_to_bookmark(row: &rusqlite::Row) -> Bookmark {
     }
 }
 
-/// Get the default bookmarks database path
+/// Get the default bookmarks database path: in the active profile's data dir,
+/// else the platform data dir
 pub fn default_bookmarks_path() -> PathBuf {
+    if let Some(dir) = crate::profiles::active_data_dir() {
+        return dir.join("bookmarks.db");
+    }
     directories::ProjectDirs::from("com", "coding-agent-search", "coding-agent-search").map_or_else(
         || PathBuf::from("bookmarks.db"),
         |dirs| dirs.data_dir().join("bookmarks.db"),
@@ -347,6 +647,14 @@ CREATE TABLE IF NOT EXISTS bookmarks (
 CREATE INDEX IF NOT EXISTS idx_bookmarks_source ON bookmarks(source_path, line_number);
 CREATE INDEX IF NOT EXISTS idx_bookmarks_created ON bookmarks(created_at DESC);
 CREATE INDEX IF NOT EXISTS idx_bookmarks_agent ON bookmarks(agent);
+
+CREATE TABLE IF NOT EXISTS undo_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    action TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
 ";
 
 #[cfg(test)]
@@ -419,6 +727,51 @@ mod tests {
         assert_eq!(store.count().unwrap(), 0);
     }
 
+    #[test]
+    fn test_undo_remove_restores_bookmark() {
+        let (store, _dir) = test_store();
+        let id = store
+            .add(&Bookmark::new("Keep me", "/x.rs", "agent", "/ws").with_note("precious"))
+            .unwrap();
+
+        assert!(store.remove(id).unwrap());
+        assert_eq!(store.count().unwrap(), 0);
+        assert_eq!(store.undo_history(10).unwrap().len(), 1);
+
+        let undone = store
+            .undo_last(Path::new("agent_search.db"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(undone.action, UndoAction::RemoveBookmark);
+        let restored = store.get(id).unwrap().unwrap();
+        assert_eq!(restored.note, "precious");
+        assert!(
+            store
+                .undo_last(Path::new("agent_search.db"))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_undo_remove_tag() {
+        let (store, _dir) = test_store();
+        let id = store
+            .add(&Bookmark::new("Tagged", "/t.rs", "agent", "/ws").with_tags("rust, important"))
+            .unwrap();
+
+        assert!(store.remove_tag(id, "important").unwrap());
+        assert!(!store.get(id).unwrap().unwrap().has_tag("important"));
+        assert!(!store.remove_tag(id, "missing").unwrap());
+
+        let undone = store
+            .undo_last(Path::new("agent_search.db"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(undone.action, UndoAction::RemoveTag);
+        assert!(store.get(id).unwrap().unwrap().has_tag("important"));
+    }
+
     #[test]
     fn test_tag_filter() {
         let (store, _dir) = test_store();
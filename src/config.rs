@@ -0,0 +1,286 @@
+//! `cass config`: where settings live, what is in effect, and single-key edits.
+//!
+//! `config.toml` holds one section per feature, each read by the module that
+//! owns it; `sources.toml` next to it lists remote machines. This module only
+//! inspects and edits the files. `validate` checks the sections whose types
+//! are known here; `[tui]` and `[classifiers]` are only checked for syntax.
+
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::hooks::HooksConfig;
+use crate::sources::config::SourcesConfig;
+
+/// Top-level keys of `config.toml`
+pub const KNOWN_KEYS: &[&str] = &[
+    "aliases",
+    "analyzers",
+    "classifiers",
+    "embedding",
+    "exclude",
+    "hooks",
+    "index",
+    "profiles",
+    "query_templates",
+    "ranking",
+    "redaction",
+    "reports",
+    "storage",
+    "tui",
+];
+
+/// Environment variables that change what the files configure: name, the
+/// `config get` key it overrides (None when it only moves files around), and
+/// what it does
+pub const ENV_OVERRIDES: &[(&str, Option<&str>, &str)] = &[
+    (
+        "XDG_CONFIG_HOME",
+        None,
+        "directory holding cass/config.toml and cass/sources.toml",
+    ),
+    ("CASS_PROFILE", None, "active profile, like --profile"),
+    (
+        "CASS_SEMANTIC_EMBEDDER",
+        Some("embedding.embedder"),
+        "semantic embedder: hash or minilm",
+    ),
+    (
+        "CASS_EMBED_DEVICE",
+        Some("embedding.device"),
+        "device for the minilm embedder",
+    ),
+    (
+        "CASS_DB_KEY",
+        Some("storage.db_key"),
+        "database key when [storage] encryption = \"env\"",
+    ),
+    (
+        "CASS_IGNORE_SOURCES_CONFIG",
+        Some("ignore_sources_config"),
+        "index without sources.toml",
+    ),
+];
+
+/// A problem found in one of the config files
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Issue {
+    /// Errors make cass ignore the file or section; warnings are likely typos
+    pub error: bool,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(message: String) -> Self {
+        Self {
+            error: true,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Self {
+            error: false,
+            message,
+        }
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    HooksConfig::config_path()
+}
+
+pub fn sources_path() -> Option<PathBuf> {
+    SourcesConfig::config_path().ok()
+}
+
+/// Parse a TOML file into a table; a missing file is an empty table.
+pub fn read_table(path: &Path) -> Result<toml::Table, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content.parse::<toml::Table>().map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(toml::Table::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn check<T: DeserializeOwned>(table: &toml::Table, key: &str) -> Option<String> {
+    let value = table.get(key)?.clone();
+    value.try_into::<T>().err().map(|e| format!("[{key}]: {e}"))
+}
+
+/// Problems in the content of `config.toml`
+pub fn check_config(content: &str) -> Vec<Issue> {
+    use std::collections::BTreeMap;
+
+    let table = match content.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => return vec![Issue::error(e.to_string())],
+    };
+    let mut issues: Vec<Issue> = [
+        check::<BTreeMap<String, String>>(&table, "aliases"),
+        check::<crate::search::analyzers::AnalyzerConfig>(&table, "analyzers"),
+        check::<crate::search::embed_backfill::EmbeddingConfig>(&table, "embedding"),
+        check::<Vec<String>>(&table, "exclude"),
+        check::<Vec<crate::hooks::HookDefinition>>(&table, "hooks"),
+        check::<crate::indexer::window::IndexWindowConfig>(&table, "index"),
+        check::<BTreeMap<String, crate::profiles::ProfileConfig>>(&table, "profiles"),
+        check::<BTreeMap<String, crate::search::templates::QueryTemplate>>(
+            &table,
+            "query_templates",
+        ),
+        check::<crate::search::feedback::RankingConfig>(&table, "ranking"),
+        check::<crate::indexer::redact::RedactionConfig>(&table, "redaction"),
+        check::<Vec<crate::reports::ReportDefinition>>(&table, "reports"),
+        check::<crate::indexer::budget::StorageConfig>(&table, "storage"),
+    ]
+    .into_iter()
+    .flatten()
+    .map(Issue::error)
+    .collect();
+    for key in table.keys() {
+        if !KNOWN_KEYS.contains(&key.as_str()) {
+            issues.push(Issue::warning(format!("unknown key '{key}' is ignored")));
+        }
+    }
+    issues
+}
+
+/// Problems in the content of `sources.toml`
+pub fn check_sources(content: &str) -> Vec<Issue> {
+    match toml::from_str::<SourcesConfig>(content) {
+        Ok(config) => config
+            .validate()
+            .err()
+            .map(|e| Issue::error(e.to_string()))
+            .into_iter()
+            .collect(),
+        Err(e) => vec![Issue::error(e.to_string())],
+    }
+}
+
+/// The value at a dotted key such as `storage.max_size`
+pub fn lookup<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Set a dotted key in a parsed `config.toml`, creating tables on the way and
+/// keeping the rest of the file (comments, ordering) as written. `raw` is read
+/// as a TOML value (`true`, `42`, `["a"]`, `"quoted"`) and otherwise kept as a string.
+pub fn set_key(doc: &mut toml_edit::DocumentMut, key: &str, raw: &str) -> Result<(), String> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("invalid key '{key}'"));
+    }
+    if !KNOWN_KEYS.contains(&parts[0]) {
+        return Err(format!(
+            "unknown key '{}'; expected one of: {}",
+            parts[0],
+            KNOWN_KEYS.join(", ")
+        ));
+    }
+    let mut value = raw
+        .parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| toml_edit::Value::from(raw));
+
+    let (last, parents) = parts.split_last().expect("split yields at least one part");
+    let mut current: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for (i, part) in parents.iter().enumerate() {
+        current = current
+            .entry(part)
+            .or_insert_with(|| {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                toml_edit::Item::Table(table)
+            })
+            .as_table_like_mut()
+            .ok_or_else(|| format!("'{}' is not a table", parts[..=i].join(".")))?;
+    }
+    match current.get_mut(last) {
+        // Replace the value in place, keeping the comments around it
+        Some(toml_edit::Item::Value(old)) => {
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        }
+        _ => {
+            value.decor_mut().clear();
+            current.insert(last, toml_edit::Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// Write the environment overrides that are set into `effective` at the keys
+/// they override, so `config get` shows what cass will actually use
+pub fn apply_env_overrides(effective: &mut toml::Table) {
+    for (name, key, _) in ENV_OVERRIDES {
+        let (Some(key), Ok(value)) = (key, std::env::var(name)) else {
+            continue;
+        };
+        let value = match *name {
+            // Never echo the key itself
+            "CASS_DB_KEY" => toml::Value::from("(set)"),
+            // Set means ignored, whatever the value
+            "CASS_IGNORE_SOURCES_CONFIG" => toml::Value::from(true),
+            _ => toml::Value::from(value),
+        };
+        let parts: Vec<&str> = key.split('.').collect();
+        let (last, parents) = parts.split_last().expect("split yields at least one part");
+        let mut current = &mut *effective;
+        for part in parents {
+            let entry = current
+                .entry(part.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            current = entry.as_table_mut().expect("just made a table");
+        }
+        current.insert(last.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_config_reports_type_errors_and_unknown_keys() {
+        assert!(check_config("[storage]\ncompression = true\n").is_empty());
+        let issues = check_config("[storage]\ncompression = \"yes\"\n[storag]\n");
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].error && issues[0].message.starts_with("[storage]"));
+        assert!(!issues[1].error && issues[1].message.contains("storag"));
+        assert!(check_config("[storage")[0].error);
+    }
+
+    #[test]
+    fn set_key_parses_values_and_creates_tables() {
+        let mut doc = "# my settings\n[storage]\n# keep it small\nmax_size = \"1GB\" # for now\n"
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        set_key(&mut doc, "storage.compression", "true").unwrap();
+        set_key(&mut doc, "storage.max_size", "2GB").unwrap();
+        set_key(&mut doc, "profiles.work.data_dir", "~/work").unwrap();
+        let content = doc.to_string();
+        assert!(content.contains("# my settings\n[storage]\n# keep it small\n"));
+        assert!(content.contains("max_size = \"2GB\" # for now\n"));
+
+        let table = content.parse::<toml::Table>().unwrap();
+        assert_eq!(
+            lookup(&table, "storage.compression"),
+            Some(&toml::Value::Boolean(true))
+        );
+        assert_eq!(
+            lookup(&table, "storage.max_size").and_then(toml::Value::as_str),
+            Some("2GB")
+        );
+        assert!(lookup(&table, "profiles.work.data_dir").is_some());
+        assert!(set_key(&mut doc, "storag.compression", "true").is_err());
+        assert!(set_key(&mut doc, "storage.compression.x", "1").is_err());
+    }
+}
@@ -56,6 +56,7 @@ impl AiderConnector {
                         content: current_content.trim().to_string(),
                         extra: json!({}),
                         snippets: Vec::new(),
+                        source: None,
                     });
                     msg_idx += 1;
                     current_content.clear();
@@ -74,6 +75,7 @@ impl AiderConnector {
                             content: current_content.trim().to_string(),
                             extra: json!({}),
                             snippets: Vec::new(),
+                            source: None,
                         });
                         msg_idx += 1;
                         current_content.clear();
@@ -94,6 +96,7 @@ impl AiderConnector {
                 content: current_content.trim().to_string(),
                 extra: json!({}),
                 snippets: Vec::new(),
+                source: None,
             });
         }
 
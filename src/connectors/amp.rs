@@ -6,6 +6,7 @@ use walkdir::WalkDir;
 
 use crate::connectors::{
     Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
+    editor_config_dirs,
 };
 
 pub struct AmpConnector;
@@ -32,15 +33,10 @@ impl AmpConnector {
     }
 
     fn vscode_global_storage() -> Vec<PathBuf> {
-        let mut roots = Vec::new();
-        if let Some(home) = dirs::home_dir() {
-            roots.push(home.join(".config/Code/User/globalStorage/sourcegraph.amp"));
-            roots.push(
-                home.join("Library/Application Support/Code/User/globalStorage/sourcegraph.amp"),
-            );
-            roots.push(home.join("AppData/Roaming/Code/User/globalStorage/sourcegraph.amp"));
-        }
-        roots
+        editor_config_dirs()
+            .into_iter()
+            .map(|dir| dir.join("Code/User/globalStorage/sourcegraph.amp"))
+            .collect()
     }
 
     pub fn candidate_roots() -> Vec<PathBuf> {
@@ -248,6 +244,7 @@ fn extract_messages(val: &Value, _since_ts: Option<i64>) -> Option<Vec<Normalize
             content,
             extra: m.clone(),
             snippets: Vec::new(),
+            source: None,
         });
     }
 
@@ -0,0 +1,112 @@
+//! Connector for conversations pulled from another cass with `cass serve --sync`.
+//!
+//! A `type = "cass"` source saves each fetch under its mirror as
+//! `cass/<fetch time>.cass.jsonl`, one already-normalized conversation per
+//! line (see [`crate::sources::serve`]). Nothing is detected locally; the
+//! connector only reads those files when the indexer hands it a remote root.
+
+use anyhow::Result;
+use walkdir::WalkDir;
+
+use crate::connectors::{
+    Connector, DetectionResult, NormalizedConversation, ScanContext, file_modified_since,
+};
+use crate::sources::serve::{STREAM_SUFFIX, read_stream};
+
+pub struct CassConnector;
+
+impl CassConnector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CassConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Connector for CassConnector {
+    fn detect(&self) -> DetectionResult {
+        DetectionResult::not_found()
+    }
+
+    fn scan(&self, ctx: &ScanContext) -> Result<Vec<NormalizedConversation>> {
+        if ctx.use_default_detection() {
+            return Ok(Vec::new());
+        }
+        let mut files: Vec<_> = ctx
+            .scan_roots
+            .iter()
+            .flat_map(|root| WalkDir::new(&root.path).max_depth(2).into_iter().flatten())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with(STREAM_SUFFIX))
+            })
+            .filter(|p| file_modified_since(p, ctx.since_ts))
+            .collect();
+        // Fetch times name the files, so later copies of a conversation win
+        files.sort();
+
+        let mut convs = Vec::new();
+        for path in files {
+            match read_stream(&path) {
+                Ok(found) => convs.extend(found),
+                Err(e) => tracing::warn!("skipping cass stream: {e:#}"),
+            }
+        }
+        Ok(convs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::ScanRoot;
+    use crate::sources::provenance::Origin;
+    use serde_json::json;
+
+    #[test]
+    fn scans_only_saved_streams_under_remote_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let conv = json!({
+            "agent_slug": "codex",
+            "external_id": "abc",
+            "title": "fix parser",
+            "workspace": "/home/me/app",
+            "source_path": "/home/me/.codex/sessions/rollout-abc.jsonl",
+            "started_at": 1_700_000_000_000_i64,
+            "ended_at": 1_700_000_060_000_i64,
+            "metadata": {},
+            "messages": [{
+                "idx": 0, "role": "user", "author": null, "created_at": null,
+                "content": "hello", "extra": {}, "snippets": []
+            }]
+        });
+        std::fs::write(
+            dir.path().join("1700000100000.cass.jsonl"),
+            format!("{conv}\n"),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.jsonl"), "{}\n").unwrap();
+
+        let connector = CassConnector::new();
+        let local = ScanContext::local_default(dir.path().to_path_buf(), None);
+        assert!(connector.scan(&local).unwrap().is_empty());
+
+        let root = ScanRoot::remote(
+            dir.path().to_path_buf(),
+            Origin::remote_with_host("desk", "desk.local"),
+            None,
+        );
+        let ctx = ScanContext::with_roots(dir.path().to_path_buf(), vec![root], None);
+        let convs = connector.scan(&ctx).unwrap();
+        assert_eq!(convs.len(), 1);
+        assert_eq!(convs[0].agent_slug, "codex");
+        assert_eq!(convs[0].messages[0].content, "hello");
+    }
+}
@@ -344,6 +344,7 @@ impl ChatGptConnector {
                     content: content_str,
                     extra: msg.clone(),
                     snippets: Vec::new(),
+                    source: None,
                 });
             }
         }
@@ -389,6 +390,7 @@ impl ChatGptConnector {
                     content: content.to_string(),
                     extra: item.clone(),
                     snippets: Vec::new(),
+                    source: None,
                 });
             }
         }
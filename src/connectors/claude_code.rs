@@ -6,7 +6,8 @@ use serde_json::Value;
 use walkdir::WalkDir;
 
 use crate::connectors::{
-    Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
+    Connector, DetectionResult, MessageSource, NormalizedConversation, NormalizedMessage,
+    ScanContext,
 };
 
 pub struct ClaudeCodeConnector;
@@ -103,7 +104,7 @@ impl Connector for ClaudeCodeConnector {
                     .with_context(|| format!("open {}", entry.path().display()))?;
                 let reader = std::io::BufReader::new(file);
 
-                for line_res in std::io::BufRead::lines(reader) {
+                for (line_no, line_res) in std::io::BufRead::lines(reader).enumerate() {
                     let line = match line_res {
                         Ok(l) => l,
                         Err(_) => continue,
@@ -186,6 +187,7 @@ impl Connector for ClaudeCodeConnector {
                         content: content_str,
                         extra: val,
                         snippets: Vec::new(),
+                        source: Some(MessageSource::line(entry.path(), line_no + 1)),
                     });
                 }
                 // Re-assign sequential indices after filtering
@@ -243,6 +245,7 @@ impl Connector for ClaudeCodeConnector {
                             content: content_str,
                             extra: item.clone(),
                             snippets: Vec::new(),
+                            source: None,
                         });
                     }
                 }
@@ -297,7 +300,18 @@ impl Connector for ClaudeCodeConnector {
                     })
             };
 
-            convs.push(NormalizedConversation {
+            // Every JSONL entry records its cwd; a session that moved between
+            // directories is split so each part is attributed to its own workspace
+            let cwds: Vec<Option<PathBuf>> = messages
+                .iter()
+                .map(|m| {
+                    m.extra
+                        .get("cwd")
+                        .and_then(|v| v.as_str())
+                        .map(PathBuf::from)
+                })
+                .collect();
+            let conv = NormalizedConversation {
                 agent_slug: "claude_code".into(),
                 external_id: entry
                     .path()
@@ -315,7 +329,8 @@ impl Connector for ClaudeCodeConnector {
                     "gitBranch": git_branch
                 }),
                 messages,
-            });
+            };
+            convs.extend(crate::connectors::split_by_workspace(conv, &cwds));
         }
 
         Ok(convs)
@@ -397,6 +412,38 @@ mod tests {
         assert!(convs[0].messages[1].content.contains("How can I help"));
     }
 
+    #[test]
+    fn scan_records_source_line_per_message() {
+        let dir = TempDir::new().unwrap();
+        let claude_dir = dir.path().join(".claude");
+        fs::create_dir_all(&claude_dir).unwrap();
+
+        let session_file = claude_dir.join("session.jsonl");
+        let content = r#"{"type":"summary","summary":"Earlier work"}
+{"type":"user","message":{"role":"user","content":"First"}}
+
+{"type":"assistant","message":{"role":"assistant","content":"Second"}}
+"#;
+        fs::write(&session_file, content).unwrap();
+
+        let connector = ClaudeCodeConnector::new();
+        let ctx = ScanContext::local_default(claude_dir.clone(), None);
+        let convs = connector.scan(&ctx).unwrap();
+
+        let sources: Vec<_> = convs[0]
+            .messages
+            .iter()
+            .map(|m| m.source.clone().unwrap())
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                MessageSource::line(&session_file, 2),
+                MessageSource::line(&session_file, 4)
+            ]
+        );
+    }
+
     #[test]
     fn scan_extracts_session_metadata() {
         let dir = TempDir::new().unwrap();
@@ -6,6 +6,7 @@ use serde_json::Value;
 
 use crate::connectors::{
     Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
+    editor_config_dirs,
 };
 
 pub struct ClineConnector;
@@ -22,25 +23,15 @@ impl ClineConnector {
 
     fn candidate_roots() -> Vec<PathBuf> {
         let mut roots = Vec::new();
-        let Some(base) = dirs::home_dir() else {
-            return roots;
-        };
-
-        let code_roots = [
-            base.join(".config/Code/User/globalStorage"),
-            base.join("Library/Application Support/Code/User/globalStorage"),
-            base.join("AppData/Roaming/Code/User/globalStorage"),
-        ];
-        let cursor_roots = [
-            base.join(".config/Cursor/User/globalStorage"),
-            base.join("Library/Application Support/Cursor/User/globalStorage"),
-            base.join("AppData/Roaming/Cursor/User/globalStorage"),
-        ];
+        let config_dirs = editor_config_dirs();
         let extensions = ["saoudrizwan.claude-dev", "rooveterinaryinc.roo-cline"];
 
-        for root in code_roots.iter().chain(cursor_roots.iter()) {
-            for ext in &extensions {
-                roots.push(root.join(ext));
+        for app in ["Code", "Cursor"] {
+            for dir in &config_dirs {
+                let storage = dir.join(app).join("User/globalStorage");
+                for ext in &extensions {
+                    roots.push(storage.join(ext));
+                }
             }
         }
 
@@ -220,6 +211,7 @@ impl Connector for ClineConnector {
                             content: content.to_string(),
                             extra: item.clone(),
                             snippets: Vec::new(),
+                            source: None,
                         });
                     }
                 }
@@ -308,6 +300,21 @@ mod tests {
         let _ = connector;
     }
 
+    #[test]
+    fn candidate_roots_probe_the_platform_config_dir() {
+        let roots = ClineConnector::candidate_roots();
+        if let Some(config) = dirs::config_dir() {
+            assert!(
+                roots.contains(&config.join("Cursor/User/globalStorage/saoudrizwan.claude-dev"))
+            );
+        }
+        if let Some(home) = dirs::home_dir() {
+            assert!(roots.contains(
+                &home.join("AppData/Roaming/Code/User/globalStorage/rooveterinaryinc.roo-cline")
+            ));
+        }
+    }
+
     // =====================================================
     // Helper: Create Cline storage structure
     // =====================================================
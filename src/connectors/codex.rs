@@ -6,7 +6,8 @@ use serde_json::Value;
 use walkdir::WalkDir;
 
 use crate::connectors::{
-    Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
+    Connector, DetectionResult, MessageSource, NormalizedConversation, NormalizedMessage,
+    ScanContext,
 };
 
 pub struct CodexConnector;
@@ -131,6 +132,10 @@ impl Connector for CodexConnector {
             let mut started_at = None;
             let mut ended_at = None;
             let mut session_cwd: Option<PathBuf> = None;
+            let mut session_id: Option<String> = None;
+            // Directory in effect for each message (turn_context can move it mid-session)
+            let mut current_cwd: Option<PathBuf> = None;
+            let mut cwds: Vec<Option<PathBuf>> = Vec::new();
 
             if ext == Some("jsonl") {
                 let f = std::fs::File::open(&file)
@@ -138,7 +143,8 @@ impl Connector for CodexConnector {
                 let reader = std::io::BufReader::new(f);
 
                 // Modern envelope format: each line has {type, timestamp, payload}
-                for line_res in std::io::BufRead::lines(reader) {
+                for (line_no, line_res) in std::io::BufRead::lines(reader).enumerate() {
+                    cwds.resize(messages.len(), current_cwd.clone());
                     let line = match line_res {
                         Ok(l) => l,
                         Err(_) => continue,
@@ -169,12 +175,40 @@ impl Connector for CodexConnector {
                                     .get("cwd")
                                     .and_then(|v| v.as_str())
                                     .map(PathBuf::from);
+                                current_cwd = session_cwd.clone();
+                                session_id = payload
+                                    .get("id")
+                                    .and_then(|v| v.as_str())
+                                    .map(str::to_string);
                             }
                             started_at = started_at.or(created);
                         }
+                        "turn_context" => {
+                            if let Some(cwd) = val.pointer("/payload/cwd").and_then(|v| v.as_str())
+                            {
+                                current_cwd = Some(PathBuf::from(cwd));
+                            }
+                        }
                         "response_item" => {
                             // Main message entries with nested payload
                             if let Some(payload) = val.get("payload") {
+                                // Shell calls have no content; keep the command so it is
+                                // searchable and the viewer can render it as a terminal block
+                                if let Some(call) = shell_call(payload) {
+                                    started_at = started_at.or(created);
+                                    ended_at = created.or(ended_at);
+                                    messages.push(NormalizedMessage {
+                                        idx: 0, // will be re-assigned after filtering
+                                        role: "tool".to_string(),
+                                        author: None,
+                                        created_at: created,
+                                        content: format!("[Tool: shell - {}]", call.command),
+                                        extra: val,
+                                        snippets: Vec::new(),
+                                        source: Some(MessageSource::line(&file, line_no + 1)),
+                                    });
+                                    continue;
+                                }
                                 let role = payload
                                     .get("role")
                                     .and_then(|v| v.as_str())
@@ -200,6 +234,7 @@ impl Connector for CodexConnector {
                                     content: content_str,
                                     extra: val,
                                     snippets: Vec::new(),
+                                    source: Some(MessageSource::line(&file, line_no + 1)),
                                 });
                             }
                         }
@@ -224,6 +259,10 @@ impl Connector for CodexConnector {
                                                 content: text.to_string(),
                                                 extra: val,
                                                 snippets: Vec::new(),
+                                                source: Some(MessageSource::line(
+                                                    &file,
+                                                    line_no + 1,
+                                                )),
                                             });
                                         }
                                     }
@@ -243,6 +282,10 @@ impl Connector for CodexConnector {
                                                 content: text.to_string(),
                                                 extra: val,
                                                 snippets: Vec::new(),
+                                                source: Some(MessageSource::line(
+                                                    &file,
+                                                    line_no + 1,
+                                                )),
                                             });
                                         }
                                     }
@@ -250,9 +293,10 @@ impl Connector for CodexConnector {
                                 }
                             }
                         }
-                        _ => {} // Skip turn_context and unknown types
+                        _ => {} // Skip unknown types
                     }
                 }
+                cwds.resize(messages.len(), current_cwd.clone());
                 // Re-assign sequential indices after filtering
                 for (i, msg) in messages.iter_mut().enumerate() {
                     msg.idx = i as i64;
@@ -272,6 +316,10 @@ impl Connector for CodexConnector {
                     .and_then(|s| s.get("cwd"))
                     .and_then(|v| v.as_str())
                     .map(PathBuf::from);
+                session_id = val
+                    .pointer("/session/id")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
 
                 // Parse items array
                 if let Some(items) = val.get("items").and_then(|v| v.as_array()) {
@@ -305,6 +353,7 @@ impl Connector for CodexConnector {
                             content: content_str,
                             extra: item.clone(),
                             snippets: Vec::new(),
+                            source: None,
                         });
                     }
                 }
@@ -338,7 +387,7 @@ impl Connector for CodexConnector {
                         .map(|s| s.chars().take(100).collect())
                 });
 
-            convs.push(NormalizedConversation {
+            let conv = NormalizedConversation {
                 agent_slug: "codex".to_string(),
                 external_id,
                 title,
@@ -346,15 +395,74 @@ impl Connector for CodexConnector {
                 source_path: source_path.clone(),
                 started_at,
                 ended_at,
-                metadata: serde_json::json!({"source": if ext == Some("json") { "rollout_json" } else { "rollout" }}),
+                metadata: serde_json::json!({
+                    "source": if ext == Some("json") { "rollout_json" } else { "rollout" },
+                    "sessionId": session_id,
+                }),
                 messages,
-            });
+            };
+            convs.extend(crate::connectors::split_by_workspace(conv, &cwds));
         }
 
         Ok(convs)
     }
 }
 
+/// A shell command Codex ran, from a `function_call` (`shell`, `container.exec`)
+/// or `local_shell_call` response item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCall {
+    pub command: String,
+    pub workdir: Option<String>,
+}
+
+/// The shell command in a `response_item` payload, if it is a shell call.
+/// `["bash", "-lc", script]` is shown as the script itself.
+pub fn shell_call(payload: &Value) -> Option<ShellCall> {
+    let args = match payload.get("type").and_then(Value::as_str)? {
+        "function_call"
+            if matches!(
+                payload.get("name").and_then(Value::as_str),
+                Some("shell" | "container.exec" | "exec_command")
+            ) =>
+        {
+            // `arguments` is a JSON document encoded as a string
+            let raw = payload.get("arguments")?;
+            match raw.as_str() {
+                Some(s) => serde_json::from_str(s).ok()?,
+                None => raw.clone(),
+            }
+        }
+        "local_shell_call" => payload.get("action")?.clone(),
+        _ => return None,
+    };
+    let command = match args.get("command").or_else(|| args.get("cmd"))? {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => {
+            let parts: Vec<&str> = parts.iter().filter_map(Value::as_str).collect();
+            match parts.as_slice() {
+                [shell, flag, script] if is_shell_wrapper(shell, flag) => (*script).to_string(),
+                _ => parts.join(" "),
+            }
+        }
+        _ => return None,
+    };
+    Some(ShellCall {
+        command,
+        workdir: args
+            .get("workdir")
+            .or_else(|| args.get("working_directory"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+/// `bash -lc`, `sh -c`, `/bin/zsh -c` and the like
+fn is_shell_wrapper(shell: &str, flag: &str) -> bool {
+    let shell = shell.rsplit('/').next().unwrap_or(shell);
+    matches!(shell, "bash" | "sh" | "zsh") && flag.starts_with('-') && flag.ends_with('c')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -540,6 +648,42 @@ mod tests {
         assert_eq!(convs[0].messages[1].role, "assistant");
     }
 
+    #[test]
+    fn scan_keeps_shell_calls_as_tool_messages() {
+        let dir = TempDir::new().unwrap();
+        let codex_dir = dir.path().join(".codex");
+        let sessions = codex_dir.join("sessions");
+        fs::create_dir_all(&sessions).unwrap();
+
+        let content = r#"{"type":"response_item","timestamp":"2025-12-01T10:00:00Z","payload":{"role":"user","content":"list files"}}
+{"type":"response_item","timestamp":"2025-12-01T10:00:01Z","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"bash\",\"-lc\",\"ls -la\"],\"workdir\":\"/repo\"}","call_id":"c1"}}
+{"type":"response_item","timestamp":"2025-12-01T10:00:02Z","payload":{"type":"function_call","name":"update_plan","arguments":"{}","call_id":"c2"}}
+"#;
+        fs::write(sessions.join("rollout-test.jsonl"), content).unwrap();
+
+        let ctx = ScanContext::local_default(codex_dir.clone(), None);
+        let convs = CodexConnector::new().scan(&ctx).unwrap();
+        assert_eq!(convs[0].messages.len(), 2);
+        let call = &convs[0].messages[1];
+        assert_eq!(call.role, "tool");
+        assert_eq!(call.content, "[Tool: shell - ls -la]");
+        assert_eq!(
+            shell_call(&call.extra["payload"]),
+            Some(ShellCall {
+                command: "ls -la".to_string(),
+                workdir: Some("/repo".to_string()),
+            })
+        );
+        assert_eq!(
+            shell_call(
+                &json!({"type": "local_shell_call", "action": {"command": ["git", "status"]}})
+            )
+            .unwrap()
+            .command,
+            "git status"
+        );
+    }
+
     #[test]
     fn scan_parses_event_msg_user_message() {
         let dir = TempDir::new().unwrap();
@@ -590,7 +734,7 @@ mod tests {
         let sessions = codex_dir.join("sessions");
         fs::create_dir_all(&sessions).unwrap();
 
-        let content = r#"{"type":"session_meta","timestamp":"2025-12-01T10:00:00Z","payload":{"cwd":"/home/user/project"}}
+        let content = r#"{"type":"session_meta","timestamp":"2025-12-01T10:00:00Z","payload":{"id":"0199a2b3-c4d5","cwd":"/home/user/project"}}
 {"type":"response_item","timestamp":"2025-12-01T10:00:01Z","payload":{"role":"user","content":"Test"}}
 "#;
         fs::write(sessions.join("rollout-meta.jsonl"), content).unwrap();
@@ -604,6 +748,39 @@ mod tests {
             convs[0].workspace,
             Some(PathBuf::from("/home/user/project"))
         );
+        assert_eq!(convs[0].metadata["sessionId"], "0199a2b3-c4d5");
+    }
+
+    #[test]
+    fn scan_splits_session_when_turn_context_changes_cwd() {
+        let dir = TempDir::new().unwrap();
+        let codex_dir = dir.path().join(".codex");
+        let sessions = codex_dir.join("sessions");
+        fs::create_dir_all(&sessions).unwrap();
+
+        let content = r#"{"type":"session_meta","timestamp":"2025-12-01T10:00:00Z","payload":{"cwd":"/home/user/api"}}
+{"type":"response_item","timestamp":"2025-12-01T10:00:01Z","payload":{"role":"user","content":"Fix the api"}}
+{"type":"turn_context","timestamp":"2025-12-01T10:05:00Z","payload":{"cwd":"/home/user/api"}}
+{"type":"response_item","timestamp":"2025-12-01T10:05:01Z","payload":{"role":"assistant","content":"Done"}}
+{"type":"turn_context","timestamp":"2025-12-01T11:00:00Z","payload":{"cwd":"/home/user/web"}}
+{"type":"response_item","timestamp":"2025-12-01T11:00:01Z","payload":{"role":"user","content":"Now the web app"}}
+"#;
+        fs::write(sessions.join("rollout-moved.jsonl"), content).unwrap();
+
+        let connector = CodexConnector::new();
+        let ctx = ScanContext::local_default(codex_dir.clone(), None);
+        let convs = connector.scan(&ctx).unwrap();
+
+        assert_eq!(convs.len(), 2);
+        assert_eq!(convs[0].workspace, Some(PathBuf::from("/home/user/api")));
+        assert_eq!(convs[0].messages.len(), 2);
+        assert_eq!(convs[1].workspace, Some(PathBuf::from("/home/user/web")));
+        assert_eq!(convs[1].title.as_deref(), Some("Now the web app"));
+        assert_eq!(convs[1].messages[0].idx, 0);
+        assert_eq!(
+            convs[1].external_id,
+            convs[0].external_id.as_ref().map(|id| format!("{id}#1"))
+        );
     }
 
     #[test]
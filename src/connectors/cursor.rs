@@ -267,6 +267,7 @@ impl CursorConnector {
                 content: user_text.to_string(),
                 extra: serde_json::json!({}),
                 snippets: Vec::new(),
+                source: None,
             });
         }
 
@@ -359,6 +360,7 @@ impl CursorConnector {
             content: content.to_string(),
             extra: bubble.clone(),
             snippets: Vec::new(),
+            source: None,
         })
     }
 
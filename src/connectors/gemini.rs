@@ -277,10 +277,27 @@ impl Connector for GeminiConnector {
                 ended_at = created.or(ended_at);
 
                 // Extract content using flatten_content for consistency
-                let content_str = item
+                let mut content_str = item
                     .get("content")
                     .map(crate::connectors::flatten_content)
                     .unwrap_or_default();
+                // Gemini CLI records tool calls beside the text; name them so
+                // tool-only turns are kept and searchable
+                for call in item
+                    .get("toolCalls")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let name = call
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown");
+                    if !content_str.is_empty() {
+                        content_str.push('\n');
+                    }
+                    content_str.push_str(&format!("[Tool: {name}]"));
+                }
 
                 // Skip entries with empty content
                 if content_str.trim().is_empty() {
@@ -295,6 +312,7 @@ impl Connector for GeminiConnector {
                     content: content_str,
                     extra: item.clone(),
                     snippets: Vec::new(),
+                    source: None,
                 });
             }
 
@@ -476,6 +494,7 @@ mod tests {
             content: "# AGENTS.md instructions for /data/projects/myapp\nHello".into(),
             extra: serde_json::Value::Null,
             snippets: vec![],
+            source: None,
         }];
         let result = extract_workspace_from_content(&messages);
         assert_eq!(result, Some(PathBuf::from("/data/projects/myapp")));
@@ -491,6 +510,7 @@ mod tests {
             content: "Working directory: /home/user/project\nLet me help.".into(),
             extra: serde_json::Value::Null,
             snippets: vec![],
+            source: None,
         }];
         let result = extract_workspace_from_content(&messages);
         assert_eq!(result, Some(PathBuf::from("/home/user/project")));
@@ -506,6 +526,7 @@ mod tests {
             content: "Check the file at /data/projects/foo/src/main.rs".into(),
             extra: serde_json::Value::Null,
             snippets: vec![],
+            source: None,
         }];
         let result = extract_workspace_from_content(&messages);
         assert_eq!(result, Some(PathBuf::from("/data/projects/foo")));
@@ -521,6 +542,7 @@ mod tests {
             content: "Hello, how are you?".into(),
             extra: serde_json::Value::Null,
             snippets: vec![],
+            source: None,
         }];
         let result = extract_workspace_from_content(&messages);
         assert_eq!(result, None);
@@ -538,6 +560,7 @@ mod tests {
                     .into(),
             extra: serde_json::Value::Null,
             snippets: vec![],
+            source: None,
         }];
         // AGENTS.md pattern should be found first
         let result = extract_workspace_from_content(&messages);
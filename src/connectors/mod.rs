@@ -7,6 +7,7 @@ use std::path::PathBuf;
 
 pub mod aider;
 pub mod amp;
+pub mod cass;
 pub mod chatgpt;
 pub mod claude_code;
 pub mod cline;
@@ -145,6 +146,15 @@ impl ScanContext {
         }
     }
 
+    /// Restrict the scan to `window`: files last modified before its start are
+    /// skipped, even on a full scan.
+    pub fn windowed(mut self, window: &ScanWindow) -> Self {
+        if let Some(start) = window.not_before {
+            self.since_ts = Some(self.since_ts.map_or(start, |ts| ts.max(start)));
+        }
+        self
+    }
+
     /// Legacy accessor for backward compatibility.
     /// Returns data_dir as the "data_root" connectors were using before.
     #[deprecated(note = "Use data_dir directly or check scan_roots for explicit roots")]
@@ -158,6 +168,35 @@ impl ScanContext {
     }
 }
 
+/// Historical period that gets indexed at all (ms since epoch, both ends optional).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanWindow {
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+}
+
+impl ScanWindow {
+    pub fn is_unbounded(&self) -> bool {
+        self.not_before.is_none() && self.not_after.is_none()
+    }
+
+    /// Whether `conv` overlaps the window. Conversations without timestamps are kept.
+    pub fn contains(&self, conv: &NormalizedConversation) -> bool {
+        let times = conv.messages.iter().filter_map(|m| m.created_at);
+        let first = conv.started_at.or_else(|| times.clone().min());
+        let last = conv.ended_at.or_else(|| times.max()).or(conv.started_at);
+        let after_start = match (self.not_before, last) {
+            (Some(start), Some(last)) => last >= start,
+            _ => true,
+        };
+        let before_end = match (self.not_after, first) {
+            (Some(end), Some(first)) => first <= end,
+            _ => true,
+        };
+        after_start && before_end
+    }
+}
+
 /// Normalized conversation emitted by connectors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NormalizedConversation {
@@ -181,6 +220,33 @@ pub struct NormalizedMessage {
     pub content: String,
     pub extra: serde_json::Value,
     pub snippets: Vec<NormalizedSnippet>,
+    /// Where this message came from, when the connector knows more than the
+    /// conversation's `source_path` (merged files, line offsets).
+    #[serde(default)]
+    pub source: Option<MessageSource>,
+}
+
+/// Originating file and 1-based line of one message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageSource {
+    pub path: PathBuf,
+    pub line: Option<i64>,
+}
+
+impl MessageSource {
+    pub fn line(path: &std::path::Path, line: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line: Some(line as i64),
+        }
+    }
+
+    pub fn file(path: &std::path::Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -192,11 +258,124 @@ pub struct NormalizedSnippet {
     pub snippet_text: Option<String>,
 }
 
+/// Split `conv` wherever the working directory changes between messages.
+///
+/// `cwds[i]` is the directory recorded for `conv.messages[i]`; `None` means
+/// "same as before". Each run of messages in one directory becomes its own
+/// conversation with that workspace and messages renumbered from 0. The first
+/// segment keeps the original external id, later ones get `<id>#<n>`, and all
+/// carry `metadata.segment = {parent, index, count, first_idx}` linking them.
+pub fn split_by_workspace(
+    conv: NormalizedConversation,
+    cwds: &[Option<PathBuf>],
+) -> Vec<NormalizedConversation> {
+    let mut starts = vec![0];
+    let mut segment_ws = vec![None];
+    let mut current: Option<&PathBuf> = None;
+    for (i, cwd) in cwds.iter().enumerate().take(conv.messages.len()) {
+        let Some(cwd) = cwd else { continue };
+        match current {
+            Some(prev) if prev != cwd => {
+                starts.push(i);
+                segment_ws.push(Some(cwd.clone()));
+            }
+            None => segment_ws[0] = Some(cwd.clone()),
+            _ => {}
+        }
+        current = Some(cwd);
+    }
+    if starts.len() == 1 {
+        return vec![conv];
+    }
+
+    let parent = conv
+        .external_id
+        .clone()
+        .unwrap_or_else(|| conv.source_path.to_string_lossy().into_owned());
+    let count = starts.len();
+    let mut messages = conv.messages;
+    let mut segments = Vec::with_capacity(count);
+    for index in (0..count).rev() {
+        let first_idx = starts[index];
+        let mut seg_msgs = messages.split_off(first_idx);
+        for (i, m) in seg_msgs.iter_mut().enumerate() {
+            m.idx = i as i64;
+        }
+        let title = if index == 0 {
+            conv.title.clone()
+        } else {
+            seg_msgs
+                .iter()
+                .find(|m| m.role == "user")
+                .and_then(|m| m.content.lines().next())
+                .map(|l| l.chars().take(100).collect())
+                .or_else(|| conv.title.clone())
+        };
+        let mut metadata = conv.metadata.clone();
+        if !metadata.is_object() {
+            metadata = serde_json::json!({});
+        }
+        metadata["segment"] = serde_json::json!({
+            "parent": parent,
+            "index": index,
+            "count": count,
+            "first_idx": first_idx,
+        });
+        let first_ts = seg_msgs.iter().find_map(|m| m.created_at);
+        let last_ts = seg_msgs.iter().rev().find_map(|m| m.created_at);
+        segments.push(NormalizedConversation {
+            agent_slug: conv.agent_slug.clone(),
+            external_id: Some(if index == 0 {
+                parent.clone()
+            } else {
+                format!("{parent}#{index}")
+            }),
+            title,
+            workspace: segment_ws[index].clone().or_else(|| conv.workspace.clone()),
+            source_path: conv.source_path.clone(),
+            started_at: if index == 0 {
+                conv.started_at.or(first_ts)
+            } else {
+                first_ts
+            },
+            ended_at: if index == count - 1 {
+                conv.ended_at.or(last_ts)
+            } else {
+                last_ts
+            },
+            metadata,
+            messages: seg_msgs,
+        });
+    }
+    segments.reverse();
+    segments
+}
+
 pub trait Connector {
     fn detect(&self) -> DetectionResult;
     fn scan(&self, ctx: &ScanContext) -> anyhow::Result<Vec<NormalizedConversation>>;
 }
 
+/// Directories that may hold a VS Code-family editor's `<App>/User` data:
+/// this platform's config dir first (`~/.config`, `~/Library/Application Support`
+/// or `%APPDATA%`), then every platform's default location under home, which
+/// also covers homes shared with WSL or copied from another machine.
+pub fn editor_config_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config);
+    }
+    if let Some(home) = dirs::home_dir() {
+        for sub in [".config", "Library/Application Support", "AppData/Roaming"] {
+            let dir = home.join(sub);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
 /// Check if a file was modified since the given timestamp.
 /// Returns true if file should be processed (modified since timestamp or no timestamp given).
 /// Uses file modification time (mtime) for comparison.
@@ -257,6 +436,7 @@ pub fn parse_timestamp(val: &serde_json::Value) -> Option<i64> {
 /// - Array of content blocks with `{"type": "text", "text": "..."}`
 /// - Tool use blocks: `{"type": "tool_use", "name": "Read", "input": {...}}`
 /// - Codex `input_text` blocks: `{"type": "input_text", "text": "..."}`
+/// - Gemini function call parts: `{"functionCall": {"name": "...", "args": {...}}}`
 pub fn flatten_content(val: &serde_json::Value) -> String {
     // Direct string content (user messages in Claude Code)
     if let Some(s) = val.as_str() {
@@ -303,6 +483,14 @@ pub fn flatten_content(val: &serde_json::Value) -> String {
                     return Some(format!("[Tool: {name} - {desc}]"));
                 }
 
+                if let Some(call) = item.get("functionCall") {
+                    let name = call
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown");
+                    return Some(format!("[Tool: {name}]"));
+                }
+
                 None
             })
             .collect();
@@ -312,10 +500,203 @@ pub fn flatten_content(val: &serde_json::Value) -> String {
     String::new()
 }
 
+/// Keys that carry a file path in tool-call inputs across agents
+const TOOL_PATH_KEYS: &[&str] = &["file_path", "filePath", "notebook_path", "path"];
+
+/// Extract fenced code blocks (```` ``` ```` or `~~~`) from message content.
+///
+/// The info string supplies the language (`rust`, `py`, ...) and, when
+/// written as `lang:path` or `lang path`, the file path. Unterminated fences
+/// are ignored.
+pub fn extract_code_snippets(content: &str) -> Vec<NormalizedSnippet> {
+    let mut snippets = Vec::new();
+    let mut open: Option<(char, usize, &str)> = None;
+    let mut body: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+
+        match open {
+            None => {
+                if let Some(c) = fence_char
+                    && fence_len >= 3
+                {
+                    open = Some((c, fence_len, trimmed[fence_len..].trim()));
+                    body.clear();
+                }
+            }
+            Some((c, len, info)) => {
+                if fence_char == Some(c)
+                    && fence_len >= len
+                    && trimmed[fence_len..].trim().is_empty()
+                {
+                    if body.iter().any(|l| !l.trim().is_empty()) {
+                        let (language, file_path) = parse_fence_info(info);
+                        snippets.push(NormalizedSnippet {
+                            file_path,
+                            start_line: None,
+                            end_line: None,
+                            language,
+                            snippet_text: Some(body.join("\n")),
+                        });
+                    }
+                    open = None;
+                } else {
+                    body.push(line);
+                }
+            }
+        }
+    }
+    snippets
+}
+
+/// Split a fence info string into (language, file path).
+fn parse_fence_info(info: &str) -> (Option<String>, Option<PathBuf>) {
+    let mut words = info.split_whitespace();
+    let Some(first) = words.next() else {
+        return (None, None);
+    };
+    let (lang, path) = match first.split_once(':') {
+        Some((lang, path)) if !path.is_empty() => (lang, Some(path)),
+        _ => (
+            first,
+            words.next().filter(|w| w.contains('/') || w.contains('.')),
+        ),
+    };
+    let language = (!lang.is_empty()).then(|| lang.to_lowercase());
+    (language, path.map(PathBuf::from))
+}
+
+/// First file path mentioned by a tool call in a message's raw `extra` JSON.
+pub fn tool_call_file_path(extra: &serde_json::Value) -> Option<PathBuf> {
+    fn walk(val: &serde_json::Value, depth: usize) -> Option<PathBuf> {
+        if depth > 6 {
+            return None;
+        }
+        match val {
+            serde_json::Value::Object(map) => {
+                for key in TOOL_PATH_KEYS {
+                    if let Some(p) = map.get(*key).and_then(|v| v.as_str())
+                        && !p.is_empty()
+                    {
+                        return Some(PathBuf::from(p));
+                    }
+                }
+                map.values().find_map(|v| walk(v, depth + 1))
+            }
+            serde_json::Value::Array(arr) => arr.iter().find_map(|v| walk(v, depth + 1)),
+            _ => None,
+        }
+    }
+    walk(extra, 0)
+}
+
+/// Code snippets for every message: connector-supplied snippets are kept,
+/// otherwise fenced blocks are extracted from the content. Blocks without an
+/// explicit path inherit one from a tool call in the same or an adjacent message.
+pub fn conversation_snippets(messages: &[NormalizedMessage]) -> Vec<Vec<NormalizedSnippet>> {
+    messages
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            if !msg.snippets.is_empty() {
+                return msg.snippets.clone();
+            }
+            let mut snippets = extract_code_snippets(&msg.content);
+            if snippets.iter().any(|s| s.file_path.is_none()) {
+                let nearby = tool_call_file_path(&msg.extra)
+                    .or_else(|| {
+                        i.checked_sub(1)
+                            .and_then(|p| tool_call_file_path(&messages[p].extra))
+                    })
+                    .or_else(|| {
+                        messages
+                            .get(i + 1)
+                            .and_then(|n| tool_call_file_path(&n.extra))
+                    });
+                if let Some(path) = nearby {
+                    for s in snippets.iter_mut().filter(|s| s.file_path.is_none()) {
+                        s.file_path = Some(path.clone());
+                    }
+                }
+            }
+            snippets
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn msg(idx: i64, content: &str, extra: serde_json::Value) -> NormalizedMessage {
+        NormalizedMessage {
+            idx,
+            role: "assistant".to_string(),
+            author: None,
+            created_at: None,
+            content: content.to_string(),
+            extra,
+            snippets: Vec::new(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn editor_config_dirs_lists_the_platform_dir_first_without_repeats() {
+        let dirs = editor_config_dirs();
+        if let Some(config) = dirs::config_dir() {
+            assert_eq!(dirs.first(), Some(&config));
+        }
+        if let Some(home) = dirs::home_dir() {
+            assert!(dirs.contains(&home.join("AppData/Roaming")));
+        }
+        let unique: std::collections::HashSet<_> = dirs.iter().collect();
+        assert_eq!(unique.len(), dirs.len());
+    }
+
+    #[test]
+    fn extract_code_snippets_reads_language_and_path() {
+        let content = "Here:\n```rust:src/main.rs\nfn main() {}\n```\ntext\n~~~py\nprint(1)\n~~~\n```\nunterminated";
+        let snippets = extract_code_snippets(content);
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].language.as_deref(), Some("rust"));
+        assert_eq!(snippets[0].file_path, Some(PathBuf::from("src/main.rs")));
+        assert_eq!(snippets[0].snippet_text.as_deref(), Some("fn main() {}"));
+        assert_eq!(snippets[1].language.as_deref(), Some("py"));
+        assert!(snippets[1].file_path.is_none());
+    }
+
+    #[test]
+    fn extract_code_snippets_skips_empty_blocks_and_keeps_nested_fences() {
+        let content = "```\n\n```\n````md\n```js\nx\n```\n````";
+        let snippets = extract_code_snippets(content);
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].language.as_deref(), Some("md"));
+        assert_eq!(snippets[0].snippet_text.as_deref(), Some("```js\nx\n```"));
+    }
+
+    #[test]
+    fn conversation_snippets_infers_path_from_nearby_tool_call() {
+        let tool = serde_json::json!({
+            "message": {"content": [{"type": "tool_use", "name": "Edit", "input": {"file_path": "/repo/lib.rs"}}]}
+        });
+        let messages = vec![
+            msg(0, "[Tool: Edit - /repo/lib.rs]", tool),
+            msg(1, "```rust\nfn x() {}\n```", serde_json::Value::Null),
+            msg(2, "no code", serde_json::Value::Null),
+        ];
+        let snippets = conversation_snippets(&messages);
+        assert!(snippets[0].is_empty());
+        assert_eq!(
+            snippets[1][0].file_path,
+            Some(PathBuf::from("/repo/lib.rs"))
+        );
+        assert!(snippets[2].is_empty());
+    }
+
     #[test]
     fn scan_root_local_creates_with_defaults() {
         let root = ScanRoot::local(PathBuf::from("/test/path"));
@@ -514,6 +895,15 @@ mod tests {
         assert!(result.contains("Read"));
     }
 
+    #[test]
+    fn flatten_content_gemini_function_call_part() {
+        let val = serde_json::json!([
+            {"text": "Checking"},
+            {"functionCall": {"name": "read_file", "args": {"path": "a.rs"}}}
+        ]);
+        assert_eq!(super::flatten_content(&val), "Checking\n[Tool: read_file]");
+    }
+
     #[test]
     fn flatten_content_mixed_blocks() {
         let val = serde_json::json!([
@@ -577,6 +967,7 @@ mod tests {
             content: "test".into(),
             extra: serde_json::json!({}),
             snippets: vec![],
+            source: None,
         };
         assert_eq!(msg.role, "user");
         assert!(msg.author.is_none());
@@ -599,6 +990,7 @@ mod tests {
                 language: Some("rust".into()),
                 snippet_text: Some("fn test()".into()),
             }],
+            source: None,
         };
         assert_eq!(msg.idx, 5);
         assert_eq!(msg.author, Some("claude".into()));
@@ -646,6 +1038,7 @@ mod tests {
                     content: "Hello".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 },
                 NormalizedMessage {
                     idx: 1,
@@ -655,6 +1048,7 @@ mod tests {
                     content: "Hi there".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 },
             ],
         };
@@ -683,4 +1077,49 @@ mod tests {
         assert!(result.detected);
         assert_eq!(result.evidence.len(), 2);
     }
+
+    #[test]
+    fn split_by_workspace_links_segments_per_directory() {
+        let mut messages: Vec<_> = (0..5)
+            .map(|i| msg(i, &format!("m{i}"), serde_json::Value::Null))
+            .collect();
+        messages[3].role = "user".into();
+        let conv = NormalizedConversation {
+            agent_slug: "claude_code".into(),
+            external_id: Some("s.jsonl".into()),
+            title: Some("first".into()),
+            workspace: Some(PathBuf::from("/a")),
+            source_path: PathBuf::from("/logs/s.jsonl"),
+            started_at: Some(1),
+            ended_at: Some(9),
+            metadata: serde_json::json!({"source": "claude_code"}),
+            messages,
+        };
+        let a = Some(PathBuf::from("/a"));
+        let b = Some(PathBuf::from("/b"));
+
+        let same = split_by_workspace(conv.clone(), &[a.clone(), None, a.clone()]);
+        assert_eq!(same.len(), 1);
+        assert!(same[0].metadata.get("segment").is_none());
+
+        let parts = split_by_workspace(conv, &[None, a.clone(), None, b, None]);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].external_id.as_deref(), Some("s.jsonl"));
+        assert_eq!(parts[0].workspace, a);
+        assert_eq!(parts[0].messages.len(), 3);
+        assert_eq!(parts[0].title.as_deref(), Some("first"));
+        assert_eq!(parts[1].external_id.as_deref(), Some("s.jsonl#1"));
+        assert_eq!(parts[1].workspace, Some(PathBuf::from("/b")));
+        assert_eq!(parts[1].title.as_deref(), Some("m3"));
+        assert_eq!(
+            parts[1].messages.iter().map(|m| m.idx).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(parts[1].ended_at, Some(9));
+        assert_eq!(
+            parts[1].metadata["segment"],
+            serde_json::json!({"parent": "s.jsonl", "index": 1, "count": 2, "first_idx": 3})
+        );
+        assert_eq!(parts[1].metadata["source"], "claude_code");
+    }
 }
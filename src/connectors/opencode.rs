@@ -15,7 +15,8 @@ use serde::Deserialize;
 use walkdir::WalkDir;
 
 use crate::connectors::{
-    Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
+    Connector, DetectionResult, MessageSource, NormalizedConversation, NormalizedMessage,
+    ScanContext,
 };
 
 pub struct OpenCodeConnector;
@@ -368,6 +369,8 @@ fn load_messages(session_msg_dir: &PathBuf, part_dir: &PathBuf) -> Result<Vec<No
                 "session_id": msg_info.session_id,
             }),
             snippets: Vec::new(),
+            // Each message is its own file under message/<session>/
+            source: Some(MessageSource::file(&msg_file)),
         });
     }
 
@@ -18,8 +18,8 @@ use serde_json::Value;
 use walkdir::WalkDir;
 
 use crate::connectors::{
-    Connector, DetectionResult, NormalizedConversation, NormalizedMessage, ScanContext,
-    file_modified_since, parse_timestamp,
+    Connector, DetectionResult, MessageSource, NormalizedConversation, NormalizedMessage,
+    ScanContext, file_modified_since, parse_timestamp,
 };
 
 pub struct PiAgentConnector;
@@ -222,7 +222,7 @@ impl Connector for PiAgentConnector {
             let mut provider: Option<String> = None;
             let mut model_id: Option<String> = None;
 
-            for line in content.lines() {
+            for (line_no, line) in content.lines().enumerate() {
                 if line.trim().is_empty() {
                     continue;
                 }
@@ -305,6 +305,7 @@ impl Connector for PiAgentConnector {
                                 content: content_str,
                                 extra: val.clone(),
                                 snippets: Vec::new(),
+                                source: Some(MessageSource::line(&file, line_no + 1)),
                             });
                         }
                     }
@@ -0,0 +1,512 @@
+//! Background indexing daemon.
+//!
+//! `cass daemon` keeps the index fresh without a TUI open:
+//! - Watch-based incremental indexing (same watcher the TUI uses)
+//! - Periodic full reconciliation (cheap: unchanged conversations are skipped by content hash)
+//! - Remote source syncs according to each source's `sync_schedule`; a failed
+//!   sync is retried with exponential backoff (5 minutes, doubling, capped at
+//!   the schedule's interval), with attempts tracked in the database's `meta`
+//!   table (see `SqliteStorage::record_source_sync`)
+//! - Scheduled `[[reports]]` rendered and delivered when due (see `reports`);
+//!   a failed delivery backs off the same way, tracked in `report_status.json`
+//! - A pidfile (`daemon.pid`) and health file (`daemon.json`) in the data dir,
+//!   read back by `cass daemon status`. Besides liveness the health file carries
+//!   indexing metrics (pass latency, error counts, watcher queue depth and
+//!   per-connector lag) so silent indexing failures can be alerted on, and
+//!   the indexer's progress so an open TUI can show it in its footer.
+//! - A reindex request file (`reindex.request`): the TUI cannot reach the
+//!   daemon's indexer directly, so it drops this file and the daemon starts a
+//!   full reconciliation at its next heartbeat.
+//! - With `--advertise`, an mDNS announcement so `cass sources discover` on
+//!   other machines finds this one (see `sources::discover`)
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::hooks::{HookEvent, HooksConfig};
+use crate::indexer::{
+    self, IndexOptions, IndexerEvent, IndexingProgress, ProgressSnapshot, ReindexCommand,
+    WatchMetrics,
+};
+use crate::reports::{
+    ReportSchedule, ReportStatus, ReportsConfig, next_report_attempt, run_due_reports,
+};
+use crate::sources::config::{SourcesConfig, SyncSchedule};
+use crate::sources::discover;
+use crate::sources::sync::{SyncEngine, SyncReport, SyncStatus};
+use crate::storage::sqlite::{SourceSyncTimes, SqliteStorage};
+
+/// How often the daemon rewrites its health file
+const HEARTBEAT_SECS: u64 = 30;
+
+/// A daemon whose heartbeat is older than this is considered dead
+const STALE_AFTER_SECS: i64 = (HEARTBEAT_SECS as i64) * 3;
+
+/// Delay before retrying a failed sync; doubles with each further failure
+const SYNC_RETRY_BASE_MS: i64 = 5 * 60 * 1000;
+
+/// Schedule state for one remote source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSource {
+    pub name: String,
+    pub sync_schedule: SyncSchedule,
+    /// Unix millis of the last sync attempt (from the database's `meta` table)
+    pub last_sync: Option<i64>,
+    /// Unix millis when the next scheduled sync is due (None for manual sources)
+    pub next_sync: Option<i64>,
+    /// Failed attempts in a row; `next_sync` is a retry while this is nonzero
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+/// Schedule state for one configured report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledReport {
+    pub name: String,
+    pub schedule: ReportSchedule,
+    /// Unix millis of the last delivery (from `report_status.json`)
+    pub last_run: Option<i64>,
+    pub next_run: i64,
+    /// Failed runs in a row; `next_run` is a retry while this is nonzero
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+/// Health snapshot written to `daemon.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: i64,
+    pub heartbeat_at: i64,
+    pub reconcile_interval_secs: u64,
+    pub last_reconcile_at: Option<i64>,
+    pub next_reconcile_at: i64,
+    pub sources: Vec<ScheduledSource>,
+    #[serde(default)]
+    pub reports: Vec<ScheduledReport>,
+    /// Watcher events waiting for the indexer at the last heartbeat
+    #[serde(default)]
+    pub queue_depth: usize,
+    #[serde(default)]
+    pub metrics: WatchMetrics,
+    /// Indexer progress at the last heartbeat
+    #[serde(default)]
+    pub progress: ProgressSnapshot,
+}
+
+impl DaemonStatus {
+    /// True if the daemon refreshed its heartbeat recently
+    pub fn is_alive(&self, now_ms: i64) -> bool {
+        now_ms - self.heartbeat_at <= STALE_AFTER_SECS * 1000
+    }
+
+    /// Alive, and the most recent indexing pass (if any) succeeded
+    pub fn is_healthy(&self, now_ms: i64) -> bool {
+        self.is_alive(now_ms) && self.metrics.consecutive_errors == 0
+    }
+}
+
+pub fn pid_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("daemon.pid")
+}
+
+pub fn status_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("daemon.json")
+}
+
+pub fn reindex_request_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("reindex.request")
+}
+
+/// Ask a running daemon for a full reconciliation at its next heartbeat.
+pub fn request_reindex(data_dir: &Path) -> Result<()> {
+    let path = reindex_request_path(data_dir);
+    std::fs::write(&path, now_millis().to_string())
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// Read the last health snapshot, if the daemon has ever run
+pub fn read_status(data_dir: &Path) -> Option<DaemonStatus> {
+    let content = std::fs::read_to_string(status_path(data_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_status(data_dir: &Path, status: &DaemonStatus) -> Result<()> {
+    let path = status_path(data_dir);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(status)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// When a source with this schedule is next due, given its last sync
+pub fn next_sync_due(schedule: SyncSchedule, last_sync: Option<i64>, now_ms: i64) -> Option<i64> {
+    let interval_ms: i64 = match schedule {
+        SyncSchedule::Manual => return None,
+        SyncSchedule::Hourly => 60 * 60 * 1000,
+        SyncSchedule::Daily => 24 * 60 * 60 * 1000,
+    };
+    Some(last_sync.map_or(now_ms, |last| last + interval_ms))
+}
+
+/// When a source is next attempted: its schedule, or sooner when the last
+/// attempt failed and a backoff retry is due first
+pub fn next_sync_attempt(
+    schedule: SyncSchedule,
+    times: &SourceSyncTimes,
+    now_ms: i64,
+) -> Option<i64> {
+    let due = next_sync_due(schedule, times.last_sync, now_ms)?;
+    match times.last_sync {
+        Some(last) if times.consecutive_failures > 0 => {
+            let shift = (times.consecutive_failures - 1).min(16);
+            Some(due.min(last + (SYNC_RETRY_BASE_MS << shift)))
+        }
+        _ => Some(due),
+    }
+}
+
+/// Sync times of a source, or none recorded if the database can't be read
+pub fn source_sync_times(storage: Option<&SqliteStorage>, source: &str) -> SourceSyncTimes {
+    storage
+        .and_then(|s| s.get_source_sync_times(source).ok())
+        .unwrap_or_default()
+}
+
+fn scheduled_sources(db_path: &Path) -> Vec<ScheduledSource> {
+    let Ok(config) = SourcesConfig::load() else {
+        return Vec::new();
+    };
+    let storage = SqliteStorage::open_readonly(db_path).ok();
+    let now = now_millis();
+    config
+        .remote_sources()
+        .map(|s| {
+            let times = source_sync_times(storage.as_ref(), &s.name);
+            ScheduledSource {
+                name: s.name.clone(),
+                sync_schedule: s.sync_schedule,
+                last_sync: times.last_sync,
+                next_sync: next_sync_attempt(s.sync_schedule, &times, now),
+                consecutive_failures: times.consecutive_failures,
+            }
+        })
+        .collect()
+}
+
+fn scheduled_reports(data_dir: &Path) -> Vec<ScheduledReport> {
+    let status = ReportStatus::load(data_dir);
+    let now = now_millis();
+    ReportsConfig::load()
+        .reports
+        .iter()
+        .map(|r| ScheduledReport {
+            name: r.name.clone(),
+            schedule: r.schedule,
+            last_run: status.last_run.get(&r.name).copied(),
+            next_run: next_report_attempt(r, &status, now),
+            consecutive_failures: status.failures.get(&r.name).map_or(0, |f| f.consecutive),
+        })
+        .collect()
+}
+
+/// Sync every remote source whose schedule (or retry) is due. Returns how
+/// many synced at least one path.
+fn run_due_syncs(data_dir: &Path, db_path: &Path) -> usize {
+    let Ok(config) = SourcesConfig::load() else {
+        return 0;
+    };
+    let mut storage = match SqliteStorage::open(db_path) {
+        Ok(storage) => storage,
+        Err(e) => {
+            warn!("daemon: cannot open the database to schedule syncs: {e}");
+            return 0;
+        }
+    };
+    let engine = SyncEngine::new(data_dir);
+    let mut sync_status = SyncStatus::load(data_dir).unwrap_or_default();
+    let now = now_millis();
+    let mut attempted = 0;
+    let mut synced = 0;
+
+    for source in config.remote_sources() {
+        let times = source_sync_times(Some(&storage), &source.name);
+        let Some(due) = next_sync_attempt(source.sync_schedule, &times, now) else {
+            continue;
+        };
+        if due > now {
+            continue;
+        }
+        let retry = times.consecutive_failures;
+        info!(source = %source.name, schedule = %source.sync_schedule, retry, "daemon: scheduled sync");
+        attempted += 1;
+        let failed = match engine.sync_source(source) {
+            Ok(report) => {
+                let failed = report.successful_paths() == 0;
+                if failed {
+                    warn!(source = %source.name, "daemon: every path failed to sync");
+                } else {
+                    synced += 1;
+                }
+                sync_status.update(&source.name, &report);
+                HooksConfig::load().fire(HookEvent::SyncComplete, report.hook_payload());
+                failed
+            }
+            Err(e) => {
+                warn!(source = %source.name, "daemon: sync failed: {e}");
+                sync_status.update(&source.name, &SyncReport::failed(&source.name, e));
+                true
+            }
+        };
+        if let Err(e) = storage.record_source_sync(&source.name, now_millis(), failed) {
+            warn!(source = %source.name, "daemon: failed to record sync time: {e}");
+        }
+    }
+
+    if attempted > 0
+        && let Err(e) = sync_status.save(data_dir)
+    {
+        warn!("daemon: failed to save sync status: {e}");
+    }
+    synced
+}
+
+/// Run the daemon in the foreground until the watcher exits.
+pub fn run(
+    data_dir: PathBuf,
+    db_path: PathBuf,
+    reconcile_interval: Duration,
+    advertise: bool,
+) -> Result<()> {
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("creating data dir {}", data_dir.display()))?;
+
+    if let Some(existing) = read_status(&data_dir)
+        && existing.is_alive(now_millis())
+        && existing.pid != std::process::id()
+    {
+        bail!(
+            "daemon already running (pid {}); see `cass daemon status`",
+            existing.pid
+        );
+    }
+
+    let pid = std::process::id();
+    std::fs::write(pid_path(&data_dir), pid.to_string())
+        .with_context(|| format!("writing {}", pid_path(&data_dir).display()))?;
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let watcher_tx = tx.clone();
+    let progress = std::sync::Arc::new(IndexingProgress::default());
+    let opts = IndexOptions {
+        full: false,
+        force_rebuild: false,
+        watch: true,
+        watch_once_paths: None,
+        db_path: db_path.clone(),
+        data_dir: data_dir.clone(),
+        gc: false,
+        progress: Some(progress.clone()),
+    };
+    let watcher = std::thread::spawn(move || indexer::run_index(opts, Some((watcher_tx, rx))));
+
+    let started_at = now_millis();
+    let interval_ms = reconcile_interval.as_millis() as i64;
+    let mut status = DaemonStatus {
+        pid,
+        started_at,
+        heartbeat_at: started_at,
+        reconcile_interval_secs: reconcile_interval.as_secs(),
+        last_reconcile_at: None,
+        next_reconcile_at: started_at + interval_ms,
+        sources: scheduled_sources(&db_path),
+        reports: scheduled_reports(&data_dir),
+        queue_depth: 0,
+        metrics: WatchMetrics::default(),
+        progress: ProgressSnapshot::default(),
+    };
+    info!(pid, "daemon started");
+
+    // Withdrawn when the daemon returns
+    let _advertisement = if advertise {
+        match discover::advertise() {
+            Ok(ad) => {
+                info!(
+                    "daemon: advertising over mDNS as {}",
+                    discover::SERVICE_TYPE
+                );
+                Some(ad)
+            }
+            Err(e) => {
+                warn!("daemon: mDNS advertising failed: {e:#}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    while !watcher.is_finished() {
+        let now = now_millis();
+        let mut needs_reconcile = now >= status.next_reconcile_at;
+        if run_due_syncs(&data_dir, &db_path) > 0 {
+            needs_reconcile = true;
+        }
+        if std::fs::remove_file(reindex_request_path(&data_dir)).is_ok() {
+            info!("daemon: reindex requested");
+            needs_reconcile = true;
+        }
+        if needs_reconcile {
+            info!("daemon: full reconciliation");
+            if tx
+                .send(IndexerEvent::Command(ReindexCommand::Full))
+                .is_err()
+            {
+                break;
+            }
+            status.last_reconcile_at = Some(now);
+            status.next_reconcile_at = now + interval_ms;
+        }
+        run_due_reports(&data_dir, &db_path);
+
+        status.heartbeat_at = now;
+        status.sources = scheduled_sources(&db_path);
+        status.reports = scheduled_reports(&data_dir);
+        status.queue_depth = tx.len();
+        if let Ok(metrics) = progress.watch.lock() {
+            status.metrics = metrics.clone();
+        }
+        status.progress = progress.snapshot();
+        if let Err(e) = write_status(&data_dir, &status) {
+            warn!("daemon: failed to write health file: {e}");
+        }
+        std::thread::sleep(Duration::from_secs(HEARTBEAT_SECS));
+    }
+
+    let _ = std::fs::remove_file(pid_path(&data_dir));
+    match watcher.join() {
+        Ok(result) => result,
+        Err(_) => bail!("indexer watcher panicked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_sources_are_never_due() {
+        assert_eq!(next_sync_due(SyncSchedule::Manual, None, 1_000), None);
+        assert_eq!(next_sync_due(SyncSchedule::Manual, Some(0), 1_000), None);
+    }
+
+    #[test]
+    fn scheduled_sources_due_immediately_when_never_synced() {
+        assert_eq!(
+            next_sync_due(SyncSchedule::Hourly, None, 5_000),
+            Some(5_000)
+        );
+        assert_eq!(next_sync_due(SyncSchedule::Daily, None, 5_000), Some(5_000));
+    }
+
+    #[test]
+    fn scheduled_sources_due_after_interval() {
+        let hour = 60 * 60 * 1000;
+        assert_eq!(next_sync_due(SyncSchedule::Hourly, Some(0), 10), Some(hour));
+        assert_eq!(
+            next_sync_due(SyncSchedule::Daily, Some(0), 10),
+            Some(24 * hour)
+        );
+    }
+
+    #[test]
+    fn failed_syncs_retry_with_backoff_capped_by_schedule() {
+        let hour = 60 * 60 * 1000;
+        let times = |failures| SourceSyncTimes {
+            last_sync: Some(0),
+            consecutive_failures: failures,
+            ..Default::default()
+        };
+        assert_eq!(
+            next_sync_attempt(SyncSchedule::Daily, &times(0), 10),
+            Some(24 * hour)
+        );
+        assert_eq!(
+            next_sync_attempt(SyncSchedule::Daily, &times(1), 10),
+            Some(SYNC_RETRY_BASE_MS)
+        );
+        assert_eq!(
+            next_sync_attempt(SyncSchedule::Daily, &times(3), 10),
+            Some(4 * SYNC_RETRY_BASE_MS)
+        );
+        assert_eq!(
+            next_sync_attempt(SyncSchedule::Hourly, &times(9), 10),
+            Some(hour)
+        );
+        assert_eq!(next_sync_attempt(SyncSchedule::Manual, &times(2), 10), None);
+    }
+
+    #[test]
+    fn status_roundtrip_and_liveness() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let status = DaemonStatus {
+            pid: 42,
+            started_at: 1_000,
+            heartbeat_at: 1_000,
+            reconcile_interval_secs: 3600,
+            last_reconcile_at: None,
+            next_reconcile_at: 3_601_000,
+            sources: Vec::new(),
+            reports: Vec::new(),
+            queue_depth: 0,
+            metrics: WatchMetrics::default(),
+            progress: ProgressSnapshot {
+                added: 3,
+                last_commit_at: Some(900),
+                ..Default::default()
+            },
+        };
+        write_status(dir.path(), &status).unwrap();
+
+        let read = read_status(dir.path()).unwrap();
+        assert_eq!(read.pid, 42);
+        assert_eq!(read.progress, status.progress);
+        assert!(read.is_alive(1_000 + 10_000));
+        assert!(!read.is_alive(1_000 + STALE_AFTER_SECS * 1000 + 1));
+    }
+
+    #[test]
+    fn failing_passes_mark_daemon_unhealthy() {
+        let mut status: DaemonStatus = serde_json::from_value(serde_json::json!({
+            "pid": 7,
+            "started_at": 0,
+            "heartbeat_at": 1_000,
+            "reconcile_interval_secs": 3600,
+            "last_reconcile_at": null,
+            "next_reconcile_at": 3_600_000,
+            "sources": [],
+        }))
+        .expect("health files from older daemons still parse");
+        assert!(status.is_healthy(2_000));
+
+        status.metrics.errors = 3;
+        status.metrics.consecutive_errors = 1;
+        assert!(status.is_alive(2_000));
+        assert!(!status.is_healthy(2_000));
+
+        status.metrics.consecutive_errors = 0;
+        assert!(status.is_healthy(2_000));
+        assert!(!status.is_healthy(1_000 + STALE_AFTER_SECS * 1000 + 1));
+    }
+}
@@ -0,0 +1,352 @@
+//! Decision log extraction (`cass decisions`).
+//!
+//! Assistant messages are scanned for decision-like statements ("we chose X
+//! because...", "decided to...", "going with...") using phrase heuristics.
+//! Matches are grouped per workspace into a log that can be printed, emitted as
+//! JSON, or exported to Markdown. Extraction runs at query time, so it always
+//! reflects the current heuristics and nothing extra is stored in the index.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+
+/// Phrases that mark a sentence as a decision
+const DECISION_MARKERS: &[&str] = &[
+    "we chose",
+    "i chose",
+    "we've chosen",
+    "i've chosen",
+    "chose to",
+    "decided to",
+    "decided on",
+    "decided against",
+    "we decided",
+    "i decided",
+    "the decision is",
+    "decision:",
+    "going with",
+    "go with",
+    "opted for",
+    "opted to",
+    "opting for",
+    "settled on",
+    "in favor of",
+    "instead of",
+    "rather than",
+];
+
+/// Phrases introducing the reason for a decision
+const RATIONALE_MARKERS: &[&str] = &[
+    " because ",
+    " since ",
+    " so that ",
+    " as it ",
+    " due to ",
+    " which means ",
+    " to avoid ",
+];
+
+/// Sentences longer than this are usually explanations, not decisions
+const MAX_STATEMENT_CHARS: usize = 400;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Decision {
+    pub statement: String,
+    /// Text after "because"/"since"/..., when the statement gives a reason
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rationale: Option<String>,
+    pub conversation_title: Option<String>,
+    pub agent: String,
+    pub source_path: String,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecisionLog {
+    /// Workspace path -> decisions in chronological order
+    pub workspaces: BTreeMap<String, Vec<Decision>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecisionQuery {
+    /// Workspace path; subdirectories match too
+    pub workspace: Option<String>,
+    pub agents: Vec<String>,
+    /// Only messages at or after this timestamp (ms)
+    pub since: Option<i64>,
+    /// Maximum decisions per workspace (most recent kept)
+    pub limit: usize,
+}
+
+impl DecisionLog {
+    pub fn total(&self) -> usize {
+        self.workspaces.values().map(Vec::len).sum()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Decision Log\n");
+        if self.workspaces.is_empty() {
+            out.push_str("\n_No decisions found._\n");
+        }
+        for (workspace, decisions) in &self.workspaces {
+            out.push_str(&format!("\n## {workspace}\n\n"));
+            for d in decisions {
+                let date = d
+                    .created_at
+                    .and_then(chrono::DateTime::from_timestamp_millis)
+                    .map(|t| t.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "undated".to_string());
+                out.push_str(&format!("- **{date}** {}\n", d.statement));
+                if let Some(why) = &d.rationale {
+                    out.push_str(&format!("  - _Why:_ {why}\n"));
+                }
+                let title = d.conversation_title.as_deref().unwrap_or("untitled");
+                out.push_str(&format!(
+                    "  - _Source:_ {title} ({}, `{}`)\n",
+                    d.agent, d.source_path
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Collect decisions from assistant messages matching `query`.
+pub fn extract_decisions(conn: &Connection, query: &DecisionQuery) -> Result<DecisionLog> {
+    let mut sql = String::from(
+        "SELECT COALESCE(w.path, ''), c.title, a.slug, c.source_path,
+                COALESCE(m.created_at, c.started_at), cass_text(m.content)
+         FROM messages m
+         JOIN conversations c ON m.conversation_id = c.id
+         JOIN agents a ON c.agent_id = a.id
+         LEFT JOIN workspaces w ON c.workspace_id = w.id
+         WHERE m.role IN ('agent', 'assistant')",
+    );
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(ws) = &query.workspace {
+        let ws = ws.trim_end_matches('/').to_string();
+        sql.push_str(&format!(
+            " AND (w.path = ?{n} OR w.path LIKE ?{n} || '/%')",
+            n = params.len() + 1
+        ));
+        params.push(Box::new(ws));
+    }
+    if !query.agents.is_empty() {
+        let placeholders: Vec<String> = query
+            .agents
+            .iter()
+            .map(|agent| {
+                params.push(Box::new(agent.clone()));
+                format!("?{}", params.len())
+            })
+            .collect();
+        sql.push_str(&format!(" AND a.slug IN ({})", placeholders.join(", ")));
+    }
+    if let Some(since) = query.since {
+        sql.push_str(&format!(
+            " AND COALESCE(m.created_at, c.started_at) >= ?{}",
+            params.len() + 1
+        ));
+        params.push(Box::new(since));
+    }
+    sql.push_str(" ORDER BY COALESCE(m.created_at, c.started_at), m.conversation_id, m.idx");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(
+        rusqlite::params_from_iter(params.iter().map(|p| &**p)),
+        |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, Option<String>>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<i64>>(4)?,
+                r.get::<_, String>(5)?,
+            ))
+        },
+    )?;
+
+    let mut log = DecisionLog::default();
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    for row in rows {
+        let (workspace, title, agent, source_path, created_at, content) = row?;
+        for statement in decision_sentences(&content) {
+            if !seen.insert((workspace.clone(), statement.to_lowercase())) {
+                continue;
+            }
+            let rationale = rationale_of(&statement);
+            log.workspaces
+                .entry(workspace.clone())
+                .or_default()
+                .push(Decision {
+                    statement,
+                    rationale,
+                    conversation_title: title.clone(),
+                    agent: agent.clone(),
+                    source_path: source_path.clone(),
+                    created_at,
+                });
+        }
+    }
+    if query.limit > 0 {
+        for decisions in log.workspaces.values_mut() {
+            let excess = decisions.len().saturating_sub(query.limit);
+            decisions.drain(..excess);
+        }
+    }
+    Ok(log)
+}
+
+/// Decision-like sentences in one message, skipping code blocks and quotes.
+pub fn decision_sentences(content: &str) -> Vec<String> {
+    let mut prose = String::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || trimmed.starts_with('>') || trimmed.starts_with('|') {
+            continue;
+        }
+        let trimmed = trimmed
+            .trim_start_matches(['-', '*', '#'])
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')')
+            .trim();
+        if trimmed.is_empty() {
+            // Paragraph break ends a sentence
+            prose.push_str(".\n");
+        } else {
+            prose.push_str(trimmed);
+            prose.push(' ');
+        }
+    }
+
+    split_sentences(&prose)
+        .into_iter()
+        .filter(|s| s.chars().count() <= MAX_STATEMENT_CHARS && is_decision(s))
+        .collect()
+}
+
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let boundary = matches!(c, '.' | '!' | '?' | '\n')
+            && chars.peek().is_none_or(|next| next.is_whitespace());
+        if boundary {
+            let sentence = current.trim().trim_end_matches('.').trim().to_string();
+            if sentence.split_whitespace().count() >= 4 {
+                sentences.push(format!("{sentence}."));
+            }
+            current.clear();
+        }
+    }
+    let tail = current.trim().trim_end_matches('.').trim();
+    if tail.split_whitespace().count() >= 4 {
+        sentences.push(format!("{tail}."));
+    }
+    sentences
+}
+
+fn is_decision(sentence: &str) -> bool {
+    let lower = sentence.to_lowercase();
+    // Questions and hypotheticals aren't decisions
+    if sentence.ends_with("?.")
+        || ["should we", "shall we", "if we", "could we", "do you want"]
+            .iter()
+            .any(|p| lower.contains(p))
+    {
+        return false;
+    }
+    let marker = DECISION_MARKERS.iter().any(|m| lower.contains(m));
+    // "instead of"/"rather than" alone are too common; require a reason with them
+    let weak_only = DECISION_MARKERS
+        .iter()
+        .filter(|m| lower.contains(*m))
+        .all(|m| matches!(*m, "instead of" | "rather than" | "in favor of" | "go with"));
+    marker && (!weak_only || rationale_of(sentence).is_some())
+}
+
+fn rationale_of(sentence: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so they can slice `sentence`
+    let lower = sentence.to_ascii_lowercase();
+    RATIONALE_MARKERS
+        .iter()
+        .filter_map(|m| lower.find(m).map(|i| i + m.len()))
+        .min()
+        .map(|start| sentence[start..].trim().trim_end_matches('.').to_string())
+        .filter(|r| !r.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_decisions_with_rationale() {
+        let content = "I looked at both options.\n\nWe chose SQLite over Postgres because the tool must run offline. Next I'll write the schema.";
+        let found = decision_sentences(content);
+        assert_eq!(
+            found,
+            vec!["We chose SQLite over Postgres because the tool must run offline."]
+        );
+        assert_eq!(
+            rationale_of(&found[0]).as_deref(),
+            Some("the tool must run offline")
+        );
+    }
+
+    #[test]
+    fn rationale_survives_case_changes_that_resize_text() {
+        // 'İ' lowercases to three bytes, which used to shift the slice offset
+        assert_eq!(
+            rationale_of("İİ We chose this because it's faster.").as_deref(),
+            Some("it's faster")
+        );
+    }
+
+    #[test]
+    fn skips_code_questions_and_weak_markers() {
+        let content = "```rust\n// we decided to inline this because it is hot\n```\nShould we go with tokio instead of async-std?\nUse a map instead of a vec here.\nI decided to keep the retry loop.";
+        assert_eq!(
+            decision_sentences(content),
+            vec!["I decided to keep the retry loop."]
+        );
+    }
+
+    #[test]
+    fn weak_markers_need_a_reason() {
+        let found = decision_sentences(
+            "- Going forward we use rustls rather than openssl since it avoids system deps",
+        );
+        assert_eq!(found.len(), 1);
+        assert!(found[0].starts_with("Going forward"));
+    }
+
+    #[test]
+    fn markdown_groups_by_workspace() {
+        let mut log = DecisionLog::default();
+        log.workspaces.insert(
+            "/ws/app".into(),
+            vec![Decision {
+                statement: "Decided to drop the cache because it was stale.".into(),
+                rationale: Some("it was stale".into()),
+                conversation_title: Some("Cache cleanup".into()),
+                agent: "codex".into(),
+                source_path: "/logs/a.jsonl".into(),
+                created_at: Some(1_700_000_000_000),
+            }],
+        );
+        let md = log.to_markdown();
+        assert!(md.contains("## /ws/app"));
+        assert!(md.contains("- **2023-11-14** Decided to drop the cache"));
+        assert!(md.contains("_Why:_ it was stale"));
+        assert_eq!(log.total(), 1);
+    }
+}
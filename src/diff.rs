@@ -0,0 +1,152 @@
+//! Line diffs: change counts, plain unified hunks, and the ops the viewer colors.
+
+/// Unchanged lines shown around each change in a diff hunk
+const DIFF_CONTEXT: usize = 3;
+/// Largest LCS table computed for a diff; bigger edits show as remove-all/add-all
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Lines added and removed when `old` becomes `new`.
+pub fn diff_stats(old: &str, new: &str) -> (usize, usize) {
+    let ops = diff_lines(old, new);
+    let count = |op: DiffOp| ops.iter().filter(|(o, _)| *o == op).count();
+    (count(DiffOp::Add), count(DiffOp::Remove))
+}
+
+/// Plain-text unified diff hunks (`@@` headers, then ` `/`-`/`+` prefixed lines).
+pub fn unified_diff(old: &str, new: &str) -> Vec<String> {
+    let ops = diff_lines(old, new);
+    hunks(&ops)
+        .into_iter()
+        .flat_map(|hunk| {
+            std::iter::once(hunk.header).chain(hunk.lines.into_iter().map(|(op, line)| {
+                let mark = match op {
+                    DiffOp::Keep => ' ',
+                    DiffOp::Remove => '-',
+                    DiffOp::Add => '+',
+                };
+                format!("{mark}{line}")
+            }))
+        })
+        .collect()
+}
+
+/// Line diff of `old` against `new` (longest common subsequence).
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(DiffOp, &'a str)> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (am, bm) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut out: Vec<(DiffOp, &str)> = a[..prefix].iter().map(|l| (DiffOp::Keep, *l)).collect();
+    if am.len().saturating_mul(bm.len()) > MAX_DIFF_CELLS {
+        out.extend(am.iter().map(|l| (DiffOp::Remove, *l)));
+        out.extend(bm.iter().map(|l| (DiffOp::Add, *l)));
+    } else {
+        // lcs[i][j]: common subsequence length of am[i..] and bm[j..]
+        let width = bm.len() + 1;
+        let mut lcs = vec![0u32; (am.len() + 1) * width];
+        for i in (0..am.len()).rev() {
+            for j in (0..bm.len()).rev() {
+                lcs[i * width + j] = if am[i] == bm[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < am.len() && j < bm.len() {
+            if am[i] == bm[j] {
+                out.push((DiffOp::Keep, am[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                out.push((DiffOp::Remove, am[i]));
+                i += 1;
+            } else {
+                out.push((DiffOp::Add, bm[j]));
+                j += 1;
+            }
+        }
+        out.extend(am[i..].iter().map(|l| (DiffOp::Remove, *l)));
+        out.extend(bm[j..].iter().map(|l| (DiffOp::Add, *l)));
+    }
+    out.extend(a[a.len() - suffix..].iter().map(|l| (DiffOp::Keep, *l)));
+    out
+}
+
+pub struct Hunk<'a> {
+    pub header: String,
+    pub lines: Vec<(DiffOp, &'a str)>,
+}
+
+/// Group a diff into unified hunks with [`DIFF_CONTEXT`] lines around each change.
+pub fn hunks<'a>(ops: &[(DiffOp, &'a str)]) -> Vec<Hunk<'a>> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != DiffOp::Keep)
+    {
+        let start = i.saturating_sub(DIFF_CONTEXT);
+        let end = (i + DIFF_CONTEXT + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let before = &ops[..start];
+            let old_start = before.iter().filter(|(op, _)| *op != DiffOp::Add).count();
+            let new_start = before
+                .iter()
+                .filter(|(op, _)| *op != DiffOp::Remove)
+                .count();
+            let lines = ops[start..end].to_vec();
+            let old_len = lines.iter().filter(|(op, _)| *op != DiffOp::Add).count();
+            let new_len = lines.iter().filter(|(op, _)| *op != DiffOp::Remove).count();
+            // Unified diff numbering: an empty side starts at the line before it
+            let first = |at: usize, len: usize| if len == 0 { at } else { at + 1 };
+            Hunk {
+                header: format!(
+                    "@@ -{},{old_len} +{},{new_len} @@",
+                    first(old_start, old_len),
+                    first(new_start, new_len)
+                ),
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let far_apart: String = (0..20).map(|i| format!("l{i}\n")).collect();
+        let edited = far_apart.replace("l1\n", "L1\n").replace("l18\n", "L18\n");
+        assert_eq!(hunks(&diff_lines(&far_apart, &edited)).len(), 2);
+        assert_eq!(diff_stats(&far_apart, &edited), (2, 2));
+        assert_eq!(
+            unified_diff("a\nb\n", "a\nc\n"),
+            ["@@ -1,2 +1,2 @@", " a", "-b", "+c"]
+        );
+    }
+}
@@ -1,11 +1,13 @@
-//! Export functionality for search results.
+//! Export functionality for search results and indexed conversations.
 //!
-//! Provides conversion of search results to various output formats:
+//! Provides conversion of search results and conversations to various output formats:
 //! - Markdown - formatted with headers, code blocks, and metadata
 //! - JSON - structured data for programmatic use
 //! - Plain Text - simple, copy-paste friendly format
 
+use crate::model::types::{Conversation, Message, MessageRole};
 use crate::search::query::SearchHit;
+use crate::storage::sqlite::SqliteStorage;
 use chrono::{DateTime, Utc};
 
 /// Supported export formats
@@ -90,6 +92,44 @@ pub fn export_results(hits: &[SearchHit], format: ExportFormat, options: &Export
     }
 }
 
+/// Export one indexed conversation and its messages to the specified format.
+/// With `include_path`, each message carries the file and line it came from.
+pub fn export_conversation(
+    conv: &Conversation,
+    messages: &[Message],
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> String {
+    match format {
+        ExportFormat::Markdown => conversation_markdown(conv, messages, options),
+        ExportFormat::Json => conversation_json(conv, messages, options),
+        ExportFormat::PlainText => conversation_plain_text(conv, messages, options),
+    }
+}
+
+/// Point each hit at the file and line its matched message came from, when the
+/// database recorded per-message provenance (merged sources, JSONL line offsets).
+/// Hits without it keep the conversation file and message number.
+pub fn resolve_message_locations(hits: &mut [SearchHit], storage: &SqliteStorage) {
+    for hit in hits {
+        let Some(idx) = hit.line_number.map(|n| n as i64 - 1) else {
+            continue;
+        };
+        let Ok(Some((path, line))) =
+            storage.message_provenance(&hit.source_id, &hit.source_path, idx)
+        else {
+            continue;
+        };
+        if path.is_none() && line.is_none() {
+            continue;
+        }
+        if let Some(path) = path {
+            hit.source_path = path;
+        }
+        hit.line_number = line.map(|l| l as usize);
+    }
+}
+
 /// Escape special Markdown characters to prevent formatting issues or injection.
 fn escape_markdown(text: &str) -> String {
     text.replace('\\', "\\\\")
@@ -327,6 +367,184 @@ fn export_plain_text(hits: &[SearchHit], options: &ExportOptions) -> String {
     output
 }
 
+fn role_label(role: &MessageRole) -> &str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Agent => "assistant",
+        MessageRole::Tool => "tool",
+        MessageRole::System => "system",
+        MessageRole::Other(r) => r.as_str(),
+    }
+}
+
+/// `path:line` a message came from, when it recorded its own provenance
+fn message_location(msg: &Message, conv_path: &str) -> Option<String> {
+    if msg.source_path.is_none() && msg.source_line.is_none() {
+        return None;
+    }
+    Some(match msg.location(conv_path) {
+        (path, Some(line)) => format!("{path}:{line}"),
+        (path, None) => path.to_string(),
+    })
+}
+
+/// Export a conversation to Markdown format
+fn conversation_markdown(
+    conv: &Conversation,
+    messages: &[Message],
+    options: &ExportOptions,
+) -> String {
+    let mut output = String::new();
+    let conv_path = conv.source_path.to_string_lossy();
+
+    let title = conv.title.as_deref().unwrap_or("Conversation Export");
+    output.push_str(&format!("# {}\n\n", escape_markdown(title)));
+
+    output.push_str("| Field | Value |\n");
+    output.push_str("|-------|-------|\n");
+    output.push_str(&format!(
+        "| Agent | {} |\n",
+        escape_markdown(&conv.agent_slug)
+    ));
+    if let Some(ws) = &conv.workspace {
+        output.push_str(&format!(
+            "| Workspace | `{}` |\n",
+            ws.to_string_lossy().replace('`', "")
+        ));
+    }
+    if let Some(ts) = conv.started_at
+        && let Some(dt) = DateTime::from_timestamp_millis(ts)
+    {
+        output.push_str(&format!("| Started | {} |\n", dt.format("%Y-%m-%d %H:%M")));
+    }
+    if options.include_path {
+        output.push_str(&format!("| Source | `{}` |\n", conv_path.replace('`', "")));
+    }
+    output.push('\n');
+
+    output.push_str(&format!(
+        "**Messages:** {} | **Exported:** {}\n\n",
+        messages.len(),
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+    output.push_str("---\n\n");
+
+    for msg in messages {
+        match &msg.role {
+            MessageRole::User => output.push_str("## 👤 User\n\n"),
+            MessageRole::Agent => output.push_str("## 🤖 Assistant\n\n"),
+            MessageRole::System => output.push_str("## ⚙️ System\n\n"),
+            role => output.push_str(&format!("## {}\n\n", escape_markdown(role_label(role)))),
+        }
+        if options.include_path
+            && let Some(loc) = message_location(msg, &conv_path)
+        {
+            output.push_str(&format!("*Source: `{}`*\n\n", loc.replace('`', "")));
+        }
+        if !msg.content.is_empty() {
+            output.push_str(&msg.content);
+            output.push_str("\n\n");
+        }
+        output.push_str("---\n\n");
+    }
+
+    output
+}
+
+/// Export a conversation to JSON format
+fn conversation_json(conv: &Conversation, messages: &[Message], options: &ExportOptions) -> String {
+    let conv_path = conv.source_path.to_string_lossy();
+    let mut conversation = serde_json::json!({
+        "id": conv.id,
+        "agent": conv.agent_slug,
+        "workspace": conv.workspace,
+        "title": conv.title,
+        "external_id": conv.external_id,
+        "started_at": conv.started_at,
+        "ended_at": conv.ended_at,
+        "source_id": conv.source_id,
+    });
+    if options.include_path {
+        conversation["source_path"] = serde_json::json!(conv_path);
+    }
+
+    let export_data = serde_json::json!({
+        "conversation": conversation,
+        "count": messages.len(),
+        "exported_at": Utc::now().to_rfc3339(),
+        "messages": messages.iter().map(|msg| {
+            let mut obj = serde_json::json!({
+                "idx": msg.idx,
+                "role": role_label(&msg.role),
+                "content": msg.content,
+            });
+            if let Some(author) = &msg.author {
+                obj["author"] = serde_json::json!(author);
+            }
+            if let Some(ts) = msg.created_at {
+                obj["created_at"] = serde_json::json!(ts);
+            }
+            // Originating file:line when the message carries its own provenance, as in `show`
+            if options.include_path && (msg.source_path.is_some() || msg.source_line.is_some()) {
+                let (path, line) = msg.location(&conv_path);
+                obj["source_path"] = serde_json::json!(path);
+                obj["source_line"] = serde_json::json!(line);
+            }
+            obj
+        }).collect::<Vec<_>>()
+    });
+
+    serde_json::to_string_pretty(&export_data).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Export a conversation to plain text format
+fn conversation_plain_text(
+    conv: &Conversation,
+    messages: &[Message],
+    options: &ExportOptions,
+) -> String {
+    let mut output = String::new();
+    let conv_path = conv.source_path.to_string_lossy();
+
+    output.push_str(conv.title.as_deref().unwrap_or("CONVERSATION"));
+    output.push('\n');
+    output.push_str(&"=".repeat(60));
+    output.push('\n');
+    output.push_str(&format!("Agent: {}\n", conv.agent_slug));
+    if let Some(ws) = &conv.workspace {
+        output.push_str(&format!("Workspace: {}\n", ws.display()));
+    }
+    if let Some(ts) = conv.started_at
+        && let Some(dt) = DateTime::from_timestamp_millis(ts)
+    {
+        output.push_str(&format!("Started: {}\n", dt.format("%Y-%m-%d %H:%M")));
+    }
+    if options.include_path {
+        output.push_str(&format!("Source: {conv_path}\n"));
+    }
+    output.push_str(&"=".repeat(60));
+    output.push_str("\n\n");
+
+    for msg in messages {
+        output.push_str(&format!(
+            "=== {} ===\n",
+            role_label(&msg.role).to_uppercase()
+        ));
+        if options.include_path
+            && let Some(loc) = message_location(msg, &conv_path)
+        {
+            output.push_str(&format!("Source: {loc}\n"));
+        }
+        output.push('\n');
+        if !msg.content.is_empty() {
+            output.push_str(&msg.content);
+            output.push_str("\n\n");
+        }
+    }
+
+    output
+}
+
 /// Truncate text to max length (in characters), adding ellipsis if needed
 fn truncate_text(text: &str, max_len: usize) -> String {
     if max_len == 0 {
@@ -422,6 +640,63 @@ mod tests {
         assert!(output.contains("Agent: claude_code"));
     }
 
+    #[test]
+    fn test_export_conversation_carries_message_provenance() {
+        let conv = Conversation {
+            id: Some(7),
+            agent_slug: "codex".to_string(),
+            workspace: Some("/projects/test".into()),
+            external_id: Some("sess-1".to_string()),
+            title: Some("Fix the build".to_string()),
+            source_path: "/path/to/session.jsonl".into(),
+            started_at: Some(1700000000000),
+            ended_at: None,
+            approx_tokens: None,
+            metadata_json: serde_json::Value::Null,
+            messages: Vec::new(),
+            source_id: "local".to_string(),
+            origin_host: None,
+        };
+        let msg = |idx: i64, role: MessageRole, content: &str, line: Option<i64>| Message {
+            id: None,
+            idx,
+            role,
+            author: None,
+            created_at: None,
+            content: content.to_string(),
+            extra_json: serde_json::Value::Null,
+            snippets: Vec::new(),
+            approx_tokens: None,
+            source_path: None,
+            source_line: line,
+        };
+        let messages = vec![
+            msg(0, MessageRole::User, "why does it fail?", Some(3)),
+            msg(1, MessageRole::Agent, "missing feature flag", None),
+        ];
+        let options = ExportOptions::default();
+
+        let json: serde_json::Value = serde_json::from_str(&export_conversation(
+            &conv,
+            &messages,
+            ExportFormat::Json,
+            &options,
+        ))
+        .unwrap();
+        assert_eq!(json["conversation"]["title"], "Fix the build");
+        assert_eq!(json["count"], 2);
+        assert_eq!(json["messages"][0]["source_line"], 3);
+        assert_eq!(json["messages"][1]["role"], "assistant");
+        assert!(json["messages"][1].get("source_line").is_none());
+
+        let md = export_conversation(&conv, &messages, ExportFormat::Markdown, &options);
+        assert!(md.starts_with("# Fix the build"));
+        assert!(md.contains("*Source: `/path/to/session.jsonl:3`*"));
+
+        let text = export_conversation(&conv, &messages, ExportFormat::PlainText, &options);
+        assert!(text.contains("=== ASSISTANT ===\n\nmissing feature flag"));
+    }
+
     #[test]
     fn test_export_markdown_escapes_special_chars() {
         let mut hit = sample_hit();
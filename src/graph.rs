@@ -0,0 +1,483 @@
+//! Conversation graph export (`cass graph`).
+//!
+//! Nodes are conversations; edges link them by:
+//! - **shared file**: both have code snippets attributed to the same file
+//! - **shared workspace**: consecutive conversations in one workspace (a chain, not a clique)
+//! - **continuation**: same agent and workspace, started shortly after the previous one ended
+//! - **similar**: overlapping vocabulary in title and opening user message (Jaccard)
+//!
+//! Output as Graphviz DOT, plain JSON, or an Obsidian JSON Canvas.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+
+/// A conversation starting within this window after the previous one ended continues it
+const CONTINUATION_GAP_MS: i64 = 30 * 60 * 1000;
+
+/// Terms shared by more conversations than this are too common to signal similarity
+const MAX_TERM_DOC_FREQ: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    SharedFile,
+    SharedWorkspace,
+    Continuation,
+    Similar,
+}
+
+impl EdgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SharedFile => "shared_file",
+            Self::SharedWorkspace => "shared_workspace",
+            Self::Continuation => "continuation",
+            Self::Similar => "similar",
+        }
+    }
+
+    fn dot_style(self) -> &'static str {
+        match self {
+            Self::SharedFile => "color=\"#d08770\"",
+            Self::SharedWorkspace => "color=\"#a3be8c\", style=dashed, dir=none",
+            Self::Continuation => "color=\"#5e81ac\", penwidth=2",
+            Self::Similar => "color=\"#b48ead\", style=dotted, dir=none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub id: i64,
+    pub title: String,
+    pub agent: String,
+    pub workspace: Option<String>,
+    pub source_path: String,
+    pub started_at: Option<i64>,
+    #[serde(skip)]
+    pub ended_at: Option<i64>,
+    /// Files referenced by this conversation's code snippets
+    #[serde(skip)]
+    pub files: Vec<String>,
+    /// Vocabulary of title + opening user message
+    #[serde(skip)]
+    pub terms: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub source: i64,
+    pub target: i64,
+    pub kind: EdgeKind,
+    pub weight: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConversationGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphOptions {
+    /// Most recent conversations to include
+    pub limit: usize,
+    /// Restrict to one workspace path
+    pub workspace: Option<String>,
+    /// Minimum Jaccard score for similarity edges (1.0+ disables them)
+    pub min_similarity: f64,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            limit: 500,
+            workspace: None,
+            min_similarity: 0.35,
+        }
+    }
+}
+
+/// Load conversations from the index database and link them.
+pub fn build_graph(conn: &Connection, opts: &GraphOptions) -> Result<ConversationGraph> {
+    let mut nodes = load_nodes(conn, opts)?;
+    // Only the selected conversations' snippets and opening messages are read
+    let ids = serde_json::to_string(&nodes.iter().map(|n| n.id).collect::<Vec<_>>())?;
+
+    let mut files: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT m.conversation_id, s.file_path FROM snippets s
+         JOIN messages m ON s.message_id = m.id
+         WHERE s.file_path IS NOT NULL
+           AND m.conversation_id IN (SELECT value FROM json_each(?1))",
+    )?;
+    let rows = stmt.query_map([&ids], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (conv_id, path) = row?;
+        files.entry(conv_id).or_default().push(path);
+    }
+
+    let mut opening: HashMap<i64, String> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT c.value,
+                (SELECT cass_text(m.content) FROM messages m
+                 WHERE m.conversation_id = c.value AND m.role = 'user'
+                 ORDER BY m.idx LIMIT 1)
+         FROM json_each(?1) c",
+    )?;
+    let rows = stmt.query_map([&ids], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, Option<String>>(1)?))
+    })?;
+    for row in rows {
+        if let (conv_id, Some(content)) = row? {
+            opening.insert(conv_id, content);
+        }
+    }
+
+    for node in &mut nodes {
+        node.files = files.remove(&node.id).unwrap_or_default();
+        let opening = opening.get(&node.id).map(String::as_str).unwrap_or("");
+        node.terms = terms_of(&format!("{} {}", node.title, opening));
+    }
+
+    Ok(link(nodes, opts.min_similarity))
+}
+
+fn load_nodes(conn: &Connection, opts: &GraphOptions) -> Result<Vec<GraphNode>> {
+    let mut sql = String::from(
+        "SELECT c.id, COALESCE(c.title, ''), a.slug, w.path, c.source_path, c.started_at, c.ended_at
+         FROM conversations c
+         JOIN agents a ON c.agent_id = a.id
+         LEFT JOIN workspaces w ON c.workspace_id = w.id",
+    );
+    if opts.workspace.is_some() {
+        sql.push_str(" WHERE w.path = ?1");
+    }
+    sql.push_str(&format!(
+        " ORDER BY c.started_at DESC LIMIT {}",
+        opts.limit.max(1)
+    ));
+    let mut stmt = conn.prepare(&sql)?;
+    let map_row = |r: &rusqlite::Row<'_>| {
+        Ok(GraphNode {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            agent: r.get(2)?,
+            workspace: r.get(3)?,
+            source_path: r.get(4)?,
+            started_at: r.get(5)?,
+            ended_at: r.get(6)?,
+            files: Vec::new(),
+            terms: HashSet::new(),
+        })
+    };
+    let rows = match &opts.workspace {
+        Some(ws) => stmt.query_map([ws], map_row)?.collect::<Vec<_>>(),
+        None => stmt.query_map([], map_row)?.collect::<Vec<_>>(),
+    };
+    rows.into_iter().map(|r| r.map_err(Into::into)).collect()
+}
+
+/// Lowercased words of 4+ chars, minus a few filler words
+fn terms_of(text: &str) -> HashSet<String> {
+    const STOP: &[&str] = &[
+        "this", "that", "with", "from", "have", "what", "when", "where", "which", "there", "their",
+        "about", "would", "could", "should", "please", "into", "them", "then", "than", "also",
+        "just", "like", "make", "need", "want",
+    ];
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.chars().count() >= 4)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP.contains(&w.as_str()))
+        .collect()
+}
+
+/// Derive all edge kinds for a set of nodes.
+pub fn link(mut nodes: Vec<GraphNode>, min_similarity: f64) -> ConversationGraph {
+    nodes.sort_by_key(|n| (n.started_at.unwrap_or(i64::MIN), n.id));
+    let mut edges = Vec::new();
+
+    // Shared files
+    let mut by_file: BTreeMap<&str, Vec<i64>> = BTreeMap::new();
+    for node in &nodes {
+        for file in &node.files {
+            by_file.entry(file.as_str()).or_default().push(node.id);
+        }
+    }
+    let mut file_pairs: BTreeMap<(i64, i64), Vec<&str>> = BTreeMap::new();
+    for (file, ids) in &by_file {
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                if a != b {
+                    file_pairs
+                        .entry((*a.min(b), *a.max(b)))
+                        .or_default()
+                        .push(file);
+                }
+            }
+        }
+    }
+    for ((a, b), shared) in file_pairs {
+        edges.push(GraphEdge {
+            source: a,
+            target: b,
+            kind: EdgeKind::SharedFile,
+            weight: shared.len() as f64,
+            label: Some(if shared.len() == 1 {
+                shared[0].to_string()
+            } else {
+                format!("{} files", shared.len())
+            }),
+        });
+    }
+
+    // Shared workspace chains and continuations (nodes are in chronological order)
+    let mut last_in_workspace: HashMap<&str, &GraphNode> = HashMap::new();
+    for node in &nodes {
+        let Some(ws) = node.workspace.as_deref() else {
+            continue;
+        };
+        if let Some(prev) = last_in_workspace.get(ws) {
+            let continues = prev.agent == node.agent
+                && match (prev.ended_at.or(prev.started_at), node.started_at) {
+                    (Some(end), Some(start)) => start >= end && start - end <= CONTINUATION_GAP_MS,
+                    _ => false,
+                };
+            edges.push(GraphEdge {
+                source: prev.id,
+                target: node.id,
+                kind: if continues {
+                    EdgeKind::Continuation
+                } else {
+                    EdgeKind::SharedWorkspace
+                },
+                weight: 1.0,
+                label: None,
+            });
+        }
+        last_in_workspace.insert(ws, node);
+    }
+
+    // Vocabulary similarity via an inverted index, skipping very common terms
+    if min_similarity < 1.0 {
+        let mut postings: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            for term in &node.terms {
+                postings.entry(term.as_str()).or_default().push(i);
+            }
+        }
+        let mut overlap: HashMap<(usize, usize), usize> = HashMap::new();
+        for list in postings.values().filter(|l| l.len() <= MAX_TERM_DOC_FREQ) {
+            for (x, &a) in list.iter().enumerate() {
+                for &b in &list[x + 1..] {
+                    *overlap.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+        let mut similar: Vec<_> = overlap
+            .into_iter()
+            .filter_map(|((a, b), shared)| {
+                let union = nodes[a].terms.len() + nodes[b].terms.len() - shared;
+                let score = shared as f64 / union.max(1) as f64;
+                (score >= min_similarity).then_some((a, b, score))
+            })
+            .collect();
+        similar.sort_by_key(|&(a, b, _)| (a, b));
+        for (a, b, score) in similar {
+            edges.push(GraphEdge {
+                source: nodes[a].id,
+                target: nodes[b].id,
+                kind: EdgeKind::Similar,
+                weight: (score * 100.0).round() / 100.0,
+                label: None,
+            });
+        }
+    }
+
+    ConversationGraph { nodes, edges }
+}
+
+impl ConversationGraph {
+    fn node_label(node: &GraphNode) -> String {
+        if node.title.is_empty() {
+            node.source_path
+                .rsplit('/')
+                .next()
+                .unwrap_or(&node.source_path)
+                .to_string()
+        } else {
+            node.title.chars().take(60).collect()
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph cass {\n  rankdir=LR;\n  node [shape=box, style=rounded];\n");
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "  c{} [label=\"{}\\n{}\", tooltip=\"{}\"];\n",
+                node.id,
+                dot_escape(&Self::node_label(node)),
+                dot_escape(&node.agent),
+                dot_escape(&node.source_path),
+            ));
+        }
+        for edge in &self.edges {
+            let label = edge
+                .label
+                .as_deref()
+                .map(|l| format!(", label=\"{}\"", dot_escape(l)))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "  c{} -> c{} [{}{}];\n",
+                edge.source,
+                edge.target,
+                edge.kind.dot_style(),
+                label
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "nodes": self.nodes,
+            "edges": self.edges,
+            "count": { "nodes": self.nodes.len(), "edges": self.edges.len() },
+        })
+    }
+
+    /// Obsidian JSON Canvas: one text card per conversation, laid out in a grid
+    /// with one row per workspace.
+    pub fn to_canvas(&self) -> serde_json::Value {
+        const W: i64 = 320;
+        const H: i64 = 120;
+        const GAP: i64 = 60;
+        let mut rows: BTreeMap<&str, i64> = BTreeMap::new();
+        let mut cols: HashMap<&str, i64> = HashMap::new();
+        let mut cards = Vec::new();
+        for node in &self.nodes {
+            let ws = node.workspace.as_deref().unwrap_or("");
+            let next_row = rows.len() as i64;
+            let row = *rows.entry(ws).or_insert(next_row);
+            let col = cols.entry(ws).or_insert(0);
+            cards.push(serde_json::json!({
+                "id": format!("c{}", node.id),
+                "type": "text",
+                "text": format!("**{}**\n{} · {}", Self::node_label(node), node.agent, node.source_path),
+                "x": *col * (W + GAP),
+                "y": row * (H + GAP),
+                "width": W,
+                "height": H,
+            }));
+            *col += 1;
+        }
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let mut edge = serde_json::json!({
+                    "id": format!("e{i}"),
+                    "fromNode": format!("c{}", e.source),
+                    "toNode": format!("c{}", e.target),
+                    "label": e.label.clone().unwrap_or_else(|| e.kind.as_str().to_string()),
+                });
+                if e.kind != EdgeKind::Continuation {
+                    edge["toEnd"] = serde_json::json!("none");
+                }
+                edge
+            })
+            .collect();
+        serde_json::json!({ "nodes": cards, "edges": edges })
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i64, ws: &str, agent: &str, start: i64, end: i64) -> GraphNode {
+        GraphNode {
+            id,
+            title: format!("conv {id}"),
+            agent: agent.to_string(),
+            workspace: Some(ws.to_string()),
+            source_path: format!("/logs/{id}.jsonl"),
+            started_at: Some(start),
+            ended_at: Some(end),
+            files: Vec::new(),
+            terms: HashSet::new(),
+        }
+    }
+
+    fn kinds(graph: &ConversationGraph) -> Vec<(i64, i64, EdgeKind)> {
+        graph
+            .edges
+            .iter()
+            .map(|e| (e.source, e.target, e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn workspace_chain_and_continuation() {
+        let minute = 60 * 1000;
+        let graph = link(
+            vec![
+                node(3, "/ws", "codex", 200 * minute, 210 * minute),
+                node(1, "/ws", "claude_code", 0, 10 * minute),
+                node(2, "/ws", "claude_code", 15 * minute, 20 * minute),
+            ],
+            1.0,
+        );
+        assert_eq!(
+            kinds(&graph),
+            vec![
+                (1, 2, EdgeKind::Continuation),
+                (2, 3, EdgeKind::SharedWorkspace),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_files_and_similarity() {
+        let mut a = node(1, "/a", "codex", 0, 1);
+        let mut b = node(2, "/b", "codex", 10, 11);
+        let mut c = node(3, "/c", "codex", 20, 21);
+        a.files = vec!["src/lib.rs".into()];
+        b.files = vec!["src/lib.rs".into(), "src/main.rs".into()];
+        a.terms = terms_of("Fix tokenizer panic on unicode input");
+        c.terms = terms_of("tokenizer panic with unicode input again");
+        b.terms = terms_of("Update README badges");
+
+        let graph = link(vec![a, b, c], 0.35);
+        let edges = kinds(&graph);
+        assert!(edges.contains(&(1, 2, EdgeKind::SharedFile)));
+        assert!(edges.contains(&(1, 3, EdgeKind::Similar)));
+        assert!(!edges.iter().any(|e| e.2 == EdgeKind::Similar && e.1 == 2));
+        assert_eq!(graph.edges[0].label.as_deref(), Some("src/lib.rs"));
+    }
+
+    #[test]
+    fn dot_output_escapes_labels() {
+        let mut n = node(7, "/ws", "codex", 0, 1);
+        n.title = "say \"hi\"".into();
+        let dot = link(vec![n], 1.0).to_dot();
+        assert!(dot.starts_with("digraph cass {"));
+        assert!(dot.contains("c7 [label=\"say \\\"hi\\\"\\ncodex\""));
+    }
+}
@@ -0,0 +1,224 @@
+//! User-configured hook commands run on index events.
+//!
+//! Hooks live in `~/.config/cass/config.toml` (or `$XDG_CONFIG_HOME/cass/config.toml`):
+//!
+//! ```toml
+//! [[hooks]]
+//! event = "new-conversation"
+//! command = "jq -r .data.title >> ~/new-sessions.txt"
+//!
+//! [[hooks]]
+//! event = "post-index"
+//! command = "notify-send 'cass index finished'"
+//! timeout_secs = 10
+//! ```
+//!
+//! Each hook runs through the shell with the event JSON on stdin:
+//! `{"event": "...", "timestamp": "...", "data": {...}}`. Hook failures are
+//! logged and never abort indexing or syncing.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Default time a hook may run before it is killed
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Events hooks can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    /// An index run (full, incremental or watch batch) finished and was committed
+    PostIndex,
+    /// A conversation was stored for the first time
+    NewConversation,
+    /// A remote source finished syncing
+    SyncComplete,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PostIndex => "post-index",
+            Self::NewConversation => "new-conversation",
+            Self::SyncComplete => "sync-complete",
+        }
+    }
+}
+
+/// A single `[[hooks]]` entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookDefinition {
+    pub event: HookEvent,
+    /// Shell command to run (`sh -c` on Unix, `cmd /C` on Windows)
+    pub command: String,
+    /// Kill the hook if it runs longer than this (default 30s)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// The hook section of `config.toml`; other sections are ignored here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub hooks: Vec<HookDefinition>,
+}
+
+impl HooksConfig {
+    /// Path of the shared config file, following the same XDG rules as `sources.toml`.
+    pub fn config_path() -> Option<PathBuf> {
+        if let Ok(xdg_config) = std::env::var("XDG_CONFIG_HOME") {
+            return Some(PathBuf::from(xdg_config).join("cass").join("config.toml"));
+        }
+        dirs::config_dir().map(|p| p.join("cass").join("config.toml"))
+    }
+
+    /// Load hooks, treating a missing or unreadable config as "no hooks".
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring hooks: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// True if any hook subscribes to `event`
+    pub fn has(&self, event: HookEvent) -> bool {
+        self.hooks.iter().any(|h| h.event == event)
+    }
+
+    /// Run every hook subscribed to `event`, feeding the event JSON on stdin.
+    pub fn fire(&self, event: HookEvent, data: serde_json::Value) {
+        if !self.has(event) {
+            return;
+        }
+        let payload = serde_json::json!({
+            "event": event.as_str(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "data": data,
+        })
+        .to_string();
+
+        for hook in self.hooks.iter().filter(|h| h.event == event) {
+            let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+            if let Err(e) = run_hook(&hook.command, &payload, timeout) {
+                warn!(event = event.as_str(), command = %hook.command, "hook failed: {e}");
+            }
+        }
+    }
+}
+
+pub(crate) fn run_hook(command: &str, payload: &str, timeout: Duration) -> std::io::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores stdin closes the pipe early; that's fine.
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            debug!(command, ?status, "hook finished");
+            if !status.success() {
+                return Err(std::io::Error::other(format!("exited with {status}")));
+            }
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::other(format!(
+                "timed out after {}s",
+                timeout.as_secs()
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hooks_and_ignores_other_sections() {
+        let config: HooksConfig = toml::from_str(
+            r#"
+            [ui]
+            theme = "dark"
+
+            [[hooks]]
+            event = "post-index"
+            command = "true"
+
+            [[hooks]]
+            event = "sync-complete"
+            command = "echo synced"
+            timeout_secs = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.hooks.len(), 2);
+        assert!(config.has(HookEvent::PostIndex));
+        assert!(config.has(HookEvent::SyncComplete));
+        assert!(!config.has(HookEvent::NewConversation));
+        assert_eq!(config.hooks[1].timeout_secs, Some(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fire_pipes_event_json_to_stdin() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("event.json");
+        let config = HooksConfig {
+            hooks: vec![HookDefinition {
+                event: HookEvent::NewConversation,
+                command: format!("cat > '{}'", out.display()),
+                timeout_secs: None,
+            }],
+        };
+        config.fire(
+            HookEvent::NewConversation,
+            serde_json::json!({"title": "hello"}),
+        );
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written["event"], "new-conversation");
+        assert_eq!(written["data"]["title"], "hello");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn slow_hooks_are_killed_after_timeout() {
+        let started = Instant::now();
+        let err = run_hook("sleep 5", "{}", Duration::from_millis(200)).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
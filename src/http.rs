@@ -0,0 +1,315 @@
+//! `cass serve`: a local HTTP API over the index, for editor plugins and
+//! dashboards that shouldn't shell out per query, and a small web UI on top.
+//!
+//! `/` serves the UI: one page compiled into the binary (`web/index.html`)
+//! with a search box, agent/workspace/source filters and a conversation
+//! viewer, all driven by the endpoints below. It holds no data itself, so it
+//! is served without the guard; the page asks for the token when the API does.
+//!
+//! Endpoints (`GET`, JSON; parameters as in [`crate::api`]):
+//! - `/api/search?q=&limit=&offset=&agent=&workspace=&source=`
+//! - `/api/conversations/{id}?agent=&source=&from_idx=&limit=`
+//! - `/api/facets?q=&fields=agent,workspace,date`
+//! - `/api/workspaces?limit=`
+//! - `/api/stats`
+//!
+//! Failures answer `{"error": {"kind", "message"}}` with a matching status.
+//!
+//! The server binds to loopback unless told otherwise. With a token every
+//! request must send `Authorization: Bearer <token>`. Without one, requests
+//! must name a loopback `Host`, so a web page can't reach the API through DNS
+//! rebinding.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use serde_json::json;
+use tokio::net::TcpListener;
+
+use crate::api::{
+    Api, ApiError, ApiResult, ConversationParams, FacetParams, SearchParams, WorkspaceParams,
+};
+
+#[derive(Clone)]
+struct AppState {
+    api: Arc<Api>,
+    token: Option<Arc<str>>,
+}
+
+/// The web UI, compiled in
+const INDEX_HTML: &str = include_str!("web/index.html");
+
+/// The web UI and the API routes, the latter behind the token or loopback-host guard
+pub fn router(api: Api, token: Option<String>) -> Router {
+    let state = AppState {
+        api: Arc::new(api),
+        token: token.map(Arc::from),
+    };
+    let api_routes = Router::new()
+        .route("/api/search", get(search))
+        .route("/api/conversations/{id}", get(conversation))
+        .route("/api/facets", get(facets))
+        .route("/api/workspaces", get(workspaces))
+        .route("/api/stats", get(stats))
+        .layer(middleware::from_fn_with_state(state.clone(), guard));
+    Router::new()
+        .route("/", get(|| async { Html(INDEX_HTML) }))
+        .merge(api_routes)
+        .with_state(state)
+}
+
+/// Serve on `listener` until Ctrl-C.
+pub async fn serve(listener: TcpListener, api: Api, token: Option<String>) -> Result<()> {
+    axum::serve(listener, router(api, token))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .context("serving HTTP")
+}
+
+async fn search(State(state): State<AppState>, Query(params): Query<SearchParams>) -> Response {
+    answer(state, move |api| api.search(&params)).await
+}
+
+async fn conversation(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(mut params): Query<ConversationParams>,
+) -> Response {
+    params.id = id;
+    answer(state, move |api| api.conversation(&params)).await
+}
+
+async fn facets(State(state): State<AppState>, Query(params): Query<FacetParams>) -> Response {
+    answer(state, move |api| api.facets(&params)).await
+}
+
+async fn workspaces(
+    State(state): State<AppState>,
+    Query(params): Query<WorkspaceParams>,
+) -> Response {
+    answer(state, move |api| api.workspaces(&params)).await
+}
+
+async fn stats(State(state): State<AppState>) -> Response {
+    answer(state, |api| api.stats()).await
+}
+
+/// Run a query off the async workers; searches and SQLite reads block.
+async fn answer(
+    state: AppState,
+    query: impl FnOnce(&Api) -> ApiResult + Send + 'static,
+) -> Response {
+    match tokio::task::spawn_blocking(move || query(&state.api)).await {
+        Ok(Ok(value)) => Json(value).into_response(),
+        Ok(Err(e)) => api_error(e),
+        Err(e) => api_error(ApiError::Internal(anyhow::anyhow!("request failed: {e}"))),
+    }
+}
+
+fn api_error(e: ApiError) -> Response {
+    let (status, kind) = match &e {
+        ApiError::BadRequest(_) => (StatusCode::BAD_REQUEST, "bad-request"),
+        ApiError::NotFound(_) => (StatusCode::NOT_FOUND, "not-found"),
+        ApiError::Unavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "unavailable"),
+        ApiError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal"),
+    };
+    error_response(status, kind, &e.to_string())
+}
+
+fn error_response(status: StatusCode, kind: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({ "error": { "kind": kind, "message": message } })),
+    )
+        .into_response()
+}
+
+async fn guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    match &state.token {
+        Some(token) => {
+            let presented = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "));
+            let valid = presented.is_some_and(|p| constant_time_eq(p.as_bytes(), token.as_bytes()));
+            if !valid {
+                return error_response(
+                    StatusCode::UNAUTHORIZED,
+                    "unauthorized",
+                    "missing or wrong bearer token",
+                );
+            }
+        }
+        None => {
+            let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+            if !host.is_some_and(is_loopback_host) {
+                return error_response(
+                    StatusCode::FORBIDDEN,
+                    "forbidden",
+                    "without a token, requests must be addressed to localhost",
+                );
+            }
+        }
+    }
+    next.run(request).await
+}
+
+/// Compare without an early exit, so response times don't reveal how much of the
+/// token a guess got right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Whether a `Host` header names this machine: `localhost`, `127.0.0.1:8765`, `[::1]`
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    /// An API over an empty data dir: its database has no conversations
+    fn app(dir: &std::path::Path, token: Option<&str>) -> Router {
+        let db_path = dir.join("agent_search.db");
+        crate::storage::sqlite::SqliteStorage::open(&db_path).unwrap();
+        router(
+            Api::new(dir.to_path_buf(), db_path),
+            token.map(str::to_string),
+        )
+    }
+
+    async fn call(
+        app: Router,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn token_is_required_and_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app(dir.path(), Some("s3cret"));
+        for auth in [
+            None,
+            Some("Bearer wrong"),
+            Some("Bearer s3cret2"),
+            Some("s3cret"),
+        ] {
+            let headers: Vec<_> = auth.map(|a| ("authorization", a)).into_iter().collect();
+            let (status, body) = call(app.clone(), "/api/stats", &headers).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{auth:?}");
+            assert_eq!(body["error"]["kind"], "unauthorized");
+        }
+        // A token also lets through hosts other than localhost
+        let (status, body) = call(
+            app,
+            "/api/stats",
+            &[("authorization", "Bearer s3cret"), ("host", "box.lan:8765")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["conversations"], 0);
+    }
+
+    #[tokio::test]
+    async fn without_a_token_only_loopback_hosts_get_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app(dir.path(), None);
+        let (status, body) =
+            call(app.clone(), "/api/stats", &[("host", "evil.example:8765")]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"]["kind"], "forbidden");
+        let (status, _) = call(app.clone(), "/api/stats", &[]).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(app.clone(), "/api/stats", &[("host", "127.0.0.1:8765")]).await;
+        assert_eq!(status, StatusCode::OK);
+        // The UI page holds no data and is served to anyone
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("host", "evil.example")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn api_errors_map_to_statuses() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = [("host", "localhost")];
+        let (status, body) = call(app(dir.path(), None), "/api/search?q=%20", &local).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["kind"], "bad-request");
+
+        let (status, body) = call(app(dir.path(), None), "/api/conversations/4242", &local).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["kind"], "not-found");
+
+        let empty = tempfile::tempdir().unwrap();
+        let missing = router(
+            Api::new(empty.path().to_path_buf(), empty.path().join("none.db")),
+            None,
+        );
+        let (status, body) = call(missing, "/api/stats", &local).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["kind"], "unavailable");
+    }
+
+    #[test]
+    fn only_loopback_hosts_pass_without_a_token() {
+        for host in [
+            "localhost",
+            "localhost:8765",
+            "127.0.0.1:8765",
+            "[::1]:8765",
+            "[::1]",
+        ] {
+            assert!(is_loopback_host(host), "{host}");
+        }
+        for host in [
+            "evil.example:8765",
+            "192.168.1.5:8765",
+            "localhost.evil.example",
+            "",
+        ] {
+            assert!(!is_loopback_host(host), "{host}");
+        }
+    }
+}
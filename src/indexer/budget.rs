@@ -0,0 +1,271 @@
+//! Storage budget: cap the size of the database plus search index.
+//!
+//! ```toml
+//! [storage]
+//! max_size = "2GB"
+//! prune_policy = "least-accessed"   # or "oldest" (default)
+//! ```
+//!
+//! After each index run, if the database and Tantivy index together exceed
+//! `max_size`, whole sessions are pruned from both until the estimated size
+//! drops below 90% of the budget. Sessions locked with `cass lock` are never
+//! pruned. Pruned sessions whose files still exist come back on the next
+//! `--full` scan and are pruned again if the budget is still exceeded.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use walkdir::WalkDir;
+
+use crate::hooks::HooksConfig;
+use crate::search::tantivy::TantivyIndex;
+use crate::storage::encryption::KeySource;
+use crate::storage::sqlite::SqliteStorage;
+
+/// Prune down to this fraction of the budget so every run doesn't prune again
+const TARGET_RATIO: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrunePolicy {
+    #[default]
+    Oldest,
+    LeastAccessed,
+}
+
+/// The `[storage]` section of `config.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StorageConfig {
+    /// Human-readable size, e.g. "500MB" or "2GB"
+    pub max_size: Option<String>,
+    #[serde(default)]
+    pub prune_policy: PrunePolicy,
+    /// Age after which sessions are pruned, e.g. "18mo" or "1y" (see `retention`)
+    pub retention: Option<String>,
+    /// zstd-compress large messages (see `storage::compression`)
+    #[serde(default)]
+    pub compression: bool,
+    /// Where the SQLCipher key comes from (see `storage::encryption`)
+    pub encryption: Option<KeySource>,
+}
+
+impl StorageConfig {
+    /// The `[storage]` section, or None when the config file is missing or invalid.
+    pub fn load() -> Option<Self> {
+        let path = HooksConfig::config_path()?;
+        let content = std::fs::read_to_string(&path).ok()?;
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => Some(config.storage),
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring [storage]: invalid config.toml: {e}");
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    storage: StorageConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageBudget {
+    pub max_bytes: u64,
+    pub policy: PrunePolicy,
+}
+
+impl StorageBudget {
+    /// The configured budget, or None when `[storage] max_size` is unset or invalid.
+    pub fn load() -> Option<Self> {
+        Self::from_config(&StorageConfig::load()?)
+    }
+
+    pub fn from_config(config: &StorageConfig) -> Option<Self> {
+        let raw = config.max_size.as_deref()?;
+        match parse_size(raw) {
+            Some(max_bytes) if max_bytes > 0 => Some(Self {
+                max_bytes,
+                policy: config.prune_policy,
+            }),
+            _ => {
+                warn!(max_size = raw, "ignoring storage budget: unrecognized size");
+                None
+            }
+        }
+    }
+}
+
+/// Parse "1048576", "500MB", "1.5 GiB", "2g" into bytes (binary multiples).
+pub fn parse_size(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let split = raw
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Total size of the files under `dir`
+pub fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter_map(|e| e.metadata().ok())
+        .filter(std::fs::Metadata::is_file)
+        .map(|m| m.len())
+        .sum()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrunedConversation {
+    pub source_id: String,
+    pub source_path: String,
+    pub agent: String,
+    pub title: Option<String>,
+    pub started_at: Option<i64>,
+    pub estimated_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneReport {
+    pub policy: PrunePolicy,
+    pub max_bytes: u64,
+    pub size_before: u64,
+    pub estimated_size_after: u64,
+    pub removed: Vec<PrunedConversation>,
+}
+
+/// Prune sessions until the database plus index fit the budget.
+/// Returns None when already within budget. The caller commits `t_index`.
+pub fn enforce_budget(
+    storage: &mut SqliteStorage,
+    t_index: &mut TantivyIndex,
+    index_path: &Path,
+    budget: &StorageBudget,
+) -> Result<Option<PruneReport>> {
+    let size_before = storage.used_bytes()? + dir_size(index_path);
+    if size_before <= budget.max_bytes {
+        return Ok(None);
+    }
+
+    let candidates = storage.prune_candidates(budget.policy == PrunePolicy::LeastAccessed)?;
+    let total_content: u64 = candidates
+        .iter()
+        .map(|c| c.content_bytes)
+        .sum::<u64>()
+        .max(1);
+    // Disk footprint is attributed to conversations by their share of stored content
+    let estimate =
+        |content: u64| (size_before as f64 * content as f64 / total_content as f64) as u64;
+    let target = (budget.max_bytes as f64 * TARGET_RATIO) as u64;
+
+    // Whole session files are pruned so SQLite and Tantivy (keyed by path) stay in step
+    let mut per_file: HashMap<(&str, &str), u64> = HashMap::new();
+    for c in &candidates {
+        *per_file
+            .entry((c.source_id.as_str(), c.source_path.as_str()))
+            .or_default() += estimate(c.content_bytes);
+    }
+    let mut chosen: HashSet<(String, String)> = HashSet::new();
+    let mut freed = 0u64;
+    for c in candidates.iter().filter(|c| !c.retained) {
+        if size_before.saturating_sub(freed) <= target {
+            break;
+        }
+        let key = (c.source_id.as_str(), c.source_path.as_str());
+        if chosen.insert((c.source_id.clone(), c.source_path.clone())) {
+            freed += per_file.get(&key).copied().unwrap_or_default();
+        }
+    }
+    if chosen.is_empty() {
+        warn!(
+            size_before,
+            max_bytes = budget.max_bytes,
+            "storage budget exceeded but every session is locked"
+        );
+        return Ok(None);
+    }
+
+    let victims: Vec<_> = candidates
+        .into_iter()
+        .filter(|c| chosen.contains(&(c.source_id.clone(), c.source_path.clone())))
+        .collect();
+    let ids: Vec<i64> = victims.iter().map(|c| c.conversation_id).collect();
+    super::delete_conversation_docs(storage, t_index, &ids)?;
+    storage.delete_conversations(&ids)?;
+
+    let removed: Vec<PrunedConversation> = victims
+        .into_iter()
+        .map(|c| PrunedConversation {
+            estimated_bytes: estimate(c.content_bytes),
+            source_id: c.source_id,
+            source_path: c.source_path,
+            agent: c.agent,
+            title: c.title,
+            started_at: c.started_at,
+        })
+        .collect();
+    tracing::info!(
+        removed = removed.len(),
+        files = chosen.len(),
+        freed,
+        policy = ?budget.policy,
+        "storage budget: pruned conversations"
+    );
+    Ok(Some(PruneReport {
+        policy: budget.policy,
+        max_bytes: budget.max_bytes,
+        size_before,
+        estimated_size_after: size_before.saturating_sub(freed),
+        removed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_human_sizes() {
+        assert_eq!(parse_size("1048576"), Some(1 << 20));
+        assert_eq!(parse_size("500MB"), Some(500 << 20));
+        assert_eq!(parse_size("1.5 GiB"), Some(3 << 29));
+        assert_eq!(parse_size("2g"), Some(2 << 30));
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(parse_size("10 parsecs"), None);
+    }
+
+    #[test]
+    fn budget_from_config() {
+        let file: ConfigFile = toml::from_str(
+            r#"
+            [storage]
+            max_size = "2GB"
+            prune_policy = "least-accessed"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            StorageBudget::from_config(&file.storage),
+            Some(StorageBudget {
+                max_bytes: 2 << 30,
+                policy: PrunePolicy::LeastAccessed,
+            })
+        );
+        assert_eq!(StorageBudget::from_config(&StorageConfig::default()), None);
+    }
+}
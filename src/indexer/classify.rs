@@ -0,0 +1,602 @@
+//! Index-time classifiers: labels attached to conversations and messages.
+//!
+//! ```toml
+//! [classifiers]
+//! builtin = ["has_code", "language", "outcome"]   # default: all of them
+//!
+//! [[classifiers.command]]
+//! name = "ticket"
+//! command = "~/bin/find-ticket-ids"
+//! timeout_secs = 10
+//! ```
+//!
+//! Every classifier sees the whole conversation and returns labels, each a
+//! `key`/`value` pair optionally pinned to one message. Labels are stored in
+//! `metadata.cass.labels`, survive re-indexing from the database, and become
+//! exact `label:key` / `label:key=value` search filters.
+//!
+//! Command classifiers run through the shell once per indexing batch. Stdin
+//! holds one conversation JSON per line; stdout must hold one JSON array of
+//! labels per line, in the same order:
+//! `[{"key": "ticket", "value": "ENG-42", "message_idx": 3}]`. `value`
+//! defaults to `"true"`; omitting `message_idx` labels the conversation.
+//! `timeout_secs` is per conversation in the batch. Failing commands are
+//! logged and skipped, never aborting indexing.
+
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::connectors::{NormalizedConversation, extract_code_snippets};
+use crate::hooks::HooksConfig;
+
+/// Default time a command classifier may take per conversation in a batch
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Built-in classifier names, in the order they run
+pub const BUILTIN_CLASSIFIERS: &[&str] = &["has_code", "language", "outcome"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Label {
+    pub key: String,
+    #[serde(default = "default_value")]
+    pub value: String,
+    /// The message this label applies to; `None` labels the whole conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_idx: Option<i64>,
+}
+
+fn default_value() -> String {
+    "true".to_string()
+}
+
+impl Label {
+    pub fn conversation(key: &str, value: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into(),
+            message_idx: None,
+        }
+    }
+
+    pub fn message(idx: i64, key: &str, value: impl Into<String>) -> Self {
+        Self {
+            message_idx: Some(idx),
+            ..Self::conversation(key, value)
+        }
+    }
+}
+
+/// Something that derives labels from a conversation at index time.
+pub trait Classifier: Send + Sync {
+    fn name(&self) -> &str;
+    fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>>;
+
+    /// Labels for each of `convs`, in order. Classifiers with a fixed cost per
+    /// call, like starting a process, override this to pay it once per batch.
+    fn classify_batch(&self, convs: &[&mut NormalizedConversation]) -> Vec<Result<Vec<Label>>> {
+        convs.iter().map(|c| self.classify(c)).collect()
+    }
+}
+
+/// `has_code`: messages with code snippets, and the conversation if any has one.
+pub struct HasCode;
+
+impl Classifier for HasCode {
+    fn name(&self) -> &str {
+        "has_code"
+    }
+
+    fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>> {
+        let mut labels: Vec<Label> = conv
+            .messages
+            .iter()
+            .filter(|m| !m.snippets.is_empty() || !extract_code_snippets(&m.content).is_empty())
+            .map(|m| Label::message(m.idx, "has_code", "true"))
+            .collect();
+        if !labels.is_empty() {
+            labels.push(Label::conversation("has_code", "true"));
+        }
+        Ok(labels)
+    }
+}
+
+/// `language`: languages of code snippets, per message and for the conversation.
+pub struct Language;
+
+impl Classifier for Language {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>> {
+        let mut labels = Vec::new();
+        let mut all = BTreeSet::new();
+        for msg in &conv.messages {
+            let extracted;
+            let snippets = if msg.snippets.is_empty() {
+                extracted = extract_code_snippets(&msg.content);
+                &extracted
+            } else {
+                &msg.snippets
+            };
+            let langs: BTreeSet<String> = snippets
+                .iter()
+                .filter_map(|s| s.language.as_deref())
+                .map(|l| l.trim().to_lowercase())
+                .filter(|l| !l.is_empty())
+                .collect();
+            for lang in langs {
+                labels.push(Label::message(msg.idx, "language", lang.clone()));
+                all.insert(lang);
+            }
+        }
+        labels.extend(
+            all.into_iter()
+                .map(|lang| Label::conversation("language", lang)),
+        );
+        Ok(labels)
+    }
+}
+
+/// `outcome`: `success`, `failure` or `unknown`, judged from the last assistant reply.
+pub struct Outcome;
+
+const FAILURE_MARKERS: &[&str] = &[
+    "still failing",
+    "still fails",
+    "unable to",
+    "i couldn't",
+    "i could not",
+    "i wasn't able",
+    "was not able to",
+    "doesn't work",
+    "does not work",
+];
+
+const SUCCESS_MARKERS: &[&str] = &[
+    "all tests pass",
+    "tests pass",
+    "tests are passing",
+    "successfully",
+    "is now fixed",
+    "has been fixed",
+    "fixed the",
+    "build succeeds",
+    "done.",
+];
+
+impl Classifier for Outcome {
+    fn name(&self) -> &str {
+        "outcome"
+    }
+
+    fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>> {
+        let last = conv
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "assistant" && !m.content.trim().is_empty());
+        let outcome = match last.map(|m| m.content.to_lowercase()) {
+            Some(text) if FAILURE_MARKERS.iter().any(|k| text.contains(k)) => "failure",
+            Some(text) if SUCCESS_MARKERS.iter().any(|k| text.contains(k)) => "success",
+            _ => "unknown",
+        };
+        Ok(vec![Label::conversation("outcome", outcome)])
+    }
+}
+
+/// A `[[classifiers.command]]` entry: an external program producing labels.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandClassifier {
+    pub name: String,
+    /// Shell command to run (`sh -c` on Unix, `cmd /C` on Windows)
+    pub command: String,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Classifier for CommandClassifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>> {
+        let mut labels = self.run(std::slice::from_ref(&conv))?;
+        Ok(labels.pop().unwrap_or_default())
+    }
+
+    fn classify_batch(&self, convs: &[&mut NormalizedConversation]) -> Vec<Result<Vec<Label>>> {
+        let convs: Vec<&NormalizedConversation> = convs.iter().map(|c| &**c).collect();
+        match self.run(&convs) {
+            Ok(labels) => labels.into_iter().map(Ok).collect(),
+            Err(e) => convs
+                .iter()
+                .map(|_| Err(anyhow::anyhow!("{e:#}")))
+                .collect(),
+        }
+    }
+}
+
+impl CommandClassifier {
+    /// One run of the command over `convs`, one label array per conversation
+    fn run(&self, convs: &[&NormalizedConversation]) -> Result<Vec<Vec<Label>>> {
+        if convs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut payload = Vec::new();
+        for conv in convs {
+            serde_json::to_writer(&mut payload, conv)?;
+            payload.push(b'\n');
+        }
+        let per_conv = self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let timeout = Duration::from_secs(per_conv.saturating_mul(convs.len() as u64));
+        let stdout = run_command(&self.command, &payload, timeout)?;
+        let labels = stdout
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(|line| {
+                serde_json::from_slice(line)
+                    .context("expected one JSON array of labels per line on stdout")
+            })
+            .collect::<Result<Vec<Vec<Label>>>>()?;
+        if labels.len() != convs.len() {
+            bail!(
+                "printed {} label lines for {} conversations",
+                labels.len(),
+                convs.len()
+            );
+        }
+        Ok(labels)
+    }
+}
+
+fn run_command(command: &str, payload: &[u8], timeout: Duration) -> Result<Vec<u8>> {
+    let mut cmd = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Read stdout on a thread so a chatty classifier can't block on a full pipe
+    let mut out = child
+        .stdout
+        .take()
+        .context("classifier stdout unavailable")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        out.read_to_end(&mut buf).map(|_| buf)
+    });
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(payload);
+    }
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let stdout = reader
+                .join()
+                .map_err(|_| anyhow::anyhow!("classifier output reader panicked"))??;
+            if !status.success() {
+                bail!("exited with {status}");
+            }
+            return Ok(stdout);
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ClassifiersConfig {
+    #[serde(default = "default_builtins")]
+    builtin: Vec<String>,
+    #[serde(default)]
+    command: Vec<CommandClassifier>,
+}
+
+impl Default for ClassifiersConfig {
+    fn default() -> Self {
+        Self {
+            builtin: default_builtins(),
+            command: Vec::new(),
+        }
+    }
+}
+
+fn default_builtins() -> Vec<String> {
+    BUILTIN_CLASSIFIERS.iter().map(|s| s.to_string()).collect()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    classifiers: ClassifiersConfig,
+}
+
+/// The classifiers enabled in `config.toml`, run together on each conversation.
+pub struct Classifiers {
+    classifiers: Vec<Box<dyn Classifier>>,
+}
+
+impl Default for Classifiers {
+    fn default() -> Self {
+        Self::from_config(ClassifiersConfig::default())
+    }
+}
+
+impl Classifiers {
+    /// The `[classifiers]` section; missing or invalid means "built-ins only".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => Self::from_config(config.classifiers),
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring classifiers: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn from_config(config: ClassifiersConfig) -> Self {
+        let mut classifiers: Vec<Box<dyn Classifier>> = Vec::new();
+        for name in &config.builtin {
+            match name.as_str() {
+                "has_code" => classifiers.push(Box::new(HasCode)),
+                "language" => classifiers.push(Box::new(Language)),
+                "outcome" => classifiers.push(Box::new(Outcome)),
+                other => warn!(classifier = %other, "ignoring unknown built-in classifier"),
+            }
+        }
+        for command in config.command {
+            classifiers.push(Box::new(command));
+        }
+        Self { classifiers }
+    }
+
+    pub fn with(mut self, classifier: Box<dyn Classifier>) -> Self {
+        self.classifiers.push(classifier);
+        self
+    }
+
+    /// Run every classifier over the batch and store each conversation's
+    /// combined labels in `metadata.cass.labels`.
+    pub fn apply(&self, convs: &mut [&mut NormalizedConversation]) {
+        let mut labels: Vec<Vec<Label>> = vec![Vec::new(); convs.len()];
+        for classifier in &self.classifiers {
+            for (found, labels) in classifier
+                .classify_batch(convs)
+                .into_iter()
+                .zip(&mut labels)
+            {
+                match found {
+                    Ok(found) => labels.extend(found.into_iter().filter_map(normalize)),
+                    Err(e) => warn!(classifier = classifier.name(), "classifier failed: {e:#}"),
+                }
+            }
+        }
+        for (conv, mut labels) in convs.iter_mut().zip(labels) {
+            labels.dedup();
+            store_labels(conv, labels);
+        }
+    }
+}
+
+/// Replace `metadata.cass.labels` with `labels`.
+fn store_labels(conv: &mut NormalizedConversation, labels: Vec<Label>) {
+    if !conv.metadata.is_object() {
+        conv.metadata = serde_json::json!({});
+    }
+    if let Some(obj) = conv.metadata.as_object_mut() {
+        let cass = obj
+            .entry("cass".to_string())
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(cass) = cass.as_object_mut() {
+            cass.insert(
+                "labels".to_string(),
+                serde_json::to_value(&labels).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+/// Keys and values are matched case-insensitively; empty keys are dropped.
+fn normalize(mut label: Label) -> Option<Label> {
+    label.key = label.key.trim().to_lowercase();
+    label.value = label.value.trim().to_lowercase();
+    (!label.key.is_empty()).then_some(label)
+}
+
+/// Labels stored on a conversation by [`Classifiers::apply`].
+pub fn stored_labels(conv: &NormalizedConversation) -> Vec<Label> {
+    conv.metadata
+        .pointer("/cass/labels")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Search terms for message `idx`: its own labels plus the conversation's, each as
+/// both `key` and `key=value`. A conversation label whose key is also assigned per
+/// message (`has_code`, `language`) only summarizes those, so it isn't spread onto
+/// every message.
+pub fn index_terms(labels: &[Label], idx: i64) -> BTreeSet<String> {
+    let per_message: BTreeSet<&str> = labels
+        .iter()
+        .filter(|l| l.message_idx.is_some())
+        .map(|l| l.key.as_str())
+        .collect();
+    labels
+        .iter()
+        .filter(|l| match l.message_idx {
+            Some(i) => i == idx,
+            None => !per_message.contains(l.key.as_str()),
+        })
+        .flat_map(|l| [l.key.clone(), format!("{}={}", l.key, l.value)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::NormalizedMessage;
+    use std::path::PathBuf;
+
+    fn msg(idx: i64, role: &str, content: &str) -> NormalizedMessage {
+        NormalizedMessage {
+            idx,
+            role: role.into(),
+            author: None,
+            created_at: None,
+            content: content.into(),
+            extra: serde_json::Value::Null,
+            snippets: Vec::new(),
+            source: None,
+        }
+    }
+
+    fn conv(messages: Vec<NormalizedMessage>) -> NormalizedConversation {
+        NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: Some("s1".into()),
+            title: None,
+            workspace: None,
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+            started_at: None,
+            ended_at: None,
+            metadata: serde_json::json!({"cass": {"origin": {"source_id": "local"}}}),
+            messages,
+        }
+    }
+
+    struct Ticket;
+
+    impl Classifier for Ticket {
+        fn name(&self) -> &str {
+            "ticket"
+        }
+
+        fn classify(&self, conv: &NormalizedConversation) -> Result<Vec<Label>> {
+            Ok(conv
+                .messages
+                .iter()
+                .filter(|m| m.content.contains("ENG-42"))
+                .map(|m| Label::message(m.idx, "Ticket", "ENG-42"))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn builtins_and_custom_classifiers_label_conversation() {
+        let mut c = conv(vec![
+            msg(0, "user", "fix ENG-42 please"),
+            msg(1, "assistant", "```rust\nfn main() {}\n```"),
+            msg(2, "assistant", "Done. All tests pass now."),
+        ]);
+        Classifiers::default()
+            .with(Box::new(Ticket))
+            .apply(&mut [&mut c]);
+        assert_eq!(c.metadata["cass"]["origin"]["source_id"], "local");
+
+        let labels = stored_labels(&c);
+        assert!(labels.contains(&Label::message(1, "has_code", "true")));
+        assert!(labels.contains(&Label::conversation("language", "rust")));
+        assert!(labels.contains(&Label::conversation("outcome", "success")));
+        assert!(labels.contains(&Label::message(0, "ticket", "eng-42")));
+
+        let terms = index_terms(&labels, 1);
+        assert!(terms.contains("has_code"));
+        assert!(terms.contains("language=rust"));
+        assert!(terms.contains("outcome=success"));
+        assert!(!terms.contains("ticket"));
+        assert!(index_terms(&labels, 0).contains("ticket=eng-42"));
+        assert!(!index_terms(&labels, 0).contains("has_code"));
+        assert!(index_terms(&labels, 0).contains("outcome=success"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_classifier_reads_labels_from_stdout() {
+        let classifier = CommandClassifier {
+            name: "echo".into(),
+            command: r#"cat >/dev/null; echo '[{"key":"team","value":"infra"},{"key":"flag","message_idx":0}]'"#
+                .into(),
+            timeout_secs: Some(5),
+        };
+        let labels = classifier
+            .classify(&conv(vec![msg(0, "user", "hi")]))
+            .unwrap();
+        assert_eq!(
+            labels,
+            vec![
+                Label::conversation("team", "infra"),
+                Label::message(0, "flag", "true"),
+            ]
+        );
+
+        let broken = CommandClassifier {
+            name: "broken".into(),
+            command: "exit 3".into(),
+            timeout_secs: Some(5),
+        };
+        assert!(broken.classify(&conv(Vec::new())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_classifier_runs_once_per_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let classifier = CommandClassifier {
+            name: "lines".into(),
+            command: format!(
+                r#"echo run >> '{}'; while read -r _; do echo '[{{"key":"seen"}}]'; done"#,
+                runs.display()
+            ),
+            timeout_secs: Some(5),
+        };
+        let mut convs: Vec<_> = (0..3).map(|_| conv(vec![msg(0, "user", "hi")])).collect();
+        let mut batch: Vec<&mut NormalizedConversation> = convs.iter_mut().collect();
+        Classifiers::from_config(ClassifiersConfig {
+            builtin: Vec::new(),
+            command: vec![classifier],
+        })
+        .apply(&mut batch);
+
+        assert_eq!(std::fs::read_to_string(&runs).unwrap().lines().count(), 1);
+        for c in &convs {
+            assert_eq!(stored_labels(c), [Label::conversation("seen", "true")]);
+        }
+
+        let short = CommandClassifier {
+            name: "short".into(),
+            command: r#"cat >/dev/null; echo '[]'"#.into(),
+            timeout_secs: Some(5),
+        };
+        let batch: Vec<&mut NormalizedConversation> = convs.iter_mut().collect();
+        assert!(short.classify_batch(&batch).iter().all(Result::is_err));
+    }
+}
@@ -0,0 +1,490 @@
+//! Near-duplicate conversations and `cass dedupe`.
+//!
+//! Two kinds of redundancy are found:
+//!
+//! - **identical**: every message matches (a session file copied or synced twice)
+//! - **retry**: the same prompts in the same workspace with different answers
+//!   (the same task re-run after a bad attempt)
+//!
+//! Conversations are bucketed by their first prompt. Within a bucket two
+//! conversations belong to the same cluster when all of their messages match,
+//! or when they share a workspace and the Jaccard similarity of their prompt
+//! sets reaches the threshold. Whitespace differences are ignored.
+//!
+//! Each cluster keeps one conversation (locked first, then the most messages,
+//! the most content, the newest). Resolving a cluster deletes the others from
+//! SQLite, Tantivy and the vector indexes; merging first carries their tags,
+//! rating and message notes over to the kept conversation. Like `cass prune`,
+//! only conversations that are alone in their session file are deleted, and
+//! locked ones never are. Deleted conversations are recorded, so rescanning
+//! their files (even with `--full`) skips them until the file changes.
+//!
+//! Copies of one agent session (same agent and external id) indexed from
+//! different sources, such as a local file and its synced mirror, are handled
+//! at index time instead: [`relate_copies`] compares their messages, the copy
+//! holding every message of the other is kept, and the dropped copy is listed
+//! by `cass dedupe --merged`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::search::tantivy::{TantivyIndex, stored_doc_key};
+use crate::search::vector_compact::{
+    CompactOptions, compact_vector_indexes_with_live, live_message_ids,
+};
+use crate::search::vector_index::VECTOR_INDEX_DIR;
+use crate::storage::sqlite::SqliteStorage;
+
+/// Default prompt similarity for two attempts to count as retries
+pub const DEFAULT_THRESHOLD: f64 = 0.8;
+
+#[derive(Debug, Clone, Default)]
+pub struct DedupeOptions {
+    /// Minimum Jaccard similarity of prompt sets, 0.0..=1.0
+    pub threshold: f64,
+    /// Only consider conversations from these agents (all agents when empty)
+    pub agents: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClusterKind {
+    Identical,
+    Retry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateMember {
+    pub conversation_id: i64,
+    pub source_id: String,
+    pub source_path: String,
+    pub agent: String,
+    pub workspace: Option<String>,
+    pub title: Option<String>,
+    pub started_at: Option<i64>,
+    pub messages: usize,
+    /// Bytes of message text and raw JSON stored for this conversation
+    pub content_bytes: u64,
+    /// Locked with `cass lock`; never deleted
+    pub retained: bool,
+    /// Other conversations come from the same session file; never deleted
+    pub shared_file: bool,
+}
+
+impl DuplicateMember {
+    /// Whether resolving a cluster may delete this conversation
+    pub fn deletable(&self) -> bool {
+        !self.retained && !self.shared_file
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster {
+    pub kind: ClusterKind,
+    /// Lowest prompt similarity between the kept conversation and a duplicate
+    pub similarity: f64,
+    /// The conversation to keep, followed by its duplicates
+    pub members: Vec<DuplicateMember>,
+    /// Bytes freed by deleting the deletable duplicates
+    pub reclaimable_bytes: u64,
+}
+
+impl DuplicateCluster {
+    pub fn keep(&self) -> &DuplicateMember {
+        &self.members[0]
+    }
+
+    pub fn duplicates(&self) -> &[DuplicateMember] {
+        &self.members[1..]
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupeReport {
+    pub threshold: f64,
+    pub agents: Vec<String>,
+    pub conversations_scanned: usize,
+    pub clusters: Vec<DuplicateCluster>,
+    /// Duplicates across all clusters (excluding the kept conversations)
+    pub duplicates: usize,
+    pub reclaimable_bytes: u64,
+}
+
+/// What to do with a cluster's duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Delete,
+    /// Carry tags, rating and notes over to the kept conversation, then delete
+    Merge,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResolveSummary {
+    pub conversations: usize,
+    pub messages: usize,
+    /// Vector index rows dropped across all embedders
+    pub vectors: usize,
+    pub reclaimed_bytes: u64,
+    /// Tags, ratings and notes carried over by merges
+    pub merged_metadata: usize,
+}
+
+struct Fingerprint {
+    member: DuplicateMember,
+    workspace_id: Option<i64>,
+    /// Hash of every (role, content) pair in order
+    full: u64,
+    first_prompt: Option<u64>,
+    prompts: HashSet<u64>,
+}
+
+fn text_hash(role: &str, content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    role.hash(&mut hasher);
+    for word in content.split_whitespace() {
+        word.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// How an incoming copy of a session relates to a stored copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyRelation {
+    /// The stored copy has every incoming message (or they're identical)
+    Covered,
+    /// The incoming copy continues the stored one
+    Richer,
+    /// Both have messages the other lacks
+    Diverged,
+}
+
+/// Order-sensitive hashes of (role, content) pairs, ignoring whitespace
+pub fn message_hashes<'a>(messages: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<u64> {
+    messages
+        .into_iter()
+        .map(|(role, content)| text_hash(role, content))
+        .collect()
+}
+
+/// Compare two copies of a session by their [`message_hashes`].
+pub fn relate_copies(stored: &[u64], incoming: &[u64]) -> CopyRelation {
+    if stored.starts_with(incoming) {
+        CopyRelation::Covered
+    } else if incoming.starts_with(stored) {
+        CopyRelation::Richer
+    } else {
+        CopyRelation::Diverged
+    }
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Find clusters of near-identical conversations, largest reclaimable space first.
+pub fn find_duplicates(storage: &SqliteStorage, opts: &DedupeOptions) -> Result<DedupeReport> {
+    let agents: HashSet<String> = opts.agents.iter().map(|a| a.to_lowercase()).collect();
+    let conn = storage.raw();
+
+    let mut file_counts: HashMap<(String, String), usize> = HashMap::new();
+    let mut prints: Vec<Fingerprint> = Vec::new();
+    let mut index: HashMap<i64, usize> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.source_id, c.source_path, a.slug, w.path, c.workspace_id, c.title,
+                    c.started_at,
+                    EXISTS(SELECT 1 FROM retained_conversations r
+                           WHERE r.source_id = c.source_id AND r.source_path = c.source_path)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN workspaces w ON c.workspace_id = w.id
+             ORDER BY c.id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                DuplicateMember {
+                    conversation_id: row.get(0)?,
+                    source_id: row.get(1)?,
+                    source_path: row.get(2)?,
+                    agent: row.get(3)?,
+                    workspace: row.get(4)?,
+                    title: row.get(6)?,
+                    started_at: row.get(7)?,
+                    messages: 0,
+                    content_bytes: 0,
+                    retained: row.get(8)?,
+                    shared_file: false,
+                },
+                row.get::<_, Option<i64>>(5)?,
+            ))
+        })?;
+        for row in rows {
+            let (member, workspace_id) = row?;
+            *file_counts
+                .entry((member.source_id.clone(), member.source_path.clone()))
+                .or_default() += 1;
+            if !agents.is_empty() && !agents.contains(&member.agent.to_lowercase()) {
+                continue;
+            }
+            index.insert(member.conversation_id, prints.len());
+            prints.push(Fingerprint {
+                member,
+                workspace_id,
+                full: 0,
+                first_prompt: None,
+                prompts: HashSet::new(),
+            });
+        }
+    }
+    {
+        let mut stmt = conn.prepare(
+            "SELECT conversation_id, role, cass_text(content),
+                    LENGTH(content) + LENGTH(COALESCE(extra_json, ''))
+             FROM messages ORDER BY conversation_id, idx",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut hashers: HashMap<usize, DefaultHasher> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let Some(&i) = index.get(&row.get::<_, i64>(0)?) else {
+                continue;
+            };
+            let role: String = row.get(1)?;
+            let content: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+            let hash = text_hash(&role, &content);
+            hashers.entry(i).or_default().write_u64(hash);
+            let print = &mut prints[i];
+            print.member.messages += 1;
+            print.member.content_bytes += row.get::<_, Option<i64>>(3)?.unwrap_or(0).max(0) as u64;
+            if role == "user" && !content.trim().is_empty() {
+                print.first_prompt.get_or_insert(hash);
+                print.prompts.insert(hash);
+            }
+        }
+        for (i, hasher) in hashers {
+            prints[i].full = hasher.finish();
+        }
+    }
+    for print in &mut prints {
+        let key = (
+            print.member.source_id.clone(),
+            print.member.source_path.clone(),
+        );
+        print.member.shared_file = file_counts.get(&key).copied().unwrap_or(0) > 1;
+    }
+
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, print) in prints.iter().enumerate() {
+        if let Some(first) = print.first_prompt {
+            buckets.entry(first).or_default().push(i);
+        }
+    }
+
+    let mut clusters = Vec::new();
+    for bucket in buckets.into_values().filter(|b| b.len() > 1) {
+        // Union-find over the bucket
+        let mut parent: Vec<usize> = (0..bucket.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for a in 0..bucket.len() {
+            for b in a + 1..bucket.len() {
+                let (pa, pb) = (&prints[bucket[a]], &prints[bucket[b]]);
+                let related = pa.full == pb.full
+                    || (pa.workspace_id == pb.workspace_id
+                        && jaccard(&pa.prompts, &pb.prompts) >= opts.threshold);
+                if related {
+                    let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                    parent[rb] = ra;
+                }
+            }
+        }
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, &print) in bucket.iter().enumerate() {
+            let r = root(&mut parent, i);
+            groups.entry(r).or_default().push(print);
+        }
+        for mut group in groups.into_values().filter(|g| g.len() > 1) {
+            group.sort_by(|&a, &b| {
+                let (ma, mb) = (&prints[a].member, &prints[b].member);
+                mb.retained
+                    .cmp(&ma.retained)
+                    .then(mb.messages.cmp(&ma.messages))
+                    .then(mb.content_bytes.cmp(&ma.content_bytes))
+                    .then(mb.started_at.cmp(&ma.started_at))
+                    .then(ma.conversation_id.cmp(&mb.conversation_id))
+            });
+            let keep = &prints[group[0]];
+            let identical = group.iter().all(|&i| prints[i].full == keep.full);
+            let similarity = group[1..]
+                .iter()
+                .map(|&i| {
+                    if prints[i].full == keep.full {
+                        1.0
+                    } else {
+                        jaccard(&keep.prompts, &prints[i].prompts)
+                    }
+                })
+                .fold(1.0, f64::min);
+            let members: Vec<DuplicateMember> =
+                group.iter().map(|&i| prints[i].member.clone()).collect();
+            let reclaimable_bytes = members[1..]
+                .iter()
+                .filter(|m| m.deletable())
+                .map(|m| m.content_bytes)
+                .sum();
+            clusters.push(DuplicateCluster {
+                kind: if identical {
+                    ClusterKind::Identical
+                } else {
+                    ClusterKind::Retry
+                },
+                similarity,
+                members,
+                reclaimable_bytes,
+            });
+        }
+    }
+    clusters.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then(a.keep().conversation_id.cmp(&b.keep().conversation_id))
+    });
+
+    Ok(DedupeReport {
+        threshold: opts.threshold,
+        agents: opts.agents.clone(),
+        conversations_scanned: prints.len(),
+        duplicates: clusters.iter().map(|c| c.members.len() - 1).sum(),
+        reclaimable_bytes: clusters.iter().map(|c| c.reclaimable_bytes).sum(),
+        clusters,
+    })
+}
+
+/// Apply the chosen resolutions: merge metadata where asked, then delete the
+/// deletable duplicates from SQLite, Tantivy and the vector indexes in one pass.
+pub fn resolve_clusters(
+    storage: &mut SqliteStorage,
+    t_index: &mut TantivyIndex,
+    data_dir: &Path,
+    decisions: &[(&DuplicateCluster, Resolution)],
+) -> Result<ResolveSummary> {
+    let mut summary = ResolveSummary::default();
+    let mut ids = Vec::new();
+    for (cluster, resolution) in decisions {
+        let victims: Vec<&DuplicateMember> = cluster
+            .duplicates()
+            .iter()
+            .filter(|m| m.deletable())
+            .collect();
+        if *resolution == Resolution::Merge {
+            let victim_ids: Vec<i64> = victims.iter().map(|m| m.conversation_id).collect();
+            summary.merged_metadata +=
+                storage.merge_conversation_metadata(cluster.keep().conversation_id, &victim_ids)?;
+        }
+        for m in victims {
+            ids.push(m.conversation_id);
+            summary.reclaimed_bytes += m.content_bytes;
+        }
+    }
+    if ids.is_empty() {
+        return Ok(summary);
+    }
+
+    let mut removed_messages: HashSet<u64> = HashSet::new();
+    {
+        let mut stmt = storage
+            .raw()
+            .prepare("SELECT id FROM messages WHERE conversation_id = ?")?;
+        for id in &ids {
+            for message_id in stmt.query_map([id], |r| r.get::<_, i64>(0))? {
+                removed_messages.insert(message_id? as u64);
+            }
+        }
+    }
+    let live = if data_dir.join(VECTOR_INDEX_DIR).is_dir() {
+        let mut live = live_message_ids(storage.raw())?;
+        live.retain(|id| !removed_messages.contains(id));
+        Some(live)
+    } else {
+        None
+    };
+
+    // By doc key: a kept copy may share the source path from another source
+    for (source_id, agent, external_id, source_path) in storage.conversation_identities(&ids)? {
+        t_index.delete_doc_key(&stored_doc_key(
+            &source_id,
+            &agent,
+            external_id.as_deref(),
+            &source_path,
+        ));
+    }
+    storage.record_deleted_conversations(&ids, "dedupe")?;
+    summary.conversations = storage.delete_conversations(&ids)?;
+    summary.messages = removed_messages.len();
+    t_index.commit()?;
+
+    if let Some(live) = live {
+        let compact = CompactOptions {
+            min_fragmentation: 0.0,
+            force: true,
+            dry_run: false,
+        };
+        let report = compact_vector_indexes_with_live(data_dir, &live, &compact)?;
+        summary.vectors = report.indexes.iter().map(|i| i.orphaned).sum();
+    }
+    tracing::info!(
+        conversations = summary.conversations,
+        vectors = summary.vectors,
+        merged = summary.merged_metadata,
+        "dedupe: removed duplicate conversations"
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(items: &[u64]) -> HashSet<u64> {
+        items.iter().copied().collect()
+    }
+
+    #[test]
+    fn hashing_ignores_whitespace_but_not_role() {
+        assert_eq!(
+            text_hash("user", "fix  the\n build"),
+            text_hash("user", "fix the build ")
+        );
+        assert_ne!(
+            text_hash("user", "fix the build"),
+            text_hash("assistant", "fix the build")
+        );
+        assert_eq!(jaccard(&set(&[1, 2, 3]), &set(&[1, 2, 3])), 1.0);
+        assert_eq!(jaccard(&set(&[1, 2]), &set(&[2, 3])), 1.0 / 3.0);
+        assert_eq!(jaccard(&set(&[]), &set(&[])), 0.0);
+    }
+
+    #[test]
+    fn relate_copies_prefers_the_longer_matching_copy() {
+        let full = message_hashes([("user", "fix it"), ("agent", "done"), ("user", "thanks")]);
+        let partial = message_hashes([("user", "fix  it"), ("agent", "done")]);
+        let other = message_hashes([("user", "fix it"), ("agent", "no")]);
+        assert_eq!(relate_copies(&full, &partial), CopyRelation::Covered);
+        assert_eq!(relate_copies(&full, &full), CopyRelation::Covered);
+        assert_eq!(relate_copies(&partial, &full), CopyRelation::Richer);
+        assert_eq!(relate_copies(&full, &other), CopyRelation::Diverged);
+    }
+}
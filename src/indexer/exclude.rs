@@ -0,0 +1,185 @@
+//! Gitignore-style exclusion patterns for indexing.
+//!
+//! ```toml
+//! exclude = [
+//!     "~/work/client-acme",   # a directory and everything under it
+//!     "scratch",              # any path component named `scratch`
+//!     "**/secret-*/**",
+//!     "!~/work/client-acme/oss",  # re-include
+//! ]
+//! ```
+//!
+//! Patterns are matched against each conversation's source path and workspace
+//! path (and their ancestors, so excluding a directory excludes its contents).
+//! As in `.gitignore`, a pattern without a `/` matches a name at any depth, a
+//! leading `!` re-includes, and the last matching pattern wins.
+
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::connectors::NormalizedConversation;
+use crate::hooks::HooksConfig;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeRules {
+    rules: Vec<Rule>,
+}
+
+impl ExcludeRules {
+    /// Load the `exclude` list from the shared config file; missing or invalid means "none".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => Self::new(&config.exclude, dirs::home_dir().as_deref()),
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring exclude patterns: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Compile patterns; invalid ones are skipped with a warning.
+    pub fn new(patterns: &[String], home: Option<&Path>) -> Self {
+        let rules = patterns
+            .iter()
+            .filter_map(|raw| {
+                let raw = raw.trim();
+                let (negated, pat) = match raw.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, raw),
+                };
+                let pat = pat.trim_end_matches('/');
+                if pat.is_empty() || pat.starts_with('#') {
+                    return None;
+                }
+                let normalized = normalize(pat, home);
+                match Pattern::new(&normalized) {
+                    Ok(pattern) => Some(Rule { pattern, negated }),
+                    Err(e) => {
+                        warn!(pattern = raw, "skipping invalid exclude pattern: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// True if `path` (or a directory containing it) is excluded.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let mut excluded = false;
+        for rule in &self.rules {
+            if path
+                .ancestors()
+                .any(|p| rule.pattern.matches_path_with(p, MATCH_OPTIONS))
+            {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+
+    /// True if the conversation's source file or workspace is excluded.
+    pub fn excludes(&self, conv: &NormalizedConversation) -> bool {
+        !self.is_empty()
+            && (self.is_excluded(&conv.source_path)
+                || conv
+                    .workspace
+                    .as_deref()
+                    .is_some_and(|w| self.is_excluded(w)))
+    }
+}
+
+/// Turn a gitignore-style pattern into an absolute-path glob.
+fn normalize(pat: &str, home: Option<&Path>) -> String {
+    if let Some(rest) = pat.strip_prefix("~/")
+        && let Some(home) = home
+    {
+        return home.join(rest).to_string_lossy().into_owned();
+    }
+    if pat.starts_with('/') || pat.starts_with("**") {
+        return pat.to_string();
+    }
+    // Relative patterns ("scratch", "clients/acme") match at any depth
+    PathBuf::from("**").join(pat).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(patterns: &[&str]) -> ExcludeRules {
+        let patterns: Vec<String> = patterns.iter().map(|p| (*p).to_string()).collect();
+        ExcludeRules::new(&patterns, Some(Path::new("/home/me")))
+    }
+
+    #[test]
+    fn directory_patterns_exclude_contents() {
+        let r = rules(&["~/work/client-acme/", "/tmp/private"]);
+        assert!(r.is_excluded(Path::new("/home/me/work/client-acme")));
+        assert!(r.is_excluded(Path::new("/home/me/work/client-acme/src/main.rs")));
+        assert!(r.is_excluded(Path::new("/tmp/private/session.jsonl")));
+        assert!(!r.is_excluded(Path::new("/home/me/work/client-acme-2")));
+        assert!(!r.is_excluded(Path::new("/home/me/work/other")));
+    }
+
+    #[test]
+    fn bare_names_match_at_any_depth() {
+        let r = rules(&["scratch", "secret-*"]);
+        assert!(r.is_excluded(Path::new("/home/me/scratch/notes.md")));
+        assert!(r.is_excluded(Path::new("/a/b/secret-project/x.jsonl")));
+        assert!(!r.is_excluded(Path::new("/home/me/scratchpad/notes.md")));
+    }
+
+    #[test]
+    fn negation_reincludes_and_last_match_wins() {
+        let r = rules(&["~/work/**", "!~/work/oss", "# comment", ""]);
+        assert!(r.is_excluded(Path::new("/home/me/work/client/a.rs")));
+        assert!(!r.is_excluded(Path::new("/home/me/work/oss/lib.rs")));
+        assert!(!r.is_excluded(Path::new("/home/me/personal/a.rs")));
+    }
+
+    #[test]
+    fn parses_config_list() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            exclude = ["~/clients"]
+
+            [aliases]
+            bugs = "has:errors"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.exclude, vec!["~/clients".to_string()]);
+    }
+}
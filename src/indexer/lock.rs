@@ -0,0 +1,121 @@
+//! Advisory writer lock for a data dir.
+//!
+//! `cass index` (one-shot or `--watch`), the daemon and the TUI's background
+//! indexer take `<data_dir>/index.lock` before writing to the database or the
+//! Tantivy index, so only one of them runs at a time. So do the one-shot
+//! commands that rewrite stored conversations: `prune`, `dedupe`, `update`,
+//! `db optimize` and `db import` (not in their dry-run/report modes). Readers (`cass search`,
+//! the TUI, stats) never take it: they open SQLite read-only and rely on WAL
+//! plus `busy_timeout` to read while the writer commits.
+//!
+//! The lock is an OS file lock, so it goes away when the holder exits, even
+//! after a crash. The file stays behind and records the last holder's pid.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const LOCK_FILE: &str = "index.lock";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub started_at: i64,
+}
+
+/// Another process holds the writer lock.
+#[derive(Debug, Error)]
+#[error("another indexer is already running on this data dir{}", .holder.as_ref().map(|h| format!(" (pid {})", h.pid)).unwrap_or_default())]
+pub struct IndexLocked {
+    pub holder: Option<LockHolder>,
+}
+
+/// Held writer lock; released on drop.
+#[derive(Debug)]
+pub struct IndexLock {
+    _file: File,
+}
+
+impl IndexLock {
+    /// Take the lock without waiting. Fails with [`IndexLocked`] if it is held.
+    pub fn acquire(data_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(data_dir)
+            .with_context(|| format!("creating data dir {}", data_dir.display()))?;
+        let path = data_dir.join(LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(IndexLocked {
+                    holder: read_holder(&mut file),
+                }
+                .into());
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("locking {}", path.display()));
+            }
+        }
+        let holder = LockHolder {
+            pid: std::process::id(),
+            started_at: chrono::Utc::now().timestamp_millis(),
+        };
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(serde_json::to_string(&holder)?.as_bytes())?;
+        file.flush()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// The current holder, or None when no writer is running.
+pub fn current_holder(data_dir: &Path) -> Option<LockHolder> {
+    let mut file = File::open(data_dir.join(LOCK_FILE)).ok()?;
+    match file.try_lock_shared() {
+        Ok(()) => None,
+        Err(TryLockError::WouldBlock) => read_holder(&mut file),
+        Err(TryLockError::Error(_)) => None,
+    }
+}
+
+fn read_holder(file: &mut File) -> Option<LockHolder> {
+    let mut content = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_writer_is_refused_until_the_first_drops() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = IndexLock::acquire(dir.path()).unwrap();
+        assert_eq!(
+            current_holder(dir.path()).map(|h| h.pid),
+            Some(std::process::id())
+        );
+
+        let err = IndexLock::acquire(dir.path()).unwrap_err();
+        let locked = err.downcast_ref::<IndexLocked>().expect("IndexLocked");
+        assert_eq!(
+            locked.holder.as_ref().map(|h| h.pid),
+            Some(std::process::id())
+        );
+
+        drop(lock);
+        assert_eq!(current_holder(dir.path()), None);
+        IndexLock::acquire(dir.path()).unwrap();
+    }
+}
@@ -1,9 +1,20 @@
-use std::collections::HashMap;
+pub mod budget;
+pub mod classify;
+pub mod dedupe;
+pub mod exclude;
+pub mod lock;
+pub mod projects;
+pub mod redact;
+pub mod retention;
+pub mod tokens;
+pub mod window;
+
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
@@ -11,20 +22,32 @@ use notify::{RecursiveMode, Watcher, recommended_watcher};
 
 use crate::connectors::NormalizedConversation;
 use crate::connectors::{
-    Connector, ScanRoot, aider::AiderConnector, amp::AmpConnector, chatgpt::ChatGptConnector,
-    claude_code::ClaudeCodeConnector, cline::ClineConnector, codex::CodexConnector,
-    cursor::CursorConnector, gemini::GeminiConnector, opencode::OpenCodeConnector,
-    pi_agent::PiAgentConnector,
+    Connector, ScanRoot, aider::AiderConnector, amp::AmpConnector, cass::CassConnector,
+    chatgpt::ChatGptConnector, claude_code::ClaudeCodeConnector, cline::ClineConnector,
+    codex::CodexConnector, cursor::CursorConnector, gemini::GeminiConnector,
+    opencode::OpenCodeConnector, pi_agent::PiAgentConnector,
 };
-use crate::search::tantivy::{TantivyIndex, index_dir};
+use crate::hooks::{HookEvent, HooksConfig};
+use crate::indexer::budget::{StorageBudget, StorageConfig, enforce_budget};
+use crate::indexer::exclude::ExcludeRules;
+use crate::indexer::redact::{RedactionStats, Redactor};
+use crate::search::analyzers::AnalyzerConfig;
+use crate::search::tantivy::{TantivyIndex, index_dir, legacy_index_dirs};
 use crate::sources::config::{Platform, SourcesConfig};
-use crate::sources::provenance::{Origin, Source};
+use crate::sources::provenance::{LOCAL_SOURCE_ID, Origin, Source, SourceKind};
+use crate::sources::staging;
 use crate::sources::sync::path_to_safe_dirname;
 use crate::storage::sqlite::SqliteStorage;
 
 #[derive(Debug, Clone)]
 pub enum ReindexCommand {
     Full,
+    /// Delete sessions, identified by `(source_id, source_path)`, through the
+    /// watcher that holds the index writer (see [`delete_sessions`])
+    DeleteSessions {
+        sessions: Vec<(String, String)>,
+        reply: Sender<std::result::Result<(usize, usize), String>>,
+    },
 }
 
 #[derive(Debug)]
@@ -44,6 +67,107 @@ pub struct IndexingProgress {
     pub discovered_agents: AtomicUsize,
     /// Names of discovered agents (protected by mutex for concurrent access)
     pub discovered_agent_names: Mutex<Vec<String>>,
+    /// Watch-mode pass timings and failures, reported by `cass daemon status`
+    pub watch: Mutex<WatchMetrics>,
+    /// Conversations created (not just updated) since the indexer started
+    pub added: AtomicUsize,
+    /// Unix millis of the last search index commit; 0 before the first
+    pub last_commit_at: AtomicI64,
+}
+
+impl IndexingProgress {
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let last_commit_at = self.last_commit_at.load(Ordering::Relaxed);
+        ProgressSnapshot {
+            phase: self.phase.load(Ordering::Relaxed),
+            current: self.current.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            added: self.added.load(Ordering::Relaxed),
+            last_commit_at: (last_commit_at > 0).then_some(last_commit_at),
+        }
+    }
+}
+
+/// Point-in-time copy of [`IndexingProgress`], as shown in the TUI footer and
+/// written to `daemon.json`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProgressSnapshot {
+    /// 0=Idle, 1=Scanning, 2=Indexing
+    pub phase: usize,
+    /// Conversations scanned so far
+    pub current: usize,
+    pub total: usize,
+    pub added: usize,
+    pub last_commit_at: Option<i64>,
+}
+
+/// Record a search index commit when a progress tracker is attached
+fn mark_committed(progress: &Option<Arc<IndexingProgress>>) {
+    if let Some(p) = progress {
+        p.last_commit_at
+            .store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+}
+
+/// Health counters for watch-mode indexing passes
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct WatchMetrics {
+    pub passes: u64,
+    pub errors: u64,
+    /// Failed passes since the last successful one
+    pub consecutive_errors: u64,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<i64>,
+    /// Unix millis when the last pass finished
+    pub last_pass_at: Option<i64>,
+    /// Wall time of the last pass
+    pub last_pass_ms: Option<u64>,
+    /// Keyed by connector name
+    pub connectors: BTreeMap<String, ConnectorLag>,
+}
+
+/// How far one connector's index trails its session files
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ConnectorLag {
+    /// Newest session file modification that triggered a pass (unix millis)
+    pub last_change_at: Option<i64>,
+    /// When that change finished indexing (unix millis)
+    pub last_indexed_at: Option<i64>,
+    pub conversations_indexed: u64,
+}
+
+impl ConnectorLag {
+    /// Delay between a session file changing and it becoming searchable
+    pub fn lag_ms(&self) -> Option<i64> {
+        Some((self.last_indexed_at? - self.last_change_at?).max(0))
+    }
+}
+
+impl WatchMetrics {
+    fn record_pass(&mut self, started: Instant, result: &Result<()>) {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.passes += 1;
+        self.last_pass_at = Some(now);
+        self.last_pass_ms = Some(started.elapsed().as_millis() as u64);
+        match result {
+            Ok(()) => self.consecutive_errors = 0,
+            Err(e) => {
+                self.errors += 1;
+                self.consecutive_errors += 1;
+                self.last_error = Some(format!("{e:#}"));
+                self.last_error_at = Some(now);
+            }
+        }
+    }
+}
+
+/// Record the outcome of one watch pass when a progress tracker is attached
+fn record_watch_pass(opts: &IndexOptions, started: Instant, result: &Result<()>) {
+    if let Some(p) = &opts.progress
+        && let Ok(mut metrics) = p.watch.lock()
+    {
+        metrics.record_pass(started, result);
+    }
 }
 
 #[derive(Clone)]
@@ -55,6 +179,8 @@ pub struct IndexOptions {
     pub watch_once_paths: Option<Vec<PathBuf>>,
     pub db_path: PathBuf,
     pub data_dir: PathBuf,
+    /// Remove conversations whose local source files no longer exist.
+    pub gc: bool,
     pub progress: Option<Arc<IndexingProgress>>,
 }
 
@@ -62,7 +188,10 @@ pub fn run_index(
     opts: IndexOptions,
     event_channel: Option<(Sender<IndexerEvent>, Receiver<IndexerEvent>)>,
 ) -> Result<()> {
+    // Held for the whole run, including `--watch`, so only one writer touches the data dir
+    let _lock = lock::IndexLock::acquire(&opts.data_dir)?;
     let mut storage = SqliteStorage::open(&opts.db_path)?;
+    storage.set_compression(StorageConfig::load().is_some_and(|c| c.compression));
     let index_path = index_dir(&opts.data_dir)?;
 
     // Detect if we are rebuilding due to missing meta/schema mismatch
@@ -77,24 +206,93 @@ pub fn run_index(
             })
             .as_deref()
             == Some(crate::search::tantivy::SCHEMA_HASH);
+    // An index in an older directory layout is migrated by re-adding every stored
+    // conversation from SQLite instead of clearing the database and rescanning.
+    let legacy_dirs = legacy_index_dirs(&opts.data_dir);
+    let migrate_legacy = !opts.force_rebuild
+        && !index_path.join("meta.json").exists()
+        && !legacy_dirs.is_empty()
+        && !storage.list_conversations(1, 0)?.is_empty();
     let needs_rebuild = opts.force_rebuild
-        || !index_path.join("meta.json").exists()
+        || (!index_path.join("meta.json").exists() && !migrate_legacy)
         || (index_path.join("schema_hash.json").exists() && !schema_matches);
 
+    // Changing `[analyzers]` only invalidates the Tantivy index; the database already
+    // holds every message, so the index is repopulated from it like a legacy migration.
+    let analyzers = AnalyzerConfig::load();
+    let reanalyze = !needs_rebuild
+        && index_path.join("meta.json").exists()
+        && tantivy::Index::open_in_dir(&index_path)
+            .ok()
+            .and_then(|idx| AnalyzerConfig::of_schema(&idx.schema()))
+            .is_some_and(|built| built != analyzers);
+
+    // A damaged index whose schema hash still matches gets recreated empty below,
+    // while unchanged conversations are skipped by content hash; refill it from the DB.
+    let corrupt = !needs_rebuild
+        && crate::search::tantivy::corruption(&index_path)
+            .inspect(|e| tracing::warn!(kind = "index-corrupt", error = %e, "search index unreadable; rebuilding it from the database"))
+            .is_some();
+
     if needs_rebuild && let Some(p) = &opts.progress {
         p.is_rebuilding.store(true, Ordering::Relaxed);
     }
 
-    let mut t_index = if needs_rebuild {
+    // Opening an index built with other analyzers fails, so clear it first
+    if needs_rebuild || reanalyze {
         std::fs::remove_dir_all(&index_path).ok();
-        TantivyIndex::open_or_create(&index_path)?
-    } else {
-        TantivyIndex::open_or_create(&index_path)?
-    };
+    }
+    let mut t_index = TantivyIndex::open_or_create_with(&index_path, analyzers)?;
+
+    // A fresh Tantivy index is refilled from the database, which keeps tags, notes and
+    // annotations; the full rescan below then relies on per-conversation content hashes
+    // to skip anything that hasn't changed since the last run.
+    if needs_rebuild {
+        let reindexed = persist::reindex_from_storage(&storage, &mut t_index)?;
+        t_index.commit()?;
+        tracing::info!(
+            conversations = reindexed,
+            "rebuilt search index from the database"
+        );
+    }
+
+    if migrate_legacy {
+        let migrated = persist::reindex_from_storage(&storage, &mut t_index)?;
+        t_index.commit()?;
+        for dir in &legacy_dirs {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                tracing::warn!(dir = %dir.display(), "failed to remove legacy index: {e}");
+            }
+        }
+        tracing::info!(
+            conversations = migrated,
+            from = ?legacy_dirs,
+            "migrated legacy search index from the database"
+        );
+    }
+
+    if reanalyze || corrupt {
+        let reindexed = persist::reindex_from_storage(&storage, &mut t_index)?;
+        t_index.commit()?;
+        tracing::info!(
+            conversations = reindexed,
+            ?analyzers,
+            corrupt,
+            "rebuilt search index from the database"
+        );
+    }
 
-    if opts.full {
-        reset_storage(&mut storage)?;
-        t_index.delete_all()?;
+    // Older indexes tagged every document as local, which broke `--source` for remotes
+    if !storage.provenance_indexed()? {
+        if !needs_rebuild && !reanalyze && !corrupt && !migrate_legacy {
+            let reindexed = persist::reindex_remote_conversations(&storage, &mut t_index)?;
+            t_index.commit()?;
+            tracing::info!(
+                conversations = reindexed,
+                "re-indexed remote conversations with their source provenance"
+            );
+        }
+        storage.set_provenance_indexed()?;
     }
 
     // Get last scan timestamp for incremental indexing.
@@ -144,8 +342,12 @@ pub fn run_index(
     // Run connector detection and scanning in parallel using rayon
     use rayon::prelude::*;
 
+    // Encrypted mirrors are readable only while they are scanned
+    let staged_plaintext = staging::decrypt_mirrors(&opts.data_dir);
+
     let progress_ref = opts.progress.as_ref();
     let data_dir = opts.data_dir.clone();
+    let scan_window = window::configured_window();
 
     let connector_factories = get_connector_factories();
 
@@ -167,7 +369,8 @@ pub fn run_index(
                     }
                 }
 
-                let ctx = crate::connectors::ScanContext::local_default(data_dir.clone(), since_ts);
+                let ctx = crate::connectors::ScanContext::local_default(data_dir.clone(), since_ts)
+                    .windowed(&scan_window);
                 match conn.scan(&ctx) {
                     Ok(mut local_convs) => {
                         let local_origin = Origin::local();
@@ -186,27 +389,13 @@ pub fn run_index(
 
             if !remote_roots.is_empty() {
                 for root in &remote_roots {
-                    let ctx = crate::connectors::ScanContext::with_roots(
-                        root.path.clone(),
-                        vec![root.clone()],
-                        None,
-                    );
-                    match conn.scan(&ctx) {
-                        Ok(mut remote_convs) => {
-                            for conv in &mut remote_convs {
-                                inject_provenance(conv, &root.origin);
-                                apply_workspace_rewrite(conv, &root.workspace_rewrites);
-                            }
-                            convs.extend(remote_convs);
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                connector = name,
-                                root = %root.path.display(),
-                                "remote scan failed: {e}"
-                            );
-                        }
-                    }
+                    convs.extend(scan_remote_root(
+                        conn.as_ref(),
+                        name,
+                        root,
+                        &staged_plaintext,
+                        &scan_window,
+                    ));
                 }
             }
 
@@ -232,24 +421,89 @@ pub fn run_index(
             Some((name, convs))
         })
         .collect();
+    drop(staged_plaintext);
 
     if let Some(p) = &opts.progress {
         p.phase.store(2, Ordering::Relaxed); // Indexing
     }
 
-    for (name, convs) in pending_batches {
-        ingest_batch(&mut storage, &mut t_index, &convs, &opts.progress)?;
+    let scanned_conversations: usize = pending_batches.iter().map(|(_, c)| c.len()).sum();
+    let mut redactions = RedactionStats::default();
+    for (name, mut convs) in pending_batches {
+        let batch_redactions =
+            ingest_batch(&mut storage, &mut t_index, &mut convs, &opts.progress)?;
         tracing::info!(
             connector = name,
             conversations = convs.len(),
+            redacted = batch_redactions.total(),
             "connector_ingest"
         );
+        redactions.merge(&batch_redactions);
+    }
+
+    if opts.gc {
+        gc_missing_sources(&mut storage, &mut t_index)?;
     }
 
     t_index.commit()?;
+    mark_committed(&opts.progress);
+
+    // Keep the database + index within the configured storage budget
+    let budget_prune = match StorageBudget::load() {
+        Some(budget) => enforce_budget(&mut storage, &mut t_index, &index_path, &budget)?,
+        None => None,
+    };
+    if budget_prune.is_some() {
+        t_index.commit()?;
+    }
+    storage.set_last_budget_prune(&serde_json::to_value(&budget_prune)?)?;
+
+    // Drop sessions that fell out of the configured retention window
+    let retention_pruned = match retention::configured_retention() {
+        Some(window) => match retention::parse_age(&window) {
+            Some(age) => {
+                let prune = retention::PruneOptions {
+                    cutoff_ms: chrono::Utc::now().timestamp_millis() - age,
+                    ..Default::default()
+                };
+                retention::prune_sessions(&mut storage, &mut t_index, &opts.data_dir, &prune)?
+                    .conversations
+            }
+            None => {
+                tracing::warn!(retention = %window, "ignoring retention: unrecognized age");
+                0
+            }
+        },
+        None => 0,
+    };
+
+    // Daily size sample for `cass stats storage` growth history
+    if let Err(e) = storage.record_storage_sample() {
+        tracing::warn!(error = %e, "failed to record storage sample");
+    }
 
     // Update last_scan_ts after successful scan and commit
     storage.set_last_scan_ts(scan_start_ts)?;
+    storage.set_last_redactions(&redactions.counts)?;
+    if redactions.total() > 0 {
+        tracing::info!(
+            total = redactions.total(),
+            by_rule = ?redactions.counts,
+            "redacted secrets before persistence"
+        );
+    }
+    HooksConfig::load().fire(
+        HookEvent::PostIndex,
+        serde_json::json!({
+            "full": opts.full,
+            "rebuild": needs_rebuild,
+            "conversations_scanned": scanned_conversations,
+            "redactions": redactions,
+            "budget_pruned": budget_prune.as_ref().map_or(0, |r| r.removed.len()),
+            "retention_pruned": retention_pruned,
+            "data_dir": opts.data_dir,
+        }),
+    );
     tracing::info!(
         scan_start_ts,
         "updated last_scan_ts for incremental indexing"
@@ -268,11 +522,22 @@ pub fn run_index(
 
         // Detect roots once for the watcher setup
         let watch_roots = detect_watch_roots();
+        let delete_storage = storage.clone();
+        let delete_index = t_index.clone();
 
         watch_sources(
             opts.watch_once_paths.clone(),
             watch_roots.clone(),
             event_channel,
+            move |sessions| {
+                let mut storage = delete_storage
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("storage lock poisoned"))?;
+                let mut t_index = delete_index
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("index lock poisoned"))?;
+                delete_sessions(&mut storage, &mut t_index, sessions)
+            },
             move |paths, roots, is_rebuild| {
                 if is_rebuild {
                     if let Ok(mut g) = state.lock() {
@@ -282,7 +547,8 @@ pub fn run_index(
                     // For rebuild, trigger reindex on all active roots
                     let all_root_paths: Vec<PathBuf> =
                         roots.iter().map(|(_, p)| p.clone()).collect();
-                    let _ = reindex_paths(
+                    let started = Instant::now();
+                    let local = reindex_paths(
                         &opts_clone,
                         all_root_paths,
                         roots,
@@ -291,8 +557,17 @@ pub fn run_index(
                         t_index.clone(),
                         true,
                     );
+                    // Remote mirrors aren't watched; a full pass is when they get picked up.
+                    let remote =
+                        reindex_remote_roots(&opts_clone, storage.clone(), t_index.clone());
+                    if let Err(e) = &remote {
+                        tracing::warn!("remote reconciliation failed: {e}");
+                    }
+                    let result = local.and(remote);
+                    record_watch_pass(&opts_clone, started, &result);
                 } else {
-                    let _ = reindex_paths(
+                    let started = Instant::now();
+                    let result = reindex_paths(
                         &opts_clone,
                         paths,
                         roots,
@@ -301,6 +576,10 @@ pub fn run_index(
                         t_index.clone(),
                         false,
                     );
+                    if let Err(e) = &result {
+                        tracing::warn!("watch reindex failed: {e}");
+                    }
+                    record_watch_pass(&opts_clone, started, &result);
                 }
             },
         )?;
@@ -309,19 +588,93 @@ pub fn run_index(
     Ok(())
 }
 
+/// Recreate the search index from the conversations already in the database
+/// (`cass index --rebuild`), without rescanning sources. Returns how many
+/// conversations were indexed.
+pub fn rebuild_search_index(data_dir: &Path, db_path: &Path) -> Result<usize> {
+    let _lock = lock::IndexLock::acquire(data_dir)?;
+    let storage = SqliteStorage::open(db_path)?;
+    let index_path = index_dir(data_dir)?;
+    std::fs::remove_dir_all(&index_path).ok();
+    let mut t_index = TantivyIndex::open_or_create(&index_path)?;
+    let reindexed = persist::reindex_from_storage(&storage, &mut t_index)?;
+    t_index.commit()?;
+    tracing::info!(
+        conversations = reindexed,
+        "rebuilt search index from the database"
+    );
+    Ok(reindexed)
+}
+
+/// Redact and persist a batch of conversations, returning what was masked.
+/// Conversations matching the configured `exclude` patterns or outside the
+/// `[index]` scan window are skipped.
 fn ingest_batch(
     storage: &mut SqliteStorage,
     t_index: &mut TantivyIndex,
-    convs: &[NormalizedConversation],
+    convs: &mut [NormalizedConversation],
     progress: &Option<Arc<IndexingProgress>>,
-) -> Result<()> {
-    for conv in convs {
-        persist::persist_conversation(storage, t_index, conv)?;
+) -> Result<RedactionStats> {
+    let hooks = HooksConfig::load();
+    let redactor = Redactor::load();
+    let excludes = ExcludeRules::load();
+    let scan_window = window::configured_window();
+    let classifiers = classify::Classifiers::load();
+    let mut redactions = RedactionStats::default();
+    let mut excluded = 0usize;
+    let mut out_of_window = 0usize;
+    let mut kept: Vec<&mut NormalizedConversation> = Vec::with_capacity(convs.len());
+    for conv in convs.iter_mut() {
+        let in_window = scan_window.contains(conv);
+        if !in_window || excludes.excludes(conv) {
+            if in_window {
+                excluded += 1;
+            } else {
+                out_of_window += 1;
+            }
+            if let Some(p) = progress {
+                p.current.fetch_add(1, Ordering::Relaxed);
+            }
+            continue;
+        }
+        redactions.merge(&redactor.redact_conversation(conv));
+        kept.push(conv);
+    }
+    // Once per batch, so command classifiers start one process rather than one each
+    classifiers.apply(&mut kept);
+    for conv in kept {
+        let created = persist::persist_conversation(storage, t_index, conv)?;
+        if created && let Some(p) = progress {
+            p.added.fetch_add(1, Ordering::Relaxed);
+        }
+        if created && hooks.has(HookEvent::NewConversation) {
+            hooks.fire(
+                HookEvent::NewConversation,
+                serde_json::json!({
+                    "agent": conv.agent_slug,
+                    "external_id": conv.external_id,
+                    "title": conv.title,
+                    "workspace": conv.workspace,
+                    "source_path": conv.source_path,
+                    "started_at": conv.started_at,
+                    "messages": conv.messages.len(),
+                }),
+            );
+        }
         if let Some(p) = progress {
             p.current.fetch_add(1, Ordering::Relaxed);
         }
     }
-    Ok(())
+    if excluded > 0 {
+        tracing::info!(excluded, "skipped conversations matching exclude patterns");
+    }
+    if out_of_window > 0 {
+        tracing::info!(
+            out_of_window,
+            "skipped conversations outside the scan window"
+        );
+    }
+    Ok(redactions)
 }
 
 /// Get all available connector factories.
@@ -338,6 +691,7 @@ pub fn get_connector_factories() -> Vec<(&'static str, fn() -> Box<dyn Connector
         ("cursor", || Box::new(CursorConnector::new())),
         ("chatgpt", || Box::new(ChatGptConnector::new())),
         ("pi_agent", || Box::new(PiAgentConnector::new())),
+        ("cass", || Box::new(CassConnector::new())),
     ]
 }
 
@@ -378,12 +732,17 @@ impl ConnectorKind {
     }
 }
 
-fn watch_sources<F: Fn(Vec<PathBuf>, &[(ConnectorKind, PathBuf)], bool) + Send + 'static>(
+fn watch_sources<F, D>(
     watch_once_paths: Option<Vec<PathBuf>>,
     roots: Vec<(ConnectorKind, PathBuf)>,
     event_channel: Option<(Sender<IndexerEvent>, Receiver<IndexerEvent>)>,
+    delete: D,
     callback: F,
-) -> Result<()> {
+) -> Result<()>
+where
+    F: Fn(Vec<PathBuf>, &[(ConnectorKind, PathBuf)], bool) + Send + 'static,
+    D: Fn(&[(String, String)]) -> Result<(usize, usize)> + Send + 'static,
+{
     if let Some(paths) = watch_once_paths {
         if !paths.is_empty() {
             callback(paths, &roots, false);
@@ -426,6 +785,9 @@ fn watch_sources<F: Fn(Vec<PathBuf>, &[(ConnectorKind, PathBuf)], bool) + Send +
                         ReindexCommand::Full => {
                             callback(vec![], &roots, true);
                         }
+                        ReindexCommand::DeleteSessions { sessions, reply } => {
+                            let _ = reply.send(delete(&sessions).map_err(|e| e.to_string()));
+                        }
                     },
                 },
                 Err(_) => break, // Channel closed
@@ -455,6 +817,9 @@ fn watch_sources<F: Fn(Vec<PathBuf>, &[(ConnectorKind, PathBuf)], bool) + Send +
                             callback(vec![], &roots, true);
                             first_event = None; // Reset debounce
                         }
+                        ReindexCommand::DeleteSessions { sessions, reply } => {
+                            let _ = reply.send(delete(&sessions).map_err(|e| e.to_string()));
+                        }
                     },
                 },
                 Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
@@ -468,24 +833,112 @@ fn watch_sources<F: Fn(Vec<PathBuf>, &[(ConnectorKind, PathBuf)], bool) + Send +
     Ok(())
 }
 
-fn reset_storage(storage: &mut SqliteStorage) -> Result<()> {
-    // Wrap in transaction to ensure atomic reset - if any DELETE fails,
-    // all changes are rolled back to prevent inconsistent state
-    storage.raw().execute_batch(
-        "BEGIN TRANSACTION;
-         DELETE FROM fts_messages;
-         DELETE FROM snippets;
-         DELETE FROM messages;
-         DELETE FROM conversations;
-         DELETE FROM agents;
-         DELETE FROM workspaces;
-         DELETE FROM tags;
-         DELETE FROM conversation_tags;
-         COMMIT;",
-    )?;
+/// Delete conversations (SQLite rows and Tantivy docs) whose source file has been
+/// removed from disk, or whose source file or workspace now matches an `exclude`
+/// pattern. Missing files are only checked for local conversations, since remote
+/// source paths refer to the remote host and are pruned via `cass sources`;
+/// exclude patterns apply to every source. Locked conversations are always kept.
+fn gc_missing_sources(storage: &mut SqliteStorage, t_index: &mut TantivyIndex) -> Result<usize> {
+    let excludes = ExcludeRules::load();
+    let workspaces: HashMap<i64, String> = if excludes.is_empty() {
+        HashMap::new()
+    } else {
+        storage
+            .list_conversation_workspaces()?
+            .into_iter()
+            .collect()
+    };
+    // Locked conversations are kept even after their source file disappears.
+    let retained: std::collections::HashSet<(String, String)> = storage
+        .list_retained()?
+        .into_iter()
+        .map(|(source_id, source_path, _)| (source_id, source_path))
+        .collect();
+    let mut stale_ids = Vec::new();
+    let mut stale_paths = std::collections::HashSet::new();
+    for (id, source_id, source_path) in storage.list_conversation_source_paths()? {
+        let missing = source_id == LOCAL_SOURCE_ID && !Path::new(&source_path).exists();
+        let excluded = !excludes.is_empty()
+            && (excludes.is_excluded(Path::new(&source_path))
+                || workspaces
+                    .get(&id)
+                    .is_some_and(|w| excludes.is_excluded(Path::new(w))));
+        if !missing && !excluded {
+            continue;
+        }
+        if retained.contains(&(source_id, source_path.clone())) {
+            continue;
+        }
+        stale_ids.push(id);
+        stale_paths.insert(source_path);
+    }
+    if stale_ids.is_empty() {
+        return Ok(0);
+    }
+
+    delete_conversation_docs(storage, t_index, &stale_ids)?;
+    let removed = storage.delete_conversations(&stale_ids)?;
+    tracing::info!(
+        removed,
+        files = stale_paths.len(),
+        "gc: pruned conversations with missing or excluded source files"
+    );
+    Ok(removed)
+}
+
+/// Delete the search index documents of conversations about to be removed from
+/// the database. By doc key rather than source path, so another session in the
+/// same file, or the same path from another source, keeps its documents.
+pub(crate) fn delete_conversation_docs(
+    storage: &SqliteStorage,
+    t_index: &mut TantivyIndex,
+    ids: &[i64],
+) -> Result<()> {
+    for (source_id, agent, external_id, source_path) in storage.conversation_identities(ids)? {
+        t_index.delete_doc_key(&crate::search::tantivy::stored_doc_key(
+            &source_id,
+            &agent,
+            external_id.as_deref(),
+            &source_path,
+        ));
+    }
     Ok(())
 }
 
+/// Remove whole sessions, identified like search hits by `(source_id, source_path)`,
+/// from the database and the search index. Locked sessions are skipped. Returns
+/// `(conversations removed, sessions skipped because they are locked)`.
+///
+/// The source files are left alone; the deleted conversations are recorded so
+/// rescans skip them until their file changes or
+/// [`SqliteStorage::forget_deleted_sessions`] restores them.
+pub fn delete_sessions(
+    storage: &mut SqliteStorage,
+    t_index: &mut TantivyIndex,
+    sessions: &[(String, String)],
+) -> Result<(usize, usize)> {
+    let retained: std::collections::HashSet<(String, String)> = storage
+        .list_retained()?
+        .into_iter()
+        .map(|(source_id, source_path, _)| (source_id, source_path))
+        .collect();
+    let mut ids = Vec::new();
+    let mut locked = 0;
+    for session in sessions {
+        if retained.contains(session) {
+            locked += 1;
+            continue;
+        }
+        ids.extend(storage.session_conversation_ids(&session.0, &session.1)?);
+    }
+    delete_conversation_docs(storage, t_index, &ids)?;
+    storage.record_deleted_conversations(&ids, "delete")?;
+    let removed = storage.delete_conversations(&ids)?;
+    t_index.commit()?;
+    tracing::info!(removed, locked, "deleted sessions");
+    Ok((removed, locked))
+}
+
 fn reindex_paths(
     opts: &IndexOptions,
     paths: Vec<PathBuf>,
@@ -538,7 +991,8 @@ fn reindex_paths(
                 .or_else(|| ts.map(|v| v.saturating_sub(1)))
                 .map(|v| v.saturating_sub(1))
         };
-        let ctx = crate::connectors::ScanContext::local_default(opts.data_dir.clone(), since_ts);
+        let ctx = crate::connectors::ScanContext::local_default(opts.data_dir.clone(), since_ts)
+            .windowed(&window::configured_window());
 
         // SCAN PHASE: IO-heavy, no locks held
         let mut convs = conn.scan(&ctx)?;
@@ -566,10 +1020,34 @@ fn reindex_paths(
                 .lock()
                 .map_err(|_| anyhow::anyhow!("index lock poisoned"))?;
 
-            ingest_batch(&mut storage, &mut t_index, &convs, &opts.progress)?;
+            let redactions = ingest_batch(&mut storage, &mut t_index, &mut convs, &opts.progress)?;
+            if redactions.total() > 0 {
+                tracing::info!(?kind, total = redactions.total(), "watch_redacted");
+            }
 
             // Commit to Tantivy immediately to ensure index consistency before advancing watch state.
             t_index.commit()?;
+            mark_committed(&opts.progress);
+        }
+        if !convs.is_empty() {
+            HooksConfig::load().fire(
+                HookEvent::PostIndex,
+                serde_json::json!({
+                    "watch": true,
+                    "connector": format!("{kind:?}"),
+                    "conversations_scanned": convs.len(),
+                    "data_dir": opts.data_dir,
+                }),
+            );
+        }
+
+        if let Some(p) = &opts.progress
+            && let Ok(mut metrics) = p.watch.lock()
+        {
+            let lag = metrics.connectors.entry(format!("{kind:?}")).or_default();
+            lag.last_change_at = ts.or(lag.last_change_at);
+            lag.last_indexed_at = Some(chrono::Utc::now().timestamp_millis());
+            lag.conversations_indexed += convs.len() as u64;
         }
 
         if let Some(ts_val) = ts {
@@ -590,6 +1068,92 @@ fn reindex_paths(
     Ok(())
 }
 
+/// Scan one remote mirror root with `conn`. Encrypted mirrors are scanned
+/// through their decrypted copy, and the results point back at the mirror.
+fn scan_remote_root(
+    conn: &dyn Connector,
+    name: &str,
+    root: &ScanRoot,
+    plaintext: &staging::Plaintext,
+    scan_window: &crate::connectors::ScanWindow,
+) -> Vec<NormalizedConversation> {
+    let scan_root = plaintext.scan_root(root);
+    let ctx =
+        crate::connectors::ScanContext::with_roots(scan_root.path.clone(), vec![scan_root], None)
+            .windowed(scan_window);
+    match conn.scan(&ctx) {
+        Ok(mut convs) => {
+            for conv in &mut convs {
+                plaintext.restore_paths(conv);
+                inject_provenance(conv, &root.origin);
+                apply_workspace_rewrite(conv, &root.workspace_rewrites);
+            }
+            convs
+        }
+        Err(e) => {
+            tracing::warn!(
+                connector = name,
+                root = %root.path.display(),
+                "remote scan failed: {e}"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Rescan synced remote mirrors (`remotes/<source>/mirror`) with every connector.
+fn reindex_remote_roots(
+    opts: &IndexOptions,
+    storage: Arc<Mutex<SqliteStorage>>,
+    t_index: Arc<Mutex<TantivyIndex>>,
+) -> Result<()> {
+    let remote_roots: Vec<ScanRoot> = {
+        let storage = storage
+            .lock()
+            .map_err(|_| anyhow::anyhow!("storage lock poisoned"))?;
+        build_scan_roots(&storage, &opts.data_dir)
+            .into_iter()
+            .filter(|r| r.origin.is_remote())
+            .collect()
+    };
+    if remote_roots.is_empty() {
+        return Ok(());
+    }
+
+    let scan_window = window::configured_window();
+    let staged_plaintext = staging::decrypt_mirrors(&opts.data_dir);
+    let mut convs = Vec::new();
+    for (name, factory) in get_connector_factories() {
+        let conn = factory();
+        for root in &remote_roots {
+            convs.extend(scan_remote_root(
+                conn.as_ref(),
+                name,
+                root,
+                &staged_plaintext,
+                &scan_window,
+            ));
+        }
+    }
+    drop(staged_plaintext);
+
+    let mut storage = storage
+        .lock()
+        .map_err(|_| anyhow::anyhow!("storage lock poisoned"))?;
+    let mut t_index = t_index
+        .lock()
+        .map_err(|_| anyhow::anyhow!("index lock poisoned"))?;
+    let redactions = ingest_batch(&mut storage, &mut t_index, &mut convs, &opts.progress)?;
+    t_index.commit()?;
+    mark_committed(&opts.progress);
+    tracing::info!(
+        conversations = convs.len(),
+        redacted = redactions.total(),
+        "remote_reconcile"
+    );
+    Ok(())
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum ConnectorKind {
     Codex,
@@ -688,7 +1252,7 @@ fn sync_sources_config_to_db(storage: &SqliteStorage) {
         let record = Source {
             id: source.name.clone(),
             kind: source.source_type,
-            host_label: source.host.clone(),
+            host_label: source.host.clone().or_else(|| source.object_store_root()),
             machine_id: None,
             platform,
             config_json: Some(config_json),
@@ -735,6 +1299,20 @@ pub fn build_scan_roots(storage: &SqliteStorage, data_dir: &Path) -> Vec<ScanRoo
                 let platform = source.platform;
                 let workspace_rewrites = source.path_mappings.clone();
 
+                if source.source_type == SourceKind::Cass {
+                    let mirror_path = data_dir
+                        .join("remotes")
+                        .join(&source.name)
+                        .join("mirror")
+                        .join("cass");
+                    if mirror_path.exists() {
+                        let mut scan_root = ScanRoot::remote(mirror_path, origin, platform);
+                        scan_root.workspace_rewrites = workspace_rewrites;
+                        roots.push(scan_root);
+                    }
+                    continue;
+                }
+
                 for path in &source.paths {
                     let expanded_path = if path.starts_with("~/") {
                         path.to_string()
@@ -970,8 +1548,11 @@ pub fn apply_workspace_rewrite(
 pub mod persist {
     use anyhow::Result;
 
-    use crate::connectors::NormalizedConversation;
-    use crate::model::types::{Agent, AgentKind, Conversation, Message, MessageRole, Snippet};
+    use crate::connectors::{NormalizedConversation, NormalizedMessage};
+    use crate::indexer::dedupe::{CopyRelation, message_hashes, relate_copies};
+    use crate::model::types::{
+        Agent, AgentKind, Conversation, MergedDuplicate, Message, MessageRole, Snippet,
+    };
     use crate::search::tantivy::TantivyIndex;
     use crate::storage::sqlite::{InsertOutcome, SqliteStorage};
 
@@ -998,6 +1579,60 @@ pub mod persist {
         (source_id, origin_host)
     }
 
+    /// Stable SHA256 over the normalized conversation (identity fields and every message).
+    ///
+    /// Provenance metadata is excluded so re-scanning the same file yields the same hash;
+    /// the source itself is part of the lookup key instead.
+    pub fn conversation_content_hash(conv: &NormalizedConversation) -> String {
+        use ring::digest::{Context, SHA256};
+
+        fn field(ctx: &mut Context, value: Option<&str>) {
+            if let Some(v) = value {
+                ctx.update(v.as_bytes());
+            }
+            ctx.update(&[0x1f]);
+        }
+
+        let mut ctx = Context::new(&SHA256);
+        field(&mut ctx, Some(&conv.agent_slug));
+        field(&mut ctx, conv.external_id.as_deref());
+        field(&mut ctx, conv.title.as_deref());
+        field(
+            &mut ctx,
+            conv.workspace
+                .as_ref()
+                .map(|w| w.to_string_lossy())
+                .as_deref(),
+        );
+        field(&mut ctx, Some(&conv.source_path.to_string_lossy()));
+        field(&mut ctx, conv.started_at.map(|t| t.to_string()).as_deref());
+        field(&mut ctx, conv.ended_at.map(|t| t.to_string()).as_deref());
+        // Classifier output, so changed classifiers relabel unchanged sessions
+        field(
+            &mut ctx,
+            conv.metadata
+                .pointer("/cass/labels")
+                .map(|v| v.to_string())
+                .as_deref(),
+        );
+        for msg in &conv.messages {
+            ctx.update(&[0x1e]);
+            field(&mut ctx, Some(&msg.idx.to_string()));
+            field(&mut ctx, Some(&msg.role));
+            field(&mut ctx, msg.author.as_deref());
+            field(&mut ctx, msg.created_at.map(|t| t.to_string()).as_deref());
+            field(&mut ctx, Some(&msg.content));
+            for snip in &msg.snippets {
+                field(&mut ctx, snip.snippet_text.as_deref());
+            }
+        }
+        ctx.finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
     /// Convert a NormalizedConversation to the internal Conversation type for SQLite storage.
     ///
     /// Extracts provenance from `metadata.cass.origin` if present, otherwise defaults to local.
@@ -1005,6 +1640,13 @@ pub mod persist {
         // Extract provenance from metadata (P2.2)
         let (source_id, origin_host) = extract_provenance(&conv.metadata);
 
+        let message_tokens: Vec<i64> = conv
+            .messages
+            .iter()
+            .map(|m| super::tokens::count_tokens(&m.content))
+            .collect();
+        let message_snippets = crate::connectors::conversation_snippets(&conv.messages);
+
         Conversation {
             id: None,
             agent_slug: conv.agent_slug.clone(),
@@ -1014,12 +1656,14 @@ pub mod persist {
             source_path: conv.source_path.clone(),
             started_at: conv.started_at,
             ended_at: conv.ended_at,
-            approx_tokens: None,
+            approx_tokens: Some(message_tokens.iter().sum()),
             metadata_json: conv.metadata.clone(),
             messages: conv
                 .messages
                 .iter()
-                .map(|m| Message {
+                .zip(&message_tokens)
+                .zip(message_snippets)
+                .map(|((m, &tokens), snippets)| Message {
                     id: None,
                     idx: m.idx,
                     role: map_role(&m.role),
@@ -1027,18 +1671,25 @@ pub mod persist {
                     created_at: m.created_at,
                     content: m.content.clone(),
                     extra_json: m.extra.clone(),
-                    snippets: m
-                        .snippets
-                        .iter()
+                    snippets: snippets
+                        .into_iter()
                         .map(|s| Snippet {
                             id: None,
-                            file_path: s.file_path.clone(),
+                            file_path: s.file_path,
                             start_line: s.start_line,
                             end_line: s.end_line,
-                            language: s.language.clone(),
-                            snippet_text: s.snippet_text.clone(),
+                            language: s.language,
+                            snippet_text: s.snippet_text,
                         })
                         .collect(),
+                    approx_tokens: Some(tokens),
+                    // Only store the path when it differs from the conversation's
+                    source_path: m
+                        .source
+                        .as_ref()
+                        .filter(|src| src.path != conv.source_path)
+                        .map(|src| src.path.to_string_lossy().into_owned()),
+                    source_line: m.source.as_ref().and_then(|src| src.line),
                 })
                 .collect(),
             source_id,
@@ -1046,11 +1697,13 @@ pub mod persist {
         }
     }
 
+    /// Store a conversation and index its new messages.
+    /// Returns true if the conversation was not previously stored.
     pub fn persist_conversation(
         storage: &mut SqliteStorage,
         t_index: &mut TantivyIndex,
         conv: &NormalizedConversation,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         tracing::info!(agent = %conv.agent_slug, messages = conv.messages.len(), "persist_conversation");
         let agent = Agent {
             id: None,
@@ -1062,6 +1715,9 @@ pub mod persist {
         let agent_id = storage.ensure_agent(&agent)?;
 
         let workspace_id = if let Some(ws) = &conv.workspace {
+            if let Err(e) = super::projects::ensure_workspace_project(storage, ws) {
+                tracing::warn!(workspace = %ws.display(), "project detection failed: {e}");
+            }
             Some(storage.ensure_workspace(ws, None)?)
         } else {
             None
@@ -1069,13 +1725,102 @@ pub mod persist {
 
         let internal_conv = map_to_internal(conv);
 
+        // Unchanged since the last index run: skip both SQLite and Tantivy writes.
+        let content_hash = conversation_content_hash(conv);
+        if storage.has_content_hash(&internal_conv.source_id, agent_id, &content_hash)? {
+            tracing::debug!(path = %conv.source_path.display(), "persist_conversation: unchanged, skipping");
+            return Ok(false);
+        }
+
+        // Another source already holds this session in full: drop this copy
+        let source_path = conv.source_path.to_string_lossy();
+        if storage.is_merged_duplicate(&internal_conv.source_id, &source_path, &content_hash)? {
+            tracing::debug!(path = %source_path, "persist_conversation: known duplicate copy, skipping");
+            return Ok(false);
+        }
+        if storage.is_deleted_conversation(&internal_conv.source_id, &source_path, &content_hash)? {
+            tracing::debug!(path = %source_path, "persist_conversation: deleted conversation, skipping");
+            return Ok(false);
+        }
+        let superseded = match &conv.external_id {
+            Some(ext) => match session_copies(storage, agent_id, ext, &internal_conv)? {
+                SessionCopies::Kept {
+                    source_id,
+                    source_path: kept_path,
+                } => {
+                    storage.record_merged_duplicate(
+                        &MergedDuplicate {
+                            source_id: internal_conv.source_id.clone(),
+                            source_path: source_path.to_string(),
+                            agent: conv.agent_slug.clone(),
+                            external_id: ext.clone(),
+                            kept_source_id: source_id,
+                            kept_source_path: kept_path,
+                            messages: conv.messages.len() as i64,
+                            merged_at: SqliteStorage::now_millis(),
+                        },
+                        &content_hash,
+                    )?;
+                    tracing::info!(path = %source_path, "persist_conversation: session already stored from another source");
+                    return Ok(false);
+                }
+                SessionCopies::Superseded(copies) => copies,
+            },
+            None => Vec::new(),
+        };
+
         let InsertOutcome {
-            conversation_id: _,
+            conversation_id,
             inserted_indices,
+            created,
+            ..
         } = storage.insert_conversation_tree(agent_id, workspace_id, &internal_conv)?;
+        storage.set_content_hash(conversation_id, &content_hash)?;
+
+        // This copy continues ones stored from other sources: keep it in their place
+        if let Some(ext) = &conv.external_id {
+            // It may have been dropped in favour of one of them before
+            storage.forget_merged_duplicate(
+                &internal_conv.source_id,
+                &source_path,
+                &conv.agent_slug,
+                ext,
+            )?;
+            for copy in superseded {
+                storage.merge_conversation_metadata(conversation_id, &[copy.id])?;
+                storage.delete_conversations(&[copy.id])?;
+                t_index.delete_session(&copy.source_id, &conv.agent_slug, ext);
+                storage.record_merged_duplicate(
+                    &MergedDuplicate {
+                        source_id: copy.source_id,
+                        source_path: copy.source_path,
+                        agent: conv.agent_slug.clone(),
+                        external_id: ext.clone(),
+                        kept_source_id: internal_conv.source_id.clone(),
+                        kept_source_path: source_path.to_string(),
+                        messages: copy.messages as i64,
+                        merged_at: SqliteStorage::now_millis(),
+                    },
+                    copy.content_hash.as_deref().unwrap_or_default(),
+                )?;
+            }
+        }
 
-        // Only add newly inserted messages to the Tantivy index (incremental)
-        if !inserted_indices.is_empty() {
+        // A changed conversation that was already stored is replaced wholesale (by doc key),
+        // so edited or removed messages don't linger in the index
+        if !created {
+            // Re-scans never change the stored workspace, which `cass update` may have moved
+            let stored_workspace = storage
+                .find_conversation(conversation_id)?
+                .and_then(|c| c.workspace);
+            if stored_workspace != conv.workspace {
+                let mut moved = conv.clone();
+                moved.workspace = stored_workspace;
+                t_index.replace_conversation(&moved)?;
+            } else {
+                t_index.replace_conversation(conv)?;
+            }
+        } else if !inserted_indices.is_empty() {
             let new_msgs: Vec<_> = conv
                 .messages
                 .iter()
@@ -1084,7 +1829,205 @@ pub mod persist {
                 .collect();
             t_index.add_messages(conv, &new_msgs)?;
         }
-        Ok(())
+        Ok(created)
+    }
+
+    /// A stored copy of a session that an incoming, longer copy replaces
+    struct StoredCopy {
+        id: i64,
+        source_id: String,
+        source_path: String,
+        content_hash: Option<String>,
+        messages: usize,
+    }
+
+    enum SessionCopies {
+        /// A stored copy from this source already has every message
+        Kept {
+            source_id: String,
+            source_path: String,
+        },
+        /// Stored copies (never locked ones) that the incoming copy continues
+        Superseded(Vec<StoredCopy>),
+    }
+
+    fn role_name(role: &MessageRole) -> &str {
+        match role {
+            MessageRole::User => "user",
+            MessageRole::Agent => "agent",
+            MessageRole::Tool => "tool",
+            MessageRole::System => "system",
+            MessageRole::Other(other) => other,
+        }
+    }
+
+    /// Compare `conv` with the copies of its session stored from other sources.
+    fn session_copies(
+        storage: &SqliteStorage,
+        agent_id: i64,
+        external_id: &str,
+        conv: &Conversation,
+    ) -> Result<SessionCopies> {
+        let incoming = message_hashes(
+            conv.messages
+                .iter()
+                .map(|m| (role_name(&m.role), m.content.as_str())),
+        );
+        let mut superseded = Vec::new();
+        for (id, source_id, source_path, content_hash, locked) in
+            storage.session_copies(agent_id, external_id, &conv.source_id)?
+        {
+            let messages = storage.fetch_messages(id)?;
+            let stored = message_hashes(
+                messages
+                    .iter()
+                    .map(|m| (role_name(&m.role), m.content.as_str())),
+            );
+            match relate_copies(&stored, &incoming) {
+                CopyRelation::Covered => {
+                    return Ok(SessionCopies::Kept {
+                        source_id,
+                        source_path,
+                    });
+                }
+                CopyRelation::Richer if !locked => superseded.push(StoredCopy {
+                    id,
+                    source_id,
+                    source_path,
+                    content_hash,
+                    messages: stored.len(),
+                }),
+                _ => {}
+            }
+        }
+        Ok(SessionCopies::Superseded(superseded))
+    }
+
+    /// Re-add every stored conversation to `t_index` (used to migrate older index layouts).
+    /// Returns the number of conversations indexed; the caller commits.
+    pub fn reindex_from_storage(
+        storage: &SqliteStorage,
+        t_index: &mut TantivyIndex,
+    ) -> Result<usize> {
+        const PAGE: i64 = 500;
+        let mut offset = 0;
+        let mut total = 0;
+        loop {
+            let page = storage.list_conversations(PAGE, offset)?;
+            if page.is_empty() {
+                break;
+            }
+            for conv in &page {
+                if add_stored_conversation(storage, t_index, conv, false)? {
+                    total += 1;
+                }
+            }
+            offset += PAGE;
+        }
+        Ok(total)
+    }
+
+    /// Add the given stored conversations to `t_index` (after `cass db import`).
+    /// Returns the number of conversations indexed; the caller commits.
+    pub fn index_stored_conversations(
+        storage: &SqliteStorage,
+        t_index: &mut TantivyIndex,
+        ids: &[i64],
+    ) -> Result<usize> {
+        let mut total = 0;
+        for id in ids {
+            if let Some(conv) = storage.find_conversation(*id)?
+                && add_stored_conversation(storage, t_index, &conv, false)?
+            {
+                total += 1;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Replace the documents of the given stored conversations with their current
+    /// database state (after `cass update`). Returns the number replaced; the caller commits.
+    pub fn replace_stored_conversations(
+        storage: &SqliteStorage,
+        t_index: &mut TantivyIndex,
+        ids: &[i64],
+    ) -> Result<usize> {
+        let mut total = 0;
+        for id in ids {
+            if let Some(conv) = storage.find_conversation(*id)?
+                && add_stored_conversation(storage, t_index, &conv, true)?
+            {
+                total += 1;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Replace the Tantivy documents of every conversation from a remote source.
+    pub fn reindex_remote_conversations(
+        storage: &SqliteStorage,
+        t_index: &mut TantivyIndex,
+    ) -> Result<usize> {
+        let mut total = 0;
+        for (id, source_id, source_path) in storage.list_conversation_source_paths()? {
+            if source_id == crate::sources::provenance::LOCAL_SOURCE_ID {
+                continue;
+            }
+            // Old documents carry the "local" doc key, so match them by path
+            t_index.delete_by_source_path(&source_path);
+            if let Some(conv) = storage.find_conversation(id)?
+                && add_stored_conversation(storage, t_index, &conv, false)?
+            {
+                total += 1;
+            }
+        }
+        Ok(total)
+    }
+
+    fn add_stored_conversation(
+        storage: &SqliteStorage,
+        t_index: &mut TantivyIndex,
+        conv: &Conversation,
+        replace: bool,
+    ) -> Result<bool> {
+        let Some(id) = conv.id else { return Ok(false) };
+        let messages = storage
+            .fetch_messages(id)?
+            .into_iter()
+            .map(|m| NormalizedMessage {
+                idx: m.idx,
+                role: match m.role {
+                    MessageRole::User => "user".to_string(),
+                    MessageRole::Agent => "assistant".to_string(),
+                    MessageRole::Tool => "tool".to_string(),
+                    MessageRole::System => "system".to_string(),
+                    MessageRole::Other(other) => other,
+                },
+                author: m.author,
+                created_at: m.created_at,
+                content: m.content,
+                extra: m.extra_json,
+                snippets: Vec::new(),
+                source: None,
+            })
+            .collect();
+        let normalized = NormalizedConversation {
+            agent_slug: conv.agent_slug.clone(),
+            external_id: conv.external_id.clone(),
+            title: conv.title.clone(),
+            workspace: conv.workspace.clone(),
+            source_path: conv.source_path.clone(),
+            started_at: conv.started_at,
+            ended_at: conv.ended_at,
+            metadata: conv.metadata_json.clone(),
+            messages,
+        };
+        if replace {
+            t_index.replace_conversation(&normalized)?;
+        } else {
+            t_index.add_conversation(&normalized)?;
+        }
+        Ok(true)
     }
 
     fn map_role(role: &str) -> MessageRole {
@@ -1147,6 +2090,7 @@ mod tests {
             content: format!("msg-{idx}"),
             extra: serde_json::json!({}),
             snippets: Vec::new(),
+            source: None,
         }
     }
 
@@ -1168,74 +2112,195 @@ mod tests {
     }
 
     #[test]
-    fn reset_storage_clears_data_but_leaves_meta() {
+    fn persist_append_only_adds_new_messages_to_index() {
         let tmp = TempDir::new().unwrap();
-        let db_path = tmp.path().join("db.sqlite");
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        let db_path = data_dir.join("db.sqlite");
         let mut storage = SqliteStorage::open(&db_path).unwrap();
         ensure_fts_schema(storage.raw());
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
 
-        let agent = crate::model::types::Agent {
-            id: None,
-            slug: "tester".into(),
-            name: "Tester".into(),
-            version: None,
-            kind: crate::model::types::AgentKind::Cli,
-        };
-        let agent_id = storage.ensure_agent(&agent).unwrap();
-        let conv = norm_conv(Some("c1"), vec![norm_msg(0, 10)]);
+        let conv1 = norm_conv(Some("ext"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        persist::persist_conversation(&mut storage, &mut index, &conv1).unwrap();
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        let conv2 = norm_conv(
+            Some("ext"),
+            vec![norm_msg(0, 100), norm_msg(1, 200), norm_msg(2, 300)],
+        );
+        persist::persist_conversation(&mut storage, &mut index, &conv2).unwrap();
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 3);
+    }
+
+    #[test]
+    fn reindex_from_storage_rebuilds_index_without_rescan() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        let db_path = data_dir.join("db.sqlite");
+        let mut storage = SqliteStorage::open(&db_path).unwrap();
+        ensure_fts_schema(storage.raw());
+        let legacy_dir = tmp.path().join("legacy");
+        let mut legacy = TantivyIndex::open_or_create(&legacy_dir).unwrap();
+        let conv = norm_conv(Some("ext"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        persist::persist_conversation(&mut storage, &mut legacy, &conv).unwrap();
+        drop(legacy);
+
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
+        assert_eq!(
+            persist::reindex_from_storage(&storage, &mut index).unwrap(),
+            1
+        );
+        index.commit().unwrap();
+
+        // Re-persisting the same conversation replaces its docs instead of duplicating them
+        let changed = norm_conv(Some("ext"), vec![norm_msg(0, 100), norm_msg(1, 250)]);
+        persist::persist_conversation(&mut storage, &mut index, &changed).unwrap();
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+    }
+
+    #[test]
+    fn persist_skips_conversations_with_unchanged_content_hash() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        let db_path = data_dir.join("db.sqlite");
+        let mut storage = SqliteStorage::open(&db_path).unwrap();
+        ensure_fts_schema(storage.raw());
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
+
+        // No external_id: without the hash check a rescan would insert a duplicate.
+        let conv = norm_conv(None, vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        persist::persist_conversation(&mut storage, &mut index, &conv).unwrap();
+        persist::persist_conversation(&mut storage, &mut index, &conv).unwrap();
+        index.commit().unwrap();
+
+        let conv_count: i64 = storage
+            .raw()
+            .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(conv_count, 1);
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        let mut changed = conv.clone();
+        changed.messages[1].content = "edited".into();
+        assert_ne!(
+            persist::conversation_content_hash(&conv),
+            persist::conversation_content_hash(&changed)
+        );
+    }
+
+    #[test]
+    fn deleted_sessions_stay_deleted_until_restored() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        let db_path = data_dir.join("db.sqlite");
+        let mut storage = SqliteStorage::open(&db_path).unwrap();
+        ensure_fts_schema(storage.raw());
         storage
-            .insert_conversation_tree(
-                agent_id,
-                None,
-                &crate::model::types::Conversation {
-                    id: None,
-                    agent_slug: conv.agent_slug.clone(),
-                    workspace: conv.workspace.clone(),
-                    external_id: conv.external_id.clone(),
-                    title: conv.title.clone(),
-                    source_path: conv.source_path.clone(),
-                    started_at: conv.started_at,
-                    ended_at: conv.ended_at,
-                    approx_tokens: None,
-                    metadata_json: conv.metadata.clone(),
-                    messages: conv
-                        .messages
-                        .iter()
-                        .map(|m| crate::model::types::Message {
-                            id: None,
-                            idx: m.idx,
-                            role: crate::model::types::MessageRole::User,
-                            author: m.author.clone(),
-                            created_at: m.created_at,
-                            content: m.content.clone(),
-                            extra_json: m.extra.clone(),
-                            snippets: Vec::new(),
-                        })
-                        .collect(),
-                    source_id: "local".to_string(),
-                    origin_host: None,
-                },
-            )
+            .upsert_source(&crate::sources::provenance::Source {
+                id: "desk".to_string(),
+                kind: SourceKind::Ssh,
+                host_label: Some("desk.local".to_string()),
+                machine_id: None,
+                platform: None,
+                config_json: None,
+                created_at: None,
+                updated_at: None,
+            })
             .unwrap();
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
 
-        let msg_count: i64 = storage
-            .raw()
-            .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+        // The same path indexed from another source keeps its documents
+        let local = norm_conv(Some("a"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        let mut remote = norm_conv(Some("b"), vec![norm_msg(0, 100)]);
+        remote.metadata = serde_json::json!({"cass": {"origin": {"source_id": "desk"}}});
+        persist::persist_conversation(&mut storage, &mut index, &local).unwrap();
+        persist::persist_conversation(&mut storage, &mut index, &remote).unwrap();
+        index.commit().unwrap();
+
+        let session = ("local".to_string(), "/logs/demo.jsonl".to_string());
+        let (removed, locked) =
+            delete_sessions(&mut storage, &mut index, std::slice::from_ref(&session)).unwrap();
+        assert_eq!((removed, locked), (1, 0));
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 1);
+
+        assert!(!persist::persist_conversation(&mut storage, &mut index, &local).unwrap());
+
+        // Undo goes through the journal, like `cass undo` and the TUI
+        let journal =
+            crate::bookmarks::BookmarkStore::open(&data_dir.join("bookmarks.db")).unwrap();
+        journal
+            .journal_deleted_sessions(std::slice::from_ref(&session), removed)
             .unwrap();
-        assert_eq!(msg_count, 1);
+        let undone = journal.undo_last(&db_path).unwrap().unwrap();
+        assert_eq!(undone.action, crate::bookmarks::UndoAction::DeleteSessions);
+        assert!(persist::persist_conversation(&mut storage, &mut index, &local).unwrap());
+    }
 
-        reset_storage(&mut storage).unwrap();
+    #[test]
+    fn gc_of_a_missing_local_file_keeps_the_remote_copy_in_the_index() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
 
-        let msg_count: i64 = storage
-            .raw()
-            .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+        let mut storage = SqliteStorage::open(&data_dir.join("db.sqlite")).unwrap();
+        ensure_fts_schema(storage.raw());
+        storage
+            .upsert_source(&crate::sources::provenance::Source {
+                id: "desk".to_string(),
+                kind: SourceKind::Ssh,
+                host_label: Some("desk.local".to_string()),
+                machine_id: None,
+                platform: None,
+                config_json: None,
+                created_at: None,
+                updated_at: None,
+            })
             .unwrap();
-        assert_eq!(msg_count, 0);
-        assert_eq!(storage.schema_version().unwrap(), 5);
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
+
+        // /logs/demo.jsonl doesn't exist here, but the remote copy isn't checked
+        let local = norm_conv(Some("a"), vec![norm_msg(0, 100)]);
+        let mut remote = norm_conv(Some("b"), vec![norm_msg(0, 100)]);
+        remote.metadata = serde_json::json!({"cass": {"origin": {"source_id": "desk"}}});
+        persist::persist_conversation(&mut storage, &mut index, &local).unwrap();
+        persist::persist_conversation(&mut storage, &mut index, &remote).unwrap();
+        index.commit().unwrap();
+
+        assert_eq!(gc_missing_sources(&mut storage, &mut index).unwrap(), 1);
+        index.commit().unwrap();
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 1);
     }
 
     #[test]
-    fn persist_append_only_adds_new_messages_to_index() {
+    fn dedupe_deletes_only_duplicates_and_keeps_them_deleted() {
+        use super::dedupe::{DedupeOptions, Resolution, find_duplicates, resolve_clusters};
+
         let tmp = TempDir::new().unwrap();
         let data_dir = tmp.path().join("data");
         std::fs::create_dir_all(&data_dir).unwrap();
@@ -1243,26 +2308,146 @@ mod tests {
         let db_path = data_dir.join("db.sqlite");
         let mut storage = SqliteStorage::open(&db_path).unwrap();
         ensure_fts_schema(storage.raw());
+        storage
+            .upsert_source(&crate::sources::provenance::Source {
+                id: "desk".to_string(),
+                kind: SourceKind::Ssh,
+                host_label: Some("desk.local".to_string()),
+                machine_id: None,
+                platform: None,
+                config_json: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .unwrap();
         let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
 
-        let conv1 = norm_conv(Some("ext"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
-        persist::persist_conversation(&mut storage, &mut index, &conv1).unwrap();
+        // Two sessions with the same messages at the same path, on two machines
+        let local = norm_conv(Some("a"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        let mut remote = norm_conv(Some("b"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        remote.metadata = serde_json::json!({"cass": {"origin": {"source_id": "desk"}}});
+        persist::persist_conversation(&mut storage, &mut index, &local).unwrap();
+        persist::persist_conversation(&mut storage, &mut index, &remote).unwrap();
         index.commit().unwrap();
 
+        let report = find_duplicates(
+            &storage,
+            &DedupeOptions {
+                threshold: 1.0,
+                agents: Vec::new(),
+            },
+        )
+        .unwrap();
+        assert_eq!(report.clusters.len(), 1);
+        let cluster = &report.clusters[0];
+        let summary = resolve_clusters(
+            &mut storage,
+            &mut index,
+            &data_dir,
+            &[(cluster, Resolution::Delete)],
+        )
+        .unwrap();
+        assert_eq!(summary.conversations, 1);
+
+        // The kept copy's documents survive even though the paths match
         let reader = index.reader().unwrap();
         reader.reload().unwrap();
         assert_eq!(reader.searcher().num_docs(), 2);
 
-        let conv2 = norm_conv(
-            Some("ext"),
-            vec![norm_msg(0, 100), norm_msg(1, 200), norm_msg(2, 300)],
-        );
-        persist::persist_conversation(&mut storage, &mut index, &conv2).unwrap();
-        index.commit().unwrap();
+        // Rescanning the deleted copy skips it until it changes
+        let deleted = if cluster.keep().source_id == "desk" {
+            &local
+        } else {
+            &remote
+        };
+        assert!(!persist::persist_conversation(&mut storage, &mut index, deleted).unwrap());
+        let count = |storage: &SqliteStorage| -> i64 {
+            storage
+                .raw()
+                .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+                .unwrap()
+        };
+        assert_eq!(count(&storage), 1);
+        let mut changed = deleted.clone();
+        changed.messages.push(norm_msg(2, 300));
+        assert!(persist::persist_conversation(&mut storage, &mut index, &changed).unwrap());
+        assert_eq!(count(&storage), 2);
+    }
+
+    #[test]
+    fn persist_keeps_one_copy_of_a_session_across_sources() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path().join("data");
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        let db_path = data_dir.join("db.sqlite");
+        let mut storage = SqliteStorage::open(&db_path).unwrap();
+        ensure_fts_schema(storage.raw());
+        storage
+            .upsert_source(&crate::sources::provenance::Source {
+                id: "desk".to_string(),
+                kind: SourceKind::Ssh,
+                host_label: Some("desk.local".to_string()),
+                machine_id: None,
+                platform: None,
+                config_json: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .unwrap();
+        let mut index = TantivyIndex::open_or_create(&index_dir(&data_dir).unwrap()).unwrap();
+        let from_desk = |msgs| {
+            let mut conv = norm_conv(Some("ext"), msgs);
+            conv.source_path = PathBuf::from("/mirror/demo.jsonl");
+            conv.metadata = serde_json::json!({"cass": {"origin": {"source_id": "desk"}}});
+            conv
+        };
+        let copies = |storage: &SqliteStorage| -> Vec<String> {
+            let mut stmt = storage
+                .raw()
+                .prepare("SELECT source_id FROM conversations ORDER BY source_id")
+                .unwrap();
+            stmt.query_map([], |r| r.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect()
+        };
+
+        let local = norm_conv(Some("ext"), vec![norm_msg(0, 100), norm_msg(1, 200)]);
+        persist::persist_conversation(&mut storage, &mut index, &local).unwrap();
+
+        // A shorter synced copy is dropped and stays dropped
+        let stale = from_desk(vec![norm_msg(0, 100)]);
+        assert!(!persist::persist_conversation(&mut storage, &mut index, &stale).unwrap());
+        assert!(!persist::persist_conversation(&mut storage, &mut index, &stale).unwrap());
+        assert_eq!(copies(&storage), vec!["local"]);
 
+        // A longer one replaces the local copy
+        let longer = from_desk(vec![norm_msg(0, 100), norm_msg(1, 200), norm_msg(2, 300)]);
+        persist::persist_conversation(&mut storage, &mut index, &longer).unwrap();
+        index.commit().unwrap();
+        assert_eq!(copies(&storage), vec!["desk"]);
         let reader = index.reader().unwrap();
         reader.reload().unwrap();
         assert_eq!(reader.searcher().num_docs(), 3);
+
+        // The desk copy is kept now, so its earlier "dropped" record is gone
+        let merged = storage.list_merged_duplicates().unwrap();
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].source_id == "local" && merged[0].kept_source_id == "desk");
+
+        // The replaced local copy is skipped until it changes
+        persist::persist_conversation(&mut storage, &mut index, &local).unwrap();
+        assert_eq!(copies(&storage), vec!["desk"]);
+
+        // ...or until the kept copy is gone
+        let desk_ids = storage
+            .session_conversation_ids("desk", "/mirror/demo.jsonl")
+            .unwrap();
+        storage.delete_conversations(&desk_ids).unwrap();
+        assert!(persist::persist_conversation(&mut storage, &mut index, &local).unwrap());
+        assert_eq!(copies(&storage), vec!["local"]);
+        assert!(storage.list_merged_duplicates().unwrap().is_empty());
     }
 
     #[test]
@@ -1372,6 +2557,7 @@ mod tests {
             force_rebuild: false,
             db_path: data_dir.join("agent_search.db"),
             data_dir: data_dir.clone(),
+            gc: false,
             progress: None,
             watch_once_paths: None,
         };
@@ -1484,6 +2670,7 @@ CREATE VIRTUAL TABLE fts_messages USING fts5(
             watch_once_paths: None,
             db_path: data_dir.join("db.sqlite"),
             data_dir: data_dir.clone(),
+            gc: false,
             progress: Some(progress.clone()),
         };
 
@@ -1509,6 +2696,9 @@ CREATE VIRTUAL TABLE fts_messages USING fts5(
         assert_eq!(progress.current.load(Ordering::Relaxed), 1);
         // Phase resets to 0 (idle) at the end
         assert_eq!(progress.phase.load(Ordering::Relaxed), 0);
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.added, 1);
+        assert!(snapshot.last_commit_at.is_some());
 
         // Explicitly drop resources to release locks before cleanup
         drop(t_index);
@@ -1699,6 +2889,47 @@ CREATE VIRTUAL TABLE fts_messages USING fts5(
         assert_eq!(roots[0].origin.source_id, "local");
     }
 
+    #[test]
+    fn encrypted_mirror_is_scanned_through_its_decrypted_copy() {
+        let tmp = TempDir::new().unwrap();
+        let mirror = tmp.path().join("remotes/laptop/mirror");
+        let session = mirror.join(".claude/projects/-test-project/agent-test123.jsonl");
+        std::fs::create_dir_all(session.parent().unwrap()).unwrap();
+        std::fs::copy(
+            "tests/fixtures/claude_code_real/projects/-test-project/agent-test123.jsonl",
+            &session,
+        )
+        .unwrap();
+        let key = [3u8; 32];
+        staging::encrypt_tree(&mirror, &key).unwrap();
+
+        let mut plaintext = staging::Plaintext::default();
+        staging::decrypt_tree(&mirror, &key, &mut plaintext).unwrap();
+        let root = ScanRoot::remote(mirror.join(".claude"), Origin::remote("laptop"), None);
+        let convs = scan_remote_root(
+            &ClaudeCodeConnector::new(),
+            "claude_code",
+            &root,
+            &plaintext,
+            &crate::connectors::ScanWindow::default(),
+        );
+        drop(plaintext);
+
+        assert_eq!(convs.len(), 1);
+        assert_eq!(convs[0].source_path, session);
+        assert!(!convs[0].messages.is_empty());
+        assert!(
+            convs[0]
+                .messages
+                .iter()
+                .filter_map(|m| m.source.as_ref())
+                .all(|s| s.path.starts_with(&mirror))
+        );
+        // Nothing readable stays behind: the mirror holds only sealed files
+        assert!(!staging::plaintext_dir(&mirror).exists());
+        assert!(!session.exists());
+    }
+
     #[test]
     fn apply_workspace_rewrite_no_rewrites() {
         let mut conv = norm_conv(None, vec![norm_msg(0, 1000)]);
@@ -0,0 +1,287 @@
+//! Retention window and `cass prune`.
+//!
+//! ```toml
+//! [storage]
+//! retention = "18mo"   # or "1y", "90d", "6w"
+//! ```
+//!
+//! With a retention window, every index run prunes sessions that started
+//! before it. `cass prune` does the same on demand: `--older-than` overrides
+//! the window, `--agent` narrows it, and `--dry-run` reports without deleting.
+//!
+//! Whole session files are pruned, and only when every conversation in the
+//! file is old enough (and from a selected agent), so SQLite and Tantivy (keyed
+//! by path) stay in step. Sessions locked with `cass lock` are never pruned.
+//! SQLite rows go in one transaction, then the Tantivy deletes are committed,
+//! then every vector index is rewritten without the pruned messages. Pruned
+//! sessions whose files still exist come back on the next `--full` scan.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::indexer::budget::{PrunedConversation, StorageConfig};
+use crate::search::tantivy::TantivyIndex;
+use crate::search::vector_compact::{
+    CompactOptions, compact_vector_indexes_with_live, live_message_ids,
+};
+use crate::search::vector_index::VECTOR_INDEX_DIR;
+use crate::storage::sqlite::{PruneCandidate, SqliteStorage};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Parse an age such as "1y", "18mo", "18 months", "6w", "90d" or "12h" into milliseconds.
+/// Months count as 30 days and years as 365.
+pub fn parse_age(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let number: i64 = number.parse().ok()?;
+    let unit_ms = match unit.trim().to_ascii_lowercase().as_str() {
+        "h" | "hour" | "hours" => DAY_MS / 24,
+        "d" | "day" | "days" => DAY_MS,
+        "w" | "week" | "weeks" => 7 * DAY_MS,
+        "mo" | "month" | "months" => 30 * DAY_MS,
+        "y" | "year" | "years" => 365 * DAY_MS,
+        _ => return None,
+    };
+    number.checked_mul(unit_ms)
+}
+
+/// The `[storage] retention` window, if configured.
+pub fn configured_retention() -> Option<String> {
+    StorageConfig::load()?.retention
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Prune sessions that started before this time (ms since epoch)
+    pub cutoff_ms: i64,
+    /// Only prune sessions from these agents (all agents when empty)
+    pub agents: Vec<String>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneSummary {
+    pub cutoff_ms: i64,
+    pub agents: Vec<String>,
+    pub dry_run: bool,
+    /// Session files pruned
+    pub sessions: usize,
+    pub conversations: usize,
+    pub messages: usize,
+    /// Vector index rows dropped across all embedders
+    pub vectors: usize,
+    /// Matching session files kept because they are locked
+    pub locked: usize,
+    pub removed: Vec<PrunedConversation>,
+}
+
+/// Prune sessions older than the cutoff from SQLite, Tantivy and the vector indexes.
+pub fn prune_sessions(
+    storage: &mut SqliteStorage,
+    t_index: &mut TantivyIndex,
+    data_dir: &Path,
+    opts: &PruneOptions,
+) -> Result<PruneSummary> {
+    let mut summary = PruneSummary {
+        cutoff_ms: opts.cutoff_ms,
+        agents: opts.agents.clone(),
+        dry_run: opts.dry_run,
+        ..Default::default()
+    };
+    let agents: HashSet<String> = opts.agents.iter().map(|a| a.to_lowercase()).collect();
+    let matches = |c: &PruneCandidate| {
+        c.started_at.is_some_and(|t| t < opts.cutoff_ms)
+            && (agents.is_empty() || agents.contains(&c.agent.to_lowercase()))
+    };
+
+    let mut files: BTreeMap<(String, String), Vec<PruneCandidate>> = BTreeMap::new();
+    for c in storage.prune_candidates(false)? {
+        files
+            .entry((c.source_id.clone(), c.source_path.clone()))
+            .or_default()
+            .push(c);
+    }
+    let mut paths = Vec::new();
+    let mut victims = Vec::new();
+    for ((_, path), convs) in files {
+        if !convs.iter().all(matches) {
+            continue;
+        }
+        if convs.iter().any(|c| c.retained) {
+            summary.locked += 1;
+            continue;
+        }
+        paths.push(path);
+        victims.extend(convs);
+    }
+    if victims.is_empty() {
+        return Ok(summary);
+    }
+    victims.sort_by_key(|c| (c.started_at, c.conversation_id));
+
+    let ids: Vec<i64> = victims.iter().map(|c| c.conversation_id).collect();
+    let mut pruned_messages: HashSet<u64> = HashSet::new();
+    {
+        let mut stmt = storage
+            .raw()
+            .prepare("SELECT id FROM messages WHERE conversation_id = ?")?;
+        for id in &ids {
+            for message_id in stmt.query_map([id], |r| r.get::<_, i64>(0))? {
+                pruned_messages.insert(message_id? as u64);
+            }
+        }
+    }
+    // Surviving message ids, taken before the delete so a dry run sees the same set
+    let live = if data_dir.join(VECTOR_INDEX_DIR).is_dir() {
+        let mut live = live_message_ids(storage.raw())?;
+        live.retain(|id| !pruned_messages.contains(id));
+        Some(live)
+    } else {
+        None
+    };
+
+    summary.sessions = paths.len();
+    summary.messages = pruned_messages.len();
+    if opts.dry_run {
+        summary.conversations = ids.len();
+    } else {
+        super::delete_conversation_docs(storage, t_index, &ids)?;
+        summary.conversations = storage.delete_conversations(&ids)?;
+        t_index.commit()?;
+    }
+
+    if let Some(live) = live {
+        let compact = CompactOptions {
+            min_fragmentation: 0.0,
+            force: true,
+            dry_run: opts.dry_run,
+        };
+        let report = compact_vector_indexes_with_live(data_dir, &live, &compact)?;
+        summary.vectors = report.indexes.iter().map(|i| i.orphaned).sum();
+    }
+
+    summary.removed = victims
+        .into_iter()
+        .map(|c| PrunedConversation {
+            estimated_bytes: c.content_bytes,
+            source_id: c.source_id,
+            source_path: c.source_path,
+            agent: c.agent,
+            title: c.title,
+            started_at: c.started_at,
+        })
+        .collect();
+    if !opts.dry_run {
+        tracing::info!(
+            sessions = summary.sessions,
+            conversations = summary.conversations,
+            vectors = summary.vectors,
+            cutoff_ms = opts.cutoff_ms,
+            "retention: pruned sessions"
+        );
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::vector_index::{Quantization, VectorEntry, VectorIndex, vector_index_path};
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("90d"), Some(90 * DAY_MS));
+        assert_eq!(parse_age("18mo"), Some(540 * DAY_MS));
+        assert_eq!(parse_age("18 months"), Some(540 * DAY_MS));
+        assert_eq!(parse_age("1y"), Some(365 * DAY_MS));
+        assert_eq!(parse_age("2 weeks"), Some(14 * DAY_MS));
+        assert_eq!(parse_age("1m"), None, "m is ambiguous");
+        assert_eq!(parse_age("soon"), None);
+    }
+
+    #[test]
+    fn prunes_old_unlocked_sessions_everywhere() {
+        let dir = tempdir().unwrap();
+        let mut storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        storage
+            .raw()
+            .execute_batch(
+                "INSERT INTO agents(id, slug, name, kind, created_at, updated_at) VALUES
+                    (1, 'amp', 'Amp', 'cli', 0, 0), (2, 'codex', 'Codex', 'cli', 0, 0);
+                 INSERT INTO conversations(id, agent_id, source_id, source_path, started_at) VALUES
+                    (1, 1, 'local', '/logs/old.jsonl', 1000),
+                    (2, 1, 'local', '/logs/new.jsonl', 9000),
+                    (3, 1, 'local', '/logs/locked.jsonl', 1000),
+                    (4, 2, 'local', '/logs/codex.jsonl', 1000);
+                 INSERT INTO messages(id, conversation_id, idx, role, content) VALUES
+                    (1, 1, 0, 'user', 'old'), (2, 1, 1, 'agent', 'old reply'),
+                    (3, 2, 0, 'user', 'new'), (4, 3, 0, 'user', 'locked'),
+                    (5, 4, 0, 'user', 'codex');",
+            )
+            .unwrap();
+        storage
+            .set_retained("local", "/logs/locked.jsonl", true)
+            .unwrap();
+        let mut t_index = TantivyIndex::open_or_create(&dir.path().join("index")).unwrap();
+
+        let path = vector_index_path(dir.path(), "fnv1a-4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let entries = (1..=5)
+            .map(|id| VectorEntry {
+                message_id: id,
+                created_at_ms: 0,
+                agent_id: 1,
+                workspace_id: 0,
+                source_id: 0,
+                role: 0,
+                chunk_idx: 0,
+                content_hash: [0; 32],
+                vector: vec![0.5; 4],
+            })
+            .collect::<Vec<_>>();
+        VectorIndex::build("fnv1a-4", "canon-v1", 4, Quantization::F16, entries)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let mut opts = PruneOptions {
+            cutoff_ms: 5000,
+            agents: vec!["amp".into()],
+            dry_run: true,
+        };
+        let preview = prune_sessions(&mut storage, &mut t_index, dir.path(), &opts).unwrap();
+        assert_eq!(
+            (
+                preview.sessions,
+                preview.messages,
+                preview.vectors,
+                preview.locked
+            ),
+            (1, 2, 2, 1)
+        );
+        assert_eq!(VectorIndex::load(&path).unwrap().rows().len(), 5);
+
+        opts.dry_run = false;
+        let summary = prune_sessions(&mut storage, &mut t_index, dir.path(), &opts).unwrap();
+        assert_eq!((summary.conversations, summary.vectors), (1, 2));
+        assert_eq!(summary.removed[0].source_path, "/logs/old.jsonl");
+        let left: i64 = storage
+            .raw()
+            .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(left, 3);
+        let ids: Vec<u64> = VectorIndex::load(&path)
+            .unwrap()
+            .rows()
+            .iter()
+            .map(|r| r.message_id)
+            .collect();
+        assert_eq!(ids, vec![3, 4, 5]);
+    }
+}
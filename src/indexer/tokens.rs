@@ -0,0 +1,51 @@
+//! BPE token counting for `approx_tokens`.
+//!
+//! Uses the `cl100k_base` encoding (tiktoken-compatible), which is a close
+//! enough proxy across current agent models for analytics and cost estimates.
+//! If the encoder can't be built, falls back to the chars/4 heuristic used
+//! elsewhere for token budgets.
+
+use once_cell::sync::Lazy;
+use tiktoken_rs::CoreBPE;
+
+static BPE: Lazy<Option<CoreBPE>> = Lazy::new(|| match tiktoken_rs::cl100k_base() {
+    Ok(bpe) => Some(bpe),
+    Err(e) => {
+        tracing::warn!("cl100k_base tokenizer unavailable, using chars/4 estimate: {e}");
+        None
+    }
+});
+
+/// Number of tokens in `text`.
+pub fn count_tokens(text: &str) -> i64 {
+    if text.is_empty() {
+        return 0;
+    }
+    match BPE.as_ref() {
+        // Ordinary encoding: special-token text in transcripts counts as plain text.
+        Some(bpe) => bpe.encode_ordinary(text).len() as i64,
+        None => text.chars().count().div_ceil(4) as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_text_has_no_tokens() {
+        assert_eq!(count_tokens(""), 0);
+    }
+
+    #[test]
+    fn counts_bpe_tokens() {
+        // "hello world" is two tokens in cl100k_base
+        assert_eq!(count_tokens("hello world"), 2);
+        assert!(count_tokens(&"fn main() {}\n".repeat(50)) > 50);
+    }
+
+    #[test]
+    fn special_token_text_is_not_rejected() {
+        assert!(count_tokens("<|endoftext|>") > 1);
+    }
+}
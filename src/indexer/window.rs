@@ -0,0 +1,156 @@
+//! Scan window: which historical period gets indexed at all.
+//!
+//! ```toml
+//! [index]
+//! ignore_older_than = "2y"     # relative to now; same units as `retention`
+//! not_before = "2023-01-01"    # or absolute bounds (UTC dates or RFC 3339)
+//! not_after = "2024-12-31"
+//! ```
+//!
+//! The window is applied to every `ScanContext`, so session files last
+//! modified before its start are never opened. That keeps first-time indexing
+//! fast for users with large histories they don't care about. Conversations
+//! that still come through are dropped when they fall entirely outside the
+//! window. Unlike `retention`, nothing already indexed is removed.
+
+use chrono::{DateTime, NaiveDate};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::connectors::ScanWindow;
+use crate::hooks::HooksConfig;
+use crate::indexer::retention::parse_age;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IndexWindowConfig {
+    pub ignore_older_than: Option<String>,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    index: IndexWindowConfig,
+}
+
+impl IndexWindowConfig {
+    /// The `[index]` section; missing or invalid means "no window".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => config.index,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring scan window: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Resolve against `now_ms`. The later of `ignore_older_than` and
+    /// `not_before` wins; unparseable values are skipped with a warning.
+    pub fn resolve(&self, now_ms: i64) -> ScanWindow {
+        let age_start = self.ignore_older_than.as_deref().and_then(|raw| {
+            let age = parse_age(raw);
+            if age.is_none() {
+                warn!(ignore_older_than = %raw, "ignoring scan window: unrecognized age");
+            }
+            age.map(|ms| now_ms.saturating_sub(ms))
+        });
+        let bound = |name: &str, raw: Option<&str>, end_of_day: bool| {
+            let raw = raw?;
+            let ts = parse_bound(raw, end_of_day);
+            if ts.is_none() {
+                warn!(%name, value = %raw, "ignoring scan window: unrecognized date");
+            }
+            ts
+        };
+        let date_start = bound("not_before", self.not_before.as_deref(), false);
+        ScanWindow {
+            not_before: age_start.max(date_start),
+            not_after: bound("not_after", self.not_after.as_deref(), true),
+        }
+    }
+}
+
+/// The configured window, resolved against the current time.
+pub fn configured_window() -> ScanWindow {
+    IndexWindowConfig::load().resolve(chrono::Utc::now().timestamp_millis())
+}
+
+/// A UTC date (start or end of that day) or an RFC 3339 timestamp.
+fn parse_bound(raw: &str, end_of_day: bool) -> Option<i64> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.timestamp_millis());
+    }
+    let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Some(time.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::{NormalizedConversation, ScanContext};
+    use std::path::PathBuf;
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    fn conv(started_at: Option<i64>, ended_at: Option<i64>) -> NormalizedConversation {
+        NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: None,
+            title: None,
+            workspace: None,
+            source_path: PathBuf::from("/tmp/s.jsonl"),
+            started_at,
+            ended_at,
+            metadata: serde_json::Value::Null,
+            messages: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn window_resolves_and_filters_scans_and_conversations() {
+        let now = 1_000 * DAY_MS;
+        let config = IndexWindowConfig {
+            ignore_older_than: Some("100d".into()),
+            not_before: Some("1970-01-02".into()),
+            not_after: Some("1970-12-31".into()),
+        };
+        let window = config.resolve(now);
+        assert_eq!(window.not_before, Some(900 * DAY_MS));
+        assert_eq!(window.not_after, Some(365 * DAY_MS - 1));
+
+        let full = ScanContext::local_default(PathBuf::new(), None).windowed(&window);
+        assert_eq!(full.since_ts, Some(900 * DAY_MS));
+        let incremental =
+            ScanContext::local_default(PathBuf::new(), Some(950 * DAY_MS)).windowed(&window);
+        assert_eq!(incremental.since_ts, Some(950 * DAY_MS));
+
+        let window = ScanWindow {
+            not_before: Some(10 * DAY_MS),
+            not_after: Some(20 * DAY_MS),
+        };
+        assert!(!window.contains(&conv(Some(DAY_MS), Some(2 * DAY_MS))));
+        assert!(window.contains(&conv(Some(DAY_MS), Some(15 * DAY_MS))));
+        assert!(!window.contains(&conv(Some(21 * DAY_MS), None)));
+        assert!(window.contains(&conv(None, None)));
+
+        let bad = IndexWindowConfig {
+            ignore_older_than: Some("forever".into()),
+            ..Default::default()
+        };
+        assert!(bad.resolve(now).is_unbounded());
+    }
+}
@@ -1,9 +1,23 @@
+pub mod api;
+pub mod audit;
 pub mod bookmarks;
+pub mod config;
 pub mod connectors;
+pub mod daemon;
+pub mod decisions;
+pub mod diff;
 pub mod export;
+pub mod graph;
+pub mod hooks;
+pub mod http;
 pub mod indexer;
+pub mod mcp;
 pub mod model;
+pub mod profiles;
+pub mod reports;
+pub mod rpc;
 pub mod search;
+pub mod selfcheck;
 pub mod sources;
 pub mod storage;
 pub mod ui;
@@ -27,6 +41,8 @@ use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitEx
 
 const CONTRACT_VERSION: &str = "1";
 const DEFAULT_STALE_THRESHOLD_SECS: u64 = 1800;
+/// Version of the `cass stats --json` payload; bumped when fields change incompatibly
+const STATS_SCHEMA_VERSION: u32 = 2;
 
 fn read_watch_once_paths_env() -> Option<Vec<std::path::PathBuf>> {
     std::env::var("CASS_TEST_WATCH_PATHS")
@@ -52,6 +68,10 @@ pub struct Cli {
     #[arg(long)]
     pub db: Option<PathBuf>,
 
+    /// Named profile with its own data dir and database (see `[profiles]` in config.toml)
+    #[arg(long, env = "CASS_PROFILE")]
+    pub profile: Option<String>,
+
     /// Deterministic machine-first help (wide, no TUI)
     #[arg(long, default_value_t = false)]
     pub robot_help: bool,
@@ -107,7 +127,7 @@ pub enum Commands {
     },
     /// Run indexer
     Index {
-        /// Perform full rebuild
+        /// Rescan all sources (unchanged conversations are skipped by content hash)
         #[arg(long)]
         full: bool,
 
@@ -115,10 +135,19 @@ pub enum Commands {
         #[arg(long, default_value_t = false)]
         force_rebuild: bool,
 
+        /// Rebuild the search index from the database before indexing (repairs a corrupt
+        /// index without rescanning sources)
+        #[arg(long, conflicts_with = "force_rebuild")]
+        rebuild: bool,
+
         /// Watch for changes and reindex automatically
         #[arg(long)]
         watch: bool,
 
+        /// Delete indexed conversations whose source files no longer exist or match `exclude`
+        #[arg(long)]
+        gc: bool,
+
         /// Trigger a single watch cycle for specific paths (comma-separated or repeated)
         #[arg(long, value_delimiter = ',', num_args = 1..)]
         watch_once: Option<Vec<PathBuf>>,
@@ -156,9 +185,12 @@ pub enum Commands {
         /// Filter by agent slug (can be specified multiple times)
         #[arg(long)]
         agent: Vec<String>,
-        /// Filter by workspace path (can be specified multiple times)
+        /// Filter by workspace path prefix (can be specified multiple times)
         #[arg(long)]
         workspace: Vec<String>,
+        /// Filter by message role (can be specified multiple times)
+        #[arg(long, value_enum)]
+        role: Vec<RoleFilter>,
         /// Max results
         #[arg(long, default_value_t = 10)]
         limit: usize,
@@ -230,16 +262,31 @@ pub enum Commands {
         /// Highlight matching terms in output (uses **bold** markers in text, <mark> in HTML)
         #[arg(long)]
         highlight: bool,
-        /// Filter by source: 'local', 'remote', 'all', or a specific source hostname
+        /// Filter by source: 'local', 'remote', 'all', or a specific source hostname (can be specified multiple times)
         #[arg(long)]
-        source: Option<String>,
+        source: Vec<String>,
         /// Filter to sessions from file (one path per line). Use '-' for stdin.
         /// Enables chained searches: `cass search "query1" --robot-format sessions | cass search "query2" --sessions-from -`
         #[arg(long)]
         sessions_from: Option<String>,
+        /// Match only code snippets (fenced code blocks) extracted from messages
+        #[arg(long)]
+        code: bool,
+        /// Filter to every workspace grouped under a project (see `cass projects list`)
+        #[arg(long)]
+        project: Option<String>,
+        /// Only starred conversations (same as `tag:starred`)
+        #[arg(long)]
+        starred: bool,
+        /// Compare matching sessions between two time ranges: `-7d..now vs -14d..-7d`.
+        /// Reports sessions that are new, continuing, or only matched in the earlier range.
+        #[arg(long, allow_hyphen_values = true, conflicts_with_all = ["days", "today", "yesterday", "week", "since", "until", "aggregate"])]
+        compare_period: Option<String>,
     },
     /// Show statistics about indexed data
     Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommand>,
         /// Override data dir
         #[arg(long)]
         data_dir: Option<PathBuf>,
@@ -252,6 +299,12 @@ pub enum Commands {
         /// Show breakdown by source
         #[arg(long)]
         by_source: bool,
+        /// Show breakdown by project (workspaces grouped by git remote)
+        #[arg(long)]
+        by_project: bool,
+        /// Days of per-day activity to report; 0 to skip
+        #[arg(long, default_value_t = 30)]
+        days: u32,
     },
     /// Output diagnostic information for troubleshooting
     Diag {
@@ -356,19 +409,56 @@ pub enum Commands {
         #[arg(long, default_value_t = 5)]
         limit: usize,
     },
-    /// Export a conversation to markdown or other formats
+    /// Export a conversation or search results to markdown or other formats
     Export {
         /// Path to session file
-        path: PathBuf,
+        #[arg(
+            required_unless_present_any = ["query", "conversation"],
+            conflicts_with_all = ["query", "conversation"]
+        )]
+        path: Option<PathBuf>,
+        /// Export the results of this search instead of a session file
+        #[arg(long, conflicts_with = "conversation")]
+        query: Option<String>,
+        /// Export an indexed conversation by conversation id or agent session id
+        #[arg(long, value_name = "ID")]
+        conversation: Option<String>,
+        /// Max search results to export (with --query)
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
         /// Output format
         #[arg(long, value_enum, default_value_t = ConvExportFormat::Markdown)]
         format: ConvExportFormat,
         /// Output file (stdout if not specified)
-        #[arg(long, short = 'o')]
+        #[arg(long, short = 'o', visible_alias = "out")]
         output: Option<PathBuf>,
         /// Include tool use details in export
         #[arg(long)]
         include_tools: bool,
+        /// Include the full content of each search result, not just its snippet (with --query)
+        #[arg(long, requires = "query")]
+        full_content: bool,
+        /// Filter search results by agent slug (with --query; can be specified multiple times)
+        #[arg(long, requires = "query")]
+        agent: Vec<String>,
+        /// Filter search results by workspace path prefix (with --query; can be specified multiple times)
+        #[arg(long, requires = "query")]
+        workspace: Vec<String>,
+        /// Filter search results by source: 'local', 'remote', 'all', or a source hostname (with --query)
+        #[arg(long, requires = "query")]
+        source: Vec<String>,
+        /// Filter search results to the last N days (with --query)
+        #[arg(long, requires = "query")]
+        days: Option<u32>,
+        /// Filter search results to entries since ISO date (with --query)
+        #[arg(long, requires = "query")]
+        since: Option<String>,
+        /// Filter search results to entries until ISO date (with --query)
+        #[arg(long, requires = "query")]
+        until: Option<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
     },
     /// Show messages around a specific line in a session file
     Expand {
@@ -414,1503 +504,6759 @@ pub enum Commands {
     /// Manage remote sources (P5.x)
     #[command(subcommand)]
     Sources(SourcesCommand),
-}
-
-/// Subcommands for managing remote sources (P5.x)
-#[derive(Subcommand, Debug, Clone)]
-pub enum SourcesCommand {
-    /// List configured sources
-    List {
-        /// Show detailed information
+    /// Pull sessions from remote sources and index them (same as `sources sync`)
+    Sync {
+        /// Sync only specific source(s)
+        #[arg(long, short)]
+        source: Option<Vec<String>>,
+        /// Don't re-index after sync
+        #[arg(long)]
+        no_index: bool,
+        /// Show detailed transfer information
         #[arg(long, short)]
         verbose: bool,
+        /// Dry run - list the files each source would fetch (new, changed, unchanged) without syncing
+        #[arg(long)]
+        dry_run: bool,
+        /// Show each source's last sync, duration, errors and staged size instead of syncing
+        #[arg(long, conflicts_with = "dry_run")]
+        status: bool,
+        /// Cap transfer bandwidth in KiB/s (overrides each source's `bwlimit`)
+        #[arg(long, value_name = "KBPS")]
+        bwlimit: Option<u32>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-    /// Add a new remote source
-    Add {
-        /// Source URL (e.g., user@host or ssh://user@host)
-        url: String,
-        /// Friendly name for this source (becomes source_id)
+    /// Serve the index over a local HTTP API and web UI (search, conversations,
+    /// facets, stats), or stream this machine's conversations to another cass with --sync
+    Serve {
+        /// Stream conversations recorded here to stdout as JSON lines; run by
+        /// `type = "cass"` sources over SSH
         #[arg(long)]
-        name: Option<String>,
-        /// Use preset paths for platform (macos-defaults, linux-defaults)
+        sync: bool,
+        /// Only conversations stored here at or after this cursor, from the
+        /// trailer of the previous stream
+        #[arg(long, requires = "sync")]
+        cursor: Option<i64>,
+        /// Only conversations active at or after this time (unix millis)
+        #[arg(long, requires = "sync")]
+        since: Option<i64>,
+        /// Address for the HTTP API
+        #[arg(long, default_value = "127.0.0.1:8765", conflicts_with = "sync")]
+        bind: std::net::SocketAddr,
+        /// Require `Authorization: Bearer <TOKEN>` on every HTTP request; needed
+        /// to bind beyond localhost
+        #[arg(long, env = "CASS_SERVE_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Open the web UI in a browser once listening
+        #[arg(long, conflicts_with = "sync")]
+        open: bool,
+        /// Override data dir
         #[arg(long)]
-        preset: Option<String>,
-        /// Paths to sync (can be specified multiple times)
-        #[arg(long = "path", short = 'p')]
-        paths: Vec<String>,
-        /// Skip connectivity test
+        data_dir: Option<PathBuf>,
+    },
+    /// Run a Model Context Protocol server on stdio so coding agents can
+    /// search past sessions (tools: search_sessions, get_conversation, list_workspaces)
+    Mcp {
+        /// Override data dir
         #[arg(long)]
-        no_test: bool,
+        data_dir: Option<PathBuf>,
     },
-    /// Remove a configured source
-    Remove {
-        /// Name of source to remove
-        name: String,
-        /// Also delete synced session data from index
+    /// Answer JSON-RPC requests on stdio (search, show, facets, workspaces,
+    /// stats) for editor extensions that keep one warm process
+    Rpc {
+        /// Override data dir
         #[arg(long)]
-        purge: bool,
-        /// Skip confirmation prompt
-        #[arg(long, short = 'y')]
-        yes: bool,
+        data_dir: Option<PathBuf>,
     },
-    /// Diagnose source connectivity and configuration issues
-    Doctor {
-        /// Check only specific source (defaults to all)
-        #[arg(long, short)]
-        source: Option<String>,
+    /// Undo the most recent bookmark/tag removal or bulk delete (within a 24h window)
+    Undo {
+        /// List undoable actions instead of undoing
+        #[arg(long)]
+        list: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-    /// Synchronize sessions from remote sources
-    Sync {
-        /// Sync only specific source(s)
-        #[arg(long, short)]
-        source: Option<Vec<String>>,
-        /// Don't re-index after sync
+    /// Lock a session file's conversations so pruning/retention never removes them.
+    /// Without a path, lists locked sessions.
+    Lock {
+        /// Path to the session file to lock
+        path: Option<PathBuf>,
+        /// Remove the lock instead of adding it
         #[arg(long)]
-        no_index: bool,
-        /// Show detailed transfer information
-        #[arg(long, short)]
-        verbose: bool,
-        /// Dry run - show what would be synced without actually syncing
+        unlock: bool,
+        /// Source the session belongs to ('local' or a remote source name)
+        #[arg(long, default_value = "local")]
+        source: String,
+        /// Override data dir
         #[arg(long)]
-        dry_run: bool,
+        data_dir: Option<PathBuf>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-    /// Manage path mappings for a source (P6.3)
-    #[command(subcommand)]
-    Mappings(MappingsAction),
-}
-
-/// Subcommands for managing path mappings (P6.3)
-#[derive(Subcommand, Debug, Clone)]
-pub enum MappingsAction {
-    /// List path mappings for a source
-    List {
-        /// Source name
+    /// Rate a session file thumbs up or down; ratings feed `rating:` searches and ranking.
+    /// Without a path, lists rated sessions.
+    Rate {
+        /// Path to the session file to rate
+        path: Option<PathBuf>,
+        /// Thumbs up: trusted session, ranked higher
+        #[arg(long, conflicts_with_all = ["down", "clear"])]
+        up: bool,
+        /// Thumbs down: ranked lower
+        #[arg(long, conflicts_with = "clear")]
+        down: bool,
+        /// Remove the rating
+        #[arg(long)]
+        clear: bool,
+        /// Optional note stored with the rating
+        #[arg(long)]
+        note: Option<String>,
+        /// Source the session belongs to ('local' or a remote source name)
+        #[arg(long, default_value = "local")]
         source: String,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-    /// Add a path mapping
-    Add {
-        /// Source name
-        source: String,
-        /// Remote path prefix to match
+    /// Group workspaces into projects by git remote; list or edit the mapping
+    #[command(subcommand)]
+    Projects(ProjectsCommand),
+    /// Export a graph of conversations linked by shared files, workspaces, continuations
+    /// and similarity (Graphviz DOT, JSON, or Obsidian canvas)
+    Graph {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Output file (stdout if not specified)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+        /// Maximum number of (most recent) conversations to include
+        #[arg(long, default_value_t = 500)]
+        limit: usize,
+        /// Only include conversations from this workspace path
         #[arg(long)]
-        from: String,
-        /// Local path prefix to replace with
+        workspace: Option<String>,
+        /// Minimum vocabulary overlap (Jaccard, 0-1) for similarity edges; 1 disables them
+        #[arg(long, default_value_t = 0.35)]
+        min_similarity: f64,
+        /// Override data dir
         #[arg(long)]
-        to: String,
-        /// Only apply to specific agents (comma-separated)
-        #[arg(long, value_delimiter = ',')]
-        agents: Option<Vec<String>>,
+        data_dir: Option<PathBuf>,
     },
-    /// Remove a path mapping by index
-    Remove {
-        /// Source name
-        source: String,
-        /// Index of mapping to remove (from list output, 0-based)
-        index: usize,
+    /// Show the decision log: decision-like statements ("we chose X because...")
+    /// extracted from assistant messages, grouped by workspace
+    Decisions {
+        /// Only include this workspace (and its subdirectories), e.g. '.'
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Filter by agent (can be repeated)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Only include messages since this time (ISO date, 'today', 'yesterday', 'Nd')
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum number of (most recent) decisions per workspace; 0 for all
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DecisionsFormat::Text)]
+        format: DecisionsFormat,
+        /// Output as JSON (same as --format json)
+        #[arg(long)]
+        json: bool,
+        /// Output file (stdout if not specified)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
     },
-    /// Test how a path would be rewritten
-    Test {
-        /// Source name
-        source: String,
-        /// Path to test
-        path: String,
-        /// Optional agent to simulate (for agent-specific rules)
+    /// Export a tamper-evident, hash-chained log of agent actions (commands run,
+    /// files edited or written, other tool calls) as JSON Lines, or verify one
+    Audit {
+        /// Only include this workspace (and its subdirectories), e.g. '.'
+        #[arg(long)]
+        workspace: Option<PathBuf>,
+        /// Filter by agent (can be repeated)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Only include actions since this time (ISO date, 'today', 'yesterday', 'Nd')
+        #[arg(long)]
+        since: Option<String>,
+        /// Output file (stdout if not specified)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+        /// Verify a previously exported log instead of exporting
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["workspace", "agent", "since", "output"])]
+        verify: Option<PathBuf>,
+        /// Print the export or verification summary as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Compute message embeddings for semantic search. With --backfill only messages
+    /// lacking a current vector are embedded; without it the vector index is rebuilt
+    Embed {
+        /// Only embed messages without a vector (or embedded by an older embedder)
+        #[arg(long)]
+        backfill: bool,
+        /// Embedder to use
+        #[arg(long, value_enum, default_value_t = EmbedderKind::Auto)]
+        embedder: EmbedderKind,
+        /// Most messages per embedding batch (the embedder's token budget may split batches further)
+        #[arg(long, default_value_t = 64)]
+        batch_size: usize,
+        /// Save a resumable checkpoint after this many embedded messages (0 = only at the end)
+        #[arg(long, default_value_t = 10_000)]
+        checkpoint_every: usize,
+        /// What to keep from each message before embedding [default: `[embedding] profile` in config.toml, else full]
+        #[arg(long, value_enum)]
+        canon_profile: Option<crate::search::canonicalize::CanonProfile>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Run the background indexing daemon (watch + periodic reconciliation + scheduled syncs)
+    Daemon {
+        #[command(subcommand)]
+        command: Option<DaemonCommand>,
+        /// Hours between full reconciliation passes
+        #[arg(long, default_value_t = 6)]
+        reconcile_hours: u64,
+        /// Announce this machine over mDNS so `cass sources discover` on the LAN finds it
+        #[arg(long)]
+        advertise: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Render or run parameterized queries from `[query_templates]` in config.toml
+    #[command(subcommand)]
+    QueryTemplate(QueryTemplateCommand),
+    /// Print a full indexed conversation by conversation id or agent session id
+    Show {
+        /// Conversation id (numeric) or the agent's session id
+        id: String,
+        /// Only match session ids from this agent
         #[arg(long)]
         agent: Option<String>,
+        /// Only match session ids from this source: 'local', 'remote', or a source name
+        #[arg(long)]
+        source: Option<String>,
+        /// First message index to print (for paging through long sessions)
+        #[arg(long, default_value_t = 0)]
+        from_idx: i64,
+        /// Max messages to print; 0 for all
+        #[arg(long, default_value_t = 0)]
+        limit: usize,
+        /// Output as Markdown
+        #[arg(long, conflicts_with = "json")]
+        markdown: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
     },
+    /// Fuzzy-pick a conversation by title, workspace or agent and print its
+    /// source path, e.g. `$EDITOR $(cass pick)`
+    Pick {
+        /// Initial query for the interactive picker
+        query: Option<String>,
+        /// Print matches for this query, best first, without the interactive picker
+        #[arg(long, value_name = "QUERY", conflicts_with = "query")]
+        filter: Option<String>,
+        /// Print the conversation id instead of the source path
+        #[arg(long)]
+        id: bool,
+        /// Only offer sessions from this agent (repeatable)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Most recent sessions to offer
+        #[arg(long, default_value_t = 5000)]
+        limit: usize,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// List known agents with whether their logs were found on this machine
+    /// and how many conversations are indexed
+    Agents {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// List indexed workspaces with session counts and last activity, most
+    /// recently active first
+    Workspaces {
+        /// Max workspaces to list; 0 for all
+        #[arg(long, default_value_t = 0)]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Tag conversations (by conversation id) to curate history; search with `tag:<name>`
+    #[command(subcommand)]
+    Tag(TagCommand),
+    /// Bulk-edit stored conversations (move workspaces, add or remove tags), e.g. after a repo moved
+    Update {
+        /// Select conversations: workspace:<path> (and below), agent:<slug>, source:<id>, tag:<name>; all must match
+        #[arg(long, required = true)]
+        filter: Vec<String>,
+        /// Change a field on every match: workspace=<path> (a workspace filter's prefix is swapped)
+        #[arg(long)]
+        set: Vec<String>,
+        /// Add this tag to every match (repeatable)
+        #[arg(long)]
+        add_tag: Vec<String>,
+        /// Remove this tag from every match (repeatable)
+        #[arg(long)]
+        remove_tag: Vec<String>,
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Drop vectors of deleted or re-embedded messages and rewrite fragmented vector indexes
+    Compact {
+        /// Rewrite an index once this fraction of its vectors is dead (0.0-1.0)
+        #[arg(long, default_value_t = 0.1)]
+        threshold: f64,
+        /// Rewrite every index with dead vectors, regardless of the threshold
+        #[arg(long)]
+        force: bool,
+        /// Report what would be reclaimed without changing any file
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Attach personal notes to messages; search them with `note:<term>`
+    #[command(subcommand)]
+    Note(NoteCommand),
+    /// Remove old sessions from the database, search index and vector indexes.
+    /// Locked sessions are kept.
+    Prune {
+        /// Prune sessions started more than this long ago, e.g. 90d, 18mo, 1y [default: `[storage] retention` in config.toml]
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Only prune sessions from this agent (repeatable)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Report what would be pruned without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Find near-identical conversations (copied session files, retries of the same
+    /// prompt) and interactively merge or delete the duplicates
+    Dedupe {
+        /// Only list the duplicate clusters and their sizes; change nothing
+        #[arg(long)]
+        report: bool,
+        /// List session copies from other sources that indexing merged away
+        #[arg(long, conflicts_with = "report")]
+        merged: bool,
+        /// Prompt similarity (0.0-1.0) at which two attempts count as retries
+        #[arg(long, default_value_t = crate::indexer::dedupe::DEFAULT_THRESHOLD)]
+        threshold: f64,
+        /// Only consider sessions from this agent (repeatable)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Output the report as JSON (implies --report)
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Check the local installation: database, search index and semantic embedder
+    Doctor {
+        /// Only explain which embedder is selected and why
+        #[arg(long)]
+        embedder: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
+    /// Database maintenance
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Scheduled reports configured as `[[reports]]` in config.toml
+    #[command(subcommand)]
+    Report(ReportCommand),
+    /// Show, check and edit settings in config.toml and sources.toml
+    #[command(subcommand)]
+    Config(ConfigCommand),
 }
 
-#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
-pub enum ColorPref {
-    Auto,
-    Never,
-    Always,
-}
-
-#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
-pub enum ProgressMode {
-    Auto,
-    Bars,
-    Plain,
-    None,
-}
-
-#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
-pub enum RobotTopic {
-    Commands,
-    Env,
-    Paths,
-    Schemas,
-    Guide,
-    ExitCodes,
-    Examples,
-    Contracts,
-    Wrap,
-}
-
-/// Output format for robot/automation mode
-#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
-pub enum RobotFormat {
-    /// Pretty-printed JSON object (default, backward compatible)
-    #[default]
-    Json,
-    /// Newline-delimited JSON: one object per line with optional _meta header
-    Jsonl,
-    /// Compact single-line JSON (no pretty printing)
-    Compact,
-    /// Session paths only: one source_path per line (for chained searches)
-    Sessions,
-}
-
-/// Human-readable display format for CLI output (non-JSON)
-#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
-pub enum DisplayFormat {
-    /// Aligned columns with headers (default human-readable)
-    #[default]
-    Table,
-    /// One-liner per result with key info
-    Lines,
-    /// Markdown with role headers and code blocks
-    Markdown,
-}
-
-/// Conversation export format (for export command)
-#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
-pub enum ConvExportFormat {
-    /// Markdown with headers and formatting
-    #[default]
-    Markdown,
-    /// Plain text
-    Text,
-    /// JSON array of messages
-    Json,
-    /// HTML with styling
-    Html,
-}
-
-/// Timeline grouping options
-#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
-pub enum TimelineGrouping {
-    /// Group by hour
-    #[default]
-    Hour,
-    /// Group by day
-    Day,
-    /// No grouping (flat list)
-    None,
+/// Subcommands for scheduled reports
+#[derive(Subcommand, Debug, Clone)]
+pub enum ReportCommand {
+    /// List configured reports with their last and next delivery
+    List {
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render a report now and deliver it to its webhook/command
+    Run {
+        /// Report name from config.toml
+        name: String,
+        /// Print the rendered report instead of delivering it
+        #[arg(long)]
+        print: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-/// Aggregation field types for --aggregate flag
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AggregateField {
-    Agent,
-    Workspace,
-    Date,
-    MatchType,
+/// Subcommands for inspecting and editing configuration
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigCommand {
+    /// Print the effective configuration (config.toml plus resolved paths and
+    /// environment overrides), or the value of one dotted key
+    Get {
+        /// Dotted key such as `storage.max_size` or `data_dir`
+        key: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set one dotted key in config.toml, e.g. `storage.compression true`.
+    /// Comments and layout are kept; the old file is kept as config.toml.bak
+    Set {
+        /// Dotted key such as `storage.max_size`
+        key: String,
+        /// TOML value (`true`, `42`, `["a", "b"]`); anything else is stored as a string
+        value: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check config.toml and sources.toml for syntax errors, wrong types and unknown keys
+    Validate {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print where the config files, data dir and database live
+    Path {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-impl AggregateField {
-    /// Parse field name to enum
-    fn from_str(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "agent" => Some(Self::Agent),
-            "workspace" => Some(Self::Workspace),
-            "date" => Some(Self::Date),
-            "match_type" | "matchtype" => Some(Self::MatchType),
-            _ => None,
-        }
-    }
-
-    /// Get the field name as a string
-    #[allow(dead_code)]
-    fn as_str(&self) -> &'static str {
-        match self {
-            Self::Agent => "agent",
-            Self::Workspace => "workspace",
-            Self::Date => "date",
-            Self::MatchType => "match_type",
-        }
-    }
+/// Subcommands for database maintenance
+#[derive(Subcommand, Debug, Clone)]
+pub enum DbCommand {
+    /// Compact the database and search index: VACUUM, ANALYZE, WAL truncation and
+    /// Tantivy segment merging. Stop the daemon or `index --watch` first.
+    Optimize {
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write conversations, messages, tags, notes and provenance to a portable
+    /// `.cassar` archive (no search index), for backups and machine migration
+    Export {
+        /// Archive to write, e.g. backup.cassar
+        file: PathBuf,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge a `.cassar` archive into the database and search index; conversations
+    /// already present are skipped, ones whose messages differ are conflicts
+    Import {
+        /// Archive written by `cass db export`
+        file: PathBuf,
+        /// How to settle conversations whose messages differ from the local copy
+        /// [default: ask on a terminal, else skip]
+        #[arg(long, value_enum)]
+        on_conflict: Option<crate::storage::archive::ConflictPolicy>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-/// A single bucket in an aggregation result
-#[derive(Debug, Clone, Serialize)]
-pub struct AggregationBucket {
-    /// The grouped key value
-    pub key: String,
-    /// Count of items in this bucket
-    pub count: u64,
+/// Subcommands for message annotations
+#[derive(Subcommand, Debug, Clone)]
+pub enum NoteCommand {
+    /// Add a note to a message (message ids are shown by `cass show`)
+    Add {
+        /// Message id
+        message_id: i64,
+        /// Note text
+        #[arg(required = true)]
+        text: Vec<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Replace the text of a note
+    Edit {
+        /// Note id
+        note_id: i64,
+        /// New note text
+        #[arg(required = true)]
+        text: Vec<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete a note
+    Rm {
+        /// Note id
+        note_id: i64,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the notes in a conversation
+    List {
+        /// Conversation id
+        conversation_id: i64,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-/// Aggregation result for a single field
-#[derive(Debug, Clone, Serialize)]
-pub struct FieldAggregation {
-    /// Top buckets (limited to 10 by default)
-    pub buckets: Vec<AggregationBucket>,
-    /// Total count of items that didn't fit in top buckets
-    pub other_count: u64,
+/// Subcommands for conversation tags
+#[derive(Subcommand, Debug, Clone)]
+pub enum TagCommand {
+    /// Add one or more tags to a conversation
+    Add {
+        /// Conversation id (see `cass show`)
+        conversation_id: i64,
+        /// Tag names (single words; stored lowercase)
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove tags from a conversation
+    Rm {
+        /// Conversation id
+        conversation_id: i64,
+        /// Tag names
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List a conversation's tags; without an id, every tag with its count
+    List {
+        /// Conversation id
+        conversation_id: Option<i64>,
+        /// List the conversations carrying this tag instead
+        #[arg(long, conflicts_with = "conversation_id")]
+        tag: Option<String>,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-/// Container for all aggregation results
-#[derive(Debug, Clone, Default, Serialize)]
-pub struct Aggregations {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub agent: Option<FieldAggregation>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub workspace: Option<FieldAggregation>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub date: Option<FieldAggregation>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub match_type: Option<FieldAggregation>,
-}
-
-impl Aggregations {
-    fn is_empty(&self) -> bool {
-        self.agent.is_none()
-            && self.workspace.is_none()
-            && self.date.is_none()
-            && self.match_type.is_none()
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct CliError {
-    pub code: i32,
-    pub kind: &'static str,
-    pub message: String,
-    pub hint: Option<String>,
-    pub retryable: bool,
-}
-
-pub type CliResult<T = ()> = std::result::Result<T, CliError>;
-
-impl std::fmt::Display for CliError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} (code {})", self.message, self.code)
-    }
+/// Subcommands for `[query_templates]`
+#[derive(Subcommand, Debug, Clone)]
+pub enum QueryTemplateCommand {
+    /// List configured templates and their placeholders
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render a template with placeholder values; --run executes it like `cass search`
+    Render {
+        /// Template name
+        name: String,
+        /// Placeholder value as KEY=VALUE (can be repeated)
+        #[arg(long = "vars", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Execute the rendered query and print the search results
+        #[arg(long)]
+        run: bool,
+        /// Max results (with --run)
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Output as JSON (--robot also works)
+        #[arg(long, visible_alias = "robot")]
+        json: bool,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+    },
 }
 
-impl std::error::Error for CliError {}
-
-impl CliError {
-    fn usage(message: impl Into<String>, hint: Option<String>) -> Self {
-        CliError {
-            code: 2,
-            kind: "usage",
-            message: message.into(),
-            hint,
-            retryable: false,
-        }
-    }
-
-    fn unknown(message: impl Into<String>) -> Self {
-        CliError {
-            code: 9,
-            kind: "unknown",
-            message: message.into(),
-            hint: None,
-            retryable: false,
-        }
-    }
+/// Subcommands for workspace -> project grouping
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProjectsCommand {
+    /// List projects and the workspaces grouped under each
+    List {
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Assign a workspace to a project by hand (auto-detection won't override it)
+    Set {
+        /// Workspace path
+        workspace: PathBuf,
+        /// Project name (e.g. github.com/owner/repo)
+        project: String,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove a workspace's mapping so it is re-detected on the next index
+    Unset {
+        /// Workspace path
+        workspace: PathBuf,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-detect git remotes for every indexed workspace (manual mappings are kept)
+    Refresh {
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum ProgressResolved {
-    Bars,
-    Plain,
-    None,
+/// Subcommands for index statistics
+#[derive(Subcommand, Debug, Clone)]
+pub enum StatsCommand {
+    /// Show per-agent sizes, the largest conversations and database growth
+    Storage {
+        /// Number of largest conversations to list
+        #[arg(long, default_value_t = 10)]
+        largest: usize,
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-struct WrapConfig {
-    width: Option<usize>,
-    nowrap: bool,
+/// Subcommands for the background daemon
+#[derive(Subcommand, Debug, Clone)]
+pub enum DaemonCommand {
+    /// Show whether the daemon is running and when it last reconciled/synced
+    Status {
+        /// Override data dir
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-impl WrapConfig {
-    fn new(width: Option<usize>, nowrap: bool) -> Self {
-        WrapConfig { width, nowrap }
-    }
-
-    fn effective_width(&self) -> Option<usize> {
-        if self.nowrap { None } else { self.width }
-    }
+/// Subcommands for managing remote sources (P5.x)
+#[derive(Subcommand, Debug, Clone)]
+pub enum SourcesCommand {
+    /// List configured sources
+    List {
+        /// Show detailed information
+        #[arg(long, short)]
+        verbose: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a new remote source
+    Add {
+        /// Source URL (e.g., user@host or ssh://user@host)
+        url: String,
+        /// Friendly name for this source (becomes source_id)
+        #[arg(long)]
+        name: Option<String>,
+        /// Use preset paths for platform (macos-defaults, linux-defaults, windows-defaults)
+        #[arg(long)]
+        preset: Option<String>,
+        /// Paths to sync (can be specified multiple times)
+        #[arg(long = "path", short = 'p')]
+        paths: Vec<String>,
+        /// Skip connectivity test
+        #[arg(long)]
+        no_test: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove a configured source
+    Remove {
+        /// Name of source to remove
+        name: String,
+        /// Also delete synced session data from index
+        #[arg(long)]
+        purge: bool,
+        /// Skip confirmation prompt
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Output as JSON (requires --yes)
+        #[arg(long, requires = "yes")]
+        json: bool,
+    },
+    /// Show one source's configuration and its last sync
+    Show {
+        /// Source name
+        name: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that a source's host is reachable, how fast it answers and that its paths exist
+    Test {
+        /// Source name
+        name: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Diagnose source connectivity and configuration issues
+    Doctor {
+        /// Check only specific source (defaults to all)
+        #[arg(long, short)]
+        source: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Synchronize sessions from remote sources
+    Sync {
+        /// Sync only specific source(s)
+        #[arg(long, short)]
+        source: Option<Vec<String>>,
+        /// Don't re-index after sync
+        #[arg(long)]
+        no_index: bool,
+        /// Show detailed transfer information
+        #[arg(long, short)]
+        verbose: bool,
+        /// Dry run - list the files each source would fetch (new, changed, unchanged) without syncing
+        #[arg(long)]
+        dry_run: bool,
+        /// Show each source's last sync, duration, errors and staged size instead of syncing
+        #[arg(long, conflicts_with = "dry_run")]
+        status: bool,
+        /// Cap transfer bandwidth in KiB/s (overrides each source's `bwlimit`)
+        #[arg(long, value_name = "KBPS")]
+        bwlimit: Option<u32>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage path mappings for a source (P6.3)
+    #[command(subcommand)]
+    Mappings(MappingsAction),
+    /// Find cass machines on the LAN (`cass daemon --advertise`) and offer to add them
+    Discover {
+        /// Seconds to listen for announcements
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+        /// Add every new machine without asking
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show how a source's path mappings rewrite a path, and which rule matched
+    MapTest {
+        /// Source name
+        source: String,
+        /// Remote path to rewrite
+        path: String,
+        /// Agent to simulate (for agent-specific rules)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-/// Normalize common robot-mode invocation mistakes to make the CLI more forgiving for AI agents.
-///
-/// This function applies multiple layers of normalization to maximize acceptance of
-/// commands where intent is clear, even if syntax is imperfect:
-///
-/// 1. **Single-dash long flags**: `-robot` → `--robot`, `-limit` → `--limit`
-/// 2. **Case normalization**: `--Robot`, `--LIMIT` → `--robot`, `--limit`
-/// 3. **Subcommand aliases**: `find`/`query`/`q` → `search`, `ls`/`list` → `stats`, etc.
-/// 4. **Flag-as-subcommand**: `--robot-docs` → `robot-docs` subcommand
-/// 5. **Global flag hoisting**: Moves global flags to front regardless of position
-///
-/// Returns normalized argv plus an optional correction note teaching proper syntax.
-fn normalize_args(raw: Vec<String>) -> (Vec<String>, Option<String>) {
-    if raw.is_empty() {
-        return (raw, None);
-    }
-    let prog = &raw[0];
-    let mut globals: Vec<String> = Vec::new();
-    let mut rest: Vec<String> = Vec::new();
-    let mut sub_seen = false;
-    let mut corrections: Vec<String> = Vec::new();
-
-    // Known long flags (without --) for single-dash and case normalization
-    const KNOWN_LONG_FLAGS: &[&str] = &[
-        "robot",
-        "json",
-        "limit",
-        "offset",
-        "agent",
-        "workspace",
-        "fields",
-        "max-tokens",
-        "request-id",
-        "cursor",
-        "since",
-        "until",
-        "days",
-        "today",
-        "week",
-        "full",
-        "watch",
-        "data-dir",
-        "verbose",
-        "quiet",
-        "color",
-        "progress",
-        "wrap",
-        "nowrap",
-        "db",
-        "trace-file",
-        "robot-help",
-        "robot-docs",
-        "help",
-        "version",
-        "force",
-        "dry-run",
-        "no-cache",
-    ];
-
-    // Subcommand aliases for common mistakes
-    const SUBCOMMAND_ALIASES: &[(&str, &str)] = &[
-        // Search aliases
-        ("find", "search"),
-        ("query", "search"),
-        ("q", "search"),
-        ("lookup", "search"),
-        ("grep", "search"),
-        // Stats aliases
-        ("ls", "stats"),
-        ("list", "stats"),
-        ("info", "stats"),
-        ("summary", "stats"),
-        // Status aliases
-        ("st", "status"),
-        ("state", "status"),
-        // Index aliases
-        ("reindex", "index"),
-        ("idx", "index"),
-        ("rebuild", "index"),
-        // View aliases
-        ("show", "view"),
-        ("get", "view"),
-        ("read", "view"),
-        // Diag aliases
-        ("diagnose", "diag"),
-        ("debug", "diag"),
-        ("check", "diag"),
-        // Capabilities aliases
-        ("caps", "capabilities"),
-        ("cap", "capabilities"),
-        // Introspect aliases
-        ("inspect", "introspect"),
-        ("intro", "introspect"),
-        // Robot-docs aliases
-        ("docs", "robot-docs"),
-        ("help-robot", "robot-docs"),
-        ("robotdocs", "robot-docs"),
-    ];
+/// Subcommands for managing path mappings (P6.3)
+#[derive(Subcommand, Debug, Clone)]
+pub enum MappingsAction {
+    /// List path mappings for a source
+    List {
+        /// Source name
+        source: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a path mapping
+    Add {
+        /// Source name
+        source: String,
+        /// Remote path prefix to match
+        #[arg(long)]
+        from: String,
+        /// Local path prefix to replace with
+        #[arg(long)]
+        to: String,
+        /// Only apply to specific agents (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        agents: Option<Vec<String>>,
+    },
+    /// Remove a path mapping by index
+    Remove {
+        /// Source name
+        source: String,
+        /// Index of mapping to remove (from list output, 0-based)
+        index: usize,
+    },
+    /// Test how a path would be rewritten
+    Test {
+        /// Source name
+        source: String,
+        /// Path to test
+        path: String,
+        /// Optional agent to simulate (for agent-specific rules)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
 
-    // Short flags that should remain as single-dash
-    const VALID_SHORT_FLAGS: &[&str] = &["-q", "-v", "-h", "-V"];
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum ColorPref {
+    Auto,
+    Never,
+    Always,
+}
 
-    // Global flags that take a value via separate argument (--flag VALUE)
-    // Note: --data-dir is NOT a global flag - it's per-subcommand
-    let global_with_value = |s: &str| {
-        matches!(
-            s,
-            "--color" | "--progress" | "--wrap" | "--db" | "--trace-file"
-        )
-    };
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum ProgressMode {
+    Auto,
+    Bars,
+    Plain,
+    None,
+}
 
-    // Global flags that take a value via `=` syntax or are standalone
-    // Note: --data-dir is NOT a global flag - it's per-subcommand
-    let is_global = |s: &str| {
-        s == "--color"
-            || s.starts_with("--color=")
-            || s == "--progress"
-            || s.starts_with("--progress=")
-            || s == "--wrap"
-            || s.starts_with("--wrap=")
-            || s == "--nowrap"
-            || s == "--db"
-            || s.starts_with("--db=")
-            || s == "--quiet"
-            || s == "-q"
-            || s == "--verbose"
-            || s == "-v"
-            || s == "--trace-file"
-            || s.starts_with("--trace-file=")
-            || s == "--robot-help"
-    };
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum RobotTopic {
+    Commands,
+    Env,
+    Paths,
+    Schemas,
+    Guide,
+    ExitCodes,
+    Examples,
+    Contracts,
+    Wrap,
+}
 
-    /// Normalize a single argument: single-dash → double-dash, case → lowercase
-    fn normalize_single_arg(arg: &str, corrections: &mut Vec<String>) -> String {
-        // Skip if already valid short flag
-        if VALID_SHORT_FLAGS.contains(&arg) {
-            return arg.to_string();
-        }
+/// Output format for robot/automation mode
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum RobotFormat {
+    /// Pretty-printed JSON object (default, backward compatible)
+    #[default]
+    Json,
+    /// Newline-delimited JSON: one object per line with optional _meta header
+    Jsonl,
+    /// Compact single-line JSON (no pretty printing)
+    Compact,
+    /// Session paths only: one source_path per line (for chained searches)
+    Sessions,
+}
 
-        // Handle single-dash long flags: -robot → --robot, -limit=5 → --limit=5
-        if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 2 {
-            let (flag_part, value_part) = if let Some(idx) = arg.find('=') {
-                (&arg[1..idx], Some(&arg[idx..]))
-            } else {
-                (&arg[1..], None)
-            };
-            let flag_lower = flag_part.to_lowercase();
-            if KNOWN_LONG_FLAGS.contains(&flag_lower.as_str()) {
-                let corrected = if let Some(val) = value_part {
-                    format!("--{flag_lower}{val}")
-                } else {
-                    format!("--{flag_lower}")
-                };
-                corrections.push(format!(
-                    "'{arg}' → '{corrected}' (use double-dash for long flags)"
-                ));
-                return corrected;
-            }
-        }
+/// Message role for `search --role`
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum RoleFilter {
+    User,
+    Assistant,
+    System,
+    Tool,
+}
 
-        // Handle case normalization for double-dash flags: --Robot → --robot
-        if let Some(stripped) = arg.strip_prefix("--") {
-            let (flag_part, value_part) = if let Some(idx) = stripped.find('=') {
-                (&stripped[..idx], Some(&stripped[idx..]))
-            } else {
-                (stripped, None)
-            };
-            let flag_lower = flag_part.to_lowercase();
-            if flag_part != flag_lower && KNOWN_LONG_FLAGS.contains(&flag_lower.as_str()) {
-                let corrected = if let Some(val) = value_part {
-                    format!("--{flag_lower}{val}")
-                } else {
-                    format!("--{flag_lower}")
-                };
-                corrections.push(format!("'{arg}' → '{corrected}' (flags are lowercase)"));
-                return corrected;
-            }
+impl RoleFilter {
+    /// The role as stored on indexed messages
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Assistant => "assistant",
+            Self::System => "system",
+            Self::Tool => "tool",
         }
-
-        arg.to_string()
     }
+}
 
-    let args: Vec<_> = raw.iter().skip(1).collect();
-    let mut i = 0;
-    while i < args.len() {
-        let arg = args[i];
-
-        // First, normalize the argument (single-dash, case)
-        let normalized_arg = normalize_single_arg(arg, &mut corrections);
-
-        // Handle --robot-docs and --robot-docs=topic (flag used as subcommand)
-        if normalized_arg == "--robot-docs" {
-            rest.push("robot-docs".into());
-            corrections
-                .push("'--robot-docs' → 'robot-docs' (it's a subcommand, not a flag)".into());
-            i += 1;
-            continue;
-        }
-        if let Some(topic) = normalized_arg.strip_prefix("--robot-docs=") {
-            rest.push("robot-docs".into());
-            if !topic.is_empty() {
-                rest.push(topic.to_string());
-            }
-            corrections.push(format!(
-                "'{}' → 'robot-docs {topic}' (robot-docs is a subcommand)",
-                arg
-            ));
-            i += 1;
-            continue;
-        }
+/// Human-readable display format for CLI output (non-JSON)
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum DisplayFormat {
+    /// Aligned columns with headers (default human-readable)
+    #[default]
+    Table,
+    /// One-liner per result with key info
+    Lines,
+    /// Markdown with role headers and code blocks
+    Markdown,
+}
 
-        // Check for subcommand aliases (only before first subcommand seen)
-        if !sub_seen && !normalized_arg.starts_with('-') {
-            let lower = normalized_arg.to_lowercase();
-            if let Some(&(alias, canonical)) = SUBCOMMAND_ALIASES
-                .iter()
-                .find(|(a, _)| a.eq_ignore_ascii_case(&lower))
-            {
-                rest.push(canonical.to_string());
-                corrections.push(format!(
-                    "'{alias}' → '{canonical}' (canonical subcommand name)"
-                ));
-                sub_seen = true;
-                i += 1;
-                continue;
-            }
-        }
+/// Conversation export format (for export command)
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum ConvExportFormat {
+    /// Markdown with headers and formatting
+    #[default]
+    Markdown,
+    /// Plain text
+    Text,
+    /// JSON array of messages
+    Json,
+    /// HTML with styling
+    Html,
+}
 
-        // Handle global flags
-        if is_global(&normalized_arg) {
-            globals.push(normalized_arg.clone());
-            // Only note if globals appear after subcommand (moved to front)
-            if sub_seen && !corrections.iter().any(|c| c.contains("moved to front")) {
-                corrections.push("Global flags moved to front of command".into());
-            }
-            // If this global takes a value and doesn't use `=` syntax, consume the next arg
-            if global_with_value(&normalized_arg)
-                && !normalized_arg.contains('=')
-                && i + 1 < args.len()
-                && !args[i + 1].starts_with('-')
-            {
-                globals.push(args[i + 1].to_string());
-                i += 1;
-            }
-            i += 1;
-            continue;
+/// Conversation graph output format (for graph command)
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// JSON nodes and edges
+    Json,
+    /// Obsidian JSON Canvas (.canvas)
+    Canvas,
+}
+
+/// Decision log output format (for decisions command)
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum DecisionsFormat {
+    /// Human-readable list
+    #[default]
+    Text,
+    /// Markdown document grouped by workspace
+    Markdown,
+    /// JSON object keyed by workspace
+    Json,
+}
+
+/// Embedder used by the embed command
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum EmbedderKind {
+    /// MiniLM when the model, CPU and memory allow it, else hash (see `cass doctor --embedder`)
+    #[default]
+    Auto,
+    /// FastEmbed MiniLM (semantic; model files must be present locally)
+    Minilm,
+    /// FNV-1a feature hashing (lexical fallback, no model needed)
+    Hash,
+}
+
+/// Timeline grouping options
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum TimelineGrouping {
+    /// Group by hour
+    #[default]
+    Hour,
+    /// Group by day
+    Day,
+    /// No grouping (flat list)
+    None,
+}
+
+/// Aggregation field types for --aggregate flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateField {
+    Agent,
+    Workspace,
+    Date,
+    MatchType,
+}
+
+impl AggregateField {
+    /// Parse field name to enum
+    fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "agent" => Some(Self::Agent),
+            "workspace" => Some(Self::Workspace),
+            "date" => Some(Self::Date),
+            "match_type" | "matchtype" => Some(Self::MatchType),
+            _ => None,
         }
+    }
 
-        if !sub_seen && !normalized_arg.starts_with('-') {
-            sub_seen = true;
+    /// Get the field name as a string
+    #[allow(dead_code)]
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Agent => "agent",
+            Self::Workspace => "workspace",
+            Self::Date => "date",
+            Self::MatchType => "match_type",
         }
-        rest.push(normalized_arg);
-        i += 1;
     }
+}
 
-    let mut normalized = Vec::with_capacity(1 + globals.len() + rest.len());
-    normalized.push(prog.clone());
-    normalized.extend(globals);
-    normalized.extend(rest);
+/// A single bucket in an aggregation result
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregationBucket {
+    /// The grouped key value
+    pub key: String,
+    /// Count of items in this bucket
+    pub count: u64,
+}
 
-    let note = if corrections.is_empty() {
-        None
-    } else {
-        Some(format!(
-            "Auto-corrected: {}. Canonical form: {}",
-            corrections.join("; "),
-            if normalized.len() > 1 {
-                normalized[1..].join(" ")
-            } else {
-                String::new()
-            }
-        ))
-    };
-    (normalized, note)
+/// Aggregation result for a single field
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldAggregation {
+    /// Top buckets (limited to 10 by default)
+    pub buckets: Vec<AggregationBucket>,
+    /// Total count of items that didn't fit in top buckets
+    pub other_count: u64,
 }
 
-/// Build a friendly parse error with actionable, context-aware examples for AI agents.
-///
-/// This function analyzes what the agent was likely trying to do and provides
-/// targeted examples that match their apparent intent.
-fn format_friendly_parse_error(err: clap::Error, raw: &[String], normalized: &[String]) -> String {
-    let is_robot = raw
-        .iter()
-        .any(|s| s == "--json" || s == "--robot" || s == "-robot" || s == "-json");
+/// Container for all aggregation results
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Aggregations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<FieldAggregation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<FieldAggregation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<FieldAggregation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_type: Option<FieldAggregation>,
+}
 
-    // Detect what the agent was probably trying to do
-    let raw_str = raw.join(" ").to_lowercase();
-    let intent = detect_command_intent(&raw_str);
+impl Aggregations {
+    fn is_empty(&self) -> bool {
+        self.agent.is_none()
+            && self.workspace.is_none()
+            && self.date.is_none()
+            && self.match_type.is_none()
+    }
+}
 
-    if is_robot {
-        let mut err_map = serde_json::Map::new();
-        err_map.insert("status".into(), "error".into());
-        err_map.insert("error".into(), err.to_string().into());
-        err_map.insert("kind".into(), "argument_parsing".into());
+#[derive(Debug, Clone)]
+pub struct CliError {
+    pub code: i32,
+    pub kind: &'static str,
+    pub message: String,
+    pub hint: Option<String>,
+    pub retryable: bool,
+}
 
-        if raw != normalized && normalized.len() > 1 {
-            err_map.insert(
-                "normalized_attempt".into(),
-                normalized[1..].join(" ").into(),
-            );
-        }
+pub type CliResult<T = ()> = std::result::Result<T, CliError>;
 
-        // Context-aware examples based on detected intent
-        let examples = get_contextual_examples(&intent);
-        err_map.insert("examples".into(), serde_json::json!(examples));
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
 
-        // Context-aware hints
-        let hints = get_contextual_hints(&intent, &raw_str);
-        err_map.insert("hints".into(), serde_json::json!(hints));
+impl std::error::Error for CliError {}
 
-        // Common mistakes for this intent
-        if let Some(common_mistakes) = get_common_mistakes(&intent) {
-            err_map.insert("common_mistakes".into(), serde_json::json!(common_mistakes));
+impl CliError {
+    fn usage(message: impl Into<String>, hint: Option<String>) -> Self {
+        CliError {
+            code: 2,
+            kind: "usage",
+            message: message.into(),
+            hint,
+            retryable: false,
         }
-
-        // Quick reference for flags
-        err_map.insert(
-            "flag_syntax".into(),
-            serde_json::json!({
-                "correct": ["--limit 5", "--robot", "--json"],
-                "incorrect": ["-limit 5", "limit=5", "--Limit"]
-            }),
-        );
-
-        return serde_json::to_string_pretty(&err_map).unwrap_or_else(|_| err.to_string());
     }
 
-    // Human-readable format
-    let mut parts = Vec::new();
-    parts.push("Argument parsing failed; command intent unclear.".to_string());
-    parts.push(format!("Error: {err}"));
-    if raw != normalized && normalized.len() > 1 {
-        parts.push(format!(
-            "Attempted normalization: {}",
-            normalized[1..].join(" ")
-        ));
-    }
-    parts.push(String::new());
-    parts.push(format!(
-        "Based on your command, you may be trying to: {intent}"
-    ));
-    parts.push(String::new());
-    parts.push("Correct examples:".to_string());
-    for ex in get_contextual_examples(&intent) {
-        parts.push(format!("  {ex}"));
+    fn unknown(message: impl Into<String>) -> Self {
+        CliError {
+            code: 9,
+            kind: "unknown",
+            message: message.into(),
+            hint: None,
+            retryable: false,
+        }
     }
-    parts.push(String::new());
-    parts.push("Quick syntax reference:".to_string());
-    parts.push("  - Long flags use double-dash: --robot, --limit 5".to_string());
-    parts.push("  - Flag values use space or equals: --limit 5 or --limit=5".to_string());
-    parts.push("  - Subcommands come first: cass search \"query\"".to_string());
-    parts.join("\n")
 }
 
-/// Detect the likely command intent from the raw argument string.
-fn detect_command_intent(raw_str: &str) -> String {
-    if raw_str.contains("search")
-        || raw_str.contains("find")
-        || raw_str.contains("query")
-        || raw_str.contains("grep")
-    {
-        "search for sessions or messages".to_string()
-    } else if raw_str.contains("doc") || raw_str.contains("help") || raw_str.contains("robot") {
-        "get robot-mode documentation".to_string()
-    } else if raw_str.contains("stats") || raw_str.contains("ls") || raw_str.contains("list") {
-        "view statistics or list sessions".to_string()
-    } else if raw_str.contains("index")
-        || raw_str.contains("rebuild")
-        || raw_str.contains("reindex")
-    {
-        "rebuild or manage the search index".to_string()
-    } else if raw_str.contains("view") || raw_str.contains("show") || raw_str.contains("get") {
-        "view a specific session".to_string()
-    } else if raw_str.contains("cap") || raw_str.contains("introspect") {
-        "discover tool capabilities".to_string()
-    } else if raw_str.contains("diag") || raw_str.contains("debug") || raw_str.contains("check") {
-        "run diagnostics".to_string()
-    } else if raw_str.contains("status") {
-        "check status".to_string()
-    } else if raw_str.contains("health") {
-        "run health check".to_string()
-    } else {
-        "run a cass command".to_string()
-    }
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProgressResolved {
+    Bars,
+    Plain,
+    None,
 }
 
-/// Get context-aware examples based on detected intent.
-fn get_contextual_examples(intent: &str) -> Vec<&'static str> {
-    if intent.contains("search") {
-        vec![
-            "cass search \"error handling\" --robot --limit 10",
-            "cass search \"authentication\" --robot --agent claude",
-            "cass search \"database\" --robot --since 2024-01-01",
-            "cass search \"TODO\" --robot --workspace /path/to/project",
-        ]
-    } else if intent.contains("documentation") {
-        vec![
-            "cass robot-docs commands",
-            "cass robot-docs schemas",
-            "cass robot-docs examples",
-            "cass --robot-help",
-        ]
-    } else if intent.contains("statistics") || intent.contains("list") {
-        vec![
-            "cass stats --robot",
-            "cass stats --robot --agent claude",
-            "cass stats --robot --workspace /path",
-            "cass stats --robot --since 2024-01-01",
-        ]
-    } else if intent.contains("index") {
-        vec![
-            "cass index --robot",
-            "cass index --robot --force",
-            "cass index --robot --data-dir /custom/path",
-        ]
-    } else if intent.contains("view") {
-        vec![
-            "cass view <session-id> --robot",
-            "cass view <session-id> --robot --full",
-            "cass view <session-id> --robot --fields content,timestamp",
-        ]
-    } else if intent.contains("capabilities") {
-        vec!["cass capabilities --json", "cass introspect --json"]
-    } else if intent.contains("diagnostics") {
-        vec!["cass diag --robot", "cass diag --robot --verbose"]
-    } else if intent.contains("status") {
-        vec!["cass status --robot", "cass status --robot --watch"]
-    } else if intent.contains("health") {
-        vec!["cass health --json"]
-    } else {
-        vec![
-            "cass --robot-help                    # Get robot-mode documentation",
-            "cass search \"query\" --robot         # Search sessions",
-            "cass capabilities --json             # Discover capabilities",
-            "cass stats --robot                   # View statistics",
-        ]
-    }
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct WrapConfig {
+    width: Option<usize>,
+    nowrap: bool,
 }
 
-/// Get context-aware hints based on detected intent and raw command.
-fn get_contextual_hints(intent: &str, raw_str: &str) -> Vec<String> {
-    let mut hints = Vec::new();
-
-    // Check for common syntax mistakes
-    if raw_str.contains("-robot") && !raw_str.contains("--robot") {
-        hints.push("Use '--robot' (double-dash), not '-robot'".to_string());
-    }
-    if raw_str.contains("-json") && !raw_str.contains("--json") {
-        hints.push("Use '--json' (double-dash), not '-json'".to_string());
-    }
-    // Only flag bare `limit=` without leading dash as problematic
-    if (raw_str.contains(" limit=") || raw_str.starts_with("limit="))
-        && !raw_str.contains("--limit=")
-        && !raw_str.contains("-limit=")
-    {
-        hints.push("Use '--limit 5' or '--limit=5', not 'limit=5'".to_string());
-    }
-    if raw_str.contains("--robot-docs") {
-        hints.push(
-            "'robot-docs' is a subcommand: use 'cass robot-docs' not 'cass --robot-docs'"
-                .to_string(),
-        );
-    }
-
-    // Intent-specific hints
-    if intent.contains("search") && !raw_str.contains("search") {
-        hints.push(
-            "Use the 'search' subcommand explicitly: cass search \"your query\" --robot"
-                .to_string(),
-        );
+impl WrapConfig {
+    fn new(width: Option<usize>, nowrap: bool) -> Self {
+        WrapConfig { width, nowrap }
     }
 
-    if hints.is_empty() {
-        hints.push(format!("For {intent}, try: cass --robot-help"));
+    fn effective_width(&self) -> Option<usize> {
+        if self.nowrap { None } else { self.width }
     }
-
-    hints
 }
 
-/// Get common mistakes for a given intent.
+/// Normalize common robot-mode invocation mistakes to make the CLI more forgiving for AI agents.
 ///
-/// Note: Only include mistakes that would actually fail after normalization.
-/// Commands that get auto-corrected and succeed (like `cass ls --robot` → `cass stats --robot`)
-/// should NOT be listed here since the user would never see this error message.
-fn get_common_mistakes(intent: &str) -> Option<serde_json::Value> {
-    let mistakes = if intent.contains("search") {
-        vec![
-            // query="foo" without subcommand - normalization adds "search" but the syntax is wrong
-            ("cass query=\"foo\" --robot", "cass search \"foo\" --robot"),
-            // Bare limit= without dashes
-            (
-                "cass search \"query\" limit=5",
-                "cass search \"query\" --limit 5",
-            ),
-            // Missing query entirely
-            (
-                "cass search --robot --limit 5",
-                "cass search \"your query\" --robot --limit 5",
-            ),
-        ]
-    } else if intent.contains("documentation") {
-        vec![
-            // Flag syntax for subcommand (--robot-docs gets normalized but shown for education)
-            ("cass --robot-docs", "cass robot-docs"),
-            ("cass --robot-docs=commands", "cass robot-docs commands"),
-            // Adding --robot to robot-docs (which doesn't accept it)
-            ("cass robot-docs --robot", "cass robot-docs"),
-        ]
-    } else if intent.contains("statistics") {
-        // Note: `cass ls --robot` actually works (normalizes to `cass stats --robot`)
-        // so we show mistakes that would actually fail
-        vec![
-            // Missing required output flag for piping
-            ("cass stats | jq .", "cass stats --json | jq ."),
-        ]
-    } else {
-        return None;
-    };
-
-    Some(serde_json::json!(
-        mistakes
-            .iter()
-            .map(|(wrong, right)| { serde_json::json!({"wrong": wrong, "correct": right}) })
-            .collect::<Vec<_>>()
-    ))
-}
-
-/// Heuristic recovery for command-line errors to help agents.
-/// Returns `(corrected_args, correction_note)` if a likely intent is found.
-fn heuristic_parse_recovery(
-    err: &clap::Error,
-    raw_args: &[String],
-) -> Option<(Vec<String>, String)> {
-    // Only attempt recovery for "unknown argument" or "unrecognized subcommand" errors
-    let is_unknown = err.kind() == clap::error::ErrorKind::UnknownArgument
-        || err.kind() == clap::error::ErrorKind::InvalidSubcommand;
-
-    if !is_unknown || raw_args.len() < 2 {
-        return None;
+/// This function applies multiple layers of normalization to maximize acceptance of
+/// commands where intent is clear, even if syntax is imperfect:
+///
+/// 1. **Single-dash long flags**: `-robot` → `--robot`, `-limit` → `--limit`
+/// 2. **Case normalization**: `--Robot`, `--LIMIT` → `--robot`, `--limit`
+/// 3. **Subcommand aliases**: `find`/`query`/`q` → `search`, `ls`/`list` → `stats`, etc.
+/// 4. **Flag-as-subcommand**: `--robot-docs` → `robot-docs` subcommand
+/// 5. **Global flag hoisting**: Moves global flags to front regardless of position
+///
+/// Returns normalized argv plus an optional correction note teaching proper syntax.
+fn normalize_args(raw: Vec<String>) -> (Vec<String>, Option<String>) {
+    if raw.is_empty() {
+        return (raw, None);
     }
+    let prog = &raw[0];
+    let mut globals: Vec<String> = Vec::new();
+    let mut rest: Vec<String> = Vec::new();
+    let mut sub_seen = false;
+    let mut corrections: Vec<String> = Vec::new();
 
-    let prog = &raw_args[0];
-    let args = &raw_args[1..];
-    let mut corrected = Vec::new();
-    corrected.push(prog.clone());
-
-    let mut made_correction = false;
-    let mut notes = Vec::new();
-
-    // 1. Detect implicit "search" subcommand
-    // If the first arg isn't a known subcommand or flag, and looks like a query, assume "search".
-    let known_cmds = [
-        "search",
-        "index",
-        "stats",
-        "status",
-        "diag",
-        "view",
-        "capabilities",
-        "introspect",
+    // Known long flags (without --) for single-dash and case normalization
+    const KNOWN_LONG_FLAGS: &[&str] = &[
+        "robot",
+        "json",
+        "limit",
+        "offset",
+        "agent",
+        "workspace",
+        "fields",
+        "max-tokens",
+        "request-id",
+        "cursor",
+        "since",
+        "until",
+        "days",
+        "today",
+        "week",
+        "full",
+        "watch",
+        "gc",
+        "data-dir",
+        "verbose",
+        "quiet",
+        "color",
+        "progress",
+        "wrap",
+        "nowrap",
+        "db",
+        "profile",
+        "trace-file",
+        "robot-help",
         "robot-docs",
-        "tui",
         "help",
-        "--help",
-        "-h",
-        "--version",
-        "-V",
+        "version",
+        "force",
+        "dry-run",
+        "no-cache",
     ];
-    if !args.is_empty() && !args[0].starts_with('-') && !known_cmds.contains(&args[0].as_str()) {
-        corrected.push("search".to_string());
-        // If the arg looks like `query="foo"`, strip the key
-        if args[0].starts_with("query=") || args[0].starts_with("q=") {
-            let val = args[0].split_once('=').map(|(_, v)| v).unwrap_or(&args[0]);
-            corrected.push(val.to_string());
-            notes.push(format!(
-                "Assumed 'search' subcommand and stripped query key from '{}'",
-                args[0]
-            ));
-        } else {
-            corrected.push(args[0].clone());
-            notes.push(format!(
-                "Assumed 'search' subcommand for positional argument '{}'",
-                args[0]
-            ));
-        }
-        made_correction = true;
-        corrected.extend_from_slice(&args[1..]);
-    } else {
-        // Just copy original structure to start
-        corrected.extend_from_slice(args);
-    }
 
-    // 2. Fuzzy match flags and fix key=value syntax
-    let mut final_args = Vec::new();
-    final_args.push(corrected[0].clone()); // prog
+    // Subcommand aliases for common mistakes
+    const SUBCOMMAND_ALIASES: &[(&str, &str)] = &[
+        // Search aliases
+        ("find", "search"),
+        ("query", "search"),
+        ("q", "search"),
+        ("lookup", "search"),
+        ("grep", "search"),
+        // Stats aliases
+        ("ls", "stats"),
+        ("list", "stats"),
+        ("info", "stats"),
+        ("summary", "stats"),
+        // Status aliases
+        ("st", "status"),
+        ("state", "status"),
+        // Index aliases
+        ("reindex", "index"),
+        ("idx", "index"),
+        ("rebuild", "index"),
+        // View aliases
+        ("show", "view"),
+        ("get", "view"),
+        ("read", "view"),
+        // Diag aliases
+        ("diagnose", "diag"),
+        ("debug", "diag"),
+        ("check", "diag"),
+        // Capabilities aliases
+        ("caps", "capabilities"),
+        ("cap", "capabilities"),
+        // Introspect aliases
+        ("inspect", "introspect"),
+        ("intro", "introspect"),
+        // Robot-docs aliases
+        ("docs", "robot-docs"),
+        ("help-robot", "robot-docs"),
+        ("robotdocs", "robot-docs"),
+    ];
 
-    for arg in corrected.iter().skip(1) {
-        if arg.starts_with("--") {
-            // Split --flag=value or --flag
-            let (flag, value) = if let Some((f, v)) = arg.split_once('=') {
-                (f, Some(v))
-            } else {
-                (arg.as_str(), None)
-            };
+    // Short flags that should remain as single-dash
+    const VALID_SHORT_FLAGS: &[&str] = &["-q", "-v", "-h", "-V"];
 
-            // Known flags for fuzzy matching
-            let known_flags = [
-                "--robot",
-                "--json",
-                "--limit",
-                "--offset",
-                "--agent",
-                "--workspace",
-                "--fields",
-                "--max-tokens",
-                "--request-id",
-                "--cursor",
-                "--since",
-                "--until",
-                "--days",
-                "--today",
-                "--week",
-                "--full",
-                "--watch",
-                "--data-dir",
-                "--verbose",
-                "--quiet",
-            ];
+    // Global flags that take a value via separate argument (--flag VALUE)
+    // Note: --data-dir is NOT a global flag - it's per-subcommand
+    let global_with_value = |s: &str| {
+        matches!(
+            s,
+            "--color" | "--progress" | "--wrap" | "--db" | "--profile" | "--trace-file"
+        )
+    };
 
-            // Check for exact match
-            if known_flags.contains(&flag) {
-                final_args.push(arg.clone());
-                continue;
-            }
+    // Global flags that take a value via `=` syntax or are standalone
+    // Note: --data-dir is NOT a global flag - it's per-subcommand
+    let is_global = |s: &str| {
+        s == "--color"
+            || s.starts_with("--color=")
+            || s == "--progress"
+            || s.starts_with("--progress=")
+            || s == "--wrap"
+            || s.starts_with("--wrap=")
+            || s == "--nowrap"
+            || s == "--db"
+            || s.starts_with("--db=")
+            || s == "--profile"
+            || s.starts_with("--profile=")
+            || s == "--quiet"
+            || s == "-q"
+            || s == "--verbose"
+            || s == "-v"
+            || s == "--trace-file"
+            || s.starts_with("--trace-file=")
+            || s == "--robot-help"
+    };
 
-            // Check for typos (levenshtein distance <= 2)
-            let best_match = known_flags
-                .iter()
-                .min_by_key(|k| strsim::levenshtein(flag, k))
-                .filter(|k| strsim::levenshtein(flag, k) <= 2);
+    /// Normalize a single argument: single-dash → double-dash, case → lowercase
+    fn normalize_single_arg(arg: &str, corrections: &mut Vec<String>) -> String {
+        // Skip if already valid short flag
+        if VALID_SHORT_FLAGS.contains(&arg) {
+            return arg.to_string();
+        }
 
-            if let Some(&correction) = best_match {
-                if let Some(v) = value {
-                    final_args.push(format!("{correction}={v}"));
-                } else {
-                    final_args.push(correction.to_string());
-                }
-                notes.push(format!("Corrected typo '{flag}' to '{correction}'"));
-                made_correction = true;
+        // Handle single-dash long flags: -robot → --robot, -limit=5 → --limit=5
+        if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 2 {
+            let (flag_part, value_part) = if let Some(idx) = arg.find('=') {
+                (&arg[1..idx], Some(&arg[idx..]))
             } else {
-                // Keep as is if no good guess
-                final_args.push(arg.clone());
-            }
-        } else if arg.contains('=') && !arg.starts_with('-') {
-            // 3. Handle `limit=5` (missing --)
-            let (key, val) = arg.split_once('=').unwrap();
-            let flag_candidate = format!("--{key}");
-            // Quick check if adding -- makes it a valid flag
-            let known_flags = ["--limit", "--offset", "--agent", "--workspace", "--days"];
-            if known_flags.contains(&flag_candidate.as_str()) {
-                final_args.push(flag_candidate);
-                final_args.push(val.to_string());
-                notes.push(format!(
-                    "Interpreted '{arg}' as flag '{key}' with value '{val}'"
+                (&arg[1..], None)
+            };
+            let flag_lower = flag_part.to_lowercase();
+            if KNOWN_LONG_FLAGS.contains(&flag_lower.as_str()) {
+                let corrected = if let Some(val) = value_part {
+                    format!("--{flag_lower}{val}")
+                } else {
+                    format!("--{flag_lower}")
+                };
+                corrections.push(format!(
+                    "'{arg}' → '{corrected}' (use double-dash for long flags)"
                 ));
-                made_correction = true;
+                return corrected;
+            }
+        }
+
+        // Handle case normalization for double-dash flags: --Robot → --robot
+        if let Some(stripped) = arg.strip_prefix("--") {
+            let (flag_part, value_part) = if let Some(idx) = stripped.find('=') {
+                (&stripped[..idx], Some(&stripped[idx..]))
             } else {
-                final_args.push(arg.clone());
+                (stripped, None)
+            };
+            let flag_lower = flag_part.to_lowercase();
+            if flag_part != flag_lower && KNOWN_LONG_FLAGS.contains(&flag_lower.as_str()) {
+                let corrected = if let Some(val) = value_part {
+                    format!("--{flag_lower}{val}")
+                } else {
+                    format!("--{flag_lower}")
+                };
+                corrections.push(format!("'{arg}' → '{corrected}' (flags are lowercase)"));
+                return corrected;
             }
-        } else {
-            final_args.push(arg.clone());
         }
-    }
 
-    if made_correction {
-        Some((final_args, notes.join("; ")))
-    } else {
-        None
+        arg.to_string()
     }
-}
 
-pub async fn run() -> CliResult<()> {
-    let raw_args: Vec<String> = std::env::args().collect();
-    // First normalization pass (global flags lift)
-    let (normalized_args, parse_note) = normalize_args(raw_args.clone());
+    let args: Vec<_> = raw.iter().skip(1).collect();
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i];
 
-    let (cli, heuristic_note) = match Cli::try_parse_from(&normalized_args) {
-        Ok(cli) => (cli, None),
-        Err(err) => {
-            // Let clap handle help/version natively (exit 0, print to stdout)
-            use clap::error::ErrorKind;
-            if matches!(
-                err.kind(),
-                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
-            ) {
-                err.exit();
+        // First, normalize the argument (single-dash, case)
+        let normalized_arg = normalize_single_arg(arg, &mut corrections);
+
+        // Handle --robot-docs and --robot-docs=topic (flag used as subcommand)
+        if normalized_arg == "--robot-docs" {
+            rest.push("robot-docs".into());
+            corrections
+                .push("'--robot-docs' → 'robot-docs' (it's a subcommand, not a flag)".into());
+            i += 1;
+            continue;
+        }
+        if let Some(topic) = normalized_arg.strip_prefix("--robot-docs=") {
+            rest.push("robot-docs".into());
+            if !topic.is_empty() {
+                rest.push(topic.to_string());
             }
-            // Attempt heuristic recovery
-            if let Some((recovered_args, note)) = heuristic_parse_recovery(&err, &normalized_args) {
-                // Try parsing again with recovered args
-                match Cli::try_parse_from(&recovered_args) {
-                    Ok(cli) => (cli, Some(note)),
-                    Err(retry_err) => {
-                        // Check again for help/version in case recovered args triggered it
-                        if matches!(
-                            retry_err.kind(),
-                            ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
-                        ) {
-                            retry_err.exit();
-                        }
-                        // Recovery failed to produce valid args, fail with original error + friendly help
-                        let friendly =
-                            format_friendly_parse_error(err, &raw_args, &normalized_args);
-                        return Err(CliError::usage("Could not parse arguments", Some(friendly)));
-                    }
-                }
-            } else {
-                // No recovery possible
-                let friendly = format_friendly_parse_error(err, &raw_args, &normalized_args);
-                return Err(CliError::usage("Could not parse arguments", Some(friendly)));
+            corrections.push(format!(
+                "'{}' → 'robot-docs {topic}' (robot-docs is a subcommand)",
+                arg
+            ));
+            i += 1;
+            continue;
+        }
+
+        // Check for subcommand aliases (only before first subcommand seen)
+        if !sub_seen && !normalized_arg.starts_with('-') {
+            let lower = normalized_arg.to_lowercase();
+            if let Some(&(alias, canonical)) = SUBCOMMAND_ALIASES
+                .iter()
+                .find(|(a, _)| a.eq_ignore_ascii_case(&lower))
+            {
+                rest.push(canonical.to_string());
+                corrections.push(format!(
+                    "'{alias}' → '{canonical}' (canonical subcommand name)"
+                ));
+                sub_seen = true;
+                i += 1;
+                continue;
             }
         }
-    };
 
-    let stdout_is_tty = io::stdout().is_terminal();
-    let stderr_is_tty = io::stderr().is_terminal();
-    configure_color(cli.color, stdout_is_tty, stderr_is_tty);
+        // Handle global flags
+        if is_global(&normalized_arg) {
+            globals.push(normalized_arg.clone());
+            // Only note if globals appear after subcommand (moved to front)
+            if sub_seen && !corrections.iter().any(|c| c.contains("moved to front")) {
+                corrections.push("Global flags moved to front of command".into());
+            }
+            // If this global takes a value and doesn't use `=` syntax, consume the next arg
+            if global_with_value(&normalized_arg)
+                && !normalized_arg.contains('=')
+                && i + 1 < args.len()
+                && !args[i + 1].starts_with('-')
+            {
+                globals.push(args[i + 1].to_string());
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
 
-    let wrap_cfg = WrapConfig::new(cli.wrap, cli.nowrap);
-    let progress_resolved = resolve_progress(cli.progress, stdout_is_tty);
+        if !sub_seen && !normalized_arg.starts_with('-') {
+            sub_seen = true;
+        }
+        rest.push(normalized_arg);
+        i += 1;
+    }
 
-    let start_ts = Utc::now();
-    let start_instant = Instant::now();
-    let command_label = describe_command(&cli);
+    let mut normalized = Vec::with_capacity(1 + globals.len() + rest.len());
+    normalized.push(prog.clone());
+    normalized.extend(globals);
+    normalized.extend(rest);
 
-    // Output correction notices for AI agents
-    // These teach the agent proper syntax while still honoring their intent
-    // Detect robot mode from raw args (more reliable than pattern matching complex enums)
-    let is_robot_mode = raw_args
-        .iter()
-        .any(|s| s == "--json" || s == "--robot" || s == "-json" || s == "-robot")
-        || matches!(&cli.command, Some(Commands::Capabilities { .. }))
-        || matches!(&cli.command, Some(Commands::Introspect { .. }));
-    let is_doc_mode = cli.robot_help || matches!(&cli.command, Some(Commands::RobotDocs { .. }));
+    let note = if corrections.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "Auto-corrected: {}. Canonical form: {}",
+            corrections.join("; "),
+            if normalized.len() > 1 {
+                normalized[1..].join(" ")
+            } else {
+                String::new()
+            }
+        ))
+    };
+    (normalized, note)
+}
 
-    // Combine all correction notes
-    let all_notes: Vec<&str> = [parse_note.as_deref(), heuristic_note.as_deref()]
-        .into_iter()
-        .flatten()
-        .collect();
+/// Build a friendly parse error with actionable, context-aware examples for AI agents.
+///
+/// This function analyzes what the agent was likely trying to do and provides
+/// targeted examples that match their apparent intent.
+fn format_friendly_parse_error(err: clap::Error, raw: &[String], normalized: &[String]) -> String {
+    let is_robot = raw
+        .iter()
+        .any(|s| s == "--json" || s == "--robot" || s == "-robot" || s == "-json");
 
-    // Suppress correction chatter for robot/doc modes; still show for humans
-    if !all_notes.is_empty() && !is_doc_mode && !is_robot_mode {
-        // Human-readable correction notice
-        eprintln!("Note: Your command was auto-corrected:");
-        for note in &all_notes {
-            eprintln!("  • {note}");
-        }
-        eprintln!("Tip: Run 'cass --help' for proper syntax.");
-    }
+    // Detect what the agent was probably trying to do
+    let raw_str = raw.join(" ").to_lowercase();
+    let intent = detect_command_intent(&raw_str);
 
-    let result = execute_cli(
-        &cli,
-        wrap_cfg,
-        progress_resolved,
-        stdout_is_tty,
-        stderr_is_tty,
-    )
-    .await;
+    if is_robot {
+        let mut err_map = serde_json::Map::new();
+        err_map.insert("status".into(), "error".into());
+        err_map.insert("error".into(), err.to_string().into());
+        err_map.insert("kind".into(), "argument_parsing".into());
 
-    if let Some(path) = &cli.trace_file {
-        let duration_ms = start_instant.elapsed().as_millis();
-        let exit_code = result.as_ref().map_or_else(|e| e.code, |()| 0);
-        if let Err(trace_err) = write_trace_line(
-            path,
-            &command_label,
-            &cli,
-            &start_ts,
-            duration_ms,
-            exit_code,
-            result.as_ref().err(),
-        ) {
-            eprintln!("trace-write error: {trace_err}");
+        if raw != normalized && normalized.len() > 1 {
+            err_map.insert(
+                "normalized_attempt".into(),
+                normalized[1..].join(" ").into(),
+            );
         }
-    }
 
-    result
-}
+        // Context-aware examples based on detected intent
+        let examples = get_contextual_examples(&intent);
+        err_map.insert("examples".into(), serde_json::json!(examples));
 
-async fn execute_cli(
-    cli: &Cli,
-    wrap: WrapConfig,
-    progress: ProgressResolved,
-    stdout_is_tty: bool,
-    stderr_is_tty: bool,
-) -> CliResult<()> {
-    let command = cli.command.clone().unwrap_or(Commands::Tui {
-        once: false,
-        reset_state: false,
-        data_dir: None,
-    });
+        // Context-aware hints
+        let hints = get_contextual_hints(&intent, &raw_str);
+        err_map.insert("hints".into(), serde_json::json!(hints));
 
-    if cli.robot_help {
-        print_robot_help(wrap)?;
-        return Ok(());
-    }
+        // Common mistakes for this intent
+        if let Some(common_mistakes) = get_common_mistakes(&intent) {
+            err_map.insert("common_mistakes".into(), serde_json::json!(common_mistakes));
+        }
 
-    if let Commands::RobotDocs { topic } = command.clone() {
-        print_robot_docs(topic, wrap)?;
-        return Ok(());
+        // Quick reference for flags
+        err_map.insert(
+            "flag_syntax".into(),
+            serde_json::json!({
+                "correct": ["--limit 5", "--robot", "--json"],
+                "incorrect": ["-limit 5", "limit=5", "--Limit"]
+            }),
+        );
+
+        return serde_json::to_string_pretty(&err_map).unwrap_or_else(|_| err.to_string());
     }
 
-    // Block TUI in non-TTY contexts unless TUI_HEADLESS is set (for testing)
-    if matches!(command, Commands::Tui { .. })
-        && !stdout_is_tty
-        && std::env::var("TUI_HEADLESS").is_err()
-    {
-        return Err(CliError::usage(
-            "No subcommand provided; in non-TTY contexts TUI is disabled.",
-            Some("Use an explicit subcommand, e.g., `cass search --json ...` or `cass --robot-help`.".to_string()),
+    // Human-readable format
+    let mut parts = Vec::new();
+    parts.push("Argument parsing failed; command intent unclear.".to_string());
+    parts.push(format!("Error: {err}"));
+    if raw != normalized && normalized.len() > 1 {
+        parts.push(format!(
+            "Attempted normalization: {}",
+            normalized[1..].join(" ")
         ));
     }
-
-    // Auto-quiet in robot mode: suppress INFO logs for clean JSON output
-    // This ensures AI agents get parseable stdout without log noise on stderr
-    let robot_mode = is_robot_mode(&command);
-    let filter = if cli.quiet || robot_mode {
-        // Robot mode implies quiet unless verbose is explicitly requested
-        if cli.verbose {
-            EnvFilter::new("debug")
-        } else {
-            EnvFilter::new("warn")
-        }
-    } else if cli.verbose {
-        EnvFilter::new("debug")
+    parts.push(String::new());
+    parts.push(format!(
+        "Based on your command, you may be trying to: {intent}"
+    ));
+    parts.push(String::new());
+    parts.push("Correct examples:".to_string());
+    for ex in get_contextual_examples(&intent) {
+        parts.push(format!("  {ex}"));
+    }
+    parts.push(String::new());
+    parts.push("Quick syntax reference:".to_string());
+    parts.push("  - Long flags use double-dash: --robot, --limit 5".to_string());
+    parts.push("  - Flag values use space or equals: --limit 5 or --limit=5".to_string());
+    parts.push("  - Subcommands come first: cass search \"query\"".to_string());
+    parts.join("\n")
+}
+
+/// Detect the likely command intent from the raw argument string.
+fn detect_command_intent(raw_str: &str) -> String {
+    if raw_str.contains("search")
+        || raw_str.contains("find")
+        || raw_str.contains("query")
+        || raw_str.contains("grep")
+    {
+        "search for sessions or messages".to_string()
+    } else if raw_str.contains("doc") || raw_str.contains("help") || raw_str.contains("robot") {
+        "get robot-mode documentation".to_string()
+    } else if raw_str.contains("stats") || raw_str.contains("ls") || raw_str.contains("list") {
+        "view statistics or list sessions".to_string()
+    } else if raw_str.contains("index")
+        || raw_str.contains("rebuild")
+        || raw_str.contains("reindex")
+    {
+        "rebuild or manage the search index".to_string()
+    } else if raw_str.contains("view") || raw_str.contains("show") || raw_str.contains("get") {
+        "view a specific session".to_string()
+    } else if raw_str.contains("cap") || raw_str.contains("introspect") {
+        "discover tool capabilities".to_string()
+    } else if raw_str.contains("diag") || raw_str.contains("debug") || raw_str.contains("check") {
+        "run diagnostics".to_string()
+    } else if raw_str.contains("status") {
+        "check status".to_string()
+    } else if raw_str.contains("health") {
+        "run health check".to_string()
     } else {
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
-    };
+        "run a cass command".to_string()
+    }
+}
 
-    match &command {
-        Commands::Tui { data_dir, .. } => {
-            let log_dir = data_dir.clone().unwrap_or_else(default_data_dir);
-            std::fs::create_dir_all(&log_dir).ok();
+/// Get context-aware examples based on detected intent.
+fn get_contextual_examples(intent: &str) -> Vec<&'static str> {
+    if intent.contains("search") {
+        vec![
+            "cass search \"error handling\" --robot --limit 10",
+            "cass search \"authentication\" --robot --agent claude",
+            "cass search \"database\" --robot --since 2024-01-01",
+            "cass search \"TODO\" --robot --workspace /path/to/project",
+        ]
+    } else if intent.contains("documentation") {
+        vec![
+            "cass robot-docs commands",
+            "cass robot-docs schemas",
+            "cass robot-docs examples",
+            "cass --robot-help",
+        ]
+    } else if intent.contains("statistics") || intent.contains("list") {
+        vec![
+            "cass stats --robot",
+            "cass stats --robot --agent claude",
+            "cass stats --robot --workspace /path",
+            "cass stats --robot --since 2024-01-01",
+        ]
+    } else if intent.contains("index") {
+        vec![
+            "cass index --robot",
+            "cass index --robot --force",
+            "cass index --robot --data-dir /custom/path",
+        ]
+    } else if intent.contains("view") {
+        vec![
+            "cass view <session-id> --robot",
+            "cass view <session-id> --robot --full",
+            "cass view <session-id> --robot --fields content,timestamp",
+        ]
+    } else if intent.contains("capabilities") {
+        vec!["cass capabilities --json", "cass introspect --json"]
+    } else if intent.contains("diagnostics") {
+        vec!["cass diag --robot", "cass diag --robot --verbose"]
+    } else if intent.contains("status") {
+        vec!["cass status --robot", "cass status --robot --watch"]
+    } else if intent.contains("health") {
+        vec!["cass health --json"]
+    } else {
+        vec![
+            "cass --robot-help                    # Get robot-mode documentation",
+            "cass search \"query\" --robot         # Search sessions",
+            "cass capabilities --json             # Discover capabilities",
+            "cass stats --robot                   # View statistics",
+        ]
+    }
+}
 
-            let file_appender = tracing_appender::rolling::daily(&log_dir, "cass.log");
-            let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+/// Get context-aware hints based on detected intent and raw command.
+fn get_contextual_hints(intent: &str, raw_str: &str) -> Vec<String> {
+    let mut hints = Vec::new();
 
-            tracing_subscriber::registry()
-                .with(filter)
-                .with(
-                    tracing_subscriber::fmt::layer()
-                        .with_writer(non_blocking)
-                        .compact()
-                        .with_target(false)
-                        .with_ansi(false),
-                )
-                .init();
+    // Check for common syntax mistakes
+    if raw_str.contains("-robot") && !raw_str.contains("--robot") {
+        hints.push("Use '--robot' (double-dash), not '-robot'".to_string());
+    }
+    if raw_str.contains("-json") && !raw_str.contains("--json") {
+        hints.push("Use '--json' (double-dash), not '-json'".to_string());
+    }
+    // Only flag bare `limit=` without leading dash as problematic
+    if (raw_str.contains(" limit=") || raw_str.starts_with("limit="))
+        && !raw_str.contains("--limit=")
+        && !raw_str.contains("-limit=")
+    {
+        hints.push("Use '--limit 5' or '--limit=5', not 'limit=5'".to_string());
+    }
+    if raw_str.contains("--robot-docs") {
+        hints.push(
+            "'robot-docs' is a subcommand: use 'cass robot-docs' not 'cass --robot-docs'"
+                .to_string(),
+        );
+    }
 
-            maybe_prompt_for_update(matches!(command, Commands::Tui { once: true, .. }))
-                .await
-                .map_err(|e| CliError {
-                    code: 9,
-                    kind: "update-check",
-                    message: format!("update check failed: {e}"),
-                    hint: None,
-                    retryable: false,
-                })?;
+    // Intent-specific hints
+    if intent.contains("search") && !raw_str.contains("search") {
+        hints.push(
+            "Use the 'search' subcommand explicitly: cass search \"your query\" --robot"
+                .to_string(),
+        );
+    }
 
-            if let Commands::Tui {
-                once: false,
-                reset_state,
-                data_dir,
-                ..
-            } = command.clone()
-            {
-                let bg_data_dir = log_dir.clone();
-                let bg_db = cli.db.clone();
-                // Create shared progress tracker
-                let progress = std::sync::Arc::new(indexer::IndexingProgress::default());
-                spawn_background_indexer(bg_data_dir, bg_db, Some(progress.clone()));
+    if hints.is_empty() {
+        hints.push(format!("For {intent}, try: cass --robot-help"));
+    }
 
-                ui::tui::run_tui(data_dir, false, reset_state, Some(progress), None).map_err(
-                    |e| CliError {
-                        code: 9,
-                        kind: "tui",
-                        message: format!("tui failed: {e}"),
-                        hint: None,
-                        retryable: false,
-                    },
-                )?;
-            } else if let Commands::Tui {
-                once,
-                reset_state,
-                data_dir,
-                ..
-            } = command.clone()
-            {
-                ui::tui::run_tui(data_dir, once, reset_state, None, None).map_err(|e| {
-                    CliError {
-                        code: 9,
-                        kind: "tui",
-                        message: format!("tui failed: {e}"),
-                        hint: None,
-                        retryable: false,
-                    }
-                })?;
-            }
-        }
-        Commands::Index { .. }
-        | Commands::Search { .. }
-        | Commands::Stats { .. }
-        | Commands::Diag { .. }
-        | Commands::Status { .. }
-        | Commands::View { .. } => {
-            tracing_subscriber::fmt()
-                .with_env_filter(filter)
-                .with_writer(std::io::stderr)
-                .compact()
-                .with_target(false)
-                .with_ansi(
-                    matches!(cli.color, ColorPref::Always)
-                        || (matches!(cli.color, ColorPref::Auto) && stderr_is_tty),
-                )
-                .init();
+    hints
+}
 
-            match command {
-                Commands::Index {
-                    full,
-                    force_rebuild,
-                    watch,
-                    watch_once,
-                    data_dir,
-                    json,
-                    idempotency_key,
-                } => {
-                    run_index_with_data(
-                        cli.db.clone(),
-                        full,
-                        force_rebuild,
-                        watch,
-                        watch_once,
-                        data_dir,
-                        progress,
-                        json,
-                        idempotency_key,
-                    )?;
-                }
-                Commands::Search {
-                    query,
-                    agent,
-                    workspace,
-                    limit,
-                    offset,
-                    json,
-                    robot_format,
-                    robot_meta,
-                    fields,
-                    max_content_length,
-                    max_tokens,
-                    request_id,
-                    cursor,
-                    display,
-                    data_dir,
-                    days,
-                    today,
-                    yesterday,
-                    week,
-                    since,
-                    until,
+/// Get common mistakes for a given intent.
+///
+/// Note: Only include mistakes that would actually fail after normalization.
+/// Commands that get auto-corrected and succeed (like `cass ls --robot` → `cass stats --robot`)
+/// should NOT be listed here since the user would never see this error message.
+fn get_common_mistakes(intent: &str) -> Option<serde_json::Value> {
+    let mistakes = if intent.contains("search") {
+        vec![
+            // query="foo" without subcommand - normalization adds "search" but the syntax is wrong
+            ("cass query=\"foo\" --robot", "cass search \"foo\" --robot"),
+            // Bare limit= without dashes
+            (
+                "cass search \"query\" limit=5",
+                "cass search \"query\" --limit 5",
+            ),
+            // Missing query entirely
+            (
+                "cass search --robot --limit 5",
+                "cass search \"your query\" --robot --limit 5",
+            ),
+        ]
+    } else if intent.contains("documentation") {
+        vec![
+            // Flag syntax for subcommand (--robot-docs gets normalized but shown for education)
+            ("cass --robot-docs", "cass robot-docs"),
+            ("cass --robot-docs=commands", "cass robot-docs commands"),
+            // Adding --robot to robot-docs (which doesn't accept it)
+            ("cass robot-docs --robot", "cass robot-docs"),
+        ]
+    } else if intent.contains("statistics") {
+        // Note: `cass ls --robot` actually works (normalizes to `cass stats --robot`)
+        // so we show mistakes that would actually fail
+        vec![
+            // Missing required output flag for piping
+            ("cass stats | jq .", "cass stats --json | jq ."),
+        ]
+    } else {
+        return None;
+    };
+
+    Some(serde_json::json!(
+        mistakes
+            .iter()
+            .map(|(wrong, right)| { serde_json::json!({"wrong": wrong, "correct": right}) })
+            .collect::<Vec<_>>()
+    ))
+}
+
+/// Heuristic recovery for command-line errors to help agents.
+/// Returns `(corrected_args, correction_note)` if a likely intent is found.
+fn heuristic_parse_recovery(
+    err: &clap::Error,
+    raw_args: &[String],
+) -> Option<(Vec<String>, String)> {
+    // Only attempt recovery for "unknown argument" or "unrecognized subcommand" errors
+    let is_unknown = err.kind() == clap::error::ErrorKind::UnknownArgument
+        || err.kind() == clap::error::ErrorKind::InvalidSubcommand;
+
+    if !is_unknown || raw_args.len() < 2 {
+        return None;
+    }
+
+    let prog = &raw_args[0];
+    let args = &raw_args[1..];
+    let mut corrected = Vec::new();
+    corrected.push(prog.clone());
+
+    let mut made_correction = false;
+    let mut notes = Vec::new();
+
+    // 1. Detect implicit "search" subcommand
+    // If the first arg isn't a known subcommand or flag, and looks like a query, assume "search".
+    let known_cmds = [
+        "search",
+        "index",
+        "stats",
+        "status",
+        "diag",
+        "view",
+        "capabilities",
+        "introspect",
+        "robot-docs",
+        "tui",
+        "undo",
+        "lock",
+        "rate",
+        "projects",
+        "graph",
+        "decisions",
+        "audit",
+        "embed",
+        "daemon",
+        "query-template",
+        "show",
+        "pick",
+        "agents",
+        "workspaces",
+        "tag",
+        "update",
+        "compact",
+        "note",
+        "prune",
+        "dedupe",
+        "doctor",
+        "db",
+        "report",
+        "config",
+        "sync",
+        "serve",
+        "mcp",
+        "rpc",
+        "help",
+        "--help",
+        "-h",
+        "--version",
+        "-V",
+    ];
+    if !args.is_empty() && !args[0].starts_with('-') && !known_cmds.contains(&args[0].as_str()) {
+        corrected.push("search".to_string());
+        // If the arg looks like `query="foo"`, strip the key
+        if args[0].starts_with("query=") || args[0].starts_with("q=") {
+            let val = args[0].split_once('=').map(|(_, v)| v).unwrap_or(&args[0]);
+            corrected.push(val.to_string());
+            notes.push(format!(
+                "Assumed 'search' subcommand and stripped query key from '{}'",
+                args[0]
+            ));
+        } else {
+            corrected.push(args[0].clone());
+            notes.push(format!(
+                "Assumed 'search' subcommand for positional argument '{}'",
+                args[0]
+            ));
+        }
+        made_correction = true;
+        corrected.extend_from_slice(&args[1..]);
+    } else {
+        // Just copy original structure to start
+        corrected.extend_from_slice(args);
+    }
+
+    // 2. Fuzzy match flags and fix key=value syntax
+    let mut final_args = Vec::new();
+    final_args.push(corrected[0].clone()); // prog
+
+    for arg in corrected.iter().skip(1) {
+        if arg.starts_with("--") {
+            // Split --flag=value or --flag
+            let (flag, value) = if let Some((f, v)) = arg.split_once('=') {
+                (f, Some(v))
+            } else {
+                (arg.as_str(), None)
+            };
+
+            // Known flags for fuzzy matching
+            let known_flags = [
+                "--robot",
+                "--json",
+                "--limit",
+                "--offset",
+                "--agent",
+                "--workspace",
+                "--fields",
+                "--max-tokens",
+                "--request-id",
+                "--cursor",
+                "--since",
+                "--until",
+                "--days",
+                "--today",
+                "--week",
+                "--full",
+                "--watch",
+                "--data-dir",
+                "--verbose",
+                "--quiet",
+            ];
+
+            // Check for exact match
+            if known_flags.contains(&flag) {
+                final_args.push(arg.clone());
+                continue;
+            }
+
+            // Check for typos (levenshtein distance <= 2)
+            let best_match = known_flags
+                .iter()
+                .min_by_key(|k| strsim::levenshtein(flag, k))
+                .filter(|k| strsim::levenshtein(flag, k) <= 2);
+
+            if let Some(&correction) = best_match {
+                if let Some(v) = value {
+                    final_args.push(format!("{correction}={v}"));
+                } else {
+                    final_args.push(correction.to_string());
+                }
+                notes.push(format!("Corrected typo '{flag}' to '{correction}'"));
+                made_correction = true;
+            } else {
+                // Keep as is if no good guess
+                final_args.push(arg.clone());
+            }
+        } else if arg.contains('=') && !arg.starts_with('-') {
+            // 3. Handle `limit=5` (missing --)
+            let (key, val) = arg.split_once('=').unwrap();
+            let flag_candidate = format!("--{key}");
+            // Quick check if adding -- makes it a valid flag
+            let known_flags = ["--limit", "--offset", "--agent", "--workspace", "--days"];
+            if known_flags.contains(&flag_candidate.as_str()) {
+                final_args.push(flag_candidate);
+                final_args.push(val.to_string());
+                notes.push(format!(
+                    "Interpreted '{arg}' as flag '{key}' with value '{val}'"
+                ));
+                made_correction = true;
+            } else {
+                final_args.push(arg.clone());
+            }
+        } else {
+            final_args.push(arg.clone());
+        }
+    }
+
+    if made_correction {
+        Some((final_args, notes.join("; ")))
+    } else {
+        None
+    }
+}
+
+pub async fn run() -> CliResult<()> {
+    let raw_args: Vec<String> = std::env::args().collect();
+    // First normalization pass (global flags lift)
+    let (normalized_args, parse_note) = normalize_args(raw_args.clone());
+
+    let (mut cli, heuristic_note) = match Cli::try_parse_from(&normalized_args) {
+        Ok(cli) => (cli, None),
+        Err(err) => {
+            // Let clap handle help/version natively (exit 0, print to stdout)
+            use clap::error::ErrorKind;
+            if matches!(
+                err.kind(),
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+            ) {
+                err.exit();
+            }
+            // Attempt heuristic recovery
+            if let Some((recovered_args, note)) = heuristic_parse_recovery(&err, &normalized_args) {
+                // Try parsing again with recovered args
+                match Cli::try_parse_from(&recovered_args) {
+                    Ok(cli) => (cli, Some(note)),
+                    Err(retry_err) => {
+                        // Check again for help/version in case recovered args triggered it
+                        if matches!(
+                            retry_err.kind(),
+                            ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+                        ) {
+                            retry_err.exit();
+                        }
+                        // Recovery failed to produce valid args, fail with original error + friendly help
+                        let friendly =
+                            format_friendly_parse_error(err, &raw_args, &normalized_args);
+                        return Err(CliError::usage("Could not parse arguments", Some(friendly)));
+                    }
+                }
+            } else {
+                // No recovery possible
+                let friendly = format_friendly_parse_error(err, &raw_args, &normalized_args);
+                return Err(CliError::usage("Could not parse arguments", Some(friendly)));
+            }
+        }
+    };
+
+    if let Some(name) = cli.profile.as_deref() {
+        let profile = profiles::activate(name)?;
+        if cli.db.is_none() {
+            cli.db = profile.db;
+        }
+    }
+
+    let stdout_is_tty = io::stdout().is_terminal();
+    let stderr_is_tty = io::stderr().is_terminal();
+    configure_color(cli.color, stdout_is_tty, stderr_is_tty);
+
+    let wrap_cfg = WrapConfig::new(cli.wrap, cli.nowrap);
+    let progress_resolved = resolve_progress(cli.progress, stdout_is_tty);
+
+    let start_ts = Utc::now();
+    let start_instant = Instant::now();
+    let command_label = describe_command(&cli);
+
+    // Output correction notices for AI agents
+    // These teach the agent proper syntax while still honoring their intent
+    // Detect robot mode from raw args (more reliable than pattern matching complex enums)
+    let is_robot_mode = raw_args
+        .iter()
+        .any(|s| s == "--json" || s == "--robot" || s == "-json" || s == "-robot")
+        || matches!(&cli.command, Some(Commands::Capabilities { .. }))
+        || matches!(&cli.command, Some(Commands::Introspect { .. }));
+    let is_doc_mode = cli.robot_help || matches!(&cli.command, Some(Commands::RobotDocs { .. }));
+
+    // Combine all correction notes
+    let all_notes: Vec<&str> = [parse_note.as_deref(), heuristic_note.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // Suppress correction chatter for robot/doc modes; still show for humans
+    if !all_notes.is_empty() && !is_doc_mode && !is_robot_mode {
+        // Human-readable correction notice
+        eprintln!("Note: Your command was auto-corrected:");
+        for note in &all_notes {
+            eprintln!("  • {note}");
+        }
+        eprintln!("Tip: Run 'cass --help' for proper syntax.");
+    }
+
+    let result = execute_cli(
+        &cli,
+        wrap_cfg,
+        progress_resolved,
+        stdout_is_tty,
+        stderr_is_tty,
+    )
+    .await;
+
+    if let Some(path) = &cli.trace_file {
+        let duration_ms = start_instant.elapsed().as_millis();
+        let exit_code = result.as_ref().map_or_else(|e| e.code, |()| 0);
+        if let Err(trace_err) = write_trace_line(
+            path,
+            &command_label,
+            &cli,
+            &start_ts,
+            duration_ms,
+            exit_code,
+            result.as_ref().err(),
+        ) {
+            eprintln!("trace-write error: {trace_err}");
+        }
+    }
+
+    result
+}
+
+async fn execute_cli(
+    cli: &Cli,
+    wrap: WrapConfig,
+    progress: ProgressResolved,
+    stdout_is_tty: bool,
+    stderr_is_tty: bool,
+) -> CliResult<()> {
+    let command = cli.command.clone().unwrap_or(Commands::Tui {
+        once: false,
+        reset_state: false,
+        data_dir: None,
+    });
+
+    if cli.robot_help {
+        print_robot_help(wrap)?;
+        return Ok(());
+    }
+
+    if let Commands::RobotDocs { topic } = command.clone() {
+        print_robot_docs(topic, wrap)?;
+        return Ok(());
+    }
+
+    // Block TUI in non-TTY contexts unless TUI_HEADLESS is set (for testing)
+    if matches!(command, Commands::Tui { .. })
+        && !stdout_is_tty
+        && std::env::var("TUI_HEADLESS").is_err()
+    {
+        return Err(CliError::usage(
+            "No subcommand provided; in non-TTY contexts TUI is disabled.",
+            Some("Use an explicit subcommand, e.g., `cass search --json ...` or `cass --robot-help`.".to_string()),
+        ));
+    }
+
+    // Auto-quiet in robot mode: suppress INFO logs for clean JSON output
+    // This ensures AI agents get parseable stdout without log noise on stderr
+    let robot_mode = is_robot_mode(&command);
+    let filter = if cli.quiet || robot_mode {
+        // Robot mode implies quiet unless verbose is explicitly requested
+        if cli.verbose {
+            EnvFilter::new("debug")
+        } else {
+            EnvFilter::new("warn")
+        }
+    } else if cli.verbose {
+        EnvFilter::new("debug")
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+    };
+
+    if let Some(data_dir) = self_check_data_dir(&command) {
+        let data_dir = data_dir.unwrap_or_else(default_data_dir);
+        let db_path = cli
+            .db
+            .clone()
+            .unwrap_or_else(|| data_dir.join("agent_search.db"));
+        selfcheck::check(&data_dir, &db_path).map_err(|e| CliError {
+            code: if e.kind == selfcheck::SelfCheckKind::ConfigInvalid {
+                2
+            } else {
+                3
+            },
+            kind: e.kind.as_str(),
+            message: e.message,
+            hint: Some(e.hint),
+            retryable: false,
+        })?;
+    }
+
+    match &command {
+        Commands::Tui { data_dir, .. } => {
+            let log_dir = data_dir.clone().unwrap_or_else(default_data_dir);
+            std::fs::create_dir_all(&log_dir).ok();
+
+            let file_appender = tracing_appender::rolling::daily(&log_dir, "cass.log");
+            let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_writer(non_blocking)
+                        .compact()
+                        .with_target(false)
+                        .with_ansi(false),
+                )
+                .init();
+
+            maybe_prompt_for_update(matches!(command, Commands::Tui { once: true, .. }))
+                .await
+                .map_err(|e| CliError {
+                    code: 9,
+                    kind: "update-check",
+                    message: format!("update check failed: {e}"),
+                    hint: None,
+                    retryable: false,
+                })?;
+
+            if let Commands::Tui {
+                once: false,
+                reset_state,
+                data_dir,
+                ..
+            } = command.clone()
+            {
+                let bg_data_dir = log_dir.clone();
+                let bg_db = cli.db.clone();
+                // Create shared progress tracker
+                let progress = std::sync::Arc::new(indexer::IndexingProgress::default());
+                let reindex_tx =
+                    spawn_background_indexer(bg_data_dir, bg_db, Some(progress.clone()));
+
+                ui::tui::run_tui(data_dir, false, reset_state, Some(progress), reindex_tx)
+                    .map_err(|e| CliError {
+                        code: 9,
+                        kind: "tui",
+                        message: format!("tui failed: {e}"),
+                        hint: None,
+                        retryable: false,
+                    })?;
+            } else if let Commands::Tui {
+                once,
+                reset_state,
+                data_dir,
+                ..
+            } = command.clone()
+            {
+                ui::tui::run_tui(data_dir, once, reset_state, None, None).map_err(|e| {
+                    CliError {
+                        code: 9,
+                        kind: "tui",
+                        message: format!("tui failed: {e}"),
+                        hint: None,
+                        retryable: false,
+                    }
+                })?;
+            }
+        }
+        Commands::Index { .. }
+        | Commands::Search { .. }
+        | Commands::Stats { .. }
+        | Commands::Diag { .. }
+        | Commands::Status { .. }
+        | Commands::View { .. } => {
+            tracing_subscriber::fmt()
+                .with_env_filter(filter)
+                .with_writer(std::io::stderr)
+                .compact()
+                .with_target(false)
+                .with_ansi(
+                    matches!(cli.color, ColorPref::Always)
+                        || (matches!(cli.color, ColorPref::Auto) && stderr_is_tty),
+                )
+                .init();
+
+            match command {
+                Commands::Index {
+                    full,
+                    force_rebuild,
+                    rebuild,
+                    watch,
+                    gc,
+                    watch_once,
+                    data_dir,
+                    json,
+                    idempotency_key,
+                } => {
+                    run_index_with_data(
+                        cli.db.clone(),
+                        full,
+                        force_rebuild,
+                        rebuild,
+                        watch,
+                        gc,
+                        watch_once,
+                        data_dir,
+                        progress,
+                        json,
+                        idempotency_key,
+                    )?;
+                }
+                Commands::Search {
+                    query,
+                    agent,
+                    workspace,
+                    role,
+                    limit,
+                    offset,
+                    json,
+                    robot_format,
+                    robot_meta,
+                    fields,
+                    max_content_length,
+                    max_tokens,
+                    request_id,
+                    cursor,
+                    display,
+                    data_dir,
+                    days,
+                    today,
+                    yesterday,
+                    week,
+                    since,
+                    until,
                     aggregate,
                     explain,
                     dry_run,
-                    timeout,
-                    highlight,
+                    timeout,
+                    highlight,
+                    source,
+                    sessions_from,
+                    code,
+                    project,
+                    starred,
+                    compare_period,
+                } => {
+                    run_cli_search(
+                        &query,
+                        &agent,
+                        &workspace,
+                        &role,
+                        &limit,
+                        &offset,
+                        &json,
+                        robot_format,
+                        robot_meta,
+                        fields,
+                        max_content_length,
+                        max_tokens,
+                        request_id.clone(),
+                        cursor.clone(),
+                        display,
+                        &data_dir,
+                        cli.db.clone(),
+                        wrap,
+                        progress,
+                        robot_mode,
+                        TimeFilter::new(
+                            days,
+                            today,
+                            yesterday,
+                            week,
+                            since.as_deref(),
+                            until.as_deref(),
+                        ),
+                        aggregate,
+                        explain,
+                        dry_run,
+                        timeout,
+                        highlight,
+                        &source,
+                        sessions_from,
+                        code,
+                        project,
+                        starred,
+                        compare_period,
+                    )?;
+                }
+                Commands::Stats {
+                    command,
+                    data_dir,
+                    json,
+                    source,
+                    by_source,
+                    by_project,
+                    days,
+                } => match command {
+                    Some(StatsCommand::Storage {
+                        largest,
+                        data_dir: storage_dir,
+                        json,
+                    }) => {
+                        run_stats_storage(largest, json, &storage_dir.or(data_dir), cli.db.clone())?
+                    }
+                    None => run_stats(
+                        &data_dir,
+                        cli.db.clone(),
+                        json,
+                        source.as_deref(),
+                        by_source,
+                        by_project,
+                        days,
+                    )?,
+                },
+                Commands::Diag {
+                    data_dir,
+                    json,
+                    verbose,
+                } => {
+                    run_diag(&data_dir, cli.db.clone(), json, verbose)?;
+                }
+                Commands::Status {
+                    data_dir,
+                    json,
+                    robot_meta,
+                    stale_threshold,
+                } => {
+                    run_status(&data_dir, cli.db.clone(), json, stale_threshold, robot_meta)?;
+                }
+                Commands::View {
+                    path,
+                    line,
+                    context,
+                    json,
+                } => {
+                    run_view(&path, line, context, json || robot_mode)?;
+                    record_session_access(cli.db.clone(), &path);
+                }
+                _ => {}
+            }
+        }
+        _ => {
+            tracing_subscriber::fmt()
+                .with_env_filter(filter)
+                .with_writer(std::io::stderr)
+                .compact()
+                .with_target(false)
+                .with_ansi(
+                    matches!(cli.color, ColorPref::Always)
+                        || (matches!(cli.color, ColorPref::Auto) && stderr_is_tty),
+                )
+                .init();
+
+            match command {
+                Commands::Completions { shell } => {
+                    let mut cmd = Cli::command();
+                    clap_complete::generate(shell, &mut cmd, "cass", &mut std::io::stdout());
+                }
+                Commands::Man => {
+                    let cmd = Cli::command();
+                    let man = clap_mangen::Man::new(cmd);
+                    man.render(&mut std::io::stdout())
+                        .map_err(|e| CliError::unknown(format!("failed to render man: {e}")))?;
+                }
+                Commands::Capabilities { json } => {
+                    run_capabilities(json)?;
+                }
+                Commands::ApiVersion { json } => {
+                    run_api_version(json)?;
+                }
+                Commands::State {
+                    data_dir,
+                    json,
+                    robot_meta,
+                    stale_threshold,
+                } => {
+                    run_status(&data_dir, None, json, stale_threshold, robot_meta)?;
+                }
+                Commands::Introspect { json } => {
+                    run_introspect(json)?;
+                }
+                Commands::Health {
+                    data_dir,
+                    json,
+                    robot_meta,
+                    stale_threshold,
+                } => {
+                    run_health(&data_dir, cli.db.clone(), json, stale_threshold, robot_meta)?;
+                }
+                Commands::Context {
+                    path,
+                    data_dir,
+                    json,
+                    limit,
+                } => {
+                    run_context(&path, &data_dir, cli.db.clone(), json, limit)?;
+                }
+                Commands::Export {
+                    path,
+                    query,
+                    conversation,
+                    limit,
+                    format,
+                    output,
+                    include_tools,
+                    full_content,
+                    agent,
+                    workspace,
+                    source,
+                    days,
+                    since,
+                    until,
+                    data_dir,
+                } => {
+                    if let Some(query) = query {
+                        let mut filters = crate::search::query::SearchFilters {
+                            agents: agent.into_iter().collect(),
+                            workspaces: workspace.into_iter().collect(),
+                            ..Default::default()
+                        };
+                        let time = TimeFilter::new(
+                            days,
+                            false,
+                            false,
+                            false,
+                            since.as_deref(),
+                            until.as_deref(),
+                        );
+                        filters.created_from = time.since;
+                        filters.created_to = time.until;
+                        if !source.is_empty() {
+                            use crate::sources::provenance::SourceFilter;
+                            filters.source_filter = SourceFilter::any_of(
+                                source.iter().map(|s| SourceFilter::parse(s)).collect(),
+                            );
+                        }
+                        run_export_query(
+                            &query,
+                            filters,
+                            limit,
+                            format,
+                            output.as_deref(),
+                            full_content,
+                            &data_dir,
+                            cli.db.clone(),
+                        )?;
+                    } else if let Some(id) = conversation {
+                        run_export_conversation(
+                            &id,
+                            format,
+                            output.as_deref(),
+                            include_tools,
+                            &data_dir,
+                            cli.db.clone(),
+                        )?;
+                    } else if let Some(path) = path {
+                        run_export(&path, format, output.as_deref(), include_tools)?;
+                        record_session_access(cli.db.clone(), &path);
+                    }
+                }
+                Commands::Expand {
+                    path,
+                    line,
+                    context,
+                    json,
+                } => {
+                    run_expand(&path, line, context, json)?;
+                    record_session_access(cli.db.clone(), &path);
+                }
+                Commands::Timeline {
+                    since,
+                    until,
+                    today,
+                    agent,
+                    data_dir,
+                    json,
+                    group_by,
+                    source,
+                } => {
+                    run_timeline(
+                        since.as_deref(),
+                        until.as_deref(),
+                        today,
+                        &agent,
+                        &data_dir,
+                        cli.db.clone(),
+                        json,
+                        group_by,
+                        source,
+                    )?;
+                }
+                Commands::Sources(subcmd) => {
+                    run_sources_command(subcmd)?;
+                }
+                Commands::Sync {
+                    source,
+                    no_index,
+                    verbose,
+                    dry_run,
+                    status,
+                    bwlimit,
+                    json,
+                } => {
+                    if status {
+                        run_sync_status(source, json)?;
+                    } else {
+                        run_sources_sync(source, no_index, verbose, dry_run, bwlimit, json)?;
+                    }
+                }
+                Commands::Serve {
+                    sync: true,
+                    cursor,
+                    since,
+                    data_dir,
+                    ..
+                } => {
+                    run_serve(cursor, since, &data_dir, cli.db.clone())?;
+                }
+                Commands::Serve {
+                    bind,
+                    token,
+                    open,
+                    data_dir,
+                    ..
+                } => {
+                    run_serve_http(bind, token, open, &data_dir, cli.db.clone()).await?;
+                }
+                Commands::Mcp { data_dir } => {
+                    run_mcp(&data_dir, cli.db.clone())?;
+                }
+                Commands::Rpc { data_dir } => {
+                    run_rpc(&data_dir, cli.db.clone())?;
+                }
+                Commands::Undo {
+                    list,
+                    data_dir,
+                    json,
+                } => {
+                    run_undo(list, &data_dir, cli.db.clone(), json)?;
+                }
+                Commands::Lock {
+                    path,
+                    unlock,
                     source,
-                    sessions_from,
+                    data_dir,
+                    json,
                 } => {
-                    run_cli_search(
-                        &query,
+                    run_lock(
+                        path.as_deref(),
+                        unlock,
+                        &source,
+                        &data_dir,
+                        cli.db.clone(),
+                        json,
+                    )?;
+                }
+                Commands::Rate {
+                    path,
+                    up,
+                    down,
+                    clear,
+                    note,
+                    source,
+                    data_dir,
+                    json,
+                } => {
+                    let rating = if clear {
+                        None
+                    } else if up {
+                        Some(1)
+                    } else if down {
+                        Some(-1)
+                    } else if path.is_some() {
+                        return Err(CliError::usage(
+                            "Specify --up, --down or --clear",
+                            Some("e.g. cass rate session.jsonl --up --note \"clean fix\"".into()),
+                        ));
+                    } else {
+                        None
+                    };
+                    run_rate(
+                        path.as_deref(),
+                        rating,
+                        note.as_deref(),
+                        &source,
+                        &data_dir,
+                        cli.db.clone(),
+                        json,
+                    )?;
+                }
+                Commands::Projects(subcmd) => {
+                    run_projects_command(subcmd, cli.db.clone())?;
+                }
+                Commands::Graph {
+                    format,
+                    output,
+                    limit,
+                    workspace,
+                    min_similarity,
+                    data_dir,
+                } => {
+                    let opts = graph::GraphOptions {
+                        limit,
+                        workspace,
+                        min_similarity,
+                    };
+                    run_graph(format, output.as_deref(), &opts, &data_dir, cli.db.clone())?;
+                }
+                Commands::Decisions {
+                    workspace,
+                    agent,
+                    since,
+                    limit,
+                    format,
+                    json,
+                    output,
+                    data_dir,
+                } => {
+                    let query = decisions::DecisionQuery {
+                        workspace: workspace
+                            .map(|w| w.canonicalize().unwrap_or(w).to_string_lossy().into_owned()),
+                        agents: agent,
+                        since: since.as_deref().and_then(parse_datetime_flexible),
+                        limit,
+                    };
+                    let format = if json { DecisionsFormat::Json } else { format };
+                    run_decisions(format, output.as_deref(), &query, &data_dir, cli.db.clone())?;
+                }
+                Commands::Audit {
+                    workspace,
+                    agent,
+                    since,
+                    output,
+                    verify,
+                    json,
+                    data_dir,
+                } => {
+                    if let Some(log) = verify {
+                        run_audit_verify(&log, json, &data_dir)?;
+                    } else {
+                        let since = since
+                            .as_deref()
+                            .map(|s| {
+                                parse_datetime_flexible(s).ok_or_else(|| {
+                                    CliError::usage(
+                                        format!("Invalid --since value: {s}"),
+                                        Some(
+                                            "Use an ISO date (2024-05-01), 'today', 'yesterday' or 'Nd'"
+                                                .to_string(),
+                                        ),
+                                    )
+                                })
+                            })
+                            .transpose()?;
+                        let query = audit::AuditQuery {
+                            workspace: workspace.map(|w| {
+                                w.canonicalize().unwrap_or(w).to_string_lossy().into_owned()
+                            }),
+                            agents: agent,
+                            since,
+                        };
+                        run_audit(output.as_deref(), json, &query, &data_dir, cli.db.clone())?;
+                    }
+                }
+                Commands::Embed {
+                    backfill,
+                    embedder,
+                    batch_size,
+                    checkpoint_every,
+                    canon_profile,
+                    json,
+                    data_dir,
+                } => {
+                    let profile = canon_profile.unwrap_or_else(|| {
+                        crate::search::embed_backfill::EmbeddingConfig::load().profile
+                    });
+                    let opts = crate::search::embed_backfill::BackfillOptions {
+                        full: !backfill,
+                        batch_size,
+                        checkpoint_every,
+                        profile,
+                    };
+                    run_embed(embedder, &opts, json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Daemon {
+                    command,
+                    reconcile_hours,
+                    advertise,
+                    data_dir,
+                } => match command {
+                    Some(DaemonCommand::Status {
+                        data_dir: status_dir,
+                        json,
+                    }) => run_daemon_status(&status_dir.or(data_dir), json)?,
+                    None => {
+                        let data_dir = data_dir.unwrap_or_else(default_data_dir);
+                        let db_path = cli
+                            .db
+                            .clone()
+                            .unwrap_or_else(|| data_dir.join("agent_search.db"));
+                        daemon::run(
+                            data_dir,
+                            db_path,
+                            Duration::from_secs(reconcile_hours.max(1) * 3600),
+                            advertise,
+                        )
+                        .map_err(|e| CliError {
+                            code: 9,
+                            kind: "daemon",
+                            message: format!("daemon failed: {e}"),
+                            hint: Some("Check `cass daemon status` for a running instance".into()),
+                            retryable: false,
+                        })?;
+                    }
+                },
+                Commands::QueryTemplate(subcmd) => {
+                    run_query_template(subcmd, cli.db.clone(), wrap, progress, robot_mode)?;
+                }
+                Commands::Tag(subcmd) => {
+                    run_tag_command(subcmd, cli.db.clone())?;
+                }
+                Commands::Update {
+                    filter,
+                    set,
+                    add_tag,
+                    remove_tag,
+                    dry_run,
+                    json,
+                    data_dir,
+                } => {
+                    run_update(
+                        &filter,
+                        &set,
+                        &add_tag,
+                        &remove_tag,
+                        dry_run,
+                        json,
+                        &data_dir,
+                        cli.db.clone(),
+                    )?;
+                }
+                Commands::Compact {
+                    threshold,
+                    force,
+                    dry_run,
+                    json,
+                    data_dir,
+                } => {
+                    let opts = crate::search::vector_compact::CompactOptions {
+                        min_fragmentation: threshold,
+                        force,
+                        dry_run,
+                    };
+                    run_compact(&opts, json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Note(subcmd) => {
+                    run_note_command(subcmd, cli.db.clone())?;
+                }
+                Commands::Prune {
+                    older_than,
+                    agent,
+                    dry_run,
+                    json,
+                    data_dir,
+                } => {
+                    run_prune(
+                        older_than.as_deref(),
+                        agent,
+                        dry_run,
+                        json,
+                        &data_dir,
+                        cli.db.clone(),
+                    )?;
+                }
+                Commands::Dedupe {
+                    merged: true,
+                    json,
+                    data_dir,
+                    ..
+                } => {
+                    run_dedupe_merged(json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Dedupe {
+                    report,
+                    merged: false,
+                    threshold,
+                    agent,
+                    json,
+                    data_dir,
+                } => {
+                    run_dedupe(
+                        report || json,
+                        threshold,
+                        agent,
+                        json,
+                        &data_dir,
+                        cli.db.clone(),
+                    )?;
+                }
+                Commands::Doctor {
+                    embedder,
+                    json,
+                    data_dir,
+                } => {
+                    run_doctor(embedder, json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Db(DbCommand::Optimize { data_dir, json }) => {
+                    run_db_optimize(json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Db(DbCommand::Export {
+                    file,
+                    data_dir,
+                    json,
+                }) => {
+                    run_db_export(&file, json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Db(DbCommand::Import {
+                    file,
+                    on_conflict,
+                    data_dir,
+                    json,
+                }) => {
+                    run_db_import(&file, on_conflict, json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Report(cmd) => {
+                    run_report_command(cmd, cli.db.clone())?;
+                }
+                Commands::Config(cmd) => {
+                    run_config_command(cmd, cli.db.clone())?;
+                }
+                Commands::Show {
+                    id,
+                    agent,
+                    source,
+                    from_idx,
+                    limit,
+                    markdown,
+                    json,
+                    data_dir,
+                } => {
+                    run_show(
+                        &id,
+                        agent.as_deref(),
+                        source.as_deref(),
+                        from_idx,
+                        limit,
+                        markdown,
+                        json,
+                        &data_dir,
+                        cli.db.clone(),
+                    )?;
+                }
+                Commands::Pick {
+                    query,
+                    filter,
+                    id,
+                    agent,
+                    limit,
+                    data_dir,
+                } => {
+                    run_pick(
+                        query.as_deref(),
+                        filter.as_deref(),
+                        id,
                         &agent,
-                        &workspace,
-                        &limit,
-                        &offset,
-                        &json,
-                        robot_format,
-                        robot_meta,
-                        fields,
-                        max_content_length,
-                        max_tokens,
-                        request_id.clone(),
-                        cursor.clone(),
-                        display,
+                        limit,
                         &data_dir,
                         cli.db.clone(),
-                        wrap,
-                        progress,
-                        robot_mode,
-                        TimeFilter::new(
-                            days,
-                            today,
-                            yesterday,
-                            week,
-                            since.as_deref(),
-                            until.as_deref(),
+                    )?;
+                }
+                Commands::Agents { json, data_dir } => {
+                    run_agents(json, &data_dir, cli.db.clone())?;
+                }
+                Commands::Workspaces {
+                    limit,
+                    json,
+                    data_dir,
+                } => {
+                    run_workspaces(limit, json, &data_dir, cli.db.clone())?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Let the user fuzzy-pick a conversation and print its source path (or id)
+fn run_pick(
+    query: Option<&str>,
+    filter: Option<&str>,
+    print_id: bool,
+    agents: &[String],
+    limit: usize,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use std::io::IsTerminal;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let items = storage
+        .pick_candidates(agents, limit)
+        .map_err(|e| CliError::unknown(format!("loading conversations: {e}")))?;
+    let output = |c: &crate::storage::sqlite::PickCandidate| {
+        if print_id {
+            c.conversation_id.to_string()
+        } else {
+            c.source_path.clone()
+        }
+    };
+
+    if let Some(filter) = filter {
+        let ranked = ui::pick::rank(filter, &items);
+        if ranked.is_empty() {
+            return Err(CliError {
+                code: 4,
+                kind: "not-found",
+                message: format!("No conversation matches '{filter}'"),
+                hint: Some("Matching covers titles, workspaces and agent names".to_string()),
+                retryable: false,
+            });
+        }
+        for i in ranked {
+            println!("{}", output(&items[i]));
+        }
+        return Ok(());
+    }
+
+    if !std::io::stderr().is_terminal() {
+        return Err(CliError::usage(
+            "cass pick draws its picker on stderr, which is not a terminal",
+            Some("Use `cass pick --filter <query>` to pick non-interactively".to_string()),
+        ));
+    }
+    let choice = ui::pick::run_picker(items, query.unwrap_or_default())
+        .map_err(|e| CliError::unknown(format!("picker failed: {e}")))?;
+    match choice {
+        Some(item) => {
+            println!("{}", output(&item));
+            Ok(())
+        }
+        None => Err(CliError {
+            code: 130,
+            kind: "cancelled",
+            message: "No conversation picked".to_string(),
+            hint: None,
+            retryable: false,
+        }),
+    }
+}
+
+/// List the agents cass knows, with detection status and indexed counts
+fn run_agents(
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    // Detection works before the first index run; counts are then zero
+    let usage = match open_existing_storage(data_dir_override, db_override) {
+        Ok(storage) => storage
+            .agent_usage()
+            .map_err(|e| CliError::unknown(format!("loading agent counts: {e}")))?,
+        Err(e) if e.kind == "missing-db" => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut rows: Vec<(String, bool, Vec<PathBuf>, i64, i64)> = Vec::new();
+    for (name, factory) in crate::indexer::get_connector_factories() {
+        // Reads what `type = "cass"` sources fetched, under their original agents
+        if name == "cass" {
+            continue;
+        }
+        let slug = if name == "claude" {
+            "claude_code"
+        } else {
+            name
+        };
+        let detection = factory().detect();
+        let (conversations, messages) = usage
+            .iter()
+            .find(|u| u.agent == slug)
+            .map_or((0, 0), |u| (u.conversations, u.messages));
+        rows.push((
+            slug.to_string(),
+            detection.detected,
+            detection.root_paths,
+            conversations,
+            messages,
+        ));
+    }
+    // Agents only known from imported or remote sessions
+    for u in &usage {
+        if !rows.iter().any(|r| r.0 == u.agent) {
+            rows.push((
+                u.agent.clone(),
+                false,
+                Vec::new(),
+                u.conversations,
+                u.messages,
+            ));
+        }
+    }
+
+    if json {
+        let payload = serde_json::json!({
+            "agents": rows.iter().map(|(slug, detected, roots, conversations, messages)| {
+                serde_json::json!({
+                    "slug": slug,
+                    "detected": detected,
+                    "roots": roots.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                    "conversations": conversations,
+                    "messages": messages,
+                })
+            }).collect::<Vec<_>>(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    for (slug, detected, roots, conversations, _) in &rows {
+        let status = if *detected {
+            "detected".green()
+        } else {
+            "not found".dimmed()
+        };
+        let root = roots
+            .first()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        println!(
+            "{slug:<width$}  {status:<9}  {conversations:>7} conversations  {}",
+            root.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// List indexed workspaces, most recently active first
+fn run_workspaces(
+    limit: usize,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use chrono::TimeZone;
+    use colored::Colorize;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let workspaces = storage
+        .workspace_activity(limit)
+        .map_err(|e| CliError::unknown(format!("loading workspaces: {e}")))?;
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    if json {
+        let payload = serde_json::json!({
+            "workspaces": workspaces.iter().map(|w| {
+                serde_json::json!({
+                    "path": w.path,
+                    "conversations": w.conversations,
+                    "agents": w.agents,
+                    "last_activity": w.last_activity.map(format_ts),
+                })
+            }).collect::<Vec<_>>(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    if workspaces.is_empty() {
+        println!("{}", "No workspaces indexed.".dimmed());
+        return Ok(());
+    }
+    for w in &workspaces {
+        let last = w
+            .last_activity
+            .and_then(|ms| chrono::Local.timestamp_millis_opt(ms).single())
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{}  {:>5} sessions  {}  {}",
+            last.dimmed(),
+            w.conversations,
+            w.path,
+            w.agents.join(",").dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Look up a stored conversation by conversation id or agent session id
+fn find_conversation_ref(
+    storage: &crate::storage::sqlite::SqliteStorage,
+    id: &str,
+    agent: Option<&str>,
+    source: &crate::sources::provenance::SourceFilter,
+) -> CliResult<crate::model::types::Conversation> {
+    // Numeric ids are conversation ids first, then session ids (some agents use counters)
+    let lookup = || -> anyhow::Result<Option<crate::model::types::Conversation>> {
+        if agent.is_none()
+            && source.is_all()
+            && let Ok(row_id) = id.parse::<i64>()
+            && let Some(conv) = storage.find_conversation(row_id)?
+        {
+            return Ok(Some(conv));
+        }
+        storage.find_by_external_id_in(id, agent, source)
+    };
+    let found = lookup().map_err(|e| CliError::unknown(format!("loading conversation: {e}")))?;
+    found.ok_or_else(|| CliError {
+        code: 3,
+        kind: "not-found",
+        message: format!("No conversation with id '{id}'"),
+        hint: Some(
+            "Use a conversation id or session id; 'cass search --json' shows source paths"
+                .to_string(),
+        ),
+        retryable: false,
+    })
+}
+
+/// Print a stored conversation with all of its messages
+#[allow(clippy::too_many_arguments)]
+fn run_show(
+    id: &str,
+    agent: Option<&str>,
+    source: Option<&str>,
+    from_idx: i64,
+    limit: usize,
+    markdown: bool,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::sources::provenance::SourceFilter;
+    use colored::Colorize;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let source = source.map(SourceFilter::parse).unwrap_or_default();
+    let mut conv = find_conversation_ref(&storage, id, agent, &source)?;
+
+    // Only the requested page is loaded, so huge sessions stay cheap to open
+    let page = storage
+        .fetch_messages_page(
+            conv.id.unwrap_or_default(),
+            from_idx,
+            if limit == 0 { u32::MAX as usize } else { limit },
+        )
+        .map_err(|e| CliError::unknown(format!("loading messages: {e}")))?;
+    conv.messages = page.messages;
+    // Databases from before annotations existed have no table; show no notes
+    let notes = storage
+        .annotations_for_conversation(conv.id.unwrap_or_default())
+        .unwrap_or_default();
+    let notes_of = |msg: &crate::model::types::Message| {
+        msg.id
+            .and_then(|id| notes.get(&id))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    let more = page.next_idx.map(|next| {
+        format!(
+            "{} of {} messages shown; continue with --from-idx {next}",
+            conv.messages.len(),
+            page.total
+        )
+    });
+
+    if json {
+        let mut payload = serde_json::to_value(&conv).unwrap_or_default();
+        payload["total_messages"] = page.total.into();
+        payload["next_idx"] = page.next_idx.into();
+        if let Some(messages) = payload["messages"].as_array_mut() {
+            for (value, msg) in messages.iter_mut().zip(&conv.messages) {
+                let msg_notes = notes_of(msg);
+                if !msg_notes.is_empty() {
+                    value["notes"] = serde_json::to_value(msg_notes).unwrap_or_default();
+                }
+            }
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    let role_label = |role: &crate::model::types::MessageRole| match role {
+        crate::model::types::MessageRole::User => "user".to_string(),
+        crate::model::types::MessageRole::Agent => "assistant".to_string(),
+        crate::model::types::MessageRole::Tool => "tool".to_string(),
+        crate::model::types::MessageRole::System => "system".to_string(),
+        crate::model::types::MessageRole::Other(r) => r.clone(),
+    };
+    let time = |ts: Option<i64>| {
+        ts.and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default()
+    };
+    let title = conv.title.as_deref().unwrap_or("Untitled conversation");
+    let conv_path = conv.source_path.to_string_lossy().into_owned();
+    // Originating file:line when the message carries its own provenance
+    let location = |msg: &crate::model::types::Message| {
+        if msg.source_path.is_none() && msg.source_line.is_none() {
+            return None;
+        }
+        let (path, line) = msg.location(&conv_path);
+        Some(line.map_or_else(|| path.to_string(), |l| format!("{path}:{l}")))
+    };
+
+    if markdown {
+        let mut md = format!("# {title}\n\n");
+        md.push_str(&format!("- **Agent:** {}\n", conv.agent_slug));
+        if let Some(ws) = &conv.workspace {
+            md.push_str(&format!("- **Workspace:** `{}`\n", ws.display()));
+        }
+        md.push_str(&format!("- **Source:** `{}`\n", conv.source_path.display()));
+        if conv.started_at.is_some() {
+            md.push_str(&format!("- **Started:** {}\n", time(conv.started_at)));
+        }
+        for msg in &conv.messages {
+            md.push_str(&format!("\n## {}", role_label(&msg.role)));
+            if msg.created_at.is_some() {
+                md.push_str(&format!(" · {}", time(msg.created_at)));
+            }
+            if let Some(loc) = location(msg) {
+                md.push_str(&format!(" · `{loc}`"));
+            }
+            md.push_str("\n\n");
+            md.push_str(msg.content.trim_end());
+            md.push('\n');
+            for note in notes_of(msg) {
+                md.push('\n');
+                for line in note.note.lines() {
+                    md.push_str(&format!("> 📝 {line}\n"));
+                }
+            }
+        }
+        if let Some(more) = &more {
+            md.push_str(&format!("\n_{more}_\n"));
+        }
+        print!("{md}");
+        return Ok(());
+    }
+
+    println!("{}", title.bold());
+    println!(
+        "{} {}  {}",
+        conv.agent_slug.as_str().cyan(),
+        conv.source_path.display().to_string().yellow(),
+        time(conv.started_at).dimmed()
+    );
+    for msg in &conv.messages {
+        println!();
+        println!(
+            "{} {} {}",
+            format!("[{}]", role_label(&msg.role)).green().bold(),
+            time(msg.created_at).dimmed(),
+            msg.id
+                .map(|id| format!("#{id}"))
+                .unwrap_or_default()
+                .dimmed()
+        );
+        if let Some(loc) = location(msg) {
+            println!("{}", loc.dimmed());
+        }
+        println!("{}", msg.content.trim_end());
+        for note in notes_of(msg) {
+            println!("{} {}", "📝".yellow(), note.note.as_str().yellow().italic());
+        }
+    }
+    if let Some(more) = more {
+        println!();
+        println!("{}", more.dimmed());
+    }
+    Ok(())
+}
+
+/// Add, edit, delete or list message notes
+fn run_note_command(cmd: NoteCommand, db_override: Option<PathBuf>) -> CliResult<()> {
+    use colored::Colorize;
+
+    let print_json = |payload: serde_json::Value| {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    };
+    let db_err = |e: anyhow::Error| CliError::unknown(format!("updating notes: {e}"));
+    let not_found = |what: &str, id: i64, hint: &str| CliError {
+        code: 3,
+        kind: "not-found",
+        message: format!("No {what} with id {id}"),
+        hint: Some(hint.to_string()),
+        retryable: false,
+    };
+    let note_text = |words: &[String]| -> CliResult<String> {
+        let text = words.join(" ");
+        if text.trim().is_empty() {
+            return Err(CliError::usage("note text is empty", None));
+        }
+        Ok(text)
+    };
+
+    match cmd {
+        NoteCommand::Add {
+            message_id,
+            text,
+            data_dir,
+            json,
+        } => {
+            let text = note_text(&text)?;
+            let mut storage = open_existing_storage(&data_dir, db_override)?;
+            if storage.get_message(message_id).map_err(db_err)?.is_none() {
+                return Err(not_found(
+                    "message",
+                    message_id,
+                    "Message ids are shown by 'cass show <conversation>'",
+                ));
+            }
+            let id = storage.add_annotation(message_id, &text).map_err(db_err)?;
+            if json {
+                let note = storage.get_annotation(id).map_err(db_err)?;
+                print_json(serde_json::to_value(note).unwrap_or_default());
+            } else {
+                println!(
+                    "{} note {} on message {message_id}",
+                    "Added".green(),
+                    format!("#{id}").bold()
+                );
+            }
+        }
+        NoteCommand::Edit {
+            note_id,
+            text,
+            data_dir,
+            json,
+        } => {
+            let text = note_text(&text)?;
+            let mut storage = open_existing_storage(&data_dir, db_override)?;
+            if !storage.update_annotation(note_id, &text).map_err(db_err)? {
+                return Err(not_found(
+                    "note",
+                    note_id,
+                    "Note ids are shown by 'cass note list <conversation>'",
+                ));
+            }
+            if json {
+                let note = storage.get_annotation(note_id).map_err(db_err)?;
+                print_json(serde_json::to_value(note).unwrap_or_default());
+            } else {
+                println!(
+                    "{} note {}",
+                    "Updated".green(),
+                    format!("#{note_id}").bold()
+                );
+            }
+        }
+        NoteCommand::Rm {
+            note_id,
+            data_dir,
+            json,
+        } => {
+            let mut storage = open_existing_storage(&data_dir, db_override)?;
+            if !storage.delete_annotation(note_id).map_err(db_err)? {
+                return Err(not_found(
+                    "note",
+                    note_id,
+                    "Note ids are shown by 'cass note list <conversation>'",
+                ));
+            }
+            if json {
+                print_json(serde_json::json!({ "id": note_id, "deleted": true }));
+            } else {
+                println!(
+                    "{} note {}",
+                    "Deleted".yellow(),
+                    format!("#{note_id}").bold()
+                );
+            }
+        }
+        NoteCommand::List {
+            conversation_id,
+            data_dir,
+            json,
+        } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            if storage
+                .find_conversation(conversation_id)
+                .map_err(db_err)?
+                .is_none()
+            {
+                return Err(not_found(
+                    "conversation",
+                    conversation_id,
+                    "Conversation ids are shown by 'cass show' and 'cass tag list --tag'",
+                ));
+            }
+            let by_message = storage
+                .annotations_for_conversation(conversation_id)
+                .map_err(db_err)?;
+            let mut notes: Vec<_> = by_message.into_values().flatten().collect();
+            notes.sort_by_key(|n| (n.message_id, n.created_at, n.id));
+            if json {
+                print_json(serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "notes": notes,
+                }));
+            } else if notes.is_empty() {
+                println!("{}", "No notes".dimmed());
+            } else {
+                for note in notes {
+                    println!(
+                        "{:>6}  {}  {}",
+                        format!("#{}", note.id).bold(),
+                        format!("msg {}", note.message_id).dimmed(),
+                        note.note
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compact the vector indexes against the messages still in the database
+fn run_compact(
+    opts: &crate::search::vector_compact::CompactOptions,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    if !(0.0..=1.0).contains(&opts.min_fragmentation) {
+        return Err(CliError::usage(
+            format!(
+                "--threshold must be between 0 and 1, got {}",
+                opts.min_fragmentation
+            ),
+            Some("e.g. --threshold 0.25 rewrites indexes that are a quarter dead".to_string()),
+        ));
+    }
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(acquire_writer_lock(&data_dir)?)
+    };
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let report =
+        crate::search::vector_compact::compact_vector_indexes(storage.raw(), &data_dir, opts)
+            .map_err(|e| CliError {
+                code: 9,
+                kind: "compact",
+                message: format!("Vector index compaction failed: {e}"),
+                hint: Some(
+                    "Indexes are replaced atomically; 'cass embed' rebuilds a damaged one."
+                        .to_string(),
+                ),
+                retryable: true,
+            })?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    if report.indexes.is_empty() && report.removed_temp_files.is_empty() {
+        println!("{}", "No vector indexes to compact".dimmed());
+        return Ok(());
+    }
+    for idx in &report.indexes {
+        let state = if idx.rewritten {
+            "compacted".green().bold()
+        } else if idx.orphaned + idx.duplicates == 0 {
+            "clean".dimmed()
+        } else if report.dry_run && idx.vectors_after < idx.vectors_before {
+            "would compact".yellow().bold()
+        } else {
+            "below threshold".yellow()
+        };
+        println!(
+            "{} {}: {} -> {} vectors ({} orphaned, {} duplicate, {:.1}% dead)",
+            state,
+            idx.embedder_id.cyan(),
+            idx.vectors_before,
+            idx.vectors_after,
+            idx.orphaned,
+            idx.duplicates,
+            idx.fragmentation * 100.0
+        );
+    }
+    if !report.removed_temp_files.is_empty() {
+        println!(
+            "{} {} interrupted save file(s)",
+            if report.dry_run {
+                "Would remove"
+            } else {
+                "Removed"
+            },
+            report.removed_temp_files.len()
+        );
+    }
+    println!(
+        "{} {}",
+        if report.dry_run {
+            "Reclaimable:"
+        } else {
+            "Reclaimed:"
+        },
+        format_bytes(report.reclaimed_bytes).bold()
+    );
+    Ok(())
+}
+
+/// Prune sessions older than `--older-than` (or the configured retention window)
+fn run_prune(
+    older_than: Option<&str>,
+    agents: Vec<String>,
+    dry_run: bool,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::indexer::retention::{
+        PruneOptions, configured_retention, parse_age, prune_sessions,
+    };
+    use colored::Colorize;
+
+    let window = older_than.map(str::to_string).or_else(configured_retention);
+    let Some(window) = window else {
+        return Err(CliError::usage(
+            "no age given and no retention window configured",
+            Some(
+                "Pass --older-than (e.g. 1y), or set retention = \"18mo\" under [storage] in config.toml"
+                    .to_string(),
+            ),
+        ));
+    };
+    let age = parse_age(&window).ok_or_else(|| {
+        CliError::usage(
+            format!("unrecognized age '{window}'"),
+            Some("Use a number with h, d, w, mo or y, e.g. 90d or 18mo".to_string()),
+        )
+    })?;
+    let opts = PruneOptions {
+        cutoff_ms: chrono::Utc::now().timestamp_millis() - age,
+        agents,
+        dry_run,
+    };
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(acquire_writer_lock(&data_dir)?)
+    };
+    let mut storage = open_existing_storage(data_dir_override, db_override)?;
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .map_err(|e| CliError::unknown(format!("locating search index: {e}")))?;
+    let mut t_index =
+        crate::search::tantivy::TantivyIndex::open_or_create(&index_path).map_err(|e| {
+            CliError {
+                code: 9,
+                kind: "index-open",
+                message: format!("Failed to open search index: {e}"),
+                hint: Some("Stop a running 'cass index --watch' or daemon and retry.".to_string()),
+                retryable: true,
+            }
+        })?;
+    let summary =
+        prune_sessions(&mut storage, &mut t_index, &data_dir, &opts).map_err(|e| CliError {
+            code: 9,
+            kind: "prune",
+            message: format!("Pruning failed: {e}"),
+            hint: Some(
+                "'cass index --full' rebuilds the search index from the database.".to_string(),
+            ),
+            retryable: true,
+        })?;
+
+    if json {
+        let mut value = serde_json::to_value(&summary).unwrap_or_default();
+        value["older_than"] = serde_json::json!(window);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    for c in &summary.removed {
+        let started = c
+            .started_at
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        println!(
+            "  {} {} {}",
+            started.dimmed(),
+            c.agent.cyan(),
+            c.title.as_deref().unwrap_or(&c.source_path)
+        );
+    }
+    println!(
+        "{} {} session(s): {} conversation(s), {} message(s), {} vector(s) older than {}",
+        if dry_run {
+            "Would prune".yellow().bold()
+        } else {
+            "Pruned".green().bold()
+        },
+        summary.sessions,
+        summary.conversations,
+        summary.messages,
+        summary.vectors,
+        window
+    );
+    if summary.locked > 0 {
+        println!(
+            "{}",
+            format!("Kept {} locked session(s)", summary.locked).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// List the session copies dropped at index time because another source
+/// already held (or later extended) the same session.
+fn run_dedupe_merged(
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let merged = storage
+        .list_merged_duplicates()
+        .map_err(|e| CliError::unknown(format!("listing merged duplicates: {e}")))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "count": merged.len(),
+                "merged": merged,
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
+    if merged.is_empty() {
+        println!("No session copies have been merged across sources.");
+        return Ok(());
+    }
+    for m in &merged {
+        let when = chrono::DateTime::from_timestamp_millis(m.merged_at)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!(
+            "{} {} {} · {} msgs",
+            when.dimmed(),
+            m.agent.cyan(),
+            m.external_id,
+            m.messages
+        );
+        println!(
+            "  {}  {} {}",
+            "dropped".red(),
+            m.source_id,
+            m.source_path.dimmed()
+        );
+        println!(
+            "  {}     {} {}",
+            "kept".green(),
+            m.kept_source_id,
+            m.kept_source_path.dimmed()
+        );
+    }
+    println!(
+        "{} {} session copy(ies) merged across sources",
+        "Merged".bold(),
+        merged.len()
+    );
+    Ok(())
+}
+
+/// Report near-duplicate conversations, or walk through them and merge/delete duplicates
+fn run_dedupe(
+    report_only: bool,
+    threshold: f64,
+    agents: Vec<String>,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::indexer::dedupe::{
+        ClusterKind, DedupeOptions, DuplicateCluster, DuplicateMember, Resolution, find_duplicates,
+        resolve_clusters,
+    };
+    use colored::Colorize;
+    use std::io::BufRead;
+
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(CliError::usage(
+            format!("threshold must be between 0.0 and 1.0, got {threshold}"),
+            None,
+        ));
+    }
+    if !report_only && !io::stdin().is_terminal() {
+        return Err(CliError::usage(
+            "interactive dedupe needs a terminal",
+            Some("Use 'cass dedupe --report' (or --json) to list duplicates".to_string()),
+        ));
+    }
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let _lock = if report_only {
+        None
+    } else {
+        Some(acquire_writer_lock(&data_dir)?)
+    };
+    let mut storage = open_existing_storage(data_dir_override, db_override)?;
+    let opts = DedupeOptions { threshold, agents };
+    let report = find_duplicates(&storage, &opts)
+        .map_err(|e| CliError::unknown(format!("finding duplicates: {e}")))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    let describe = |m: &DuplicateMember| {
+        let started = m
+            .started_at
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let note = if m.retained {
+            " (locked)".to_string()
+        } else if m.shared_file {
+            " (shares its session file)".to_string()
+        } else {
+            String::new()
+        };
+        format!(
+            "{} {} {} · {} msgs, {}{}\n         {}",
+            started.dimmed(),
+            m.agent.cyan(),
+            m.title.as_deref().unwrap_or("(untitled)"),
+            m.messages,
+            format_bytes(m.content_bytes),
+            note.dimmed(),
+            m.source_path.dimmed()
+        )
+    };
+    let print_cluster = |n: usize, c: &DuplicateCluster| {
+        let kind = match c.kind {
+            ClusterKind::Identical => "identical".yellow(),
+            ClusterKind::Retry => format!("retry {:.0}%", c.similarity * 100.0).yellow(),
+        };
+        println!(
+            "{} {} · {} conversations · {} reclaimable",
+            format!("Cluster {n}").bold(),
+            kind,
+            c.members.len(),
+            format_bytes(c.reclaimable_bytes)
+        );
+        println!("  {}   {}", "keep".green(), describe(c.keep()));
+        for m in c.duplicates() {
+            println!("  {}    {}", "dup".red(), describe(m));
+        }
+    };
+
+    if report.clusters.is_empty() {
+        println!(
+            "No duplicate conversations among {} scanned.",
+            report.conversations_scanned
+        );
+        return Ok(());
+    }
+    if report_only {
+        for (i, c) in report.clusters.iter().enumerate() {
+            print_cluster(i + 1, c);
+            println!();
+        }
+        println!(
+            "{} {} duplicate(s) in {} cluster(s) among {} conversations; {} reclaimable",
+            "Found".bold(),
+            report.duplicates,
+            report.clusters.len(),
+            report.conversations_scanned,
+            format_bytes(report.reclaimable_bytes).bold()
+        );
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut decisions: Vec<(&DuplicateCluster, Resolution)> = Vec::new();
+    let total = report.clusters.len();
+    for (i, c) in report.clusters.iter().enumerate() {
+        print_cluster(i + 1, c);
+        if c.duplicates().iter().all(|m| !m.deletable()) {
+            println!(
+                "{}\n",
+                "  Nothing deletable in this cluster; skipping.".dimmed()
+            );
+            continue;
+        }
+        print!(
+            "[{}/{total}] {}elete duplicates, {}erge into kept, {}kip, {}uit [s]: ",
+            i + 1,
+            "[d]".bold(),
+            "[m]".bold(),
+            "[s]".bold(),
+            "[q]".bold()
+        );
+        io::stdout().flush().ok();
+        let answer = match lines.next() {
+            Some(Ok(line)) => line.trim().to_lowercase(),
+            _ => "q".to_string(),
+        };
+        println!();
+        match answer.as_str() {
+            "d" | "delete" => decisions.push((c, Resolution::Delete)),
+            "m" | "merge" => decisions.push((c, Resolution::Merge)),
+            "q" | "quit" => break,
+            _ => {}
+        }
+    }
+
+    let victims: Vec<&DuplicateMember> = decisions
+        .iter()
+        .flat_map(|(c, _)| c.duplicates().iter().filter(|m| m.deletable()))
+        .collect();
+    if victims.is_empty() {
+        println!("Nothing to do.");
+        return Ok(());
+    }
+    print!(
+        "Delete {} conversation(s), reclaiming {}? This cannot be undone. [y/N]: ",
+        victims.len(),
+        format_bytes(victims.iter().map(|m| m.content_bytes).sum())
+    );
+    io::stdout().flush().ok();
+    let confirmed = matches!(
+        lines.next(),
+        Some(Ok(line)) if matches!(line.trim().to_lowercase().as_str(), "y" | "yes")
+    );
+    if !confirmed {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .map_err(|e| CliError::unknown(format!("locating search index: {e}")))?;
+    let mut t_index =
+        crate::search::tantivy::TantivyIndex::open_or_create(&index_path).map_err(|e| {
+            CliError {
+                code: 9,
+                kind: "index-open",
+                message: format!("Failed to open search index: {e}"),
+                hint: Some("Stop a running 'cass index --watch' or daemon and retry.".to_string()),
+                retryable: true,
+            }
+        })?;
+    let summary =
+        resolve_clusters(&mut storage, &mut t_index, &data_dir, &decisions).map_err(|e| {
+            CliError {
+                code: 9,
+                kind: "dedupe",
+                message: format!("Removing duplicates failed: {e}"),
+                hint: Some(
+                    "'cass index --full' rebuilds the search index from the database.".to_string(),
+                ),
+                retryable: true,
+            }
+        })?;
+    println!(
+        "{} {} conversation(s), {} message(s), {} vector(s); reclaimed {}",
+        "Removed".green().bold(),
+        summary.conversations,
+        summary.messages,
+        summary.vectors,
+        format_bytes(summary.reclaimed_bytes).bold()
+    );
+    if summary.merged_metadata > 0 {
+        println!(
+            "{}",
+            format!(
+                "Carried {} tag(s), rating(s) and note(s) over to the kept conversations",
+                summary.merged_metadata
+            )
+            .dimmed()
+        );
+    }
+    println!(
+        "{}",
+        "Run 'cass db optimize' to shrink the database file.".dimmed()
+    );
+    Ok(())
+}
+
+/// Check the database, search index and embedder selection
+fn run_doctor(
+    embedder_only: bool,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::search::embedder_select::{EmbedderChoice, auto_select_embedder};
+    use crate::search::vector_index::vector_index_path;
+    use colored::Colorize;
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .unwrap_or_else(|_| data_dir.join("index").join("v4"));
+    let selection = auto_select_embedder(&data_dir);
+    let vector_index = vector_index_path(&data_dir, &selection.embedder_id);
+
+    // (name, ok, detail, fix)
+    let mut checks: Vec<(&str, bool, String, Option<String>)> = Vec::new();
+    if !embedder_only {
+        checks.push((
+            "database",
+            db_path.is_file(),
+            db_path.display().to_string(),
+            Some("cass index --full".to_string()),
+        ));
+        checks.push((
+            "search index",
+            index_path.is_dir(),
+            index_path.display().to_string(),
+            Some("cass index --full".to_string()),
+        ));
+        if let Some(source) = crate::storage::encryption::configured_key_source() {
+            let verified = if db_path.is_file() {
+                crate::storage::encryption::verify_key(&db_path)
+            } else {
+                Ok(())
+            };
+            checks.push((
+                "encryption",
+                verified.is_ok(),
+                match verified {
+                    Ok(()) => match source {
+                        crate::storage::encryption::KeySource::Keychain => {
+                            "SQLCipher, key in the OS keychain (search index is not encrypted)"
+                                .to_string()
+                        }
+                        crate::storage::encryption::KeySource::Env => format!(
+                            "SQLCipher, key from {} (search index is not encrypted)",
+                            crate::storage::encryption::DB_KEY_ENV
                         ),
-                        aggregate,
-                        explain,
-                        dry_run,
-                        timeout,
-                        highlight,
-                        source,
-                        sessions_from,
-                    )?;
+                    },
+                    Err(e) => e.to_string(),
+                },
+                Some(if crate::storage::encryption::supported() {
+                    format!(
+                        "Check the OS keychain entry '{}' or {}",
+                        crate::storage::encryption::KEYCHAIN_SERVICE,
+                        crate::storage::encryption::DB_KEY_ENV
+                    )
+                } else {
+                    "cargo install with --features sqlcipher".to_string()
+                }),
+            ));
+        }
+    }
+    checks.push((
+        "vector index",
+        vector_index.is_file(),
+        vector_index.display().to_string(),
+        Some("cass embed".to_string()),
+    ));
+    if selection.choice == EmbedderChoice::Hash && !selection.probe.missing_model_files.is_empty() {
+        checks.push((
+            "minilm model",
+            false,
+            format!(
+                "missing {} in {}",
+                selection.probe.missing_model_files.join(", "),
+                selection.probe.model_dir.display()
+            ),
+            Some(
+                "Place the MiniLM model files there for semantic (not lexical) vectors".to_string(),
+            ),
+        ));
+    }
+
+    if json {
+        let checks: Vec<_> = checks
+            .iter()
+            .map(|(name, ok, detail, fix)| {
+                serde_json::json!({
+                    "check": name,
+                    "ok": ok,
+                    "detail": detail,
+                    "fix": if *ok { None } else { fix.clone() },
+                })
+            })
+            .collect();
+        let payload = serde_json::json!({
+            "ok": checks.iter().all(|c| c["ok"] == true),
+            "checks": checks,
+            "embedder": selection,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    let probe = &selection.probe;
+    println!(
+        "{} {}",
+        "Embedder:".bold(),
+        selection.embedder_id.cyan().bold()
+    );
+    for reason in &selection.reasons {
+        println!("  - {reason}");
+    }
+    let memory = match (probe.available_memory_bytes, probe.total_memory_bytes) {
+        (Some(available), Some(total)) => {
+            format!(
+                "{} free of {}",
+                format_bytes(available),
+                format_bytes(total)
+            )
+        }
+        _ => "unknown".to_string(),
+    };
+    println!(
+        "  {}",
+        format!(
+            "arch {}, cpu [{}], gpu providers [{}], memory {}",
+            probe.arch,
+            probe.cpu_features.join(", "),
+            probe.gpu_providers.join(", "),
+            memory
+        )
+        .dimmed()
+    );
+    println!();
+    for (name, ok, detail, fix) in &checks {
+        if *ok {
+            println!("{} {name}: {}", "✓".green(), detail.dimmed());
+        } else {
+            println!("{} {name}: {detail}", "✗".red());
+            if let Some(fix) = fix {
+                println!("    {} {fix}", "fix:".yellow());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Database file plus its WAL and shared-memory sidecars
+fn sqlite_footprint(db_path: &Path) -> u64 {
+    ["", "-wal", "-shm"]
+        .iter()
+        .filter_map(|suffix| {
+            let mut path = db_path.as_os_str().to_owned();
+            path.push(suffix);
+            std::fs::metadata(PathBuf::from(path)).ok()
+        })
+        .map(|m| m.len())
+        .sum()
+}
+
+/// VACUUM/ANALYZE/checkpoint the database and merge the Tantivy segments
+fn run_db_optimize(
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::indexer::budget::dir_size;
+    use colored::Colorize;
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override
+        .clone()
+        .unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let _lock = acquire_writer_lock(&data_dir)?;
+    let mut storage = open_existing_storage(data_dir_override, db_override)?;
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .map_err(|e| CliError::unknown(format!("locating search index: {e}")))?;
+
+    let db_before = sqlite_footprint(&db_path);
+    let index_before = dir_size(&index_path);
+    let started = Instant::now();
+
+    // Apply a changed `[storage] compression` setting to rows written before it
+    let compression = crate::indexer::budget::StorageConfig::load().is_some_and(|c| c.compression);
+    storage.set_compression(compression);
+    let recompressed = storage
+        .recompress_messages()
+        .map_err(|e| CliError::unknown(format!("recompressing messages: {e}")))?;
+
+    storage.optimize().map_err(|e| CliError {
+        code: 9,
+        kind: "db-optimize",
+        message: format!("Database optimization failed: {e}"),
+        hint: Some(
+            "VACUUM needs exclusive access and free disk space about the size of the database; stop other cass processes and retry."
+                .to_string(),
+        ),
+        retryable: true,
+    })?;
+    drop(storage);
+
+    let mut segments_before = 0;
+    let mut segments_after = 0;
+    if index_path.is_dir() {
+        let mut t_index = crate::search::tantivy::TantivyIndex::open_or_create(&index_path)
+            .map_err(|e| CliError {
+                code: 9,
+                kind: "index-open",
+                message: format!("Failed to open search index: {e}"),
+                hint: Some("Stop a running 'cass index --watch' or daemon and retry.".to_string()),
+                retryable: true,
+            })?;
+        segments_before = t_index.segment_count();
+        if segments_before > 1 {
+            t_index
+                .force_merge()
+                .map_err(|e| CliError::unknown(format!("merging index segments: {e}")))?;
+        }
+        segments_after = t_index.segment_count();
+    }
+
+    let db_after = sqlite_footprint(&db_path);
+    let index_after = dir_size(&index_path);
+    let reclaimed = (db_before + index_before).saturating_sub(db_after + index_after);
+    if json {
+        let payload = serde_json::json!({
+            "database": {
+                "path": db_path.display().to_string(),
+                "bytes_before": db_before,
+                "bytes_after": db_after,
+            },
+            "index": {
+                "path": index_path.display().to_string(),
+                "bytes_before": index_before,
+                "bytes_after": index_after,
+                "segments_before": segments_before,
+                "segments_after": segments_after,
+            },
+            "compression": compression,
+            "messages_recompressed": recompressed,
+            "reclaimed_bytes": reclaimed,
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    println!(
+        "{} database {} -> {} (vacuumed, analyzed, WAL truncated)",
+        "Optimized".green().bold(),
+        format_bytes(db_before),
+        format_bytes(db_after)
+    );
+    println!(
+        "{} search index {} -> {} ({} -> {} segments)",
+        "Optimized".green().bold(),
+        format_bytes(index_before),
+        format_bytes(index_after),
+        segments_before,
+        segments_after
+    );
+    if recompressed > 0 {
+        println!(
+            "{} {} messages",
+            if compression {
+                "Compressed"
+            } else {
+                "Decompressed"
+            }
+            .green()
+            .bold(),
+            recompressed
+        );
+    }
+    println!("Reclaimed: {}", format_bytes(reclaimed).bold());
+    Ok(())
+}
+
+/// Write the whole database to a portable archive (`cass db export`)
+fn run_db_export(
+    file: &Path,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::storage::archive::export_archive;
+    use colored::Colorize;
+
+    let storage = open_existing_snapshot(data_dir_override, db_override)?;
+    let started = Instant::now();
+    let summary = export_archive(&storage, file).map_err(|e| CliError {
+        code: 9,
+        kind: "db-export",
+        message: format!("Export failed: {e}"),
+        hint: Some("Check that the target directory exists and is writable.".to_string()),
+        retryable: false,
+    })?;
+    if json {
+        let mut value = serde_json::to_value(&summary).unwrap_or_default();
+        value["elapsed_ms"] = serde_json::json!(started.elapsed().as_millis() as u64);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    println!(
+        "{} {} conversation(s), {} message(s), {} tag(s), {} note(s) to {} ({})",
+        "Exported".green().bold(),
+        summary.conversations,
+        summary.messages,
+        summary.tags,
+        summary.notes,
+        file.display(),
+        format_bytes(summary.bytes).bold()
+    );
+    Ok(())
+}
+
+/// Merge a portable archive into the database and search index (`cass db import`)
+fn run_db_import(
+    file: &Path,
+    on_conflict: Option<crate::storage::archive::ConflictPolicy>,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::indexer::persist::{index_stored_conversations, replace_stored_conversations};
+    use crate::storage::archive::{ConflictPolicy, import_archive, read_header};
+    use colored::Colorize;
+
+    let interactive = !json && io::stdin().is_terminal() && io::stdout().is_terminal();
+    let policy = on_conflict.unwrap_or(if interactive {
+        ConflictPolicy::Ask
+    } else {
+        ConflictPolicy::Skip
+    });
+    if policy == ConflictPolicy::Ask && !interactive {
+        return Err(CliError::usage(
+            "--on-conflict ask needs an interactive terminal",
+            Some(
+                "Use --on-conflict skip, keep-newest or keep-both in scripts and with --json"
+                    .to_string(),
+            ),
+        ));
+    }
+
+    let header = read_header(file).map_err(|e| {
+        CliError::usage(
+            e.to_string(),
+            Some("Archives are written by 'cass db export'".to_string()),
+        )
+    })?;
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| CliError::unknown(format!("creating {}: {e}", parent.display())))?;
+    }
+    let _lock = acquire_writer_lock(&data_dir)?;
+    if db_path.exists()
+        && let crate::storage::sqlite::SchemaCheck::NeedsRebuild(reason) =
+            crate::storage::sqlite::check_schema_compatibility(&db_path).map_err(|e| CliError {
+                code: 9,
+                kind: "db-open",
+                message: format!("Failed to read database schema: {e}"),
+                hint: None,
+                retryable: false,
+            })?
+    {
+        return Err(CliError {
+            code: 9,
+            kind: "db-schema",
+            message: format!("Cannot import into {}: {reason}", db_path.display()),
+            hint: Some(
+                "Upgrade cass if the database is from a newer version; otherwise 'cass index --full' rebuilds it."
+                    .to_string(),
+            ),
+            retryable: false,
+        });
+    }
+    let mut storage =
+        crate::storage::sqlite::SqliteStorage::open(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    storage.set_compression(
+        crate::indexer::budget::StorageConfig::load().is_some_and(|c| c.compression),
+    );
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .map_err(|e| CliError::unknown(format!("locating search index: {e}")))?;
+    let mut t_index =
+        crate::search::tantivy::TantivyIndex::open_or_create(&index_path).map_err(|e| {
+            CliError {
+                code: 9,
+                kind: "index-open",
+                message: format!("Failed to open search index: {e}"),
+                hint: Some("Stop a running 'cass index --watch' or daemon and retry.".to_string()),
+                retryable: true,
+            }
+        })?;
+
+    let started = Instant::now();
+    let mut resolve = conflict_resolver(policy);
+    let summary = import_archive(&mut storage, file, &mut resolve).map_err(|e| CliError {
+        code: 9,
+        kind: "db-import",
+        message: format!("Import failed: {e}"),
+        hint: Some(
+            "Conversations imported before the failure are kept; re-running the import skips them."
+                .to_string(),
+        ),
+        retryable: false,
+    })?;
+    let indexed = index_stored_conversations(&storage, &mut t_index, &summary.created_ids)
+        .and_then(|n| {
+            replace_stored_conversations(&storage, &mut t_index, &summary.replaced_ids)
+                .map(|m| n + m)
+        })
+        .and_then(|n| t_index.commit().map(|()| n))
+        .map_err(|e| CliError {
+            code: 9,
+            kind: "index",
+            message: format!("Adding imported conversations to the search index failed: {e}"),
+            hint: Some(
+                "'cass index --full' rebuilds the search index from the database.".to_string(),
+            ),
+            retryable: true,
+        })?;
+
+    if json {
+        let mut value = serde_json::to_value(&summary).unwrap_or_default();
+        value["exported_by"] = serde_json::json!(header.cass_version);
+        value["exported_at"] = serde_json::json!(header.exported_at);
+        value["indexed"] = serde_json::json!(indexed);
+        value["elapsed_ms"] = serde_json::json!(started.elapsed().as_millis() as u64);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+        return Ok(());
+    }
+    println!(
+        "{} {} conversation(s), {} message(s), {} tag(s), {} note(s) from {}",
+        "Imported".green().bold(),
+        summary.conversations,
+        summary.messages,
+        summary.tags,
+        summary.notes,
+        file.display()
+    );
+    if summary.skipped > 0 {
+        println!(
+            "{}",
+            format!(
+                "Skipped {} conversation(s) already present",
+                summary.skipped
+            )
+            .dimmed()
+        );
+    }
+    if !summary.conflicts.is_empty() {
+        use crate::storage::archive::ConflictResolution;
+        let count = |r: ConflictResolution| {
+            summary
+                .conflicts
+                .iter()
+                .filter(|c| c.resolution == r)
+                .count()
+        };
+        println!(
+            "{} {} conflict(s): {} kept local, {} took the archive copy, {} kept both",
+            "Resolved".yellow().bold(),
+            summary.conflicts.len(),
+            count(ConflictResolution::KeepLocal),
+            count(ConflictResolution::KeepArchive),
+            count(ConflictResolution::KeepBoth)
+        );
+    }
+    if summary.conversations > 0 || !summary.replaced_ids.is_empty() {
+        println!(
+            "{}",
+            "Run 'cass embed' to add the imported messages to semantic search.".dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Settle import conflicts by policy, or by prompting for each one (`ask`).
+/// Quitting the prompt keeps the local copy of every remaining conflict.
+fn conflict_resolver(
+    policy: crate::storage::archive::ConflictPolicy,
+) -> impl FnMut(&crate::storage::archive::ImportConflict) -> crate::storage::archive::ConflictResolution
+{
+    use crate::storage::archive::ConflictResolution;
+    use colored::Colorize;
+
+    let mut quit = false;
+    move |conflict| {
+        if let Some(resolution) = policy.resolve(conflict) {
+            return resolution;
+        }
+        if quit {
+            return ConflictResolution::KeepLocal;
+        }
+        let when = |ms: Option<i64>| {
+            ms.and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "undated".to_string())
+        };
+        println!(
+            "{} {} {} ({})",
+            "Conflict".yellow().bold(),
+            conflict.agent.as_str().cyan(),
+            conflict.title.as_deref().unwrap_or("(untitled)"),
+            conflict.external_id.as_str().dimmed()
+        );
+        println!(
+            "  local:   {} message(s), last activity {}",
+            conflict.local_messages,
+            when(conflict.local_updated_at)
+        );
+        println!(
+            "  archive: {} message(s), last activity {}",
+            conflict.archived_messages,
+            when(conflict.archived_updated_at)
+        );
+        println!(
+            "  {} message(s) differ: {}",
+            conflict.differing.len(),
+            conflict
+                .differing
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        loop {
+            print!(
+                "  {}ewest, {}ocal, {}rchive, {}oth, {}iff, {}uit [n]: ",
+                "[n]".bold(),
+                "[l]".bold(),
+                "[a]".bold(),
+                "[b]".bold(),
+                "[d]".bold(),
+                "[q]".bold()
+            );
+            io::stdout().flush().ok();
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+                quit = true;
+                return ConflictResolution::KeepLocal;
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "" | "n" | "newest" => return conflict.newest(),
+                "l" | "local" => return ConflictResolution::KeepLocal,
+                "a" | "archive" => return ConflictResolution::KeepArchive,
+                "b" | "both" => return ConflictResolution::KeepBoth,
+                "d" | "diff" => {
+                    let (local, archived) = &conflict.first_difference;
+                    println!("  {} {}", "--- local".red(), "+++ archive".green());
+                    for line in crate::diff::unified_diff(local, archived) {
+                        let line = match line.chars().next() {
+                            Some('-') => line.red().to_string(),
+                            Some('+') => line.green().to_string(),
+                            Some('@') => line.cyan().to_string(),
+                            _ => line,
+                        };
+                        println!("  {line}");
+                    }
+                }
+                "q" | "quit" => {
+                    quit = true;
+                    return ConflictResolution::KeepLocal;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Per-agent storage, largest conversations and growth (`cass stats storage`)
+fn run_stats_storage(
+    largest: usize,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override
+        .clone()
+        .unwrap_or_else(|| data_dir.join("agent_search.db"));
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing-db",
+            message: format!(
+                "Database not found at {}. Run 'cass index --full' first.",
+                db_path.display()
+            ),
+            hint: None,
+            retryable: true,
+        });
+    }
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_readonly(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let db_err = |e: anyhow::Error| CliError::unknown(format!("reading storage stats: {e}"));
+
+    let agents = storage.agent_storage().map_err(db_err)?;
+    let conversations = storage.largest_conversations(largest).map_err(db_err)?;
+    // Index runs record one sample a day; today's figures are shown live, not stored
+    let mut growth = storage.storage_samples(30).map_err(db_err)?;
+    let today = storage.current_storage_sample().map_err(db_err)?;
+    if growth.last().is_some_and(|last| last.day == today.day) {
+        growth.pop();
+    } else if growth.len() == 30 {
+        growth.remove(0);
+    }
+    growth.push(today);
+    let db_bytes = sqlite_footprint(&db_path);
+    let content_bytes: u64 = agents.iter().map(|a| a.content_bytes).sum();
+    let total_conversations: i64 = agents.iter().map(|a| a.conversations).sum();
+    let total_messages: i64 = agents.iter().map(|a| a.messages).sum();
+
+    if json {
+        let payload = serde_json::json!({
+            "database": {
+                "path": db_path.display().to_string(),
+                "bytes": db_bytes,
+            },
+            "totals": {
+                "conversations": total_conversations,
+                "messages": total_messages,
+                "content_bytes": content_bytes,
+            },
+            "agents": agents,
+            "largest_conversations": conversations,
+            "growth": growth,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} {} ({} on disk, {} of content)",
+        "Database:".bold(),
+        db_path.display(),
+        format_bytes(db_bytes),
+        format_bytes(content_bytes)
+    );
+    println!();
+    println!("{}", "By agent:".bold());
+    println!(
+        "  {:<16} {:>8} {:>10} {:>10}",
+        "AGENT", "CONVS", "MESSAGES", "CONTENT"
+    );
+    for a in &agents {
+        println!(
+            "  {:<16} {:>8} {:>10} {:>10}",
+            a.agent.cyan(),
+            a.conversations,
+            a.messages,
+            format_bytes(a.content_bytes)
+        );
+    }
+    println!(
+        "  {:<16} {:>8} {:>10} {:>10}",
+        "total".bold(),
+        total_conversations,
+        total_messages,
+        format_bytes(content_bytes)
+    );
+
+    if !conversations.is_empty() {
+        println!();
+        println!("{}", "Largest conversations:".bold());
+        for c in &conversations {
+            println!(
+                "  {:>10}  #{:<6} {:<12} {} {}",
+                format_bytes(c.content_bytes),
+                c.conversation_id,
+                c.agent.cyan(),
+                c.title.as_deref().unwrap_or("(untitled)"),
+                format!("({} msgs, {})", c.messages, c.source_path).dimmed()
+            );
+        }
+    }
+
+    if growth.len() > 1 {
+        println!();
+        println!("{}", "Growth (daily samples):".bold());
+        let mut prev: Option<&crate::storage::sqlite::StorageSample> = None;
+        for s in &growth {
+            let delta = prev
+                .map(|p| {
+                    let diff = s.db_bytes as i64 - p.db_bytes as i64;
+                    let sign = if diff < 0 { "-" } else { "+" };
+                    format!(
+                        "{sign}{} / {:+} msgs",
+                        format_bytes(diff.unsigned_abs()),
+                        s.messages - p.messages
+                    )
+                })
+                .unwrap_or_default();
+            println!(
+                "  {}  {:>10} {:>10} msgs  {}",
+                s.day,
+                format_bytes(s.db_bytes),
+                s.messages,
+                delta.dimmed()
+            );
+            prev = Some(s);
+        }
+    }
+    Ok(())
+}
+
+/// List scheduled reports or run one now (`cass report`)
+fn run_report_command(cmd: ReportCommand, db_override: Option<PathBuf>) -> CliResult<()> {
+    use crate::reports::{
+        ReportStatus, ReportsConfig, next_report_attempt, render_report, run_report,
+    };
+    use colored::Colorize;
+
+    let config = ReportsConfig::load();
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+    match cmd {
+        ReportCommand::List { data_dir, json } => {
+            let data_dir = data_dir.unwrap_or_else(default_data_dir);
+            let status = ReportStatus::load(&data_dir);
+            let now = Utc::now().timestamp_millis();
+            let rows: Vec<_> = config
+                .reports
+                .iter()
+                .map(|r| {
+                    let last = status.last_run.get(&r.name).copied();
+                    (r, last, next_report_attempt(r, &status, now))
+                })
+                .collect();
+            if json {
+                let payload = serde_json::json!({
+                    "reports": rows.iter().map(|(r, last, next)| serde_json::json!({
+                        "name": r.name,
+                        "kind": r.kind,
+                        "schedule": r.schedule,
+                        "webhook": r.webhook.is_some(),
+                        "command": r.command,
+                        "last_run": last.map(format_ts),
+                        "next_run": format_ts(*next),
+                    })).collect::<Vec<_>>(),
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+                return Ok(());
+            }
+            if rows.is_empty() {
+                println!(
+                    "{}",
+                    "No reports configured. Add [[reports]] to config.toml.".dimmed()
+                );
+            }
+            for (r, last, next) in rows {
+                let targets: Vec<&str> = [
+                    r.webhook.as_ref().map(|_| "webhook"),
+                    r.command.as_ref().map(|_| "command"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                println!(
+                    "{} [{:?}, {}] -> {}  last: {}  next: {}",
+                    r.name.cyan(),
+                    r.kind,
+                    r.schedule,
+                    if targets.is_empty() {
+                        "nowhere".red().to_string()
+                    } else {
+                        targets.join(" + ")
+                    },
+                    last.map_or_else(|| "never".to_string(), format_ts),
+                    format_ts(next).dimmed()
+                );
+            }
+            Ok(())
+        }
+        ReportCommand::Run {
+            name,
+            print,
+            data_dir,
+            json,
+        } => {
+            let def = config.get(&name).ok_or_else(|| CliError {
+                code: 3,
+                kind: "not-found",
+                message: format!("No report named '{name}'"),
+                hint: Some("List configured reports with 'cass report list'".to_string()),
+                retryable: false,
+            })?;
+            let dir = data_dir.clone().unwrap_or_else(default_data_dir);
+            let db_path = db_override
+                .clone()
+                .unwrap_or_else(|| dir.join("agent_search.db"));
+            let report = if print {
+                let storage = open_existing_storage(&data_dir, db_override)?;
+                render_report(storage.raw(), def, Utc::now().timestamp_millis())
+            } else {
+                run_report(&dir, &db_path, def)
+            }
+            .map_err(|e| CliError {
+                code: 9,
+                kind: "report",
+                message: format!("Report '{name}' failed: {e:#}"),
+                hint: Some("Check the report's webhook URL or command".to_string()),
+                retryable: true,
+            })?;
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "delivered": !print,
+                        "report": report,
+                    }))
+                    .unwrap_or_default()
+                );
+            } else if print {
+                print!("{}", report.markdown);
+            } else {
+                println!("{} report '{}'", "Delivered".green().bold(), name);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// `cass config`: show, check and edit config.toml and sources.toml
+fn run_config_command(cmd: ConfigCommand, db_override: Option<PathBuf>) -> CliResult<()> {
+    use crate::config::{
+        ENV_OVERRIDES, apply_env_overrides, check_config, check_sources, config_path, lookup,
+        read_table, set_key, sources_path,
+    };
+    use colored::Colorize;
+
+    let config_file = config_path()
+        .ok_or_else(|| CliError::unknown("could not determine the config directory".to_string()))?;
+    let invalid = |path: &Path, message: String| CliError {
+        code: 5,
+        kind: "config-invalid",
+        message: format!("{}: {message}", path.display()),
+        hint: Some("Run 'cass config validate' for details".to_string()),
+        retryable: false,
+    };
+    let print_json = |payload: serde_json::Value| {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    };
+
+    match cmd {
+        ConfigCommand::Path { json } => {
+            let data_dir = default_data_dir();
+            let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+            let rows = [
+                ("config", Some(config_file)),
+                ("sources", sources_path()),
+                ("data_dir", Some(data_dir)),
+                ("db", Some(db_path)),
+            ];
+            if json {
+                let mut payload = serde_json::Map::new();
+                for (name, path) in &rows {
+                    payload.insert(
+                        (*name).to_string(),
+                        serde_json::json!(path.as_ref().map(|p| p.display().to_string())),
+                    );
                 }
-                Commands::Stats {
-                    data_dir,
-                    json,
-                    source,
-                    by_source,
-                } => {
-                    run_stats(
-                        &data_dir,
-                        cli.db.clone(),
-                        json,
-                        source.as_deref(),
-                        by_source,
-                    )?;
+                payload.insert(
+                    "env".to_string(),
+                    ENV_OVERRIDES
+                        .iter()
+                        .map(|(name, _, what)| {
+                            serde_json::json!({
+                                "name": name,
+                                "description": what,
+                                "set": std::env::var_os(name).is_some(),
+                            })
+                        })
+                        .collect(),
+                );
+                print_json(payload.into());
+                return Ok(());
+            }
+            for (name, path) in rows {
+                let Some(path) = path else { continue };
+                let state = if path.exists() { "" } else { " (missing)" };
+                println!("{:<9} {}{}", name, path.display(), state.dimmed());
+            }
+            println!();
+            println!("Environment overrides:");
+            for (name, _, what) in ENV_OVERRIDES {
+                let set = if std::env::var_os(name).is_some() {
+                    "set".green()
+                } else {
+                    "unset".dimmed()
+                };
+                println!("  {name:<27} {set:<5}  {}", what.dimmed());
+            }
+        }
+        ConfigCommand::Get { key, json } => {
+            let mut effective = read_table(&config_file).map_err(|e| invalid(&config_file, e))?;
+            let data_dir = default_data_dir();
+            let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+            if let Some(profile) = crate::profiles::active() {
+                effective.insert("profile".into(), profile.name.clone().into());
+            }
+            effective.insert("data_dir".into(), data_dir.display().to_string().into());
+            effective.insert("db_path".into(), db_path.display().to_string().into());
+            apply_env_overrides(&mut effective);
+            let mut env = toml::Table::new();
+            for (name, _, _) in ENV_OVERRIDES {
+                if let Ok(value) = std::env::var(name) {
+                    // Never echo the key itself
+                    let shown = if *name == "CASS_DB_KEY" {
+                        "(set)".to_string()
+                    } else {
+                        value
+                    };
+                    env.insert((*name).to_string(), shown.into());
+                }
+            }
+            effective.insert("env".into(), env.into());
+
+            let value = match &key {
+                Some(key) => lookup(&effective, key).cloned().ok_or_else(|| CliError {
+                    code: 4,
+                    kind: "not-found",
+                    message: format!("'{key}' is not set"),
+                    hint: Some("Run 'cass config get' to see every setting".to_string()),
+                    retryable: false,
+                })?,
+                None => toml::Value::Table(effective),
+            };
+            if json {
+                print_json(serde_json::to_value(&value).unwrap_or_default());
+            } else {
+                match value {
+                    toml::Value::String(s) => println!("{s}"),
+                    toml::Value::Table(t) => {
+                        print!("{}", toml::to_string_pretty(&t).unwrap_or_default())
+                    }
+                    other => println!("{other}"),
+                }
+            }
+        }
+        ConfigCommand::Set { key, value, json } => {
+            let mut doc = match std::fs::read_to_string(&config_file) {
+                Ok(content) => content.parse::<toml_edit::DocumentMut>(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Default::default()),
+                Err(e) => return Err(invalid(&config_file, e.to_string())),
+            }
+            .map_err(|e| invalid(&config_file, e.to_string()))?;
+            set_key(&mut doc, &key, &value)
+                .map_err(|e| CliError::usage(e, Some("Keys look like storage.max_size".into())))?;
+            let content = doc.to_string();
+            if let Some(issue) = check_config(&content).into_iter().find(|i| i.error) {
+                return Err(CliError::usage(
+                    format!(
+                        "{key} = {value} would make config.toml invalid: {}",
+                        issue.message
+                    ),
+                    None,
+                ));
+            }
+            let write = || -> std::io::Result<()> {
+                if let Some(parent) = config_file.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                if config_file.exists() {
+                    std::fs::copy(&config_file, config_file.with_extension("toml.bak"))?;
+                }
+                std::fs::write(&config_file, &content)
+            };
+            write().map_err(|e| CliError::unknown(format!("writing config: {e}")))?;
+            let stored = content
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|table| lookup(&table, &key).cloned())
+                .unwrap_or(toml::Value::from(value));
+            if json {
+                print_json(serde_json::json!({
+                    "path": config_file.display().to_string(),
+                    "key": key,
+                    "value": serde_json::to_value(&stored).unwrap_or_default(),
+                }));
+            } else {
+                println!("{} {key} = {stored}", "Set".green().bold());
+            }
+        }
+        ConfigCommand::Validate { json } => {
+            type Check = fn(&str) -> Vec<crate::config::Issue>;
+            let files: [(Option<PathBuf>, Check); 2] = [
+                (Some(config_file), check_config),
+                (sources_path(), check_sources),
+            ];
+            let results: Vec<(PathBuf, bool, Vec<crate::config::Issue>)> = files
+                .into_iter()
+                .filter_map(|(path, check)| Some((path?, check)))
+                .map(|(path, check)| match std::fs::read_to_string(&path) {
+                    Ok(content) => {
+                        let issues = check(&content);
+                        (path, true, issues)
+                    }
+                    Err(_) => (path, false, Vec::new()),
+                })
+                .collect();
+            let errors = results
+                .iter()
+                .flat_map(|r| &r.2)
+                .filter(|i| i.error)
+                .count();
+
+            if json {
+                print_json(serde_json::json!({
+                    "valid": errors == 0,
+                    "files": results.iter().map(|(path, exists, issues)| serde_json::json!({
+                        "path": path.display().to_string(),
+                        "exists": exists,
+                        "issues": issues,
+                    })).collect::<Vec<_>>(),
+                }));
+            } else {
+                for (path, exists, issues) in &results {
+                    let status = if !exists {
+                        "not present".dimmed()
+                    } else if issues.iter().any(|i| i.error) {
+                        "invalid".red().bold()
+                    } else {
+                        "ok".green()
+                    };
+                    println!("{} {status}", path.display());
+                    for issue in issues {
+                        let label = if issue.error {
+                            "error:".red()
+                        } else {
+                            "warning:".yellow()
+                        };
+                        println!("  {label} {}", issue.message);
+                    }
+                }
+            }
+            if errors > 0 {
+                return Err(CliError {
+                    code: 5,
+                    kind: "config-invalid",
+                    message: format!("{errors} error(s) in configuration"),
+                    hint: Some("cass ignores a file or section that fails to parse".to_string()),
+                    retryable: false,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add, remove or list conversation tags
+fn run_tag_command(cmd: TagCommand, db_override: Option<PathBuf>) -> CliResult<()> {
+    use crate::storage::sqlite::normalize_tag;
+    use colored::Colorize;
+
+    let print_json = |payload: serde_json::Value| {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    };
+    let db_err = |e: anyhow::Error| CliError::unknown(format!("updating tags: {e}"));
+    let require_conversation = |storage: &crate::storage::sqlite::SqliteStorage,
+                                id: i64|
+     -> CliResult<crate::model::types::Conversation> {
+        storage
+            .find_conversation(id)
+            .map_err(db_err)?
+            .ok_or_else(|| CliError {
+                code: 3,
+                kind: "not-found",
+                message: format!("No conversation with id {id}"),
+                hint: Some(
+                    "Conversation ids are shown by 'cass show' and 'cass tag list --tag'"
+                        .to_string(),
+                ),
+                retryable: false,
+            })
+    };
+    let normalize_all = |tags: &[String]| -> CliResult<Vec<String>> {
+        tags.iter()
+            .map(|t| {
+                normalize_tag(t).ok_or_else(|| {
+                    CliError::usage(
+                        format!("invalid tag name '{t}'"),
+                        Some("Tags are single words without whitespace".to_string()),
+                    )
+                })
+            })
+            .collect()
+    };
+
+    match cmd {
+        TagCommand::Add {
+            conversation_id,
+            tags,
+            data_dir,
+            json,
+        } => {
+            let tags = normalize_all(&tags)?;
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            require_conversation(&storage, conversation_id)?;
+            let mut added = Vec::new();
+            for tag in &tags {
+                if storage.add_tag(conversation_id, tag).map_err(db_err)? {
+                    added.push(tag.clone());
+                }
+            }
+            let current = storage.conversation_tags(conversation_id).map_err(db_err)?;
+            if json {
+                print_json(serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "added": added,
+                    "tags": current,
+                }));
+            } else if added.is_empty() {
+                println!("{}", "Already tagged; nothing changed".dimmed());
+            } else {
+                println!(
+                    "{} {} on conversation {conversation_id}",
+                    "Tagged".green(),
+                    added.join(", ").bold()
+                );
+            }
+        }
+        TagCommand::Rm {
+            conversation_id,
+            tags,
+            data_dir,
+            json,
+        } => {
+            let tags = normalize_all(&tags)?;
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            require_conversation(&storage, conversation_id)?;
+            let existing = storage.conversation_tags(conversation_id).map_err(db_err)?;
+            let journal = crate::bookmarks::BookmarkStore::open(
+                &data_dir
+                    .clone()
+                    .unwrap_or_else(default_data_dir)
+                    .join("bookmarks.db"),
+            )
+            .map_err(|e| CliError::unknown(format!("opening the undo journal: {e}")))?;
+            let mut removed = Vec::new();
+            for tag in tags.iter().filter(|t| existing.contains(t)) {
+                journal
+                    .journal_conversation_tag(conversation_id, tag)
+                    .map_err(|e| CliError::unknown(format!("writing the undo journal: {e}")))?;
+                if storage.remove_tag(conversation_id, tag).map_err(db_err)? {
+                    removed.push(tag.clone());
+                }
+            }
+            let current = storage.conversation_tags(conversation_id).map_err(db_err)?;
+            if json {
+                print_json(serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "removed": removed,
+                    "tags": current,
+                }));
+            } else if removed.is_empty() {
+                println!("{}", "No matching tags; nothing changed".dimmed());
+            } else {
+                println!(
+                    "{} {} from conversation {conversation_id}",
+                    "Removed".yellow(),
+                    removed.join(", ").bold()
+                );
+            }
+        }
+        TagCommand::List {
+            conversation_id,
+            tag,
+            data_dir,
+            json,
+        } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            if let Some(id) = conversation_id {
+                require_conversation(&storage, id)?;
+                let tags = storage.conversation_tags(id).map_err(db_err)?;
+                if json {
+                    print_json(serde_json::json!({ "conversation_id": id, "tags": tags }));
+                } else if tags.is_empty() {
+                    println!("{}", "No tags".dimmed());
+                } else {
+                    for tag in tags {
+                        println!("{}", tag.cyan());
+                    }
+                }
+            } else if let Some(tag) = tag {
+                let tag = normalize_all(std::slice::from_ref(&tag))?.remove(0);
+                let convs = storage.conversations_with_tag(&tag).map_err(db_err)?;
+                if json {
+                    let conversations: Vec<serde_json::Value> = convs
+                        .iter()
+                        .map(|c| {
+                            serde_json::json!({
+                                "id": c.id,
+                                "agent": c.agent_slug,
+                                "title": c.title,
+                                "workspace": c.workspace,
+                                "source_path": c.source_path,
+                                "started_at": c.started_at,
+                            })
+                        })
+                        .collect();
+                    print_json(serde_json::json!({ "tag": tag, "conversations": conversations }));
+                } else if convs.is_empty() {
+                    println!("{}", format!("No conversations tagged '{tag}'").dimmed());
+                } else {
+                    for c in convs {
+                        println!(
+                            "{:>6}  {}  {}",
+                            c.id.unwrap_or_default().to_string().bold(),
+                            c.agent_slug.cyan(),
+                            c.title.as_deref().unwrap_or("Untitled conversation")
+                        );
+                    }
+                }
+            } else {
+                let tags = storage.list_tags().map_err(db_err)?;
+                if json {
+                    let tags: Vec<serde_json::Value> = tags
+                        .iter()
+                        .map(|(name, count)| serde_json::json!({ "name": name, "conversations": count }))
+                        .collect();
+                    print_json(serde_json::json!({ "tags": tags }));
+                } else if tags.is_empty() {
+                    println!(
+                        "{}",
+                        "No tags yet; add one with 'cass tag add <id> <tag>'".dimmed()
+                    );
+                } else {
+                    for (name, count) in tags {
+                        println!("{:<24} {}", name.cyan(), count.to_string().dimmed());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply `--set`/`--add-tag`/`--remove-tag` to every conversation matching `--filter`
+#[allow(clippy::too_many_arguments)]
+fn run_update(
+    filters: &[String],
+    sets: &[String],
+    add_tags: &[String],
+    remove_tags: &[String],
+    dry_run: bool,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::storage::sqlite::{ConversationFilter, normalize_tag};
+    use colored::Colorize;
+
+    let mut filter = ConversationFilter::default();
+    for raw in filters {
+        let (key, value) = raw
+            .split_once(':')
+            .filter(|(_, v)| !v.trim().is_empty())
+            .ok_or_else(|| {
+                CliError::usage(
+                    format!("invalid filter '{raw}'"),
+                    Some(
+                        "Use workspace:<path>, agent:<slug>, source:<id> or tag:<name>".to_string(),
+                    ),
+                )
+            })?;
+        let value = value.trim().to_string();
+        match key {
+            "workspace" => {
+                let ws = crate::profiles::expand_home(PathBuf::from(value));
+                filter.workspace = Some(ws.to_string_lossy().into_owned());
+            }
+            "agent" => filter.agent = Some(value),
+            "source" => filter.source_id = Some(value),
+            "tag" => filter.tag = Some(value),
+            other => {
+                return Err(CliError::usage(
+                    format!("unknown filter field '{other}'"),
+                    Some("Filter on workspace, agent, source or tag".to_string()),
+                ));
+            }
+        }
+    }
+
+    let mut new_workspace: Option<PathBuf> = None;
+    for raw in sets {
+        match raw.split_once('=') {
+            Some(("workspace", value)) if !value.trim().is_empty() => {
+                new_workspace = Some(crate::profiles::expand_home(PathBuf::from(value.trim())));
+            }
+            _ => {
+                return Err(CliError::usage(
+                    format!("invalid --set '{raw}'"),
+                    Some("Only workspace=<path> can be set".to_string()),
+                ));
+            }
+        }
+    }
+    let normalize_all = |tags: &[String]| -> CliResult<Vec<String>> {
+        tags.iter()
+            .map(|t| {
+                normalize_tag(t).ok_or_else(|| {
+                    CliError::usage(
+                        format!("invalid tag name '{t}'"),
+                        Some("Tags are single words without whitespace".to_string()),
+                    )
+                })
+            })
+            .collect()
+    };
+    let add_tags = normalize_all(add_tags)?;
+    let remove_tags = normalize_all(remove_tags)?;
+    if new_workspace.is_none() && add_tags.is_empty() && remove_tags.is_empty() {
+        return Err(CliError::usage(
+            "nothing to update",
+            Some("Pass --set workspace=<path>, --add-tag or --remove-tag".to_string()),
+        ));
+    }
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(acquire_writer_lock(&data_dir)?)
+    };
+    let mut storage = open_existing_storage(data_dir_override, db_override)?;
+    let db_err = |e: anyhow::Error| CliError::unknown(format!("updating conversations: {e}"));
+    let matches = storage.filter_conversations(&filter).map_err(db_err)?;
+
+    // A workspace filter moves the whole tree: ~/old/sub becomes ~/new/sub
+    let moved_to = |current: Option<&Path>| -> Option<PathBuf> {
+        let target = new_workspace.as_ref()?;
+        let moved = match (current, &filter.workspace) {
+            (Some(cur), Some(prefix)) => cur
+                .strip_prefix(prefix)
+                .map(|rest| target.join(rest))
+                .unwrap_or_else(|_| target.clone()),
+            _ => target.clone(),
+        };
+        (current != Some(moved.as_path())).then_some(moved)
+    };
+
+    let mut changes = Vec::new();
+    let mut moved_ids = Vec::new();
+    for conv in &matches {
+        let Some(id) = conv.id else { continue };
+        let tags = storage.conversation_tags(id).map_err(db_err)?;
+        let adding: Vec<&String> = add_tags.iter().filter(|t| !tags.contains(t)).collect();
+        let removing: Vec<&String> = remove_tags.iter().filter(|t| tags.contains(t)).collect();
+        let workspace = moved_to(conv.workspace.as_deref());
+        if workspace.is_none() && adding.is_empty() && removing.is_empty() {
+            continue;
+        }
+        if !dry_run {
+            if let Some(ws) = &workspace {
+                storage
+                    .set_conversation_workspace(id, Some(ws.as_path()))
+                    .map_err(db_err)?;
+                if let Err(e) = crate::indexer::projects::ensure_workspace_project(&storage, ws) {
+                    tracing::warn!(workspace = %ws.display(), "project detection failed: {e}");
+                }
+                moved_ids.push(id);
+            }
+            for tag in &adding {
+                storage.add_tag(id, tag).map_err(db_err)?;
+            }
+            for tag in &removing {
+                storage.remove_tag(id, tag).map_err(db_err)?;
+            }
+        }
+        changes.push(serde_json::json!({
+            "id": id,
+            "agent": conv.agent_slug,
+            "title": conv.title,
+            "source_path": conv.source_path,
+            "workspace": conv.workspace,
+            "new_workspace": workspace,
+            "added_tags": adding,
+            "removed_tags": removing,
+        }));
+    }
+
+    // Workspaces are indexed in Tantivy too; tags are filtered from SQLite at search time
+    let mut reindexed = 0;
+    if !moved_ids.is_empty() {
+        let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+        let index_err = |e: anyhow::Error| CliError {
+            code: 9,
+            kind: "index",
+            message: format!("Updating the search index failed: {e}"),
+            hint: Some(
+                "The database was updated; 'cass index --full' rebuilds the search index from it."
+                    .to_string(),
+            ),
+            retryable: true,
+        };
+        let index_path = crate::search::tantivy::index_dir(&data_dir).map_err(index_err)?;
+        let mut t_index =
+            crate::search::tantivy::TantivyIndex::open_or_create(&index_path).map_err(index_err)?;
+        reindexed = crate::indexer::persist::replace_stored_conversations(
+            &storage,
+            &mut t_index,
+            &moved_ids,
+        )
+        .and_then(|n| t_index.commit().map(|()| n))
+        .map_err(index_err)?;
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "dry_run": dry_run,
+                "matched": matches.len(),
+                "changed": changes.len(),
+                "reindexed": reindexed,
+                "conversations": changes,
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
+    if changes.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "{} conversation(s) matched; nothing to change",
+                matches.len()
+            )
+            .dimmed()
+        );
+        return Ok(());
+    }
+    for change in &changes {
+        let mut parts = Vec::new();
+        if let Some(ws) = change["new_workspace"].as_str() {
+            parts.push(format!(
+                "{} → {}",
+                change["workspace"].as_str().unwrap_or("(none)").dimmed(),
+                ws.cyan()
+            ));
+        }
+        for tag in change["added_tags"].as_array().into_iter().flatten() {
+            parts.push(
+                format!("+{}", tag.as_str().unwrap_or_default())
+                    .green()
+                    .to_string(),
+            );
+        }
+        for tag in change["removed_tags"].as_array().into_iter().flatten() {
+            parts.push(
+                format!("-{}", tag.as_str().unwrap_or_default())
+                    .yellow()
+                    .to_string(),
+            );
+        }
+        println!(
+            "{:>6}  {}  {}",
+            change["id"].to_string().bold(),
+            change["title"].as_str().unwrap_or("Untitled conversation"),
+            parts.join("  ")
+        );
+    }
+    if dry_run {
+        println!(
+            "{}",
+            format!(
+                "Dry run: {} of {} matching conversation(s) would change",
+                changes.len(),
+                matches.len()
+            )
+            .dimmed()
+        );
+    } else {
+        println!(
+            "{} {} of {} matching conversation(s)",
+            "Updated".green().bold(),
+            changes.len(),
+            matches.len()
+        );
+    }
+    Ok(())
+}
+
+/// List `[query_templates]`, or render one (and optionally run it as a search)
+fn run_query_template(
+    cmd: QueryTemplateCommand,
+    db_override: Option<PathBuf>,
+    wrap: WrapConfig,
+    progress: ProgressResolved,
+    robot_mode: bool,
+) -> CliResult<()> {
+    use crate::search::templates::{QueryTemplates, TemplateError, parse_vars};
+    use colored::Colorize;
+
+    let templates = QueryTemplates::load();
+    match cmd {
+        QueryTemplateCommand::List { json } => {
+            let entries: Vec<serde_json::Value> = templates
+                .query_templates
+                .iter()
+                .map(|(name, t)| {
+                    serde_json::json!({
+                        "name": name,
+                        "query": t.query,
+                        "description": t.description,
+                        "variables": t.variables().unwrap_or_default(),
+                        "defaults": t.defaults,
+                    })
+                })
+                .collect();
+            if json {
+                let payload = serde_json::json!({
+                    "templates": entries,
+                    "count": entries.len(),
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else {
+                if templates.query_templates.is_empty() {
+                    println!(
+                        "{}",
+                        "No query templates. Add [query_templates.<name>] to config.toml.".dimmed()
+                    );
+                }
+                for (name, t) in &templates.query_templates {
+                    println!("  {}  {}", name.as_str().green(), t.query.as_str().yellow());
+                    if let Some(desc) = &t.description {
+                        println!("      {}", desc.as_str().dimmed());
+                    }
+                }
+            }
+            Ok(())
+        }
+        QueryTemplateCommand::Render {
+            name,
+            vars,
+            run,
+            limit,
+            json,
+            data_dir,
+        } => {
+            let vars = parse_vars(&vars)
+                .map_err(|e| CliError::usage(e, Some("Example: --vars pkg=tokio".to_string())))?;
+            let query = templates
+                .get(&name)
+                .and_then(|t| t.render(&vars))
+                .map_err(|e| CliError {
+                    code: 2,
+                    kind: "template",
+                    message: e.to_string(),
+                    hint: Some(match e {
+                        TemplateError::Unknown(_) => {
+                            "List templates with 'cass query-template list'".to_string()
+                        }
+                        _ => format!("Pass placeholders as --vars KEY=VALUE for '{name}'"),
+                    }),
+                    retryable: false,
+                })?;
+
+            if run {
+                return run_cli_search(
+                    &query,
+                    &[],
+                    &[],
+                    &[],
+                    &limit,
+                    &0,
+                    &json,
+                    None,
+                    false,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &data_dir,
+                    db_override,
+                    wrap,
+                    progress,
+                    robot_mode,
+                    TimeFilter::default(),
+                    None,
+                    false,
+                    false,
+                    None,
+                    false,
+                    &[],
+                    None,
+                    false,
+                    None,
+                    false,
+                    None,
+                );
+            }
+            if json {
+                let payload = serde_json::json!({
+                    "template": name,
+                    "query": query,
+                    "vars": vars,
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else {
+                println!("{query}");
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Revert the newest journaled bookmark/tag removal or bulk delete, or list what can be undone
+fn run_undo(
+    list: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+    json: bool,
+) -> CliResult<()> {
+    use crate::bookmarks::BookmarkStore;
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let store = BookmarkStore::open(&data_dir.join("bookmarks.db")).map_err(|e| CliError {
+        code: 9,
+        kind: "bookmarks-open",
+        message: format!("Failed to open bookmarks database: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    if list {
+        let entries = store
+            .undo_history(50)
+            .map_err(|e| CliError::unknown(format!("reading undo journal: {e}")))?;
+        if json {
+            let payload = serde_json::json!({
+                "entries": entries.iter().map(|e| serde_json::json!({
+                    "id": e.id,
+                    "action": e.action,
+                    "summary": e.summary,
+                    "created_at": format_ts(e.created_at),
+                })).collect::<Vec<_>>(),
+                "count": entries.len(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&payload).unwrap_or_default()
+            );
+        } else {
+            use colored::Colorize;
+            if entries.is_empty() {
+                println!("{}", "Nothing to undo.".dimmed());
+            }
+            for e in &entries {
+                println!(
+                    "  #{} {} {}",
+                    e.id,
+                    e.summary.as_str().yellow(),
+                    format_ts(e.created_at).dimmed()
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let undone = store
+        .undo_last(&db_path)
+        .map_err(|e| CliError::unknown(format!("undo failed: {e}")))?;
+    if json {
+        let payload = serde_json::json!({
+            "undone": undone.as_ref().map(|e| serde_json::json!({
+                "id": e.id,
+                "action": e.action,
+                "summary": e.summary,
+                "created_at": format_ts(e.created_at),
+            })),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    } else {
+        use colored::Colorize;
+        match undone {
+            Some(e) => {
+                println!("{} {}", "Undone:".green().bold(), e.summary);
+                if e.action == crate::bookmarks::UndoAction::DeleteSessions {
+                    println!(
+                        "{}",
+                        "Run 'cass index --full' to re-add the restored sessions.".dimmed()
+                    );
                 }
-                Commands::Diag {
-                    data_dir,
-                    json,
-                    verbose,
-                } => {
-                    run_diag(&data_dir, cli.db.clone(), json, verbose)?;
+            }
+            None => println!("{}", "Nothing to undo.".dimmed()),
+        }
+    }
+    Ok(())
+}
+
+/// `cass serve --sync`: write local conversations to stdout for a cass source
+fn run_serve(
+    cursor: Option<i64>,
+    since: Option<i64>,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let count = crate::sources::serve::write_stream(&storage, cursor, since, &mut out)
+        .map_err(|e| CliError::unknown(format!("streaming conversations: {e:#}")))?;
+    tracing::debug!(count, "served conversations");
+    Ok(())
+}
+
+async fn run_serve_http(
+    bind: std::net::SocketAddr,
+    token: Option<String>,
+    open: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    let token = token.filter(|t| !t.is_empty());
+    if token.is_none() && !bind.ip().is_loopback() {
+        return Err(CliError::usage(
+            format!("refusing to serve on {bind} without a token"),
+            Some(
+                "Pass --token (or set CASS_SERVE_TOKEN) to expose the API beyond localhost"
+                    .to_string(),
+            ),
+        ));
+    }
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .map_err(|e| CliError {
+            code: 9,
+            kind: "bind",
+            message: format!("cannot listen on {bind}: {e}"),
+            hint: Some("Pick another address with --bind".to_string()),
+            retryable: true,
+        })?;
+    let addr = listener.local_addr().unwrap_or(bind);
+    let url = format!("http://{addr}/");
+    eprintln!(
+        "{} {url}  {}",
+        "cass serve listening on".green(),
+        if token.is_some() {
+            "(bearer token required)"
+        } else {
+            "(localhost only)"
+        }
+        .dimmed()
+    );
+    if open && let Err(e) = crate::update_check::open_in_browser(&url) {
+        eprintln!("{} {e}", "could not open a browser:".yellow());
+    }
+    crate::http::serve(listener, crate::api::Api::new(data_dir, db_path), token)
+        .await
+        .map_err(|e| CliError::unknown(format!("{e:#}")))
+}
+
+fn run_mcp(data_dir_override: &Option<PathBuf>, db_override: Option<PathBuf>) -> CliResult<()> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let stdout = std::io::stdout();
+    crate::mcp::Server::new(crate::api::Api::new(data_dir, db_path))
+        .run(std::io::stdin().lock(), &mut stdout.lock())
+        .map_err(|e| CliError::unknown(format!("mcp server: {e:#}")))
+}
+
+fn run_rpc(data_dir_override: &Option<PathBuf>, db_override: Option<PathBuf>) -> CliResult<()> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let stdout = std::io::stdout();
+    crate::rpc::Server::new(crate::api::Api::new(data_dir, db_path))
+        .run(std::io::stdin().lock(), &mut stdout.lock())
+        .map_err(|e| CliError::unknown(format!("rpc server: {e:#}")))
+}
+
+/// Take the data dir's writer lock for a command that changes the database or
+/// search index, so it can't interleave with a running indexer.
+fn acquire_writer_lock(data_dir: &Path) -> CliResult<indexer::lock::IndexLock> {
+    indexer::lock::IndexLock::acquire(data_dir).map_err(|e| {
+        match e.downcast_ref::<indexer::lock::IndexLocked>() {
+            Some(locked) => CliError {
+                code: 7,
+                kind: "index-locked",
+                message: locked.to_string(),
+                hint: Some(
+                    "Stop `cass index --watch`, the daemon or the TUI, then retry.".to_string(),
+                ),
+                retryable: true,
+            },
+            None => CliError::unknown(format!("taking the index lock: {e:#}")),
+        }
+    })
+}
+
+/// Open the index database for a maintenance command, failing if it hasn't been created yet
+fn open_existing_storage(
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<crate::storage::sqlite::SqliteStorage> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing-db",
+            message: format!(
+                "Database not found at {}. Run 'cass index --full' first.",
+                db_path.display()
+            ),
+            hint: None,
+            retryable: true,
+        });
+    }
+    crate::storage::sqlite::SqliteStorage::open(&db_path).map_err(|e| CliError {
+        code: 9,
+        kind: "db-open",
+        message: format!("Failed to open database: {e}"),
+        hint: None,
+        retryable: false,
+    })
+}
+
+/// Like `open_existing_storage`, but read-only on a snapshot pinned for the
+/// caller's lifetime, so a concurrent index run can't tear a long read
+fn open_existing_snapshot(
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<crate::storage::sqlite::SqliteStorage> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing-db",
+            message: format!(
+                "Database not found at {}. Run 'cass index --full' first.",
+                db_path.display()
+            ),
+            hint: None,
+            retryable: true,
+        });
+    }
+    crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+        code: 9,
+        kind: "db-open",
+        message: format!("Failed to open database: {e}"),
+        hint: None,
+        retryable: false,
+    })
+}
+
+/// Lock/unlock a session file against pruning, or list locked sessions
+fn run_lock(
+    path: Option<&Path>,
+    unlock: bool,
+    source: &str,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+    json: bool,
+) -> CliResult<()> {
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    let Some(path) = path else {
+        let locked = storage
+            .list_retained()
+            .map_err(|e| CliError::unknown(format!("listing locks: {e}")))?;
+        if json {
+            let payload = serde_json::json!({
+                "locked": locked.iter().map(|(source_id, source_path, created_at)| serde_json::json!({
+                    "source_id": source_id,
+                    "source_path": source_path,
+                    "locked_at": format_ts(*created_at),
+                })).collect::<Vec<_>>(),
+                "count": locked.len(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&payload).unwrap_or_default()
+            );
+        } else {
+            use colored::Colorize;
+            if locked.is_empty() {
+                println!("{}", "No locked sessions.".dimmed());
+            }
+            for (source_id, source_path, created_at) in &locked {
+                println!(
+                    "  🔒 {} [{}] {}",
+                    source_path.as_str().yellow(),
+                    source_id.as_str().green(),
+                    format_ts(*created_at).dimmed()
+                );
+            }
+        }
+        return Ok(());
+    };
+
+    // Local paths are stored absolute; remote paths are kept exactly as given.
+    let path_str = if source == crate::sources::provenance::LOCAL_SOURCE_ID {
+        std::fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        path.to_string_lossy().into_owned()
+    };
+    let changed = storage
+        .set_retained(source, &path_str, !unlock)
+        .map_err(|e| CliError::unknown(format!("updating lock: {e}")))?;
+
+    if json {
+        let payload = serde_json::json!({
+            "source_id": source,
+            "source_path": path_str,
+            "locked": !unlock,
+            "changed": changed,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    } else {
+        use colored::Colorize;
+        let verb = if unlock { "Unlocked" } else { "Locked" };
+        if changed {
+            println!("{} {}", verb.green().bold(), path_str);
+        } else {
+            println!(
+                "{} {} {}",
+                "Already".dimmed(),
+                verb.to_lowercase().dimmed(),
+                path_str
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Rate (or clear the rating of) a session file, or list ratings when no path is given
+fn run_rate(
+    path: Option<&Path>,
+    rating: Option<i8>,
+    note: Option<&str>,
+    source: &str,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+    json: bool,
+) -> CliResult<()> {
+    use colored::Colorize;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let thumb = |rating: i8| if rating > 0 { "👍" } else { "👎" };
+
+    let Some(path) = path else {
+        let ratings = storage
+            .list_ratings()
+            .map_err(|e| CliError::unknown(format!("listing ratings: {e}")))?;
+        if json {
+            let payload = serde_json::json!({
+                "ratings": ratings,
+                "count": ratings.len(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&payload).unwrap_or_default()
+            );
+        } else {
+            if ratings.is_empty() {
+                println!("{}", "No rated sessions.".dimmed());
+            }
+            for r in &ratings {
+                println!(
+                    "  {} {} [{}]{}",
+                    thumb(r.rating),
+                    r.source_path.as_str().yellow(),
+                    r.source_id.as_str().green(),
+                    r.note
+                        .as_deref()
+                        .map(|n| format!(" — {n}").dimmed().to_string())
+                        .unwrap_or_default()
+                );
+            }
+        }
+        return Ok(());
+    };
+
+    // Local paths are stored absolute; remote paths are kept exactly as given.
+    let path_str = if source == crate::sources::provenance::LOCAL_SOURCE_ID {
+        std::fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        path.to_string_lossy().into_owned()
+    };
+    let changed = match rating {
+        Some(rating) => storage
+            .set_rating(source, &path_str, rating, note)
+            .map(|()| true),
+        None => storage.clear_rating(source, &path_str),
+    }
+    .map_err(|e| CliError::unknown(format!("updating rating: {e}")))?;
+
+    if json {
+        let payload = serde_json::json!({
+            "source_id": source,
+            "source_path": path_str,
+            "rating": rating,
+            "note": note,
+            "changed": changed,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+    } else {
+        match rating {
+            Some(rating) => println!("{} {} {}", "Rated".green().bold(), thumb(rating), path_str),
+            None if changed => println!("{} {}", "Cleared rating for".green().bold(), path_str),
+            None => println!("{} {}", "Not rated:".dimmed(), path_str),
+        }
+    }
+    Ok(())
+}
+
+/// List or edit the workspace -> project mapping
+fn run_projects_command(cmd: ProjectsCommand, db_override: Option<PathBuf>) -> CliResult<()> {
+    use colored::Colorize;
+    use std::collections::BTreeMap;
+
+    // Workspaces are stored as the agent reported them (absolute); normalize user input the same way.
+    let workspace_key = |path: &Path| {
+        std::fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    match cmd {
+        ProjectsCommand::List { data_dir, json } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            let mappings = storage
+                .list_workspace_projects()
+                .map_err(|e| CliError::unknown(format!("listing projects: {e}")))?;
+            let mut grouped: BTreeMap<&str, Vec<&crate::model::types::WorkspaceProject>> =
+                BTreeMap::new();
+            for m in &mappings {
+                grouped.entry(m.project.as_str()).or_default().push(m);
+            }
+
+            if json {
+                let payload = serde_json::json!({
+                    "projects": grouped.iter().map(|(project, members)| serde_json::json!({
+                        "project": project,
+                        "git_remote": members.iter().find_map(|m| m.git_remote.clone()),
+                        "workspaces": members.iter().map(|m| serde_json::json!({
+                            "path": m.workspace,
+                            "manual": m.manual,
+                        })).collect::<Vec<_>>(),
+                    })).collect::<Vec<_>>(),
+                    "count": grouped.len(),
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else {
+                if grouped.is_empty() {
+                    println!(
+                        "{}",
+                        "No projects detected yet. Run 'cass index' or 'cass projects refresh'."
+                            .dimmed()
+                    );
                 }
-                Commands::Status {
-                    data_dir,
-                    json,
-                    robot_meta,
-                    stale_threshold,
-                } => {
-                    run_status(&data_dir, cli.db.clone(), json, stale_threshold, robot_meta)?;
+                for (project, members) in &grouped {
+                    println!("{}", project.bold());
+                    for m in members {
+                        let marker = if m.manual { " (manual)" } else { "" };
+                        println!("  {}{}", m.workspace, marker.dimmed());
+                    }
                 }
-                Commands::View {
-                    path,
-                    line,
-                    context,
-                    json,
-                } => {
-                    run_view(&path, line, context, json || robot_mode)?;
+            }
+        }
+        ProjectsCommand::Set {
+            workspace,
+            project,
+            data_dir,
+            json,
+        } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            let key = workspace_key(&workspace);
+            storage
+                .set_workspace_project(&key, Some(&project))
+                .map_err(|e| CliError::unknown(format!("updating project: {e}")))?;
+            if json {
+                let payload = serde_json::json!({
+                    "workspace": key,
+                    "project": project,
+                    "manual": true,
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else {
+                println!("{} {} → {}", "Mapped".green().bold(), key, project.bold());
+            }
+        }
+        ProjectsCommand::Unset {
+            workspace,
+            data_dir,
+            json,
+        } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            let key = workspace_key(&workspace);
+            let changed = storage
+                .set_workspace_project(&key, None)
+                .map_err(|e| CliError::unknown(format!("updating project: {e}")))?;
+            if json {
+                let payload = serde_json::json!({
+                    "workspace": key,
+                    "removed": changed,
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else if changed {
+                println!("{} {}", "Unmapped".green().bold(), key);
+            } else {
+                println!("{} {}", "No mapping for".dimmed(), key);
+            }
+        }
+        ProjectsCommand::Refresh { data_dir, json } => {
+            let storage = open_existing_storage(&data_dir, db_override)?;
+            let workspaces = storage
+                .list_workspaces()
+                .map_err(|e| CliError::unknown(format!("listing workspaces: {e}")))?;
+            let mut detected = 0usize;
+            for ws in &workspaces {
+                match indexer::projects::refresh_workspace_project(&storage, &ws.path) {
+                    Ok(Some(_)) => detected += 1,
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::warn!(workspace = %ws.path.display(), "project detection failed: {e}")
+                    }
                 }
-                _ => {}
             }
+            if json {
+                let payload = serde_json::json!({
+                    "workspaces": workspaces.len(),
+                    "with_project": detected,
+                });
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&payload).unwrap_or_default()
+                );
+            } else {
+                println!(
+                    "{} {detected}/{} workspaces mapped to a project",
+                    "Refreshed:".green().bold(),
+                    workspaces.len()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Count an explicit open of a session toward `least-accessed` budget pruning (best effort).
+fn record_session_access(db_override: Option<PathBuf>, path: &Path) {
+    let db_path = db_override.unwrap_or_else(default_db_path);
+    if !db_path.exists() {
+        return;
+    }
+    if let Ok(storage) = crate::storage::sqlite::SqliteStorage::open(&db_path)
+        && let Err(e) = storage.record_access(&path.to_string_lossy())
+    {
+        tracing::debug!("failed to record session access: {e}");
+    }
+}
+
+/// Export the conversation graph in the requested format
+fn run_graph(
+    format: GraphFormat,
+    output: Option<&Path>,
+    opts: &graph::GraphOptions,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing_index",
+            message: "Database not found".to_string(),
+            hint: Some("Run 'cass index --full' to create the database.".to_string()),
+            retryable: true,
+        });
+    }
+
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let conn = storage.raw();
+
+    let graph = graph::build_graph(conn, opts).map_err(|e| CliError {
+        code: 9,
+        kind: "db-query",
+        message: format!("Failed to build conversation graph: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+
+    let formatted = match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Json => serde_json::to_string_pretty(&graph.to_json()).unwrap_or_default(),
+        GraphFormat::Canvas => serde_json::to_string_pretty(&graph.to_canvas()).unwrap_or_default(),
+    };
+
+    if let Some(out_path) = output {
+        std::fs::write(out_path, formatted.as_bytes()).map_err(|e| CliError {
+            code: 9,
+            kind: "file-write",
+            message: format!("Failed to write output: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+        eprintln!(
+            "Exported {} conversations, {} edges to: {}",
+            graph.nodes.len(),
+            graph.edges.len(),
+            out_path.display()
+        );
+    } else {
+        println!("{}", formatted.trim_end());
+    }
+
+    Ok(())
+}
+
+/// Print or export the decision log
+fn run_decisions(
+    format: DecisionsFormat,
+    output: Option<&Path>,
+    query: &decisions::DecisionQuery,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing_index",
+            message: "Database not found".to_string(),
+            hint: Some("Run 'cass index --full' to create the database.".to_string()),
+            retryable: true,
+        });
+    }
+
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let conn = storage.raw();
+
+    let log = decisions::extract_decisions(conn, query).map_err(|e| CliError {
+        code: 9,
+        kind: "db-query",
+        message: format!("Failed to extract decisions: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+
+    let formatted = match format {
+        DecisionsFormat::Markdown => log.to_markdown(),
+        DecisionsFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "total": log.total(),
+            "workspaces": log.workspaces,
+        }))
+        .unwrap_or_default(),
+        DecisionsFormat::Text => {
+            use colored::Colorize;
+            let mut out = String::new();
+            if log.workspaces.is_empty() {
+                out.push_str(&"No decisions found.".dimmed().to_string());
+            }
+            for (workspace, decisions) in &log.workspaces {
+                let label = if workspace.is_empty() {
+                    "(no workspace)"
+                } else {
+                    workspace.as_str()
+                };
+                out.push_str(&format!("{}\n", label.bold()));
+                for d in decisions {
+                    let date = d
+                        .created_at
+                        .and_then(chrono::DateTime::from_timestamp_millis)
+                        .map(|t| t.format("%Y-%m-%d").to_string())
+                        .unwrap_or_else(|| "undated".to_string());
+                    out.push_str(&format!("  {} {}\n", date.dimmed(), d.statement));
+                    out.push_str(&format!(
+                        "    {} {}\n",
+                        d.agent.cyan(),
+                        d.source_path.dimmed()
+                    ));
+                }
+                out.push('\n');
+            }
+            out
+        }
+    };
+
+    if let Some(out_path) = output {
+        std::fs::write(out_path, formatted.as_bytes()).map_err(|e| CliError {
+            code: 9,
+            kind: "file-write",
+            message: format!("Failed to write output: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+        eprintln!(
+            "Exported {} decisions from {} workspaces to: {}",
+            log.total(),
+            log.workspaces.len(),
+            out_path.display()
+        );
+    } else {
+        println!("{}", formatted.trim_end());
+    }
+
+    Ok(())
+}
+
+/// Export the hash-chained audit log as JSON Lines
+fn run_audit(
+    output: Option<&Path>,
+    json: bool,
+    query: &audit::AuditQuery,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use colored::Colorize;
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing_index",
+            message: "Database not found".to_string(),
+            hint: Some("Run 'cass index --full' to create the database.".to_string()),
+            retryable: true,
+        });
+    }
+
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let conn = storage.raw();
+
+    let key = audit::load_or_create_key(&data_dir).map_err(|e| CliError {
+        code: 9,
+        kind: "audit-key",
+        message: format!("Failed to load the audit key: {e:#}"),
+        hint: None,
+        retryable: false,
+    })?;
+    let entries = audit::collect_actions(conn, query, &key).map_err(|e| CliError {
+        code: 9,
+        kind: "db-query",
+        message: format!("Failed to collect agent actions: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+    let head_hash = entries
+        .last()
+        .map(|e| e.hash.clone())
+        .unwrap_or_else(|| audit::GENESIS_HASH.to_string());
+
+    let mut lines = String::new();
+    for entry in &entries {
+        lines.push_str(&serde_json::to_string(entry).unwrap_or_default());
+        lines.push('\n');
+    }
+
+    match output {
+        Some(out_path) => {
+            std::fs::write(out_path, lines.as_bytes()).map_err(|e| CliError {
+                code: 9,
+                kind: "file-write",
+                message: format!("Failed to write output: {e}"),
+                hint: None,
+                retryable: false,
+            })?;
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "entries": entries.len(),
+                        "head_hash": head_hash,
+                        "output": out_path.display().to_string(),
+                    })
+                );
+            } else {
+                eprintln!(
+                    "Exported {} audit entries to: {}",
+                    entries.len(),
+                    out_path.display()
+                );
+                eprintln!("Head hash: {}", head_hash.cyan());
+            }
+        }
+        None => {
+            print!("{lines}");
+            if !json {
+                eprintln!(
+                    "{} {} entries, head hash {}",
+                    "Audit log:".bold(),
+                    entries.len(),
+                    head_hash.cyan()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-compute the hash chain of an exported audit log
+fn run_audit_verify(log: &Path, json: bool, data_dir_override: &Option<PathBuf>) -> CliResult<()> {
+    use colored::Colorize;
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let key = audit::load_key(&data_dir).map_err(|e| CliError {
+        code: 9,
+        kind: "audit-key",
+        message: format!("Failed to load the audit key: {e:#}"),
+        hint: Some(format!(
+            "Verify with the data dir the log was exported from; its {} signs the chain.",
+            audit::KEY_FILE
+        )),
+        retryable: false,
+    })?;
+    let file = std::fs::File::open(log).map_err(|e| CliError {
+        code: 9,
+        kind: "file-read",
+        message: format!("Failed to open {}: {e}", log.display()),
+        hint: None,
+        retryable: false,
+    })?;
+    let result = audit::verify_chain(std::io::BufReader::new(file), &key)
+        .map_err(|e| CliError::unknown(format!("Failed to read audit log: {e}")))?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "valid": result.broken.is_none(),
+                "entries": result.entries,
+                "head_hash": result.head_hash,
+                "broken": result.broken,
+            }))
+            .unwrap_or_default()
+        );
+    } else if result.broken.is_none() {
+        println!(
+            "{} {} entries, head hash {}",
+            "Audit log intact:".green().bold(),
+            result.entries,
+            result.head_hash.cyan()
+        );
+    }
+
+    match result.broken {
+        None => Ok(()),
+        Some(b) => Err(CliError {
+            code: 1,
+            kind: "audit-tampered",
+            message: format!("Audit log broken at line {}: {}", b.line, b.reason),
+            hint: Some(format!(
+                "The first {} entries verify; everything from line {} on was modified, reordered or removed.",
+                result.entries, b.line
+            )),
+            retryable: false,
+        }),
+    }
+}
+
+fn run_embed(
+    kind: EmbedderKind,
+    opts: &crate::search::embed_backfill::BackfillOptions,
+    json: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::search::embedder::Embedder;
+    use crate::search::embedder_select::{EmbedderChoice, auto_select_embedder};
+    use crate::search::fastembed_embedder::FastEmbedder;
+    use crate::search::hash_embedder::HashEmbedder;
+
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let selection = (kind == EmbedderKind::Auto).then(|| auto_select_embedder(&data_dir));
+    let kind = match selection.as_ref().map(|s| s.choice) {
+        Some(EmbedderChoice::Minilm) => EmbedderKind::Minilm,
+        Some(EmbedderChoice::Hash) => EmbedderKind::Hash,
+        None => kind,
+    };
+
+    if !db_path.exists() {
+        return Err(CliError {
+            code: 3,
+            kind: "missing_index",
+            message: "Database not found".to_string(),
+            hint: Some("Run 'cass index --full' to create the database.".to_string()),
+            retryable: true,
+        });
+    }
+
+    let embedder: Box<dyn Embedder> = match kind {
+        EmbedderKind::Auto | EmbedderKind::Hash => Box::new(HashEmbedder::default_dimension()),
+        EmbedderKind::Minilm => {
+            let model_dir = FastEmbedder::default_model_dir(&data_dir);
+            let embedder = FastEmbedder::load_from_dir(&model_dir).map_err(|e| CliError {
+                code: 3,
+                kind: "missing_model",
+                message: format!("Failed to load embedding model: {e}"),
+                hint: Some(format!(
+                    "Place the MiniLM model files in {}, or use --embedder hash.",
+                    model_dir.display()
+                )),
+                retryable: false,
+            })?;
+            Box::new(embedder)
         }
-        _ => {
-            tracing_subscriber::fmt()
-                .with_env_filter(filter)
-                .with_writer(std::io::stderr)
-                .compact()
-                .with_target(false)
-                .with_ansi(
-                    matches!(cli.color, ColorPref::Always)
-                        || (matches!(cli.color, ColorPref::Auto) && stderr_is_tty),
-                )
-                .init();
+    };
 
-            match command {
-                Commands::Completions { shell } => {
-                    let mut cmd = Cli::command();
-                    clap_complete::generate(shell, &mut cmd, "cass", &mut std::io::stdout());
-                }
-                Commands::Man => {
-                    let cmd = Cli::command();
-                    let man = clap_mangen::Man::new(cmd);
-                    man.render(&mut std::io::stdout())
-                        .map_err(|e| CliError::unknown(format!("failed to render man: {e}")))?;
-                }
-                Commands::Capabilities { json } => {
-                    run_capabilities(json)?;
-                }
-                Commands::ApiVersion { json } => {
-                    run_api_version(json)?;
-                }
-                Commands::State {
-                    data_dir,
-                    json,
-                    robot_meta,
-                    stale_threshold,
-                } => {
-                    run_status(&data_dir, None, json, stale_threshold, robot_meta)?;
-                }
-                Commands::Introspect { json } => {
-                    run_introspect(json)?;
-                }
-                Commands::Health {
-                    data_dir,
-                    json,
-                    robot_meta,
-                    stale_threshold,
-                } => {
-                    run_health(&data_dir, cli.db.clone(), json, stale_threshold, robot_meta)?;
-                }
-                Commands::Context {
-                    path,
-                    data_dir,
-                    json,
-                    limit,
-                } => {
-                    run_context(&path, &data_dir, cli.db.clone(), json, limit)?;
-                }
-                Commands::Export {
-                    path,
-                    format,
-                    output,
-                    include_tools,
-                } => {
-                    run_export(&path, format, output.as_deref(), include_tools)?;
-                }
-                Commands::Expand {
-                    path,
-                    line,
-                    context,
-                    json,
-                } => {
-                    run_expand(&path, line, context, json)?;
-                }
-                Commands::Timeline {
-                    since,
-                    until,
-                    today,
-                    agent,
-                    data_dir,
-                    json,
-                    group_by,
-                    source,
-                } => {
-                    run_timeline(
-                        since.as_deref(),
-                        until.as_deref(),
-                        today,
-                        &agent,
-                        &data_dir,
-                        cli.db.clone(),
-                        json,
-                        group_by,
-                        source,
-                    )?;
-                }
-                Commands::Sources(subcmd) => {
-                    run_sources_command(subcmd)?;
-                }
-                _ => {}
-            }
+    let _lock = acquire_writer_lock(&data_dir)?;
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let index_path = crate::search::vector_index::vector_index_path(&data_dir, embedder.id());
+    let report = crate::search::embed_backfill::backfill_embeddings(
+        storage.raw(),
+        embedder.as_ref(),
+        &index_path,
+        opts,
+    )
+    .map_err(|e| CliError {
+        code: 9,
+        kind: "embed",
+        message: format!("Embedding failed: {e}"),
+        hint: Some(
+            "Progress up to the last checkpoint was saved; re-run with --backfill to resume."
+                .to_string(),
+        ),
+        retryable: true,
+    })?;
+
+    if json {
+        let mut value = serde_json::to_value(&report).unwrap_or_default();
+        value["index_path"] = serde_json::json!(index_path.display().to_string());
+        if let Some(selection) = &selection {
+            value["selected_because"] = serde_json::json!(selection.reasons);
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+    } else {
+        use colored::Colorize;
+        println!(
+            "{} {} messages with {} [{}] ({} reused, {} dropped, {} empty)",
+            "Embedded".green().bold(),
+            report.embedded,
+            report.embedder_id.cyan(),
+            report.profile.as_str(),
+            report.reused,
+            report.dropped,
+            report.skipped_empty
+        );
+        if let Some(selection) = &selection {
+            println!(
+                "{}",
+                format!("auto-selected: {}", selection.reasons.join("; ")).dimmed()
+            );
         }
+        println!(
+            "{} vectors in {}",
+            report.total_vectors,
+            index_path.display().to_string().dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Report daemon liveness from its health file
+fn run_daemon_status(data_dir_override: &Option<PathBuf>, json: bool) -> CliResult<()> {
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let now_ms = Utc::now().timestamp_millis();
+    let status = daemon::read_status(&data_dir);
+    let running = status.as_ref().is_some_and(|s| s.is_alive(now_ms));
+    let healthy = status.as_ref().is_some_and(|s| s.is_healthy(now_ms));
+
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+
+    if json {
+        let payload = serde_json::json!({
+            "running": running,
+            "healthy": healthy,
+            "pid": status.as_ref().map(|s| s.pid),
+            "started_at": status.as_ref().map(|s| format_ts(s.started_at)),
+            "uptime_secs": status.as_ref().filter(|_| running).map(|s| (now_ms - s.started_at) / 1000),
+            "heartbeat_at": status.as_ref().map(|s| format_ts(s.heartbeat_at)),
+            "reconcile_interval_secs": status.as_ref().map(|s| s.reconcile_interval_secs),
+            "last_reconcile_at": status.as_ref().and_then(|s| s.last_reconcile_at).map(format_ts),
+            "next_reconcile_at": status.as_ref().map(|s| format_ts(s.next_reconcile_at)),
+            "sources": status.as_ref().map(|s| s.sources.iter().map(|src| serde_json::json!({
+                "name": src.name,
+                "sync_schedule": src.sync_schedule.to_string(),
+                "last_sync": src.last_sync.map(format_ts),
+                "next_sync": src.next_sync.map(format_ts),
+                "consecutive_failures": src.consecutive_failures,
+            })).collect::<Vec<_>>()).unwrap_or_default(),
+            "reports": status.as_ref().map(|s| s.reports.iter().map(|r| serde_json::json!({
+                "name": r.name,
+                "schedule": r.schedule.to_string(),
+                "last_run": r.last_run.map(format_ts),
+                "next_run": format_ts(r.next_run),
+                "consecutive_failures": r.consecutive_failures,
+            })).collect::<Vec<_>>()).unwrap_or_default(),
+            "indexing": status.as_ref().map(|s| serde_json::json!({
+                "passes": s.metrics.passes,
+                "last_pass_at": s.metrics.last_pass_at.map(format_ts),
+                "last_latency_ms": s.metrics.last_pass_ms,
+                "queue_depth": s.queue_depth,
+                "errors": s.metrics.errors,
+                "consecutive_errors": s.metrics.consecutive_errors,
+                "last_error": s.metrics.last_error,
+                "last_error_at": s.metrics.last_error_at.map(format_ts),
+                "connectors": s.metrics.connectors.iter().map(|(name, c)| serde_json::json!({
+                    "name": name,
+                    "last_change_at": c.last_change_at.map(format_ts),
+                    "last_indexed_at": c.last_indexed_at.map(format_ts),
+                    "lag_ms": c.lag_ms(),
+                    "conversations_indexed": c.conversations_indexed,
+                })).collect::<Vec<_>>(),
+            })),
+            "health_file": daemon::status_path(&data_dir).display().to_string(),
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
     }
 
+    use colored::Colorize;
+    let Some(status) = status else {
+        println!(
+            "{}",
+            "Daemon has never run. Start it with `cass daemon`.".dimmed()
+        );
+        return Ok(());
+    };
+    if running && !healthy {
+        println!(
+            "{} (pid {}): last {} indexing passes failed",
+            "Daemon unhealthy".red().bold(),
+            status.pid,
+            status.metrics.consecutive_errors
+        );
+    } else if running {
+        println!("{} (pid {})", "Daemon running".green().bold(), status.pid);
+    } else {
+        println!(
+            "{} (last heartbeat {})",
+            "Daemon not running".yellow().bold(),
+            format_ts(status.heartbeat_at)
+        );
+    }
+    println!("  Started:         {}", format_ts(status.started_at));
+    if let Some(ts) = status.last_reconcile_at {
+        println!("  Last reconcile:  {}", format_ts(ts));
+    }
+    println!("  Next reconcile:  {}", format_ts(status.next_reconcile_at));
+    let metrics = &status.metrics;
+    if let (Some(at), Some(ms)) = (metrics.last_pass_at, metrics.last_pass_ms) {
+        println!("  Last index pass: {} ({ms} ms)", format_ts(at));
+    }
+    println!(
+        "  Queue depth:     {}   Passes: {}   Errors: {}",
+        status.queue_depth, metrics.passes, metrics.errors
+    );
+    if let Some(err) = &metrics.last_error {
+        let at = metrics.last_error_at.map(format_ts).unwrap_or_default();
+        println!("  Last error:      {} {}", err.as_str().red(), at.dimmed());
+    }
+    for (name, lag) in &metrics.connectors {
+        let lag = lag
+            .lag_ms()
+            .map(|ms| format!("{:.1}s lag", ms as f64 / 1000.0))
+            .unwrap_or_else(|| "no changes yet".to_string());
+        println!("  {} {}", name.as_str().cyan(), lag.dimmed());
+    }
+    for src in &status.sources {
+        let next = src
+            .next_sync
+            .map(format_ts)
+            .unwrap_or_else(|| "manual".to_string());
+        let retry = if src.consecutive_failures > 0 {
+            format!(" (retry after {} failures)", src.consecutive_failures)
+                .yellow()
+                .to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} [{}] next sync: {}{retry}",
+            src.name.as_str().cyan(),
+            src.sync_schedule,
+            next.dimmed()
+        );
+    }
+    for report in &status.reports {
+        let retry = if report.consecutive_failures > 0 {
+            format!(" (retry after {} failures)", report.consecutive_failures)
+                .yellow()
+                .to_string()
+        } else {
+            String::new()
+        };
+        println!(
+            "  report {} [{}] next run: {}{retry}",
+            report.name.as_str().cyan(),
+            report.schedule,
+            format_ts(report.next_run).dimmed()
+        );
+    }
     Ok(())
 }
 
 /// Compute lightweight state snapshot (index/db freshness) for robot meta and state command reuse
 fn state_meta_json(data_dir: &Path, db_path: &Path, stale_threshold: u64) -> serde_json::Value {
-    use rusqlite::Connection;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // Use the actual versioned index path (index/v4, not tantivy_index)
@@ -1929,7 +7275,7 @@ fn state_meta_json(data_dir: &Path, db_path: &Path, stale_threshold: u64) -> ser
     let mut message_count: i64 = 0;
     let mut last_indexed_at: Option<i64> = None;
 
-    if db_exists && let Ok(conn) = Connection::open(db_path) {
+    if db_exists && let Ok(conn) = crate::storage::sqlite::open_readonly_connection(db_path) {
         conversation_count = conn
             .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
             .unwrap_or(0);
@@ -2043,7 +7389,11 @@ fn describe_command(cli: &Cli) -> String {
         Some(Commands::Tui { .. }) => "tui".to_string(),
         Some(Commands::Index { .. }) => "index".to_string(),
         Some(Commands::Search { .. }) => "search".to_string(),
-        Some(Commands::Stats { .. }) => "stats".to_string(),
+        Some(Commands::Stats { command: None, .. }) => "stats".to_string(),
+        Some(Commands::Stats {
+            command: Some(StatsCommand::Storage { .. }),
+            ..
+        }) => "stats:storage".to_string(),
         Some(Commands::Diag { .. }) => "diag".to_string(),
         Some(Commands::Status { .. }) => "status".to_string(),
         Some(Commands::View { .. }) => "view".to_string(),
@@ -2060,10 +7410,64 @@ fn describe_command(cli: &Cli) -> String {
         Some(Commands::Expand { .. }) => "expand".to_string(),
         Some(Commands::Timeline { .. }) => "timeline".to_string(),
         Some(Commands::Sources(..)) => "sources".to_string(),
+        Some(Commands::Sync { .. }) => "sync".to_string(),
+        Some(Commands::Serve { .. }) => "serve".to_string(),
+        Some(Commands::Mcp { .. }) => "mcp".to_string(),
+        Some(Commands::Rpc { .. }) => "rpc".to_string(),
+        Some(Commands::Undo { .. }) => "undo".to_string(),
+        Some(Commands::Lock { .. }) => "lock".to_string(),
+        Some(Commands::Rate { .. }) => "rate".to_string(),
+        Some(Commands::Projects(..)) => "projects".to_string(),
+        Some(Commands::Graph { .. }) => "graph".to_string(),
+        Some(Commands::Decisions { .. }) => "decisions".to_string(),
+        Some(Commands::Audit { .. }) => "audit".to_string(),
+        Some(Commands::Embed { .. }) => "embed".to_string(),
+        Some(Commands::Daemon { command: None, .. }) => "daemon".to_string(),
+        Some(Commands::QueryTemplate(..)) => "query-template".to_string(),
+        Some(Commands::Show { .. }) => "show".to_string(),
+        Some(Commands::Pick { .. }) => "pick".to_string(),
+        Some(Commands::Agents { .. }) => "agents".to_string(),
+        Some(Commands::Workspaces { .. }) => "workspaces".to_string(),
+        Some(Commands::Tag(..)) => "tag".to_string(),
+        Some(Commands::Update { .. }) => "update".to_string(),
+        Some(Commands::Compact { .. }) => "compact".to_string(),
+        Some(Commands::Note(..)) => "note".to_string(),
+        Some(Commands::Prune { .. }) => "prune".to_string(),
+        Some(Commands::Dedupe { .. }) => "dedupe".to_string(),
+        Some(Commands::Doctor { .. }) => "doctor".to_string(),
+        Some(Commands::Db(DbCommand::Optimize { .. })) => "db:optimize".to_string(),
+        Some(Commands::Db(DbCommand::Export { .. })) => "db:export".to_string(),
+        Some(Commands::Db(DbCommand::Import { .. })) => "db:import".to_string(),
+        Some(Commands::Report(ReportCommand::List { .. })) => "report:list".to_string(),
+        Some(Commands::Report(ReportCommand::Run { .. })) => "report:run".to_string(),
+        Some(Commands::Config(ConfigCommand::Get { .. })) => "config:get".to_string(),
+        Some(Commands::Config(ConfigCommand::Set { .. })) => "config:set".to_string(),
+        Some(Commands::Config(ConfigCommand::Validate { .. })) => "config:validate".to_string(),
+        Some(Commands::Config(ConfigCommand::Path { .. })) => "config:path".to_string(),
+        Some(Commands::Daemon {
+            command: Some(DaemonCommand::Status { .. }),
+            ..
+        }) => "daemon:status".to_string(),
         None => "(default)".to_string(),
     }
 }
 
+/// Read commands validated by [`selfcheck::check`] before they run, with their
+/// `--data-dir` override. Writers (index, import, ...) migrate or rebuild instead.
+fn self_check_data_dir(command: &Commands) -> Option<Option<PathBuf>> {
+    match command {
+        Commands::Search { data_dir, .. }
+        | Commands::Stats { data_dir, .. }
+        | Commands::Timeline { data_dir, .. }
+        | Commands::Context { data_dir, .. }
+        | Commands::Show { data_dir, .. }
+        | Commands::Pick { data_dir, .. }
+        | Commands::Serve { data_dir, .. }
+        | Commands::Workspaces { data_dir, .. } => Some(data_dir.clone()),
+        _ => None,
+    }
+}
+
 /// Returns true if the command is using robot/JSON output mode.
 /// Used to auto-suppress INFO logs for clean machine-parseable output.
 fn is_robot_mode(command: &Commands) -> bool {
@@ -2075,6 +7479,10 @@ fn is_robot_mode(command: &Commands) -> bool {
             ..
         } => *json || robot_format.is_some() || *robot_meta,
         Commands::Index { json, .. } => *json,
+        Commands::Stats {
+            command: Some(StatsCommand::Storage { json, .. }),
+            ..
+        } => *json,
         Commands::Stats { json, .. } => *json,
         Commands::Diag { json, .. } => *json,
         Commands::Status { json, .. } => *json,
@@ -2085,6 +7493,75 @@ fn is_robot_mode(command: &Commands) -> bool {
         Commands::Capabilities { json, .. } => *json,
         Commands::Introspect { json, .. } => *json,
         Commands::Context { json, .. } => *json,
+        Commands::Undo { json, .. } => *json,
+        Commands::Lock { json, .. } => *json,
+        Commands::Rate { json, .. } => *json,
+        Commands::Decisions { json, format, .. } => *json || *format == DecisionsFormat::Json,
+        Commands::Audit { json, .. } => *json,
+        Commands::Embed { json, .. } => *json,
+        Commands::Projects(
+            ProjectsCommand::List { json, .. }
+            | ProjectsCommand::Set { json, .. }
+            | ProjectsCommand::Unset { json, .. }
+            | ProjectsCommand::Refresh { json, .. },
+        ) => *json,
+        Commands::Daemon {
+            command: Some(DaemonCommand::Status { json, .. }),
+            ..
+        } => *json,
+        Commands::QueryTemplate(
+            QueryTemplateCommand::List { json } | QueryTemplateCommand::Render { json, .. },
+        ) => *json,
+        Commands::Show { json, .. } => *json,
+        // stderr belongs to the picker and stdout to the selection
+        Commands::Pick { .. } => true,
+        Commands::Agents { json, .. } => *json,
+        Commands::Workspaces { json, .. } => *json,
+        Commands::Tag(
+            TagCommand::Add { json, .. }
+            | TagCommand::Rm { json, .. }
+            | TagCommand::List { json, .. },
+        ) => *json,
+        Commands::Update { json, .. } => *json,
+        Commands::Compact { json, .. } => *json,
+        Commands::Note(
+            NoteCommand::Add { json, .. }
+            | NoteCommand::Edit { json, .. }
+            | NoteCommand::Rm { json, .. }
+            | NoteCommand::List { json, .. },
+        ) => *json,
+        Commands::Prune { json, .. } => *json,
+        Commands::Dedupe { json, .. } => *json,
+        Commands::Doctor { json, .. } => *json,
+        Commands::Db(
+            DbCommand::Optimize { json, .. }
+            | DbCommand::Export { json, .. }
+            | DbCommand::Import { json, .. },
+        ) => *json,
+        Commands::Report(ReportCommand::List { json, .. } | ReportCommand::Run { json, .. }) => {
+            *json
+        }
+        Commands::Config(
+            ConfigCommand::Get { json, .. }
+            | ConfigCommand::Set { json, .. }
+            | ConfigCommand::Validate { json }
+            | ConfigCommand::Path { json },
+        ) => *json,
+        Commands::Sources(
+            SourcesCommand::List { json, .. }
+            | SourcesCommand::Add { json, .. }
+            | SourcesCommand::Remove { json, .. }
+            | SourcesCommand::Show { json, .. }
+            | SourcesCommand::Test { json, .. }
+            | SourcesCommand::MapTest { json, .. }
+            | SourcesCommand::Discover { json, .. }
+            | SourcesCommand::Sync { json, .. },
+        ) => *json,
+        Commands::Sync { json, .. } => *json,
+        // stdout carries the stream
+        Commands::Serve { sync, .. } => *sync,
+        // stdout carries the protocol
+        Commands::Mcp { .. } | Commands::Rpc { .. } => true,
         _ => false,
     }
 }
@@ -2285,7 +7762,10 @@ fn print_robot_docs(topic: RobotTopic, wrap: WrapConfig) -> CliResult<()> {
             "  Tip: `--robot-docs=<topic>` is normalized to `robot-docs <topic>`; globals can appear before/after subcommands.".to_string(),
             "  cass search <query> [OPTIONS]".to_string(),
             "    --agent A         Filter by agent (codex, claude_code, gemini, opencode, amp, cline)".to_string(),
-            "    --workspace W     Filter by workspace path".to_string(),
+            "    --workspace W     Filter by workspace path prefix".to_string(),
+            "    --role R          Filter by message role (user, assistant, system, tool)".to_string(),
+            "    --source S        Filter by source (local, remote, or a source id)".to_string(),
+            "                      --agent, --workspace, --role and --source repeat; values of one flag are OR-ed".to_string(),
             "    --limit N         Max results (default: 10)".to_string(),
             "    --offset N        Pagination offset (default: 0)".to_string(),
             "    --json | --robot  JSON output for automation".to_string(),
@@ -2368,7 +7848,7 @@ fn print_robot_docs(topic: RobotTopic, wrap: WrapConfig) -> CliResult<()> {
             "  cass view /path/to/session.jsonl -n 42 -C 10  # 10 lines context".to_string(),
             String::new(),
             "# Get index statistics".to_string(),
-            "  cass stats --json                        # JSON stats".to_string(),
+            "  cass stats --json                        # JSON stats (versioned; see schemas)".to_string(),
             "  cass stats                               # Human-readable stats".to_string(),
             String::new(),
             "# Aggregation (overview queries - 99% token reduction)".to_string(),
@@ -2660,6 +8140,7 @@ fn run_cli_search(
     query: &str,
     agents: &[String],
     workspaces: &[String],
+    roles: &[RoleFilter],
     limit: &usize,
     offset: &usize,
     json: &bool,
@@ -2682,9 +8163,14 @@ fn run_cli_search(
     dry_run: bool,
     timeout_ms: Option<u64>,
     highlight: bool,
-    source: Option<String>,
+    sources: &[String],
     sessions_from: Option<String>,
+    code_only: bool,
+    project: Option<String>,
+    starred: bool,
+    compare_period: Option<String>,
 ) -> CliResult<()> {
+    use crate::search::aliases::QueryAliases;
     use crate::search::query::{QueryExplanation, SearchClient, SearchFilters};
     use crate::search::tantivy::index_dir;
     use crate::sources::provenance::SourceFilter;
@@ -2693,6 +8179,10 @@ fn run_cli_search(
     // Start timing for robot_meta elapsed_ms
     let start_time = Instant::now();
 
+    // Expand `@alias` macros from config.toml before anything inspects the query
+    let expanded_query = QueryAliases::load().expand(query);
+    let query = expanded_query.as_str();
+
     let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
     let index_path = index_dir(&data_dir).map_err(|e| CliError {
         code: 9,
@@ -2722,6 +8212,12 @@ fn run_cli_search(
             retryable: true,
         })?;
 
+    // Hits, fallbacks and hydration all read the same index generation and DB snapshot,
+    // even if the indexer commits while a large result set is being assembled.
+    if let Err(e) = client.pin_snapshot() {
+        tracing::warn!("could not pin search snapshot: {e}");
+    }
+
     let mut filters = SearchFilters::default();
     if !agents.is_empty() {
         filters.agents = HashSet::from_iter(agents.iter().cloned());
@@ -2729,12 +8225,43 @@ fn run_cli_search(
     if !workspaces.is_empty() {
         filters.workspaces = HashSet::from_iter(workspaces.iter().cloned());
     }
+    filters.roles = roles.iter().map(|r| r.as_str().to_string()).collect();
     filters.created_from = time_filter.since;
     filters.created_to = time_filter.until;
+    filters.code_only = code_only;
+    if starred {
+        filters
+            .tags
+            .insert(crate::storage::sqlite::STARRED_TAG.to_string());
+    }
+
+    // A project expands to all of its workspaces (clones, worktrees, remote copies)
+    if let Some(ref project) = project {
+        let project_workspaces = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path)
+            .and_then(|s| s.project_workspaces(project))
+            .map_err(|e| CliError {
+                code: 9,
+                kind: "db-open",
+                message: format!("failed to read project mapping: {e}"),
+                hint: None,
+                retryable: true,
+            })?;
+        if project_workspaces.is_empty() {
+            return Err(CliError {
+                code: 2,
+                kind: "unknown-project",
+                message: format!("no workspaces are mapped to project '{project}'"),
+                hint: Some("List projects with 'cass projects list'".to_string()),
+                retryable: false,
+            });
+        }
+        filters.workspaces.extend(project_workspaces);
+    }
 
-    // Apply source filter (P3.1)
-    if let Some(ref source_str) = source {
-        filters.source_filter = SourceFilter::parse(source_str);
+    // Apply source filter (P3.1); repeated sources match any of them
+    if !sources.is_empty() {
+        filters.source_filter =
+            SourceFilter::any_of(sources.iter().map(|s| SourceFilter::parse(s)).collect());
     }
 
     // Apply session paths filter (for chained searches)
@@ -2852,6 +8379,18 @@ fn run_cli_search(
         });
     }
 
+    if let Some(ref spec) = compare_period {
+        return run_compare_period(
+            &client,
+            query,
+            &filters,
+            spec,
+            limit_val,
+            effective_robot.is_some(),
+            request_id,
+        );
+    }
+
     let result = client
         .search_with_fallback(
             query,
@@ -2981,6 +8520,15 @@ fn run_cli_search(
             )
         });
 
+    // A corrupt Tantivy index degrades to SQLite FTS; say so instead of failing
+    let index_error = client.index_error();
+    if let Some(err) = &index_error {
+        warn_index_corrupt(err, effective_robot.is_some());
+    }
+    let warning = index_error
+        .map(|e| format!("index-corrupt: {e}. Results come from SQLite FTS; run `cass index --rebuild` to repair the search index."))
+        .or(warning);
+
     let index_freshness_for_closure = index_freshness.clone();
     let state_meta_with_warning = state_meta.map(|mut meta| {
         if let Some(fresh) = index_freshness_for_closure
@@ -3052,6 +8600,147 @@ fn run_cli_search(
     Ok(())
 }
 
+/// Structured `index-corrupt` warning on stderr: JSON for robots, a colored line otherwise
+fn warn_index_corrupt(error: &str, robot: bool) {
+    let hint = "Run 'cass index --rebuild' to rebuild the search index from the database.";
+    if robot {
+        eprintln!(
+            "{}",
+            serde_json::json!({
+                "warning": {
+                    "kind": "index-corrupt",
+                    "message": format!("search index unreadable, falling back to SQLite FTS: {error}"),
+                    "hint": hint,
+                }
+            })
+        );
+    } else {
+        use colored::Colorize;
+        eprintln!(
+            "{} search index unreadable ({error}); results come from SQLite FTS.\n  {}",
+            "warning[index-corrupt]:".yellow().bold(),
+            hint.dimmed()
+        );
+    }
+}
+
+/// Run the query over two time ranges and report how the matching sessions changed
+fn run_compare_period(
+    client: &crate::search::query::SearchClient,
+    query: &str,
+    filters: &crate::search::query::SearchFilters,
+    spec: &str,
+    limit: usize,
+    json: bool,
+    request_id: Option<String>,
+) -> CliResult<()> {
+    use crate::search::compare::{
+        COMPARE_HIT_LIMIT, ComparePeriod, SessionMatch, TimeRange, compare_hits,
+    };
+    use colored::Colorize;
+
+    let period =
+        ComparePeriod::parse(spec, chrono::Utc::now().timestamp_millis()).map_err(|e| {
+            CliError::usage(
+                format!("invalid --compare-period: {e}"),
+                Some("Example: --compare-period '-7d..now vs -14d..-7d'".to_string()),
+            )
+        })?;
+    // Sessions are compared, not messages, so fetch enough hits to cover each range
+    let fetch = COMPARE_HIT_LIMIT.max(limit);
+    let search = |range: &TimeRange| {
+        let mut filters = filters.clone();
+        filters.created_from = range.from;
+        filters.created_to = range.to;
+        client
+            .search_with_fallback(query, filters, fetch, 0, 3)
+            .map(|r| r.hits)
+            .map_err(|e| CliError {
+                code: 9,
+                kind: "search",
+                message: format!("search failed for range '{}': {e}", range.label),
+                hint: None,
+                retryable: true,
+            })
+    };
+    let current = search(&period.current)?;
+    let previous = search(&period.previous)?;
+    let mut comparison = compare_hits(&period, &current, &previous, fetch);
+    for list in [
+        &mut comparison.new,
+        &mut comparison.continuing,
+        &mut comparison.previous_only,
+    ] {
+        list.truncate(limit);
+    }
+
+    if json {
+        let payload = serde_json::json!({
+            "query": query,
+            "compare_period": comparison,
+            "request_id": request_id,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    for name in &comparison.truncated {
+        let range = if *name == "current" {
+            &comparison.current
+        } else {
+            &comparison.previous
+        };
+        eprintln!(
+            "{} {} reached {} hits; some sessions may be missing or undercounted.\n  {}",
+            "warning[compare-truncated]:".yellow().bold(),
+            range.label,
+            fetch,
+            "Narrow the query or the range, or raise --limit.".dimmed()
+        );
+    }
+
+    let section = |heading: String, sessions: &[SessionMatch]| {
+        println!("{heading} ({})", sessions.len());
+        for s in sessions {
+            let when = s
+                .last_match_at
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map_or_else(
+                    || "unknown".to_string(),
+                    |d| d.format("%Y-%m-%d").to_string(),
+                );
+            println!(
+                "  {}  {:<10} {}  {}",
+                when,
+                s.agent.cyan(),
+                s.title,
+                format!("({} hits) {}", s.hits, s.source_path).dimmed()
+            );
+        }
+        println!();
+    };
+    section(
+        format!("{} in {}", "New".green().bold(), comparison.current.label),
+        &comparison.new,
+    );
+    section(
+        format!("{} in both ranges", "Continuing".yellow().bold()),
+        &comparison.continuing,
+    );
+    section(
+        format!(
+            "{} {}",
+            "Only in".dimmed().bold(),
+            comparison.previous.label
+        ),
+        &comparison.previous_only,
+    );
+    Ok(())
+}
+
 /// Output search results in human-readable display format
 fn output_display_results(
     hits: &[crate::search::query::SearchHit],
@@ -3655,9 +9344,10 @@ fn run_stats(
     json: bool,
     source: Option<&str>,
     by_source: bool,
+    by_project: bool,
+    days: u32,
 ) -> CliResult<()> {
     use crate::sources::provenance::SourceFilter;
-    use rusqlite::Connection;
 
     let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
     let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
@@ -3675,13 +9365,15 @@ fn run_stats(
         });
     }
 
-    let conn = Connection::open(&db_path).map_err(|e| CliError {
-        code: 9,
-        kind: "db-open",
-        message: format!("Failed to open database: {e}"),
-        hint: None,
-        retryable: false,
-    })?;
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+    let conn = storage.raw();
 
     // Parse source filter (P3.7)
     let source_filter = source.map(SourceFilter::parse);
@@ -3694,6 +9386,28 @@ fn run_stats(
         Some(SourceFilter::SourceId(id)) => {
             (" WHERE c.source_id = ?".to_string(), Some(id.clone()))
         }
+        Some(filter @ SourceFilter::Any(_)) if filter.is_all() => (String::new(), None),
+        Some(SourceFilter::Any(filters)) => {
+            // Named sources share one JSON array parameter so the queries below keep
+            // their single optional binding
+            let mut conditions = Vec::new();
+            let mut ids = Vec::new();
+            for filter in filters {
+                match filter {
+                    SourceFilter::Local => conditions.push("c.source_id = 'local'"),
+                    SourceFilter::Remote => conditions.push("c.source_id != 'local'"),
+                    SourceFilter::SourceId(id) => ids.push(id.clone()),
+                    SourceFilter::All | SourceFilter::Any(_) => {}
+                }
+            }
+            if !ids.is_empty() {
+                conditions.push("c.source_id IN (SELECT value FROM json_each(?))");
+            }
+            (
+                format!(" WHERE ({})", conditions.join(" OR ")),
+                (!ids.is_empty()).then(|| serde_json::json!(ids).to_string()),
+            )
+        }
     };
 
     // Get counts and statistics with source filter
@@ -3731,30 +9445,88 @@ fn run_stats(
     }
     .unwrap_or(0);
 
+    // Sum of BPE token counts (NULL for conversations indexed before token counting)
+    let token_sql =
+        format!("SELECT COALESCE(SUM(c.approx_tokens), 0) FROM conversations c{source_where}");
+    let token_count: i64 = if let Some(ref param) = source_param {
+        conn.query_row(&token_sql, [param], |r| r.get(0))
+    } else {
+        conn.query_row(&token_sql, [], |r| r.get(0))
+    }
+    .unwrap_or(0);
+
     // Get per-agent breakdown with source filter
     let agent_sql = format!(
-        "SELECT a.slug, COUNT(*) FROM conversations c JOIN agents a ON c.agent_id = a.id{source_where} GROUP BY a.slug ORDER BY COUNT(*) DESC"
+        "SELECT a.slug, COUNT(*),
+                SUM((SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)),
+                COALESCE(SUM(c.approx_tokens), 0)
+         FROM conversations c JOIN agents a ON c.agent_id = a.id{source_where}
+         GROUP BY a.slug ORDER BY COUNT(*) DESC"
     );
-    let agent_rows: Vec<(String, i64)> = if let Some(ref param) = source_param {
+    let agent_row = |r: &rusqlite::Row| -> rusqlite::Result<(String, i64, i64, i64)> {
+        Ok((
+            r.get(0)?,
+            r.get(1)?,
+            r.get::<_, Option<i64>>(2)?.unwrap_or(0),
+            r.get(3)?,
+        ))
+    };
+    let agent_rows: Vec<(String, i64, i64, i64)> = {
         let mut stmt = conn
             .prepare(&agent_sql)
             .map_err(|e| CliError::unknown(format!("query prep: {e}")))?;
-        stmt.query_map([param], |r| {
-            Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+        stmt.query_map(rusqlite::params_from_iter(&source_param), agent_row)
+            .map_err(|e| CliError::unknown(format!("query: {e}")))?
+            .filter_map(std::result::Result::ok)
+            .collect()
+    };
+
+    // Per local day over the last `days` days, oldest first, by when each message was
+    // sent (falling back to its conversation's start) so long sessions spread over
+    // the days they were active
+    let daily_rows: Vec<(String, i64, i64)> = if days > 0 {
+        let since = chrono::Utc::now().timestamp_millis() - i64::from(days) * 86_400_000;
+        let joiner = if source_where.is_empty() {
+            " WHERE"
+        } else {
+            " AND"
+        };
+        let daily_sql = format!(
+            "SELECT date(COALESCE(m.created_at, c.started_at) / 1000, 'unixepoch', 'localtime') AS day,
+                    COUNT(DISTINCT c.id), COUNT(*)
+             FROM messages m
+             JOIN conversations c ON m.conversation_id = c.id{source_where}{joiner}
+                  COALESCE(m.created_at, c.started_at) >= ?
+             GROUP BY day ORDER BY day"
+        );
+        let mut params: Vec<rusqlite::types::Value> =
+            source_param.iter().cloned().map(Into::into).collect();
+        params.push(since.into());
+        let mut stmt = conn
+            .prepare(&daily_sql)
+            .map_err(|e| CliError::unknown(format!("query prep: {e}")))?;
+        stmt.query_map(rusqlite::params_from_iter(params), |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?))
         })
         .map_err(|e| CliError::unknown(format!("query: {e}")))?
         .filter_map(std::result::Result::ok)
         .collect()
     } else {
-        let mut stmt = conn
-            .prepare(&agent_sql)
-            .map_err(|e| CliError::unknown(format!("query prep: {e}")))?;
-        stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))
-            .map_err(|e| CliError::unknown(format!("query: {e}")))?
-            .filter_map(std::result::Result::ok)
-            .collect()
+        Vec::new()
     };
 
+    // Sizes and freshness describe the whole data dir, whatever the source filter
+    let content_bytes: u64 = storage
+        .agent_storage()
+        .map(|agents| agents.iter().map(|a| a.content_bytes).sum())
+        .unwrap_or(0);
+    let db_bytes = sqlite_footprint(&db_path);
+    let index_bytes = crate::search::tantivy::index_dir(&data_dir)
+        .map(|p| fs_dir_size(&p))
+        .unwrap_or(0);
+    let freshness =
+        state_meta_json(&data_dir, &db_path, DEFAULT_STALE_THRESHOLD_SECS)["index"].clone();
+
     // Get workspace breakdown with source filter (top 10)
     let ws_sql = format!(
         "SELECT w.path, COUNT(*) FROM conversations c JOIN workspaces w ON c.workspace_id = w.id{source_where} GROUP BY w.path ORDER BY COUNT(*) DESC LIMIT 10"
@@ -3816,11 +9588,56 @@ fn run_stats(
         Vec::new()
     };
 
+    // Per-project breakdown; unmapped workspaces count as their own project
+    let project_rows: Vec<(String, i64, i64)> = if by_project {
+        let mut stmt = conn
+            .prepare(
+                "SELECT COALESCE(p.project, w.path) AS project, COUNT(DISTINCT c.id) as convs, COUNT(m.id) as msgs
+             FROM conversations c
+             JOIN workspaces w ON c.workspace_id = w.id
+             LEFT JOIN workspace_projects p ON p.workspace = w.path
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             GROUP BY project
+             ORDER BY convs DESC",
+            )
+            .map_err(|e| CliError::unknown(format!("query prep: {e}")))?;
+        stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, i64>(1)?,
+                r.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| CliError::unknown(format!("query: {e}")))?
+        .filter_map(std::result::Result::ok)
+        .collect()
+    } else {
+        Vec::new()
+    };
+
     if json {
         let mut payload = serde_json::json!({
+            "schema_version": STATS_SCHEMA_VERSION,
             "conversations": conversation_count,
             "messages": message_count,
-            "by_agent": agent_rows.iter().map(|(a, c)| serde_json::json!({"agent": a, "count": c})).collect::<Vec<_>>(),
+            "approx_tokens": token_count,
+            "by_agent": agent_rows.iter().map(|(a, c, m, t)| serde_json::json!({
+                "agent": a,
+                "count": c,
+                "messages": m,
+                "approx_tokens": t,
+            })).collect::<Vec<_>>(),
+            "daily": daily_rows.iter().map(|(day, c, m)| serde_json::json!({
+                "day": day,
+                "conversations": c,
+                "messages": m,
+            })).collect::<Vec<_>>(),
+            "size": {
+                "db_bytes": db_bytes,
+                "index_bytes": index_bytes,
+                "content_bytes": content_bytes,
+            },
+            "index": freshness,
             "top_workspaces": ws_rows.iter().map(|(w, c)| serde_json::json!({"workspace": w, "count": c})).collect::<Vec<_>>(),
             "date_range": {
                 "oldest": oldest.map(|ts| chrono::DateTime::from_timestamp_millis(ts).map(|d| d.to_rfc3339())),
@@ -3850,6 +9667,21 @@ fn run_stats(
             );
         }
 
+        if by_project && !project_rows.is_empty() {
+            payload["by_project"] = serde_json::json!(
+                project_rows
+                    .iter()
+                    .map(|(p, convs, msgs)| {
+                        serde_json::json!({
+                            "project": p,
+                            "conversations": convs,
+                            "messages": msgs
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            );
+        }
+
         println!(
             "{}",
             serde_json::to_string_pretty(&payload).unwrap_or_default()
@@ -3877,15 +9709,51 @@ fn run_stats(
             println!();
         }
 
+        if by_project && !project_rows.is_empty() {
+            println!("By Project:");
+            println!("  {:40} {:>10} {:>12}", "Project", "Convs", "Messages");
+            println!("  {}", "-".repeat(64));
+            for (project, convs, msgs) in &project_rows {
+                println!("  {:40} {:>10} {:>12}", project, convs, msgs);
+            }
+            println!();
+        }
+
         println!("Totals:");
         println!("  Conversations: {conversation_count}");
         println!("  Messages: {message_count}");
+        println!("  Tokens (approx): {token_count}");
         println!();
         println!("By Agent:");
-        for (agent, count) in &agent_rows {
-            println!("  {agent}: {count}");
+        for (agent, count, messages, tokens) in &agent_rows {
+            println!("  {agent}: {count} ({messages} messages, ~{tokens} tokens)");
+        }
+        println!();
+        if !daily_rows.is_empty() {
+            let busiest = daily_rows.iter().map(|d| d.1).max().unwrap_or(1).max(1);
+            println!("Last {days} Days:");
+            for (day, convs, msgs) in &daily_rows {
+                let bar = "#".repeat(((convs * 30 + busiest - 1) / busiest) as usize);
+                println!("  {day} {convs:>5} convs {msgs:>7} msgs  {bar}");
+            }
+            println!();
         }
+        println!("Size:");
+        println!("  Database: {}", format_bytes(db_bytes));
+        println!("  Index: {}", format_bytes(index_bytes));
+        println!("  Message content: {}", format_bytes(content_bytes));
         println!();
+        match freshness["last_indexed_at"].as_str() {
+            Some(at) => {
+                let state = if freshness["stale"].as_bool().unwrap_or(true) {
+                    "stale"
+                } else {
+                    "fresh"
+                };
+                println!("Last Indexed: {at} ({state})");
+            }
+            None => println!("Last Indexed: never"),
+        }
         if !ws_rows.is_empty() {
             println!("Top Workspaces:");
             for (ws, count) in &ws_rows {
@@ -3916,7 +9784,6 @@ fn run_diag(
     json: bool,
     verbose: bool,
 ) -> CliResult<()> {
-    use rusqlite::Connection;
     use std::fs;
 
     let version = env!("CARGO_PKG_VERSION");
@@ -3929,17 +9796,18 @@ fn run_diag(
     // Check database existence and get stats
     let (db_exists, db_size, conversation_count, message_count) = if db_path.exists() {
         let size = fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
-        let (convs, msgs) = if let Ok(conn) = Connection::open(&db_path) {
-            let convs: i64 = conn
-                .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
-                .unwrap_or(0);
-            let msgs: i64 = conn
-                .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
-                .unwrap_or(0);
-            (convs, msgs)
-        } else {
-            (0, 0)
-        };
+        let (convs, msgs) =
+            if let Ok(conn) = crate::storage::sqlite::open_readonly_connection(&db_path) {
+                let convs: i64 = conn
+                    .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+                    .unwrap_or(0);
+                let msgs: i64 = conn
+                    .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+                    .unwrap_or(0);
+                (convs, msgs)
+            } else {
+                (0, 0)
+            };
         (true, size, convs, msgs)
     } else {
         (false, 0, 0, 0)
@@ -3986,6 +9854,7 @@ fn run_diag(
         let payload = serde_json::json!({
             "version": version,
             "platform": { "os": platform, "arch": arch },
+            "profile": crate::profiles::active().map(|p| p.name.clone()),
             "paths": {
                 "data_dir": data_dir.display().to_string(),
                 "db_path": db_path.display().to_string(),
@@ -4021,6 +9890,9 @@ fn run_diag(
         println!("Platform: {platform} ({arch})");
         println!();
         println!("Paths:");
+        if let Some(profile) = crate::profiles::active() {
+            println!("  Profile: {}", profile.name);
+        }
         println!("  Data directory: {}", data_dir.display());
         println!("  Database: {}", db_path.display());
         println!("  Tantivy index: {}", index_path.display());
@@ -4135,7 +10007,6 @@ fn run_status(
     stale_threshold: u64,
     _robot_meta: bool,
 ) -> CliResult<()> {
-    use rusqlite::Connection;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
@@ -4160,7 +10031,7 @@ fn run_status(
     let mut message_count: i64 = 0;
     let mut last_indexed_at: Option<i64> = None;
 
-    if db_exists && let Ok(conn) = Connection::open(&db_path) {
+    if db_exists && let Ok(conn) = crate::storage::sqlite::open_readonly_connection(&db_path) {
         // Get counts
         conversation_count = conn
             .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
@@ -4414,8 +10285,6 @@ fn run_context(
     json: bool,
     limit: usize,
 ) -> CliResult<()> {
-    use rusqlite::Connection;
-
     let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
     let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
 
@@ -4429,13 +10298,14 @@ fn run_context(
         });
     }
 
-    let conn = Connection::open(&db_path).map_err(|e| CliError {
-        code: 9,
-        kind: "db-open",
-        message: format!("Failed to open database: {e}"),
-        hint: None,
-        retryable: false,
-    })?;
+    let conn =
+        crate::storage::sqlite::open_readonly_connection(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
 
     // Find the source conversation by path (normalized to string)
     let path_str = path.to_string_lossy().to_string();
@@ -4798,6 +10668,18 @@ fn build_global_flag_schemas() -> Vec<ArgumentSchema> {
             enum_values: None,
             repeatable: None,
         },
+        ArgumentSchema {
+            name: "profile".to_string(),
+            short: None,
+            description: "Named profile with its own data dir and database (env: CASS_PROFILE)"
+                .to_string(),
+            arg_type: "option".to_string(),
+            value_type: Some("string".to_string()),
+            required: false,
+            default: None,
+            enum_values: None,
+            repeatable: None,
+        },
         ArgumentSchema {
             name: "robot-help".to_string(),
             short: None,
@@ -5552,10 +11434,14 @@ fn build_response_schemas() -> std::collections::HashMap<String, serde_json::Val
                 "elapsed_ms": { "type": "integer" },
                 "full": { "type": ["boolean", "null"] },
                 "force_rebuild": { "type": ["boolean", "null"] },
+                "rebuild": { "type": ["boolean", "null"] },
+                "gc": { "type": ["boolean", "null"] },
                 "data_dir": { "type": ["string", "null"] },
                 "db_path": { "type": ["string", "null"] },
                 "conversations": { "type": ["integer", "null"] },
                 "messages": { "type": ["integer", "null"] },
+                "redactions": { "type": ["object", "null"] },
+                "budget_prune": { "type": ["object", "null"] },
                 "error": { "type": ["string", "null"] }
             }
         }),
@@ -5574,6 +11460,7 @@ fn build_response_schemas() -> std::collections::HashMap<String, serde_json::Val
                         "arch": { "type": "string" }
                     }
                 },
+                "profile": { "type": ["string", "null"] },
                 "paths": {
                     "type": "object",
                     "properties": {
@@ -5642,18 +11529,52 @@ fn build_response_schemas() -> std::collections::HashMap<String, serde_json::Val
         json!({
             "type": "object",
             "properties": {
+                "schema_version": { "type": "integer" },
                 "conversations": { "type": "integer" },
                 "messages": { "type": "integer" },
+                "approx_tokens": { "type": "integer" },
                 "by_agent": {
                     "type": "array",
                     "items": {
                         "type": "object",
                         "properties": {
                             "agent": { "type": "string" },
-                            "count": { "type": "integer" }
+                            "count": { "type": "integer" },
+                            "messages": { "type": "integer" },
+                            "approx_tokens": { "type": "integer" }
                         }
                     }
                 },
+                "daily": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "day": { "type": "string" },
+                            "conversations": { "type": "integer" },
+                            "messages": { "type": "integer" }
+                        }
+                    }
+                },
+                "size": {
+                    "type": "object",
+                    "properties": {
+                        "db_bytes": { "type": "integer" },
+                        "index_bytes": { "type": "integer" },
+                        "content_bytes": { "type": "integer" }
+                    }
+                },
+                "index": {
+                    "type": "object",
+                    "properties": {
+                        "exists": { "type": "boolean" },
+                        "fresh": { "type": "boolean" },
+                        "last_indexed_at": { "type": ["string", "null"] },
+                        "age_seconds": { "type": ["integer", "null"] },
+                        "stale": { "type": "boolean" },
+                        "stale_threshold_seconds": { "type": "integer" }
+                    }
+                },
                 "top_workspaces": {
                     "type": "array",
                     "items": {
@@ -5866,11 +11787,17 @@ fn spawn_background_indexer(
             watch_once_paths: read_watch_once_paths_env(),
             db_path,
             data_dir,
+            gc: false,
             progress,
         };
         // Pass the receiver to run_index so it can listen for commands
-        if let Err(e) = indexer::run_index(opts, Some((tx_clone, rx))) {
-            warn!("Background indexer failed: {}", e);
+        match indexer::run_index(opts, Some((tx_clone, rx))) {
+            Ok(()) => {}
+            // `cass index --watch` or the daemon already keeps this data dir current
+            Err(e) if e.is::<indexer::lock::IndexLocked>() => {
+                info!("Background indexer skipped: {}", e);
+            }
+            Err(e) => warn!("Background indexer failed: {}", e),
         }
     });
     Some(tx)
@@ -5881,14 +11808,15 @@ fn run_index_with_data(
     db_override: Option<PathBuf>,
     full: bool,
     force_rebuild: bool,
+    rebuild: bool,
     watch: bool,
+    gc: bool,
     watch_once: Option<Vec<PathBuf>>,
     data_dir_override: Option<PathBuf>,
     progress: ProgressResolved,
     json: bool,
     idempotency_key: Option<String>,
 ) -> CliResult<()> {
-    use rusqlite::Connection;
     use std::time::Instant;
 
     let data_dir = data_dir_override.unwrap_or_else(default_data_dir);
@@ -5900,14 +11828,16 @@ fn run_index_with_data(
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
         full.hash(&mut hasher);
         force_rebuild.hash(&mut hasher);
+        rebuild.hash(&mut hasher);
         watch.hash(&mut hasher);
+        gc.hash(&mut hasher);
         format!("{}", data_dir.display()).hash(&mut hasher);
         hasher.finish()
     };
 
     // Check for cached idempotency result
     if let Some(key) = &idempotency_key
-        && let Ok(conn) = Connection::open(&db_path)
+        && let Ok(conn) = crate::storage::sqlite::open_connection(&db_path)
     {
         // Ensure idempotency_keys table exists
         let _ = conn.execute(
@@ -5984,6 +11914,7 @@ fn run_index_with_data(
         watch_once_paths: watch_once_paths.clone(),
         db_path: db_path.clone(),
         data_dir: data_dir.clone(),
+        gc,
         progress: None,
     };
     let spinner = if json {
@@ -6011,8 +11942,48 @@ fn run_index_with_data(
     }
 
     let start = Instant::now();
+    if rebuild && db_path.exists() {
+        if let Some(pb) = &spinner {
+            pb.set_message("rebuilding search index from the database");
+        }
+        indexer::rebuild_search_index(&data_dir, &db_path).map_err(|e| {
+            if let Some(locked) = e.downcast_ref::<indexer::lock::IndexLocked>() {
+                return CliError {
+                    code: 7,
+                    kind: "index-locked",
+                    message: locked.to_string(),
+                    hint: Some(
+                        "Stop `cass index --watch`, the daemon or the TUI, then retry the rebuild."
+                            .to_string(),
+                    ),
+                    retryable: true,
+                };
+            }
+            CliError {
+                code: 9,
+                kind: "index",
+                message: format!("search index rebuild failed: {e:#}"),
+                hint: Some(
+                    "'cass index --force-rebuild' recreates the database and index from the session files."
+                        .to_string(),
+                ),
+                retryable: true,
+            }
+        })?;
+    }
     // CLI index command doesn't support manual reindex triggering from TUI, so pass None
     let res = indexer::run_index(opts, None).map_err(|e| {
+        if let Some(locked) = e.downcast_ref::<indexer::lock::IndexLocked>() {
+            return CliError {
+                code: 7,
+                kind: "index-locked",
+                message: locked.to_string(),
+                hint: Some(
+                    "`cass index --watch`, the daemon or the TUI is already indexing; searches see its commits. Stop it to run a one-shot index.".to_string(),
+                ),
+                retryable: true,
+            };
+        }
         let chain = e
             .chain()
             .map(std::string::ToString::to_string)
@@ -6044,26 +12015,40 @@ fn run_index_with_data(
         }
     } else if json {
         // Get stats after successful indexing
-        let (conversations, messages) = if let Ok(conn) = Connection::open(&db_path) {
-            let convs: i64 = conn
-                .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
-                .unwrap_or(0);
-            let msgs: i64 = conn
-                .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
-                .unwrap_or(0);
-            (convs, msgs)
-        } else {
-            (0, 0)
-        };
+        let (conversations, messages) =
+            if let Ok(conn) = crate::storage::sqlite::open_readonly_connection(&db_path) {
+                let convs: i64 = conn
+                    .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+                    .unwrap_or(0);
+                let msgs: i64 = conn
+                    .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+                    .unwrap_or(0);
+                (convs, msgs)
+            } else {
+                (0, 0)
+            };
+        let run_storage = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path).ok();
+        let redactions = run_storage
+            .as_ref()
+            .and_then(|s| s.get_last_redactions().ok())
+            .unwrap_or_default();
+        let budget_prune = run_storage
+            .as_ref()
+            .and_then(|s| s.get_last_budget_prune().ok())
+            .flatten();
         let mut payload = serde_json::json!({
             "success": true,
             "elapsed_ms": elapsed_ms,
             "full": full,
             "force_rebuild": force_rebuild,
+            "rebuild": rebuild,
+            "gc": gc,
             "data_dir": data_dir.display().to_string(),
             "db_path": db_path.display().to_string(),
             "conversations": conversations,
             "messages": messages,
+            "redactions": redactions,
+            "budget_prune": budget_prune,
         });
 
         // Store idempotency key if provided
@@ -6071,7 +12056,7 @@ fn run_index_with_data(
             payload["idempotency_key"] = serde_json::json!(key);
             payload["cached"] = serde_json::json!(false);
 
-            if let Ok(conn) = Connection::open(&db_path) {
+            if let Ok(conn) = crate::storage::sqlite::open_connection(&db_path) {
                 let now_ms = chrono::Utc::now().timestamp_millis();
                 let expires_ms = now_ms + 24 * 60 * 60 * 1000; // 24 hours
                 let result_json = serde_json::to_string(&payload).unwrap_or_default();
@@ -6093,6 +12078,34 @@ fn run_index_with_data(
     } else if !json && matches!(progress, ProgressResolved::Plain) {
         eprintln!("index completed");
     }
+    if !json
+        && res.is_ok()
+        && let Ok(storage) = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path)
+        && let Ok(redactions) = storage.get_last_redactions()
+        && !redactions.is_empty()
+    {
+        let summary = redactions
+            .iter()
+            .map(|(rule, n)| format!("{rule}={n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!(
+            "redacted {} secrets ({summary})",
+            redactions.values().sum::<usize>()
+        );
+    }
+    if !json
+        && res.is_ok()
+        && let Ok(storage) = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path)
+        && let Ok(Some(report)) = storage.get_last_budget_prune()
+    {
+        let removed = report["removed"].as_array().map_or(0, Vec::len);
+        eprintln!(
+            "storage budget exceeded: pruned {removed} conversations ({} policy, max {} bytes)",
+            report["policy"].as_str().unwrap_or("oldest"),
+            report["max_bytes"]
+        );
+    }
 
     res
 }
@@ -6102,6 +12115,14 @@ pub fn default_db_path() -> PathBuf {
 }
 
 pub fn default_data_dir() -> PathBuf {
+    if let Some(dir) = profiles::active_data_dir() {
+        return dir;
+    }
+    base_data_dir()
+}
+
+/// The platform data dir, ignoring any active profile
+pub fn base_data_dir() -> PathBuf {
     directories::ProjectDirs::from("com", "coding-agent-search", "coding-agent-search")
         .map(|p| p.data_dir().to_path_buf())
         .or_else(|| dirs::home_dir().map(|h| h.join(".coding-agent-search")))
@@ -6254,7 +12275,7 @@ fn run_export(
     include_tools: bool,
 ) -> CliResult<()> {
     use std::fs::File;
-    use std::io::{BufRead, BufReader, Write};
+    use std::io::{BufRead, BufReader};
 
     if !path.exists() {
         return Err(CliError {
@@ -6280,11 +12301,12 @@ fn run_export(
     let mut session_start: Option<i64> = None;
     let mut session_end: Option<i64> = None;
 
-    for line in reader.lines().map_while(Result::ok) {
+    let source_path = path.to_string_lossy();
+    for (line_idx, line) in reader.lines().map_while(Result::ok).enumerate() {
         if line.trim().is_empty() {
             continue;
         }
-        if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
+        if let Ok(mut msg) = serde_json::from_str::<serde_json::Value>(&line) {
             // Skip internal message types (file snapshots, etc.)
             if let Some(msg_type) = msg.get("type").and_then(|t| t.as_str()) {
                 if msg_type.contains("snapshot")
@@ -6303,6 +12325,10 @@ fn run_export(
                     session_end = Some(ts);
                 }
             }
+            if let Some(obj) = msg.as_object_mut() {
+                obj.insert("source_path".into(), source_path.as_ref().into());
+                obj.insert("source_line".into(), (line_idx + 1).into());
+            }
             messages.push(msg);
         }
     }
@@ -6317,36 +12343,178 @@ fn run_export(
         });
     }
 
-    // Find title from first user message
-    for msg in &messages {
-        let role = extract_role(msg);
-        if role == "user" {
-            let content = extract_text_content(msg);
-            if !content.is_empty() {
-                session_title = Some(
-                    content
-                        .lines()
-                        .next()
-                        .unwrap_or("Untitled Session")
-                        .chars()
-                        .take(80)
-                        .collect(),
-                );
-                break;
-            }
-        }
+    // Find title from first user message
+    for msg in &messages {
+        let role = extract_role(msg);
+        if role == "user" {
+            let content = extract_text_content(msg);
+            if !content.is_empty() {
+                session_title = Some(
+                    content
+                        .lines()
+                        .next()
+                        .unwrap_or("Untitled Session")
+                        .chars()
+                        .take(80)
+                        .collect(),
+                );
+                break;
+            }
+        }
+    }
+
+    let formatted = format_conversation(
+        &messages,
+        &session_title,
+        session_start,
+        format,
+        include_tools,
+    );
+    write_export(&formatted, output)
+}
+
+/// Export an indexed conversation, read from the database rather than its session file
+fn run_export_conversation(
+    id: &str,
+    format: ConvExportFormat,
+    output: Option<&Path>,
+    include_tools: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::export::{ExportOptions, export_conversation};
+    use crate::model::types::MessageRole;
+    use crate::sources::provenance::SourceFilter;
+
+    let storage = open_existing_storage(data_dir_override, db_override)?;
+    let conv = find_conversation_ref(&storage, id, None, &SourceFilter::All)?;
+    let page = storage
+        .fetch_messages_page(conv.id.unwrap_or_default(), 0, u32::MAX as usize)
+        .map_err(|e| CliError::unknown(format!("loading messages: {e}")))?;
+    let messages: Vec<_> = page
+        .messages
+        .into_iter()
+        .filter(|m| include_tools || m.role != MessageRole::Tool)
+        .collect();
+    let formatted = match export_format(format) {
+        Some(format) => export_conversation(&conv, &messages, format, &ExportOptions::default()),
+        // HTML has no search-result counterpart; shape messages like session-file lines
+        None => {
+            let conv_path = conv.source_path.to_string_lossy().into_owned();
+            let messages: Vec<serde_json::Value> = messages
+                .iter()
+                .map(|m| {
+                    let role = match &m.role {
+                        MessageRole::User => "user",
+                        MessageRole::Agent => "assistant",
+                        MessageRole::Tool => "tool",
+                        MessageRole::System => "system",
+                        MessageRole::Other(r) => r.as_str(),
+                    };
+                    let mut msg = serde_json::json!({
+                        "role": role,
+                        "content": m.content,
+                        "timestamp": m.created_at,
+                    });
+                    if m.source_path.is_some() || m.source_line.is_some() {
+                        let (path, line) = m.location(&conv_path);
+                        msg["source_path"] = path.into();
+                        msg["source_line"] = line.into();
+                    }
+                    msg
+                })
+                .collect();
+            format_as_html(&messages, &conv.title, conv.started_at, include_tools)
+        }
+    };
+    write_export(&formatted, output)?;
+    storage
+        .record_access(&conv.source_path.to_string_lossy())
+        .ok();
+    Ok(())
+}
+
+/// The `export.rs` format for a CLI export format; HTML has none
+fn export_format(format: ConvExportFormat) -> Option<crate::export::ExportFormat> {
+    use crate::export::ExportFormat;
+    match format {
+        ConvExportFormat::Markdown => Some(ExportFormat::Markdown),
+        ConvExportFormat::Json => Some(ExportFormat::Json),
+        ConvExportFormat::Text => Some(ExportFormat::PlainText),
+        ConvExportFormat::Html => None,
+    }
+}
+
+/// Export search results for `query` under `filters`, as the TUI export does
+#[allow(clippy::too_many_arguments)]
+fn run_export_query(
+    query: &str,
+    filters: crate::search::query::SearchFilters,
+    limit: usize,
+    format: ConvExportFormat,
+    output: Option<&Path>,
+    full_content: bool,
+    data_dir_override: &Option<PathBuf>,
+    db_override: Option<PathBuf>,
+) -> CliResult<()> {
+    use crate::export::{ExportOptions, export_results, resolve_message_locations};
+    use crate::search::query::SearchClient;
+
+    let Some(format) = export_format(format) else {
+        return Err(CliError::usage(
+            "--format html is only available for single conversations",
+            Some("Use --format markdown, json or text with --query".to_string()),
+        ));
+    };
+    let data_dir = data_dir_override.clone().unwrap_or_else(default_data_dir);
+    let index_path = crate::search::tantivy::index_dir(&data_dir)
+        .map_err(|e| CliError::unknown(format!("failed to open index dir: {e}")))?;
+    let db_path = db_override.unwrap_or_else(|| data_dir.join("agent_search.db"));
+    let client = SearchClient::open(&index_path, Some(&db_path))
+        .map_err(|e| CliError::unknown(format!("failed to open index: {e}")))?
+        .ok_or_else(|| CliError {
+            code: 3,
+            kind: "missing-index",
+            message: format!(
+                "Index not found at {}. Run 'cass index --full' first.",
+                index_path.display()
+            ),
+            hint: None,
+            retryable: true,
+        })?;
+    let mut hits = client
+        .search(query, filters, limit, 0)
+        .map_err(|e| CliError::unknown(format!("search failed: {e}")))?;
+    if let Ok(storage) = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path) {
+        resolve_message_locations(&mut hits, &storage);
+    }
+    let options = ExportOptions {
+        include_content: full_content,
+        query: Some(query.to_string()),
+        ..ExportOptions::default()
+    };
+    write_export(&export_results(&hits, format, &options), output)
+}
+
+fn format_conversation(
+    messages: &[serde_json::Value],
+    title: &Option<String>,
+    start_ts: Option<i64>,
+    format: ConvExportFormat,
+    include_tools: bool,
+) -> String {
+    match format {
+        ConvExportFormat::Markdown => format_as_markdown(messages, title, start_ts, include_tools),
+        ConvExportFormat::Text => format_as_text(messages, include_tools),
+        ConvExportFormat::Json => serde_json::to_string_pretty(messages).unwrap_or_default(),
+        ConvExportFormat::Html => format_as_html(messages, title, start_ts, include_tools),
     }
+}
 
-    let formatted = match format {
-        ConvExportFormat::Markdown => {
-            format_as_markdown(&messages, &session_title, session_start, include_tools)
-        }
-        ConvExportFormat::Text => format_as_text(&messages, include_tools),
-        ConvExportFormat::Json => serde_json::to_string_pretty(&messages).unwrap_or_default(),
-        ConvExportFormat::Html => {
-            format_as_html(&messages, &session_title, session_start, include_tools)
-        }
-    };
+/// Write an export to `output`, or to stdout when unset
+fn write_export(formatted: &str, output: Option<&Path>) -> CliResult<()> {
+    use std::fs::File;
+    use std::io::Write;
 
     if let Some(out_path) = output {
         let mut out_file = File::create(out_path).map_err(|e| CliError {
@@ -6429,6 +12597,9 @@ fn format_as_markdown(
             "system" => md.push_str("## ⚙️ System\n\n"),
             _ => md.push_str(&format!("## {}\n\n", role)),
         }
+        if let Some(loc) = extract_location(msg) {
+            md.push_str(&format!("*Source: `{}`*\n\n", loc.replace('`', "")));
+        }
 
         if !content.is_empty() {
             md.push_str(&content);
@@ -6484,7 +12655,11 @@ fn format_as_text(messages: &[serde_json::Value], include_tools: bool) -> String
     let mut text = String::new();
     for msg in messages {
         let role = extract_role(msg);
-        text.push_str(&format!("=== {} ===\n\n", role.to_uppercase()));
+        text.push_str(&format!("=== {} ===\n", role.to_uppercase()));
+        if let Some(loc) = extract_location(msg) {
+            text.push_str(&format!("Source: {loc}\n"));
+        }
+        text.push('\n');
 
         let content = extract_text_content(msg);
         if !content.is_empty() {
@@ -6613,8 +12788,15 @@ fn format_as_html(
         html.push_str(&format!(
             r#"    <details class="message {role_class}"{open_attr}>
         <summary class="role">{role_display}</summary>
-        <div class="content markdown">"#
+"#
         ));
+        if let Some(loc) = extract_location(msg) {
+            html.push_str(&format!(
+                "        <p class=\"meta\">{}</p>\n",
+                html_escape(&loc)
+            ));
+        }
+        html.push_str(r#"        <div class="content markdown">"#);
 
         // Content will be rendered as markdown by client-side JS
         html.push_str(&html_escape(&content));
@@ -6845,6 +13027,15 @@ fn extract_text_content(msg: &serde_json::Value) -> String {
     String::new()
 }
 
+/// `path:line` a message was exported from, when the exporter recorded it
+fn extract_location(msg: &serde_json::Value) -> Option<String> {
+    let path = msg.get("source_path").and_then(|p| p.as_str())?;
+    Some(match msg.get("source_line").and_then(|l| l.as_i64()) {
+        Some(line) => format!("{path}:{line}"),
+        None => path.to_string(),
+    })
+}
+
 /// Extract role from message (supports various formats)
 fn extract_role(msg: &serde_json::Value) -> String {
     // Check for isMeta flag (Claude Code skill/command expansions)
@@ -6889,7 +13080,6 @@ fn run_timeline(
 ) -> CliResult<()> {
     use crate::sources::provenance::SourceFilter;
     use chrono::{Local, TimeZone, Utc};
-    use rusqlite::Connection;
     use std::collections::HashMap;
 
     // Parse source filter (P3.2)
@@ -6908,13 +13098,15 @@ fn run_timeline(
         });
     }
 
-    let conn = Connection::open(&db_path).map_err(|e| CliError {
-        code: 9,
-        kind: "db-open",
-        message: format!("Failed to open database: {e}"),
-        hint: None,
-        retryable: true,
-    })?;
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_snapshot(&db_path).map_err(|e| CliError {
+            code: 9,
+            kind: "db-open",
+            message: format!("Failed to open database: {e}"),
+            hint: None,
+            retryable: true,
+        })?;
+    let conn = storage.raw();
 
     let now = Local::now();
     let (start_ts, end_ts) = if today {
@@ -6971,6 +13163,24 @@ fn run_timeline(
                 sql.push_str(&format!(" AND c.source_id = ?{}", params.len() + 1));
                 params.push(Box::new(id.clone()));
             }
+            SourceFilter::Any(_) if filter.is_all() => {}
+            SourceFilter::Any(filters) => {
+                let mut conditions = Vec::new();
+                for filter in filters {
+                    match filter {
+                        SourceFilter::Local => conditions.push("c.source_id = 'local'".to_string()),
+                        SourceFilter::Remote => {
+                            conditions.push("c.source_id != 'local'".to_string())
+                        }
+                        SourceFilter::SourceId(id) => {
+                            conditions.push(format!("c.source_id = ?{}", params.len() + 1));
+                            params.push(Box::new(id.clone()));
+                        }
+                        SourceFilter::All | SourceFilter::Any(_) => {}
+                    }
+                }
+                sql.push_str(&format!(" AND ({})", conditions.join(" OR ")));
+            }
         }
     }
 
@@ -7227,11 +13437,34 @@ fn run_sources_command(cmd: SourcesCommand) -> CliResult<()> {
             preset,
             paths,
             no_test,
+            json,
+        } => {
+            run_sources_add(&url, name, preset, paths, no_test, json)?;
+        }
+        SourcesCommand::Remove {
+            name,
+            purge,
+            yes,
+            json,
+        } => {
+            run_sources_remove(&name, purge, yes, json)?;
+        }
+        SourcesCommand::Show { name, json } => {
+            run_sources_show(&name, json)?;
+        }
+        SourcesCommand::Test { name, json } => {
+            run_sources_test(&name, json)?;
+        }
+        SourcesCommand::MapTest {
+            source,
+            path,
+            agent,
+            json,
         } => {
-            run_sources_add(&url, name, preset, paths, no_test)?;
+            run_mappings_test(&source, &path, agent.as_deref(), json)?;
         }
-        SourcesCommand::Remove { name, purge, yes } => {
-            run_sources_remove(&name, purge, yes)?;
+        SourcesCommand::Discover { timeout, yes, json } => {
+            run_sources_discover(timeout, yes, json)?;
         }
         SourcesCommand::Doctor { source, json } => {
             run_sources_doctor(source.as_deref(), json)?;
@@ -7241,9 +13474,15 @@ fn run_sources_command(cmd: SourcesCommand) -> CliResult<()> {
             no_index,
             verbose,
             dry_run,
+            status,
+            bwlimit,
             json,
         } => {
-            run_sources_sync(source, no_index, verbose, dry_run, json)?;
+            if status {
+                run_sync_status(source, json)?;
+            } else {
+                run_sources_sync(source, no_index, verbose, dry_run, bwlimit, json)?;
+            }
         }
         SourcesCommand::Mappings(action) => {
             run_mappings_command(action)?;
@@ -7270,11 +13509,31 @@ fn run_sources_list(verbose: bool, json: bool) -> CliResult<()> {
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "unknown".into());
 
+    // Last and next sync times, as the daemon schedules them
+    let storage = sources_data_dir().and_then(|dir| {
+        crate::storage::sqlite::SqliteStorage::open_readonly(&dir.join("agent_search.db")).ok()
+    });
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let sync_times = |s: &crate::sources::config::SourceDefinition| {
+        let times = daemon::source_sync_times(storage.as_ref(), &s.name);
+        let next = s
+            .is_remote()
+            .then(|| daemon::next_sync_attempt(s.sync_schedule, &times, now_ms))
+            .flatten();
+        (times.last_sync, next)
+    };
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+
     if json {
         let sources_json: Vec<serde_json::Value> = config
             .sources
             .iter()
             .map(|s| {
+                let (last_sync, next_sync) = sync_times(s);
                 serde_json::json!({
                     "name": s.name,
                     "type": s.source_type.as_str(),
@@ -7282,6 +13541,8 @@ fn run_sources_list(verbose: bool, json: bool) -> CliResult<()> {
                     "paths": s.paths,
                     "sync_schedule": s.sync_schedule.to_string(),
                     "platform": s.platform.map(|p| p.to_string()),
+                    "last_sync": last_sync.map(format_ts),
+                    "next_sync": next_sync.map(format_ts),
                 })
             })
             .collect();
@@ -7317,7 +13578,17 @@ fn run_sources_list(verbose: bool, json: bool) -> CliResult<()> {
                 if let Some(ref host) = source.host {
                     println!("  Host: {host}");
                 }
+                if let Some(root) = source.object_store_root() {
+                    println!("  Location: {root}");
+                }
                 println!("  Schedule: {}", source.sync_schedule);
+                let (last_sync, next_sync) = sync_times(source);
+                if let Some(last) = last_sync {
+                    println!("  Last sync: {}", format_ts(last));
+                }
+                if let Some(next) = next_sync {
+                    println!("  Next sync: {}", format_ts(next));
+                }
                 if let Some(platform) = source.platform {
                     println!("  Platform: {platform}");
                 }
@@ -7346,21 +13617,37 @@ fn run_sources_list(verbose: bool, json: bool) -> CliResult<()> {
             }
         } else {
             // Table output
-            println!("  {:15} {:8} {:30} {:>5}", "NAME", "TYPE", "HOST", "PATHS");
-            println!("  {}", "-".repeat(62));
+            println!(
+                "  {:15} {:8} {:30} {:>5}  {:16}",
+                "NAME", "TYPE", "HOST", "PATHS", "NEXT SYNC"
+            );
+            println!("  {}", "-".repeat(80));
             for source in &config.sources {
-                let host = source.host.as_deref().unwrap_or("-");
+                let location = source.host.clone().or_else(|| source.object_store_root());
+                let host = location.as_deref().unwrap_or("-");
                 let host_truncated = if host.len() > 30 {
                     format!("{}...", &host[..27])
                 } else {
                     host.to_string()
                 };
+                let next_sync = match sync_times(source).1 {
+                    Some(ms) if ms <= now_ms => "due".to_string(),
+                    Some(ms) => chrono::DateTime::from_timestamp_millis(ms)
+                        .map(|d| {
+                            d.with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                        })
+                        .unwrap_or_default(),
+                    None => "-".to_string(),
+                };
                 println!(
-                    "  {:15} {:8} {:30} {:>5}",
+                    "  {:15} {:8} {:30} {:>5}  {:16}",
                     source.name,
                     source.source_type.as_str(),
                     host_truncated,
-                    source.paths.len()
+                    source.paths.len(),
+                    next_sync
                 );
             }
             println!();
@@ -7379,6 +13666,7 @@ fn run_sources_add(
     preset: Option<String>,
     paths_arg: Vec<String>,
     no_test: bool,
+    json: bool,
 ) -> CliResult<()> {
     use crate::sources::config::{Platform, SourceDefinition, SourcesConfig, get_preset_paths};
     use crate::sources::provenance::SourceKind;
@@ -7392,7 +13680,7 @@ fn run_sources_add(
             code: 10,
             kind: "config",
             message: format!("Invalid preset: {e}"),
-            hint: Some("Valid presets: macos-defaults, linux-defaults".into()),
+            hint: Some("Valid presets: macos-defaults, linux-defaults, windows-defaults".into()),
             retryable: false,
         })?
     } else if !paths_arg.is_empty() {
@@ -7409,9 +13697,13 @@ fn run_sources_add(
 
     // Test SSH connectivity unless --no-test
     if !no_test {
-        println!("Testing SSH connectivity to {host}...");
+        if !json {
+            println!("Testing SSH connectivity to {host}...");
+        }
         test_ssh_connectivity(&host)?;
-        println!("  Connected successfully");
+        if !json {
+            println!("  Connected successfully");
+        }
     }
 
     // Load existing config
@@ -7440,6 +13732,8 @@ fn run_sources_add(
             Some(Platform::Macos)
         } else if p.contains("linux") {
             Some(Platform::Linux)
+        } else if p.contains("windows") {
+            Some(Platform::Windows)
         } else {
             None
         }
@@ -7456,7 +13750,7 @@ fn run_sources_add(
     };
 
     // Add and save
-    config.add_source(source).map_err(|e| CliError {
+    config.add_source(source.clone()).map_err(|e| CliError {
         code: 10,
         kind: "config",
         message: format!("Failed to add source: {e}"),
@@ -7478,6 +13772,18 @@ fn run_sources_add(
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "~/.config/cass/sources.toml".into());
 
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "added": source,
+                "config_path": config_path,
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
+
     println!();
     println!("Added source '{source_id}'");
     println!("  Host: {host}");
@@ -7566,7 +13872,7 @@ fn test_ssh_connectivity(host: &str) -> CliResult<()> {
 }
 
 /// Remove a configured source (P5.7)
-fn run_sources_remove(name: &str, purge: bool, skip_confirm: bool) -> CliResult<()> {
+fn run_sources_remove(name: &str, purge: bool, skip_confirm: bool, json: bool) -> CliResult<()> {
     use crate::sources::config::SourcesConfig;
 
     // Load existing config
@@ -7601,61 +13907,456 @@ fn run_sources_remove(name: &str, purge: bool, skip_confirm: bool) -> CliResult<
         print!("{msg}");
         std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        let mut input = String::new();
-        std::io::stdin()
-            .read_line(&mut input)
-            .map_err(|e| CliError {
-                code: 14,
-                kind: "io",
-                message: format!("Failed to read input: {e}"),
-                hint: None,
-                retryable: false,
-            })?;
+        let mut input = String::new();
+        std::io::stdin()
+            .read_line(&mut input)
+            .map_err(|e| CliError {
+                code: 14,
+                kind: "io",
+                message: format!("Failed to read input: {e}"),
+                hint: None,
+                retryable: false,
+            })?;
+
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    // Remove from config
+    config.remove_source(name);
+    config.save().map_err(|e| CliError {
+        code: 11,
+        kind: "config",
+        message: format!("Failed to save config: {e}"),
+        hint: Some("Check file permissions on config directory".into()),
+        retryable: false,
+    })?;
+
+    if !json {
+        println!("Removed '{name}' from configuration.");
+    }
+
+    // Handle purge
+    let mut purged: Option<PathBuf> = None;
+    if purge {
+        // Find and remove synced data directory
+        if let Some(data_dir) = sources_data_dir() {
+            let source_dir = data_dir.join("remotes").join(name);
+            if source_dir.exists() {
+                std::fs::remove_dir_all(&source_dir).map_err(|e| CliError {
+                    code: 15,
+                    kind: "io",
+                    message: format!("Failed to delete synced data: {e}"),
+                    hint: None,
+                    retryable: false,
+                })?;
+                if !json {
+                    println!("Deleted synced data at {}", source_dir.display());
+                }
+                purged = Some(source_dir);
+            }
+        }
+        if !json {
+            println!("Note: Run 'cass reindex' to remove entries from the search index.");
+        }
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "removed": name,
+                "purged_dir": purged.map(|p| p.display().to_string()),
+            }))
+            .unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+/// Where synced remote data and `sync_status.json` live: the active profile's
+/// data dir, else `$XDG_DATA_HOME/cass`, else the platform local data dir
+fn sources_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = profiles::active_data_dir() {
+        return Some(dir);
+    }
+    std::env::var("XDG_DATA_HOME")
+        .ok()
+        .map(|p| PathBuf::from(p).join("cass"))
+        .or_else(|| dirs::data_local_dir().map(|d| d.join("cass")))
+}
+
+/// Look up a configured source by name
+fn find_source(name: &str) -> CliResult<crate::sources::config::SourceDefinition> {
+    use crate::sources::config::SourcesConfig;
+
+    let config = SourcesConfig::load().map_err(|e| CliError {
+        code: 9,
+        kind: "config",
+        message: format!("Failed to load sources config: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+    config.find_source(name).cloned().ok_or_else(|| CliError {
+        code: 13,
+        kind: "not_found",
+        message: format!("Source '{name}' not found"),
+        hint: Some("Run 'cass sources list' to see configured sources".into()),
+        retryable: false,
+    })
+}
+
+/// Browse for advertised cass machines and add them as `type = "cass"` sources
+fn run_sources_discover(timeout: u64, yes: bool, json: bool) -> CliResult<()> {
+    use crate::sources::config::{SourceDefinition, SourcesConfig};
+    use crate::sources::discover::{Peer, browse, target_host};
+    use crate::sources::provenance::SourceKind;
+    use colored::Colorize;
+    use std::io::BufRead;
+
+    let mut config = SourcesConfig::load().map_err(|e| CliError {
+        code: 9,
+        kind: "config",
+        message: format!("Failed to load sources config: {e}"),
+        hint: None,
+        retryable: false,
+    })?;
+    if !json {
+        println!("Looking for cass machines on the LAN ({timeout}s)...");
+    }
+    let peers = browse(Duration::from_secs(timeout.max(1))).map_err(|e| CliError {
+        code: 9,
+        kind: "discover",
+        message: format!("mDNS discovery failed: {e:#}"),
+        hint: Some("Multicast DNS (UDP port 5353) must be allowed on this network".into()),
+        retryable: true,
+    })?;
+
+    // A peer is already configured when a source points at its host
+    let configured_as = |config: &SourcesConfig, peer: &Peer| {
+        config
+            .sources
+            .iter()
+            .find(|s| {
+                s.host
+                    .as_deref()
+                    .is_some_and(|h| target_host(h).eq_ignore_ascii_case(&peer.host))
+            })
+            .map(|s| s.name.clone())
+    };
+    let new_peers: Vec<&Peer> = peers
+        .iter()
+        .filter(|p| configured_as(&config, p).is_none())
+        .collect();
+
+    if !json {
+        if peers.is_empty() {
+            println!("No cass machines found.");
+            println!(
+                "  {}: run 'cass daemon --advertise' on the other machine",
+                "Hint".cyan()
+            );
+            return Ok(());
+        }
+        for peer in &peers {
+            let status = match configured_as(&config, peer) {
+                Some(name) => format!("already added as '{name}'").dimmed(),
+                None => "new".green(),
+            };
+            println!(
+                "  {} {} ({}) {}",
+                peer.name.bold(),
+                peer.ssh_host(),
+                peer.addresses.join(", ").dimmed(),
+                status
+            );
+        }
+    }
+
+    let interactive = !yes && !json && io::stdin().is_terminal();
+    let mut stdin = io::stdin().lock().lines();
+    let mut added = Vec::new();
+    for peer in new_peers {
+        if !yes {
+            if !interactive {
+                continue;
+            }
+            print!("Add {} as a cass source? [y/N] ", peer.ssh_host());
+            let _ = io::stdout().flush();
+            let answer = stdin.next().and_then(|l| l.ok()).unwrap_or_default();
+            if !answer.trim().eq_ignore_ascii_case("y") {
+                continue;
+            }
+        }
+        let base: String = peer
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let mut name = base.clone();
+        let mut n = 2;
+        while config.find_source(&name).is_some() {
+            name = format!("{base}-{n}");
+            n += 1;
+        }
+        let source = SourceDefinition {
+            name,
+            source_type: SourceKind::Cass,
+            host: Some(peer.ssh_host()),
+            ..Default::default()
+        };
+        config.add_source(source.clone()).map_err(|e| CliError {
+            code: 10,
+            kind: "config",
+            message: format!("Failed to add source: {e}"),
+            hint: None,
+            retryable: false,
+        })?;
+        added.push(source);
+    }
+    if !added.is_empty() {
+        config.save().map_err(|e| CliError {
+            code: 11,
+            kind: "config",
+            message: format!("Failed to save config: {e}"),
+            hint: Some("Check file permissions on config directory".into()),
+            retryable: false,
+        })?;
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "peers": peers.iter().map(|p| serde_json::json!({
+                    "peer": p,
+                    "configured_as": configured_as(&config, p),
+                })).collect::<Vec<_>>(),
+                "added": added,
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
+    if added.is_empty() {
+        if !yes && !interactive && peers.iter().any(|p| configured_as(&config, p).is_none()) {
+            println!(
+                "  {}: rerun with --yes to add the new machine(s)",
+                "Hint".cyan()
+            );
+        }
+        return Ok(());
+    }
+    println!();
+    for source in &added {
+        println!(
+            "Added cass source '{}' ({})",
+            source.name,
+            source.host.as_deref().unwrap_or_default()
+        );
+    }
+    println!("Next steps:");
+    println!("  cass sources test <name>   # Check SSH access (keys must already be set up)");
+    println!("  cass sources sync          # Pull their conversations");
+    Ok(())
+}
+
+/// Show one source's configuration and last sync
+fn run_sources_show(name: &str, json: bool) -> CliResult<()> {
+    use crate::sources::sync::{SyncResult, SyncStatus};
+    use colored::Colorize;
+
+    let source = find_source(name)?;
+    let data_dir = sources_data_dir();
+    let sync = data_dir
+        .as_deref()
+        .and_then(|dir| SyncStatus::load(dir).ok())
+        .and_then(|status| status.get(name).cloned());
+    let mirror = data_dir.map(|dir| dir.join("remotes").join(name).join("mirror"));
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "source": source,
+                "last_sync": sync,
+                "mirror_dir": mirror.map(|p| p.display().to_string()),
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
 
-        let input = input.trim().to_lowercase();
-        if input != "y" && input != "yes" {
-            println!("Cancelled.");
-            return Ok(());
+    println!("{}", source.name.bold());
+    println!("  Type: {}", source.source_type);
+    if let Some(host) = &source.host {
+        println!("  Host: {host}");
+    }
+    if let Some(root) = source.object_store_root() {
+        println!("  Location: {root}");
+    }
+    if let Some(platform) = source.platform {
+        println!("  Platform: {platform}");
+    }
+    println!("  Schedule: {}", source.sync_schedule);
+    if let Some(kib) = source.bwlimit {
+        println!("  Bandwidth limit: {kib} KiB/s");
+    }
+    println!("  Paths:");
+    for path in &source.paths {
+        println!("    - {path}");
+    }
+    if !source.path_mappings.is_empty() {
+        println!("  Path Mappings:");
+        for (i, mapping) in source.path_mappings.iter().enumerate() {
+            let agents = mapping
+                .agents
+                .as_ref()
+                .map(|a| format!(" (agents: {})", a.join(", ")))
+                .unwrap_or_default();
+            println!("    [{i}] {} -> {}{agents}", mapping.from, mapping.to);
+        }
+    }
+    if let Some(mirror) = mirror {
+        let encrypted = if source.encrypt { " (encrypted)" } else { "" };
+        println!("  Mirror: {}{encrypted}", mirror.display());
+    }
+    match sync.and_then(|info| Some((info.last_sync?, info))) {
+        Some((at, info)) => {
+            let when = chrono::DateTime::from_timestamp_millis(at)
+                .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_default();
+            let result = match &info.last_result {
+                SyncResult::Success => "ok".green(),
+                SyncResult::PartialFailure(e) => format!("partial: {e}").yellow(),
+                SyncResult::Failed(e) => format!("failed: {e}").red(),
+                SyncResult::Skipped => "skipped".dimmed(),
+            };
+            println!(
+                "  Last sync: {when}, {result} ({} files, {})",
+                info.files_synced,
+                format_bytes(info.bytes_transferred)
+            );
         }
+        None => println!("  Last sync: {}", "never".dimmed()),
     }
+    Ok(())
+}
 
-    // Remove from config
-    config.remove_source(name);
-    config.save().map_err(|e| CliError {
-        code: 11,
-        kind: "config",
-        message: format!("Failed to save config: {e}"),
-        hint: Some("Check file permissions on config directory".into()),
-        retryable: false,
-    })?;
+/// Check that a source's host accepts an SSH connection, or that rclone
+/// can list an object store source
+fn run_sources_test(name: &str, json: bool) -> CliResult<()> {
+    use colored::Colorize;
 
-    println!("Removed '{name}' from configuration.");
+    let source = find_source(name)?;
+    let started = Instant::now();
+    let (host, check) = if let Some(root) = source.object_store_root() {
+        let check = check_object_store(&root);
+        (root, check)
+    } else if let Some(host) = source.host.clone().filter(|_| source.is_ssh()) {
+        let check = check_ssh_connectivity(&host);
+        (host, check)
+    } else {
+        return Err(CliError::usage(
+            format!("Source '{name}' is not a remote source"),
+            Some("Only SSH and object store sources can be tested".into()),
+        ));
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let reachable = check.status == "pass";
 
-    // Handle purge
-    if purge {
-        // Find and remove synced data directory
-        // Respect XDG_DATA_HOME first (important for testing and Linux users)
-        let data_dir = std::env::var("XDG_DATA_HOME")
-            .ok()
-            .map(PathBuf::from)
-            .or_else(dirs::data_local_dir);
+    // Paths are only worth checking once the host answers
+    let paths: Vec<DiagnosticCheck> = if !reachable {
+        Vec::new()
+    } else if source.object_store_root().is_some() {
+        source
+            .paths
+            .iter()
+            .map(|path| {
+                let key = path.trim_start_matches('~').trim_matches('/');
+                let mut check = check_object_store(&format!("{host}/{key}"));
+                check.name = format!("Remote Path: {path}");
+                check
+            })
+            .collect()
+    } else {
+        source
+            .paths
+            .iter()
+            .map(|path| check_remote_path(&host, path))
+            .collect()
+    };
+    let missing: Vec<&str> = source
+        .paths
+        .iter()
+        .zip(&paths)
+        .filter(|(_, c)| c.status == "fail")
+        .map(|(p, _)| p.as_str())
+        .collect();
+    let ok = reachable && missing.is_empty();
 
-        if let Some(data_dir) = data_dir {
-            let source_dir = data_dir.join("cass").join("remotes").join(name);
-            if source_dir.exists() {
-                std::fs::remove_dir_all(&source_dir).map_err(|e| CliError {
-                    code: 15,
-                    kind: "io",
-                    message: format!("Failed to delete synced data: {e}"),
-                    hint: None,
-                    retryable: false,
-                })?;
-                println!("Deleted synced data at {}", source_dir.display());
-            }
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "source": name,
+                "host": host,
+                "ok": ok,
+                "reachable": reachable,
+                "latency_ms": latency_ms,
+                "check": check,
+                "paths": source.paths.iter().zip(&paths).map(|(path, c)| serde_json::json!({
+                    "path": path,
+                    "exists": c.status != "fail",
+                    "status": c.status,
+                    "message": c.message,
+                })).collect::<Vec<_>>(),
+                "missing_paths": missing,
+            }))
+            .unwrap_or_default()
+        );
+    } else if reachable {
+        println!(
+            "{} {name}: {} ({latency_ms} ms)",
+            "✓".green(),
+            check.message
+        );
+        for (path, c) in source.paths.iter().zip(&paths) {
+            let icon = match c.status.as_str() {
+                "pass" => "✓".green(),
+                "warn" => "⚠".yellow(),
+                _ => "✗".red(),
+            };
+            println!("  {icon} {path}: {}", c.message);
+        }
+        if !missing.is_empty() {
+            println!(
+                "  {}: remove the missing path(s) from sources.toml or create them on the remote",
+                "Hint".cyan()
+            );
+        }
+    } else {
+        println!("{} {name}: {}", "✗".red(), check.message);
+        if let Some(hint) = &check.remediation {
+            println!("  {}: {hint}", "Hint".cyan());
         }
-        println!("Note: Run 'cass reindex' to remove entries from the search index.");
     }
 
+    // Same convention as `sources doctor`: failures exit 1 after the report
+    if !ok {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
@@ -7729,25 +14430,67 @@ fn run_sources_doctor(source_filter: Option<&str>, json_output: bool) -> CliResu
     for source in sources_to_check {
         let mut checks = Vec::new();
 
-        // Check 1: SSH connectivity
-        let host = source.host.as_deref().unwrap_or("unknown");
-        let ssh_check = check_ssh_connectivity(host);
-        checks.push(ssh_check);
-
-        // Check 2: rsync availability on remote
-        let rsync_check = check_rsync_available(host);
-        checks.push(rsync_check);
+        if let Some(root) = source.object_store_root() {
+            // Object stores: the bucket, then each path under it, via rclone
+            checks.push(check_object_store(&root));
+            for path in &source.paths {
+                let key = path.trim_start_matches('~').trim_matches('/');
+                checks.push(check_object_store(&format!("{root}/{key}")));
+            }
+        } else {
+            // Check 1: SSH connectivity
+            let host = source.host.as_deref().unwrap_or("unknown");
+            let ssh_check = check_ssh_connectivity(host);
+            checks.push(ssh_check);
+
+            if source.source_type == crate::sources::provenance::SourceKind::Cass {
+                // Check 2: cass on the remote, which serves the conversations
+                checks.push(check_remote_tool(
+                    host,
+                    "cass",
+                    "Install cass on the remote host and put it on the SSH PATH",
+                ));
+            } else {
+                // Check 2: rsync availability on remote
+                checks.push(check_remote_tool(
+                    host,
+                    "rsync",
+                    "Install rsync on the remote host",
+                ));
+            }
 
-        // Check 3: Remote paths exist
-        for path in &source.paths {
-            let path_check = check_remote_path(host, path);
-            checks.push(path_check);
+            // Check 3: Remote paths exist
+            for path in &source.paths {
+                let path_check = check_remote_path(host, path);
+                checks.push(path_check);
+            }
         }
 
         // Check 4: Local storage writable
         let storage_check = check_local_storage(&source.name);
         checks.push(storage_check);
 
+        // Check 5: the key that encrypts staged files
+        if source.encrypt {
+            checks.push(match crate::storage::encryption::staging_key() {
+                Ok(_) => DiagnosticCheck {
+                    name: "Staging Encryption".into(),
+                    status: "pass".into(),
+                    message: "Synced files are encrypted with the database key".into(),
+                    remediation: None,
+                },
+                Err(e) => DiagnosticCheck {
+                    name: "Staging Encryption".into(),
+                    status: "fail".into(),
+                    message: format!("{e:#}"),
+                    remediation: Some(
+                        "Configure [storage] encryption in config.toml, or set encrypt = false"
+                            .into(),
+                    ),
+                },
+            });
+        }
+
         // Compute summary
         let passed = checks.iter().filter(|c| c.status == "pass").count();
         let warnings = checks.iter().filter(|c| c.status == "warn").count();
@@ -7862,8 +14605,46 @@ fn check_ssh_connectivity(host: &str) -> DiagnosticCheck {
     }
 }
 
-/// Check rsync availability on remote
-fn check_rsync_available(host: &str) -> DiagnosticCheck {
+/// Check that rclone can list an object store location
+fn check_object_store(location: &str) -> DiagnosticCheck {
+    let output = std::process::Command::new("rclone")
+        .args(["lsf", "--max-depth", "1", "--contimeout", "5s", location])
+        .output();
+
+    match output {
+        Ok(out) if out.status.success() => DiagnosticCheck {
+            name: "Object Store".into(),
+            status: "pass".into(),
+            message: format!("Listed {} successfully", location),
+            remediation: None,
+        },
+        Ok(out) => {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let remediation = if stderr.contains("didn't find section in config file") {
+                Some("Configure the remote with 'rclone config' or set rclone_remote".into())
+            } else if stderr.contains("directory not found") {
+                Some("Check bucket, prefix and paths in sources.toml".into())
+            } else {
+                Some("Check rclone credentials and network connectivity".into())
+            };
+            DiagnosticCheck {
+                name: "Object Store".into(),
+                status: "fail".into(),
+                message: stderr.trim().to_string(),
+                remediation,
+            }
+        }
+        Err(e) => DiagnosticCheck {
+            name: "Object Store".into(),
+            status: "fail".into(),
+            message: format!("Failed to run rclone: {}", e),
+            remediation: Some("Install rclone (https://rclone.org/install/)".into()),
+        },
+    }
+}
+
+/// Check that `tool --version` runs on the remote
+fn check_remote_tool(host: &str, tool: &str, remediation: &str) -> DiagnosticCheck {
     let output = std::process::Command::new("ssh")
         .args([
             "-o",
@@ -7871,7 +14652,7 @@ fn check_rsync_available(host: &str) -> DiagnosticCheck {
             "-o",
             "BatchMode=yes",
             host,
-            "rsync",
+            tool,
             "--version",
         ])
         .output();
@@ -7885,7 +14666,7 @@ fn check_rsync_available(host: &str) -> DiagnosticCheck {
                 .unwrap_or("version unknown")
                 .to_string();
             DiagnosticCheck {
-                name: "rsync Available".into(),
+                name: format!("{tool} Available"),
                 status: "pass".into(),
                 message: version,
                 remediation: None,
@@ -7894,32 +14675,24 @@ fn check_rsync_available(host: &str) -> DiagnosticCheck {
         Ok(out) => {
             let stderr = String::from_utf8_lossy(&out.stderr);
             DiagnosticCheck {
-                name: "rsync Available".into(),
+                name: format!("{tool} Available"),
                 status: "fail".into(),
-                message: format!("rsync not found: {}", stderr.trim()),
-                remediation: Some("Install rsync on the remote host".into()),
+                message: format!("{tool} not found: {}", stderr.trim()),
+                remediation: Some(remediation.into()),
             }
         }
         Err(e) => DiagnosticCheck {
-            name: "rsync Available".into(),
+            name: format!("{tool} Available"),
             status: "warn".into(),
-            message: format!("Could not check rsync: {}", e),
+            message: format!("Could not check {tool}: {}", e),
             remediation: Some("SSH connectivity may have failed".into()),
         },
     }
 }
 
-fn sh_quote(value: &str) -> String {
-    if value.is_empty() {
-        "''".to_string()
-    } else {
-        format!("'{}'", value.replace('\'', "'\"'\"'"))
-    }
-}
-
 /// Check if a remote path exists
 fn check_remote_path(host: &str, path: &str) -> DiagnosticCheck {
-    let quoted = sh_quote(path);
+    let quoted = crate::sources::sync::sh_quote(path);
     let cmd = format!("test -d {quoted} && ls -1 {quoted} | wc -l");
     let output = std::process::Command::new("ssh")
         .args([
@@ -7972,8 +14745,8 @@ fn check_remote_path(host: &str, path: &str) -> DiagnosticCheck {
 
 /// Check if local storage directory is writable
 fn check_local_storage(source_name: &str) -> DiagnosticCheck {
-    if let Some(data_dir) = dirs::data_local_dir() {
-        let source_dir = data_dir.join("cass").join("remotes").join(source_name);
+    if let Some(data_dir) = sources_data_dir() {
+        let source_dir = data_dir.join("remotes").join(source_name);
 
         // Try to create the directory if it doesn't exist
         if !source_dir.exists() {
@@ -8028,6 +14801,7 @@ fn run_sources_sync(
     no_index: bool,
     verbose: bool,
     dry_run: bool,
+    bwlimit: Option<u32>,
     json_output: bool,
 ) -> CliResult<()> {
     use crate::sources::config::SourcesConfig;
@@ -8089,24 +14863,38 @@ fn run_sources_sync(
     }
 
     // Get data directory for sync engine and status
-    // Respect XDG_DATA_HOME first (important for testing and Linux users)
-    let data_dir = std::env::var("XDG_DATA_HOME")
-        .ok()
-        .map(|p| PathBuf::from(p).join("cass"))
-        .or_else(|| dirs::data_local_dir().map(|d| d.join("cass")))
-        .ok_or_else(|| CliError {
-            code: 9,
-            kind: "config",
-            message: "Could not determine data directory".into(),
-            hint: Some("Set XDG_DATA_HOME or HOME environment variable".into()),
-            retryable: false,
-        })?;
+    let data_dir = sources_data_dir().ok_or_else(|| CliError {
+        code: 9,
+        kind: "config",
+        message: "Could not determine data directory".into(),
+        hint: Some("Set XDG_DATA_HOME or HOME environment variable".into()),
+        retryable: false,
+    })?;
 
     // Create sync engine
-    let engine = SyncEngine::new(&data_dir);
+    let engine = SyncEngine::new(&data_dir).with_bwlimit(bwlimit);
 
     // Load existing sync status
     let mut status = SyncStatus::load(&data_dir).unwrap_or_default();
+    // Sync times live in the database, so manual syncs reset the daemon's schedule too
+    let mut storage = if dry_run {
+        None
+    } else {
+        crate::storage::sqlite::SqliteStorage::open(&data_dir.join("agent_search.db"))
+            .map_err(|e| tracing::warn!("Failed to open the database to record sync times: {e}"))
+            .ok()
+    };
+    let mut record_sync = |name: &str, failed: bool| {
+        if let Some(storage) = storage.as_mut()
+            && let Err(e) = storage.record_source_sync(
+                name,
+                crate::storage::sqlite::SqliteStorage::now_millis(),
+                failed,
+            )
+        {
+            tracing::warn!("Failed to record sync time for {name}: {e}");
+        }
+    };
 
     if dry_run && !json_output {
         println!("{}", "DRY RUN - no changes will be made".cyan().bold());
@@ -8127,13 +14915,56 @@ fn run_sources_sync(
         }
 
         if dry_run {
-            // In dry run, just show what would be synced
-            if !json_output {
-                for path in &source.paths {
-                    println!("  {} {}", "Would sync:".dimmed(), path);
+            let previews = match engine.preview_source(source) {
+                Ok(p) => p,
+                Err(e) => {
+                    if json_output {
+                        all_reports.push(serde_json::json!({
+                            "source": source.name,
+                            "status": "error",
+                            "error": e.to_string()
+                        }));
+                    } else {
+                        println!("  {} {}", "Error:".red().bold(), e.to_string().red());
+                        println!();
+                    }
+                    continue;
+                }
+            };
+            let files: u64 = previews.iter().map(|p| p.new_files + p.changed_files).sum();
+            let bytes: u64 = previews.iter().map(|p| p.bytes).sum();
+            if json_output {
+                all_reports.push(serde_json::json!({
+                    "source": source.name,
+                    "status": "preview",
+                    "paths": previews,
+                    "total_files": files,
+                    "total_bytes": bytes,
+                }));
+            } else {
+                for preview in &previews {
+                    if let Some(error) = &preview.error {
+                        println!("  {}: {}", preview.remote_path.dimmed(), error.red());
+                        continue;
+                    }
+                    println!(
+                        "  {}: {} new, {} changed, {} unchanged ({} to fetch)",
+                        preview.remote_path.dimmed(),
+                        preview.new_files.to_string().green(),
+                        preview.changed_files.to_string().yellow(),
+                        preview.unchanged_files,
+                        format_bytes(preview.bytes)
+                    );
+                    if verbose {
+                        for file in &preview.files {
+                            println!("    {file}");
+                        }
+                    }
                 }
                 println!();
             }
+            total_files += files;
+            total_bytes += bytes;
             continue;
         }
 
@@ -8141,6 +14972,7 @@ fn run_sources_sync(
         let report = match engine.sync_source(source) {
             Ok(r) => r,
             Err(e) => {
+                record_sync(&source.name, true);
                 if json_output {
                     all_reports.push(serde_json::json!({
                         "source": source.name,
@@ -8156,6 +14988,8 @@ fn run_sources_sync(
 
         // Update status
         status.update(&source.name, &report);
+        record_sync(&source.name, report.successful_paths() == 0);
+        hooks::HooksConfig::load().fire(hooks::HookEvent::SyncComplete, report.hook_payload());
 
         // Print results
         if json_output {
@@ -8256,7 +15090,9 @@ fn run_sources_sync(
             None,           // db_override (uses data_dir default)
             false,          // full
             false,          // force_rebuild
+            false,          // rebuild
             false,          // watch
+            false,          // gc
             None,           // watch_once
             Some(data_dir), // data_dir
             progress,
@@ -8268,6 +15104,151 @@ fn run_sources_sync(
     Ok(())
 }
 
+/// `cass sync --status`: the last sync of each remote source and how much
+/// it has staged in its mirror
+fn run_sync_status(source_filter: Option<Vec<String>>, json_output: bool) -> CliResult<()> {
+    use crate::sources::config::SourcesConfig;
+    use crate::sources::sync::{SyncResult, SyncStatus, tree_size};
+    use colored::Colorize;
+
+    let config = SourcesConfig::load().map_err(|e| CliError {
+        code: 9,
+        kind: "config",
+        message: format!("Failed to load sources config: {e}"),
+        hint: Some("Run 'cass sources add' to configure a source".into()),
+        retryable: false,
+    })?;
+    let data_dir = sources_data_dir().ok_or_else(|| CliError {
+        code: 9,
+        kind: "config",
+        message: "Could not determine data directory".into(),
+        hint: Some("Set XDG_DATA_HOME or HOME environment variable".into()),
+        retryable: false,
+    })?;
+    let status = SyncStatus::load(&data_dir).unwrap_or_default();
+    let storage =
+        crate::storage::sqlite::SqliteStorage::open_readonly(&data_dir.join("agent_search.db"))
+            .ok();
+    let sources: Vec<_> = config
+        .remote_sources()
+        .filter(|s| {
+            source_filter
+                .as_ref()
+                .is_none_or(|names| names.contains(&s.name))
+        })
+        .collect();
+
+    let format_ts = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default()
+    };
+    let rows: Vec<_> = sources
+        .iter()
+        .map(|source| {
+            let info = status.get(&source.name);
+            let (staged_files, staged_bytes) =
+                tree_size(&data_dir.join("remotes").join(&source.name).join("mirror"));
+            let (result, error) = match info.map(|i| &i.last_result) {
+                Some(SyncResult::Success) => ("success", None),
+                Some(SyncResult::PartialFailure(e)) => ("partial", Some(e.clone())),
+                Some(SyncResult::Failed(e)) => ("failed", Some(e.clone())),
+                Some(SyncResult::Skipped) | None => ("never", None),
+            };
+            let times = daemon::source_sync_times(storage.as_ref(), &source.name);
+            (
+                source,
+                info,
+                times,
+                result,
+                error,
+                staged_files,
+                staged_bytes,
+            )
+        })
+        .collect();
+
+    if json_output {
+        let sources_json: Vec<_> = rows
+            .iter()
+            .map(
+                |(source, info, times, result, error, staged_files, staged_bytes)| {
+                    serde_json::json!({
+                        "source": source.name,
+                        "last_sync": info.and_then(|i| i.last_sync).map(format_ts),
+                        "last_success": times.last_success.map(format_ts),
+                        "result": result,
+                        "error": error,
+                        "duration_ms": info.map(|i| i.duration_ms),
+                        "files_synced": info.map(|i| i.files_synced),
+                        "bytes_transferred": info.map(|i| i.bytes_transferred),
+                        "consecutive_failures": times.consecutive_failures,
+                        "staged_files": staged_files,
+                        "staged_bytes": staged_bytes,
+                    })
+                },
+            )
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "sources": sources_json,
+                "total_staged_bytes": rows.iter().map(|r| r.6).sum::<u64>(),
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!(
+            "{}",
+            "No remote sources configured. Run 'cass sources add' first.".yellow()
+        );
+        return Ok(());
+    }
+    for (source, info, times, result, error, staged_files, staged_bytes) in &rows {
+        println!("{}", source.name.white().bold());
+        match info.and_then(|i| i.last_sync.map(|t| (i, t))) {
+            Some((info, last)) => {
+                let result = match *result {
+                    "success" => result.green(),
+                    "partial" => result.yellow(),
+                    _ => result.red(),
+                };
+                println!(
+                    "  Last sync: {} ({result}, {:.1}s, {} files, {})",
+                    format_ts(last),
+                    info.duration_ms as f64 / 1000.0,
+                    info.files_synced,
+                    format_bytes(info.bytes_transferred)
+                );
+                if times.consecutive_failures > 0 {
+                    println!(
+                        "  Failing since: {} ({} attempts)",
+                        times
+                            .last_success
+                            .map(format_ts)
+                            .unwrap_or_else(|| "first sync".into()),
+                        times.consecutive_failures
+                    );
+                }
+            }
+            None => println!("  Last sync: {}", "never".dimmed()),
+        }
+        if let Some(error) = error {
+            println!("  Errors: {}", error.red());
+        }
+        println!(
+            "  Staged: {} files, {}",
+            staged_files,
+            format_bytes(*staged_bytes)
+        );
+        println!();
+    }
+    Ok(())
+}
+
 /// Handle mappings subcommands (P6.3)
 fn run_mappings_command(action: MappingsAction) -> CliResult<()> {
     match action {
@@ -8289,8 +15270,9 @@ fn run_mappings_command(action: MappingsAction) -> CliResult<()> {
             source,
             path,
             agent,
+            json,
         } => {
-            run_mappings_test(&source, &path, agent.as_deref())?;
+            run_mappings_test(&source, &path, agent.as_deref(), json)?;
         }
     }
     Ok(())
@@ -8476,8 +15458,13 @@ fn run_mappings_remove(source_name: &str, index: usize) -> CliResult<()> {
 }
 
 /// Test how a path would be rewritten for a source (P6.3)
-fn run_mappings_test(source_name: &str, path: &str, agent: Option<&str>) -> CliResult<()> {
-    use crate::sources::config::{PathMapping, SourcesConfig};
+fn run_mappings_test(
+    source_name: &str,
+    path: &str,
+    agent: Option<&str>,
+    json_output: bool,
+) -> CliResult<()> {
+    use crate::sources::config::SourcesConfig;
     use colored::Colorize;
 
     let config = SourcesConfig::load().map_err(|e| CliError {
@@ -8496,46 +15483,48 @@ fn run_mappings_test(source_name: &str, path: &str, agent: Option<&str>) -> CliR
         retryable: false,
     })?;
 
-    // Find the matching mapping
-    let mut matching_mapping = None;
     let rewritten = source.rewrite_path_for_agent(path, agent);
+    let matching_mapping = source.matching_mapping(path, agent);
 
-    // Find which rule matched (if any)
-    if rewritten != path {
-        // Find the longest matching prefix
-        let mut best_match: Option<&PathMapping> = None;
-        for mapping in &source.path_mappings {
-            if !mapping.applies_to_agent(agent) {
-                continue;
-            }
-            if path.starts_with(&mapping.from)
-                && (best_match.is_none()
-                    || mapping.from.len() > best_match.as_ref().unwrap().from.len())
-            {
-                best_match = Some(mapping);
-            }
-        }
-        matching_mapping = best_match;
+    if json_output {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "source": source_name,
+                "input": path,
+                "output": rewritten,
+                "agent": agent,
+                "mapped": matching_mapping.is_some(),
+                "rule": matching_mapping.map(|(index, m)| serde_json::json!({
+                    "index": index,
+                    "from": m.from,
+                    "to": m.to,
+                    "agents": m.agents,
+                })),
+            }))
+            .unwrap_or_default()
+        );
+        return Ok(());
     }
 
     println!();
     println!("Input:  {}", path);
     println!("Output: {}", rewritten);
 
-    if let Some(mapping) = matching_mapping {
-        println!("Rule:   {} → {}", mapping.from, mapping.to);
+    if let Some((index, mapping)) = matching_mapping {
+        println!("Rule:   [{}] {} → {}", index, mapping.from, mapping.to);
         if let Some(ref agents) = mapping.agents {
             println!("        agents: {}", agents.join(", "));
         }
         println!("Status: {} mapped", "✓".green());
-    } else if rewritten == path {
+    } else {
         println!("Status: {} no matching rule", "✗".yellow());
 
         if !source.path_mappings.is_empty() {
             println!();
             println!("Available rules:");
-            for mapping in &source.path_mappings {
-                println!("  {} → {}", mapping.from, mapping.to);
+            for (i, mapping) in source.path_mappings.iter().enumerate() {
+                println!("  [{}] {} → {}", i, mapping.from, mapping.to);
                 if let Some(ref agents) = mapping.agents {
                     println!("    agents: {}", agents.join(", "));
                 }
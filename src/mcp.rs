@@ -0,0 +1,249 @@
+//! `cass mcp`: a Model Context Protocol server on stdin/stdout.
+//!
+//! Coding agents (Claude Code, Codex, ...) start `cass mcp` as an MCP server
+//! and query past sessions mid-task. Messages are newline-delimited JSON-RPC
+//! 2.0; stdout carries only protocol messages, logs go to stderr.
+//!
+//! Tools:
+//! - `search_sessions`: full-text search, like `cass search`
+//! - `get_conversation`: one stored conversation with a page of its messages,
+//!   like `cass show`
+//! - `list_workspaces`: indexed workspaces by recent activity, like
+//!   `cass workspaces`
+//!
+//! The server only reads the index and database. Tool failures (no index yet,
+//! unknown conversation) are reported as tool results with `isError` set, so
+//! the calling model sees them; malformed requests get JSON-RPC errors. The
+//! framing is shared with `cass rpc` (see [`crate::rpc`]).
+
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use crate::api::{Api, ApiResult};
+use crate::rpc::{
+    INVALID_PARAMS, METHOD_NOT_FOUND, RpcError, error, params, parse_request, response,
+    write_message,
+};
+
+/// MCP revision this server implements
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+pub struct Server {
+    api: Api,
+}
+
+impl Server {
+    pub fn new(api: Api) -> Self {
+        Self { api }
+    }
+
+    /// Serve requests from `input` until it closes.
+    pub fn run(&self, input: impl BufRead, out: &mut impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line.context("reading request")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_line(&line) {
+                write_message(out, &response).context("writing response")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one message; `None` for notifications.
+    pub fn handle_line(&self, line: &str) -> Option<Value> {
+        let request = match parse_request(line) {
+            Ok(request) => request,
+            Err(response) => return Some(response),
+        };
+        // Notifications (no id) never get a response
+        let id = request.id?;
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "cass", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&request.params),
+            method => Err(RpcError(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{method}'"),
+            )),
+        };
+        Some(match result {
+            Ok(result) => response(id, result),
+            Err(RpcError(code, message)) => error(id, code, &message),
+        })
+    }
+
+    fn call_tool(&self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError(INVALID_PARAMS, "missing tool name".to_string()))?;
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+        let outcome = match name {
+            "search_sessions" => call(&args, |p| self.api.search(&p)),
+            "get_conversation" => call(&args, |p| self.api.conversation(&p)),
+            "list_workspaces" => call(&args, |p| self.api.workspaces(&p)),
+            _ => {
+                return Err(RpcError(INVALID_PARAMS, format!("unknown tool '{name}'")));
+            }
+        };
+        Ok(match outcome {
+            Ok(value) => json!({
+                "content": [{
+                    "type": "text",
+                    "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+                }],
+            }),
+            Err(message) => json!({
+                "content": [{ "type": "text", "text": message }],
+                "isError": true,
+            }),
+        })
+    }
+}
+
+/// Run a tool on its parsed arguments; failures become text for the model.
+fn call<P: DeserializeOwned>(
+    args: &Value,
+    tool: impl FnOnce(P) -> ApiResult,
+) -> std::result::Result<Value, String> {
+    let params = params(args).map_err(|RpcError(_, message)| message)?;
+    tool(params).map_err(|e| e.to_string())
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_sessions",
+            "description": "Full-text search across past coding agent sessions indexed by cass. \
+                Returns matching messages with a snippet, the session's workspace and agent, \
+                and a conversation_id to pass to get_conversation.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search query; supports cass query syntax (quotes, AND/OR/NOT, agent:, tag:)" },
+                    "limit": { "type": "integer", "description": "Max hits (default 10, max 100)" },
+                    "offset": { "type": "integer", "description": "Hits to skip, for paging" },
+                    "agent": { "type": "string", "description": "Only this agent, e.g. claude_code or codex" },
+                    "workspace": { "type": "string", "description": "Only sessions in this workspace path (or below)" },
+                    "source": { "type": "string", "description": "local, remote, or a source name" },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "get_conversation",
+            "description": "Read a past session: its metadata and a page of its messages. \
+                Page with from_idx; next_idx in the result is the start of the following page.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": ["string", "integer"], "description": "Conversation id (from search_sessions) or the agent's session id" },
+                    "agent": { "type": "string", "description": "Agent of a session id, when several agents could share it" },
+                    "source": { "type": "string", "description": "local, remote, or a source name, when the session exists on several machines" },
+                    "from_idx": { "type": "integer", "description": "First message index (default 0)" },
+                    "limit": { "type": "integer", "description": "Max messages (default 100; 0 for all)" },
+                },
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "list_workspaces",
+            "description": "List workspaces (project directories) with indexed sessions, \
+                most recently active first, with session counts and agents.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "limit": { "type": "integer", "description": "Max workspaces (default 50; 0 for all)" },
+                },
+            },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::PARSE_ERROR;
+
+    fn server() -> (tempfile::TempDir, Server) {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Server::new(Api::new(
+            dir.path().to_path_buf(),
+            dir.path().join("agent_search.db"),
+        ));
+        (dir, server)
+    }
+
+    #[test]
+    fn answers_handshake_and_lists_tools() {
+        let (_dir, server) = server();
+        let init = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#)
+            .unwrap();
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["serverInfo"]["name"], "cass");
+        assert!(
+            server
+                .handle_line(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+                .is_none()
+        );
+
+        let list = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":"2","method":"tools/list"}"#)
+            .unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["search_sessions", "get_conversation", "list_workspaces"]
+        );
+
+        let unknown = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"resources/list"}"#)
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        let garbled = server.handle_line("{not json").unwrap();
+        assert_eq!(garbled["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn tool_failures_are_reported_to_the_model() {
+        let (_dir, server) = server();
+        let resp = server
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"list_workspaces","arguments":{}}}"#,
+            )
+            .unwrap();
+        assert_eq!(resp["result"]["isError"], true);
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("cass index --full"), "{text}");
+
+        let resp = server
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"search_sessions","arguments":{}}}"#,
+            )
+            .unwrap();
+        assert_eq!(resp["result"]["isError"], true);
+
+        let resp = server
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"nope"}}"#,
+            )
+            .unwrap();
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+    }
+}
@@ -1,2 +1,3 @@
 //! Domain models for normalized entities.
+pub mod tool_calls;
 pub mod types;
@@ -0,0 +1,164 @@
+//! Tool calls recorded in a message's raw entry.
+//!
+//! Connectors flatten tool calls into `[Tool: name - detail]` markers for
+//! search and keep the raw entry in `extra_json`. This reads them back per
+//! agent: Claude `tool_use` blocks, Codex shell calls and Gemini function
+//! calls, with file edits and writes from either agent as old/new text pairs.
+//! The conversation viewer renders them; `cass audit` records them.
+
+use serde_json::Value;
+
+use crate::connectors::codex::shell_call;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCall {
+    /// Claude `tool_use` block
+    Panel { name: String, input: Value },
+    /// Codex shell command
+    Shell {
+        command: String,
+        workdir: Option<String>,
+    },
+    /// Gemini function call
+    Function {
+        name: String,
+        args: Value,
+        status: Option<String>,
+        result: Option<String>,
+    },
+    /// File edit or write: (old, new) text pairs; a write has an empty old side
+    Edit {
+        name: String,
+        path: String,
+        changes: Vec<(String, String)>,
+        status: Option<String>,
+    },
+}
+
+/// Tool calls recorded in a message's raw entry, in order.
+pub fn tool_calls(agent: &str, extra: &Value) -> Vec<ToolCall> {
+    match agent {
+        "claude_code" => extra
+            .pointer("/message/content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_use"))
+            .map(|b| {
+                let name = str_field(b, "name").unwrap_or_else(|| "unknown".to_string());
+                let input = b.get("input").cloned().unwrap_or(Value::Null);
+                edit_call(&name, &input, None).unwrap_or(ToolCall::Panel { name, input })
+            })
+            .collect(),
+        "codex" => extra
+            .get("payload")
+            .and_then(shell_call)
+            .map(|call| ToolCall::Shell {
+                command: call.command,
+                workdir: call.workdir,
+            })
+            .into_iter()
+            .collect(),
+        "gemini" => {
+            let recorded = extra
+                .get("toolCalls")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|call| {
+                    let name = str_field(call, "name").unwrap_or_else(|| "unknown".to_string());
+                    let args = call.get("args").cloned().unwrap_or(Value::Null);
+                    let status = str_field(call, "status");
+                    edit_call(&name, &args, status.clone()).unwrap_or(ToolCall::Function {
+                        name,
+                        args,
+                        status,
+                        result: str_field(call, "resultDisplay"),
+                    })
+                });
+            let parts = extra
+                .get("content")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|part| part.get("functionCall"))
+                .map(|call| ToolCall::Function {
+                    name: str_field(call, "name").unwrap_or_else(|| "unknown".to_string()),
+                    args: call.get("args").cloned().unwrap_or(Value::Null),
+                    status: None,
+                    result: None,
+                });
+            recorded.chain(parts).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The call as a file edit, when its input carries old/new strings, a list of
+/// edits (`MultiEdit`), or the content of a write tool.
+fn edit_call(name: &str, input: &Value, status: Option<String>) -> Option<ToolCall> {
+    let path = ["file_path", "path", "absolute_path"]
+        .iter()
+        .find_map(|key| str_field(input, key))?;
+    let pair = |v: &Value| Some((str_field(v, "old_string")?, str_field(v, "new_string")?));
+    let changes: Vec<(String, String)> = if let Some(change) = pair(input) {
+        vec![change]
+    } else if let Some(edits) = input.get("edits").and_then(Value::as_array) {
+        edits.iter().filter_map(pair).collect()
+    } else if name.to_lowercase().contains("write") {
+        vec![(String::new(), str_field(input, "content")?)]
+    } else {
+        return None;
+    };
+    (!changes.is_empty()).then(|| ToolCall::Edit {
+        name: name.to_string(),
+        path,
+        changes,
+        status,
+    })
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extracts_calls_per_agent() {
+        let claude = json!({"message": {"content": [
+            {"type": "text", "text": "Reading"},
+            {"type": "tool_use", "name": "Read", "input": {"file_path": "/src/main.rs"}}
+        ]}});
+        assert_eq!(
+            tool_calls("claude_code", &claude),
+            vec![ToolCall::Panel {
+                name: "Read".into(),
+                input: json!({"file_path": "/src/main.rs"}),
+            }]
+        );
+
+        let codex = json!({"payload": {"type": "function_call", "name": "shell",
+            "arguments": "{\"command\":[\"bash\",\"-lc\",\"cargo test\"]}"}});
+        assert_eq!(
+            tool_calls("codex", &codex),
+            vec![ToolCall::Shell {
+                command: "cargo test".into(),
+                workdir: None,
+            }]
+        );
+
+        let gemini = json!({"content": "Done", "toolCalls": [
+            {"name": "run_shell_command", "args": {"command": "ls"}, "status": "success",
+             "resultDisplay": "a.rs"}
+        ]});
+        let calls = tool_calls("gemini", &gemini);
+        assert!(matches!(&calls[0], ToolCall::Function { name, status, .. }
+            if name == "run_shell_command" && status.as_deref() == Some("success")));
+
+        assert!(tool_calls("aider", &claude).is_empty());
+    }
+}
@@ -36,6 +36,42 @@ pub struct Workspace {
     pub display_name: Option<String>,
 }
 
+/// Logical project a workspace belongs to. Workspaces sharing a git remote
+/// (worktrees, clones, synced machines) map to the same project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceProject {
+    pub workspace: String,
+    pub project: String,
+    pub git_remote: Option<String>,
+    /// Set by `cass projects set`; auto-detection never overwrites it
+    pub manual: bool,
+}
+
+/// Thumbs up (+1) or down (-1) given to a session with `cass rate` or from the TUI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationRating {
+    pub source_id: String,
+    pub source_path: String,
+    pub rating: i8,
+    pub note: Option<String>,
+    pub updated_at: i64,
+}
+
+/// A copy of an agent session that indexing folded into a richer copy of the
+/// same session (same agent and external id) from another source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergedDuplicate {
+    pub source_id: String,
+    pub source_path: String,
+    pub agent: String,
+    pub external_id: String,
+    pub kept_source_id: String,
+    pub kept_source_path: String,
+    /// Messages in the dropped copy
+    pub messages: i64,
+    pub merged_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: Option<i64>,
@@ -62,6 +98,16 @@ fn default_source_id() -> String {
     "local".to_string()
 }
 
+/// A personal note attached to one message (`cass note`), searchable with `note:`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: i64,
+    pub message_id: i64,
+    pub note: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: Option<i64>,
@@ -72,6 +118,25 @@ pub struct Message {
     pub content: String,
     pub extra_json: serde_json::Value,
     pub snippets: Vec<Snippet>,
+    /// BPE token count of `content` (cl100k_base), computed at index time.
+    #[serde(default)]
+    pub approx_tokens: Option<i64>,
+    /// Originating file when it differs from the conversation's `source_path`
+    #[serde(default)]
+    pub source_path: Option<String>,
+    /// 1-based line of the message in its originating file
+    #[serde(default)]
+    pub source_line: Option<i64>,
+}
+
+impl Message {
+    /// File and line to open for this message, falling back to the conversation's file.
+    pub fn location<'a>(&'a self, conversation_path: &'a str) -> (&'a str, Option<i64>) {
+        (
+            self.source_path.as_deref().unwrap_or(conversation_path),
+            self.source_line,
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,142 @@
+//! Named profiles: separate data dirs (database, index, UI state) per client.
+//!
+//! `cass --profile work ...` (or `CASS_PROFILE=work`) points every command,
+//! the indexer and the TUI at the profile's data dir. Profiles may be mapped
+//! explicitly in `config.toml`:
+//!
+//! ```toml
+//! [profiles.work]
+//! data_dir = "~/clients/acme/cass"
+//! db = "/secure/acme.db"        # optional, defaults to <data_dir>/agent_search.db
+//! ```
+//!
+//! A profile without an entry lives under `<data dir>/profiles/<name>`.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::CliError;
+use crate::hooks::HooksConfig;
+
+static ACTIVE: OnceLock<Profile> = OnceLock::new();
+
+/// One entry of the `[profiles]` section of `config.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProfileConfig {
+    pub data_dir: Option<PathBuf>,
+    pub db: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub data_dir: PathBuf,
+    /// Explicit database path; None means `<data_dir>/agent_search.db`
+    pub db: Option<PathBuf>,
+}
+
+/// The configured profiles, empty when the config file is missing or invalid.
+pub fn configured() -> BTreeMap<String, ProfileConfig> {
+    let Some(path) = HooksConfig::config_path() else {
+        return BTreeMap::new();
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return BTreeMap::new();
+    };
+    match toml::from_str::<ConfigFile>(&content) {
+        Ok(config) => config.profiles,
+        Err(e) => {
+            warn!(path = %path.display(), "ignoring profiles: invalid config.toml: {e}");
+            BTreeMap::new()
+        }
+    }
+}
+
+/// Resolve `name` against `profiles`, falling back to `<base>/profiles/<name>`.
+pub fn resolve(
+    name: &str,
+    profiles: &BTreeMap<String, ProfileConfig>,
+    base: PathBuf,
+) -> Result<Profile, CliError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CliError::usage(
+            format!("invalid profile name: {name:?}"),
+            Some("Profile names may contain letters, digits, '-' and '_'".to_string()),
+        ));
+    }
+    let entry = profiles.get(name).cloned().unwrap_or_default();
+    let data_dir = entry
+        .data_dir
+        .map(expand_home)
+        .unwrap_or_else(|| base.join("profiles").join(name));
+    Ok(Profile {
+        name: name.to_string(),
+        data_dir,
+        db: entry.db.map(expand_home),
+    })
+}
+
+/// Make `name` the active profile for the rest of the process.
+pub fn activate(name: &str) -> Result<Profile, CliError> {
+    let profile = resolve(name, &configured(), crate::base_data_dir())?;
+    Ok(ACTIVE.get_or_init(|| profile).clone())
+}
+
+/// The active profile, if `--profile` was given
+pub fn active() -> Option<&'static Profile> {
+    ACTIVE.get()
+}
+
+/// Data dir of the active profile; `default_data_dir` defers to this
+pub fn active_data_dir() -> Option<PathBuf> {
+    ACTIVE.get().map(|p| p.data_dir.clone())
+}
+
+pub(crate) fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_configured_and_implicit_profiles() {
+        let profiles: BTreeMap<String, ProfileConfig> = toml::from_str::<ConfigFile>(
+            "[profiles.work]\ndata_dir = \"/clients/acme\"\ndb = \"/secure/acme.db\"\n",
+        )
+        .unwrap()
+        .profiles;
+        let base = PathBuf::from("/data/cass");
+
+        let work = resolve("work", &profiles, base.clone()).unwrap();
+        assert_eq!(work.data_dir, PathBuf::from("/clients/acme"));
+        assert_eq!(work.db, Some(PathBuf::from("/secure/acme.db")));
+
+        let personal = resolve("personal", &profiles, base.clone()).unwrap();
+        assert_eq!(
+            personal.data_dir,
+            PathBuf::from("/data/cass/profiles/personal")
+        );
+        assert_eq!(personal.db, None);
+
+        assert!(resolve("../escape", &profiles, base).is_err());
+    }
+}
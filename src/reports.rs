@@ -0,0 +1,553 @@
+//! Scheduled reports delivered by the daemon.
+//!
+//! Reports live in `config.toml` next to the hooks:
+//!
+//! ```toml
+//! [[reports]]
+//! name = "weekly-usage"
+//! kind = "digest"          # or "stats"
+//! schedule = "weekly"      # hourly, daily or weekly
+//! webhook = "https://hooks.slack.com/services/..."
+//! command = "mail -s 'cass weekly' me@example.com"
+//! format = "markdown"      # what `command` receives on stdin; or "json"
+//! ```
+//!
+//! A digest covers the sessions started during the last schedule interval
+//! (agents, workspaces, busiest sessions); a stats report adds index-wide
+//! totals and growth. The daemon renders each report when it falls due and
+//! delivers it to the webhook (a JSON POST whose `text` field holds the
+//! markdown, which Slack and most chat webhooks display) and/or pipes it to the
+//! command. Delivery times, and failed runs the daemon is backing off from, are
+//! kept in `report_status.json` in the data dir.
+//! `cass report run <name>` does the same on demand.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::hooks::{HooksConfig, run_hook};
+use crate::storage::sqlite::SqliteStorage;
+
+/// Default time a report command or webhook may take
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Sessions and workspaces listed in a digest
+const TOP_N: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    /// Activity during the last schedule interval
+    Digest,
+    /// Digest plus index-wide totals and growth
+    Stats,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportSchedule {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl ReportSchedule {
+    pub fn interval_ms(self) -> i64 {
+        const HOUR: i64 = 60 * 60 * 1000;
+        match self {
+            Self::Hourly => HOUR,
+            Self::Daily => 24 * HOUR,
+            Self::Weekly => 7 * 24 * HOUR,
+        }
+    }
+}
+
+impl std::fmt::Display for ReportSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hourly => write!(f, "hourly"),
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly => write!(f, "weekly"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+/// A single `[[reports]]` entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDefinition {
+    pub name: String,
+    pub kind: ReportKind,
+    pub schedule: ReportSchedule,
+    /// URL to POST `{"text": <markdown>, "report": {...}}` to
+    #[serde(default)]
+    pub webhook: Option<String>,
+    /// Shell command that receives the report on stdin
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub format: ReportFormat,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// The report section of `config.toml`; other sections are ignored here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportsConfig {
+    #[serde(default)]
+    pub reports: Vec<ReportDefinition>,
+}
+
+impl ReportsConfig {
+    /// Load reports, treating a missing or unreadable config as "no reports".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring reports: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ReportDefinition> {
+        self.reports.iter().find(|r| r.name == name)
+    }
+}
+
+/// Delay before retrying a failed report; doubles with each further failure
+const REPORT_RETRY_BASE_MS: i64 = 5 * 60 * 1000;
+
+/// Last delivery per report, persisted as `report_status.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportStatus {
+    /// Unix millis of the last delivery, keyed by report name
+    pub last_run: HashMap<String, i64>,
+    /// Failed scheduled runs since the last delivery, keyed by report name
+    #[serde(default)]
+    pub failures: HashMap<String, ReportFailures>,
+}
+
+/// Failed scheduled runs of one report in a row
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportFailures {
+    pub consecutive: u32,
+    /// Unix millis of the latest failed attempt
+    pub last_attempt: i64,
+    pub last_error: String,
+}
+
+impl ReportStatus {
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("report_status.json")
+    }
+
+    pub fn load(data_dir: &Path) -> Self {
+        std::fs::read_to_string(Self::path(data_dir))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = Self::path(data_dir);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// When a report is next due, given its last delivery
+pub fn next_report_due(def: &ReportDefinition, last_run: Option<i64>, now_ms: i64) -> i64 {
+    last_run.map_or(now_ms, |last| last + def.schedule.interval_ms())
+}
+
+/// When the daemon next tries a report: when due, or after a backoff (5
+/// minutes, doubling, capped at the schedule's interval) while it is failing
+pub fn next_report_attempt(def: &ReportDefinition, status: &ReportStatus, now_ms: i64) -> i64 {
+    let due = next_report_due(def, status.last_run.get(&def.name).copied(), now_ms);
+    match status.failures.get(&def.name) {
+        Some(failed) if failed.consecutive > 0 => {
+            let shift = (failed.consecutive - 1).min(16);
+            let backoff = (REPORT_RETRY_BASE_MS << shift).min(def.schedule.interval_ms());
+            due.max(failed.last_attempt + backoff)
+        }
+        _ => due,
+    }
+}
+
+/// A rendered report
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedReport {
+    pub name: String,
+    pub kind: ReportKind,
+    pub from_ms: i64,
+    pub to_ms: i64,
+    pub markdown: String,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AgentActivity {
+    agent: String,
+    sessions: i64,
+    messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SessionActivity {
+    title: String,
+    agent: String,
+    workspace: Option<String>,
+    messages: i64,
+}
+
+/// Render a report covering the schedule interval that ends at `now_ms`.
+pub fn render_report(
+    conn: &Connection,
+    def: &ReportDefinition,
+    now_ms: i64,
+) -> Result<RenderedReport> {
+    let from_ms = now_ms - def.schedule.interval_ms();
+    let agents: Vec<AgentActivity> = conn
+        .prepare(
+            "SELECT a.slug, COUNT(DISTINCT c.id), COUNT(m.id)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             WHERE c.started_at >= ?1 AND c.started_at < ?2
+             GROUP BY a.slug
+             ORDER BY 2 DESC, a.slug",
+        )?
+        .query_map(params![from_ms, now_ms], |r| {
+            Ok(AgentActivity {
+                agent: r.get(0)?,
+                sessions: r.get(1)?,
+                messages: r.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let workspaces: Vec<(String, i64)> = conn
+        .prepare(
+            "SELECT w.path, COUNT(*) FROM conversations c
+             JOIN workspaces w ON c.workspace_id = w.id
+             WHERE c.started_at >= ?1 AND c.started_at < ?2
+             GROUP BY w.path
+             ORDER BY 2 DESC, w.path
+             LIMIT ?3",
+        )?
+        .query_map(params![from_ms, now_ms, TOP_N as i64], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let busiest: Vec<SessionActivity> = conn
+        .prepare(
+            "SELECT COALESCE(c.title, '(untitled)'), a.slug, w.path, COUNT(m.id)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN workspaces w ON c.workspace_id = w.id
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             WHERE c.started_at >= ?1 AND c.started_at < ?2
+             GROUP BY c.id
+             ORDER BY 4 DESC, c.id
+             LIMIT ?3",
+        )?
+        .query_map(params![from_ms, now_ms, TOP_N as i64], |r| {
+            Ok(SessionActivity {
+                title: r.get(0)?,
+                agent: r.get(1)?,
+                workspace: r.get(2)?,
+                messages: r.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let sessions: i64 = agents.iter().map(|a| a.sessions).sum();
+    let messages: i64 = agents.iter().map(|a| a.messages).sum();
+    let day = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    };
+
+    let mut md = format!(
+        "*cass {} report: {}* ({} to {} UTC)\n\n",
+        def.schedule,
+        def.name,
+        day(from_ms),
+        day(now_ms)
+    );
+    md.push_str(&format!(
+        "{sessions} sessions, {messages} messages across {} agents\n",
+        agents.len()
+    ));
+    for a in &agents {
+        md.push_str(&format!(
+            "• {}: {} sessions, {} messages\n",
+            a.agent, a.sessions, a.messages
+        ));
+    }
+    if !workspaces.is_empty() {
+        md.push_str("\nTop workspaces:\n");
+        for (path, count) in &workspaces {
+            md.push_str(&format!("• {path} ({count} sessions)\n"));
+        }
+    }
+    if !busiest.is_empty() {
+        md.push_str("\nBusiest sessions:\n");
+        for s in &busiest {
+            md.push_str(&format!(
+                "• {} ({}, {} messages)\n",
+                s.title, s.agent, s.messages
+            ));
+        }
+    }
+
+    let mut data = serde_json::json!({
+        "sessions": sessions,
+        "messages": messages,
+        "agents": agents,
+        "top_workspaces": workspaces
+            .iter()
+            .map(|(path, sessions)| serde_json::json!({"workspace": path, "sessions": sessions}))
+            .collect::<Vec<_>>(),
+        "busiest_sessions": busiest,
+    });
+
+    if def.kind == ReportKind::Stats {
+        let (total_conversations, total_messages): (i64, i64) = conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM conversations), (SELECT COUNT(*) FROM messages)",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let db_bytes: i64 = conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |r| r.get(0),
+        )?;
+        md.push_str(&format!(
+            "\nIndex totals: {total_conversations} sessions, {total_messages} messages, {:.1} MB on disk\n",
+            db_bytes as f64 / 1_000_000.0
+        ));
+        data["totals"] = serde_json::json!({
+            "conversations": total_conversations,
+            "messages": total_messages,
+            "db_bytes": db_bytes,
+        });
+    }
+
+    Ok(RenderedReport {
+        name: def.name.clone(),
+        kind: def.kind,
+        from_ms,
+        to_ms: now_ms,
+        markdown: md,
+        data,
+    })
+}
+
+/// Send a rendered report to the definition's webhook and/or command.
+pub fn deliver_report(def: &ReportDefinition, report: &RenderedReport) -> Result<()> {
+    if def.webhook.is_none() && def.command.is_none() {
+        bail!("report '{}' has neither a webhook nor a command", def.name);
+    }
+    let timeout = Duration::from_secs(def.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    if let Some(url) = &def.webhook {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("cass/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .context("building http client")?;
+        let response = client
+            .post(url)
+            .json(&serde_json::json!({"text": report.markdown, "report": report}))
+            .send()
+            .with_context(|| format!("posting report '{}' to webhook", def.name))?;
+        if !response.status().is_success() {
+            bail!(
+                "webhook for report '{}' returned {}",
+                def.name,
+                response.status()
+            );
+        }
+    }
+    if let Some(command) = &def.command {
+        let body = match def.format {
+            ReportFormat::Markdown => report.markdown.clone(),
+            ReportFormat::Json => serde_json::to_string(report)?,
+        };
+        run_hook(command, &body, timeout)
+            .with_context(|| format!("running command for report '{}'", def.name))?;
+    }
+    Ok(())
+}
+
+/// Render and deliver one report, recording the delivery time.
+pub fn run_report(
+    data_dir: &Path,
+    db_path: &Path,
+    def: &ReportDefinition,
+) -> Result<RenderedReport> {
+    let storage = SqliteStorage::open_readonly(db_path)?;
+    let now = SqliteStorage::now_millis();
+    let report = render_report(storage.raw(), def, now)?;
+    deliver_report(def, &report)?;
+    let mut status = ReportStatus::load(data_dir);
+    status.last_run.insert(def.name.clone(), now);
+    status.failures.remove(&def.name);
+    status.save(data_dir)?;
+    Ok(report)
+}
+
+/// Deliver every configured report that is due. Returns how many were delivered.
+pub fn run_due_reports(data_dir: &Path, db_path: &Path) -> usize {
+    let config = ReportsConfig::load();
+    if config.reports.is_empty() {
+        return 0;
+    }
+    let status = ReportStatus::load(data_dir);
+    let now = SqliteStorage::now_millis();
+    let mut delivered = 0;
+    for def in &config.reports {
+        if next_report_attempt(def, &status, now) > now {
+            continue;
+        }
+        let retry = status.failures.get(&def.name).map_or(0, |f| f.consecutive);
+        info!(report = %def.name, schedule = %def.schedule, retry, "daemon: scheduled report");
+        match run_report(data_dir, db_path, def) {
+            Ok(_) => delivered += 1,
+            Err(e) => {
+                warn!(report = %def.name, "daemon: report failed: {e:#}");
+                // run_report saved nothing; reload in case an earlier report did
+                let mut status = ReportStatus::load(data_dir);
+                let failed = status.failures.entry(def.name.clone()).or_default();
+                failed.consecutive += 1;
+                failed.last_attempt = now;
+                failed.last_error = format!("{e:#}");
+                if let Err(e) = status.save(data_dir) {
+                    warn!("daemon: failed to save report status: {e:#}");
+                }
+            }
+        }
+    }
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    fn weekly_digest() -> ReportDefinition {
+        toml::from_str::<ReportsConfig>(
+            r#"
+            [[hooks]]
+            event = "post-index"
+            command = "true"
+
+            [[reports]]
+            name = "weekly"
+            kind = "digest"
+            schedule = "weekly"
+            command = "cat"
+            "#,
+        )
+        .unwrap()
+        .reports
+        .remove(0)
+    }
+
+    #[test]
+    fn due_immediately_then_once_per_interval() {
+        let def = weekly_digest();
+        assert_eq!(def.format, ReportFormat::Markdown);
+        assert_eq!(next_report_due(&def, None, 5), 5);
+        assert_eq!(next_report_due(&def, Some(0), 5), 7 * DAY);
+    }
+
+    #[test]
+    fn failing_reports_back_off_up_to_the_interval() {
+        let def = weekly_digest();
+        let mut status = ReportStatus::default();
+        assert_eq!(next_report_attempt(&def, &status, 5), 5);
+
+        let attempt = 10 * DAY;
+        let fail = |consecutive| ReportFailures {
+            consecutive,
+            last_attempt: attempt,
+            last_error: "webhook returned 500".into(),
+        };
+        status.failures.insert("weekly".into(), fail(1));
+        assert_eq!(
+            next_report_attempt(&def, &status, attempt),
+            attempt + REPORT_RETRY_BASE_MS
+        );
+        status.failures.insert("weekly".into(), fail(3));
+        assert_eq!(
+            next_report_attempt(&def, &status, attempt),
+            attempt + 4 * REPORT_RETRY_BASE_MS
+        );
+        status.failures.insert("weekly".into(), fail(30));
+        assert_eq!(
+            next_report_attempt(&def, &status, attempt),
+            attempt + 7 * DAY
+        );
+    }
+
+    #[test]
+    fn digest_covers_only_the_last_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let now = 30 * DAY;
+        storage
+            .raw()
+            .execute_batch(&format!(
+                "INSERT INTO agents(id, slug, name, kind, created_at, updated_at) VALUES
+                    (1, 'codex', 'Codex', 'cli', 0, 0), (2, 'amp', 'Amp', 'cli', 0, 0);
+                 INSERT INTO workspaces(id, path) VALUES (1, '/src/app');
+                 INSERT INTO conversations(id, agent_id, workspace_id, source_id, source_path, title, started_at) VALUES
+                    (1, 1, 1, 'local', '/a.jsonl', 'Fix login', {recent}),
+                    (2, 2, 1, 'local', '/b.jsonl', 'Add tests', {recent}),
+                    (3, 1, NULL, 'local', '/c.jsonl', 'Old work', {old});
+                 INSERT INTO messages(conversation_id, idx, role, content) VALUES
+                    (1, 0, 'user', 'a'), (1, 1, 'agent', 'b'), (2, 0, 'user', 'c'), (3, 0, 'user', 'd');",
+                recent = now - DAY,
+                old = now - 20 * DAY,
+            ))
+            .unwrap();
+
+        let report = render_report(storage.raw(), &weekly_digest(), now).unwrap();
+        assert_eq!(report.data["sessions"], 2);
+        assert_eq!(report.data["messages"], 3);
+        assert_eq!(report.data["busiest_sessions"][0]["title"], "Fix login");
+        assert_eq!(report.data["top_workspaces"][0]["sessions"], 2);
+        assert!(report.data.get("totals").is_none());
+        assert!(report.markdown.contains("• codex: 1 sessions, 2 messages"));
+        assert!(!report.markdown.contains("Old work"));
+
+        let stats = ReportDefinition {
+            kind: ReportKind::Stats,
+            ..weekly_digest()
+        };
+        let report = render_report(storage.raw(), &stats, now).unwrap();
+        assert_eq!(report.data["totals"]["conversations"], 3);
+    }
+}
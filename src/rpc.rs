@@ -0,0 +1,323 @@
+//! `cass rpc`: JSON-RPC 2.0 over stdio for editor integrations.
+//!
+//! Neovim and VS Code extensions keep one `cass rpc` process running and send
+//! it requests, instead of paying a cold start and index open per query.
+//! Messages are newline-delimited JSON; requests are answered in order.
+//!
+//! Methods take the parameters of [`crate::api`] and return its JSON:
+//! - `search`: hits for a query, like `cass search`
+//! - `show`: a conversation with a page of its messages, like `cass show`
+//! - `facets`, `workspaces`, `stats`
+//! - `ping`
+//!
+//! With `"stream": true`, `search` sends each hit as a `$/hit` notification
+//! and `show` sends each message as `$/message`, both carrying the request's
+//! id, before the response with the remaining fields. A streamed `show` walks
+//! every page (`limit` messages at a time), so a huge session arrives
+//! incrementally instead of in one response. A streamed `search` still runs
+//! the whole query first and then replays its page of hits, so it spares the
+//! client one large message but not the wait for the search itself.
+//!
+//! `cass mcp` speaks the same framing; the shared pieces live here.
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+use crate::api::{Api, ApiError, ConversationParams, FacetParams, SearchParams, WorkspaceParams};
+
+pub(crate) const PARSE_ERROR: i64 = -32700;
+pub(crate) const INVALID_REQUEST: i64 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// Server-defined codes for [`ApiError`]s
+const NOT_FOUND: i64 = -32001;
+const UNAVAILABLE: i64 = -32002;
+
+/// A parsed message; `id` is `None` for notifications.
+pub(crate) struct Request {
+    pub id: Option<Value>,
+    pub method: String,
+    pub params: Value,
+}
+
+/// Parse one line, or return the error response to send for it.
+pub(crate) fn parse_request(line: &str) -> std::result::Result<Request, Value> {
+    let request: Value = serde_json::from_str(line)
+        .map_err(|e| error(Value::Null, PARSE_ERROR, &format!("parse error: {e}")))?;
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Err(error(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "missing method",
+        ));
+    };
+    Ok(Request {
+        method: method.to_string(),
+        params: request.get("params").cloned().unwrap_or(json!({})),
+        id,
+    })
+}
+
+pub(crate) fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub(crate) fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub(crate) fn write_message(out: &mut impl Write, message: &Value) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, message)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+/// A failed request: JSON-RPC code and message
+pub(crate) struct RpcError(pub i64, pub String);
+
+impl From<ApiError> for RpcError {
+    fn from(e: ApiError) -> Self {
+        let code = match &e {
+            ApiError::BadRequest(_) => INVALID_PARAMS,
+            ApiError::NotFound(_) => NOT_FOUND,
+            ApiError::Unavailable(_) => UNAVAILABLE,
+            ApiError::Internal(_) => INTERNAL_ERROR,
+        };
+        Self(code, e.to_string())
+    }
+}
+
+/// Method parameters; a missing `params` counts as `{}`.
+pub(crate) fn params<P: DeserializeOwned>(params: &Value) -> std::result::Result<P, RpcError> {
+    let params = if params.is_null() {
+        json!({})
+    } else {
+        params.clone()
+    };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError(INVALID_PARAMS, format!("invalid params: {e}")))
+}
+
+fn take_array(value: &mut Value, key: &str) -> Vec<Value> {
+    match value.get_mut(key).map(Value::take) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    }
+}
+
+pub struct Server {
+    api: Api,
+}
+
+impl Server {
+    pub fn new(api: Api) -> Self {
+        Self { api }
+    }
+
+    /// Serve requests from `input` until it closes.
+    pub fn run(&self, input: impl BufRead, out: &mut impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line.context("reading request")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut failed = None;
+            self.handle_line(&line, &mut |message| {
+                if failed.is_none() {
+                    failed = write_message(out, &message).err();
+                }
+            });
+            if let Some(e) = failed {
+                return Err(e).context("writing response");
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one message through `send`: streamed notifications, then the
+    /// response. Notifications get no response.
+    pub fn handle_line(&self, line: &str, send: &mut dyn FnMut(Value)) {
+        let request = match parse_request(line) {
+            Ok(request) => request,
+            Err(response) => return send(response),
+        };
+        let Some(id) = request.id else {
+            return;
+        };
+        let stream = request.params.get("stream").and_then(Value::as_bool) == Some(true);
+        let result = match request.method.as_str() {
+            "ping" => Ok(json!({})),
+            "search" => self.search(&id, &request.params, stream, send),
+            "show" => self.show(&id, &request.params, stream, send),
+            "facets" => {
+                params::<FacetParams>(&request.params).and_then(|p| Ok(self.api.facets(&p)?))
+            }
+            "workspaces" => params::<WorkspaceParams>(&request.params)
+                .and_then(|p| Ok(self.api.workspaces(&p)?)),
+            "stats" => self.api.stats().map_err(RpcError::from),
+            method => Err(RpcError(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{method}'"),
+            )),
+        };
+        send(match result {
+            Ok(result) => response(id, result),
+            Err(RpcError(code, message)) => error(id, code, &message),
+        });
+    }
+
+    fn search(
+        &self,
+        id: &Value,
+        raw: &Value,
+        stream: bool,
+        send: &mut dyn FnMut(Value),
+    ) -> std::result::Result<Value, RpcError> {
+        let mut result = self.api.search(&params::<SearchParams>(raw)?)?;
+        if stream {
+            for hit in take_array(&mut result, "hits") {
+                send(notification("$/hit", json!({ "id": id, "hit": hit })));
+            }
+            if let Some(result) = result.as_object_mut() {
+                result.remove("hits");
+            }
+        }
+        Ok(result)
+    }
+
+    fn show(
+        &self,
+        id: &Value,
+        raw: &Value,
+        stream: bool,
+        send: &mut dyn FnMut(Value),
+    ) -> std::result::Result<Value, RpcError> {
+        let mut query = params::<ConversationParams>(raw)?;
+        let mut conv = self.api.conversation(&query)?;
+        if !stream {
+            return Ok(conv);
+        }
+        let mut messages = take_array(&mut conv, "messages");
+        loop {
+            for message in messages {
+                send(notification(
+                    "$/message",
+                    json!({ "id": id, "message": message }),
+                ));
+            }
+            let Some(next) = conv["next_idx"].as_i64() else {
+                break;
+            };
+            query.from_idx = Some(next);
+            let mut page = self.api.conversation(&query)?;
+            messages = take_array(&mut page, "messages");
+            conv["next_idx"] = page["next_idx"].take();
+        }
+        if let Some(conv) = conv.as_object_mut() {
+            conv.remove("messages");
+        }
+        Ok(conv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(server: &Server, line: &str) -> Vec<Value> {
+        let mut sent = Vec::new();
+        server.handle_line(line, &mut |message| sent.push(message));
+        sent
+    }
+
+    #[test]
+    fn answers_in_json_rpc_and_maps_api_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Server::new(Api::new(
+            dir.path().to_path_buf(),
+            dir.path().join("agent_search.db"),
+        ));
+
+        let pong = exchange(&server, r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#);
+        assert_eq!(pong, [json!({"jsonrpc": "2.0", "id": 1, "result": {}})]);
+        assert!(exchange(&server, r#"{"jsonrpc":"2.0","method":"ping"}"#).is_empty());
+
+        let missing = exchange(
+            &server,
+            r#"{"jsonrpc":"2.0","id":2,"method":"show","params":{"id":7,"stream":true}}"#,
+        );
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0]["error"]["code"], UNAVAILABLE);
+
+        let bad = exchange(&server, r#"{"jsonrpc":"2.0","id":3,"method":"search"}"#);
+        assert_eq!(bad[0]["error"]["code"], INVALID_PARAMS);
+        let unknown = exchange(&server, r#"{"jsonrpc":"2.0","id":4,"method":"index"}"#);
+        assert_eq!(unknown[0]["error"]["code"], METHOD_NOT_FOUND);
+        let garbled = exchange(&server, "{");
+        assert_eq!(garbled[0]["error"]["code"], PARSE_ERROR);
+    }
+
+    fn demo_server() -> Server {
+        let data_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/search_demo_data");
+        Server::new(Api::new(data_dir.clone(), data_dir.join("agent_search.db")))
+    }
+
+    #[test]
+    fn streamed_search_sends_each_hit_before_the_response() {
+        let server = demo_server();
+        let whole = exchange(
+            &server,
+            r#"{"jsonrpc":"2.0","id":1,"method":"search","params":{"query":"hello","limit":3}}"#,
+        );
+        let hits = whole[0]["result"]["hits"].as_array().expect("hits").clone();
+        assert!(!hits.is_empty());
+
+        let streamed = exchange(
+            &server,
+            r#"{"jsonrpc":"2.0","id":2,"method":"search","params":{"query":"hello","limit":3,"stream":true}}"#,
+        );
+        let (last, notes) = streamed.split_last().unwrap();
+        assert!(
+            notes
+                .iter()
+                .all(|n| n["method"] == "$/hit" && n["params"]["id"] == 2)
+        );
+        let streamed_hits: Vec<&Value> = notes.iter().map(|n| &n["params"]["hit"]).collect();
+        assert_eq!(streamed_hits, hits.iter().collect::<Vec<_>>());
+        assert_eq!(last["id"], 2);
+        assert!(last["result"].get("hits").is_none());
+    }
+
+    #[test]
+    fn streamed_show_walks_every_page() {
+        let server = demo_server();
+        let streamed = exchange(
+            &server,
+            r#"{"jsonrpc":"2.0","id":1,"method":"show","params":{"id":4,"limit":5,"stream":true}}"#,
+        );
+        let (last, notes) = streamed.split_last().unwrap();
+        assert!(
+            notes
+                .iter()
+                .all(|n| n["method"] == "$/message" && n["params"]["id"] == 1)
+        );
+        let idx: Vec<i64> = notes
+            .iter()
+            .map(|n| n["params"]["message"]["idx"].as_i64().unwrap())
+            .collect();
+        assert_eq!(idx.len(), 17, "all pages of a 17-message conversation");
+        assert!(idx.windows(2).all(|w| w[0] < w[1]));
+        assert!(last["result"].get("messages").is_none());
+        assert!(last["result"]["next_idx"].is_null());
+    }
+}
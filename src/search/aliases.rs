@@ -0,0 +1,154 @@
+//! Query aliases (`@name` macros) defined in `config.toml`.
+//!
+//! ```toml
+//! [aliases]
+//! bugs = "has:errors role:user -workspace:~/scratch"
+//! rust = "cargo OR rustc"
+//! ```
+//!
+//! `cass search "@bugs panic"` and the TUI search bar both expand `@bugs`
+//! before the query is parsed. Aliases may reference other aliases; unknown
+//! names, cycles and `@` inside quoted phrases are left untouched.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+
+/// Nesting limit for aliases that reference other aliases
+const MAX_DEPTH: usize = 8;
+
+/// The `[aliases]` section of `config.toml`; other sections are ignored here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryAliases {
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl QueryAliases {
+    /// Load aliases from the shared config file, treating a missing or invalid file as "none".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring aliases: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Replace every `@name` token with its definition.
+    pub fn expand(&self, query: &str) -> String {
+        if self.is_empty() || !query.contains('@') {
+            return query.to_string();
+        }
+        let mut stack = Vec::new();
+        self.expand_inner(query, &mut stack)
+    }
+
+    fn expand_inner(&self, query: &str, stack: &mut Vec<String>) -> String {
+        let mut out = String::with_capacity(query.len());
+        let mut chars = query.char_indices().peekable();
+        let mut in_quote = false;
+        let mut at_token_start = true;
+
+        while let Some((i, c)) = chars.next() {
+            if c == '"' {
+                in_quote = !in_quote;
+            }
+            if c == '@' && at_token_start && !in_quote {
+                let rest = &query[i + 1..];
+                let len = rest
+                    .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '-'))
+                    .unwrap_or(rest.len());
+                let name = &rest[..len];
+                if let Some(definition) = self.aliases.get(name)
+                    && stack.len() < MAX_DEPTH
+                    && !stack.iter().any(|s| s == name)
+                {
+                    stack.push(name.to_string());
+                    out.push_str(&self.expand_inner(definition, stack));
+                    stack.pop();
+                    // Skip the alias name itself
+                    while chars.peek().is_some_and(|&(j, _)| j <= i + len) {
+                        chars.next();
+                    }
+                    at_token_start = false;
+                    continue;
+                }
+            }
+            out.push(c);
+            at_token_start = c.is_whitespace() || c == '(';
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(pairs: &[(&str, &str)]) -> QueryAliases {
+        QueryAliases {
+            aliases: pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_aliases_section() {
+        let config: QueryAliases = toml::from_str(
+            r#"
+            [[hooks]]
+            event = "post-index"
+            command = "true"
+
+            [aliases]
+            bugs = "has:errors role:user"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.aliases["bugs"], "has:errors role:user");
+    }
+
+    #[test]
+    fn expands_aliases_in_place() {
+        let a = aliases(&[("bugs", "has:errors role:user -workspace:~/scratch")]);
+        assert_eq!(
+            a.expand("@bugs panic"),
+            "has:errors role:user -workspace:~/scratch panic"
+        );
+        assert_eq!(
+            a.expand("timeout @bugs"),
+            "timeout has:errors role:user -workspace:~/scratch"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_quoted_and_embedded_at_alone() {
+        let a = aliases(&[("bugs", "error")]);
+        assert_eq!(a.expand("@nope"), "@nope");
+        assert_eq!(a.expand("\"@bugs\" x"), "\"@bugs\" x");
+        assert_eq!(a.expand("user@bugs"), "user@bugs");
+    }
+
+    #[test]
+    fn nested_aliases_expand_and_cycles_stop() {
+        let a = aliases(&[("a", "@b one"), ("b", "two"), ("x", "@y"), ("y", "@x")]);
+        assert_eq!(a.expand("@a"), "two one");
+        assert_eq!(a.expand("@x"), "@x");
+    }
+}
@@ -0,0 +1,171 @@
+//! Per-field analyzers for the Tantivy `title` and `content` fields.
+//!
+//! ```toml
+//! [analyzers]
+//! title = "plain"      # lowercase only (default)
+//! content = "stemmed"  # lowercase + English stemming
+//! ```
+//!
+//! Titles are short and full of tool and library names, where stemming costs
+//! precision ("pandas" and "panda" collapse), while long message bodies benefit
+//! from matching inflections. The analyzer each field was built with is recorded
+//! in the index schema itself, so queries normalize terms the same way the index
+//! did, and `cass index` rebuilds the search index from SQLite when the
+//! configured analyzers no longer match it.
+
+use serde::{Deserialize, Serialize};
+use tantivy::Index;
+use tantivy::schema::{FieldType, Schema};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, TextAnalyzer, TokenStream,
+};
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+
+/// Tokenizer name for lowercase-only text fields
+pub const PLAIN_TOKENIZER: &str = "hyphen_normalize";
+/// Tokenizer name for lowercase + English-stemmed text fields
+pub const STEMMED_TOKENIZER: &str = "hyphen_normalize_stem";
+
+/// Tokens longer than this are dropped (hashes, base64 blobs)
+const MAX_TOKEN_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldAnalyzer {
+    /// Split on non-alphanumerics and lowercase
+    #[default]
+    Plain,
+    /// `plain` followed by English (Snowball) stemming
+    Stemmed,
+}
+
+impl FieldAnalyzer {
+    pub fn tokenizer_name(self) -> &'static str {
+        match self {
+            Self::Plain => PLAIN_TOKENIZER,
+            Self::Stemmed => STEMMED_TOKENIZER,
+        }
+    }
+
+    pub fn from_tokenizer_name(name: &str) -> Option<Self> {
+        match name {
+            PLAIN_TOKENIZER => Some(Self::Plain),
+            STEMMED_TOKENIZER => Some(Self::Stemmed),
+            _ => None,
+        }
+    }
+
+    /// Normalize a single (already lowercased) query term the way this analyzer indexed text.
+    pub fn normalize_term(self, term: &str) -> String {
+        match self {
+            Self::Plain => term.to_string(),
+            Self::Stemmed => {
+                let mut analyzer = stemmed_analyzer();
+                let mut stream = analyzer.token_stream(term);
+                if stream.advance() {
+                    stream.token().text.clone()
+                } else {
+                    term.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// The `[analyzers]` section of `config.toml`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyzerConfig {
+    #[serde(default)]
+    pub title: FieldAnalyzer,
+    #[serde(default)]
+    pub content: FieldAnalyzer,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    analyzers: AnalyzerConfig,
+}
+
+impl AnalyzerConfig {
+    /// Load `[analyzers]` from the shared config file; missing or invalid means defaults.
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => config.analyzers,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring analyzer settings: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// The analyzers an existing index was built with, read from its schema.
+    pub fn of_schema(schema: &Schema) -> Option<Self> {
+        let analyzer = |name: &str| {
+            let field = schema.get_field(name).ok()?;
+            match schema.get_field_entry(field).field_type() {
+                FieldType::Str(opts) => {
+                    FieldAnalyzer::from_tokenizer_name(opts.get_indexing_options()?.tokenizer())
+                }
+                _ => None,
+            }
+        };
+        Some(Self {
+            title: analyzer("title")?,
+            content: analyzer("content")?,
+        })
+    }
+}
+
+fn plain_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(LowerCaser)
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .build()
+}
+
+fn stemmed_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(LowerCaser)
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .filter(Stemmer::new(Language::English))
+        .build()
+}
+
+/// Register every analyzer an index schema may reference.
+pub fn register(index: &Index) {
+    index
+        .tokenizers()
+        .register(PLAIN_TOKENIZER, plain_analyzer());
+    index
+        .tokenizers()
+        .register(STEMMED_TOKENIZER, stemmed_analyzer());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stemmed_normalizes_inflections_and_plain_does_not() {
+        assert_eq!(FieldAnalyzer::Stemmed.normalize_term("running"), "run");
+        assert_eq!(FieldAnalyzer::Stemmed.normalize_term("panics"), "panic");
+        assert_eq!(FieldAnalyzer::Plain.normalize_term("running"), "running");
+    }
+
+    #[test]
+    fn parses_analyzers_section() {
+        let file: ConfigFile = toml::from_str("[analyzers]\ncontent = \"stemmed\"\n").unwrap();
+        assert_eq!(file.analyzers.title, FieldAnalyzer::Plain);
+        assert_eq!(file.analyzers.content, FieldAnalyzer::Stemmed);
+        assert!(toml::from_str::<ConfigFile>("[analyzers]\ntitle = \"porter\"\n").is_err());
+    }
+}
@@ -14,6 +14,15 @@
 //! 5. **Low-signal filtering** - Remove "OK", "Done.", etc.
 //! 6. **Truncation** - Limit to MAX_EMBED_CHARS (2000)
 //!
+//! # Profiles
+//!
+//! A [`CanonProfile`] decides what survives before the pipeline runs. The
+//! default `full` profile keeps everything (with long code blocks collapsed);
+//! `strip-code` drops code blocks down to their `[code: lang]` marker,
+//! `strip-tool-output` drops `[Tool: ...]` markers and `[Tool Output]`
+//! sections, and `user-only` additionally restricts embedding to user turns
+//! (enforced by the backfill, since it depends on the message role).
+//!
 //! # Why This Matters
 //!
 //! Without proper canonicalization:
@@ -34,6 +43,7 @@
 //! ```
 
 use ring::digest::{self, SHA256};
+use serde::{Deserialize, Serialize};
 use unicode_normalization::UnicodeNormalization;
 
 /// Maximum characters to keep after canonicalization.
@@ -64,6 +74,32 @@ const LOW_SIGNAL_CONTENT: &[&str] = &[
     "thank you.",
 ];
 
+/// What to keep from a message before embedding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CanonProfile {
+    /// Everything, with long code blocks collapsed to head and tail
+    #[default]
+    Full,
+    /// Drop fenced code blocks, keeping only their `[code: lang]` marker
+    StripCode,
+    /// Drop tool-call markers and tool output sections
+    StripToolOutput,
+    /// Embed user messages only (tool output stripped)
+    UserOnly,
+}
+
+impl CanonProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CanonProfile::Full => "full",
+            CanonProfile::StripCode => "strip-code",
+            CanonProfile::StripToolOutput => "strip-tool-output",
+            CanonProfile::UserOnly => "user-only",
+        }
+    }
+}
+
 /// Canonicalize text for embedding.
 ///
 /// Applies the full preprocessing pipeline to produce clean, consistent text
@@ -78,11 +114,25 @@ const LOW_SIGNAL_CONTENT: &[&str] = &[
 ///
 /// Canonicalized text, suitable for embedding and hashing.
 pub fn canonicalize_for_embedding(text: &str) -> String {
+    canonicalize_with_profile(text, CanonProfile::Full)
+}
+
+/// Canonicalize text for embedding under a [`CanonProfile`].
+///
+/// Profile stripping happens before markdown stripping, so the output is as
+/// deterministic as [`canonicalize_for_embedding`] for a given profile.
+pub fn canonicalize_with_profile(text: &str, profile: CanonProfile) -> String {
     // Step 1: Unicode NFC normalization (CRITICAL for hash stability)
     let normalized: String = text.nfc().collect();
 
-    // Step 2: Strip markdown and collapse code blocks
-    let stripped = strip_markdown_and_code(&normalized);
+    // Step 2: Strip markdown and collapse (or drop) code blocks
+    let stripped = match profile {
+        CanonProfile::Full => strip_markdown_and_code(&normalized, true),
+        CanonProfile::StripCode => strip_markdown_and_code(&normalized, false),
+        CanonProfile::StripToolOutput | CanonProfile::UserOnly => {
+            strip_markdown_and_code(&strip_tool_output(&normalized), true)
+        }
+    };
 
     // Step 3: Normalize whitespace
     let whitespace_normalized = normalize_whitespace(&stripped);
@@ -122,8 +172,32 @@ fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
 
-/// Strip markdown formatting and collapse code blocks.
-fn strip_markdown_and_code(text: &str) -> String {
+/// Drop `[Tool: ...]` call markers and `[Tool Output]` sections.
+///
+/// Connectors join message parts with blank lines, so an output section ends
+/// at the next blank line.
+fn strip_tool_output(text: &str) -> String {
+    let mut kept = Vec::new();
+    let mut in_output = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if in_output {
+            if !trimmed.is_empty() {
+                continue;
+            }
+            in_output = false;
+        }
+        if trimmed.starts_with("[Tool Output]") {
+            in_output = true;
+        } else if !trimmed.starts_with("[Tool:") {
+            kept.push(line);
+        }
+    }
+    kept.join("\n")
+}
+
+/// Strip markdown formatting and collapse (or, without `keep_code`, drop) code blocks.
+fn strip_markdown_and_code(text: &str, keep_code: bool) -> String {
     let mut result = String::with_capacity(text.len());
     let mut in_code_block = false;
     let mut code_block_lang = String::new();
@@ -133,7 +207,7 @@ fn strip_markdown_and_code(text: &str) -> String {
         if line.starts_with("```") {
             if in_code_block {
                 // End of code block - collapse it
-                result.push_str(&collapse_code_block(&code_block_lang, &code_lines));
+                result.push_str(&code_block(&code_block_lang, &code_lines, keep_code));
                 result.push('\n');
                 code_lines.clear();
                 code_block_lang.clear();
@@ -157,20 +231,33 @@ fn strip_markdown_and_code(text: &str) -> String {
 
     // Handle unclosed code block
     if in_code_block && !code_lines.is_empty() {
-        result.push_str(&collapse_code_block(&code_block_lang, &code_lines));
+        result.push_str(&code_block(&code_block_lang, &code_lines, keep_code));
         result.push('\n');
     }
 
     result
 }
 
-/// Collapse a code block to first N + last M lines.
-fn collapse_code_block(lang: &str, lines: &[&str]) -> String {
-    let lang_label = if lang.is_empty() {
+/// Render a code block: collapsed when kept, just its label otherwise.
+fn code_block(lang: &str, lines: &[&str], keep_code: bool) -> String {
+    if keep_code {
+        collapse_code_block(lang, lines)
+    } else {
+        format!("[{}]", code_label(lang))
+    }
+}
+
+fn code_label(lang: &str) -> String {
+    if lang.is_empty() {
         "code".to_string()
     } else {
         format!("code: {lang}")
-    };
+    }
+}
+
+/// Collapse a code block to first N + last M lines.
+fn collapse_code_block(lang: &str, lines: &[&str]) -> String {
+    let lang_label = code_label(lang);
 
     if lines.len() <= CODE_HEAD_LINES + CODE_TAIL_LINES {
         // Short enough to keep in full
@@ -659,4 +746,25 @@ See [docs](http://docs.rs) for more.
         assert!(!canonical.starts_with('-'));
         assert!(!canonical.contains("\n-"));
     }
+
+    #[test]
+    fn test_profiles_strip_code_and_tool_output() {
+        let text = "Fix the parser\n```rust\nfn parse() {}\n```\n[Tool: Edit - src/lib.rs]\n\n[Tool Output]\nerror[E0308]: mismatched types\n  --> src/lib.rs:3\n\nThe build passes now.";
+
+        let full = canonicalize_with_profile(text, CanonProfile::Full);
+        assert_eq!(full, canonicalize_for_embedding(text));
+        assert!(full.contains("fn parse()"));
+        assert!(full.contains("mismatched types"));
+
+        let no_code = canonicalize_with_profile(text, CanonProfile::StripCode);
+        assert!(no_code.contains("[code: rust]"));
+        assert!(!no_code.contains("fn parse()"));
+        assert!(no_code.contains("mismatched types"));
+
+        let no_tools = canonicalize_with_profile(text, CanonProfile::StripToolOutput);
+        assert!(no_tools.contains("fn parse()"));
+        assert!(!no_tools.contains("Edit"));
+        assert!(!no_tools.contains("mismatched"));
+        assert!(no_tools.ends_with("The build passes now."));
+    }
 }
@@ -0,0 +1,279 @@
+//! Compare search matches between two time ranges.
+//!
+//! `cass search <q> --compare-period "-7d..now vs -14d..-7d"` runs the query
+//! once per range and groups the hits by session, so an ongoing issue can be
+//! followed week over week: sessions that match only in the current range are
+//! new, sessions that match in both are continuing, and sessions that matched
+//! only in the previous range have gone quiet.
+
+use std::collections::HashMap;
+
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::Serialize;
+
+use crate::indexer::retention::parse_age;
+use crate::search::query::SearchHit;
+
+/// Hits fetched per range unless `--limit` asks for more
+pub const COMPARE_HIT_LIMIT: usize = 1000;
+
+/// One side of a comparison; open bounds are `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimeRange {
+    /// The range as written, e.g. `-7d..now`
+    pub label: String,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComparePeriod {
+    pub current: TimeRange,
+    pub previous: TimeRange,
+}
+
+impl ComparePeriod {
+    /// Parse `<current> vs <previous>`, each range written `from..to`. Bounds are
+    /// `now`, a relative age (`-7d`, `-12h`, `-2w`, `-3mo`), a date (`YYYY-MM-DD`),
+    /// an RFC 3339 timestamp, or empty for an open end.
+    pub fn parse(spec: &str, now_ms: i64) -> Result<Self, String> {
+        let (current, previous) = spec
+            .split_once(" vs ")
+            .ok_or_else(|| format!("expected '<range> vs <range>', got '{spec}'"))?;
+        Ok(ComparePeriod {
+            current: parse_range(current, now_ms)?,
+            previous: parse_range(previous, now_ms)?,
+        })
+    }
+}
+
+fn parse_range(raw: &str, now_ms: i64) -> Result<TimeRange, String> {
+    let raw = raw.trim();
+    let (from, to) = raw
+        .split_once("..")
+        .ok_or_else(|| format!("expected 'from..to', got '{raw}'"))?;
+    let range = TimeRange {
+        label: raw.to_string(),
+        from: parse_bound(from, now_ms)?,
+        to: parse_bound(to, now_ms)?,
+    };
+    if let (Some(from), Some(to)) = (range.from, range.to)
+        && from >= to
+    {
+        return Err(format!("range '{raw}' ends before it starts"));
+    }
+    Ok(range)
+}
+
+fn parse_bound(raw: &str, now_ms: i64) -> Result<Option<i64>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    if raw.eq_ignore_ascii_case("now") {
+        return Ok(Some(now_ms));
+    }
+    if let Some(age) = parse_age(raw.strip_prefix('-').unwrap_or(raw)) {
+        return Ok(Some(now_ms - age));
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Ok(Some(dt.timestamp_millis()));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(|dt| Local.from_local_datetime(&dt).single())
+        .map(|dt| Some(dt.timestamp_millis()))
+        .ok_or_else(|| format!("unrecognized time '{raw}' (use now, -7d, or YYYY-MM-DD)"))
+}
+
+/// A session's matches within one range
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMatch {
+    pub source_id: String,
+    pub source_path: String,
+    pub agent: String,
+    pub workspace: String,
+    pub title: String,
+    /// Matching messages in the range
+    pub hits: usize,
+    pub best_score: f32,
+    /// Newest matching message (ms since epoch)
+    pub last_match_at: Option<i64>,
+    /// Snippet of the best-scoring match
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodComparison {
+    pub current: TimeRange,
+    pub previous: TimeRange,
+    /// Sessions matching only in the current range
+    pub new: Vec<SessionMatch>,
+    /// Sessions matching in both ranges (current-range figures)
+    pub continuing: Vec<SessionMatch>,
+    /// Sessions matching only in the previous range
+    pub previous_only: Vec<SessionMatch>,
+    /// Hits fetched per range
+    pub hit_limit: usize,
+    /// Ranges (`current`, `previous`) whose search reached `hit_limit`; their
+    /// sessions and hit counts may be incomplete
+    pub truncated: Vec<&'static str>,
+}
+
+/// Group each range's hits by session and split them into new, continuing and
+/// previous-only. Each range's search was capped at `hit_limit` hits.
+pub fn compare_hits(
+    period: &ComparePeriod,
+    current: &[SearchHit],
+    previous: &[SearchHit],
+    hit_limit: usize,
+) -> PeriodComparison {
+    let truncated = [("current", current), ("previous", previous)]
+        .into_iter()
+        .filter(|(_, hits)| hits.len() >= hit_limit)
+        .map(|(name, _)| name)
+        .collect();
+    let current = group_by_session(current);
+    let mut previous = group_by_session(previous);
+    let mut comparison = PeriodComparison {
+        current: period.current.clone(),
+        previous: period.previous.clone(),
+        new: Vec::new(),
+        continuing: Vec::new(),
+        previous_only: Vec::new(),
+        hit_limit,
+        truncated,
+    };
+    for (key, session) in current {
+        if previous.remove(&key).is_some() {
+            comparison.continuing.push(session);
+        } else {
+            comparison.new.push(session);
+        }
+    }
+    comparison.previous_only = previous.into_values().collect();
+    for list in [
+        &mut comparison.new,
+        &mut comparison.continuing,
+        &mut comparison.previous_only,
+    ] {
+        list.sort_by(|a, b| {
+            b.last_match_at
+                .cmp(&a.last_match_at)
+                .then_with(|| a.source_path.cmp(&b.source_path))
+        });
+    }
+    comparison
+}
+
+fn group_by_session(hits: &[SearchHit]) -> HashMap<(String, String), SessionMatch> {
+    let mut sessions: HashMap<(String, String), SessionMatch> = HashMap::new();
+    for hit in hits {
+        let key = (hit.source_id.clone(), hit.source_path.clone());
+        let session = sessions.entry(key).or_insert_with(|| SessionMatch {
+            source_id: hit.source_id.clone(),
+            source_path: hit.source_path.clone(),
+            agent: hit.agent.clone(),
+            workspace: hit.workspace.clone(),
+            title: hit.title.clone(),
+            hits: 0,
+            best_score: f32::MIN,
+            last_match_at: None,
+            snippet: String::new(),
+        });
+        session.hits += 1;
+        if hit.score > session.best_score {
+            session.best_score = hit.score;
+            session.snippet = hit.snippet.clone();
+        }
+        session.last_match_at = session.last_match_at.max(hit.created_at);
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+    const NOW: i64 = 100 * DAY;
+
+    fn hit(path: &str, created_at: i64, score: f32) -> SearchHit {
+        SearchHit {
+            title: format!("title {path}"),
+            snippet: format!("snippet {score}"),
+            content: String::new(),
+            score,
+            source_path: path.to_string(),
+            agent: "codex".to_string(),
+            workspace: "/ws".to_string(),
+            workspace_original: None,
+            created_at: Some(created_at),
+            line_number: None,
+            match_type: Default::default(),
+            source_id: "local".to_string(),
+            origin_kind: "local".to_string(),
+            origin_host: None,
+        }
+    }
+
+    #[test]
+    fn parses_relative_and_open_ranges() {
+        let period = ComparePeriod::parse("-7d..now vs -14d..-7d", NOW).unwrap();
+        assert_eq!(period.current.from, Some(NOW - 7 * DAY));
+        assert_eq!(period.current.to, Some(NOW));
+        assert_eq!(period.previous.from, Some(NOW - 14 * DAY));
+        assert_eq!(period.previous.to, Some(NOW - 7 * DAY));
+        assert_eq!(period.previous.label, "-14d..-7d");
+
+        let open = ComparePeriod::parse("2w.. vs ..2w", NOW).unwrap();
+        assert_eq!(
+            (open.current.from, open.current.to),
+            (Some(NOW - 14 * DAY), None)
+        );
+        assert_eq!(open.previous.from, None);
+
+        assert!(ComparePeriod::parse("-7d..now", NOW).is_err());
+        assert!(ComparePeriod::parse("now..-7d vs -14d..-7d", NOW).is_err());
+        assert!(ComparePeriod::parse("-7d..later vs -14d..-7d", NOW).is_err());
+    }
+
+    #[test]
+    fn splits_sessions_into_new_continuing_and_previous_only() {
+        let period = ComparePeriod::parse("-7d..now vs -14d..-7d", NOW).unwrap();
+        let current = vec![
+            hit("/a.jsonl", NOW - DAY, 1.0),
+            hit("/a.jsonl", NOW - 2 * DAY, 3.0),
+            hit("/b.jsonl", NOW - 3 * DAY, 2.0),
+        ];
+        let previous = vec![
+            hit("/b.jsonl", NOW - 9 * DAY, 1.0),
+            hit("/c.jsonl", NOW - 10 * DAY, 1.0),
+        ];
+        let cmp = compare_hits(&period, &current, &previous, COMPARE_HIT_LIMIT);
+
+        assert!(cmp.truncated.is_empty());
+        assert_eq!(cmp.new.len(), 1);
+        assert_eq!(cmp.new[0].source_path, "/a.jsonl");
+        assert_eq!(cmp.new[0].hits, 2);
+        assert_eq!(cmp.new[0].snippet, "snippet 3");
+        assert_eq!(cmp.new[0].last_match_at, Some(NOW - DAY));
+        assert_eq!(cmp.continuing[0].source_path, "/b.jsonl");
+        assert_eq!(cmp.continuing[0].last_match_at, Some(NOW - 3 * DAY));
+        assert_eq!(cmp.previous_only[0].source_path, "/c.jsonl");
+    }
+
+    #[test]
+    fn reports_ranges_that_reached_the_hit_limit() {
+        let period = ComparePeriod::parse("-7d..now vs -14d..-7d", NOW).unwrap();
+        let current = vec![
+            hit("/a.jsonl", NOW - DAY, 1.0),
+            hit("/b.jsonl", NOW - DAY, 1.0),
+        ];
+        let previous = vec![hit("/c.jsonl", NOW - 9 * DAY, 1.0)];
+        let cmp = compare_hits(&period, &current, &previous, 2);
+        assert_eq!(cmp.hit_limit, 2);
+        assert_eq!(cmp.truncated, ["current"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Dependency mentions, for `dep:` searches.
+//!
+//! Package names are pulled from install commands (`cargo add`, `npm install`,
+//! `pip install`, `go get`, ...) and from `Cargo.toml` / `package.json`
+//! dependency sections that appear in a message (file contents, edits or
+//! diffs). `cass search "dep:tokio why"` then finds the messages where an agent
+//! added or upgraded tokio, next to the reasoning around it.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `cargo add tokio serde@1`, `npm i -D vitest`, `pip install "requests>=2"`, ...
+static INSTALL_COMMAND: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?m)(?:^[ \t]*(?:[$>][ \t]*)?|[;&|`(][ \t]*)(?:sudo[ \t]+)?(?:cargo add|npm (?:install|i|add)|yarn add|pnpm (?:add|install|i)|bun add|pip3? install|python3? -m pip install|uv (?:add|pip install)|poetry add|go get|gem install)((?:[ \t]+[^\s;&|`)]+)+)",
+    )
+    .unwrap()
+});
+/// `[dependencies]`, `[dev-dependencies]`, `[workspace.dependencies]`, `[target.'cfg(..)'.dependencies]`
+static CARGO_SECTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[(?:[^\]]*\.)?(?:dev-|build-)?dependencies\]$").unwrap());
+/// `tokio = "1"` / `tokio = { version = "1", features = [..] }` / `[dependencies.tokio]`
+static CARGO_ENTRY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^([A-Za-z0-9_-]+)(?:\.workspace\s*=|\s*=\s*["{])"#).unwrap());
+static CARGO_TABLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[(?:dev-|build-)?dependencies\.([A-Za-z0-9_-]+)\]$").unwrap());
+/// `"dependencies": {` and its `"name": "^1.2.3"` entries
+static NPM_SECTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#""(?:dependencies|devDependencies|peerDependencies|optionalDependencies)"\s*:\s*\{"#,
+    )
+    .unwrap()
+});
+static NPM_ENTRY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^"((?:@[\w.-]+/)?[\w.-]+)"\s*:\s*""#).unwrap());
+
+/// Install-command flags whose value is not a package name
+const VALUE_FLAGS: &[&str] = &[
+    "--features",
+    "-F",
+    "--path",
+    "--git",
+    "--branch",
+    "--tag",
+    "--rev",
+    "--registry",
+    "--rename",
+    "--package",
+    "-p",
+    "--target",
+    "-r",
+    "--requirement",
+    "-c",
+    "--constraint",
+    "-i",
+    "--index-url",
+    "--extra-index-url",
+    "--group",
+    "-G",
+];
+
+/// Normalized dependency names mentioned in `text`, deduplicated, in order.
+pub fn extract_dependencies(text: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut push = |raw: &str| {
+        if let Some(name) = normalize(raw)
+            && !out.contains(&name)
+        {
+            out.push(name);
+        }
+    };
+
+    for caps in INSTALL_COMMAND.captures_iter(text) {
+        let mut skip_value = false;
+        for arg in caps[1].split_whitespace() {
+            let arg = arg.trim_matches(|c| c == '"' || c == '\'');
+            if std::mem::take(&mut skip_value) {
+                continue;
+            }
+            if VALUE_FLAGS.contains(&arg) {
+                skip_value = true;
+                continue;
+            }
+            // Flags, paths, URLs and requirement files aren't package names
+            if arg.starts_with('-')
+                || arg.starts_with('.')
+                || arg.starts_with('/')
+                || arg.contains("://")
+                || arg.ends_with(".txt")
+            {
+                continue;
+            }
+            push(strip_version(arg));
+        }
+    }
+
+    let mut in_cargo = false;
+    let mut in_npm = false;
+    for line in text.lines() {
+        // Diff and edit markers: `+tokio = "1.38"`
+        let line = line
+            .trim()
+            .trim_start_matches(['+', '-'])
+            .trim_start_matches(' ');
+        if line.starts_with('[') {
+            in_cargo = CARGO_SECTION.is_match(line);
+            if let Some(caps) = CARGO_TABLE.captures(line) {
+                push(&caps[1]);
+            }
+            continue;
+        }
+        if NPM_SECTION.is_match(line) {
+            in_npm = true;
+            continue;
+        }
+        if in_npm {
+            if line.starts_with('}') {
+                in_npm = false;
+            } else if let Some(caps) = NPM_ENTRY.captures(line) {
+                push(&caps[1]);
+            }
+        }
+        if in_cargo && let Some(caps) = CARGO_ENTRY.captures(line) {
+            push(&caps[1]);
+        }
+    }
+    out
+}
+
+/// `serde@1.0` / `requests==2.31` / `lodash@^4` / `@types/node@20` -> bare name.
+fn strip_version(arg: &str) -> &str {
+    let scoped = arg.starts_with('@');
+    let search_from = usize::from(scoped);
+    let cut = arg[search_from..]
+        .find(['@', '=', '<', '>', '~', '!', '[', ';'])
+        .map_or(arg.len(), |i| i + search_from);
+    &arg[..cut]
+}
+
+/// Lowercase; `_` and `-` are equivalent for crates and Python packages.
+pub fn normalize(raw: &str) -> Option<String> {
+    let name = raw.trim().trim_end_matches(',');
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@'))
+        && name.chars().any(|c| c.is_ascii_alphabetic());
+    valid.then(|| name.to_ascii_lowercase().replace('_', "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_install_commands() {
+        assert_eq!(
+            extract_dependencies("Run `cargo add tokio serde@1.0 --features derive` first."),
+            vec!["tokio", "serde"]
+        );
+        assert_eq!(
+            extract_dependencies(
+                "$ npm install -D @types/node@20 lodash\n$ pip install \"requests>=2\" -r requirements.txt"
+            ),
+            vec!["@types/node", "lodash", "requests"]
+        );
+        assert_eq!(
+            extract_dependencies("go get github.com/spf13/cobra@v1.8.0"),
+            vec!["github.com/spf13/cobra"]
+        );
+        assert!(extract_dependencies("I will install the update later").is_empty());
+    }
+
+    #[test]
+    fn extracts_manifest_sections_and_diffs() {
+        let cargo = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[dependencies]\n-tokio = \"1.37\"\n+tokio = { version = \"1.38\", features = [\"full\"] }\nserde_json = \"1\"\n\n[dependencies.reqwest]\nversion = \"0.12\"";
+        assert_eq!(
+            extract_dependencies(cargo),
+            vec!["tokio", "serde-json", "reqwest"]
+        );
+        let npm = "{\n  \"name\": \"web\",\n  \"devDependencies\": {\n    \"vitest\": \"^1.6.0\",\n    \"@vue/test-utils\": \"2\"\n  },\n  \"scripts\": { \"test\": \"vitest\" }\n}";
+        assert_eq!(extract_dependencies(npm), vec!["vitest", "@vue/test-utils"]);
+    }
+}
@@ -0,0 +1,424 @@
+//! Embedding backfill for the semantic vector index (`cass embed --backfill`).
+//!
+//! Messages are walked in id order. A message keeps its existing vector when the
+//! index was built by the same embedder and its canonical content hash is
+//! unchanged; everything else (new messages, edited messages, or an index built
+//! by an older embedder) is embedded in batches. Rows for deleted messages are
+//! dropped. Each page of messages is streamed through
+//! [`embed_stream`](crate::search::embedder::embed_stream), so batches are
+//! sized by the embedder's text and token budget and only one batch of
+//! canonicalized text is held at a time.
+//!
+//! Text is canonicalized under the configured [`CanonProfile`] (`--canon-profile`
+//! or `[embedding] profile` in `config.toml`). The profile is recorded in the
+//! index header's revision, so switching profiles re-embeds every message
+//! rather than mixing vectors of differently stripped text.
+//!
+//! Large corpora are checkpointed: the index is saved every
+//! `checkpoint_every` newly embedded messages, together with the untouched
+//! rows that have not been walked yet. An interrupted run therefore loses at
+//! most one checkpoint of work, and the next `--backfill` resumes by skipping
+//! the messages that already have an up-to-date vector.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::hooks::HooksConfig;
+use crate::search::canonicalize::{CanonProfile, canonicalize_with_profile, content_hash};
+use crate::search::embedder::{Embedder, embed_stream};
+use crate::search::vector_index::{
+    Quantization, ROLE_ASSISTANT, ROLE_USER, VectorEntry, VectorIndex, role_code_from_str,
+    source_id_hash,
+};
+
+/// Bumped when canonicalization changes in a way that invalidates stored vectors
+pub const EMBED_REVISION: &str = "canon-v1";
+
+/// Index revision for vectors canonicalized under `profile`; `full` keeps the bare revision.
+pub fn embed_revision(profile: CanonProfile) -> String {
+    match profile {
+        CanonProfile::Full => EMBED_REVISION.to_string(),
+        other => format!("{EMBED_REVISION}+{}", other.as_str()),
+    }
+}
+
+/// The `[embedding]` section of `config.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmbeddingConfig {
+    #[serde(default)]
+    pub profile: CanonProfile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    embedding: EmbeddingConfig,
+}
+
+impl EmbeddingConfig {
+    /// Load the section from the shared config file, treating a missing or invalid file as defaults.
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => config.embedding,
+            Err(e) => {
+                warn!(path = %path.display(), "using default embedding profile: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Messages read from SQLite per page
+const PAGE_SIZE: i64 = 2_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BackfillOptions {
+    /// Re-embed everything instead of keeping up-to-date vectors
+    pub full: bool,
+    /// Upper bound on texts per batch; the embedder's token budget may split batches further
+    pub batch_size: usize,
+    /// Save the index after this many newly embedded messages (0 = only at the end)
+    pub checkpoint_every: usize,
+    /// What to keep from each message before embedding
+    pub profile: CanonProfile,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self {
+            full: false,
+            batch_size: 64,
+            checkpoint_every: 10_000,
+            profile: CanonProfile::Full,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackfillReport {
+    pub embedder_id: String,
+    pub profile: CanonProfile,
+    pub messages_scanned: usize,
+    pub embedded: usize,
+    /// Messages whose stored vector was still current
+    pub reused: usize,
+    /// Stored vectors dropped because their message no longer exists
+    pub dropped: usize,
+    /// Messages with no embeddable text after canonicalization
+    pub skipped_empty: usize,
+    pub checkpoints: usize,
+    pub total_vectors: usize,
+}
+
+/// Embed messages that lack a current vector and write the updated index to `index_path`.
+pub fn backfill_embeddings(
+    conn: &Connection,
+    embedder: &dyn Embedder,
+    index_path: &Path,
+    opts: &BackfillOptions,
+) -> Result<BackfillReport> {
+    if opts.batch_size == 0 {
+        bail!("batch size must be at least 1");
+    }
+    let mut report = BackfillReport {
+        embedder_id: embedder.id().to_string(),
+        profile: opts.profile,
+        ..Default::default()
+    };
+    let revision = embed_revision(opts.profile);
+
+    // Existing vectors by message id; entries are removed as the walk reaches them
+    let mut existing: BTreeMap<u64, VectorEntry> = BTreeMap::new();
+    if !opts.full && index_path.is_file() {
+        let index = VectorIndex::load(index_path)
+            .with_context(|| format!("load vector index {}", index_path.display()))?;
+        let header = index.header();
+        let current = header.embedder_id == embedder.id()
+            && header.embedder_revision == revision
+            && header.dimension as usize == embedder.dimension();
+        if current {
+            for row in index.rows().iter().filter(|r| r.chunk_idx == 0) {
+                existing.insert(
+                    row.message_id,
+                    VectorEntry {
+                        message_id: row.message_id,
+                        created_at_ms: row.created_at_ms,
+                        agent_id: row.agent_id,
+                        workspace_id: row.workspace_id,
+                        source_id: row.source_id,
+                        role: row.role,
+                        chunk_idx: row.chunk_idx,
+                        content_hash: row.content_hash,
+                        vector: index.vector_at_f32(row)?,
+                    },
+                );
+            }
+        } else {
+            info!(
+                old = %header.embedder_id,
+                old_revision = %header.embedder_revision,
+                new = embedder.id(),
+                new_revision = %revision,
+                "vector index was built by another embedder or profile; re-embedding all messages"
+            );
+        }
+    }
+
+    let hints = embedder.batch_hints().with_max_texts(opts.batch_size);
+    let mut done: Vec<VectorEntry> = Vec::new();
+    let mut since_checkpoint = 0usize;
+    let mut cursor: i64 = 0;
+
+    let mut stmt = conn.prepare(
+        "SELECT m.id, COALESCE(m.created_at, c.started_at, 0), c.agent_id,
+                COALESCE(c.workspace_id, 0), c.source_id, m.role, cass_text(m.content)
+         FROM messages m
+         JOIN conversations c ON m.conversation_id = c.id
+         WHERE m.id > ?1
+         ORDER BY m.id
+         LIMIT ?2",
+    )?;
+    loop {
+        let rows = stmt
+            .query_map(rusqlite::params![cursor, PAGE_SIZE], |r| {
+                Ok((
+                    r.get::<_, i64>(0)?,
+                    r.get::<_, i64>(1)?,
+                    r.get::<_, i64>(2)?,
+                    r.get::<_, i64>(3)?,
+                    r.get::<_, String>(4)?,
+                    r.get::<_, String>(5)?,
+                    r.get::<_, String>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        cursor = last.0;
+
+        // Canonicalization and inference are pipelined batch by batch through the
+        // embed stream; reused vectors are set aside while the page is walked
+        let mut reused = Vec::new();
+        let to_embed = rows.into_iter().filter_map(
+            |(id, created_at, agent_id, workspace_id, source_id, role, content)| {
+                let message_id = id as u64;
+                // Semantic search only covers user and assistant turns
+                let role = role_code_from_str(&role)
+                    .filter(|r| matches!(*r, ROLE_USER | ROLE_ASSISTANT))
+                    .filter(|r| opts.profile != CanonProfile::UserOnly || *r == ROLE_USER)?;
+                report.messages_scanned += 1;
+                let previous = existing.remove(&message_id);
+                let text = canonicalize_with_profile(&content, opts.profile);
+                if text.is_empty() {
+                    report.skipped_empty += 1;
+                    return None;
+                }
+                let hash = content_hash(&text);
+                if let Some(prev) = previous
+                    && prev.content_hash == hash
+                {
+                    report.reused += 1;
+                    reused.push(prev);
+                    return None;
+                }
+                let entry = VectorEntry {
+                    message_id,
+                    created_at_ms: created_at,
+                    agent_id: u32::try_from(agent_id).unwrap_or_default(),
+                    workspace_id: u32::try_from(workspace_id).unwrap_or_default(),
+                    source_id: source_id_hash(&source_id),
+                    role,
+                    chunk_idx: 0,
+                    content_hash: hash,
+                    vector: Vec::new(),
+                };
+                Some((entry, text))
+            },
+        );
+        for batch in embed_stream(embedder, to_embed, hints) {
+            let batch = batch?;
+            since_checkpoint += batch.len();
+            done.extend(
+                batch
+                    .into_iter()
+                    .map(|(entry, vector)| VectorEntry { vector, ..entry }),
+            );
+        }
+        done.append(&mut reused);
+
+        // Messages walked past without a row were deleted
+        let stale: Vec<u64> = existing
+            .range(..=cursor as u64)
+            .map(|(id, _)| *id)
+            .collect();
+        report.dropped += stale.len();
+        for id in stale {
+            existing.remove(&id);
+        }
+
+        if opts.checkpoint_every > 0 && since_checkpoint >= opts.checkpoint_every {
+            report.embedded += since_checkpoint;
+            since_checkpoint = 0;
+            let snapshot = done.iter().chain(existing.values()).cloned();
+            save_index(embedder, &revision, index_path, snapshot)?;
+            report.checkpoints += 1;
+            info!(
+                embedded = report.embedded,
+                cursor, "embedding backfill checkpoint saved"
+            );
+        }
+    }
+    report.embedded += since_checkpoint;
+    // Anything left has an id beyond the last message
+    report.dropped += existing.len();
+
+    report.total_vectors = done.len();
+    save_index(embedder, &revision, index_path, done)?;
+    Ok(report)
+}
+
+fn save_index(
+    embedder: &dyn Embedder,
+    revision: &str,
+    index_path: &Path,
+    entries: impl IntoIterator<Item = VectorEntry>,
+) -> Result<()> {
+    if let Some(parent) = index_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut entries: Vec<VectorEntry> = entries.into_iter().collect();
+    entries.sort_by_key(|e| (e.message_id, e.chunk_idx));
+    VectorIndex::build(
+        embedder.id(),
+        revision,
+        embedder.dimension(),
+        Quantization::F16,
+        entries,
+    )?
+    .save(index_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::hash_embedder::HashEmbedder;
+    use crate::storage::sqlite::SqliteStorage;
+    use tempfile::tempdir;
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO agents(id, slug, name, kind, created_at, updated_at)
+                VALUES (1, 'codex', 'Codex', 'cli', 0, 0);
+             INSERT INTO conversations(id, agent_id, source_id, source_path, started_at)
+                VALUES (1, 1, 'local', '/logs/a.jsonl', 1000);
+             INSERT INTO messages(id, conversation_id, idx, role, created_at, content) VALUES
+                (1, 1, 0, 'user', 1000, 'How do I configure the retry policy?'),
+                (2, 1, 1, 'agent', 2000, 'Set max_retries in the client builder.'),
+                (3, 1, 2, 'tool', 3000, 'cargo build output'),
+                (4, 1, 3, 'agent', 4000, '');",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn backfill_embeds_missing_and_reuses_current_vectors() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let conn = storage.raw();
+        seed(conn);
+        let embedder = HashEmbedder::new(16);
+        let index_path = dir.path().join("vector_index/index-fnv1a-16.cvvi");
+        let opts = BackfillOptions {
+            batch_size: 1,
+            checkpoint_every: 1,
+            ..Default::default()
+        };
+
+        let first = backfill_embeddings(conn, &embedder, &index_path, &opts).unwrap();
+        assert_eq!(first.embedded, 2);
+        assert_eq!(first.skipped_empty, 1);
+        assert_eq!(first.total_vectors, 2);
+
+        // Edit one message, delete the other, add a new one
+        conn.execute_batch(
+            "UPDATE messages SET content = 'Use exponential backoff instead.' WHERE id = 2;
+             DELETE FROM messages WHERE id = 1;
+             INSERT INTO messages(id, conversation_id, idx, role, created_at, content)
+                VALUES (5, 1, 4, 'user', 5000, 'Thanks, that works.');",
+        )
+        .unwrap();
+        let second = backfill_embeddings(conn, &embedder, &index_path, &opts).unwrap();
+        assert_eq!(second.embedded, 2);
+        assert_eq!(second.reused, 0);
+        assert_eq!(second.dropped, 1);
+
+        let third = backfill_embeddings(conn, &embedder, &index_path, &opts).unwrap();
+        assert_eq!((third.embedded, third.reused), (0, 2));
+        let index = VectorIndex::load(&index_path).unwrap();
+        let ids: Vec<u64> = index.rows().iter().map(|r| r.message_id).collect();
+        assert_eq!(ids, vec![2, 5]);
+        assert_eq!(index.header().embedder_id, "fnv1a-16");
+    }
+
+    #[test]
+    fn other_embedder_index_is_rebuilt() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let conn = storage.raw();
+        seed(conn);
+        let index_path = dir.path().join("index.cvvi");
+        let opts = BackfillOptions::default();
+        backfill_embeddings(conn, &HashEmbedder::new(8), &index_path, &opts).unwrap();
+
+        let report = backfill_embeddings(conn, &HashEmbedder::new(16), &index_path, &opts).unwrap();
+        assert_eq!((report.embedded, report.reused), (2, 0));
+        assert_eq!(
+            VectorIndex::load(&index_path).unwrap().header().dimension,
+            16
+        );
+    }
+
+    #[test]
+    fn profile_is_recorded_and_switching_it_re_embeds() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let conn = storage.raw();
+        seed(conn);
+        let embedder = HashEmbedder::new(16);
+        let index_path = dir.path().join("index.cvvi");
+        backfill_embeddings(conn, &embedder, &index_path, &BackfillOptions::default()).unwrap();
+        assert_eq!(
+            VectorIndex::load(&index_path)
+                .unwrap()
+                .header()
+                .embedder_revision,
+            EMBED_REVISION
+        );
+
+        let user_only = BackfillOptions {
+            profile: CanonProfile::UserOnly,
+            ..Default::default()
+        };
+        let report = backfill_embeddings(conn, &embedder, &index_path, &user_only).unwrap();
+        assert_eq!((report.embedded, report.reused), (1, 0));
+        let index = VectorIndex::load(&index_path).unwrap();
+        assert_eq!(index.header().embedder_revision, "canon-v1+user-only");
+        let ids: Vec<u64> = index.rows().iter().map(|r| r.message_id).collect();
+        assert_eq!(ids, vec![1]);
+
+        let again = backfill_embeddings(conn, &embedder, &index_path, &user_only).unwrap();
+        assert_eq!((again.embedded, again.reused), (0, 1));
+    }
+}
@@ -13,6 +13,14 @@
 //! - **ML embedder**: Uses FastEmbed with the MiniLM model for semantic embeddings.
 //!   Requires model download with user consent.
 //!
+//! # Batching
+//!
+//! Large backfills go through [`embed_stream`], which pulls `(key, text)` items
+//! lazily and groups them into batches bounded by the embedder's
+//! [`BatchHints`] (text count and an estimated token budget), so texts are only
+//! materialized one batch at a time and vectors can be written as batches
+//! complete.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -57,6 +65,40 @@ impl std::error::Error for EmbedderError {}
 /// Result type for embedder operations.
 pub type EmbedderResult<T> = Result<T, EmbedderError>;
 
+/// Sizing hints for batched embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchHints {
+    /// Most texts a single [`Embedder::embed_batch`] call should receive.
+    pub max_batch_texts: usize,
+    /// Token budget per batch, estimated with [`estimate_tokens`]. ML embedders pad
+    /// every text to the longest one in the batch, so this bounds inference memory.
+    pub max_batch_tokens: usize,
+}
+
+impl Default for BatchHints {
+    fn default() -> Self {
+        Self {
+            max_batch_texts: 64,
+            max_batch_tokens: 16_384,
+        }
+    }
+}
+
+impl BatchHints {
+    /// Cap the text count (e.g. from `--batch-size`), keeping the token budget.
+    pub fn with_max_texts(self, max_batch_texts: usize) -> Self {
+        Self {
+            max_batch_texts: self.max_batch_texts.min(max_batch_texts).max(1),
+            ..self
+        }
+    }
+}
+
+/// Rough token count used for batching decisions (about 4 bytes per token).
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4).max(1)
+}
+
 /// Trait for text embedding implementations.
 ///
 /// All embedders must produce fixed-dimension vectors suitable for similarity search.
@@ -119,6 +161,14 @@ pub trait Embedder: Send + Sync {
         texts.iter().map(|t| self.embed(t)).collect()
     }
 
+    /// How large the batches passed to [`embed_batch()`](Self::embed_batch) should be.
+    ///
+    /// Used by [`embed_stream`] to size batches. The default suits small ML models;
+    /// cheap embedders can raise the limits, large models should lower them.
+    fn batch_hints(&self) -> BatchHints {
+        BatchHints::default()
+    }
+
     /// The output dimension of this embedder.
     ///
     /// All embeddings produced by this embedder will have exactly this many components.
@@ -150,6 +200,74 @@ pub trait Embedder: Send + Sync {
     fn is_semantic(&self) -> bool;
 }
 
+/// Iterator returned by [`embed_stream`].
+pub struct EmbedStream<'e, K, I: Iterator<Item = (K, String)>> {
+    embedder: &'e dyn Embedder,
+    items: std::iter::Peekable<I>,
+    hints: BatchHints,
+}
+
+/// Embed `(key, text)` items lazily, one hint-sized batch per iteration.
+///
+/// Each item yields a batch of `(key, vector)` pairs in input order. Items are
+/// only pulled from `items` when the next batch is assembled, so callers can feed
+/// it straight from a database cursor. A single text larger than the token budget
+/// forms a batch of its own.
+pub fn embed_stream<'e, K, I>(
+    embedder: &'e dyn Embedder,
+    items: I,
+    hints: BatchHints,
+) -> EmbedStream<'e, K, I::IntoIter>
+where
+    I: IntoIterator<Item = (K, String)>,
+{
+    EmbedStream {
+        embedder,
+        items: items.into_iter().peekable(),
+        hints,
+    }
+}
+
+impl<K, I: Iterator<Item = (K, String)>> Iterator for EmbedStream<'_, K, I> {
+    type Item = EmbedderResult<Vec<(K, Vec<f32>)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut keys = Vec::new();
+        let mut texts = Vec::new();
+        let mut tokens = 0usize;
+        while let Some((_, text)) = self.items.peek() {
+            let cost = estimate_tokens(text);
+            if !texts.is_empty()
+                && (texts.len() >= self.hints.max_batch_texts
+                    || tokens.saturating_add(cost) > self.hints.max_batch_tokens)
+            {
+                break;
+            }
+            let (key, text) = self.items.next()?;
+            tokens += cost;
+            keys.push(key);
+            texts.push(text);
+        }
+        if texts.is_empty() {
+            return None;
+        }
+
+        let refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let vectors = match self.embedder.embed_batch(&refs) {
+            Ok(vectors) => vectors,
+            Err(e) => return Some(Err(e)),
+        };
+        if vectors.len() != keys.len() {
+            return Some(Err(EmbedderError::EmbeddingFailed(format!(
+                "embedder returned {} vectors for {} inputs",
+                vectors.len(),
+                keys.len()
+            ))));
+        }
+        Some(Ok(keys.into_iter().zip(vectors).collect()))
+    }
+}
+
 /// Metadata about an embedder for display and logging.
 #[derive(Debug, Clone)]
 pub struct EmbedderInfo {
@@ -260,6 +378,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn embed_stream_respects_text_and_token_budgets() {
+        let embedder = MockEmbedder {
+            dimension: 4,
+            is_semantic: false,
+        };
+        let hints = BatchHints {
+            max_batch_texts: 3,
+            max_batch_tokens: 10,
+        };
+        // 4 short texts (1 token each), one oversized text (25 tokens), one short
+        let long = "x".repeat(100);
+        let items = ["a", "b", "c", "d", long.as_str(), "e"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| (i, t.to_string()));
+        let batches: Vec<Vec<usize>> = embed_stream(&embedder, items, hints)
+            .map(|batch| batch.unwrap().into_iter().map(|(k, _)| k).collect())
+            .collect();
+        assert_eq!(batches, vec![vec![0, 1, 2], vec![3], vec![4], vec![5]]);
+
+        assert_eq!(hints.with_max_texts(0).max_batch_texts, 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
     #[test]
     fn test_embedder_empty_input_error() {
         let embedder = MockEmbedder {
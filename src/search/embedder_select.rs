@@ -0,0 +1,320 @@
+//! Automatic embedder selection.
+//!
+//! Semantic search can run on the MiniLM model or on the FNV-1a hash embedder.
+//! [`probe_resources`] looks at what this machine offers (model files, CPU
+//! features, memory) and [`select_embedder`] picks the best embedder that will
+//! actually work, with the reasons recorded so `cass doctor --embedder` can
+//! explain the decision. `CASS_SEMANTIC_EMBEDDER=hash|minilm` overrides the
+//! heuristics; a `minilm` override still falls back to hash when the model
+//! files are missing, since the model cannot load without them.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::search::embedder::Embedder;
+use crate::search::fastembed_embedder::{FastEmbedder, compiled_gpu_providers};
+use crate::search::hash_embedder::HashEmbedder;
+
+/// Environment override for the embedder choice
+pub const EMBEDDER_ENV: &str = "CASS_SEMANTIC_EMBEDDER";
+
+/// Available memory below which MiniLM inference is not attempted
+pub const MIN_ML_MEMORY_BYTES: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbedderChoice {
+    Minilm,
+    Hash,
+}
+
+impl EmbedderChoice {
+    /// Vector index id for this choice (matches `vector_index_path` naming)
+    pub fn embedder_id(self) -> String {
+        match self {
+            EmbedderChoice::Minilm => FastEmbedder::embedder_id_static().to_string(),
+            EmbedderChoice::Hash => HashEmbedder::default_dimension().id().to_string(),
+        }
+    }
+}
+
+/// What this machine offers for embedding
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResourceProbe {
+    pub model_dir: PathBuf,
+    /// Required MiniLM files not present in `model_dir`
+    pub missing_model_files: Vec<String>,
+    pub arch: String,
+    /// SIMD features that make ONNX inference fast enough
+    pub cpu_features: Vec<String>,
+    /// False on x86_64 without AVX2, where inference is impractically slow
+    pub cpu_suitable: bool,
+    /// GPU execution providers compiled into this build (`gpu-*` features)
+    pub gpu_providers: Vec<String>,
+    pub total_memory_bytes: Option<u64>,
+    pub available_memory_bytes: Option<u64>,
+    /// Value of `CASS_SEMANTIC_EMBEDDER`, if set
+    pub env_override: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbedderSelection {
+    pub choice: EmbedderChoice,
+    pub embedder_id: String,
+    /// Why this embedder was chosen, in the order the checks ran
+    pub reasons: Vec<String>,
+    pub probe: ResourceProbe,
+}
+
+/// Probe the model files, CPU and memory available for embedding.
+pub fn probe_resources(data_dir: &Path) -> ResourceProbe {
+    let model_dir = FastEmbedder::default_model_dir(data_dir);
+    let missing_model_files = FastEmbedder::required_model_files()
+        .iter()
+        .filter(|name| !model_dir.join(*name).is_file())
+        .map(|name| (*name).to_string())
+        .collect();
+    let (cpu_features, cpu_suitable) = cpu_features();
+    let (total_memory_bytes, available_memory_bytes) = memory_info();
+    ResourceProbe {
+        model_dir,
+        missing_model_files,
+        arch: std::env::consts::ARCH.to_string(),
+        cpu_features,
+        cpu_suitable,
+        gpu_providers: compiled_gpu_providers()
+            .iter()
+            .map(|p| (*p).to_string())
+            .collect(),
+        total_memory_bytes,
+        available_memory_bytes,
+        env_override: std::env::var(EMBEDDER_ENV)
+            .ok()
+            .filter(|v| !v.trim().is_empty()),
+    }
+}
+
+/// Pick an embedder from a probe. Pure, so the heuristics are testable.
+pub fn select_embedder(probe: ResourceProbe) -> EmbedderSelection {
+    let mut reasons = Vec::new();
+    let choice = decide(&probe, &mut reasons);
+    EmbedderSelection {
+        choice,
+        embedder_id: choice.embedder_id(),
+        reasons,
+        probe,
+    }
+}
+
+/// Probe this machine, select an embedder and log the decision.
+pub fn auto_select_embedder(data_dir: &Path) -> EmbedderSelection {
+    let selection = select_embedder(probe_resources(data_dir));
+    info!(
+        embedder = %selection.embedder_id,
+        reasons = %selection.reasons.join("; "),
+        "selected embedder"
+    );
+    selection
+}
+
+fn decide(probe: &ResourceProbe, reasons: &mut Vec<String>) -> EmbedderChoice {
+    let model_present = probe.missing_model_files.is_empty();
+    match probe.env_override.as_deref().map(str::to_ascii_lowercase) {
+        Some(v) if v == "hash" => {
+            reasons.push(format!("{EMBEDDER_ENV}=hash"));
+            return EmbedderChoice::Hash;
+        }
+        Some(v) if matches!(v.as_str(), "minilm" | "fastembed" | "ml") => {
+            reasons.push(format!("{EMBEDDER_ENV}={v}"));
+            if model_present {
+                return EmbedderChoice::Minilm;
+            }
+            reasons.push(format!(
+                "but MiniLM files are missing from {}",
+                probe.model_dir.display()
+            ));
+            return EmbedderChoice::Hash;
+        }
+        Some(v) => reasons.push(format!("ignoring unknown {EMBEDDER_ENV}={v}")),
+        None => {}
+    }
+
+    if !model_present {
+        reasons.push(format!(
+            "MiniLM model not installed (missing {} in {})",
+            probe.missing_model_files.join(", "),
+            probe.model_dir.display()
+        ));
+        return EmbedderChoice::Hash;
+    }
+    reasons.push("MiniLM model files present".to_string());
+
+    if !probe.gpu_providers.is_empty() {
+        reasons.push(format!(
+            "GPU execution providers available ({}), CPU as fallback",
+            probe.gpu_providers.join(", ")
+        ));
+    } else if !probe.cpu_suitable {
+        reasons.push(format!(
+            "{} CPU lacks AVX2; ML inference would be too slow",
+            probe.arch
+        ));
+        return EmbedderChoice::Hash;
+    }
+    if !probe.gpu_providers.is_empty() {
+        // The GPU does the work; host CPU features don't matter
+    } else if probe.cpu_features.is_empty() {
+        reasons.push(format!("{} CPU (no SIMD probe)", probe.arch));
+    } else {
+        reasons.push(format!("CPU supports {}", probe.cpu_features.join(", ")));
+    }
+
+    match probe.available_memory_bytes {
+        Some(available) if available < MIN_ML_MEMORY_BYTES => {
+            reasons.push(format!(
+                "only {} MiB of memory available (MiniLM needs {} MiB)",
+                available >> 20,
+                MIN_ML_MEMORY_BYTES >> 20
+            ));
+            return EmbedderChoice::Hash;
+        }
+        Some(available) => reasons.push(format!("{} MiB of memory available", available >> 20)),
+        None => reasons.push("available memory unknown; assuming enough".to_string()),
+    }
+    EmbedderChoice::Minilm
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpu_features() -> (Vec<String>, bool) {
+    let mut features = Vec::new();
+    if std::arch::is_x86_feature_detected!("avx2") {
+        features.push("avx2".to_string());
+    }
+    if std::arch::is_x86_feature_detected!("fma") {
+        features.push("fma".to_string());
+    }
+    if std::arch::is_x86_feature_detected!("avx512f") {
+        features.push("avx512f".to_string());
+    }
+    let suitable = features.iter().any(|f| f == "avx2");
+    (features, suitable)
+}
+
+#[cfg(target_arch = "aarch64")]
+fn cpu_features() -> (Vec<String>, bool) {
+    // NEON is part of the aarch64 baseline
+    (vec!["neon".to_string()], true)
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cpu_features() -> (Vec<String>, bool) {
+    (Vec::new(), true)
+}
+
+/// (total, available) memory from `/proc/meminfo`; unknown on other platforms.
+fn memory_info() -> (Option<u64>, Option<u64>) {
+    let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") else {
+        return (None, None);
+    };
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| {
+                rest.trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .map(|kb| kb * 1024)
+    };
+    (field("MemTotal:"), field("MemAvailable:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capable() -> ResourceProbe {
+        ResourceProbe {
+            model_dir: PathBuf::from("/data/models/minilm"),
+            arch: "x86_64".to_string(),
+            cpu_features: vec!["avx2".to_string()],
+            cpu_suitable: true,
+            total_memory_bytes: Some(16 << 30),
+            available_memory_bytes: Some(8 << 30),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_minilm_only_when_every_check_passes() {
+        let selection = select_embedder(capable());
+        assert_eq!(selection.choice, EmbedderChoice::Minilm);
+        assert_eq!(selection.embedder_id, FastEmbedder::embedder_id_static());
+
+        let missing = ResourceProbe {
+            missing_model_files: vec!["model.onnx".to_string()],
+            ..capable()
+        };
+        let selection = select_embedder(missing);
+        assert_eq!(selection.choice, EmbedderChoice::Hash);
+        assert_eq!(selection.embedder_id, "fnv1a-384");
+        assert!(selection.reasons[0].contains("model.onnx"));
+
+        let old_cpu = ResourceProbe {
+            cpu_features: Vec::new(),
+            cpu_suitable: false,
+            ..capable()
+        };
+        assert_eq!(
+            select_embedder(old_cpu.clone()).choice,
+            EmbedderChoice::Hash
+        );
+        let old_cpu_with_gpu = ResourceProbe {
+            gpu_providers: vec!["cuda".to_string()],
+            ..old_cpu
+        };
+        assert_eq!(
+            select_embedder(old_cpu_with_gpu).choice,
+            EmbedderChoice::Minilm
+        );
+
+        let low_memory = ResourceProbe {
+            available_memory_bytes: Some(256 << 20),
+            ..capable()
+        };
+        let selection = select_embedder(low_memory);
+        assert_eq!(selection.choice, EmbedderChoice::Hash);
+        assert!(selection.reasons.last().unwrap().contains("256 MiB"));
+    }
+
+    #[test]
+    fn env_override_wins_but_cannot_conjure_a_model() {
+        let forced_hash = ResourceProbe {
+            env_override: Some("hash".to_string()),
+            ..capable()
+        };
+        assert_eq!(select_embedder(forced_hash).choice, EmbedderChoice::Hash);
+
+        let forced_ml = ResourceProbe {
+            env_override: Some("MiniLM".to_string()),
+            cpu_suitable: false,
+            ..capable()
+        };
+        assert_eq!(select_embedder(forced_ml).choice, EmbedderChoice::Minilm);
+
+        let forced_ml_no_model = ResourceProbe {
+            env_override: Some("minilm".to_string()),
+            missing_model_files: vec!["model.onnx".to_string()],
+            ..capable()
+        };
+        assert_eq!(
+            select_embedder(forced_ml_no_model).choice,
+            EmbedderChoice::Hash
+        );
+    }
+}
@@ -3,16 +3,27 @@
 //! Loads a local ONNX model + tokenizer bundle and produces semantic embeddings.
 //! This implementation never downloads model assets; it expects the model files
 //! to be present on disk and returns a clear error when they are missing.
+//!
+//! # Execution providers
+//!
+//! Builds with `gpu-cuda`, `gpu-directml` or `gpu-coreml` register those ONNX
+//! Runtime execution providers ahead of the CPU. ONNX Runtime skips a provider
+//! whose device or driver is unavailable, and if the session still cannot be
+//! created the model is loaded again on the CPU alone. `CASS_EMBED_DEVICE=cpu`
+//! (or `cuda`, `directml`, `coreml`) restricts the choice; the default `auto`
+//! tries every compiled-in provider.
 
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use fastembed::{
-    InitOptionsUserDefined, Pooling, TextEmbedding, TokenizerFiles, UserDefinedEmbeddingModel,
+    ExecutionProviderDispatch, InitOptionsUserDefined, Pooling, TextEmbedding, TokenizerFiles,
+    UserDefinedEmbeddingModel,
 };
+use tracing::{info, warn};
 
-use super::embedder::{Embedder, EmbedderError, EmbedderResult};
+use super::embedder::{BatchHints, Embedder, EmbedderError, EmbedderResult};
 
 const MODEL_ID: &str = "all-minilm-l6-v2";
 const MODEL_DIR_NAME: &str = "all-MiniLM-L6-v2";
@@ -25,12 +36,67 @@ const CONFIG_JSON: &str = "config.json";
 const SPECIAL_TOKENS_JSON: &str = "special_tokens_map.json";
 const TOKENIZER_CONFIG_JSON: &str = "tokenizer_config.json";
 
+/// Environment variable restricting the execution provider (`auto`, `cpu`, `cuda`, `directml`, `coreml`)
+pub const EMBED_DEVICE_ENV: &str = "CASS_EMBED_DEVICE";
+
+/// GPU execution providers compiled into this build, in preference order.
+pub fn compiled_gpu_providers() -> &'static [&'static str] {
+    &[
+        #[cfg(feature = "gpu-cuda")]
+        "cuda",
+        #[cfg(feature = "gpu-directml")]
+        "directml",
+        #[cfg(feature = "gpu-coreml")]
+        "coreml",
+    ]
+}
+
+/// Compiled-in GPU providers allowed by `CASS_EMBED_DEVICE`.
+fn requested_gpu_providers() -> Vec<&'static str> {
+    let device = std::env::var(EMBED_DEVICE_ENV)
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let compiled = compiled_gpu_providers().iter().copied();
+    match device.as_str() {
+        "" | "auto" | "gpu" => compiled.collect(),
+        "cpu" => Vec::new(),
+        other => {
+            let chosen: Vec<_> = compiled.filter(|p| *p == other).collect();
+            if chosen.is_empty() {
+                warn!(
+                    device = other,
+                    compiled = ?compiled_gpu_providers(),
+                    "{EMBED_DEVICE_ENV} names a provider this build lacks; using the CPU"
+                );
+            }
+            chosen
+        }
+    }
+}
+
+// Every arm but the fallback is feature-gated
+#[allow(clippy::match_single_binding)]
+fn execution_provider(name: &str) -> Option<ExecutionProviderDispatch> {
+    match name {
+        #[cfg(feature = "gpu-cuda")]
+        "cuda" => Some(ort::ep::CUDA::default().build()),
+        #[cfg(feature = "gpu-directml")]
+        "directml" => Some(ort::ep::DirectML::default().build()),
+        #[cfg(feature = "gpu-coreml")]
+        "coreml" => Some(ort::ep::CoreML::default().build()),
+        _ => None,
+    }
+}
+
 /// FastEmbed-backed semantic embedder using MiniLM.
 pub struct FastEmbedder {
     model: Mutex<TextEmbedding>,
     id: String,
     model_id: String,
     dimension: usize,
+    /// GPU providers registered ahead of the CPU (empty = CPU only)
+    providers: Vec<&'static str>,
 }
 
 impl FastEmbedder {
@@ -106,19 +172,54 @@ impl FastEmbedder {
         let mut model = UserDefinedEmbeddingModel::new(model_file, tokenizer_files);
         model.pooling = Some(Pooling::Mean);
 
-        let init_options = InitOptionsUserDefined::new();
-
-        let model = TextEmbedding::try_new_from_user_defined(model, init_options)
-            .map_err(|e| EmbedderError::EmbeddingFailed(format!("fastembed init failed: {e}")))?;
+        let mut providers = requested_gpu_providers();
+        let dispatch: Vec<_> = providers
+            .iter()
+            .filter_map(|p| execution_provider(p))
+            .collect();
+        let attempt = if dispatch.is_empty() {
+            TextEmbedding::try_new_from_user_defined(model.clone(), InitOptionsUserDefined::new())
+        } else {
+            TextEmbedding::try_new_from_user_defined(
+                model.clone(),
+                InitOptionsUserDefined::new().with_execution_providers(dispatch),
+            )
+        };
+        let model = match attempt {
+            Ok(model) => model,
+            Err(e) if !providers.is_empty() => {
+                warn!(providers = ?providers, "GPU embedding session failed ({e}); falling back to the CPU");
+                providers.clear();
+                TextEmbedding::try_new_from_user_defined(model, InitOptionsUserDefined::new())
+                    .map_err(|e| {
+                        EmbedderError::EmbeddingFailed(format!("fastembed init failed: {e}"))
+                    })?
+            }
+            Err(e) => {
+                return Err(EmbedderError::EmbeddingFailed(format!(
+                    "fastembed init failed: {e}"
+                )));
+            }
+        };
+        if !providers.is_empty() {
+            info!(providers = ?providers, "MiniLM embedder using GPU execution providers");
+        }
 
         Ok(Self {
             model: Mutex::new(model),
             id: EMBEDDER_ID.to_string(),
             model_id: MODEL_ID.to_string(),
             dimension: EMBEDDING_DIMENSION,
+            providers,
         })
     }
 
+    /// GPU execution providers registered for this session; empty when running on the CPU.
+    /// ONNX Runtime may still place work on the CPU if a registered provider has no device.
+    pub fn execution_providers(&self) -> &[&'static str] {
+        &self.providers
+    }
+
     /// Stable model identifier for compatibility checks.
     pub fn model_id(&self) -> &str {
         &self.model_id
@@ -208,6 +309,21 @@ impl Embedder for FastEmbedder {
         Ok(embeddings)
     }
 
+    fn batch_hints(&self) -> BatchHints {
+        if !self.providers.is_empty() {
+            // GPUs amortize kernel launches over much larger batches
+            return BatchHints {
+                max_batch_texts: 256,
+                max_batch_tokens: 65_536,
+            };
+        }
+        // MiniLM truncates at 256 tokens; keep padded batches around 32 full-length inputs
+        BatchHints {
+            max_batch_texts: 64,
+            max_batch_tokens: 8_192,
+        }
+    }
+
     fn dimension(&self) -> usize {
         self.dimension
     }
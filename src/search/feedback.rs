@@ -0,0 +1,175 @@
+//! Personal click feedback for ranking.
+//!
+//! ```toml
+//! [ranking]
+//! click_feedback = true
+//! ```
+//!
+//! When `click_feedback` is enabled, every result opened from the TUI result
+//! list is logged locally (`result_clicks`, aged out after 180 days and capped
+//! at 10,000 clicks). Those clicks are aggregated into click-through priors per
+//! conversation, workspace and agent, and search scores are multiplied by a
+//! small boost derived from them. The boost is bounded so a frequently opened
+//! session can't bury a clearly better text match. Nothing leaves the machine.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+
+/// Weight of ln(1 + clicks) on the same session
+const CONVERSATION_WEIGHT: f32 = 0.12;
+/// Weight of the workspace's share of all clicks
+const WORKSPACE_WEIGHT: f32 = 0.10;
+/// Weight of the agent's share of all clicks
+const AGENT_WEIGHT: f32 = 0.05;
+/// Upper bound on the combined multiplier
+const MAX_BOOST: f32 = 1.5;
+
+/// The `[ranking]` section of `config.toml`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct RankingConfig {
+    /// Boost results using click-through priors from previously opened hits
+    #[serde(default)]
+    pub click_feedback: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    ranking: RankingConfig,
+}
+
+impl RankingConfig {
+    /// Load `[ranking]` from the shared config file; missing or invalid means defaults.
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => config.ranking,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring ranking settings: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Click counts aggregated from `result_clicks`.
+#[derive(Debug, Clone, Default)]
+pub struct ClickPriors {
+    conversations: HashMap<(String, String), u32>,
+    workspaces: HashMap<String, u32>,
+    agents: HashMap<String, u32>,
+    total: u32,
+}
+
+impl ClickPriors {
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let mut priors = Self::default();
+        let mut stmt = conn.prepare(
+            "SELECT source_id, source_path, agent, COALESCE(workspace, ''), COUNT(*)
+             FROM result_clicks
+             GROUP BY source_id, source_path, agent, workspace",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, u32>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (source_id, source_path, agent, workspace, clicks) = row?;
+            priors.add(&source_id, &source_path, &agent, &workspace, clicks);
+        }
+        Ok(priors)
+    }
+
+    pub fn add(
+        &mut self,
+        source_id: &str,
+        source_path: &str,
+        agent: &str,
+        workspace: &str,
+        clicks: u32,
+    ) {
+        *self
+            .conversations
+            .entry((source_id.to_string(), source_path.to_string()))
+            .or_default() += clicks;
+        if !workspace.is_empty() {
+            *self.workspaces.entry(workspace.to_string()).or_default() += clicks;
+        }
+        *self.agents.entry(agent.to_string()).or_default() += clicks;
+        self.total += clicks;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Score multiplier (>= 1.0) for a hit from this session, workspace and agent.
+    pub fn boost(&self, source_id: &str, source_path: &str, agent: &str, workspace: &str) -> f32 {
+        if self.is_empty() {
+            return 1.0;
+        }
+        let total = self.total as f32;
+        let conversation = self
+            .conversations
+            .get(&(source_id.to_string(), source_path.to_string()))
+            .copied()
+            .unwrap_or_default() as f32;
+        let workspace = self.workspaces.get(workspace).copied().unwrap_or_default() as f32;
+        let agent = self.agents.get(agent).copied().unwrap_or_default() as f32;
+        let boost = 1.0
+            + CONVERSATION_WEIGHT * conversation.ln_1p()
+            + WORKSPACE_WEIGHT * workspace / total
+            + AGENT_WEIGHT * agent / total;
+        boost.min(MAX_BOOST)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boost_grows_with_clicks_and_is_bounded() {
+        let mut priors = ClickPriors::default();
+        assert_eq!(priors.boost("local", "/a.jsonl", "codex", "/ws"), 1.0);
+
+        priors.add("local", "/a.jsonl", "codex", "/ws/app", 3);
+        priors.add("local", "/b.jsonl", "claude_code", "/ws/lib", 1);
+        let clicked = priors.boost("local", "/a.jsonl", "codex", "/ws/app");
+        let same_workspace = priors.boost("local", "/c.jsonl", "codex", "/ws/app");
+        let unrelated = priors.boost("local", "/d.jsonl", "gemini", "/elsewhere");
+        assert!(clicked > same_workspace);
+        assert!(same_workspace > unrelated);
+        assert_eq!(unrelated, 1.0);
+
+        priors.add("local", "/a.jsonl", "codex", "/ws/app", 10_000);
+        assert_eq!(
+            priors.boost("local", "/a.jsonl", "codex", "/ws/app"),
+            MAX_BOOST
+        );
+    }
+
+    #[test]
+    fn parses_ranking_section() {
+        let file: ConfigFile = toml::from_str("[ranking]\nclick_feedback = true\n").unwrap();
+        assert!(file.ranking.click_feedback);
+        let empty: ConfigFile = toml::from_str("exclude = []").unwrap();
+        assert!(!empty.ranking.click_feedback);
+    }
+}
@@ -32,7 +32,7 @@
 //! assert_eq!(embedding.len(), 384);
 //! ```
 
-use super::embedder::{Embedder, EmbedderError, EmbedderResult};
+use super::embedder::{BatchHints, Embedder, EmbedderError, EmbedderResult};
 
 /// FNV-1a offset basis (64-bit).
 const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
@@ -169,6 +169,14 @@ impl Embedder for HashEmbedder {
         texts.iter().map(|t| self.embed(t)).collect()
     }
 
+    fn batch_hints(&self) -> BatchHints {
+        // No model to pad for: batch size only amortizes per-call overhead
+        BatchHints {
+            max_batch_texts: 1_024,
+            max_batch_tokens: usize::MAX,
+        }
+    }
+
     fn dimension(&self) -> usize {
         self.dimension
     }
@@ -0,0 +1,125 @@
+//! Exact identifiers, for the keyword shadow field (`ident`).
+//!
+//! The main analyzer splits on punctuation and drops tokens longer than 40
+//! characters, so `src/main.rs`, `std::io::Error`, `0x7fff_ffff` or a full
+//! commit hash can only be found as a loose AND of their fragments, or not at
+//! all. Message content is additionally scanned for identifier-shaped tokens
+//! (paths, qualified symbols, hex literals, hashes, error codes), which are
+//! indexed verbatim (lowercased) as single terms. A query term of the same
+//! shape matches that term in addition to the regular analyzed fields.
+
+use std::collections::HashSet;
+
+/// Longest token kept as an identifier (longer ones are data, not names)
+const MAX_LEN: usize = 256;
+/// Cap on identifiers per message, so pasted logs don't bloat the index
+const MAX_PER_MESSAGE: usize = 512;
+
+/// Characters that delimit identifiers in running text (besides whitespace)
+const DELIMITERS: &[char] = &[
+    '"', '\'', '`', '(', ')', '[', ']', '{', '}', '<', '>', ',', ';', '|', '=',
+];
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || DELIMITERS.contains(&c)
+}
+
+/// Strip sentence punctuation around a candidate token.
+fn trim(token: &str) -> &str {
+    token
+        .trim_start_matches(['*', '&'])
+        .trim_end_matches(['.', ',', ':', ';', '!', '?'])
+}
+
+/// Whether `token` is shaped like something people look up verbatim.
+pub fn is_identifier(token: &str) -> bool {
+    let token = trim(token);
+    let len = token.chars().count();
+    if !(2..=MAX_LEN).contains(&len) || !token.chars().any(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // Joined by characters the analyzer splits on: paths, `a::b`, `foo.bar`, `snake_case`, `--flag`
+    let joined = token.char_indices().any(|(i, c)| {
+        matches!(c, '/' | '\\' | '.' | ':' | '_' | '-' | '#' | '@' | '$')
+            && i > 0
+            && token[i + c.len_utf8()..]
+                .chars()
+                .next()
+                .is_some_and(|n| n.is_alphanumeric())
+    });
+    if joined {
+        return true;
+    }
+    if !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // `0x7fff`, long hashes the analyzer would drop
+    if len > 40 || (token.len() > 2 && token[..2].eq_ignore_ascii_case("0x")) {
+        return true;
+    }
+    // Error codes and constants: `ENOENT`, `E0502`, `SIGSEGV`
+    len >= 3
+        && token.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Indexed form of an identifier token.
+pub fn normalize(token: &str) -> String {
+    trim(token).to_lowercase()
+}
+
+/// Distinct identifier terms in `text`, normalized for the `ident` field.
+pub fn extract_identifiers(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for token in text.split(is_delimiter) {
+        if out.len() >= MAX_PER_MESSAGE {
+            break;
+        }
+        if is_identifier(token) {
+            let term = normalize(token);
+            if seen.insert(term.clone()) {
+                out.push(term);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_paths_symbols_hex_and_codes() {
+        let text = "open(\"src/main.rs\") failed with ENOENT at 0x7fff_ffff; see std::io::Error, \
+                    commit 3f2a9c0d1e4b5a6978c0d1e2f3a4b5c6d7e8f9a0b1c2.";
+        let ids = extract_identifiers(text);
+        for expected in [
+            "src/main.rs",
+            "enoent",
+            "0x7fff_ffff",
+            "std::io::error",
+            "3f2a9c0d1e4b5a6978c0d1e2f3a4b5c6d7e8f9a0b1c2",
+        ] {
+            assert!(
+                ids.contains(&expected.to_string()),
+                "missing {expected}: {ids:?}"
+            );
+        }
+        assert!(!ids.contains(&"failed".to_string()));
+        assert!(!ids.contains(&"see".to_string()));
+    }
+
+    #[test]
+    fn plain_words_are_not_identifiers() {
+        assert!(!is_identifier("error"));
+        assert!(!is_identifier("Error."));
+        assert!(!is_identifier("--"));
+        assert!(is_identifier("E0502"));
+        assert!(is_identifier("--release"));
+        assert!(is_identifier("0x7FFF"));
+    }
+}
@@ -4,17 +4,38 @@
 //!
 //! - **[`query`]**: Query parsing, execution, and caching for Tantivy-based full-text search.
 //! - **[`tantivy`]**: Tantivy index creation, schema management, and document indexing.
+//! - **[`embed_backfill`]**: Batch (re)embedding of messages into the vector index (`cass embed`).
+//! - **[`vector_compact`]**: Orphan/duplicate vector cleanup for the vector index (`cass compact`).
 //! - **[`embedder`]**: Embedder trait for semantic search (hash and ML implementations).
+//! - **[`embedder_select`]**: Picks MiniLM or hash from model files, CPU and memory (`cass doctor --embedder`).
 //! - **[`hash_embedder`]**: FNV-1a feature hashing embedder (deterministic fallback).
 //! - **[`fastembed_embedder`]**: FastEmbed-backed ML embedder (MiniLM).
 //! - **[`model_manager`]**: Semantic model detection + context wiring (no downloads).
 //! - **[`canonicalize`]**: Text preprocessing for consistent embedding input.
+//! - **[`feedback`]**: Click-through priors from opened results for personal re-ranking.
+//! - **[`analyzers`]**: Per-field (title vs content) tokenizer configuration.
+//! - **[`aliases`]**: `@name` query macros from `config.toml`.
+//! - **[`templates`]**: Parameterized `{var}` query templates from `config.toml`.
+//! - **[`test_names`]**: Test names from tool output for `test:` searches.
+//! - **[`identifiers`]**: Identifier-shaped tokens (paths, symbols, hashes) for exact lookups.
+//! - **[`deps`]**: Package names from install commands and manifests for `dep:` searches.
 
+pub mod aliases;
+pub mod analyzers;
 pub mod canonicalize;
+pub mod compare;
+pub mod deps;
+pub mod embed_backfill;
 pub mod embedder;
+pub mod embedder_select;
 pub mod fastembed_embedder;
+pub mod feedback;
 pub mod hash_embedder;
+pub mod identifiers;
 pub mod model_manager;
 pub mod query;
 pub mod tantivy;
+pub mod templates;
+pub mod test_names;
+pub mod vector_compact;
 pub mod vector_index;
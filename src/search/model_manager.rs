@@ -1,6 +1,7 @@
 //! Semantic model management (local-only detection).
 //!
-//! This module wires the FastEmbed MiniLM embedder into semantic search by:
+//! This module wires an embedder into semantic search by:
+//! - picking MiniLM or the hash embedder ([`auto_select_embedder`])
 //! - validating the local model files
 //! - loading the vector index
 //! - building filter maps from the SQLite database
@@ -13,7 +14,9 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::search::embedder::Embedder;
+use crate::search::embedder_select::{EmbedderChoice, auto_select_embedder};
 use crate::search::fastembed_embedder::FastEmbedder;
+use crate::search::hash_embedder::HashEmbedder;
 use crate::search::vector_index::{
     ROLE_ASSISTANT, ROLE_USER, SemanticFilterMaps, VectorIndex, vector_index_path,
 };
@@ -79,27 +82,23 @@ pub struct SemanticSetup {
 }
 
 pub fn load_semantic_context(data_dir: &Path, db_path: &Path) -> SemanticSetup {
-    let model_dir = FastEmbedder::default_model_dir(data_dir);
-    let missing_files = FastEmbedder::required_model_files()
-        .iter()
-        .filter(|name| !model_dir.join(*name).is_file())
-        .map(|name| (*name).to_string())
-        .collect::<Vec<_>>();
+    let selection = auto_select_embedder(data_dir);
+    let model_dir = selection.probe.model_dir.clone();
+    let missing_files = selection.probe.missing_model_files.clone();
 
-    if !missing_files.is_empty() {
-        return SemanticSetup {
-            availability: SemanticAvailability::ModelMissing {
+    let index_path = vector_index_path(data_dir, &selection.embedder_id);
+    if !index_path.is_file() {
+        // Without the model, installing it is the more useful hint than a hash index
+        let availability = if missing_files.is_empty() {
+            SemanticAvailability::IndexMissing { index_path }
+        } else {
+            SemanticAvailability::ModelMissing {
                 model_dir,
                 missing_files,
-            },
-            context: None,
+            }
         };
-    }
-
-    let index_path = vector_index_path(data_dir, FastEmbedder::embedder_id_static());
-    if !index_path.is_file() {
         return SemanticSetup {
-            availability: SemanticAvailability::IndexMissing { index_path },
+            availability,
             context: None,
         };
     }
@@ -141,16 +140,19 @@ pub fn load_semantic_context(data_dir: &Path, db_path: &Path) -> SemanticSetup {
         }
     };
 
-    let embedder = match FastEmbedder::load_from_dir(&model_dir) {
-        Ok(embedder) => Arc::new(embedder) as Arc<dyn Embedder>,
-        Err(err) => {
-            return SemanticSetup {
-                availability: SemanticAvailability::LoadFailed {
-                    context: format!("model load: {err}"),
-                },
-                context: None,
-            };
-        }
+    let embedder = match selection.choice {
+        EmbedderChoice::Hash => Arc::new(HashEmbedder::default_dimension()) as Arc<dyn Embedder>,
+        EmbedderChoice::Minilm => match FastEmbedder::load_from_dir(&model_dir) {
+            Ok(embedder) => Arc::new(embedder) as Arc<dyn Embedder>,
+            Err(err) => {
+                return SemanticSetup {
+                    availability: SemanticAvailability::LoadFailed {
+                        context: format!("model load: {err}"),
+                    },
+                    context: None,
+                };
+            }
+        },
     };
 
     let roles = Some(HashSet::from([ROLE_USER, ROLE_ASSISTANT]));
@@ -15,7 +15,7 @@ use tantivy::query::{
 };
 use tantivy::schema::{IndexRecordOption, Term, Value};
 use tantivy::snippet::SnippetGenerator;
-use tantivy::{Index, IndexReader, Searcher, TantivyDocument};
+use tantivy::{IndexReader, Searcher, TantivyDocument};
 use tokio::runtime::Handle;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
@@ -24,7 +24,7 @@ use rusqlite::Connection;
 
 use crate::search::canonicalize::canonicalize_for_embedding;
 use crate::search::embedder::Embedder;
-use crate::search::tantivy::fields_from_schema;
+use crate::search::feedback::ClickPriors;
 use crate::search::vector_index::{
     SemanticFilter, SemanticFilterMaps, VectorIndex, VectorSearchResult,
 };
@@ -34,17 +34,96 @@ use crate::sources::provenance::SourceFilter;
 #[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct SearchFilters {
     pub agents: HashSet<String>,
+    /// Workspace path prefixes; a conversation matches when its workspace starts with one
     pub workspaces: HashSet<String>,
     pub created_from: Option<i64>,
     pub created_to: Option<i64>,
-    /// Filter by conversation source (local, remote, or specific source ID)
+    /// Filter by conversation source (local, remote, specific source ID, or any of several)
     #[serde(skip_serializing_if = "SourceFilter::is_all")]
     pub source_filter: SourceFilter,
+    /// Only messages with one of these roles: user, assistant, system, tool
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub roles: HashSet<String>,
     /// Filter to specific session source paths (for chained searches)
     #[serde(skip_serializing_if = "HashSet::is_empty")]
     pub session_paths: HashSet<String>,
+    /// Match query terms against extracted code snippets only
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub code_only: bool,
+    /// Only messages where one of these tests failed or was fixed (`test:` in the query)
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub test_names: HashSet<String>,
+    /// Only messages that add, install or edit one of these packages (`dep:` in the query)
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub dependencies: HashSet<String>,
+    /// Only messages carrying these classifier labels (`label:key` or `label:key=value`)
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub labels: HashSet<String>,
+    /// Only sessions with this rating (`rating:` in the query)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<RatingFilter>,
+    /// Only sessions tagged with all of these (`tag:` in the query, `cass tag add`)
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub tags: HashSet<String>,
+    /// Only messages with a note containing all of these (`note:` in the query, `cass note add`)
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub notes: HashSet<String>,
+}
+
+impl SearchFilters {
+    /// Whether the source filter narrows the search at all
+    pub fn has_source_filter(&self) -> bool {
+        !self.source_filter.is_all()
+    }
+
+    /// Whether `workspace` is one of the workspace prefixes or lies under one (or none are set)
+    pub fn workspace_matches(&self, workspace: &str) -> bool {
+        self.workspaces.is_empty()
+            || self.workspaces.iter().any(|w| {
+                let w = w.trim_end_matches('/');
+                workspace == w
+                    || workspace
+                        .strip_prefix(w)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+/// `rating:up`, `rating:down`, `rating:any`, `rating:none`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RatingFilter {
+    Up,
+    Down,
+    Rated,
+    Unrated,
+}
+
+impl RatingFilter {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "up" | "good" | "+" | "+1" | "1" => Some(Self::Up),
+            "down" | "bad" | "-" | "-1" => Some(Self::Down),
+            "any" | "rated" => Some(Self::Rated),
+            "none" | "unrated" => Some(Self::Unrated),
+            _ => None,
+        }
+    }
+
+    pub fn matches(self, rating: Option<i8>) -> bool {
+        match self {
+            Self::Up => rating == Some(1),
+            Self::Down => rating == Some(-1),
+            Self::Rated => rating.is_some(),
+            Self::Unrated => rating.is_none(),
+        }
+    }
 }
 
+/// Score multipliers for sessions rated up / down with `cass rate`
+const RATING_UP_BOOST: f32 = 1.25;
+const RATING_DOWN_PENALTY: f32 = 0.6;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SearchMode {
@@ -275,9 +354,10 @@ impl QueryExplanation {
         // Check for filters first (they modify everything)
         let has_filters = !filters.agents.is_empty()
             || !filters.workspaces.is_empty()
+            || !filters.roles.is_empty()
             || filters.created_from.is_some()
             || filters.created_to.is_some()
-            || !filters.source_filter.is_all();
+            || filters.has_source_filter();
 
         if has_filters {
             return QueryType::Filtered;
@@ -819,6 +899,12 @@ pub struct SearchClient {
     metrics: Metrics,
     cache_namespace: String,
     semantic: Mutex<Option<SemanticSearchState>>,
+    /// Set by `pin_snapshot`: every search uses this searcher and reloads are skipped
+    pinned_searcher: Mutex<Option<Searcher>>,
+    /// Boost hits by click-through priors (`[ranking] click_feedback`)
+    click_feedback: bool,
+    /// Why the Tantivy index couldn't be used; searches then go to SQLite FTS
+    index_error: Mutex<Option<String>>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -1033,6 +1119,26 @@ thread_local! {
     static THREAD_SEARCHER: RefCell<Option<SearcherCacheEntry>> = const { RefCell::new(None) };
 }
 
+/// Split `field:<value>` tokens (e.g. `test:my_mod::my_test`, `dep:tokio`) out of a query.
+/// Returns the remaining query and the values, which are matched exactly rather than tokenized.
+pub fn split_field_filters(query: &str, field: &str) -> (String, Vec<String>) {
+    let prefix = format!("{field}:");
+    if !query.contains(&prefix) {
+        return (query.to_string(), Vec::new());
+    }
+    let mut rest = Vec::new();
+    let mut values = Vec::new();
+    for token in query.split_whitespace() {
+        match token.strip_prefix(&prefix) {
+            Some(value) if !value.trim_matches('"').is_empty() => {
+                values.push(value.trim_matches('"').to_string());
+            }
+            _ => rest.push(token),
+        }
+    }
+    (rest.join(" "), values)
+}
+
 fn sanitize_query(raw: &str) -> String {
     // Replace any character that is not alphanumeric, asterisk, or double quote with a space.
     // Asterisks are preserved for wildcard query support (*foo, foo*, *bar*).
@@ -1288,6 +1394,27 @@ fn build_compound_term_query(
     }
 }
 
+/// Let an identifier-shaped term (`src/main.rs`, `0x7fff`, `ENOENT`) also match the
+/// verbatim `ident` field, since the analyzed fields only hold its fragments.
+fn with_identifier_match(
+    raw: &str,
+    analyzed: Option<Box<dyn Query>>,
+    fields: &crate::search::tantivy::Fields,
+) -> Option<Box<dyn Query>> {
+    if raw.contains('*') || !crate::search::identifiers::is_identifier(raw) {
+        return analyzed;
+    }
+    let ident: Box<dyn Query> = Box::new(TermQuery::new(
+        Term::from_field_text(fields.ident, &crate::search::identifiers::normalize(raw)),
+        IndexRecordOption::Basic,
+    ));
+    let mut shoulds = vec![(Occur::Should, ident)];
+    if let Some(analyzed) = analyzed {
+        shoulds.push((Occur::Should, analyzed));
+    }
+    Some(Box::new(BooleanQuery::new(shoulds)))
+}
+
 /// Build a phrase query (exact order) across title/content fields.
 fn build_phrase_query(
     terms: &[String],
@@ -1301,11 +1428,9 @@ fn build_phrase_query(
     }
 
     let mut shoulds: Vec<(Occur, Box<dyn Query>)> = Vec::new();
-    for field in [fields.title, fields.content] {
-        let phrase_terms = terms
-            .iter()
-            .map(|t| Term::from_field_text(field, t))
-            .collect::<Vec<_>>();
+    let title_terms = terms.iter().map(|t| fields.title_term(t)).collect();
+    let content_terms = terms.iter().map(|t| fields.content_term(t)).collect();
+    for phrase_terms in [title_terms, content_terms] {
         shoulds.push((Occur::Should, Box::new(PhraseQuery::new(phrase_terms))));
     }
     Some(Box::new(BooleanQuery::new(shoulds)))
@@ -1365,7 +1490,8 @@ fn build_boolean_query_clauses(
             }
             QueryToken::Term(term) => {
                 let parts = normalize_term_parts(term);
-                let term_query = build_compound_term_query(&parts, fields);
+                let term_query =
+                    with_identifier_match(term, build_compound_term_query(&parts, fields), fields);
                 if term_query.is_none() {
                     continue;
                 }
@@ -1451,17 +1577,27 @@ fn build_term_query_clauses(
             if term.is_empty() {
                 return shoulds;
             }
+            // Whole terms are normalized like the indexed field (e.g. stemmed content);
+            // a prefix is matched as typed
+            let (title_term, content_term) = if matches!(pattern, WildcardPattern::Exact(_)) {
+                (fields.title_term(term), fields.content_term(term))
+            } else {
+                (
+                    Term::from_field_text(fields.title, term),
+                    Term::from_field_text(fields.content, term),
+                )
+            };
             shoulds.push((
                 Occur::Should,
                 Box::new(TermQuery::new(
-                    Term::from_field_text(fields.title, term),
+                    title_term,
                     IndexRecordOption::WithFreqsAndPositions,
                 )),
             ));
             shoulds.push((
                 Occur::Should,
                 Box::new(TermQuery::new(
-                    Term::from_field_text(fields.content, term),
+                    content_term,
                     IndexRecordOption::WithFreqsAndPositions,
                 )),
             ));
@@ -1575,15 +1711,23 @@ fn deduplicate_hits(hits: Vec<SearchHit>) -> Vec<SearchHit> {
 
 impl SearchClient {
     pub fn open(index_path: &Path, db_path: Option<&Path>) -> Result<Option<Self>> {
-        let tantivy = Index::open_in_dir(index_path).ok().and_then(|mut idx| {
-            // Register custom tokenizer so searches work
-            crate::search::tantivy::ensure_tokenizer(&mut idx);
-            let schema = idx.schema();
-            let fields = fields_from_schema(&schema).ok()?;
-            idx.reader().ok().map(|reader| (reader, fields))
-        });
+        // A missing index just means nothing was indexed yet; one that exists but
+        // won't open is corrupt, and searches degrade to SQLite FTS.
+        let index_error = crate::search::tantivy::corruption(index_path);
+        if let Some(err) = &index_error {
+            tracing::warn!(
+                kind = "index-corrupt",
+                path = %index_path.display(),
+                error = %err,
+                "search index unreadable; falling back to SQLite FTS (fix: cass index --rebuild)"
+            );
+        }
+        let tantivy = index_error
+            .is_none()
+            .then(|| crate::search::tantivy::open_reader(index_path).ok())
+            .flatten();
 
-        let sqlite = db_path.and_then(|p| Connection::open(p).ok());
+        let sqlite = db_path.and_then(|p| crate::storage::sqlite::open_readonly_connection(p).ok());
 
         if tantivy.is_none() && sqlite.is_none() {
             return Ok(None);
@@ -1623,18 +1767,65 @@ impl SearchClient {
             metrics,
             cache_namespace,
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: crate::search::feedback::RankingConfig::load().click_feedback,
+            index_error: Mutex::new(index_error),
         }))
     }
 
+    /// Set when the Tantivy index is corrupt and searches use SQLite FTS instead
+    pub fn index_error(&self) -> Option<String> {
+        self.index_error.lock().ok().and_then(|e| e.clone())
+    }
+
+    /// Enable or disable the click-feedback boost regardless of `config.toml`.
+    pub fn with_click_feedback(mut self, enabled: bool) -> Self {
+        self.click_feedback = enabled;
+        self
+    }
+
     pub fn search(
         &self,
         query: &str,
-        filters: SearchFilters,
+        mut filters: SearchFilters,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<SearchHit>> {
+        let (query, test_names) = split_field_filters(query, "test");
+        let (query, deps) = split_field_filters(&query, "dep");
+        let (query, ratings) = split_field_filters(&query, "rating");
+        let (query, tags) = split_field_filters(&query, "tag");
+        let (query, notes) = split_field_filters(&query, "note");
+        let (query, labels) = split_field_filters(&query, "label");
+        let query = query.as_str();
+        filters
+            .labels
+            .extend(labels.iter().map(|l| l.trim().to_lowercase()));
+        filters.notes.extend(notes.iter().map(|n| n.to_lowercase()));
+        filters.tags.extend(
+            tags.iter()
+                .filter_map(|t| crate::storage::sqlite::normalize_tag(t)),
+        );
+        if let Some(rating) = ratings.last().and_then(|r| RatingFilter::parse(r)) {
+            filters.rating = Some(rating);
+        }
+        filters.test_names.extend(test_names);
+        filters.dependencies.extend(
+            deps.iter()
+                .filter_map(|d| crate::search::deps::normalize(d)),
+        );
         let sanitized = sanitize_query(query);
 
+        // A query of only `note:` terms searches the notes themselves
+        if !filters.notes.is_empty() && sanitized.trim().is_empty() {
+            let Some(conn) = &self.sqlite else {
+                return Ok(Vec::new());
+            };
+            let mut hits = self.search_notes(conn, &filters)?;
+            self.personalize(&mut hits, &filters);
+            return Ok(hits.into_iter().skip(offset).take(limit).collect());
+        }
+
         // Schedule warmup for likely prefixes when user pauses typing.
         if offset == 0
             && let Some(tx) = &self.warm_tx
@@ -1678,20 +1869,36 @@ impl SearchClient {
                 offset = offset,
                 "search_start"
             );
-            let hits = self.search_tantivy(
+            let hits = match self.search_tantivy(
                 reader,
                 fields,
                 &sanitized,
                 filters.clone(),
                 limit * 3,
                 offset,
-            )?;
+            ) {
+                Ok(hits) => hits,
+                // Segments damaged after opening surface here; degrade rather than fail
+                Err(e) if self.sqlite.is_some() => {
+                    tracing::warn!(
+                        kind = "index-corrupt",
+                        error = %e,
+                        "search index read failed; falling back to SQLite FTS (fix: cass index --rebuild)"
+                    );
+                    if let Ok(mut slot) = self.index_error.lock() {
+                        *slot = Some(format!("{e:#}"));
+                    }
+                    Vec::new()
+                }
+                Err(e) => return Err(e),
+            };
             if !hits.is_empty() {
                 let mut deduped = deduplicate_hits(hits);
                 // Apply session_paths filter (post-search since source_path is not indexed)
                 if !filters.session_paths.is_empty() {
                     deduped.retain(|h| filters.session_paths.contains(&h.source_path));
                 }
+                self.personalize(&mut deduped, &filters);
                 deduped.truncate(limit);
                 self.put_cache(&sanitized, &filters, &deduped);
                 return Ok(deduped);
@@ -1709,9 +1916,13 @@ impl SearchClient {
         // Also skip SQLite fallback when source filtering is applied, since the FTS table
         // doesn't have a source_id column (P3.1 limitation).
         let query_has_wildcards = sanitized.contains('*');
-        let has_source_filter = !matches!(filters.source_filter, SourceFilter::All);
+        let has_source_filter = filters.has_source_filter();
+        // Test names, dependencies and labels are only indexed in Tantivy
+        let has_exact_field_filter = !filters.test_names.is_empty()
+            || !filters.dependencies.is_empty()
+            || !filters.labels.is_empty();
         if let Some(conn) = &self.sqlite {
-            if query_has_wildcards || has_source_filter {
+            if query_has_wildcards || has_source_filter || has_exact_field_filter {
                 return Ok(Vec::new());
             }
             tracing::info!(
@@ -1727,6 +1938,7 @@ impl SearchClient {
             if !filters.session_paths.is_empty() {
                 deduped.retain(|h| filters.session_paths.contains(&h.source_path));
             }
+            self.personalize(&mut deduped, &filters);
             deduped.truncate(limit);
             self.put_cache(&sanitized, &filters, &deduped);
             return Ok(deduped);
@@ -1810,6 +2022,11 @@ impl SearchClient {
         let mut semantic_filter =
             SemanticFilter::from_search_filters(&filters, &state.filter_maps)?;
         if let Some(roles) = state.roles.clone() {
+            // Requested roles narrow the ones the vector index is searched for
+            let roles = match semantic_filter.roles.take() {
+                Some(requested) => requested.intersection(&roles).copied().collect(),
+                None => roles,
+            };
             semantic_filter = semantic_filter.with_roles(Some(roles));
         }
 
@@ -1831,6 +2048,7 @@ impl SearchClient {
         if !filters.session_paths.is_empty() {
             hits.retain(|h| filters.session_paths.contains(&h.source_path));
         }
+        self.personalize(&mut hits, &filters);
         Ok(hits)
     }
 
@@ -1854,12 +2072,11 @@ impl SearchClient {
         }
 
         let sql = format!(
-            "SELECT m.id, m.content, m.created_at, m.idx, m.role, c.title, c.source_path, c.source_id, c.origin_host, a.slug, w.path, COALESCE(s.kind, 'local')
+            "SELECT m.id, cass_text(m.content), m.created_at, m.idx, m.role, c.title, c.source_path, c.source_id, c.origin_host, a.slug, w.path, c.origin_kind
              FROM messages m
              JOIN conversations c ON m.conversation_id = c.id
              JOIN agents a ON c.agent_id = a.id
              LEFT JOIN workspaces w ON c.workspace_id = w.id
-             LEFT JOIN sources s ON c.source_id = s.id
              WHERE m.id IN ({placeholders})"
         );
 
@@ -2130,7 +2347,27 @@ impl SearchClient {
         suggestions
     }
 
+    /// Pin every following search to the current index generation and SQLite
+    /// snapshot, so a long export or report stays internally consistent while the
+    /// indexer commits. Returns the pinned Tantivy generation, if there is an index.
+    pub fn pin_snapshot(&self) -> Result<Option<u64>> {
+        if let Some(conn) = &self.sqlite {
+            crate::storage::sqlite::begin_read_snapshot(conn)?;
+        }
+        let Some((reader, _)) = &self.reader else {
+            return Ok(None);
+        };
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let generation = searcher.generation().generation_id();
+        *self.pinned_searcher.lock().unwrap() = Some(searcher);
+        Ok(Some(generation))
+    }
+
     fn searcher_for_thread(&self, reader: &IndexReader) -> Searcher {
+        if let Some(searcher) = self.pinned_searcher.lock().unwrap().as_ref() {
+            return searcher.clone();
+        }
         let epoch = self.reload_epoch.load(Ordering::Relaxed);
         THREAD_SEARCHER.with(|slot| {
             let mut slot = slot.borrow_mut();
@@ -2159,6 +2396,153 @@ impl SearchClient {
         *guard = Some(generation);
     }
 
+    /// Session ratings from `cass rate`, keyed by (source_id, source_path).
+    fn session_ratings(&self) -> HashMap<(String, String), i8> {
+        let Some(conn) = &self.sqlite else {
+            return HashMap::new();
+        };
+        // Databases from before ratings existed have no table; treat as unrated
+        crate::storage::sqlite::load_ratings(conn).unwrap_or_default()
+    }
+
+    /// Click-through priors from opened results, when click feedback is enabled.
+    fn click_priors(&self) -> ClickPriors {
+        match &self.sqlite {
+            Some(conn) if self.click_feedback => ClickPriors::load(conn).unwrap_or_default(),
+            _ => ClickPriors::default(),
+        }
+    }
+
+    /// Messages whose notes match every `note:` term, best match first. The note is
+    /// the snippet; agent, workspace and time filters apply as usual.
+    fn search_notes(&self, conn: &Connection, filters: &SearchFilters) -> Result<Vec<SearchHit>> {
+        let terms: Vec<String> = filters.notes.iter().cloned().collect();
+        let Some(expr) = crate::storage::sqlite::note_match_expr(&terms) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(c.title, ''), cass_text(m.content), a.note, c.source_path, ag.slug,
+                    COALESCE(w.path, ''), COALESCE(m.created_at, c.started_at), m.idx,
+                    c.source_id, c.origin_kind, c.origin_host,
+                    bm25(fts_annotations)
+             FROM fts_annotations f
+             JOIN annotations a ON a.id = f.rowid
+             JOIN messages m ON m.id = a.message_id
+             JOIN conversations c ON c.id = m.conversation_id
+             JOIN agents ag ON ag.id = c.agent_id
+             LEFT JOIN workspaces w ON w.id = c.workspace_id
+             WHERE fts_annotations MATCH ?
+             ORDER BY bm25(fts_annotations), a.updated_at DESC",
+        )?;
+        let rows = stmt.query_map([expr], |row| {
+            Ok(SearchHit {
+                title: row.get(0)?,
+                content: row.get(1)?,
+                snippet: row.get(2)?,
+                source_path: row.get(3)?,
+                agent: row.get(4)?,
+                workspace: row.get(5)?,
+                workspace_original: None,
+                created_at: row.get(6)?,
+                line_number: row.get::<_, i64>(7).ok().map(|i| (i + 1) as usize),
+                // bm25 is lower-is-better and negative
+                score: -(row.get::<_, f64>(11)? as f32),
+                match_type: MatchType::Exact,
+                source_id: row.get(8)?,
+                origin_kind: row.get(9)?,
+                origin_host: row.get(10)?,
+            })
+        })?;
+        let mut hits = Vec::new();
+        for row in rows {
+            let hit = row?;
+            let in_range = |ts: i64| {
+                filters.created_from.is_none_or(|from| ts >= from)
+                    && filters.created_to.is_none_or(|to| ts <= to)
+            };
+            let source_ok = filters
+                .source_filter
+                .alternatives()
+                .into_iter()
+                .any(|f| match f {
+                    SourceFilter::All | SourceFilter::Any(_) => true,
+                    SourceFilter::Local => hit.origin_kind == "local",
+                    SourceFilter::Remote => hit.origin_kind != "local",
+                    SourceFilter::SourceId(id) => &hit.source_id == id,
+                });
+            if !source_ok
+                || (!filters.agents.is_empty() && !filters.agents.contains(&hit.agent))
+                || !filters.workspace_matches(&hit.workspace)
+                || ((filters.created_from.is_some() || filters.created_to.is_some())
+                    && !hit.created_at.is_some_and(in_range))
+            {
+                continue;
+            }
+            hits.push(hit);
+        }
+        Ok(hits)
+    }
+
+    /// Apply `tag:`, `note:` and `rating:` filters, then boost (or demote) hits by session
+    /// rating and click-through priors, keeping score order.
+    fn personalize(&self, hits: &mut Vec<SearchHit>, filters: &SearchFilters) {
+        if !filters.tags.is_empty() {
+            let tags: Vec<String> = filters.tags.iter().cloned().collect();
+            let tagged = self
+                .sqlite
+                .as_ref()
+                .and_then(|conn| crate::storage::sqlite::load_tagged_sessions(conn, &tags).ok())
+                .unwrap_or_default();
+            hits.retain(|h| tagged.contains(&(h.source_id.clone(), h.source_path.clone())));
+        }
+        if !filters.notes.is_empty() {
+            let terms: Vec<String> = filters.notes.iter().cloned().collect();
+            let noted = self
+                .sqlite
+                .as_ref()
+                .and_then(|conn| crate::storage::sqlite::load_note_matches(conn, &terms).ok())
+                .unwrap_or_default();
+            hits.retain(|h| {
+                h.line_number.is_some_and(|line| {
+                    noted.contains(&(h.source_id.clone(), h.source_path.clone(), line as i64 - 1))
+                })
+            });
+        }
+        let filter = filters.rating;
+        let ratings = self.session_ratings();
+        let priors = self.click_priors();
+        if ratings.is_empty() && priors.is_empty() && filter.is_none() {
+            return;
+        }
+        let rating_of = |h: &SearchHit| {
+            ratings
+                .get(&(h.source_id.clone(), h.source_path.clone()))
+                .copied()
+        };
+        if let Some(filter) = filter {
+            hits.retain(|h| filter.matches(rating_of(h)));
+        }
+        if ratings.is_empty() && priors.is_empty() {
+            return;
+        }
+        for hit in hits.iter_mut() {
+            match rating_of(hit) {
+                Some(1) => hit.score *= RATING_UP_BOOST,
+                Some(-1) => hit.score *= RATING_DOWN_PENALTY,
+                _ => {}
+            }
+            hit.score *= priors.boost(&hit.source_id, &hit.source_path, &hit.agent, &hit.workspace);
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+
+    /// Drop cached results, e.g. after a rating changes their order.
+    pub fn invalidate_cache(&self) {
+        if let Ok(mut cache) = self.prefix_cache.lock() {
+            cache.clear();
+        }
+    }
+
     fn search_tantivy(
         &self,
         reader: &IndexReader,
@@ -2174,6 +2558,13 @@ impl SearchClient {
 
         let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
 
+        // Code-only search matches terms against the snippet fields instead of title/content
+        let query_fields = if filters.code_only {
+            fields.code_only()
+        } else {
+            *fields
+        };
+
         // Parse query with boolean operator support (AND, OR, NOT, "phrases")
         // Falls back to simple whitespace split for plain queries (implicit AND)
         let tokens = parse_boolean_query(query);
@@ -2181,16 +2572,23 @@ impl SearchClient {
             clauses.push((Occur::Must, Box::new(AllQuery)));
         } else if has_boolean_operators(query) {
             // Use boolean query builder for complex queries
-            let bool_clauses = build_boolean_query_clauses(&tokens, fields);
+            let bool_clauses = build_boolean_query_clauses(&tokens, &query_fields);
             clauses.extend(bool_clauses);
         } else {
             // Simple query: treat each term as MUST (implicit AND)
             for token in tokens {
                 if let QueryToken::Term(term_str) = token {
                     let pattern = WildcardPattern::parse(&term_str);
-                    let term_shoulds = build_term_query_clauses(&pattern, fields);
-                    if !term_shoulds.is_empty() {
-                        clauses.push((Occur::Must, Box::new(BooleanQuery::new(term_shoulds))));
+                    let term_shoulds = build_term_query_clauses(&pattern, &query_fields);
+                    let analyzed = (!term_shoulds.is_empty())
+                        .then(|| Box::new(BooleanQuery::new(term_shoulds)) as Box<dyn Query>);
+                    let term_query = if filters.code_only {
+                        analyzed
+                    } else {
+                        with_identifier_match(&term_str, analyzed, fields)
+                    };
+                    if let Some(term_query) = term_query {
+                        clauses.push((Occur::Must, term_query));
                     }
                 }
             }
@@ -2199,12 +2597,12 @@ impl SearchClient {
         if !filters.agents.is_empty() {
             let terms = filters
                 .agents
-                .into_iter()
+                .iter()
                 .map(|agent| {
                     (
                         Occur::Should,
                         Box::new(TermQuery::new(
-                            Term::from_field_text(fields.agent, &agent),
+                            Term::from_field_text(fields.agent, agent),
                             IndexRecordOption::Basic,
                         )) as Box<dyn Query>,
                     )
@@ -2213,15 +2611,29 @@ impl SearchClient {
             clauses.push((Occur::Must, Box::new(BooleanQuery::new(terms))));
         }
 
+        // Workspaces match by prefix, so a parent directory covers its subprojects
         if !filters.workspaces.is_empty() {
+            let mut terms = Vec::new();
+            for ws in &filters.workspaces {
+                let pattern = format!("{}(/.*)?", escape_regex(ws.trim_end_matches('/')));
+                terms.push((
+                    Occur::Should,
+                    Box::new(RegexQuery::from_pattern(&pattern, fields.workspace)?)
+                        as Box<dyn Query>,
+                ));
+            }
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(terms))));
+        }
+
+        if !filters.roles.is_empty() {
             let terms = filters
-                .workspaces
-                .into_iter()
-                .map(|ws| {
+                .roles
+                .iter()
+                .map(|role| {
                     (
                         Occur::Should,
                         Box::new(TermQuery::new(
-                            Term::from_field_text(fields.workspace, &ws),
+                            Term::from_field_text(fields.role, role),
                             IndexRecordOption::Basic,
                         )) as Box<dyn Query>,
                     )
@@ -2230,6 +2642,39 @@ impl SearchClient {
             clauses.push((Occur::Must, Box::new(BooleanQuery::new(terms))));
         }
 
+        for (field, values) in [
+            (fields.test, &filters.test_names),
+            (fields.dep, &filters.dependencies),
+        ] {
+            if values.is_empty() {
+                continue;
+            }
+            let terms = values
+                .iter()
+                .map(|value| {
+                    (
+                        Occur::Should,
+                        Box::new(TermQuery::new(
+                            Term::from_field_text(field, value),
+                            IndexRecordOption::Basic,
+                        )) as Box<dyn Query>,
+                    )
+                })
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(terms))));
+        }
+
+        // Labels narrow like tags: every requested label must be present
+        for label in &filters.labels {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(fields.label, label),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+
         if filters.created_from.is_some() || filters.created_to.is_some() {
             use std::ops::Bound::{Included, Unbounded};
             let lower = filters.created_from.map_or(Unbounded, |v| {
@@ -2242,36 +2687,37 @@ impl SearchClient {
             clauses.push((Occur::Must, Box::new(range)));
         }
 
-        // Source filter (P3.1)
-        match &filters.source_filter {
-            SourceFilter::All => {
-                // No filtering needed
-            }
-            SourceFilter::Local => {
-                // Filter to local sources only (origin_kind == "local")
-                let term = Term::from_field_text(fields.origin_kind, "local");
-                clauses.push((
-                    Occur::Must,
-                    Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
-                ));
-            }
-            SourceFilter::Remote => {
-                // Filter to remote sources only (origin_kind == "ssh")
-                // We use "ssh" since that's the only remote kind currently
-                let term = Term::from_field_text(fields.origin_kind, "ssh");
-                clauses.push((
-                    Occur::Must,
-                    Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
-                ));
-            }
-            SourceFilter::SourceId(source_id) => {
-                // Filter to specific source by ID
-                let term = Term::from_field_text(fields.source_id, source_id);
-                clauses.push((
-                    Occur::Must,
-                    Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
-                ));
-            }
+        // Source filter (P3.1); repeated sources are alternatives
+        if filters.has_source_filter() {
+            let term_query = |field, value: &str| -> Box<dyn Query> {
+                Box::new(TermQuery::new(
+                    Term::from_field_text(field, value),
+                    IndexRecordOption::Basic,
+                ))
+            };
+            let terms = filters
+                .source_filter
+                .alternatives()
+                .into_iter()
+                .filter_map(|source| {
+                    let query = match source {
+                        SourceFilter::All | SourceFilter::Any(_) => return None,
+                        // Filter to local sources only (origin_kind == "local")
+                        SourceFilter::Local => term_query(fields.origin_kind, "local"),
+                        // Anything not local: ssh, object stores, cass peers
+                        SourceFilter::Remote => Box::new(BooleanQuery::new(vec![
+                            (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+                            (Occur::MustNot, term_query(fields.origin_kind, "local")),
+                        ])),
+                        // Filter to specific source by ID
+                        SourceFilter::SourceId(source_id) => {
+                            term_query(fields.source_id, source_id)
+                        }
+                    };
+                    Some((Occur::Should, query))
+                })
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(terms))));
         }
 
         // NOTE: session_paths filtering is applied post-search since source_path
@@ -2293,7 +2739,8 @@ impl SearchClient {
             Box::new(BooleanQuery::new(clauses))
         };
 
-        let prefix_only = is_prefix_only(query);
+        // Code-only hits have no matching terms in `content`, so use prefix snippets
+        let prefix_only = is_prefix_only(query) || filters.code_only;
         let snippet_generator = if prefix_only {
             None
         } else {
@@ -2424,13 +2871,22 @@ impl SearchClient {
         }
 
         if !filters.workspaces.is_empty() {
-            let placeholders = (0..filters.workspaces.len())
-                .map(|_| "?".to_string())
-                .collect::<Vec<_>>()
-                .join(",");
-            sql.push_str(&format!(" AND f.workspace IN ({placeholders})"));
+            let prefixes =
+                vec!["(f.workspace = ? OR instr(f.workspace, ?) = 1)"; filters.workspaces.len()]
+                    .join(" OR ");
+            sql.push_str(&format!(" AND ({prefixes})"));
             for w in filters.workspaces {
-                params.push(Box::new(w));
+                let w = w.trim_end_matches('/').to_string();
+                params.push(Box::new(w.clone()));
+                params.push(Box::new(format!("{w}/")));
+            }
+        }
+
+        if !filters.roles.is_empty() {
+            let placeholders = vec!["?"; filters.roles.len()].join(",");
+            sql.push_str(&format!(" AND m.role IN ({placeholders})"));
+            for r in filters.roles {
+                params.push(Box::new(r));
             }
         }
 
@@ -2442,6 +2898,11 @@ impl SearchClient {
             sql.push_str(" AND f.created_at <= ?");
             params.push(Box::new(created_to));
         }
+        if filters.code_only {
+            sql.push_str(
+                " AND EXISTS (SELECT 1 FROM snippets s WHERE s.message_id = f.message_id)",
+            );
+        }
 
         sql.push_str(" ORDER BY score LIMIT ? OFFSET ?");
         params.push(Box::new(limit as i64));
@@ -2587,14 +3048,14 @@ fn maybe_spawn_warm_worker(
                     (
                         Occur::Should,
                         Box::new(TermQuery::new(
-                            Term::from_field_text(fields.title, &term_lower),
+                            fields.title_term(&term_lower),
                             IndexRecordOption::WithFreqsAndPositions,
                         )),
                     ),
                     (
                         Occur::Should,
                         Box::new(TermQuery::new(
-                            Term::from_field_text(fields.content, &term_lower),
+                            fields.content_term(&term_lower),
                             IndexRecordOption::WithFreqsAndPositions,
                         )),
                     ),
@@ -2768,24 +3229,60 @@ fn filters_fingerprint(filters: &SearchFilters) -> String {
         parts.push(format!("to:{t}"));
     }
     // Include source_filter in cache key (P3.1)
-    if !matches!(
-        filters.source_filter,
-        crate::sources::provenance::SourceFilter::All
-    ) {
+    if filters.has_source_filter() {
         parts.push(format!("src:{:?}", filters.source_filter));
     }
+    if !filters.roles.is_empty() {
+        let mut v: Vec<_> = filters.roles.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("role:{v:?}"));
+    }
     // Include session_paths in cache key (for chained searches)
     if !filters.session_paths.is_empty() {
         let mut v: Vec<_> = filters.session_paths.iter().cloned().collect();
         v.sort();
         parts.push(format!("sp:{v:?}"));
     }
+    if filters.code_only {
+        parts.push("code".to_string());
+    }
+    if !filters.test_names.is_empty() {
+        let mut v: Vec<_> = filters.test_names.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("t:{v:?}"));
+    }
+    if let Some(rating) = filters.rating {
+        parts.push(format!("r:{rating:?}"));
+    }
+    if !filters.tags.is_empty() {
+        let mut v: Vec<_> = filters.tags.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("tg:{v:?}"));
+    }
+    if !filters.notes.is_empty() {
+        let mut v: Vec<_> = filters.notes.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("n:{v:?}"));
+    }
+    if !filters.dependencies.is_empty() {
+        let mut v: Vec<_> = filters.dependencies.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("d:{v:?}"));
+    }
+    if !filters.labels.is_empty() {
+        let mut v: Vec<_> = filters.labels.iter().cloned().collect();
+        v.sort();
+        parts.push(format!("l:{v:?}"));
+    }
     parts.join("|")
 }
 
 impl SearchClient {
     fn maybe_reload_reader(&self, reader: &IndexReader) -> Result<()> {
         const MIN_RELOAD_INTERVAL: Duration = Duration::from_millis(300);
+        if self.pinned_searcher.lock().unwrap().is_some() {
+            return Ok(());
+        }
         let now = Instant::now();
         let mut guard = self.last_reload.lock().unwrap();
         if guard
@@ -2920,6 +3417,53 @@ mod tests {
     use crate::search::tantivy::TantivyIndex;
     use tempfile::TempDir;
 
+    #[test]
+    fn repeated_sources_combine_into_one_source_filter() {
+        let mut filters = SearchFilters::default();
+        assert!(!filters.has_source_filter());
+        filters.source_filter = SourceFilter::any_of(vec![SourceFilter::Local]);
+        assert_eq!(filters.source_filter, SourceFilter::Local);
+
+        filters.source_filter = SourceFilter::any_of(vec![
+            SourceFilter::Remote,
+            SourceFilter::SourceId("laptop".into()),
+        ]);
+        assert_eq!(filters.source_filter.alternatives().len(), 2);
+        assert!(filters.has_source_filter());
+        filters.source_filter = SourceFilter::any_of(vec![SourceFilter::Remote, SourceFilter::All]);
+        assert!(!filters.has_source_filter());
+    }
+
+    #[test]
+    fn workspace_filters_match_prefixes() {
+        let mut filters = SearchFilters::default();
+        assert!(filters.workspace_matches("/anything"));
+        filters.workspaces.insert("/src/acme".into());
+        assert!(filters.workspace_matches("/src/acme"));
+        assert!(filters.workspace_matches("/src/acme/api"));
+        assert!(!filters.workspace_matches("/src/other"));
+        assert!(!filters.workspace_matches("/src/acmecorp"));
+    }
+
+    #[test]
+    fn split_field_filters_extracts_exact_values() {
+        assert_eq!(
+            split_field_filters("panic test:my_module::my_test", "test"),
+            ("panic".to_string(), vec!["my_module::my_test".to_string()])
+        );
+        assert_eq!(
+            split_field_filters("unit test: flaky", "test"),
+            ("unit test: flaky".to_string(), Vec::new())
+        );
+        assert_eq!(
+            split_field_filters("dep:tokio why dep:\"serde\"", "dep"),
+            (
+                "why".to_string(),
+                vec!["tokio".to_string(), "serde".to_string()]
+            )
+        );
+    }
+
     #[test]
     fn cache_prefix_lookup_handles_utf8_boundaries() {
         let client = SearchClient {
@@ -2935,6 +3479,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let hits = vec![SearchHit {
@@ -3021,6 +3568,7 @@ mod tests {
                     language: None,
                     snippet_text: None,
                 }],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3037,6 +3585,188 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn stemmed_content_matches_inflections_but_plain_title_stays_exact() -> Result<()> {
+        use crate::search::analyzers::{AnalyzerConfig, FieldAnalyzer};
+
+        let dir = TempDir::new()?;
+        let analyzers = AnalyzerConfig {
+            title: FieldAnalyzer::Plain,
+            content: FieldAnalyzer::Stemmed,
+        };
+        let mut index = TantivyIndex::open_or_create_with(dir.path(), analyzers)?;
+        let conv = NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: None,
+            title: Some("panda notes".into()),
+            workspace: None,
+            source_path: dir.path().join("rollout-1.jsonl"),
+            started_at: Some(1_700_000_000_000),
+            ended_at: None,
+            metadata: serde_json::json!({}),
+            messages: vec![NormalizedMessage {
+                idx: 0,
+                role: "user".into(),
+                author: None,
+                created_at: Some(1_700_000_000_000),
+                content: "the migrations kept failing".into(),
+                extra: serde_json::json!({}),
+                snippets: vec![],
+                source: None,
+            }],
+        };
+        index.add_conversation(&conv)?;
+        index.commit()?;
+
+        let client = SearchClient::open(dir.path(), None)?.expect("index present");
+        assert_eq!(
+            client
+                .search("migration fails", SearchFilters::default(), 10, 0)?
+                .len(),
+            1
+        );
+        assert_eq!(
+            client
+                .search("\"kept fails\"", SearchFilters::default(), 10, 0)?
+                .len(),
+            1
+        );
+        assert!(
+            client
+                .search("pandas", SearchFilters::default(), 10, 0)?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn identifier_terms_hit_even_when_analyzer_drops_them() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut index = TantivyIndex::open_or_create(dir.path())?;
+        let hash = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let conv = NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: None,
+            title: None,
+            workspace: None,
+            source_path: dir.path().join("rollout-1.jsonl"),
+            started_at: Some(1_700_000_000_000),
+            ended_at: None,
+            metadata: serde_json::json!({}),
+            messages: vec![NormalizedMessage {
+                idx: 0,
+                role: "tool".into(),
+                author: None,
+                created_at: Some(1_700_000_000_000),
+                content: format!("checksum {hash} mismatch reading src/main.rs (ENOENT)"),
+                extra: serde_json::json!({}),
+                snippets: vec![],
+                source: None,
+            }],
+        };
+        index.add_conversation(&conv)?;
+        index.commit()?;
+
+        let client = SearchClient::open(dir.path(), None)?.expect("index present");
+        for query in [hash, "src/main.rs", "ENOENT"] {
+            let hits = client.search(query, SearchFilters::default(), 10, 0)?;
+            assert_eq!(hits.len(), 1, "query {query}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn label_filters_match_message_and_conversation_labels() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut index = TantivyIndex::open_or_create(dir.path())?;
+        let msg = |idx: i64, content: &str| NormalizedMessage {
+            idx,
+            role: "assistant".into(),
+            author: None,
+            created_at: Some(1_700_000_000_000),
+            content: content.into(),
+            extra: serde_json::json!({}),
+            snippets: Vec::new(),
+            source: None,
+        };
+        let mut conv = NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: Some("s1".into()),
+            title: None,
+            workspace: None,
+            source_path: dir.path().join("rollout-1.jsonl"),
+            started_at: Some(1_700_000_000_000),
+            ended_at: None,
+            metadata: serde_json::json!({}),
+            messages: vec![
+                msg(0, "build the parser"),
+                msg(1, "build it with\n```rust\nfn parse() {}\n```"),
+                msg(2, "Done. All tests pass."),
+            ],
+        };
+        crate::indexer::classify::Classifiers::default().apply(&mut [&mut conv]);
+        index.add_conversation(&conv)?;
+        index.commit()?;
+
+        let client = SearchClient::open(dir.path(), None)?.expect("index present");
+        let search = |q: &str| client.search(q, SearchFilters::default(), 10, 0);
+        assert_eq!(search("build")?.len(), 2);
+        let hits = search("build label:has_code")?;
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].content.contains("fn parse"));
+        assert_eq!(search("build label:outcome=success")?.len(), 2);
+        assert_eq!(
+            search("build label:outcome=success label:language=rust")?.len(),
+            1
+        );
+        assert!(search("build label:outcome=failure")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn pinned_snapshot_ignores_later_commits() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut index = TantivyIndex::open_or_create(dir.path())?;
+        let conv = |name: &str, content: &str| NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: None,
+            title: Some(name.into()),
+            workspace: None,
+            source_path: dir.path().join(format!("{name}.jsonl")),
+            started_at: Some(1_700_000_000_000),
+            ended_at: None,
+            metadata: serde_json::json!({}),
+            messages: vec![NormalizedMessage {
+                idx: 0,
+                role: "user".into(),
+                author: None,
+                created_at: Some(1_700_000_000_000),
+                content: content.into(),
+                extra: serde_json::json!({}),
+                snippets: Vec::new(),
+                source: None,
+            }],
+        };
+        index.add_conversation(&conv("first", "alpha export"))?;
+        index.commit()?;
+
+        let client = SearchClient::open(dir.path(), None)?.expect("index present");
+        let generation = client.pin_snapshot()?;
+        assert!(generation.is_some());
+
+        index.add_conversation(&conv("second", "beta export"))?;
+        index.commit()?;
+
+        let hits = client.search("export", SearchFilters::default(), 10, 0)?;
+        assert_eq!(hits.len(), 1);
+        assert!(
+            client
+                .search("beta", SearchFilters::default(), 10, 0)?
+                .is_empty()
+        );
+        Ok(())
+    }
+
     #[test]
     fn search_honors_created_range_and_workspace() -> Result<()> {
         let dir = TempDir::new()?;
@@ -3065,6 +3795,7 @@ mod tests {
                     language: None,
                     snippet_text: None,
                 }],
+                source: None,
             }],
         };
         let conv_b = NormalizedConversation {
@@ -3090,6 +3821,7 @@ mod tests {
                     language: None,
                     snippet_text: None,
                 }],
+                source: None,
             }],
         };
         index.add_conversation(&conv_a)?;
@@ -3137,6 +3869,7 @@ mod tests {
                         language: None,
                         snippet_text: None,
                     }],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -3176,6 +3909,7 @@ mod tests {
                     language: None,
                     snippet_text: None,
                 }],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3209,6 +3943,7 @@ mod tests {
                 content: "please calculate the entropy".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3249,6 +3984,7 @@ mod tests {
                 content: "check the my_variable_name please".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3288,6 +4024,7 @@ mod tests {
                 content: "working with c++ and foo.bar today".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3328,6 +4065,7 @@ mod tests {
                 content: "the request handler delegates".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3372,6 +4110,7 @@ mod tests {
                 content: "the request handler delegates".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -3405,6 +4144,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let hits = client.search("*handler", SearchFilters::default(), 5, 0)?;
@@ -3439,6 +4181,7 @@ mod tests {
                 content: "apple banana".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv1)?;
@@ -3477,6 +4220,7 @@ mod tests {
                 content: "apricot".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv2)?;
@@ -3520,6 +4264,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let hit = SearchHit {
@@ -3574,6 +4321,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let hit = SearchHit {
@@ -3624,6 +4374,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         client.metrics.inc_cache_hits();
@@ -3656,6 +4409,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let hit = SearchHit {
@@ -3717,6 +4473,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         // Large content to exceed byte cap quickly
@@ -4304,6 +5063,7 @@ mod tests {
                     content: format!("apple fruit number {i} is delicious and healthy"),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -4350,6 +5110,7 @@ mod tests {
                 content: "configuration management system".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -4395,6 +5156,7 @@ mod tests {
                 content: "testing data".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -4446,6 +5208,7 @@ mod tests {
                     content: body.to_string(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -4491,6 +5254,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: "vtest|schema:none".into(),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let result = client.search_with_fallback("ghost", SearchFilters::default(), 5, 0, 3)?;
@@ -4536,6 +5302,7 @@ mod tests {
                 content: "testing data".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -4566,6 +5333,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: "vtest|schema:none".into(),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let result = client.search_with_fallback("ghost", SearchFilters::default(), 5, 10, 3)?;
@@ -4601,6 +5371,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: "vtest|schema:none".into(),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let mut filters = SearchFilters::default();
@@ -4800,6 +5573,7 @@ mod tests {
                 content: "hello world findme alpha".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         // Agent B (claude)
@@ -4820,6 +5594,7 @@ mod tests {
                 content: "hello world findme beta".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv_a)?;
@@ -4877,6 +5652,7 @@ mod tests {
                 content: "workspace test needle".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         // Workspace B
@@ -4897,6 +5673,7 @@ mod tests {
                 content: "workspace test needle".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv_a)?;
@@ -4957,6 +5734,7 @@ mod tests {
                 content: "date range test".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         // Middle doc (ts=500)
@@ -4977,6 +5755,7 @@ mod tests {
                 content: "date range test".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         // Late doc (ts=900)
@@ -4997,6 +5776,7 @@ mod tests {
                 content: "date range test".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv_early)?;
@@ -5073,6 +5853,7 @@ mod tests {
                     content: "hello world combotest query".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -5132,6 +5913,7 @@ mod tests {
                 content: "source filter test local".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         // Remote source doc (would need to be indexed with ssh origin_kind)
@@ -5181,6 +5963,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn remote_filter_covers_every_non_local_kind() -> Result<()> {
+        let dir = TempDir::new()?;
+        let mut index = TantivyIndex::open_or_create(dir.path())?;
+        let conv = |name: &str, origin: serde_json::Value| NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: Some(name.into()),
+            title: Some(name.into()),
+            workspace: None,
+            source_path: dir.path().join(format!("{name}.jsonl")),
+            started_at: Some(100),
+            ended_at: None,
+            metadata: serde_json::json!({ "cass": { "origin": origin } }),
+            messages: vec![NormalizedMessage {
+                idx: 0,
+                role: "user".into(),
+                author: None,
+                created_at: Some(100),
+                content: "remotefilter probe".into(),
+                extra: serde_json::json!({}),
+                snippets: vec![],
+                source: None,
+            }],
+        };
+        index.add_conversation(&conv(
+            "here",
+            serde_json::json!({"source_id": "local", "kind": "local"}),
+        ))?;
+        index.add_conversation(&conv(
+            "laptop",
+            serde_json::json!({"source_id": "laptop", "kind": "ssh"}),
+        ))?;
+        index.add_conversation(&conv(
+            "bucket",
+            serde_json::json!({"source_id": "bucket", "kind": "rclone"}),
+        ))?;
+        index.commit()?;
+
+        let client = SearchClient::open(dir.path(), None)?.expect("index present");
+        let filters = SearchFilters {
+            source_filter: SourceFilter::Remote,
+            ..Default::default()
+        };
+        let mut sources: Vec<String> = client
+            .search("remotefilter", filters, 10, 0)?
+            .into_iter()
+            .map(|h| h.source_id)
+            .collect();
+        sources.sort();
+        assert_eq!(sources, ["bucket", "laptop"]);
+        Ok(())
+    }
+
     #[test]
     fn filter_fidelity_cache_key_isolation() {
         // Different filters should have different cache keys
@@ -5197,6 +6032,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let filters_empty = SearchFilters::default();
@@ -5448,6 +6286,7 @@ mod tests {
                 content: "alpha beta gamma".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         let conv2 = NormalizedConversation {
@@ -5467,6 +6306,7 @@ mod tests {
                 content: "alpha delta".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv1)?;
@@ -5510,6 +6350,7 @@ mod tests {
                 content: "unique xyzzy term".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         let conv2 = NormalizedConversation {
@@ -5529,6 +6370,7 @@ mod tests {
                 content: "unique plugh term".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv1)?;
@@ -5566,6 +6408,7 @@ mod tests {
                 content: "nottest keep this".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         let conv2 = NormalizedConversation {
@@ -5585,6 +6428,7 @@ mod tests {
                 content: "nottest exclude this".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv1)?;
@@ -5631,6 +6475,7 @@ mod tests {
                 content: "the quick brown fox".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         let conv2 = NormalizedConversation {
@@ -5650,6 +6495,7 @@ mod tests {
                 content: "the brown quick fox".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv1)?;
@@ -5692,6 +6538,7 @@ mod tests {
                 content: "foo bar baz".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -5881,6 +6728,7 @@ mod tests {
                     content: (*content).into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -5934,6 +6782,7 @@ mod tests {
                     content: format!("needle from {agent}"),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -5977,6 +6826,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         // Initial metrics should be zero
@@ -6013,6 +6865,9 @@ mod tests {
             metrics: Metrics::default(),
             cache_namespace: format!("v{CACHE_KEY_VERSION}|schema:test"),
             semantic: Mutex::new(None),
+            pinned_searcher: Mutex::new(None),
+            click_feedback: false,
+            index_error: Mutex::new(None),
         };
 
         let filters1 = SearchFilters::default();
@@ -6065,6 +6920,7 @@ mod tests {
                 content: "unique specific term here".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
 
@@ -6085,6 +6941,7 @@ mod tests {
                 content: "unique specific also here".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
 
@@ -6127,6 +6984,7 @@ mod tests {
                 content: "authentication authorization oauth".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
@@ -6176,6 +7034,7 @@ mod tests {
                     content: "Help me implement JWT authentication for my Express API".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 },
                 NormalizedMessage {
                     idx: 1,
@@ -6191,6 +7050,7 @@ mod tests {
                         language: Some("json".into()),
                         snippet_text: Some(r#"{"dependencies":{"jsonwebtoken":"^9.0.0"}}"#.into()),
                     }],
+                    source: None,
                 },
                 NormalizedMessage {
                     idx: 2,
@@ -6200,6 +7060,7 @@ mod tests {
                     content: "Can you also add refresh token support?".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 },
             ],
         };
@@ -6257,6 +7118,7 @@ mod tests {
                     content: "implement the sorting algorithm".into(),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -6314,6 +7176,7 @@ mod tests {
                     content: format!("needle content for session {}", i),
                     extra: serde_json::json!({}),
                     snippets: vec![],
+                    source: None,
                 }],
             };
             index.add_conversation(&conv)?;
@@ -6377,6 +7240,7 @@ mod tests {
                 content: "needle content".into(),
                 extra: serde_json::json!({}),
                 snippets: vec![],
+                source: None,
             }],
         };
         index.add_conversation(&conv)?;
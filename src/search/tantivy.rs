@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -11,9 +11,12 @@ use tantivy::{Index, IndexReader, IndexWriter, doc};
 use tracing::{debug, info, warn};
 
 use crate::connectors::NormalizedConversation;
+use crate::search::analyzers::{self, AnalyzerConfig, PLAIN_TOKENIZER};
 use crate::sources::provenance::LOCAL_SOURCE_ID;
 
-const SCHEMA_VERSION: &str = "v6";
+// Index directory layout version (`<data_dir>/index/<version>`). Older directories are
+// migrated by rebuilding from SQLite, see `legacy_index_dirs`.
+const SCHEMA_VERSION: &str = "v7";
 
 /// Minimum time (ms) between merge operations
 const MERGE_COOLDOWN_MS: i64 = 300_000; // 5 minutes
@@ -48,7 +51,7 @@ impl MergeStatus {
 }
 
 // Bump this when schema/tokenizer changes. Used to trigger rebuilds.
-pub const SCHEMA_HASH: &str = "tantivy-schema-v6-workspace-original";
+pub const SCHEMA_HASH: &str = "tantivy-schema-v13-role";
 
 #[derive(Clone, Copy)]
 pub struct Fields {
@@ -67,6 +70,48 @@ pub struct Fields {
     pub source_id: Field,
     pub origin_kind: Field,
     pub origin_host: Field,
+    /// Fenced code blocks only (for code-only search)
+    pub code: Field,
+    pub code_prefix: Field,
+    /// Unique per conversation (see `doc_key`); lets a conversation be deleted and re-added
+    pub doc_key: Field,
+    /// Failed/fixed test names from tool output (`test:` searches)
+    pub test: Field,
+    /// Packages added/installed/edited in the message (`dep:` searches)
+    pub dep: Field,
+    /// Identifier-shaped content tokens kept whole (paths, symbols, hashes, error codes)
+    pub ident: Field,
+    /// Classifier labels as `key` and `key=value` terms (`label:` searches)
+    pub label: Field,
+    /// Message role, lowercased (`--role` filters)
+    pub role: Field,
+    /// Analyzers the `title`/`content` fields were indexed with
+    pub analyzers: AnalyzerConfig,
+}
+
+impl Fields {
+    /// These fields with title/content swapped for the code fields, so the
+    /// query builders match fenced code snippets only.
+    pub fn code_only(self) -> Self {
+        Self {
+            title: self.code,
+            content: self.code,
+            title_prefix: self.code_prefix,
+            content_prefix: self.code_prefix,
+            analyzers: AnalyzerConfig::default(),
+            ..self
+        }
+    }
+
+    /// Query term for `title`, normalized like the indexed titles.
+    pub fn title_term(&self, text: &str) -> tantivy::Term {
+        tantivy::Term::from_field_text(self.title, &self.analyzers.title.normalize_term(text))
+    }
+
+    /// Query term for `content`, normalized like the indexed message bodies.
+    pub fn content_term(&self, text: &str) -> tantivy::Term {
+        tantivy::Term::from_field_text(self.content, &self.analyzers.content.normalize_term(text))
+    }
 }
 
 pub struct TantivyIndex {
@@ -76,9 +121,17 @@ pub struct TantivyIndex {
 }
 
 impl TantivyIndex {
+    /// Open or create the index using the `[analyzers]` from `config.toml`.
     pub fn open_or_create(path: &Path) -> Result<Self> {
+        Self::open_or_create_with(path, AnalyzerConfig::load())
+    }
+
+    /// Open or create the index. An existing index built with different analyzers
+    /// is an error rather than being recreated empty: only `run_index` rebuilds it,
+    /// refilling it from the database.
+    pub fn open_or_create_with(path: &Path, analyzers: AnalyzerConfig) -> Result<Self> {
         // Schema we will use if we need to (re)create the index.
-        let schema = build_schema();
+        let schema = build_schema_with(analyzers);
         std::fs::create_dir_all(path)?;
 
         let meta_path = path.join("schema_hash.json");
@@ -102,7 +155,15 @@ impl TantivyIndex {
             // We believe the schema hash matches; try to open. If this fails
             // (e.g. corrupted meta.json / index), fall back to a clean rebuild.
             match Index::open_in_dir(path) {
-                Ok(idx) => idx,
+                Ok(idx) if AnalyzerConfig::of_schema(&idx.schema()) == Some(analyzers) => idx,
+                Ok(idx) => {
+                    return Err(anyhow!(
+                        "search index {} was built with analyzers {:?}, but [analyzers] is now {:?}; run `cass index` to rebuild it",
+                        path.display(),
+                        AnalyzerConfig::of_schema(&idx.schema()),
+                        analyzers
+                    ));
+                }
                 Err(e) => {
                     warn!(
                         error = %e,
@@ -141,11 +202,50 @@ impl TantivyIndex {
         self.add_messages(conv, &conv.messages)
     }
 
+    /// Delete every document of `conv` (matched by its doc key).
+    /// Takes effect on the next `commit()`.
+    pub fn delete_conversation(&mut self, conv: &NormalizedConversation) {
+        let term = tantivy::Term::from_field_text(self.fields.doc_key, &doc_key(conv));
+        self.writer.delete_term(term);
+    }
+
+    /// Delete every document of a session from one source (the `doc_key` of a
+    /// conversation with an external id). Takes effect on the next `commit()`.
+    pub fn delete_session(&mut self, source_id: &str, agent_slug: &str, external_id: &str) {
+        self.delete_doc_key(&stored_doc_key(
+            source_id,
+            agent_slug,
+            Some(external_id),
+            "",
+        ));
+    }
+
+    /// Delete every document with this doc key (see [`stored_doc_key`]).
+    /// Takes effect on the next `commit()`.
+    pub fn delete_doc_key(&mut self, key: &str) {
+        let term = tantivy::Term::from_field_text(self.fields.doc_key, key);
+        self.writer.delete_term(term);
+    }
+
+    /// Replace all indexed messages of `conv` with its current messages.
+    /// Deletes only apply to documents added before them, so this is safe within one commit.
+    pub fn replace_conversation(&mut self, conv: &NormalizedConversation) -> Result<()> {
+        self.delete_conversation(conv);
+        self.add_conversation(conv)
+    }
+
     pub fn delete_all(&mut self) -> Result<()> {
         self.writer.delete_all_documents()?;
         Ok(())
     }
 
+    /// Delete every document originating from `source_path`.
+    /// Takes effect on the next `commit()`.
+    pub fn delete_by_source_path(&mut self, source_path: &str) {
+        let term = tantivy::Term::from_field_text(self.fields.source_path, source_path);
+        self.writer.delete_term(term);
+    }
+
     pub fn commit(&mut self) -> Result<()> {
         self.writer.commit()?;
         Ok(())
@@ -263,16 +363,31 @@ impl TantivyIndex {
         conv: &NormalizedConversation,
         messages: &[crate::connectors::NormalizedMessage],
     ) -> Result<()> {
+        let key = doc_key(conv);
+        let (source_id, origin_kind) = origin_of(conv);
+        // Tests failing anywhere in the conversation, so later passing runs count as fixes
+        let failed_tests = crate::search::test_names::failed_tests(
+            conv.messages.iter().map(|m| m.content.as_str()),
+        );
+        let labels = crate::indexer::classify::stored_labels(conv);
         for msg in messages {
             let mut d = doc! {
+                self.fields.doc_key => key.clone(),
                 self.fields.agent => conv.agent_slug.clone(),
                 self.fields.source_path => conv.source_path.to_string_lossy().into_owned(),
                 self.fields.msg_idx => msg.idx as u64,
                 self.fields.content => msg.content.clone(),
-                // Provenance fields - default to local until Phase 2 adds origin to NormalizedConversation
-                self.fields.source_id => LOCAL_SOURCE_ID,
-                self.fields.origin_kind => "local",
+                self.fields.source_id => source_id,
+                self.fields.origin_kind => origin_kind,
+                self.fields.role => msg.role.to_lowercase(),
             };
+            if let Some(host) = conv
+                .metadata
+                .pointer("/cass/origin/host")
+                .and_then(|v| v.as_str())
+            {
+                d.add_text(self.fields.origin_host, host);
+            }
             if let Some(ws) = &conv.workspace {
                 d.add_text(self.fields.workspace, ws.to_string_lossy());
             }
@@ -297,6 +412,23 @@ impl TantivyIndex {
                 generate_edge_ngrams(&msg.content),
             );
             d.add_text(self.fields.preview, build_preview(&msg.content, 400));
+            let code = code_text(msg);
+            if !code.is_empty() {
+                d.add_text(self.fields.code_prefix, generate_edge_ngrams(&code));
+                d.add_text(self.fields.code, code);
+            }
+            for name in crate::search::test_names::index_terms(&msg.content, &failed_tests) {
+                d.add_text(self.fields.test, name);
+            }
+            for name in crate::search::deps::extract_dependencies(&msg.content) {
+                d.add_text(self.fields.dep, name);
+            }
+            for ident in crate::search::identifiers::extract_identifiers(&msg.content) {
+                d.add_text(self.fields.ident, ident);
+            }
+            for term in crate::indexer::classify::index_terms(&labels, msg.idx) {
+                d.add_text(self.fields.label, term);
+            }
             // Note: origin_host not added here as it's empty for local sources
             // Will be populated in Phase 2 when NormalizedConversation has origin
             self.writer.add_document(d)?;
@@ -305,6 +437,67 @@ impl TantivyIndex {
     }
 }
 
+/// Stable identity of a conversation in the index: source, agent and external id,
+/// falling back to the source path for connectors without session ids. Mirrors the
+/// `(source_id, agent_id, external_id)` uniqueness of the SQLite `conversations` table.
+pub fn doc_key(conv: &NormalizedConversation) -> String {
+    let (source_id, _) = origin_of(conv);
+    stored_doc_key(
+        source_id,
+        &conv.agent_slug,
+        conv.external_id.as_deref(),
+        &conv.source_path.to_string_lossy(),
+    )
+}
+
+/// [`doc_key`] of a conversation as stored in SQLite (`source_path` is only used
+/// without an external id).
+pub fn stored_doc_key(
+    source_id: &str,
+    agent_slug: &str,
+    external_id: Option<&str>,
+    source_path: &str,
+) -> String {
+    match external_id {
+        Some(ext) => format!("{source_id}/{agent_slug}/id:{ext}"),
+        None => format!("{source_id}/{agent_slug}/path:{source_path}"),
+    }
+}
+
+/// `(source_id, origin_kind)` from the `cass.origin` provenance injected at scan time.
+fn origin_of(conv: &NormalizedConversation) -> (&str, &str) {
+    let origin = conv.metadata.pointer("/cass/origin");
+    let source_id = origin
+        .and_then(|o| o.get("source_id"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(LOCAL_SOURCE_ID);
+    let kind = origin
+        .and_then(|o| o.get("kind"))
+        .and_then(|v| v.as_str())
+        .unwrap_or(if source_id == LOCAL_SOURCE_ID {
+            "local"
+        } else {
+            "ssh"
+        });
+    (source_id, kind)
+}
+
+/// Concatenated code snippets of a message: connector-supplied ones, else fenced blocks.
+fn code_text(msg: &crate::connectors::NormalizedMessage) -> String {
+    let extracted;
+    let snippets = if msg.snippets.is_empty() {
+        extracted = crate::connectors::extract_code_snippets(&msg.content);
+        &extracted
+    } else {
+        &msg.snippets
+    };
+    snippets
+        .iter()
+        .filter_map(|s| s.snippet_text.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn generate_edge_ngrams(text: &str) -> String {
     let mut ngrams = String::with_capacity(text.len() * 2);
     // Split by non-alphanumeric characters to identify words
@@ -338,19 +531,27 @@ fn generate_edge_ngrams(text: &str) -> String {
     ngrams
 }
 
+/// Schema with the default (plain) analyzers.
 pub fn build_schema() -> Schema {
+    build_schema_with(AnalyzerConfig::default())
+}
+
+pub fn build_schema_with(analyzers: AnalyzerConfig) -> Schema {
     let mut schema_builder = Schema::builder();
-    let text = TextOptions::default()
-        .set_indexing_options(
-            TextFieldIndexing::default()
-                .set_tokenizer("hyphen_normalize")
-                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
-        )
-        .set_stored();
+    let text = |tokenizer: &str| {
+        TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(tokenizer)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+            .set_stored()
+    };
 
+    // Prefix n-grams and code stay plain: stemming a prefix or an identifier never helps
     let text_not_stored = TextOptions::default().set_indexing_options(
         TextFieldIndexing::default()
-            .set_tokenizer("hyphen_normalize")
+            .set_tokenizer(PLAIN_TOKENIZER)
             .set_index_option(IndexRecordOption::WithFreqsAndPositions),
     );
 
@@ -360,18 +561,34 @@ pub fn build_schema() -> Schema {
     schema_builder.add_text_field("workspace", STRING | STORED);
     // workspace_original stores the pre-rewrite path for audit/display (P6.2)
     schema_builder.add_text_field("workspace_original", STORED);
-    schema_builder.add_text_field("source_path", STORED);
+    // source_path is indexed as a single term so stale sessions can be deleted by path (--gc)
+    schema_builder.add_text_field("source_path", STRING | STORED);
     schema_builder.add_u64_field("msg_idx", INDEXED | STORED);
     schema_builder.add_i64_field("created_at", INDEXED | STORED | FAST);
-    schema_builder.add_text_field("title", text.clone());
-    schema_builder.add_text_field("content", text);
+    schema_builder.add_text_field("title", text(analyzers.title.tokenizer_name()));
+    schema_builder.add_text_field("content", text(analyzers.content.tokenizer_name()));
     schema_builder.add_text_field("title_prefix", text_not_stored.clone());
-    schema_builder.add_text_field("content_prefix", text_not_stored);
+    schema_builder.add_text_field("content_prefix", text_not_stored.clone());
     schema_builder.add_text_field("preview", TEXT | STORED);
     // Provenance fields (P1.4) - STRING for exact match filtering
     schema_builder.add_text_field("source_id", STRING | STORED);
     schema_builder.add_text_field("origin_kind", STRING | STORED);
     schema_builder.add_text_field("origin_host", STRING | STORED);
+    // Code snippets (fenced blocks) for code-only search; the content itself is stored already
+    schema_builder.add_text_field("code", text_not_stored.clone());
+    schema_builder.add_text_field("code_prefix", text_not_stored);
+    // Conversation identity as a single term so re-indexing can delete before re-adding
+    schema_builder.add_text_field("doc_key", STRING | STORED);
+    // One untokenized term per failed/fixed test name (and its `::` suffixes)
+    schema_builder.add_text_field("test", STRING);
+    // Normalized package names from install commands and manifest edits
+    schema_builder.add_text_field("dep", STRING);
+    // Raw keyword shadow of identifier-like content tokens, for exact lookups
+    schema_builder.add_text_field("ident", STRING);
+    // Index-time classifier labels, exact `key` / `key=value` terms
+    schema_builder.add_text_field("label", STRING);
+    // Message role as a single term: user, assistant, system, tool
+    schema_builder.add_text_field("role", STRING);
     schema_builder.build()
 }
 
@@ -396,6 +613,15 @@ pub fn fields_from_schema(schema: &Schema) -> Result<Fields> {
         source_id: get("source_id")?,
         origin_kind: get("origin_kind")?,
         origin_host: get("origin_host")?,
+        code: get("code")?,
+        code_prefix: get("code_prefix")?,
+        doc_key: get("doc_key")?,
+        test: get("test")?,
+        dep: get("dep")?,
+        ident: get("ident")?,
+        label: get("label")?,
+        role: get("role")?,
+        analyzers: AnalyzerConfig::of_schema(schema).unwrap_or_default(),
     })
 }
 
@@ -427,13 +653,40 @@ pub fn index_dir(base: &Path) -> Result<std::path::PathBuf> {
     Ok(dir)
 }
 
+/// Index directories from older layout versions under `<base>/index`.
+pub fn legacy_index_dirs(base: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(base.join("index")) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.file_name() != SCHEMA_VERSION && e.path().join("meta.json").exists())
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
 pub fn ensure_tokenizer(index: &mut Index) {
-    use tantivy::tokenizer::{LowerCaser, RemoveLongFilter, SimpleTokenizer, TextAnalyzer};
-    let analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
-        .filter(LowerCaser)
-        .filter(RemoveLongFilter::limit(40))
-        .build();
-    index.tokenizers().register("hyphen_normalize", analyzer);
+    analyzers::register(index);
+}
+
+/// Open an existing index for searching. Fails when the index is missing or
+/// damaged (unreadable `meta.json`, missing or truncated segment files).
+pub fn open_reader(path: &Path) -> Result<(IndexReader, Fields)> {
+    let mut index = Index::open_in_dir(path)?;
+    ensure_tokenizer(&mut index);
+    let fields = fields_from_schema(&index.schema())?;
+    Ok((index.reader()?, fields))
+}
+
+/// Why an index that exists on disk can't be opened, or None if it opens (or
+/// hasn't been built yet).
+pub fn corruption(path: &Path) -> Option<String> {
+    if !path.join("meta.json").exists() {
+        return None;
+    }
+    open_reader(path).err().map(|e| format!("{e:#}"))
 }
 
 // =============================================================================
@@ -570,6 +823,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn open_or_create_refuses_an_index_built_with_other_analyzers() {
+        use crate::search::analyzers::{AnalyzerConfig, FieldAnalyzer};
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path();
+        {
+            let mut index =
+                TantivyIndex::open_or_create_with(path, AnalyzerConfig::default()).unwrap();
+            let doc = doc! {
+                index.fields.agent => "test_agent",
+                index.fields.content => "kept content",
+            };
+            index.writer.add_document(doc).unwrap();
+            index.commit().unwrap();
+        }
+
+        let stemmed = AnalyzerConfig {
+            title: FieldAnalyzer::Plain,
+            content: FieldAnalyzer::Stemmed,
+        };
+        let err = TantivyIndex::open_or_create_with(path, stemmed)
+            .err()
+            .expect("analyzer mismatch is an error");
+        assert!(err.to_string().contains("cass index"), "{err}");
+
+        // The existing documents are left for `run_index` to rebuild from
+        let index = TantivyIndex::open_or_create_with(path, AnalyzerConfig::default()).unwrap();
+        assert_eq!(index.reader().unwrap().searcher().num_docs(), 1);
+    }
+
+    #[test]
+    fn corruption_reported_only_for_damaged_indexes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path();
+        assert_eq!(corruption(path), None, "a missing index isn't corrupt");
+
+        {
+            let _index = TantivyIndex::open_or_create(path).unwrap();
+        }
+        assert_eq!(corruption(path), None);
+        assert!(open_reader(path).is_ok());
+
+        fs::write(path.join("meta.json"), "{ not json").unwrap();
+        assert!(corruption(path).is_some());
+        assert!(open_reader(path).is_err());
+    }
+
     #[test]
     fn open_or_create_handles_truncated_segment_file() {
         let dir = TempDir::new().unwrap();
@@ -718,6 +1019,8 @@ mod tests {
         let _ = fields.source_id;
         let _ = fields.origin_kind;
         let _ = fields.origin_host;
+        let _ = fields.code;
+        let _ = fields.code_prefix;
     }
 
     #[test]
@@ -1117,6 +1420,147 @@ mod tests {
         );
     }
 
+    #[test]
+    fn delete_by_source_path_removes_only_matching_docs() {
+        let dir = TempDir::new().unwrap();
+        let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+        for (path, idx) in [
+            ("/gone.jsonl", 0u64),
+            ("/gone.jsonl", 1),
+            ("/kept.jsonl", 0),
+        ] {
+            let d = doc! {
+                index.fields.agent => "codex",
+                index.fields.source_path => path,
+                index.fields.msg_idx => idx,
+                index.fields.content => "hello",
+            };
+            index.writer.add_document(d).unwrap();
+        }
+        index.commit().unwrap();
+
+        index.delete_by_source_path("/gone.jsonl");
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 1);
+    }
+
+    #[test]
+    fn replace_conversation_swaps_docs_by_key() {
+        use crate::connectors::NormalizedMessage;
+        let dir = TempDir::new().unwrap();
+        let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+        let conv = |external_id: &str, contents: &[&str]| NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: Some(external_id.into()),
+            title: None,
+            workspace: None,
+            source_path: "/sessions/shared.jsonl".into(),
+            started_at: None,
+            ended_at: None,
+            metadata: serde_json::json!({}),
+            messages: contents
+                .iter()
+                .enumerate()
+                .map(|(i, c)| NormalizedMessage {
+                    idx: i as i64,
+                    role: "user".into(),
+                    author: None,
+                    created_at: None,
+                    content: (*c).into(),
+                    extra: serde_json::json!({}),
+                    snippets: Vec::new(),
+                    source: None,
+                })
+                .collect(),
+        };
+
+        index.add_conversation(&conv("a", &["one", "two"])).unwrap();
+        index.add_conversation(&conv("b", &["other"])).unwrap();
+        index.commit().unwrap();
+
+        // Same key, edited history: old docs go, siblings in the same file stay
+        index
+            .replace_conversation(&conv("a", &["one", "two", "three"]))
+            .unwrap();
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 4);
+        assert_ne!(doc_key(&conv("a", &[])), doc_key(&conv("b", &[])));
+    }
+
+    #[test]
+    fn documents_carry_conversation_provenance() {
+        use crate::connectors::NormalizedMessage;
+        let dir = TempDir::new().unwrap();
+        let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+        let conv = |metadata: serde_json::Value| NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: Some("s1".into()),
+            title: None,
+            workspace: None,
+            source_path: "/sessions/s1.jsonl".into(),
+            started_at: None,
+            ended_at: None,
+            metadata,
+            messages: vec![NormalizedMessage {
+                idx: 0,
+                role: "user".into(),
+                author: None,
+                created_at: None,
+                content: "hello".into(),
+                extra: serde_json::json!({}),
+                snippets: Vec::new(),
+                source: None,
+            }],
+        };
+        index
+            .add_conversation(&conv(serde_json::json!({
+                "cass": {"origin": {"source_id": "laptop", "kind": "ssh", "host": "laptop"}}
+            })))
+            .unwrap();
+        index
+            .add_conversation(&conv(serde_json::json!({})))
+            .unwrap();
+        index.commit().unwrap();
+
+        let reader = index.reader().unwrap();
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        let count = |field, value: &str| {
+            searcher
+                .search(
+                    &tantivy::query::TermQuery::new(
+                        tantivy::Term::from_field_text(field, value),
+                        tantivy::schema::IndexRecordOption::Basic,
+                    ),
+                    &tantivy::collector::Count,
+                )
+                .unwrap()
+        };
+        assert_eq!(count(index.fields.source_id, "laptop"), 1);
+        assert_eq!(count(index.fields.origin_kind, "ssh"), 1);
+        assert_eq!(count(index.fields.origin_kind, "local"), 1);
+    }
+
+    #[test]
+    fn legacy_index_dirs_lists_older_layouts() {
+        let dir = TempDir::new().unwrap();
+        let current = index_dir(dir.path()).unwrap();
+        fs::write(current.join("meta.json"), "{}").unwrap();
+        let old = dir.path().join("index").join("v6");
+        fs::create_dir_all(&old).unwrap();
+        fs::write(old.join("meta.json"), "{}").unwrap();
+        fs::create_dir_all(dir.path().join("index").join("empty")).unwrap();
+
+        assert_eq!(legacy_index_dirs(dir.path()), vec![old]);
+    }
+
     #[test]
     fn rebuild_empty_index_has_zero_docs() {
         let dir = TempDir::new().unwrap();
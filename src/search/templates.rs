@@ -0,0 +1,244 @@
+//! Parameterized query templates defined in `config.toml`.
+//!
+//! ```toml
+//! [query_templates.dep_changes]
+//! description = "Why a dependency was added or upgraded"
+//! query = "dep:{pkg} {reason}"
+//! defaults = { reason = "upgrade OR add" }
+//!
+//! [query_templates.test_regressions]
+//! query = "test:{test} \"{error}\" -workspace:~/scratch"
+//! ```
+//!
+//! `cass query-template render dep_changes --vars pkg=tokio` renders (and with
+//! `--run` executes) the query, so automation shares one definition instead of
+//! each script assembling query strings. Placeholders are `{name}`; every
+//! placeholder needs a value or a default, and unknown variables are rejected.
+//! Defaults are part of the template and inserted as query syntax; a `--vars`
+//! value is data, so double quotes in it are dropped and, outside a quoted
+//! phrase, a value containing whitespace is quoted to stay a single term.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryTemplate {
+    /// Query with `{name}` placeholders
+    pub query: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Values for placeholders not given on the command line
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+}
+
+/// The `[query_templates]` section of `config.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryTemplates {
+    #[serde(default)]
+    pub query_templates: BTreeMap<String, QueryTemplate>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("no query template named '{0}'")]
+    Unknown(String),
+    #[error("missing value for {}", .0.iter().map(|v| format!("{{{v}}}")).collect::<Vec<_>>().join(", "))]
+    MissingVars(Vec<String>),
+    #[error("template has no placeholder {}", .0.iter().map(|v| format!("{{{v}}}")).collect::<Vec<_>>().join(", "))]
+    UnknownVars(Vec<String>),
+    #[error("unclosed '{{' in template")]
+    Unclosed,
+}
+
+impl QueryTemplates {
+    /// Load templates from the shared config file, treating a missing or invalid file as "none".
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring query templates: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<&QueryTemplate, TemplateError> {
+        self.query_templates
+            .get(name)
+            .ok_or_else(|| TemplateError::Unknown(name.to_string()))
+    }
+}
+
+/// A template split into literal text and placeholders.
+enum Piece<'a> {
+    Text(&'a str),
+    Var(&'a str),
+}
+
+fn pieces(query: &str) -> Result<Vec<Piece<'_>>, TemplateError> {
+    let mut out = Vec::new();
+    let mut rest = query;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..].find('}').ok_or(TemplateError::Unclosed)? + open;
+        out.push(Piece::Text(&rest[..open]));
+        out.push(Piece::Var(rest[open + 1..close].trim()));
+        rest = &rest[close + 1..];
+    }
+    out.push(Piece::Text(rest));
+    Ok(out)
+}
+
+impl QueryTemplate {
+    /// Placeholder names in order of first appearance.
+    pub fn variables(&self) -> Result<Vec<String>, TemplateError> {
+        let mut vars: Vec<String> = Vec::new();
+        for piece in pieces(&self.query)? {
+            if let Piece::Var(name) = piece
+                && !vars.iter().any(|v| v == name)
+            {
+                vars.push(name.to_string());
+            }
+        }
+        Ok(vars)
+    }
+
+    /// Substitute `vars` (falling back to `defaults`) into the query.
+    pub fn render(&self, vars: &BTreeMap<String, String>) -> Result<String, TemplateError> {
+        let declared = self.variables()?;
+        let unknown: Vec<String> = vars
+            .keys()
+            .filter(|k| !declared.contains(k))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Err(TemplateError::UnknownVars(unknown));
+        }
+        let missing: Vec<String> = declared
+            .iter()
+            .filter(|v| !vars.contains_key(*v) && !self.defaults.contains_key(*v))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVars(missing));
+        }
+
+        let mut out = String::new();
+        let mut in_phrase = false;
+        for piece in pieces(&self.query)? {
+            match piece {
+                Piece::Text(text) => {
+                    in_phrase ^= text.matches('"').count() % 2 == 1;
+                    out.push_str(text);
+                }
+                Piece::Var(name) => {
+                    let Some(value) = vars.get(name) else {
+                        out.push_str(self.defaults.get(name).map_or("", String::as_str));
+                        continue;
+                    };
+                    let value = value.replace('"', "");
+                    let value = value.trim();
+                    if !in_phrase && value.contains(char::is_whitespace) {
+                        out.push('"');
+                        out.push_str(value);
+                        out.push('"');
+                    } else {
+                        out.push_str(value);
+                    }
+                }
+            }
+        }
+        Ok(out.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Parse `key=value` command-line pairs.
+pub fn parse_vars(pairs: &[String]) -> Result<BTreeMap<String, String>, String> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.trim().to_string(), v.to_string()))
+                .filter(|(k, _)| !k.is_empty())
+                .ok_or_else(|| format!("expected KEY=VALUE, got '{pair}'"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_with_defaults_and_quotes_multiword_values() {
+        let config: QueryTemplates = toml::from_str(
+            r#"
+            [query_templates.deps]
+            query = "dep:{pkg} {reason} \"{error}\""
+            defaults = { reason = "upgrade OR add" }
+            "#,
+        )
+        .unwrap();
+        let template = config.get("deps").unwrap();
+        assert_eq!(template.variables().unwrap(), ["pkg", "reason", "error"]);
+        assert_eq!(
+            template
+                .render(&vars(&[("pkg", "tokio"), ("error", "no \"such\" crate")]))
+                .unwrap(),
+            "dep:tokio upgrade OR add \"no such crate\""
+        );
+        assert_eq!(
+            template
+                .render(&vars(&[
+                    ("pkg", "tokio"),
+                    ("reason", "why add"),
+                    ("error", "x")
+                ]))
+                .unwrap(),
+            "dep:tokio \"why add\" \"x\""
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_vars() {
+        let template = QueryTemplate {
+            query: "test:{test} {extra}".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            template.render(&vars(&[("test", "a")])),
+            Err(TemplateError::MissingVars(vec!["extra".into()]))
+        );
+        assert_eq!(
+            template.render(&vars(&[("test", "a"), ("extra", ""), ("tset", "b")])),
+            Err(TemplateError::UnknownVars(vec!["tset".into()]))
+        );
+        assert_eq!(
+            QueryTemplates::default().get("nope").unwrap_err(),
+            TemplateError::Unknown("nope".into())
+        );
+        assert!(parse_vars(&["=x".into()]).is_err());
+        assert_eq!(
+            parse_vars(&["k=a=b".into()]).unwrap(),
+            vars(&[("k", "a=b")])
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Test names from tool output, for `test:` searches.
+//!
+//! Test runner output pasted into a conversation (cargo test, pytest, go test)
+//! is scanned for test names and their outcome. A message is indexed under a
+//! test if the test fails there, or passes there after failing earlier in the
+//! same conversation, so `cass search test:my_module::my_test` finds the
+//! sessions where that test broke or was fixed without matching every
+//! green test run.
+
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `test my_module::my_test ... FAILED` / `... ok`
+static CARGO_RESULT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^test (\S+) \.\.\. (ok|FAILED)\b").unwrap());
+/// `---- my_module::my_test stdout ----`
+static CARGO_FAILURE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^---- (\S+) stdout ----").unwrap());
+/// `tests/test_x.py::test_foo PASSED` (pytest -v)
+static PYTEST_RESULT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^(\S+\.py::\S+) (PASSED|FAILED|ERROR)\b").unwrap());
+/// `FAILED tests/test_x.py::test_foo - AssertionError` (pytest summary)
+static PYTEST_SUMMARY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^(FAILED|ERROR) (\S+\.py::\S+)").unwrap());
+/// `--- FAIL: TestFoo (0.00s)` / `--- PASS: TestFoo`
+static GO_RESULT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*--- (PASS|FAIL): (\S+)").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestMention {
+    pub name: String,
+    pub failed: bool,
+}
+
+/// Every test result line in `text`, in order of appearance per runner.
+pub fn extract_test_mentions(text: &str) -> Vec<TestMention> {
+    // Cheap pre-check: most messages contain no test output at all
+    if !(text.contains("test ")
+        || text.contains("----")
+        || text.contains(".py::")
+        || text.contains("--- "))
+    {
+        return Vec::new();
+    }
+    let mut out = Vec::new();
+    let mut push = |name: &str, failed: bool| {
+        out.push(TestMention {
+            name: name.trim_end_matches(':').to_string(),
+            failed,
+        });
+    };
+    for caps in CARGO_RESULT.captures_iter(text) {
+        push(&caps[1], &caps[2] == "FAILED");
+    }
+    for caps in CARGO_FAILURE.captures_iter(text) {
+        push(&caps[1], true);
+    }
+    for caps in PYTEST_RESULT.captures_iter(text) {
+        push(&caps[1], &caps[2] != "PASSED");
+    }
+    for caps in PYTEST_SUMMARY.captures_iter(text) {
+        push(&caps[2], true);
+    }
+    for caps in GO_RESULT.captures_iter(text) {
+        push(&caps[2], &caps[1] == "FAIL");
+    }
+    out
+}
+
+/// Tests that fail anywhere in the given messages.
+pub fn failed_tests<'a>(texts: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    texts
+        .into_iter()
+        .flat_map(extract_test_mentions)
+        .filter(|m| m.failed)
+        .map(|m| m.name)
+        .collect()
+}
+
+/// Index terms for one message: tests failing here, or passing here after failing
+/// elsewhere in the conversation (`failed`). Each name is also indexed under its
+/// shorter `::` suffixes so `test:my_test` matches `my_module::my_test`.
+pub fn index_terms(text: &str, failed: &HashSet<String>) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for mention in extract_test_mentions(text) {
+        if !(mention.failed || failed.contains(&mention.name)) {
+            continue;
+        }
+        // pytest parametrization: `test_foo[case-1]` is also indexed as `test_foo`
+        let base = mention
+            .name
+            .split_once('[')
+            .map_or(mention.name.as_str(), |(base, _)| base);
+        for name in [mention.name.as_str(), base] {
+            let mut rest = name;
+            loop {
+                if !terms.iter().any(|t| t == rest) {
+                    terms.push(rest.to_string());
+                }
+                match rest.split_once("::") {
+                    Some((_, tail)) if !tail.is_empty() => rest = tail,
+                    _ => break,
+                }
+            }
+        }
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO: &str = "running 3 tests\ntest parser::tests::parses_empty ... ok\ntest parser::tests::rejects_tabs ... FAILED\n\nfailures:\n\n---- parser::tests::rejects_tabs stdout ----\nthread panicked";
+    const PYTEST: &str = "tests/test_api.py::test_login PASSED\ntests/test_api.py::test_logout[admin] FAILED\n=== short test summary info ===\nFAILED tests/test_api.py::test_logout[admin] - AssertionError";
+
+    #[test]
+    fn extracts_cargo_and_pytest_outcomes() {
+        let cargo = extract_test_mentions(CARGO);
+        assert!(cargo.contains(&TestMention {
+            name: "parser::tests::parses_empty".into(),
+            failed: false
+        }));
+        assert!(
+            cargo
+                .iter()
+                .filter(|m| m.failed)
+                .all(|m| m.name == "parser::tests::rejects_tabs")
+        );
+        let py = extract_test_mentions(PYTEST);
+        assert_eq!(py.iter().filter(|m| m.failed).count(), 2);
+        assert_eq!(
+            extract_test_mentions("--- FAIL: TestParse (0.01s)")[0].name,
+            "TestParse"
+        );
+        assert!(extract_test_mentions("the test passed, all ok").is_empty());
+    }
+
+    #[test]
+    fn indexes_failures_and_fixes_with_suffixes() {
+        let failed = failed_tests([CARGO]);
+        let terms = index_terms(CARGO, &failed);
+        assert_eq!(
+            terms,
+            vec![
+                "parser::tests::rejects_tabs",
+                "tests::rejects_tabs",
+                "rejects_tabs"
+            ]
+        );
+        // A later green run counts as the fix; unrelated passing tests are not indexed
+        let fixed = index_terms(
+            "test parser::tests::rejects_tabs ... ok\ntest other::works ... ok",
+            &failed,
+        );
+        assert_eq!(fixed[0], "parser::tests::rejects_tabs");
+        assert!(!fixed.iter().any(|t| t.contains("works")));
+
+        let py = index_terms(PYTEST, &failed_tests([PYTEST]));
+        assert!(py.contains(&"tests/test_api.py::test_logout[admin]".to_string()));
+        assert!(py.contains(&"test_logout".to_string()));
+        assert!(!py.contains(&"test_login".to_string()));
+    }
+}
@@ -0,0 +1,319 @@
+//! Vector index compaction (`cass compact`).
+//!
+//! Vectors outlive their messages: `cass index --gc`, `--force-rebuild` and
+//! edited sessions delete or renumber message rows, but the CVVI files under
+//! `vector_index/` only shed those rows on the next `cass embed --backfill`.
+//! Compaction drops rows whose message no longer exists and duplicate rows
+//! for the same message chunk, for every embedder's index.
+//!
+//! The index is a flat slab scanned in full on every query, so dead rows cost
+//! both disk and search time. A file is only rewritten once its dead fraction
+//! reaches `min_fragmentation` (or with `force`), since a rewrite copies every
+//! live vector. Leftover `*.cvvi.tmp` files from interrupted saves are removed.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing::info;
+
+use crate::search::vector_index::{VECTOR_INDEX_DIR, VectorEntry, VectorIndex};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompactOptions {
+    /// Rewrite an index once this fraction of its rows is dead (0.0..=1.0)
+    pub min_fragmentation: f64,
+    /// Rewrite every index with dead rows, regardless of fragmentation
+    pub force: bool,
+    /// Report what would be reclaimed without touching any file
+    pub dry_run: bool,
+}
+
+impl Default for CompactOptions {
+    fn default() -> Self {
+        Self {
+            min_fragmentation: 0.1,
+            force: false,
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexCompaction {
+    pub path: PathBuf,
+    pub embedder_id: String,
+    pub vectors_before: usize,
+    /// Rows whose message was deleted from the database
+    pub orphaned: usize,
+    /// Extra rows for a message chunk that already has a newer row
+    pub duplicates: usize,
+    pub vectors_after: usize,
+    /// Dead rows as a fraction of all rows
+    pub fragmentation: f64,
+    pub rewritten: bool,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactReport {
+    pub indexes: Vec<IndexCompaction>,
+    /// Interrupted-save leftovers that were (or would be) deleted
+    pub removed_temp_files: Vec<PathBuf>,
+    pub reclaimed_bytes: u64,
+    pub dry_run: bool,
+}
+
+/// Compact every vector index under `data_dir/vector_index` against the messages in `conn`.
+pub fn compact_vector_indexes(
+    conn: &Connection,
+    data_dir: &Path,
+    opts: &CompactOptions,
+) -> Result<CompactReport> {
+    let (report, index_files) = scan_index_dir(data_dir, opts)?;
+    if index_files.is_empty() {
+        return Ok(report);
+    }
+    let live = live_message_ids(conn)?;
+    compact_index_files(report, index_files, &live, opts)
+}
+
+/// Compact every vector index, treating only `live` message ids as alive.
+///
+/// `cass prune` passes the surviving ids before it deletes anything, so a dry
+/// run reports the vectors it would drop.
+pub fn compact_vector_indexes_with_live(
+    data_dir: &Path,
+    live: &HashSet<u64>,
+    opts: &CompactOptions,
+) -> Result<CompactReport> {
+    let (report, index_files) = scan_index_dir(data_dir, opts)?;
+    compact_index_files(report, index_files, live, opts)
+}
+
+/// List the index files under `data_dir/vector_index`, removing leftover temp files.
+fn scan_index_dir(data_dir: &Path, opts: &CompactOptions) -> Result<(CompactReport, Vec<PathBuf>)> {
+    let mut report = CompactReport {
+        dry_run: opts.dry_run,
+        ..Default::default()
+    };
+    let mut index_files = Vec::new();
+    let dir = data_dir.join(VECTOR_INDEX_DIR);
+    if !dir.is_dir() {
+        return Ok((report, index_files));
+    }
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.ends_with(".cvvi.tmp") {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if !opts.dry_run {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("remove {}", path.display()))?;
+            }
+            report.reclaimed_bytes += size;
+            report.removed_temp_files.push(path);
+        } else if name.ends_with(".cvvi") {
+            index_files.push(path);
+        }
+    }
+    index_files.sort();
+    Ok((report, index_files))
+}
+
+fn compact_index_files(
+    mut report: CompactReport,
+    index_files: Vec<PathBuf>,
+    live: &HashSet<u64>,
+    opts: &CompactOptions,
+) -> Result<CompactReport> {
+    for path in index_files {
+        let compaction = compact_index(&path, live, opts)?;
+        report.reclaimed_bytes += compaction
+            .bytes_before
+            .saturating_sub(compaction.bytes_after);
+        report.indexes.push(compaction);
+    }
+    Ok(report)
+}
+
+pub(crate) fn live_message_ids(conn: &Connection) -> Result<HashSet<u64>> {
+    let mut stmt = conn.prepare("SELECT id FROM messages")?;
+    let ids = stmt
+        .query_map([], |r| r.get::<_, i64>(0))?
+        .map(|id| id.map(|id| id as u64))
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
+
+fn compact_index(
+    path: &Path,
+    live: &HashSet<u64>,
+    opts: &CompactOptions,
+) -> Result<IndexCompaction> {
+    let bytes_before = std::fs::metadata(path)?.len();
+    let index =
+        VectorIndex::load(path).with_context(|| format!("load vector index {}", path.display()))?;
+    let header = index.header().clone();
+    let rows = index.rows();
+
+    // Walk newest-first so the last row written for a chunk is the one kept
+    let mut seen = HashSet::new();
+    let mut keep = vec![false; rows.len()];
+    let (mut orphaned, mut duplicates) = (0, 0);
+    for (i, row) in rows.iter().enumerate().rev() {
+        if !live.contains(&row.message_id) {
+            orphaned += 1;
+        } else if !seen.insert((row.message_id, row.chunk_idx)) {
+            duplicates += 1;
+        } else {
+            keep[i] = true;
+        }
+    }
+
+    let dead = orphaned + duplicates;
+    let fragmentation = if rows.is_empty() {
+        0.0
+    } else {
+        dead as f64 / rows.len() as f64
+    };
+    let mut compaction = IndexCompaction {
+        path: path.to_path_buf(),
+        embedder_id: header.embedder_id.clone(),
+        vectors_before: rows.len(),
+        orphaned,
+        duplicates,
+        vectors_after: rows.len(),
+        fragmentation,
+        rewritten: false,
+        bytes_before,
+        bytes_after: bytes_before,
+    };
+    let rewrite = dead > 0 && (opts.force || fragmentation >= opts.min_fragmentation);
+    if !rewrite {
+        return Ok(compaction);
+    }
+
+    let mut entries = Vec::with_capacity(rows.len() - dead);
+    for (row, _) in rows.iter().zip(&keep).filter(|(_, keep)| **keep) {
+        entries.push(VectorEntry {
+            message_id: row.message_id,
+            created_at_ms: row.created_at_ms,
+            agent_id: row.agent_id,
+            workspace_id: row.workspace_id,
+            source_id: row.source_id,
+            role: row.role,
+            chunk_idx: row.chunk_idx,
+            content_hash: row.content_hash,
+            vector: index.vector_at_f32(row)?,
+        });
+    }
+    // Release the mmap before the file is replaced
+    drop(index);
+    entries.sort_by_key(|e| (e.message_id, e.chunk_idx));
+    compaction.vectors_after = entries.len();
+
+    let compacted = VectorIndex::build(
+        header.embedder_id,
+        header.embedder_revision,
+        header.dimension as usize,
+        header.quantization,
+        entries,
+    )?;
+    if opts.dry_run {
+        let mut size = Vec::new();
+        compacted.write_to(&mut size)?;
+        compaction.bytes_after = size.len() as u64;
+    } else {
+        compacted.save(path)?;
+        compaction.bytes_after = std::fs::metadata(path)?.len();
+        compaction.rewritten = true;
+        info!(
+            path = %path.display(),
+            orphaned,
+            duplicates,
+            "vector index compacted"
+        );
+    }
+    Ok(compaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::vector_index::{Quantization, vector_index_path};
+    use crate::storage::sqlite::SqliteStorage;
+    use tempfile::tempdir;
+
+    fn entry(message_id: u64, value: f32) -> VectorEntry {
+        VectorEntry {
+            message_id,
+            created_at_ms: 0,
+            agent_id: 1,
+            workspace_id: 0,
+            source_id: 0,
+            role: 0,
+            chunk_idx: 0,
+            content_hash: [0; 32],
+            vector: vec![value; 4],
+        }
+    }
+
+    #[test]
+    fn drops_orphans_and_duplicates_past_threshold() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite")).unwrap();
+        let conn = storage.raw();
+        conn.execute_batch(
+            "INSERT INTO agents(id, slug, name, kind, created_at, updated_at)
+                VALUES (1, 'codex', 'Codex', 'cli', 0, 0);
+             INSERT INTO conversations(id, agent_id, source_id, source_path)
+                VALUES (1, 1, 'local', '/logs/a.jsonl');
+             INSERT INTO messages(id, conversation_id, idx, role, content) VALUES
+                (1, 1, 0, 'user', 'a'), (2, 1, 1, 'agent', 'b');",
+        )
+        .unwrap();
+
+        let path = vector_index_path(dir.path(), "fnv1a-4");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        // Message 3 is gone; message 2 was embedded twice
+        VectorIndex::build(
+            "fnv1a-4",
+            "canon-v1",
+            4,
+            Quantization::F16,
+            vec![entry(1, 0.1), entry(2, 0.2), entry(2, 0.5), entry(3, 0.3)],
+        )
+        .unwrap()
+        .save(&path)
+        .unwrap();
+        std::fs::write(path.with_extension("cvvi.tmp"), b"partial").unwrap();
+
+        let strict = CompactOptions {
+            min_fragmentation: 0.9,
+            ..Default::default()
+        };
+        let report = compact_vector_indexes(conn, dir.path(), &strict).unwrap();
+        let idx = &report.indexes[0];
+        assert_eq!((idx.orphaned, idx.duplicates), (1, 1));
+        assert_eq!(idx.fragmentation, 0.5);
+        assert!(!idx.rewritten, "below threshold");
+        assert_eq!(report.removed_temp_files.len(), 1);
+        assert_eq!(report.reclaimed_bytes, 7);
+
+        let report = compact_vector_indexes(conn, dir.path(), &CompactOptions::default()).unwrap();
+        let idx = &report.indexes[0];
+        assert!(idx.rewritten);
+        assert_eq!(idx.vectors_after, 2);
+        assert!(report.reclaimed_bytes > 0);
+
+        let index = VectorIndex::load(&path).unwrap();
+        let ids: Vec<u64> = index.rows().iter().map(|r| r.message_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        // The newest duplicate wins
+        assert_eq!(index.vector_at_f32(&index.rows()[1]).unwrap()[0], 0.5);
+    }
+}
@@ -374,14 +374,23 @@ impl SemanticFilter {
 
     pub fn from_search_filters(filters: &SearchFilters, maps: &SemanticFilterMaps) -> Result<Self> {
         let agents = map_filter_set(&filters.agents, &maps.agent_slug_to_id);
-        let workspaces = map_filter_set(&filters.workspaces, &maps.workspace_path_to_id);
+        let workspaces = (!filters.workspaces.is_empty()).then(|| {
+            maps.workspace_path_to_id
+                .iter()
+                .filter(|(path, _)| filters.workspace_matches(path))
+                .map(|(_, id)| *id)
+                .collect()
+        });
         let sources = maps.sources_from_filter(&filters.source_filter)?;
+        let roles = (!filters.roles.is_empty())
+            .then(|| parse_role_codes(&filters.roles))
+            .transpose()?;
 
         Ok(Self {
             agents,
             workspaces,
             sources,
-            roles: None,
+            roles,
             created_from: filters.created_from,
             created_to: filters.created_to,
         })
@@ -494,6 +503,17 @@ impl SemanticFilterMaps {
             SourceFilter::Local => Some(HashSet::from([self.source_id(LOCAL_SOURCE_ID)])),
             SourceFilter::Remote => Some(self.remote_source_ids.clone()),
             SourceFilter::SourceId(id) => Some(HashSet::from([self.source_id(id)])),
+            SourceFilter::Any(filters) => {
+                let mut ids = HashSet::new();
+                for filter in filters {
+                    match self.sources_from_filter(filter)? {
+                        Some(set) => ids.extend(set),
+                        // Any source matches, so the alternatives don't narrow anything
+                        None => return Ok(None),
+                    }
+                }
+                Some(ids)
+            }
         };
         Ok(result)
     }
@@ -1083,7 +1103,7 @@ fn map_filter_set(keys: &HashSet<String>, map: &HashMap<String, u32>) -> Option<
     Some(set)
 }
 
-fn source_id_hash(source_id: &str) -> u32 {
+pub(crate) fn source_id_hash(source_id: &str) -> u32 {
     let mut hasher = crc32fast::Hasher::new();
     hasher.update(source_id.as_bytes());
     hasher.finalize()
@@ -0,0 +1,328 @@
+//! Startup validation run before commands that read the database or index.
+//!
+//! The full check validates the keys and values of `config.toml` and
+//! `sources.toml` (as `cass config validate` does), reads the database schema
+//! version and compares the search index's schema hash. Its result is cached
+//! in `<data_dir>/selfcheck.json` as a stamp of the cass version, the size and
+//! modification time of the config and schema hash files, and the database
+//! file's identity (device and inode) and schema version. The database's size
+//! and mtime change on every write, so they aren't stamped; a replaced or
+//! migrated database still changes the stamp. An invocation whose inputs are
+//! unchanged only stats a few files and reads one `meta` row. Any mismatch becomes a
+//! [`SelfCheckError`] naming the command that fixes it, instead of a failure
+//! deep inside a query.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::config::{Issue, check_config, check_sources, config_path, sources_path};
+use crate::search::tantivy::{SCHEMA_HASH, index_dir};
+use crate::storage::encryption;
+use crate::storage::sqlite::{SchemaCheck, check_schema_compatibility};
+
+const STAMP_FILE: &str = "selfcheck.json";
+
+/// What the startup check found wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfCheckKind {
+    /// `config.toml` or `sources.toml` is not valid TOML or has invalid values
+    ConfigInvalid,
+    /// The database predates this cass version; `cass index` migrates it
+    SchemaOutdated,
+    /// The database cannot be migrated (newer cass, or missing metadata)
+    SchemaIncompatible,
+    /// The search index was built with a different schema
+    IndexOutdated,
+}
+
+impl SelfCheckKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ConfigInvalid => "config-invalid",
+            Self::SchemaOutdated => "schema-outdated",
+            Self::SchemaIncompatible => "schema-incompatible",
+            Self::IndexOutdated => "index-outdated",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SelfCheckError {
+    pub kind: SelfCheckKind,
+    pub message: String,
+    pub hint: String,
+}
+
+/// Generation stamp of the inputs the last successful check validated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    cass_version: String,
+    files: Vec<FileStamp>,
+    /// `None` when the database is missing or unreadable
+    db: Option<DbStamp>,
+}
+
+/// Identity and schema version of the database file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DbStamp {
+    /// Device and inode; `None` where the platform doesn't expose them
+    file_id: Option<(u64, u64)>,
+    schema_version: Option<String>,
+}
+
+impl DbStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        #[cfg(unix)]
+        let file_id = {
+            use std::os::unix::fs::MetadataExt;
+            Some((meta.dev(), meta.ino()))
+        };
+        #[cfg(not(unix))]
+        let file_id = {
+            let _ = meta;
+            None
+        };
+        let conn = rusqlite::Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .ok()?;
+        encryption::apply_key(&conn).ok()?;
+        let schema_version = conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'schema_version'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        Some(Self {
+            file_id,
+            schema_version,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    /// `None` when the file does not exist
+    size: Option<u64>,
+    mtime_ns: Option<u128>,
+}
+
+impl FileStamp {
+    fn of(path: PathBuf) -> Self {
+        let meta = std::fs::metadata(&path).ok();
+        Self {
+            size: meta.as_ref().map(std::fs::Metadata::len),
+            mtime_ns: meta
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos()),
+            path,
+        }
+    }
+}
+
+type ConfigCheck = fn(&str) -> Vec<Issue>;
+
+struct Inputs {
+    config: Vec<(PathBuf, ConfigCheck)>,
+    db: PathBuf,
+    schema_hash: Option<PathBuf>,
+}
+
+impl Inputs {
+    fn resolve(data_dir: &Path, db_path: &Path) -> Self {
+        let checks: [(Option<PathBuf>, ConfigCheck); 2] = [
+            (config_path(), check_config),
+            (sources_path(), check_sources),
+        ];
+        let config = checks
+            .into_iter()
+            .filter_map(|(path, check)| Some((path?, check)))
+            .collect();
+        Self {
+            config,
+            db: db_path.to_path_buf(),
+            schema_hash: index_dir(data_dir).ok().map(|d| d.join("schema_hash.json")),
+        }
+    }
+
+    fn stamp(&self) -> Stamp {
+        let files = self
+            .config
+            .iter()
+            .map(|(path, _)| path)
+            .chain(self.schema_hash.iter())
+            .cloned()
+            .map(FileStamp::of)
+            .collect();
+        Stamp {
+            cass_version: env!("CARGO_PKG_VERSION").to_string(),
+            files,
+            db: DbStamp::of(&self.db),
+        }
+    }
+}
+
+/// Validate config, database schema and index generation for `data_dir`.
+///
+/// Returns immediately when nothing changed since the last successful check.
+pub fn check(data_dir: &Path, db_path: &Path) -> Result<(), SelfCheckError> {
+    let inputs = Inputs::resolve(data_dir, db_path);
+    let stamp = inputs.stamp();
+    let stamp_path = data_dir.join(STAMP_FILE);
+    if read_stamp(&stamp_path).as_ref() == Some(&stamp) {
+        return Ok(());
+    }
+
+    run_checks(&inputs)?;
+
+    // Only record success, so a broken setup is re-examined on every run
+    if let Ok(json) = serde_json::to_vec(&stamp) {
+        let _ = std::fs::write(&stamp_path, json);
+    }
+    Ok(())
+}
+
+fn read_stamp(path: &Path) -> Option<Stamp> {
+    let bytes = std::fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn run_checks(inputs: &Inputs) -> Result<(), SelfCheckError> {
+    for (path, check) in &inputs.config {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        // Warnings (unknown keys) don't stop commands; `cass config validate` lists them
+        if let Some(issue) = check(&text).into_iter().find(|i| i.error) {
+            return Err(SelfCheckError {
+                kind: SelfCheckKind::ConfigInvalid,
+                message: format!("{}: {}", path.display(), issue.message),
+                hint: format!(
+                    "Run `cass config validate` for details, or fix {}",
+                    path.display()
+                ),
+            });
+        }
+    }
+
+    if inputs.db.exists() {
+        match check_schema_compatibility(&inputs.db) {
+            Ok(SchemaCheck::Compatible) => {}
+            Ok(SchemaCheck::NeedsMigration) => {
+                return Err(SelfCheckError {
+                    kind: SelfCheckKind::SchemaOutdated,
+                    message: format!(
+                        "database {} uses an older schema than this cass version",
+                        inputs.db.display()
+                    ),
+                    hint: "Run `cass index` to migrate it".to_string(),
+                });
+            }
+            Ok(SchemaCheck::NeedsRebuild(reason)) => {
+                return Err(SelfCheckError {
+                    kind: SelfCheckKind::SchemaIncompatible,
+                    message: format!("database {}: {reason}", inputs.db.display()),
+                    hint: "Upgrade cass, or rebuild with `cass index --full --force-rebuild`"
+                        .to_string(),
+                });
+            }
+            // Locked or unreadable databases are reported by the command itself
+            Err(_) => {}
+        }
+    }
+
+    if let Some(hash_path) = &inputs.schema_hash
+        && let Some(dir) = hash_path.parent()
+        && dir.join("meta.json").exists()
+    {
+        let hash = std::fs::read_to_string(hash_path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+            .and_then(|v| v.get("schema_hash")?.as_str().map(str::to_string));
+        if hash.as_deref() != Some(SCHEMA_HASH) {
+            return Err(SelfCheckError {
+                kind: SelfCheckKind::IndexOutdated,
+                message: format!(
+                    "search index {} was built with schema {}, expected {SCHEMA_HASH}",
+                    dir.display(),
+                    hash.as_deref().unwrap_or("<unknown>")
+                ),
+                hint: "Run `cass index --full` to rebuild it".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamp_skips_checks_until_inputs_change() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let data_dir = tmp.path();
+        let db = data_dir.join("agent_search.db");
+        let index = index_dir(data_dir).unwrap();
+        std::fs::write(index.join("meta.json"), "{}").unwrap();
+        let hash_file = index.join("schema_hash.json");
+        std::fs::write(&hash_file, format!(r#"{{"schema_hash":"{SCHEMA_HASH}"}}"#)).unwrap();
+
+        check(data_dir, &db).unwrap();
+        assert!(data_dir.join(STAMP_FILE).exists());
+
+        // A stale hash of a different size invalidates the stamp
+        std::fs::write(&hash_file, r#"{"schema_hash":"tantivy-schema-v1"}"#).unwrap();
+        let err = check(data_dir, &db).unwrap_err();
+        assert_eq!(err.kind, SelfCheckKind::IndexOutdated);
+        assert!(err.hint.contains("cass index --full"));
+    }
+
+    #[test]
+    fn db_stamp_ignores_writes_but_not_migrations() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db = tmp.path().join("agent_search.db");
+        let mut storage = crate::storage::sqlite::SqliteStorage::open(&db).unwrap();
+        let before = DbStamp::of(&db).unwrap();
+        assert!(before.schema_version.is_some());
+
+        storage.set_last_scan_ts(42).unwrap();
+        assert_eq!(DbStamp::of(&db).unwrap(), before);
+
+        storage
+            .raw()
+            .execute(
+                "UPDATE meta SET value = '1' WHERE key = 'schema_version'",
+                [],
+            )
+            .unwrap();
+        assert_ne!(DbStamp::of(&db).unwrap(), before);
+    }
+
+    #[test]
+    fn config_values_are_validated_not_just_syntax() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config = tmp.path().join("config.toml");
+        let inputs = Inputs {
+            config: vec![(config.clone(), check_config as ConfigCheck)],
+            db: tmp.path().join("agent_search.db"),
+            schema_hash: None,
+        };
+
+        // Unknown keys are only warnings
+        std::fs::write(&config, "[no_such_section]\nx = 1\n").unwrap();
+        run_checks(&inputs).unwrap();
+
+        std::fs::write(&config, "exclude = \"not a list\"\n").unwrap();
+        let err = run_checks(&inputs).unwrap_err();
+        assert_eq!(err.kind, SelfCheckKind::ConfigInvalid);
+        assert!(err.message.contains("[exclude]"), "{}", err.message);
+    }
+}
@@ -31,6 +31,26 @@
 //! from = "/opt/work"
 //! to = "/Volumes/Work"
 //! agents = ["claude-code"]
+//!
+//! # Another machine running cass: its indexed conversations are streamed
+//! # over SSH by `cass serve --sync`, so no paths are needed.
+//! [[sources]]
+//! name = "desktop"
+//! type = "cass"
+//! host = "me@desktop.local"
+//!
+//! # Logs archived to a bucket, fetched with rclone. Paths are key prefixes
+//! # under `bucket`/`prefix`; `rclone_remote` names a remote in rclone.conf.
+//! [[sources]]
+//! name = "archive"
+//! type = "object_store"
+//! rclone_remote = "s3"
+//! bucket = "agent-logs"
+//! prefix = "laptop"
+//! paths = [".claude/projects", ".codex/sessions"]
+//! sync_schedule = "daily"
+//! # Keep the synced copies encrypted with the database key
+//! encrypt = true
 //! ```
 
 use serde::{Deserialize, Serialize};
@@ -108,8 +128,23 @@ impl PathMapping {
     /// Apply this mapping to a path if it matches.
     ///
     /// Returns `Some(rewritten_path)` if the path starts with `from` prefix,
-    /// `None` otherwise.
+    /// `None` otherwise. Windows paths (`C:\...`, `\\server\...`) match
+    /// ignoring case and separator style, and the rest of the path switches
+    /// to `/` when `to` is a Unix path.
     pub fn apply(&self, path: &str) -> Option<String> {
+        if is_windows_path(&self.from) && is_windows_path(path) {
+            // Both normalizations keep byte lengths, so `from.len()` splits `path` too
+            let fold = |s: &str| s.replace('\\', "/").to_ascii_lowercase();
+            if !fold(path).starts_with(&fold(&self.from)) {
+                return None;
+            }
+            let rest = &path[self.from.len()..];
+            return Some(if is_windows_path(&self.to) {
+                format!("{}{rest}", self.to)
+            } else {
+                format!("{}{}", self.to, rest.replace('\\', "/"))
+            });
+        }
         if path.starts_with(&self.from) {
             Some(path.replacen(&self.from, &self.to, 1))
         } else {
@@ -127,6 +162,12 @@ impl PathMapping {
     }
 }
 
+/// Whether `path` is a Windows path: drive-letter (`C:\`, `c:/`) or UNC (`\\host\share`).
+pub fn is_windows_path(path: &str) -> bool {
+    let b = path.as_bytes();
+    (b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':') || path.starts_with("\\\\")
+}
+
 /// Definition of a single source (local or remote).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SourceDefinition {
@@ -161,6 +202,29 @@ pub struct SourceDefinition {
     /// Platform hint for default paths (macos, linux).
     #[serde(default)]
     pub platform: Option<Platform>,
+
+    /// Bandwidth cap while syncing this source, in KiB/s.
+    /// `cass sync --bwlimit` overrides it for one run.
+    #[serde(default)]
+    pub bwlimit: Option<u32>,
+
+    /// Object stores: bucket holding the logs.
+    #[serde(default)]
+    pub bucket: Option<String>,
+
+    /// Object stores: key prefix inside the bucket that `paths` are relative to.
+    #[serde(default)]
+    pub prefix: Option<String>,
+
+    /// Object stores: rclone remote to reach the bucket through
+    /// (defaults to "s3"; see `rclone config`).
+    #[serde(default)]
+    pub rclone_remote: Option<String>,
+
+    /// Keep this source's synced files encrypted on disk (see [`super::staging`]).
+    /// Needs `[storage] encryption` in config.toml, whose key protects them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypt: bool,
 }
 
 impl SourceDefinition {
@@ -183,9 +247,27 @@ impl SourceDefinition {
         }
     }
 
-    /// Check if this source requires SSH connectivity.
+    /// Check if this source is synced from elsewhere (SSH or object store).
     pub fn is_remote(&self) -> bool {
-        matches!(self.source_type, SourceKind::Ssh)
+        self.source_type.is_remote()
+    }
+
+    /// Check if this source requires SSH connectivity.
+    pub fn is_ssh(&self) -> bool {
+        matches!(self.source_type, SourceKind::Ssh | SourceKind::Cass)
+    }
+
+    /// rclone location of an object store source: `remote:bucket[/prefix]`.
+    pub fn object_store_root(&self) -> Option<String> {
+        if self.source_type != SourceKind::ObjectStore {
+            return None;
+        }
+        let bucket = self.bucket.as_deref()?.trim_matches('/');
+        let remote = self.rclone_remote.as_deref().unwrap_or("s3");
+        Some(match self.prefix.as_deref().map(|p| p.trim_matches('/')) {
+            Some(prefix) if !prefix.is_empty() => format!("{remote}:{bucket}/{prefix}"),
+            _ => format!("{remote}:{bucket}"),
+        })
     }
 
     /// Validate the source definition.
@@ -208,8 +290,19 @@ impl SourceDefinition {
             ));
         }
 
-        if self.is_remote() && self.host.is_none() {
-            return Err(ConfigError::Validation("SSH sources require a host".into()));
+        if self.is_ssh() && self.host.is_none() {
+            return Err(ConfigError::Validation(format!(
+                "{} sources require a host",
+                self.source_type
+            )));
+        }
+
+        if self.source_type == SourceKind::ObjectStore
+            && self.bucket.as_deref().is_none_or(|b| b.trim().is_empty())
+        {
+            return Err(ConfigError::Validation(
+                "Object store sources require a bucket".into(),
+            ));
         }
 
         Ok(())
@@ -227,21 +320,29 @@ impl SourceDefinition {
     ///
     /// Uses longest-prefix matching, filtering by agent.
     pub fn rewrite_path_for_agent(&self, path: &str, agent: Option<&str>) -> String {
-        // Sort by prefix length descending for longest-prefix match
-        let mut mappings: Vec<_> = self
-            .path_mappings
-            .iter()
-            .filter(|m| m.applies_to_agent(agent))
-            .collect();
-        mappings.sort_by(|a, b| b.from.len().cmp(&a.from.len()));
-
-        for mapping in mappings {
-            if let Some(rewritten) = mapping.apply(path) {
-                return rewritten;
+        self.matching_mapping(path, agent)
+            .and_then(|(_, mapping)| mapping.apply(path))
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    /// The mapping (and its index in `path_mappings`) that
+    /// [`rewrite_path_for_agent`](Self::rewrite_path_for_agent) applies to `path`:
+    /// the longest matching prefix, the earliest rule on ties.
+    pub fn matching_mapping(
+        &self,
+        path: &str,
+        agent: Option<&str>,
+    ) -> Option<(usize, &PathMapping)> {
+        let mut best: Option<(usize, &PathMapping)> = None;
+        for (i, mapping) in self.path_mappings.iter().enumerate() {
+            if !mapping.applies_to_agent(agent) || mapping.apply(path).is_none() {
+                continue;
+            }
+            if best.is_none_or(|(_, b)| mapping.from.len() > b.from.len()) {
+                best = Some((i, mapping));
             }
         }
-
-        path.to_string()
+        best
     }
 }
 
@@ -460,8 +561,24 @@ pub fn get_preset_paths(preset: &str) -> Result<Vec<String>, ConfigError> {
             "~/.aider.chat.history.md".into(),
             "~/.goose/sessions".into(),
         ]),
+        // Relative to %USERPROFILE%: editors keep extension data under %APPDATA%
+        // (AppData/Roaming), OpenCode under %LOCALAPPDATA% (AppData/Local)
+        "windows-defaults" | "windows" => Ok(vec![
+            "~/.claude/projects".into(),
+            "~/.codex/sessions".into(),
+            "~/AppData/Roaming/Code/User/globalStorage/saoudrizwan.claude-dev".into(),
+            "~/AppData/Roaming/Code/User/globalStorage/rooveterinaryinc.roo-cline".into(),
+            "~/AppData/Roaming/Cursor/User/globalStorage/saoudrizwan.claude-dev".into(),
+            "~/AppData/Roaming/Cursor/User/globalStorage/rooveterinaryinc.roo-cline".into(),
+            "~/.gemini/tmp".into(),
+            "~/.pi/agent/sessions".into(),
+            "~/AppData/Local/opencode".into(),
+            "~/.continue/sessions".into(),
+            "~/.aider.chat.history.md".into(),
+            "~/.goose/sessions".into(),
+        ]),
         _ => Err(ConfigError::Validation(format!(
-            "Unknown preset: '{}'. Valid presets: macos-defaults, linux-defaults",
+            "Unknown preset: '{}'. Valid presets: macos-defaults, linux-defaults, windows-defaults",
             preset
         ))),
     }
@@ -494,6 +611,35 @@ mod tests {
         assert!(source.is_remote());
     }
 
+    #[test]
+    fn test_object_store_source() {
+        let mut source: SourceDefinition = toml::from_str(
+            r#"
+            name = "archive"
+            type = "object_store"
+            prefix = "/laptop/"
+            paths = [".claude/projects"]
+            "#,
+        )
+        .unwrap();
+        assert!(source.is_remote());
+        assert!(!source.is_ssh());
+        assert!(source.validate().is_err());
+
+        source.bucket = Some("agent-logs".into());
+        assert!(source.validate().is_ok());
+        assert_eq!(
+            source.object_store_root().as_deref(),
+            Some("s3:agent-logs/laptop")
+        );
+        source.rclone_remote = Some("gcs".into());
+        source.prefix = None;
+        assert_eq!(
+            source.object_store_root().as_deref(),
+            Some("gcs:agent-logs")
+        );
+    }
+
     #[test]
     fn test_source_validation_empty_name() {
         let source = SourceDefinition::default();
@@ -634,6 +780,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_matching_mapping_reports_the_applied_rule() {
+        let mut source = SourceDefinition::local("test");
+        source
+            .path_mappings
+            .push(PathMapping::new("/home/user", "/Users/me"));
+        source.path_mappings.push(PathMapping::with_agents(
+            "/home/user/projects",
+            "/Volumes/Work/projects",
+            vec!["claude-code".into()],
+        ));
+        // A rule that maps a path onto itself still counts as a match
+        source.path_mappings.push(PathMapping::new("/srv", "/srv"));
+
+        let (i, _) = source
+            .matching_mapping("/home/user/projects/app", Some("claude-code"))
+            .unwrap();
+        assert_eq!(i, 1);
+        let (i, _) = source
+            .matching_mapping("/home/user/projects/app", Some("cursor"))
+            .unwrap();
+        assert_eq!(i, 0);
+        let (i, _) = source.matching_mapping("/srv/app", None).unwrap();
+        assert_eq!(i, 2);
+        assert!(source.matching_mapping("/opt/data", None).is_none());
+    }
+
     #[test]
     fn test_config_duplicate_names() {
         let mut config = SourcesConfig::default();
@@ -675,6 +848,8 @@ mod tests {
             sync_schedule: SyncSchedule::Daily,
             path_mappings: vec![PathMapping::new("/home/user", "/Users/me")],
             platform: Some(Platform::Linux),
+            bwlimit: Some(500),
+            ..Default::default()
         });
 
         let serialized = toml::to_string_pretty(&config).unwrap();
@@ -683,6 +858,7 @@ mod tests {
         assert_eq!(deserialized.sources.len(), 1);
         assert_eq!(deserialized.sources[0].name, "laptop");
         assert_eq!(deserialized.sources[0].sync_schedule, SyncSchedule::Daily);
+        assert_eq!(deserialized.sources[0].bwlimit, Some(500));
         assert_eq!(deserialized.sources[0].path_mappings.len(), 1);
         assert_eq!(deserialized.sources[0].path_mappings[0].from, "/home/user");
         assert_eq!(deserialized.sources[0].path_mappings[0].to, "/Users/me");
@@ -702,6 +878,8 @@ mod tests {
                 PathMapping::with_agents("/opt/work", "/Volumes/Work", vec!["claude-code".into()]),
             ],
             platform: None,
+            bwlimit: None,
+            ..Default::default()
         });
 
         let serialized = toml::to_string_pretty(&config).unwrap();
@@ -726,9 +904,66 @@ mod tests {
         let linux = get_preset_paths("linux-defaults").unwrap();
         assert!(!linux.is_empty());
 
+        let windows = get_preset_paths("windows-defaults").unwrap();
+        assert!(windows.iter().any(|p| p.contains(".claude")));
+        assert!(
+            windows
+                .iter()
+                .any(|p| p.starts_with("~/AppData/Roaming/Cursor"))
+        );
+        assert_eq!(get_preset_paths("windows").unwrap(), windows);
+
         assert!(get_preset_paths("unknown").is_err());
     }
 
+    #[test]
+    fn test_windows_path_mapping() {
+        assert!(is_windows_path("C:\\Users\\me"));
+        assert!(is_windows_path("d:/work"));
+        assert!(is_windows_path("\\\\nas\\share"));
+        assert!(!is_windows_path("/home/me"));
+        assert!(!is_windows_path("~/AppData"));
+
+        // Drive letter case and separator style don't matter
+        let mapping = PathMapping::new("C:\\Users\\me\\projects", "/home/me/projects");
+        assert_eq!(
+            mapping.apply("c:/Users/me/projects/app/src").as_deref(),
+            Some("/home/me/projects/app/src")
+        );
+        assert_eq!(
+            mapping
+                .apply("C:\\Users\\me\\projects\\app\\src")
+                .as_deref(),
+            Some("/home/me/projects/app/src")
+        );
+        assert_eq!(mapping.apply("D:\\Users\\me\\projects"), None);
+        assert_eq!(mapping.apply("/home/me/projects"), None);
+
+        // Windows to Windows keeps backslashes
+        let mapping = PathMapping::new("C:\\work", "D:\\work");
+        assert_eq!(
+            mapping.apply("c:\\work\\api").as_deref(),
+            Some("D:\\work\\api")
+        );
+
+        // Longest-prefix matching still applies
+        let mut source = SourceDefinition::local("win");
+        source
+            .path_mappings
+            .push(PathMapping::new("C:\\Users\\me", "/Users/me"));
+        source
+            .path_mappings
+            .push(PathMapping::new("C:\\Users\\me\\work", "/Volumes/Work"));
+        assert_eq!(
+            source.rewrite_path("C:\\Users\\me\\work\\api"),
+            "/Volumes/Work/api"
+        );
+        assert_eq!(
+            source.rewrite_path("C:\\Users\\me\\notes"),
+            "/Users/me/notes"
+        );
+    }
+
     #[test]
     fn test_sync_schedule_display() {
         assert_eq!(SyncSchedule::Manual.to_string(), "manual");
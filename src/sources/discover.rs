@@ -0,0 +1,219 @@
+//! LAN discovery of other cass machines over mDNS.
+//!
+//! `cass daemon --advertise` registers a `_cass._tcp.local.` service named
+//! after the host. Its TXT record carries the SSH user and the cass version,
+//! and its port is the SSH port, since peers pull with `cass serve --sync`
+//! over SSH (a `type = "cass"` source). `cass sources discover` browses for
+//! those services for a few seconds and offers to add each peer it finds.
+//!
+//! Advertising is opt-in: it announces `user@host` to everyone on the LAN.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+
+/// mDNS service type cass daemons advertise
+pub const SERVICE_TYPE: &str = "_cass._tcp.local.";
+
+/// Port peers reach this machine on
+const SSH_PORT: u16 = 22;
+
+/// Another cass machine found on the LAN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Peer {
+    /// Advertised instance name (the peer's host name)
+    pub name: String,
+    /// mDNS host name, e.g. `desktop.local`
+    pub host: String,
+    /// SSH user to connect as
+    pub user: Option<String>,
+    pub port: u16,
+    pub addresses: Vec<String>,
+    pub version: Option<String>,
+}
+
+impl Peer {
+    /// SSH target for a `type = "cass"` source; an `ssh://` URL when the peer's SSH
+    /// port isn't the default, since plain `user@host` can't carry one
+    pub fn ssh_host(&self) -> String {
+        let target = match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        };
+        if self.port == SSH_PORT {
+            target
+        } else {
+            format!("ssh://{target}:{}", self.port)
+        }
+    }
+}
+
+/// Host name of an SSH target (`host`, `user@host` or `ssh://user@host:port`)
+pub fn target_host(target: &str) -> &str {
+    let target = target.strip_prefix("ssh://").unwrap_or(target);
+    let host = target.rsplit('@').next().unwrap_or(target);
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    host.trim_end_matches('.')
+}
+
+/// Add `peer` to `peers`, leaving out this machine (`own`) and replacing an earlier
+/// announcement of the same instance.
+fn merge_peer(peers: &mut Vec<Peer>, peer: Peer, own: Option<&str>) {
+    if own.is_some_and(|own| own.eq_ignore_ascii_case(&peer.name)) {
+        return;
+    }
+    match peers.iter_mut().find(|p| p.name == peer.name) {
+        Some(existing) => *existing = peer,
+        None => peers.push(peer),
+    }
+}
+
+/// This machine's host name, without any domain
+pub fn local_hostname() -> Option<String> {
+    let name = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            let out = std::process::Command::new("hostname").output().ok()?;
+            out.status
+                .success()
+                .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        })?;
+    let short = name.split('.').next().unwrap_or_default().to_string();
+    (!short.is_empty()).then_some(short)
+}
+
+fn local_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|u| !u.is_empty())
+}
+
+/// A registered advertisement; withdrawn when dropped.
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Announce this machine as a cass peer until the returned value is dropped.
+pub fn advertise() -> Result<Advertisement> {
+    let name = local_hostname().context("cannot determine this machine's host name")?;
+    let mut properties =
+        HashMap::from([("version".to_string(), env!("CARGO_PKG_VERSION").to_string())]);
+    if let Some(user) = local_user() {
+        properties.insert("user".to_string(), user);
+    }
+    let daemon = ServiceDaemon::new().context("starting mDNS responder")?;
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &name,
+        &format!("{name}.local."),
+        "",
+        SSH_PORT,
+        properties,
+    )
+    .context("building mDNS service record")?
+    .enable_addr_auto();
+    let fullname = info.get_fullname().to_string();
+    daemon.register(info).context("registering mDNS service")?;
+    Ok(Advertisement { daemon, fullname })
+}
+
+/// Browse for advertised peers for `timeout`, leaving out this machine.
+pub fn browse(timeout: Duration) -> Result<Vec<Peer>> {
+    let daemon = ServiceDaemon::new().context("starting mDNS browser")?;
+    let receiver = daemon
+        .browse(SERVICE_TYPE)
+        .context("browsing for cass peers")?;
+    let own = local_hostname();
+    let deadline = Instant::now() + timeout;
+    let mut peers: Vec<Peer> = Vec::new();
+
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = receiver.recv_timeout(left) else {
+            break;
+        };
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let host = info.get_hostname().trim_end_matches('.').to_string();
+        let name = info
+            .get_fullname()
+            .strip_suffix(&format!(".{SERVICE_TYPE}"))
+            .unwrap_or(info.get_fullname())
+            .to_string();
+        let mut addresses: Vec<String> =
+            info.get_addresses().iter().map(|a| a.to_string()).collect();
+        addresses.sort();
+        let peer = Peer {
+            name,
+            host,
+            user: info.get_property_val_str("user").map(str::to_string),
+            port: info.get_port(),
+            addresses,
+            version: info.get_property_val_str("version").map(str::to_string),
+        };
+        merge_peer(&mut peers, peer, own.as_deref());
+    }
+    let _ = daemon.shutdown();
+    peers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(peers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(name: &str, user: Option<&str>, port: u16) -> Peer {
+        Peer {
+            name: name.to_string(),
+            host: format!("{name}.local"),
+            user: user.map(str::to_string),
+            port,
+            addresses: Vec::new(),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn ssh_host_carries_a_non_default_port() {
+        assert_eq!(
+            peer("desktop", Some("me"), 22).ssh_host(),
+            "me@desktop.local"
+        );
+        assert_eq!(peer("desktop", None, 22).ssh_host(), "desktop.local");
+        assert_eq!(
+            peer("desktop", Some("me"), 2222).ssh_host(),
+            "ssh://me@desktop.local:2222"
+        );
+        for port in [22, 2222] {
+            let target = peer("desktop", Some("me"), port).ssh_host();
+            assert_eq!(target_host(&target), "desktop.local");
+        }
+        assert_eq!(target_host("desktop.local."), "desktop.local");
+    }
+
+    #[test]
+    fn merge_peer_skips_this_machine_and_keeps_the_latest_announcement() {
+        let mut peers = Vec::new();
+        merge_peer(&mut peers, peer("desktop", None, 22), Some("laptop"));
+        merge_peer(&mut peers, peer("LAPTOP", None, 22), Some("laptop"));
+        merge_peer(
+            &mut peers,
+            peer("desktop", Some("me"), 2222),
+            Some("laptop"),
+        );
+        assert_eq!(peers, [peer("desktop", Some("me"), 2222)]);
+    }
+}
@@ -7,8 +7,11 @@
 //! # Architecture
 //!
 //! - **config**: Configuration types for defining remote sources
+//! - **discover**: mDNS advertising and discovery of other cass machines
 //! - **provenance**: Types for tracking conversation origins
 //! - **sync**: Sync engine for pulling sessions from remotes via rsync/SSH
+//! - **serve**: The stream `cass serve --sync` writes for `type = "cass"` peers
+//! - **staging**: Encryption of synced files for sources with `encrypt = true`
 //! - **status** (future): Sync status tracking
 //!
 //! # Configuration
@@ -69,7 +72,10 @@
 //! ```
 
 pub mod config;
+pub mod discover;
 pub mod provenance;
+pub mod serve;
+pub mod staging;
 pub mod sync;
 
 // Re-export commonly used config types
@@ -39,8 +39,12 @@ pub enum SourceKind {
     Local,
     /// Remote machine via SSH.
     Ssh,
+    /// S3/GCS/MinIO bucket (or anything else rclone can reach).
+    #[serde(rename = "object_store")]
+    ObjectStore,
+    /// Another machine running cass, streaming over SSH (`cass serve --sync`).
+    Cass,
     // Future extensions:
-    // S3,
     // Git,
     // Http,
 }
@@ -56,6 +60,8 @@ impl SourceKind {
         match self {
             Self::Local => "local",
             Self::Ssh => "ssh",
+            Self::ObjectStore => "object_store",
+            Self::Cass => "cass",
         }
     }
 
@@ -64,6 +70,8 @@ impl SourceKind {
         match s.to_lowercase().as_str() {
             "local" => Some(Self::Local),
             "ssh" => Some(Self::Ssh),
+            "object_store" => Some(Self::ObjectStore),
+            "cass" => Some(Self::Cass),
             _ => None,
         }
     }
@@ -244,10 +252,14 @@ impl Origin {
     /// Returns format like "local" or "laptop (remote)".
     pub fn display_label(&self) -> String {
         match (&self.host, &self.kind) {
-            (Some(host), SourceKind::Ssh) => format!("{} (remote)", host),
+            (Some(host), SourceKind::Ssh | SourceKind::ObjectStore | SourceKind::Cass) => {
+                format!("{} (remote)", host)
+            }
             (Some(host), SourceKind::Local) => host.clone(),
             (None, SourceKind::Local) => "local".to_string(),
-            (None, SourceKind::Ssh) => format!("{} (remote)", self.source_id),
+            (None, SourceKind::Ssh | SourceKind::ObjectStore | SourceKind::Cass) => {
+                format!("{} (remote)", self.source_id)
+            }
         }
     }
 
@@ -278,6 +290,8 @@ pub enum SourceFilter {
     Remote,
     /// Match a specific source by ID.
     SourceId(String),
+    /// Match any of several filters (repeated `--source`).
+    Any(Vec<SourceFilter>),
 }
 
 impl SourceFilter {
@@ -303,12 +317,34 @@ impl SourceFilter {
             Self::Local => origin.is_local(),
             Self::Remote => origin.is_remote(),
             Self::SourceId(id) => origin.source_id == *id,
+            Self::Any(filters) => filters.iter().any(|f| f.matches(origin)),
+        }
+    }
+
+    /// Combine several filters into one that matches any of them.
+    pub fn any_of(mut filters: Vec<SourceFilter>) -> Self {
+        match filters.len() {
+            0 => Self::All,
+            1 => filters.remove(0),
+            _ => Self::Any(filters),
+        }
+    }
+
+    /// The alternatives this filter accepts; a single filter is its own alternative.
+    pub fn alternatives(&self) -> Vec<&SourceFilter> {
+        match self {
+            Self::Any(filters) => filters.iter().collect(),
+            other => vec![other],
         }
     }
 
     /// Check if this filter allows any source.
     pub fn is_all(&self) -> bool {
-        matches!(self, Self::All)
+        match self {
+            Self::All => true,
+            Self::Any(filters) => filters.iter().any(SourceFilter::is_all),
+            _ => false,
+        }
     }
 
     /// Cycle to the next filter in sequence (for F11 hotkey).
@@ -320,7 +356,7 @@ impl SourceFilter {
             Self::All => Self::Local,
             Self::Local => Self::Remote,
             Self::Remote => Self::All,
-            Self::SourceId(_) => Self::All,
+            Self::SourceId(_) | Self::Any(_) => Self::All,
         }
     }
 }
@@ -332,6 +368,10 @@ impl std::fmt::Display for SourceFilter {
             Self::Local => write!(f, "local"),
             Self::Remote => write!(f, "remote"),
             Self::SourceId(id) => write!(f, "{}", id),
+            Self::Any(filters) => {
+                let names: Vec<String> = filters.iter().map(ToString::to_string).collect();
+                write!(f, "{}", names.join(","))
+            }
         }
     }
 }
@@ -349,6 +389,7 @@ mod tests {
     fn test_source_kind_is_remote() {
         assert!(!SourceKind::Local.is_remote());
         assert!(SourceKind::Ssh.is_remote());
+        assert!(SourceKind::ObjectStore.is_remote());
     }
 
     #[test]
@@ -373,6 +414,14 @@ mod tests {
             "\"local\""
         );
         assert_eq!(serde_json::to_string(&SourceKind::Ssh).unwrap(), "\"ssh\"");
+        assert_eq!(
+            serde_json::to_string(&SourceKind::ObjectStore).unwrap(),
+            "\"object_store\""
+        );
+        assert_eq!(
+            SourceKind::parse(SourceKind::ObjectStore.as_str()),
+            Some(SourceKind::ObjectStore)
+        );
     }
 
     #[test]
@@ -0,0 +1,336 @@
+//! `cass serve --sync`: stream this machine's conversations to another cass.
+//!
+//! A peer with a `type = "cass"` source runs `ssh <host> cass serve --sync`
+//! and saves the output in its mirror as `<fetch time>.cass.jsonl`. Every line
+//! is one [`NormalizedConversation`], so the peer indexes the file with the
+//! cass connector through the same provenance pipeline as rsync'd session
+//! logs. SSH authenticates the peer; the server only reads its database.
+//!
+//! The last line is a trailer, `{"cursor": N}`: the largest `indexed_at` in
+//! the server's database when the stream started. The peer passes it back
+//! with `--cursor` next time and gets only conversations stored since, so
+//! neither machine's clock matters. A stream without a trailer was cut off.
+//!
+//! Only conversations recorded on this machine are served. Ones synced here
+//! from elsewhere stay out of the stream, so machines that pull from each
+//! other never pass sessions back and forth.
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::connectors::{
+    MessageSource, NormalizedConversation, NormalizedMessage, NormalizedSnippet,
+};
+use crate::model::types::{Conversation, MessageRole};
+use crate::storage::sqlite::SqliteStorage;
+
+/// File name suffix of a saved stream in a cass source's mirror
+pub const STREAM_SUFFIX: &str = ".cass.jsonl";
+
+/// Saved streams a mirror holds before [`compact_streams`] merges them
+pub const COMPACT_AFTER: usize = 8;
+
+/// Last line of a stream: where the next fetch picks up
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Trailer {
+    cursor: i64,
+}
+
+/// The fields that tell conversations apart within a source
+#[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+struct ConversationKey {
+    agent_slug: String,
+    external_id: Option<String>,
+    source_path: PathBuf,
+}
+
+/// The connector form of a stored conversation
+pub fn to_normalized(conv: Conversation) -> NormalizedConversation {
+    let source_path = conv.source_path;
+    let messages = conv
+        .messages
+        .into_iter()
+        .map(|m| NormalizedMessage {
+            idx: m.idx,
+            role: match m.role {
+                MessageRole::User => "user".to_string(),
+                MessageRole::Agent => "agent".to_string(),
+                MessageRole::Tool => "tool".to_string(),
+                MessageRole::System => "system".to_string(),
+                MessageRole::Other(other) => other,
+            },
+            author: m.author,
+            created_at: m.created_at,
+            content: m.content,
+            extra: m.extra_json,
+            snippets: m
+                .snippets
+                .into_iter()
+                .map(|s| NormalizedSnippet {
+                    file_path: s.file_path,
+                    start_line: s.start_line,
+                    end_line: s.end_line,
+                    language: s.language,
+                    snippet_text: s.snippet_text,
+                })
+                .collect(),
+            source: (m.source_path.is_some() || m.source_line.is_some()).then(|| MessageSource {
+                path: m
+                    .source_path
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| source_path.clone()),
+                line: m.source_line,
+            }),
+        })
+        .collect();
+    NormalizedConversation {
+        agent_slug: conv.agent_slug,
+        external_id: conv.external_id,
+        title: conv.title,
+        workspace: conv.workspace,
+        source_path,
+        started_at: conv.started_at,
+        ended_at: conv.ended_at,
+        metadata: conv.metadata_json,
+        messages,
+    }
+}
+
+/// Write every local conversation stored at or after `cursor` and active at
+/// or after `since` (unix millis) to `out`, one JSON line each, then the
+/// trailer. Returns how many conversations were written.
+pub fn write_stream(
+    storage: &SqliteStorage,
+    cursor: Option<i64>,
+    since: Option<i64>,
+    out: &mut impl Write,
+) -> Result<usize> {
+    // Read first: anything stored while streaming is sent again next time
+    let next_cursor = storage.max_indexed_at()?;
+    let mut written = 0;
+    for id in storage.local_conversations_indexed_since(cursor)? {
+        let Some(conv) = storage.get_conversation(id)? else {
+            continue;
+        };
+        let last_activity = conv.ended_at.or(conv.started_at);
+        if since.is_some_and(|since| last_activity.is_some_and(|t| t < since)) {
+            continue;
+        }
+        serde_json::to_writer(&mut *out, &to_normalized(conv))?;
+        out.write_all(b"\n")?;
+        written += 1;
+    }
+    if let Some(cursor) = next_cursor.or(cursor) {
+        serde_json::to_writer(&mut *out, &Trailer { cursor })?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// What a saved stream holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    pub conversations: u64,
+    pub bytes: u64,
+    /// From the trailer; `None` if the stream was cut off
+    pub cursor: Option<i64>,
+}
+
+/// Count a saved stream's conversations and read its trailer, a line at a time.
+pub fn summarize_stream(path: &Path) -> Result<StreamSummary> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let bytes = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut lines = 0;
+    let (mut line, mut last) = (String::new(), String::new());
+    loop {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .with_context(|| format!("reading {}", path.display()))?
+            == 0
+        {
+            break;
+        }
+        if !line.trim().is_empty() {
+            lines += 1;
+            std::mem::swap(&mut line, &mut last);
+        }
+    }
+    let cursor = serde_json::from_str::<Trailer>(&last)
+        .ok()
+        .map(|t| t.cursor);
+    Ok(StreamSummary {
+        conversations: lines - u64::from(cursor.is_some()),
+        bytes,
+        cursor,
+    })
+}
+
+/// Conversations in a saved stream
+pub fn read_stream(path: &Path) -> Result<Vec<NormalizedConversation>> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let mut convs = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("reading {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(conv) => convs.push(conv),
+            Err(_) if serde_json::from_str::<Trailer>(&line).is_ok() => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("{}: line {} is malformed", path.display(), n + 1));
+            }
+        }
+    }
+    Ok(convs)
+}
+
+/// Merge the saved streams in `dir` into the newest one once there are
+/// [`COMPACT_AFTER`] of them, keeping the latest copy of each conversation.
+/// Returns how many files were removed.
+pub fn compact_streams(dir: &Path) -> Result<usize> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("listing {}", dir.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(STREAM_SUFFIX))
+        })
+        .collect();
+    if files.len() < COMPACT_AFTER {
+        return Ok(0);
+    }
+    // Fetch times name the files; read newest first so its copies win
+    files.sort();
+    files.reverse();
+    let newest = files[0].clone();
+    let merged = newest.with_extension("compacting");
+
+    let mut out = std::io::BufWriter::new(
+        std::fs::File::create(&merged).with_context(|| format!("creating {}", merged.display()))?,
+    );
+    let mut seen = HashSet::new();
+    for path in &files {
+        let file =
+            std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("reading {}", path.display()))?;
+            // Trailers and blank lines have no key
+            let Ok(key) = serde_json::from_str::<ConversationKey>(&line) else {
+                continue;
+            };
+            if seen.insert(key) {
+                out.write_all(line.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+        }
+    }
+    out.flush()?;
+    drop(out);
+
+    std::fs::rename(&merged, &newest).with_context(|| format!("replacing {}", newest.display()))?;
+    for path in &files[1..] {
+        std::fs::remove_file(path).with_context(|| format!("removing {}", path.display()))?;
+    }
+    Ok(files.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn line(external_id: &str, content: &str) -> String {
+        json!({
+            "agent_slug": "codex",
+            "external_id": external_id,
+            "title": null,
+            "workspace": null,
+            "source_path": format!("/home/me/.codex/sessions/{external_id}.jsonl"),
+            "started_at": 1_700_000_000_000_i64,
+            "ended_at": null,
+            "metadata": {},
+            "messages": [{
+                "idx": 0, "role": "user", "author": null, "created_at": null,
+                "content": content, "extra": {}, "snippets": []
+            }]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn trailer_carries_the_cursor_and_is_not_a_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        let done = dir.path().join("1.cass.jsonl");
+        std::fs::write(
+            &done,
+            format!(
+                "{}\n{}\n{{\"cursor\":42}}\n",
+                line("a", "x"),
+                line("b", "y")
+            ),
+        )
+        .unwrap();
+        let summary = summarize_stream(&done).unwrap();
+        assert_eq!(summary.conversations, 2);
+        assert_eq!(summary.cursor, Some(42));
+        assert_eq!(summary.bytes, std::fs::metadata(&done).unwrap().len());
+        assert_eq!(read_stream(&done).unwrap().len(), 2);
+
+        let cut_off = dir.path().join("2.cass.jsonl");
+        std::fs::write(&cut_off, format!("{}\n", line("a", "x"))).unwrap();
+        let summary = summarize_stream(&cut_off).unwrap();
+        assert_eq!((summary.conversations, summary.cursor), (1, None));
+    }
+
+    #[test]
+    fn compaction_keeps_the_latest_copy_of_each_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        for n in 1..COMPACT_AFTER {
+            std::fs::write(
+                dir.path().join(format!("{n}.cass.jsonl")),
+                format!("{}\n{{\"cursor\":{n}}}\n", line("a", &format!("v{n}"))),
+            )
+            .unwrap();
+        }
+        assert_eq!(compact_streams(dir.path()).unwrap(), 0);
+
+        let newest = dir.path().join(format!("{COMPACT_AFTER}.cass.jsonl"));
+        std::fs::write(&newest, format!("{}\n", line("b", "only"))).unwrap();
+        assert_eq!(compact_streams(dir.path()).unwrap(), COMPACT_AFTER - 1);
+
+        let left: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(left, std::slice::from_ref(&newest));
+        let convs = read_stream(&newest).unwrap();
+        let contents: Vec<_> = convs
+            .iter()
+            .map(|c| {
+                (
+                    c.external_id.clone().unwrap(),
+                    c.messages[0].content.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            contents,
+            [
+                ("b".to_string(), "only".to_string()),
+                ("a".to_string(), format!("v{}", COMPACT_AFTER - 1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Encryption of staged remote data, for sources with `encrypt = true`.
+//!
+//! After each sync every file in the source's mirror is replaced by
+//! `<name>.cassenc`: `CASSENC1`, a random 12-byte nonce, then the AES-256-GCM
+//! ciphertext of the file. The key is derived from the database key (see
+//! [`crate::storage::encryption::staging_key`]), so staged copies of other
+//! machines' sessions are protected by the same secret as the index.
+//!
+//! Indexing decrypts a mirror into `remotes/<name>/plaintext/`, a private
+//! (0700) directory with the mirror's layout, for the duration of the scan
+//! only. Connectors scan that copy and [`Plaintext::restore_paths`] points the
+//! results back at the mirror, so stored `source_path`s are the usual ones.
+//! [`Plaintext`] removes the copy when dropped; since a crash skips `Drop`,
+//! every scan first clears whatever copy an earlier run left behind.
+//! Decrypted files keep the original's mtime, so incremental scans still
+//! skip unchanged sessions.
+//!
+//! Encrypted mirrors give up rsync's delta transfers: without the previous
+//! plaintext, every sync fetches each path in full.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use aes_gcm::{
+    Aes256Gcm, Nonce,
+    aead::{Aead, KeyInit},
+};
+use anyhow::{Context, Result, bail};
+use walkdir::WalkDir;
+
+use super::config::SourcesConfig;
+use crate::connectors::{NormalizedConversation, ScanRoot};
+
+/// Suffix of an encrypted file in a mirror
+pub const ENCRYPTED_SUFFIX: &str = ".cassenc";
+
+const MAGIC: &[u8; 8] = b"CASSENC1";
+const NONCE_SIZE: usize = 12;
+
+fn cipher(key: &[u8; 32]) -> Result<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| anyhow::anyhow!("invalid staging key: {e}"))
+}
+
+/// Encrypt `plaintext` into the `.cassenc` format.
+pub fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
+    use ring::rand::SecureRandom;
+    let mut nonce = [0u8; NONCE_SIZE];
+    ring::rand::SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow::anyhow!("system random number generator unavailable"))?;
+    let ciphertext = cipher(key)?
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| anyhow::anyhow!("encryption failed: {e}"))?;
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_SIZE + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data in the `.cassenc` format.
+pub fn decrypt_bytes(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>> {
+    let Some(rest) = data.strip_prefix(MAGIC.as_slice()) else {
+        bail!("not a cass-encrypted file");
+    };
+    if rest.len() < NONCE_SIZE {
+        bail!("encrypted file is truncated");
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+    cipher(key)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("decryption failed: wrong key or corrupted file"))
+}
+
+fn is_encrypted(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(ENCRYPTED_SUFFIX))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Encrypt every plaintext file under `dir` in place. Returns how many were encrypted.
+pub fn encrypt_tree(dir: &Path, key: &[u8; 32]) -> Result<usize> {
+    let mut encrypted = 0;
+    for entry in WalkDir::new(dir).into_iter().flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_file() || is_encrypted(path) {
+            continue;
+        }
+        // Left behind by an interrupted run; its source is still in plaintext
+        if path.to_string_lossy().ends_with(".cassenc.partial") {
+            fs::remove_file(path)?;
+            continue;
+        }
+        let plaintext = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let target = with_suffix(path, ENCRYPTED_SUFFIX);
+        let partial = with_suffix(&target, ".partial");
+        fs::write(&partial, encrypt_bytes(key, &plaintext)?)
+            .with_context(|| format!("writing {}", partial.display()))?;
+        // The sealed copy carries the session's mtime for the decrypted one to restore
+        set_modified(&partial, entry.metadata()?.modified()?)?;
+        fs::rename(&partial, &target)?;
+        fs::remove_file(path)?;
+        encrypted += 1;
+    }
+    Ok(encrypted)
+}
+
+fn set_modified(path: &Path, mtime: SystemTime) -> Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(mtime))
+        .with_context(|| format!("setting mtime of {}", path.display()))
+}
+
+/// Where a mirror is decrypted for scanning: `plaintext/` next to it.
+pub fn plaintext_dir(mirror: &Path) -> PathBuf {
+    mirror.with_file_name("plaintext")
+}
+
+/// Remove the decrypted copy of `mirror`, if an earlier run left one behind.
+pub fn clear_plaintext(mirror: &Path) -> Result<()> {
+    let dir = plaintext_dir(mirror);
+    match fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("removing {}", dir.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(dir)
+        .with_context(|| format!("creating {}", dir.display()))
+}
+
+/// Mirrors decrypted for a scan; the copies are removed again when dropped.
+#[derive(Debug, Default)]
+pub struct Plaintext {
+    /// `(mirror, decrypted copy)` pairs
+    dirs: Vec<(PathBuf, PathBuf)>,
+    files: usize,
+}
+
+impl Plaintext {
+    pub fn len(&self) -> usize {
+        self.files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files == 0
+    }
+
+    /// `root` as connectors should scan it: inside the decrypted copy when it
+    /// lies in a decrypted mirror, unchanged otherwise.
+    pub fn scan_root(&self, root: &ScanRoot) -> ScanRoot {
+        let mut root = root.clone();
+        for (mirror, plain) in &self.dirs {
+            if let Ok(rest) = root.path.strip_prefix(mirror) {
+                root.path = plain.join(rest);
+                break;
+            }
+        }
+        root
+    }
+
+    /// Point the paths of a conversation scanned from a decrypted copy back
+    /// at the mirror, so nothing stored refers to the short-lived plaintext.
+    pub fn restore_paths(&self, conv: &mut NormalizedConversation) {
+        let restore = |path: &mut PathBuf| {
+            for (mirror, plain) in &self.dirs {
+                if let Ok(rest) = path.strip_prefix(plain) {
+                    *path = mirror.join(rest);
+                    return;
+                }
+            }
+        };
+        restore(&mut conv.source_path);
+        for msg in &mut conv.messages {
+            if let Some(source) = &mut msg.source {
+                restore(&mut source.path);
+            }
+        }
+    }
+}
+
+impl Drop for Plaintext {
+    fn drop(&mut self) {
+        for (mirror, _) in &self.dirs {
+            if let Err(e) = clear_plaintext(mirror) {
+                tracing::warn!("removing decrypted staging files: {e:#}");
+            }
+        }
+    }
+}
+
+/// Decrypt every `.cassenc` file under `mirror` into its private plaintext
+/// directory, replacing any stale copy. Files that are still in plaintext
+/// (synced but not yet encrypted) are linked in and win over a sealed copy;
+/// files that fail to decrypt are skipped with a warning.
+pub fn decrypt_tree(mirror: &Path, key: &[u8; 32], plaintext: &mut Plaintext) -> Result<()> {
+    clear_plaintext(mirror)?;
+    let plain_root = plaintext_dir(mirror);
+    create_private_dir(&plain_root)?;
+    plaintext
+        .dirs
+        .push((mirror.to_path_buf(), plain_root.clone()));
+
+    for entry in WalkDir::new(mirror).into_iter().flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(rel) = path.strip_prefix(mirror) else {
+            continue;
+        };
+        if !is_encrypted(path) {
+            if path.to_string_lossy().ends_with(".partial") {
+                continue;
+            }
+            let target = plain_root.join(rel);
+            create_private_dir(target.parent().unwrap_or(&plain_root))?;
+            if fs::hard_link(path, &target).is_err() {
+                fs::copy(path, &target).with_context(|| format!("copying {}", path.display()))?;
+            }
+            plaintext.files += 1;
+            continue;
+        }
+        let original = path.with_file_name(
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(ENCRYPTED_SUFFIX))
+                .unwrap_or_default(),
+        );
+        // A newer plaintext copy (synced but not yet encrypted) wins
+        if original.exists() {
+            continue;
+        }
+        let target = plain_root.join(original.strip_prefix(mirror).unwrap_or(rel));
+        let data = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        match decrypt_bytes(key, &data) {
+            Ok(bytes) => {
+                create_private_dir(target.parent().unwrap_or(&plain_root))?;
+                fs::write(&target, bytes)
+                    .with_context(|| format!("writing {}", target.display()))?;
+                set_modified(&target, entry.metadata()?.modified()?)?;
+                plaintext.files += 1;
+            }
+            Err(e) => tracing::warn!(path = %path.display(), "skipping staged file: {e:#}"),
+        }
+    }
+    Ok(())
+}
+
+/// Decrypt the mirrors of every source with `encrypt = true` under `data_dir`
+/// for a scan. Sources whose key is unavailable are skipped with a warning.
+pub fn decrypt_mirrors(data_dir: &Path) -> Plaintext {
+    let mut plaintext = Plaintext::default();
+    if std::env::var("CASS_IGNORE_SOURCES_CONFIG").is_ok() {
+        return plaintext;
+    }
+    let Ok(config) = SourcesConfig::load() else {
+        return plaintext;
+    };
+    for source in config.remote_sources() {
+        let mirror = data_dir.join("remotes").join(&source.name).join("mirror");
+        if !source.encrypt || !mirror.exists() {
+            // Also covers sources whose `encrypt` was turned off after a crash
+            if let Err(e) = clear_plaintext(&mirror) {
+                tracing::warn!(source = %source.name, "{e:#}");
+            }
+            continue;
+        }
+        let result = crate::storage::encryption::staging_key()
+            .and_then(|key| decrypt_tree(&mirror, &key, &mut plaintext));
+        if let Err(e) = result {
+            tracing::warn!(source = %source.name, "not indexing encrypted mirror: {e:#}");
+        }
+    }
+    plaintext
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_tree_round_trips_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = [7u8; 32];
+        let session = dir.path().join("projects/app/session.jsonl");
+        fs::create_dir_all(session.parent().unwrap()).unwrap();
+        fs::write(&session, "{\"role\":\"user\"}\n").unwrap();
+
+        assert_eq!(encrypt_tree(dir.path(), &key).unwrap(), 1);
+        assert!(!session.exists());
+        let sealed = with_suffix(&session, ENCRYPTED_SUFFIX);
+        let data = fs::read(&sealed).unwrap();
+        assert!(data.starts_with(MAGIC));
+        assert!(decrypt_bytes(&[8u8; 32], &data).is_err());
+        // Already-encrypted files are left alone
+        assert_eq!(encrypt_tree(dir.path(), &key).unwrap(), 0);
+
+        let plain = plaintext_dir(dir.path()).join("projects/app/session.jsonl");
+        {
+            let mut plaintext = Plaintext::default();
+            decrypt_tree(dir.path(), &key, &mut plaintext).unwrap();
+            assert_eq!(plaintext.len(), 1);
+            // Decrypted into the private copy, never back into the mirror
+            assert!(!session.exists());
+            assert_eq!(fs::read_to_string(&plain).unwrap(), "{\"role\":\"user\"}\n");
+        }
+        assert!(!plain.exists());
+        assert!(sealed.exists());
+    }
+
+    #[test]
+    fn decrypted_copy_keeps_mtime_and_maps_back_to_the_mirror() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mirror = tmp.path().join("remotes/laptop/mirror");
+        let key = [7u8; 32];
+        let session = mirror.join("home/.codex/sessions/rollout.jsonl");
+        fs::create_dir_all(session.parent().unwrap()).unwrap();
+        fs::write(&session, "{}\n").unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        set_modified(&session, mtime).unwrap();
+        encrypt_tree(&mirror, &key).unwrap();
+
+        // A copy left behind by a run that never got to drop its Plaintext
+        let stale = plaintext_dir(&mirror).join("stale.jsonl");
+        fs::create_dir_all(stale.parent().unwrap()).unwrap();
+        fs::write(&stale, "old").unwrap();
+
+        let mut plaintext = Plaintext::default();
+        decrypt_tree(&mirror, &key, &mut plaintext).unwrap();
+        assert!(!stale.exists());
+        let plain = plaintext_dir(&mirror).join("home/.codex/sessions/rollout.jsonl");
+        assert_eq!(fs::metadata(&plain).unwrap().modified().unwrap(), mtime);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(plaintext_dir(&mirror))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+
+        let root = ScanRoot::remote(
+            mirror.join("home"),
+            crate::sources::provenance::Origin::remote("laptop"),
+            None,
+        );
+        let scanned = plaintext.scan_root(&root);
+        assert_eq!(scanned.path, plaintext_dir(&mirror).join("home"));
+
+        let mut conv = NormalizedConversation {
+            agent_slug: "codex".into(),
+            external_id: None,
+            title: None,
+            workspace: None,
+            source_path: plain.clone(),
+            started_at: None,
+            ended_at: None,
+            metadata: serde_json::Value::Null,
+            messages: Vec::new(),
+        };
+        plaintext.restore_paths(&mut conv);
+        assert_eq!(conv.source_path, session);
+        drop(plaintext);
+        assert!(!plaintext_dir(&mirror).exists());
+    }
+}
@@ -1,7 +1,9 @@
 //! Sync engine for pulling agent sessions from remote sources.
 //!
 //! This module provides the core sync functionality using rsync over SSH
-//! for efficient delta transfers, with progress reporting and error recovery.
+//! for efficient delta transfers, falling back to OpenSSH's scp where rsync
+//! is missing, with progress reporting and error recovery. Object store
+//! sources are fetched with `rclone copy` into the same mirror layout.
 //!
 //! # Safety
 //!
@@ -24,6 +26,7 @@
 //! }
 //! ```
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
@@ -31,6 +34,12 @@ use std::time::Instant;
 use thiserror::Error;
 
 use super::config::SourceDefinition;
+use super::provenance::SourceKind;
+use super::serve::{self, STREAM_SUFFIX};
+use super::staging;
+
+/// Where a cass source's mirror keeps the remote's cursor between fetches
+const CASS_CURSOR_FILE: &str = "cursor";
 
 /// Errors that can occur during sync operations.
 #[derive(Error, Debug)]
@@ -41,6 +50,9 @@ pub enum SyncError {
     #[error("Source has no paths configured")]
     NoPaths,
 
+    #[error("Object store source has no bucket configured")]
+    NoBucket,
+
     #[error("rsync command failed: {0}")]
     RsyncFailed(String),
 
@@ -55,6 +67,9 @@ pub enum SyncError {
 
     #[error("Sync cancelled")]
     Cancelled,
+
+    #[error("Staging encryption failed: {0}")]
+    Encryption(String),
 }
 
 /// Method used for syncing files from remote.
@@ -62,8 +77,12 @@ pub enum SyncError {
 pub enum SyncMethod {
     /// rsync over SSH - preferred for delta transfers
     Rsync,
-    /// SFTP fallback when rsync is unavailable
+    /// scp (SFTP) fallback when rsync is unavailable
     Sftp,
+    /// rclone for object store sources
+    Rclone,
+    /// `cass serve --sync` over SSH for cass sources
+    Cass,
 }
 
 impl std::fmt::Display for SyncMethod {
@@ -71,6 +90,8 @@ impl std::fmt::Display for SyncMethod {
         match self {
             Self::Rsync => write!(f, "rsync"),
             Self::Sftp => write!(f, "sftp"),
+            Self::Rclone => write!(f, "rclone"),
+            Self::Cass => write!(f, "cass"),
         }
     }
 }
@@ -163,6 +184,72 @@ impl SyncReport {
     pub fn failed_paths(&self) -> usize {
         self.path_results.iter().filter(|r| !r.success).count()
     }
+
+    /// Summary passed to `sync-complete` hooks.
+    pub fn hook_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "source": self.source_name,
+            "method": self.method.to_string(),
+            "success": self.all_succeeded,
+            "files": self.total_files(),
+            "bytes": self.total_bytes(),
+            "failed_paths": self.failed_paths(),
+            "duration_ms": self.total_duration_ms,
+        })
+    }
+}
+
+/// How a remote file compares with the mirror.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    New,
+    Changed,
+    Unchanged,
+}
+
+/// What syncing one path would fetch, from [`SyncEngine::preview_source`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PathPreview {
+    /// Remote path that was listed.
+    pub remote_path: String,
+    pub new_files: u64,
+    pub changed_files: u64,
+    pub unchanged_files: u64,
+    /// Size of the new and changed files.
+    pub bytes: u64,
+    /// New and changed files, relative to the mirror of this path.
+    pub files: Vec<String>,
+    /// Why the path couldn't be listed.
+    pub error: Option<String>,
+}
+
+impl PathPreview {
+    fn new(remote_path: &str) -> Self {
+        Self {
+            remote_path: remote_path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn failed(remote_path: &str, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(remote_path)
+        }
+    }
+
+    fn add(&mut self, name: &str, size: u64, change: FileChange) {
+        match change {
+            FileChange::New => self.new_files += 1,
+            FileChange::Changed => self.changed_files += 1,
+            FileChange::Unchanged => {
+                self.unchanged_files += 1;
+                return;
+            }
+        }
+        self.bytes += size;
+        self.files.push(name.to_string());
+    }
 }
 
 /// Statistics parsed from rsync output.
@@ -181,6 +268,8 @@ pub struct SyncEngine {
     connection_timeout: u64,
     /// Transfer timeout in seconds (0 = no timeout).
     transfer_timeout: u64,
+    /// Bandwidth cap in KiB/s, overriding each source's `bwlimit`.
+    bwlimit: Option<u32>,
 }
 
 impl SyncEngine {
@@ -193,6 +282,7 @@ impl SyncEngine {
             local_store: data_dir.to_path_buf(),
             connection_timeout: 10,
             transfer_timeout: 300, // 5 minutes
+            bwlimit: None,
         }
     }
 
@@ -208,6 +298,12 @@ impl SyncEngine {
         self
     }
 
+    /// Cap bandwidth for every source, in KiB/s.
+    pub fn with_bwlimit(mut self, kib_per_sec: Option<u32>) -> Self {
+        self.bwlimit = kib_per_sec;
+        self
+    }
+
     /// Get the local mirror directory for a source.
     pub fn mirror_dir(&self, source_name: &str) -> PathBuf {
         self.local_store
@@ -233,19 +329,51 @@ impl SyncEngine {
     /// Sync a single source.
     ///
     /// Syncs all configured paths from the source to the local mirror directory.
-    /// Individual path failures don't abort the entire sync.
+    /// Individual path failures don't abort the entire sync. Sources with
+    /// `encrypt = true` have their mirror encrypted afterwards ([`staging`]).
     pub fn sync_source(&self, source: &SourceDefinition) -> Result<SyncReport, SyncError> {
         if !source.is_remote() {
             return Err(SyncError::NoHost);
         }
+        // Check the key before fetching anything that would then sit in plaintext
+        let key = if source.encrypt {
+            let key = crate::storage::encryption::staging_key()
+                .map_err(|e| SyncError::Encryption(format!("{e:#}")))?;
+            Some(key)
+        } else {
+            None
+        };
 
-        let host = source.host.as_ref().ok_or(SyncError::NoHost)?;
+        let report = self.fetch_source(source)?;
+        if let Some(key) = key {
+            let encrypted = staging::encrypt_tree(&self.mirror_dir(&source.name), &key)
+                .map_err(|e| SyncError::Encryption(format!("{e:#}")))?;
+            tracing::debug!(source = %source.name, encrypted, "encrypted staged files");
+        }
+        Ok(report)
+    }
 
-        if source.paths.is_empty() {
+    /// Fetch every configured path into the mirror.
+    fn fetch_source(&self, source: &SourceDefinition) -> Result<SyncReport, SyncError> {
+        // SSH host, or `remote:bucket/prefix` for object stores
+        let (location, method) = match source.source_type {
+            SourceKind::Cass => (
+                source.host.clone().ok_or(SyncError::NoHost)?,
+                SyncMethod::Cass,
+            ),
+            _ if source.is_ssh() => (
+                source.host.clone().ok_or(SyncError::NoHost)?,
+                Self::detect_sync_method(),
+            ),
+            _ => (
+                source.object_store_root().ok_or(SyncError::NoBucket)?,
+                SyncMethod::Rclone,
+            ),
+        };
+        if method != SyncMethod::Cass && source.paths.is_empty() {
             return Err(SyncError::NoPaths);
         }
 
-        let method = Self::detect_sync_method();
         let mut report = SyncReport::new(&source.name, method);
         let overall_start = Instant::now();
 
@@ -253,12 +381,27 @@ impl SyncEngine {
         let mirror_dir = self.mirror_dir(&source.name);
         std::fs::create_dir_all(&mirror_dir)?;
 
+        let sync_path = match method {
+            SyncMethod::Rsync => Self::sync_path_rsync,
+            SyncMethod::Sftp => Self::sync_path_sftp,
+            SyncMethod::Rclone => Self::sync_path_rclone,
+            // One stream per source; `paths` are the remote cass's business
+            SyncMethod::Cass => {
+                report.add_path_result(self.sync_cass(&location, &mirror_dir));
+                report.total_duration_ms = overall_start.elapsed().as_millis() as u64;
+                return Ok(report);
+            }
+        };
+
+        let bwlimit = self.bwlimit.or(source.bwlimit);
         for remote_path in &source.paths {
-            let result = match method {
-                SyncMethod::Rsync => self.sync_path_rsync(host, remote_path, &mirror_dir),
-                SyncMethod::Sftp => self.sync_path_sftp(host, remote_path, &mirror_dir),
-            };
-            report.add_path_result(result);
+            report.add_path_result(sync_path(
+                self,
+                &location,
+                remote_path,
+                &mirror_dir,
+                bwlimit,
+            ));
         }
 
         report.total_duration_ms = overall_start.elapsed().as_millis() as u64;
@@ -281,10 +424,62 @@ impl SyncEngine {
             .collect()
     }
 
+    /// List what [`Self::sync_source`] would fetch, without changing the mirror.
+    ///
+    /// rsync reports per file what it would transfer. Without rsync, and for
+    /// object stores, the remote listing is compared with the mirror by size
+    /// (and modification time, where rclone reports it). A cass source can't
+    /// be listed without fetching its stream.
+    pub fn preview_source(&self, source: &SourceDefinition) -> Result<Vec<PathPreview>, SyncError> {
+        if !source.is_remote() {
+            return Err(SyncError::NoHost);
+        }
+        if source.source_type == SourceKind::Cass {
+            return Ok(vec![PathPreview::failed(
+                "cass serve --sync",
+                "cass sources can't be previewed; the stream is only known once fetched".into(),
+            )]);
+        }
+        if source.paths.is_empty() {
+            return Err(SyncError::NoPaths);
+        }
+
+        let mirror_dir = self.mirror_dir(&source.name);
+        if source.is_ssh() {
+            let host = source.host.as_deref().ok_or(SyncError::NoHost)?;
+            let method = Self::detect_sync_method();
+            Ok(source
+                .paths
+                .iter()
+                .map(|path| match method {
+                    SyncMethod::Rsync => self.preview_path_rsync(host, path, &mirror_dir),
+                    _ => self.preview_path_ssh(host, path, &mirror_dir),
+                })
+                .collect())
+        } else {
+            let root = source.object_store_root().ok_or(SyncError::NoBucket)?;
+            Ok(source
+                .paths
+                .iter()
+                .map(|path| self.preview_path_rclone(&root, path, &mirror_dir))
+                .collect())
+        }
+    }
+
     /// Sync a single path using rsync.
     ///
     /// **IMPORTANT**: Uses rsync WITHOUT --delete for safe additive syncs.
-    fn sync_path_rsync(&self, host: &str, remote_path: &str, dest_dir: &Path) -> PathSyncResult {
+    ///
+    /// rsync skips files whose size and mtime match the mirror, and for the
+    /// rest sends only changed blocks, so a session log that grew since the
+    /// last sync costs roughly its new tail. `-z` compresses on the wire.
+    fn sync_path_rsync(
+        &self,
+        host: &str,
+        remote_path: &str,
+        dest_dir: &Path,
+        bwlimit: Option<u32>,
+    ) -> PathSyncResult {
         let start = Instant::now();
 
         // Expand ~ in remote path
@@ -330,9 +525,12 @@ impl SyncEngine {
             &self.transfer_timeout.to_string(),
             "-e",
             &ssh_opts,
-            &remote_spec,
-            local_path.to_str().unwrap_or("."),
         ]);
+        if let Some(kib) = bwlimit {
+            cmd.arg(format!("--bwlimit={kib}"));
+        }
+        cmd.arg(&remote_spec)
+            .arg(local_path.to_str().unwrap_or("."));
 
         tracing::debug!(
             host = %host,
@@ -413,26 +611,611 @@ impl SyncEngine {
         }
     }
 
-    /// Sync a single path using SFTP (fallback when rsync unavailable).
+    /// Sync a single path with `scp` (fallback when rsync is unavailable).
     ///
-    /// This is a placeholder for Windows/no-rsync environments.
-    /// TODO: Implement using ssh2 or russh crate.
-    fn sync_path_sftp(&self, host: &str, remote_path: &str, dest_dir: &Path) -> PathSyncResult {
+    /// OpenSSH's scp speaks SFTP and ships with Windows, so this works where
+    /// rsync doesn't. It copies the whole tree on every run instead of deltas;
+    /// like rsync it never removes local files.
+    fn sync_path_sftp(
+        &self,
+        host: &str,
+        remote_path: &str,
+        dest_dir: &Path,
+        bwlimit: Option<u32>,
+    ) -> PathSyncResult {
         let start = Instant::now();
+        let expanded_path = if remote_path.starts_with('~') && !remote_path.starts_with("~/") {
+            remote_path.replacen('~', "~/", 1)
+        } else {
+            remote_path.to_string()
+        };
+        let local_path = dest_dir.join(path_to_safe_dirname(&expanded_path));
+        let failed = |local_path: PathBuf, error: String| PathSyncResult {
+            remote_path: remote_path.to_string(),
+            local_path,
+            success: false,
+            error: Some(error),
+            duration_ms: start.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&local_path) {
+            return failed(local_path, format!("Failed to create directory: {}", e));
+        }
+        let before = tree_files(&local_path);
+
+        let mut cmd = Command::new("scp");
+        if let Some(kib) = bwlimit {
+            // scp's -l is in Kbit/s
+            cmd.args(["-l", &(u64::from(kib) * 8).to_string()]);
+        }
+        cmd.args([
+            "-r",
+            "-p", // Keep mtimes so the indexer's incremental scan still works
+            "-q",
+            "-C",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            &format!("ConnectTimeout={}", self.connection_timeout),
+            "-o",
+            "StrictHostKeyChecking=accept-new",
+            &format!("{}:{}", host, expanded_path),
+        ]);
+        cmd.arg(&local_path);
+
+        tracing::debug!(
+            host = %host,
+            remote_path = %expanded_path,
+            local_path = %local_path.display(),
+            "starting scp"
+        );
+
+        let output = match cmd.output() {
+            Ok(o) => o,
+            Err(e) => {
+                return failed(
+                    local_path,
+                    format!(
+                        "Failed to execute scp: {}. Install rsync or OpenSSH to sync from {}",
+                        e, host
+                    ),
+                );
+            }
+        };
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error_msg = if stderr.contains("No such file or directory") {
+                format!("Remote path not found: {}", expanded_path)
+            } else if stderr.contains("Permission denied") {
+                format!("Permission denied: {}", stderr.trim())
+            } else {
+                format!("scp failed: {}", stderr.trim())
+            };
+            tracing::warn!(host = %host, remote_path = %expanded_path, error = %error_msg, "scp failed");
+            return failed(local_path, error_msg);
+        }
+
+        // scp reports nothing parseable; count what it changed in the mirror
+        let (files_transferred, bytes_transferred) =
+            changed_files(&before, &tree_files(&local_path));
+        let duration_ms = start.elapsed().as_millis() as u64;
+        tracing::info!(
+            host = %host,
+            remote_path = %expanded_path,
+            files = files_transferred,
+            duration_ms,
+            "scp completed"
+        );
 
-        // For now, return an error indicating SFTP is not yet implemented
         PathSyncResult {
             remote_path: remote_path.to_string(),
-            local_path: dest_dir.join(path_to_safe_dirname(remote_path)),
+            local_path,
+            files_transferred,
+            bytes_transferred,
+            success: true,
+            error: None,
+            duration_ms,
+        }
+    }
+
+    /// Fetch conversations from a cass source with `cass serve --sync`.
+    ///
+    /// Each fetch lands in `cass/<start millis>.cass.jsonl` under the mirror.
+    /// The remote's cursor from the previous stream is kept in `cass/cursor`,
+    /// so only conversations the remote stored since then come over; once
+    /// enough files pile up they are compacted into the newest.
+    fn sync_cass(&self, host: &str, dest_dir: &Path) -> PathSyncResult {
+        let start = Instant::now();
+        let local_path = dest_dir.join("cass");
+        let failed = |error: String| PathSyncResult {
+            remote_path: "cass serve --sync".to_string(),
+            local_path: local_path.clone(),
             success: false,
-            error: Some(format!(
-                "SFTP fallback not yet implemented. Install rsync to sync from {}",
-                host
-            )),
+            error: Some(error),
             duration_ms: start.elapsed().as_millis() as u64,
             ..Default::default()
+        };
+        if let Err(e) = std::fs::create_dir_all(&local_path) {
+            return failed(format!("Failed to create directory: {}", e));
+        }
+
+        let cursor_file = local_path.join(CASS_CURSOR_FILE);
+        let cursor = std::fs::read_to_string(&cursor_file)
+            .ok()
+            .and_then(|c| c.trim().parse::<i64>().ok());
+        let fetched_at = chrono::Utc::now().timestamp_millis();
+        let dest = local_path.join(format!("{fetched_at}{STREAM_SUFFIX}"));
+        let partial = dest.with_extension("partial");
+        let file = match std::fs::File::create(&partial) {
+            Ok(f) => f,
+            Err(e) => return failed(format!("Failed to create {}: {}", partial.display(), e)),
+        };
+
+        let mut cmd = Command::new("ssh");
+        cmd.args([
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            &format!("ConnectTimeout={}", self.connection_timeout),
+            "-o",
+            "StrictHostKeyChecking=accept-new",
+            host,
+            "cass",
+            "serve",
+            "--sync",
+        ]);
+        if let Some(cursor) = cursor {
+            cmd.args(["--cursor", &cursor.to_string()]);
+        }
+        cmd.stdout(file);
+
+        tracing::debug!(host = %host, cursor = ?cursor, "starting cass serve --sync");
+
+        let output = match cmd.output() {
+            Ok(o) => o,
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                return failed(format!("Failed to execute ssh: {}", e));
+            }
+        };
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&partial);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error_msg = if stderr.contains("command not found") {
+                format!("cass is not installed on {} (or not on its PATH)", host)
+            } else {
+                format!("cass serve failed: {}", stderr.trim())
+            };
+            tracing::warn!(host = %host, error = %error_msg, "cass serve failed");
+            return failed(error_msg);
+        }
+
+        let summary = match serve::summarize_stream(&partial) {
+            Ok(summary) => summary,
+            Err(e) => return failed(format!("{e:#}")),
+        };
+        if summary.conversations == 0 {
+            let _ = std::fs::remove_file(&partial);
+        } else if let Err(e) = std::fs::rename(&partial, &dest) {
+            return failed(format!("Failed to save {}: {}", dest.display(), e));
+        }
+        // Without a trailer the stream was cut short; fetch from the old cursor again
+        if let Some(next) = summary.cursor
+            && let Err(e) = std::fs::write(&cursor_file, next.to_string())
+        {
+            return failed(format!("Failed to save {}: {}", cursor_file.display(), e));
+        }
+        match serve::compact_streams(&local_path) {
+            Ok(0) => {}
+            Ok(removed) => tracing::debug!(host = %host, removed, "compacted cass streams"),
+            Err(e) => tracing::warn!(host = %host, "compacting cass streams: {e:#}"),
+        }
+        let duration_ms = start.elapsed().as_millis() as u64;
+        tracing::info!(
+            host = %host,
+            conversations = summary.conversations,
+            duration_ms,
+            "cass serve completed"
+        );
+
+        PathSyncResult {
+            remote_path: "cass serve --sync".to_string(),
+            local_path,
+            files_transferred: summary.conversations,
+            bytes_transferred: summary.bytes,
+            success: true,
+            error: None,
+            duration_ms,
+        }
+    }
+
+    /// Sync one key prefix of an object store with `rclone copy`.
+    ///
+    /// rclone skips objects whose size and modification time match the
+    /// mirror and never deletes local files. Objects are copied whole.
+    fn sync_path_rclone(
+        &self,
+        root: &str,
+        remote_path: &str,
+        dest_dir: &Path,
+        bwlimit: Option<u32>,
+    ) -> PathSyncResult {
+        let start = Instant::now();
+        let (remote_spec, local_path) = rclone_target(root, remote_path, dest_dir);
+        let failed = |local_path: PathBuf, error: String| PathSyncResult {
+            remote_path: remote_path.to_string(),
+            local_path,
+            success: false,
+            error: Some(error),
+            duration_ms: start.elapsed().as_millis() as u64,
+            ..Default::default()
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&local_path) {
+            return failed(local_path, format!("Failed to create directory: {}", e));
+        }
+        let before = tree_size(&local_path);
+
+        let mut cmd = Command::new("rclone");
+        cmd.args([
+            "copy",
+            "--verbose", // Logs the final "Transferred: n / m" count
+            "--contimeout",
+            &format!("{}s", self.connection_timeout),
+            "--timeout",
+            &format!("{}s", self.transfer_timeout),
+        ]);
+        if let Some(kib) = bwlimit {
+            cmd.args(["--bwlimit", &format!("{kib}K")]);
+        }
+        cmd.arg(&remote_spec).arg(&local_path);
+
+        tracing::debug!(
+            remote = %remote_spec,
+            local_path = %local_path.display(),
+            "starting rclone"
+        );
+
+        let output = match cmd.output() {
+            Ok(o) => o,
+            Err(e) => {
+                return failed(
+                    local_path,
+                    format!(
+                        "Failed to execute rclone: {}. Install rclone to sync object stores",
+                        e
+                    ),
+                );
+            }
+        };
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            let error_msg = if stderr.contains("directory not found") {
+                format!("Remote path not found: {}", remote_spec)
+            } else if stderr.contains("didn't find section in config file") {
+                format!("rclone remote not configured: {}", stderr.trim())
+            } else {
+                format!("rclone failed: {}", stderr.trim())
+            };
+            tracing::warn!(remote = %remote_spec, error = %error_msg, "rclone failed");
+            return failed(local_path, error_msg);
+        }
+
+        let after = tree_size(&local_path);
+        let files_transferred =
+            parse_rclone_transferred(&stderr).unwrap_or(after.0.saturating_sub(before.0));
+        let duration_ms = start.elapsed().as_millis() as u64;
+        tracing::info!(
+            remote = %remote_spec,
+            files = files_transferred,
+            duration_ms,
+            "rclone completed"
+        );
+
+        PathSyncResult {
+            remote_path: remote_path.to_string(),
+            local_path,
+            files_transferred,
+            bytes_transferred: after.1.saturating_sub(before.1),
+            success: true,
+            error: None,
+            duration_ms,
+        }
+    }
+
+    /// Ask rsync what it would transfer (`--dry-run`, itemizing every file).
+    fn preview_path_rsync(&self, host: &str, remote_path: &str, dest_dir: &Path) -> PathPreview {
+        let expanded_path = if remote_path.starts_with('~') && !remote_path.starts_with("~/") {
+            remote_path.replacen('~', "~/", 1)
+        } else {
+            remote_path.to_string()
+        };
+        let local_path = dest_dir.join(path_to_safe_dirname(&expanded_path));
+        let ssh_opts = format!(
+            "ssh -o BatchMode=yes -o ConnectTimeout={} -o StrictHostKeyChecking=accept-new",
+            self.connection_timeout
+        );
+
+        // Same source and destination as `sync_path_rsync`; `-ii` itemizes
+        // unchanged files too
+        let output = Command::new("rsync")
+            .args([
+                "-a",
+                "--dry-run",
+                "-ii",
+                "--out-format=%i %l %n",
+                "--protect-args",
+                "--timeout",
+                &self.transfer_timeout.to_string(),
+                "-e",
+                &ssh_opts,
+            ])
+            .arg(format!("{}:{}", host, expanded_path))
+            .arg(&local_path)
+            .output();
+
+        match output {
+            Ok(out) if out.status.success() => {
+                let mut preview = PathPreview::new(remote_path);
+                for (name, size, change) in parse_itemized(&String::from_utf8_lossy(&out.stdout)) {
+                    preview.add(name, size, change);
+                }
+                preview
+            }
+            Ok(out) => PathPreview::failed(
+                remote_path,
+                format!(
+                    "rsync dry run failed: {}",
+                    String::from_utf8_lossy(&out.stderr).trim()
+                ),
+            ),
+            Err(e) => PathPreview::failed(remote_path, format!("Failed to execute rsync: {}", e)),
         }
     }
+
+    /// List the remote path over SSH and compare sizes with the mirror, for
+    /// hosts synced with scp (which copies every file again regardless).
+    fn preview_path_ssh(&self, host: &str, remote_path: &str, dest_dir: &Path) -> PathPreview {
+        let expanded_path = if remote_path.starts_with('~') && !remote_path.starts_with("~/") {
+            remote_path.replacen('~', "~/", 1)
+        } else {
+            remote_path.to_string()
+        };
+        // scp -r puts the remote directory itself inside the mirror path
+        let local_root = dest_dir.join(path_to_safe_dirname(&expanded_path)).join(
+            Path::new(expanded_path.trim_end_matches('/'))
+                .file_name()
+                .unwrap_or_default(),
+        );
+        let remote_dir = match expanded_path.strip_prefix("~/") {
+            Some(rest) => format!("~/{}", sh_quote(rest)),
+            None => sh_quote(&expanded_path),
+        };
+
+        let output = Command::new("ssh")
+            .args([
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                &format!("ConnectTimeout={}", self.connection_timeout),
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                host,
+                &format!("cd {remote_dir} && find . -type f -exec wc -c {{}} +"),
+            ])
+            .output();
+
+        match output {
+            Ok(out) if out.status.success() => {
+                let mut preview = PathPreview::new(remote_path);
+                for line in String::from_utf8_lossy(&out.stdout).lines() {
+                    let Some((size, name)) = line.trim_start().split_once(' ') else {
+                        continue;
+                    };
+                    let (Ok(size), Some(name)) = (size.parse(), name.strip_prefix("./")) else {
+                        continue; // wc's "total" line
+                    };
+                    let change = compare_with_mirror(&local_root.join(name), size, None);
+                    preview.add(name, size, change);
+                }
+                preview
+            }
+            Ok(out) => PathPreview::failed(
+                remote_path,
+                format!(
+                    "Listing {} failed: {}",
+                    expanded_path,
+                    String::from_utf8_lossy(&out.stderr).trim()
+                ),
+            ),
+            Err(e) => PathPreview::failed(remote_path, format!("Failed to execute ssh: {}", e)),
+        }
+    }
+
+    /// List an object store prefix with `rclone lsjson` and compare it with
+    /// the mirror the way `rclone copy` does: by size and modification time.
+    fn preview_path_rclone(&self, root: &str, remote_path: &str, dest_dir: &Path) -> PathPreview {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Entry {
+            path: String,
+            size: i64,
+            mod_time: Option<String>,
+        }
+
+        let (remote_spec, local_path) = rclone_target(root, remote_path, dest_dir);
+
+        let output = Command::new("rclone")
+            .args([
+                "lsjson",
+                "-R",
+                "--files-only",
+                "--contimeout",
+                &format!("{}s", self.connection_timeout),
+                &remote_spec,
+            ])
+            .output();
+
+        match output {
+            Ok(out) if out.status.success() => {
+                let entries: Vec<Entry> = match serde_json::from_slice(&out.stdout) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        return PathPreview::failed(
+                            remote_path,
+                            format!("Unreadable rclone listing: {}", e),
+                        );
+                    }
+                };
+                let mut preview = PathPreview::new(remote_path);
+                for entry in entries {
+                    let size = entry.size.max(0) as u64;
+                    let mtime = entry
+                        .mod_time
+                        .as_deref()
+                        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                        .map(|t| t.timestamp());
+                    let change = compare_with_mirror(&local_path.join(&entry.path), size, mtime);
+                    preview.add(&entry.path, size, change);
+                }
+                preview
+            }
+            Ok(out) => PathPreview::failed(
+                remote_path,
+                format!(
+                    "rclone lsjson failed: {}",
+                    String::from_utf8_lossy(&out.stderr).trim()
+                ),
+            ),
+            Err(e) => PathPreview::failed(remote_path, format!("Failed to execute rclone: {}", e)),
+        }
+    }
+}
+
+/// Files from rsync `--dry-run -ii --out-format='%i %l %n'` output as
+/// (name, size, change). Directories, symlinks and messages are skipped.
+fn parse_itemized(output: &str) -> Vec<(&str, u64, FileChange)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            // The change summary is a fixed 11 characters, padded with spaces
+            let (item, rest) = line.split_at_checked(11)?;
+            if item.as_bytes().get(1) != Some(&b'f') {
+                return None;
+            }
+            let (size, name) = rest.trim_start().split_once(' ')?;
+            let size = size.replace(',', "").parse().ok()?;
+            let change = if item.starts_with('.') {
+                FileChange::Unchanged
+            } else if item[2..].starts_with("+++") {
+                FileChange::New
+            } else {
+                FileChange::Changed
+            };
+            Some((name, size, change))
+        })
+        .collect()
+}
+
+/// Compare a remote file's size and mtime (unix seconds) with its mirror copy.
+fn compare_with_mirror(local: &Path, size: u64, mtime: Option<i64>) -> FileChange {
+    let Ok(meta) = std::fs::metadata(local) else {
+        return FileChange::New;
+    };
+    let local_mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    let same_time = match (mtime, local_mtime) {
+        (Some(remote), Some(local)) => (remote - local).abs() <= 1,
+        _ => true,
+    };
+    if meta.len() == size && same_time {
+        FileChange::Unchanged
+    } else {
+        FileChange::Changed
+    }
+}
+
+/// Quote a value for a POSIX shell.
+pub fn sh_quote(value: &str) -> String {
+    if value.is_empty() {
+        "''".to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\"'\"'"))
+    }
+}
+
+/// The rclone spec for `remote_path` under `root`, and the mirror directory it
+/// is copied into. `rclone copy` copies a prefix's contents, while rsync
+/// creates the source directory itself, so the prefix's last segment is
+/// appended to get the same `<safe name>/<basename>` layout.
+fn rclone_target(root: &str, remote_path: &str, dest_dir: &Path) -> (String, PathBuf) {
+    let key = remote_path.trim_start_matches('~').trim_matches('/');
+    let (remote_spec, basename) = if key.is_empty() {
+        let bucket = root.rsplit([':', '/']).find(|s| !s.is_empty());
+        (root.to_string(), bucket.unwrap_or("root"))
+    } else {
+        (
+            format!("{}/{}", root, key),
+            key.rsplit('/').next().unwrap_or(key),
+        )
+    };
+    let local_path = dest_dir
+        .join(path_to_safe_dirname(remote_path))
+        .join(basename);
+    (remote_spec, local_path)
+}
+
+/// File count from rclone's `Transferred: 3 / 3, 100%` stats line (the
+/// other `Transferred:` line carries byte sizes with units).
+fn parse_rclone_transferred(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once("Transferred:"))
+        .filter_map(|(_, rest)| rest.split('/').next()?.trim().parse().ok())
+        .next_back()
+}
+
+/// Number of files and total bytes under `dir`
+pub fn tree_size(dir: &Path) -> (u64, u64) {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .fold((0, 0), |(files, bytes), m| (files + 1, bytes + m.len()))
+}
+
+/// Size and modification time of every file under `dir`
+fn tree_files(dir: &Path) -> HashMap<PathBuf, (u64, Option<std::time::SystemTime>)> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((e.into_path(), (meta.len(), meta.modified().ok())))
+        })
+        .collect()
+}
+
+/// Number and total size of the files that are new or differ in `after`, as
+/// rsync would count them. scp -p keeps mtimes, so files copied again
+/// unchanged don't count and files left behind in the mirror never do.
+fn changed_files(
+    before: &HashMap<PathBuf, (u64, Option<std::time::SystemTime>)>,
+    after: &HashMap<PathBuf, (u64, Option<std::time::SystemTime>)>,
+) -> (u64, u64) {
+    after
+        .iter()
+        .filter(|(path, state)| before.get(*path) != Some(state))
+        .fold((0, 0), |(files, bytes), (_, (len, _))| {
+            (files + 1, bytes + len)
+        })
 }
 
 /// Convert a remote path to a safe directory name.
@@ -628,6 +1411,31 @@ Total transferred file size: 1,234 bytes
         assert_eq!(stats.bytes_transferred, 1234);
     }
 
+    #[test]
+    fn test_parse_rclone_transferred() {
+        let output = "Transferred:   \t   12.500 KiB / 12.500 KiB, 100%, 0 B/s, ETA -\n\
+                      Transferred:            3 / 3, 100%\n\
+                      Elapsed time:         0.4s\n";
+        assert_eq!(parse_rclone_transferred(output), Some(3));
+        assert_eq!(parse_rclone_transferred("nothing to do"), None);
+    }
+
+    #[test]
+    fn test_rclone_target_keeps_the_prefix_directory() {
+        let mirror = Path::new("/data/remotes/s3/mirror");
+        let (spec, local) = rclone_target("s3:bucket", "~/.claude/projects", mirror);
+        assert_eq!(spec, "s3:bucket/.claude/projects");
+        assert_eq!(
+            local,
+            mirror
+                .join(path_to_safe_dirname("~/.claude/projects"))
+                .join("projects")
+        );
+        let (spec, local) = rclone_target("gcs:logs/", "/", mirror);
+        assert_eq!(spec, "gcs:logs/");
+        assert!(local.ends_with("logs"));
+    }
+
     #[test]
     fn test_parse_rsync_stats_empty() {
         let stats = parse_rsync_stats("");
@@ -688,9 +1496,93 @@ Total transferred file size: 1,234 bytes
         assert_eq!(mirror, PathBuf::from("/data/cass/remotes/laptop/mirror"));
     }
 
+    #[test]
+    fn test_encrypted_source_needs_key_before_fetching() {
+        let tmp = tempfile::tempdir().unwrap();
+        let engine = SyncEngine::new(tmp.path());
+        let mut source = SourceDefinition::ssh("laptop", "user@laptop.invalid");
+        source.paths = vec!["~/.claude/projects".into()];
+        source.encrypt = true;
+        if crate::storage::encryption::configured_key_source().is_some() {
+            return;
+        }
+        assert!(matches!(
+            engine.sync_source(&source),
+            Err(SyncError::Encryption(_))
+        ));
+        // Nothing was fetched in plaintext
+        assert!(!engine.mirror_dir("laptop").exists());
+    }
+
     #[test]
     fn test_sync_method_display() {
         assert_eq!(SyncMethod::Rsync.to_string(), "rsync");
         assert_eq!(SyncMethod::Sftp.to_string(), "sftp");
     }
+
+    #[test]
+    fn test_parse_itemized_classifies_files() {
+        let output = "\
+cd+++++++++ 4096 projects/
+>f+++++++++ 1200 projects/a/new.jsonl
+>f.st...... 5300 projects/a/grown.jsonl
+.f          800 projects/a/same.jsonl
+cL+++++++++ 9 projects/link
+";
+        assert_eq!(
+            parse_itemized(output),
+            vec![
+                ("projects/a/new.jsonl", 1200, FileChange::New),
+                ("projects/a/grown.jsonl", 5300, FileChange::Changed),
+                ("projects/a/same.jsonl", 800, FileChange::Unchanged),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_files_counts_only_new_and_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("kept.jsonl"), "same").unwrap();
+        std::fs::write(dir.path().join("grown.jsonl"), "short").unwrap();
+        std::fs::write(dir.path().join("stale.jsonl"), "gone remotely").unwrap();
+        let before = tree_files(dir.path());
+
+        std::fs::write(dir.path().join("grown.jsonl"), "much longer").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("new.jsonl"), "new").unwrap();
+        let after = tree_files(dir.path());
+
+        assert_eq!(after.len(), 4);
+        assert_eq!(changed_files(&before, &after), (2, 14));
+        assert_eq!(changed_files(&after, &after), (0, 0));
+    }
+
+    #[test]
+    fn test_compare_with_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("s.jsonl");
+        std::fs::write(&file, "12345").unwrap();
+        let mtime = std::fs::metadata(&file)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        assert_eq!(
+            compare_with_mirror(&dir.path().join("missing"), 5, None),
+            FileChange::New
+        );
+        assert_eq!(compare_with_mirror(&file, 5, None), FileChange::Unchanged);
+        assert_eq!(compare_with_mirror(&file, 9, None), FileChange::Changed);
+        assert_eq!(
+            compare_with_mirror(&file, 5, Some(mtime)),
+            FileChange::Unchanged
+        );
+        assert_eq!(
+            compare_with_mirror(&file, 5, Some(mtime + 3600)),
+            FileChange::Changed
+        );
+    }
 }
@@ -0,0 +1,649 @@
+//! Portable archives of the whole database (`cass db export` / `cass db import`).
+//!
+//! A `.cassar` file is zstd-compressed JSON Lines. The first line is an
+//! [`ArchiveHeader`]; every following line is one [`ArchiveRecord`]: sources,
+//! agents, conversations (with their messages, snippets, tags and notes), then
+//! locks, ratings and workspace projects. The records describe the data, not
+//! the tables that hold it, so an archive written by one schema version
+//! imports into any later one. Search indexes are not included; import adds
+//! the new conversations to Tantivy, and `cass embed` rebuilds vectors.
+//!
+//! Import merges into the existing database. Conversations are matched on
+//! (source, agent, external id), or on source path and start time when there
+//! is no external id, so importing the same archive twice adds nothing.
+//! Notes are merged into matched conversations; a note whose message already
+//! has the same text is skipped. The caller holds the index lock and checks
+//! that the target database's schema can be migrated before importing.
+//! A matched conversation whose messages differ from the archived copy is an
+//! [`ImportConflict`]; the caller decides per conflict whether to keep the
+//! local copy, take the archive's, or keep both (see [`ConflictPolicy`]).
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::model::types::{
+    Agent, AgentKind, Conversation, ConversationRating, Snippet, WorkspaceProject,
+};
+use crate::sources::provenance::{LOCAL_SOURCE_ID, Source};
+use crate::storage::sqlite::{CURRENT_SCHEMA_VERSION, SqliteStorage};
+
+/// Value of the header's `format` field
+pub const ARCHIVE_FORMAT: &str = "cassar";
+
+/// Archive layout version; bumped only when records change incompatibly
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const ZSTD_LEVEL: i32 = 9;
+
+/// Conversations read from the database per query during export
+const PAGE: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveHeader {
+    pub format: String,
+    pub version: u32,
+    /// cass version that wrote the archive
+    pub cass_version: String,
+    /// Database schema the archive was exported from (informational)
+    pub schema_version: i64,
+    pub exported_at: i64,
+    pub conversations: usize,
+}
+
+/// A note on one message, addressed by the message's position in its conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedNote {
+    pub idx: i64,
+    pub note: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedConversation {
+    pub conversation: Conversation,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: Vec<ArchivedNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Source(Source),
+    Agent(Agent),
+    Conversation(Box<ArchivedConversation>),
+    Lock {
+        source_id: String,
+        source_path: String,
+        created_at: i64,
+    },
+    Rating(ConversationRating),
+    Project(WorkspaceProject),
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveSummary {
+    pub path: PathBuf,
+    pub archive_version: u32,
+    pub sources: usize,
+    pub agents: usize,
+    pub conversations: usize,
+    pub messages: usize,
+    pub tags: usize,
+    pub notes: usize,
+    pub locks: usize,
+    pub ratings: usize,
+    pub projects: usize,
+    /// Conversations already present (import only)
+    pub skipped: usize,
+    pub bytes: u64,
+    /// Conversations created by an import, to be added to the search index
+    #[serde(skip)]
+    pub created_ids: Vec<i64>,
+    /// Conflicting conversations and how each was settled (import only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ResolvedConflict>,
+    /// Existing conversations overwritten by the archive, to be re-indexed
+    #[serde(skip)]
+    pub replaced_ids: Vec<i64>,
+}
+
+/// A conversation present both locally and in the archive with different messages
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportConflict {
+    /// Local conversation id
+    pub conversation_id: i64,
+    pub agent: String,
+    pub external_id: String,
+    pub title: Option<String>,
+    pub local_messages: usize,
+    pub archived_messages: usize,
+    /// Latest activity (unix millis) of each copy
+    pub local_updated_at: Option<i64>,
+    pub archived_updated_at: Option<i64>,
+    /// Message indices whose content differs
+    pub differing: Vec<i64>,
+    /// Local and archived text of the first differing message
+    #[serde(skip)]
+    pub first_difference: (String, String),
+}
+
+impl ImportConflict {
+    /// Keep whichever copy saw activity last; ties keep the local one
+    pub fn newest(&self) -> ConflictResolution {
+        if self.archived_updated_at > self.local_updated_at {
+            ConflictResolution::KeepArchive
+        } else {
+            ConflictResolution::KeepLocal
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Leave the local conversation as it is
+    KeepLocal,
+    /// Overwrite the differing local messages with the archive's
+    KeepArchive,
+    /// Import the archive copy as a separate conversation with a suffixed external id
+    KeepBoth,
+}
+
+/// How `cass db import` settles conflicts without asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the local conversation
+    Skip,
+    /// Keep whichever copy was updated last
+    KeepNewest,
+    /// Keep the local conversation and import the archive copy beside it
+    KeepBoth,
+    /// Show each conflict and prompt (needs a terminal)
+    Ask,
+}
+
+impl ConflictPolicy {
+    /// The resolution for a conflict, or None when the user has to choose
+    pub fn resolve(self, conflict: &ImportConflict) -> Option<ConflictResolution> {
+        match self {
+            Self::Skip => Some(ConflictResolution::KeepLocal),
+            Self::KeepNewest => Some(conflict.newest()),
+            Self::KeepBoth => Some(ConflictResolution::KeepBoth),
+            Self::Ask => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedConflict {
+    #[serde(flatten)]
+    pub conflict: ImportConflict,
+    pub resolution: ConflictResolution,
+    /// External id given to the imported copy (keep both)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imported_as: Option<String>,
+}
+
+/// Write every conversation and its user data to `path`.
+pub fn export_archive(storage: &SqliteStorage, path: &Path) -> Result<ArchiveSummary> {
+    let mut summary = ArchiveSummary {
+        path: path.to_path_buf(),
+        archive_version: ARCHIVE_VERSION,
+        ..Default::default()
+    };
+    let conn = storage.raw();
+    let total: i64 = conn.query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))?;
+
+    // Write beside the target and rename, so a failed export never leaves a truncated archive
+    let tmp = path.with_extension("cassar.partial");
+    let file = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+    let mut out = zstd::stream::write::Encoder::new(BufWriter::new(file), ZSTD_LEVEL)?;
+    write_line(
+        &mut out,
+        &ArchiveHeader {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            cass_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            exported_at: chrono::Utc::now().timestamp_millis(),
+            conversations: total.max(0) as usize,
+        },
+    )?;
+    for source in storage.list_sources()? {
+        if source.id != LOCAL_SOURCE_ID {
+            write_line(&mut out, &ArchiveRecord::Source(source))?;
+            summary.sources += 1;
+        }
+    }
+    for mut agent in storage.list_agents()? {
+        agent.id = None;
+        write_line(&mut out, &ArchiveRecord::Agent(agent))?;
+        summary.agents += 1;
+    }
+
+    let mut snippet_stmt = conn.prepare(
+        "SELECT s.file_path, s.start_line, s.end_line, s.language, s.snippet_text
+         FROM snippets s WHERE s.message_id = ? ORDER BY s.id",
+    )?;
+    let mut note_stmt = conn.prepare(
+        "SELECT m.idx, a.note, a.created_at, a.updated_at
+         FROM annotations a JOIN messages m ON a.message_id = m.id
+         WHERE m.conversation_id = ? ORDER BY m.idx, a.id",
+    )?;
+    let mut offset = 0;
+    loop {
+        let page = storage.list_conversations(PAGE, offset)?;
+        if page.is_empty() {
+            break;
+        }
+        for mut conv in page {
+            let Some(id) = conv.id.take() else { continue };
+            conv.messages = storage.fetch_messages(id)?;
+            for msg in &mut conv.messages {
+                let Some(message_id) = msg.id.take() else {
+                    continue;
+                };
+                msg.snippets = snippet_stmt
+                    .query_map([message_id], |r| {
+                        Ok(Snippet {
+                            id: None,
+                            file_path: r.get::<_, Option<String>>(0)?.map(PathBuf::from),
+                            start_line: r.get(1)?,
+                            end_line: r.get(2)?,
+                            language: r.get(3)?,
+                            snippet_text: r.get(4)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<_>>()?;
+            }
+            let notes: Vec<ArchivedNote> = note_stmt
+                .query_map([id], |r| {
+                    Ok(ArchivedNote {
+                        idx: r.get(0)?,
+                        note: r.get(1)?,
+                        created_at: r.get(2)?,
+                        updated_at: r.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            let tags = storage.conversation_tags(id)?;
+            summary.messages += conv.messages.len();
+            summary.tags += tags.len();
+            summary.notes += notes.len();
+            summary.conversations += 1;
+            write_line(
+                &mut out,
+                &ArchiveRecord::Conversation(Box::new(ArchivedConversation {
+                    conversation: conv,
+                    tags,
+                    notes,
+                })),
+            )?;
+        }
+        offset += PAGE;
+    }
+
+    for (source_id, source_path, created_at) in storage.list_retained()? {
+        write_line(
+            &mut out,
+            &ArchiveRecord::Lock {
+                source_id,
+                source_path,
+                created_at,
+            },
+        )?;
+        summary.locks += 1;
+    }
+    for rating in storage.list_ratings()? {
+        write_line(&mut out, &ArchiveRecord::Rating(rating))?;
+        summary.ratings += 1;
+    }
+    for project in storage.list_workspace_projects()? {
+        write_line(&mut out, &ArchiveRecord::Project(project))?;
+        summary.projects += 1;
+    }
+
+    out.finish()?.flush()?;
+    std::fs::rename(&tmp, path).with_context(|| format!("writing {}", path.display()))?;
+    summary.bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Ok(summary)
+}
+
+/// Read an archive's header without importing it.
+pub fn read_header(path: &Path) -> Result<ArchiveHeader> {
+    let mut lines = open_lines(path)?;
+    parse_header(path, &mut lines)
+}
+
+/// Merge an archive into `storage`, asking `resolve` how to settle each conflict.
+pub fn import_archive(
+    storage: &mut SqliteStorage,
+    path: &Path,
+    resolve: &mut dyn FnMut(&ImportConflict) -> ConflictResolution,
+) -> Result<ArchiveSummary> {
+    let mut lines = open_lines(path)?;
+    let header = parse_header(path, &mut lines)?;
+    let mut summary = ArchiveSummary {
+        path: path.to_path_buf(),
+        archive_version: header.version,
+        bytes: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        ..Default::default()
+    };
+    let mut agents: HashMap<String, i64> = HashMap::new();
+
+    for (n, line) in lines.enumerate() {
+        let line = line.with_context(|| format!("reading {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: ArchiveRecord = serde_json::from_str(&line)
+            .with_context(|| format!("{}: record {} is malformed", path.display(), n + 1))?;
+        match record {
+            ArchiveRecord::Source(source) => {
+                storage.upsert_source(&source)?;
+                summary.sources += 1;
+            }
+            ArchiveRecord::Agent(agent) => {
+                agents.insert(agent.slug.clone(), storage.ensure_agent(&agent)?);
+                summary.agents += 1;
+            }
+            ArchiveRecord::Conversation(archived) => {
+                import_conversation(storage, &mut agents, *archived, resolve, &mut summary)?;
+            }
+            ArchiveRecord::Lock {
+                source_id,
+                source_path,
+                ..
+            } => {
+                if storage.set_retained(&source_id, &source_path, true)? {
+                    summary.locks += 1;
+                }
+            }
+            ArchiveRecord::Rating(rating) => {
+                storage.set_rating(
+                    &rating.source_id,
+                    &rating.source_path,
+                    rating.rating,
+                    rating.note.as_deref(),
+                )?;
+                summary.ratings += 1;
+            }
+            ArchiveRecord::Project(project) => {
+                if project.manual {
+                    storage.set_workspace_project(&project.workspace, Some(&project.project))?;
+                } else {
+                    storage.upsert_detected_project(
+                        &project.workspace,
+                        &project.project,
+                        project.git_remote.as_deref(),
+                    )?;
+                }
+                summary.projects += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn import_conversation(
+    storage: &mut SqliteStorage,
+    agents: &mut HashMap<String, i64>,
+    archived: ArchivedConversation,
+    resolve: &mut dyn FnMut(&ImportConflict) -> ConflictResolution,
+    summary: &mut ArchiveSummary,
+) -> Result<()> {
+    let ArchivedConversation {
+        conversation: mut conv,
+        tags,
+        notes,
+    } = archived;
+    let agent_id = match agents.get(&conv.agent_slug) {
+        Some(id) => *id,
+        None => {
+            let id = storage.ensure_agent(&Agent {
+                id: None,
+                slug: conv.agent_slug.clone(),
+                name: conv.agent_slug.clone(),
+                version: None,
+                kind: AgentKind::Cli,
+            })?;
+            agents.insert(conv.agent_slug.clone(), id);
+            id
+        }
+    };
+    if conv.external_id.is_none()
+        && let Some(existing) = storage
+            .raw()
+            .query_row(
+                "SELECT id FROM conversations
+                 WHERE source_id = ? AND agent_id = ? AND source_path = ? AND started_at IS ?",
+                params![
+                    conv.source_id,
+                    agent_id,
+                    conv.source_path.to_string_lossy(),
+                    conv.started_at
+                ],
+                |r| r.get::<_, i64>(0),
+            )
+            .optional()?
+    {
+        merge_notes(storage, existing, &notes, summary)?;
+        summary.skipped += 1;
+        return Ok(());
+    }
+    if let Some(conflict) = find_conflict(storage, agent_id, &conv)? {
+        let resolution = resolve(&conflict);
+        let mut imported_as = None;
+        match resolution {
+            ConflictResolution::KeepLocal => summary.skipped += 1,
+            ConflictResolution::KeepArchive => {
+                storage.insert_conversation_tree(agent_id, None, &conv)?;
+                for tag in &tags {
+                    if storage.add_tag(conflict.conversation_id, tag)? {
+                        summary.tags += 1;
+                    }
+                }
+                merge_notes(storage, conflict.conversation_id, &notes, summary)?;
+                summary.replaced_ids.push(conflict.conversation_id);
+            }
+            ConflictResolution::KeepBoth => {
+                let suffixed = free_external_id(storage, agent_id, &conv, &conflict.external_id)?;
+                conv.external_id = Some(suffixed.clone());
+                imported_as = Some(suffixed);
+            }
+        }
+        summary.conflicts.push(ResolvedConflict {
+            conflict,
+            resolution,
+            imported_as,
+        });
+        if resolution != ConflictResolution::KeepBoth {
+            return Ok(());
+        }
+    }
+    let workspace_id = conv
+        .workspace
+        .as_deref()
+        .map(|w| storage.ensure_workspace(w, None))
+        .transpose()?;
+    let outcome = storage.insert_conversation_tree(agent_id, workspace_id, &conv)?;
+    for tag in &tags {
+        if storage.add_tag(outcome.conversation_id, tag)? {
+            summary.tags += 1;
+        }
+    }
+    merge_notes(storage, outcome.conversation_id, &notes, summary)?;
+    if !outcome.created {
+        summary.skipped += 1;
+        return Ok(());
+    }
+    summary.conversations += 1;
+    summary.messages += conv.messages.len();
+    summary.created_ids.push(outcome.conversation_id);
+    Ok(())
+}
+
+/// Add the archived notes a stored conversation lacks, keeping their timestamps.
+fn merge_notes(
+    storage: &mut SqliteStorage,
+    conversation_id: i64,
+    notes: &[ArchivedNote],
+    summary: &mut ArchiveSummary,
+) -> Result<()> {
+    for note in notes {
+        if let Some(message_id) = storage.message_id_at(conversation_id, note.idx)?
+            && storage.import_annotation(
+                message_id,
+                &note.note,
+                note.created_at,
+                note.updated_at,
+            )?
+        {
+            summary.notes += 1;
+        }
+    }
+    Ok(())
+}
+
+/// The stored conversation with the same external id, if any of the messages
+/// both copies have differ. Messages only one side has are not a conflict:
+/// the archive's extra messages are appended and local extras are kept.
+fn find_conflict(
+    storage: &SqliteStorage,
+    agent_id: i64,
+    conv: &Conversation,
+) -> Result<Option<ImportConflict>> {
+    let Some(external_id) = &conv.external_id else {
+        return Ok(None);
+    };
+    let Some((local_id, local_title, local_ended)) = storage
+        .raw()
+        .query_row(
+            "SELECT id, title, ended_at FROM conversations
+             WHERE source_id = ? AND agent_id = ? AND external_id = ?",
+            params![conv.source_id, agent_id, external_id],
+            |r| {
+                Ok((
+                    r.get::<_, i64>(0)?,
+                    r.get::<_, Option<String>>(1)?,
+                    r.get::<_, Option<i64>>(2)?,
+                ))
+            },
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let local = storage.fetch_messages(local_id)?;
+    let by_idx: HashMap<i64, &crate::model::types::Message> =
+        local.iter().map(|m| (m.idx, m)).collect();
+    let mut differing = Vec::new();
+    let mut first_difference = None;
+    for msg in &conv.messages {
+        if let Some(stored) = by_idx.get(&msg.idx)
+            && (stored.content != msg.content || stored.role != msg.role)
+        {
+            differing.push(msg.idx);
+            first_difference.get_or_insert_with(|| (stored.content.clone(), msg.content.clone()));
+        }
+    }
+    let Some(first_difference) = first_difference else {
+        return Ok(None);
+    };
+    let latest = |ended: Option<i64>, messages: &[crate::model::types::Message]| {
+        messages
+            .iter()
+            .filter_map(|m| m.created_at)
+            .max()
+            .max(ended)
+    };
+    Ok(Some(ImportConflict {
+        conversation_id: local_id,
+        agent: conv.agent_slug.clone(),
+        external_id: external_id.clone(),
+        title: local_title.or_else(|| conv.title.clone()),
+        local_messages: local.len(),
+        archived_messages: conv.messages.len(),
+        local_updated_at: latest(local_ended, &local),
+        archived_updated_at: latest(conv.ended_at, &conv.messages),
+        differing,
+        first_difference,
+    }))
+}
+
+/// `<id>.imported`, or `<id>.imported-N` when that is taken too
+fn free_external_id(
+    storage: &SqliteStorage,
+    agent_id: i64,
+    conv: &Conversation,
+    external_id: &str,
+) -> Result<String> {
+    for n in 1.. {
+        let candidate = if n == 1 {
+            format!("{external_id}.imported")
+        } else {
+            format!("{external_id}.imported-{n}")
+        };
+        let taken = storage
+            .raw()
+            .query_row(
+                "SELECT 1 FROM conversations
+                 WHERE source_id = ? AND agent_id = ? AND external_id = ?",
+                params![conv.source_id, agent_id, candidate],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !taken {
+            return Ok(candidate);
+        }
+    }
+    unreachable!("unbounded range")
+}
+
+type Lines = std::io::Lines<BufReader<zstd::stream::read::Decoder<'static, BufReader<File>>>>;
+
+fn open_lines(path: &Path) -> Result<Lines> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    let decoder = zstd::stream::read::Decoder::new(file)
+        .with_context(|| format!("{} is not a cass archive", path.display()))?;
+    Ok(BufReader::new(decoder).lines())
+}
+
+fn parse_header(path: &Path, lines: &mut Lines) -> Result<ArchiveHeader> {
+    let first = match lines.next() {
+        Some(Ok(line)) => line,
+        _ => bail!("{} is not a cass archive", path.display()),
+    };
+    let header: ArchiveHeader = match serde_json::from_str(&first) {
+        Ok(header) => header,
+        Err(_) => bail!("{} is not a cass archive", path.display()),
+    };
+    if header.format != ARCHIVE_FORMAT {
+        bail!("{} is not a cass archive", path.display());
+    }
+    if header.version > ARCHIVE_VERSION {
+        bail!(
+            "{} uses archive version {} but this cass reads up to {ARCHIVE_VERSION}; upgrade cass (it was written by {})",
+            path.display(),
+            header.version,
+            header.cass_version
+        );
+    }
+    Ok(header)
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
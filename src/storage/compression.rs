@@ -0,0 +1,132 @@
+//! Optional zstd compression of message text.
+//!
+//! With `[storage] compression = true`, messages whose content and raw JSON
+//! together reach [`COMPRESS_MIN_BYTES`] (full file dumps, long tool output)
+//! are stored as zstd blobs and flagged with `messages.compressed = 1`. Smaller
+//! messages stay plain text, where the frame overhead would outweigh the gain,
+//! and existing rows are only rewritten by `cass db optimize`.
+//!
+//! Readers never see the blobs: `SqliteStorage` decodes them, and SQL that
+//! reads the columns directly goes through `cass_text(value)`, which
+//! [`register_functions`] installs on every connection. Message text is always
+//! written as TEXT, so `cass_text` treats any BLOB as compressed; that keeps it
+//! usable on databases that predate the `compressed` column.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::{Value, ValueRef};
+
+/// Messages smaller than this are never compressed
+pub const COMPRESS_MIN_BYTES: usize = 1024;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Column values for a message's content and extra_json, plus the `compressed` flag.
+pub fn encode_message(
+    content: &str,
+    extra_json: &str,
+    enabled: bool,
+) -> Result<(Value, Value, bool)> {
+    if !enabled || content.len() + extra_json.len() < COMPRESS_MIN_BYTES {
+        return Ok((
+            Value::Text(content.to_string()),
+            Value::Text(extra_json.to_string()),
+            false,
+        ));
+    }
+    Ok((
+        Value::Blob(zstd::encode_all(content.as_bytes(), ZSTD_LEVEL)?),
+        Value::Blob(zstd::encode_all(extra_json.as_bytes(), ZSTD_LEVEL)?),
+        true,
+    ))
+}
+
+/// Read a content or extra_json column, decompressing it when the row is flagged.
+pub fn decode_text(value: ValueRef<'_>, compressed: bool) -> std::io::Result<Option<String>> {
+    let bytes = match value {
+        ValueRef::Null => return Ok(None),
+        ValueRef::Text(b) => b,
+        ValueRef::Blob(b) if compressed => {
+            let raw = zstd::decode_all(b)?;
+            return String::from_utf8(raw)
+                .map(Some)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+        }
+        ValueRef::Blob(b) => b,
+        ValueRef::Integer(i) => return Ok(Some(i.to_string())),
+        ValueRef::Real(f) => return Ok(Some(f.to_string())),
+    };
+    Ok(Some(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// Same as [`decode_text`], for use inside a row mapper.
+pub fn column_text(
+    row: &rusqlite::Row<'_>,
+    idx: usize,
+    compressed: bool,
+) -> rusqlite::Result<Option<String>> {
+    decode_text(row.get_ref(idx)?, compressed).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Blob, Box::new(e))
+    })
+}
+
+/// Register `cass_text(value)` on a connection.
+pub fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "cass_text",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let value = ctx.get_raw(0);
+            decode_text(value, matches!(value, ValueRef::Blob(_)))
+                .map_err(|e| rusqlite::Error::UserFunctionError(Box::new(e)))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_messages_round_trip_through_sql() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn.execute_batch("CREATE TABLE m (content TEXT, extra_json TEXT, compressed INTEGER)")
+            .unwrap();
+
+        let big = "fn main() {}\n".repeat(500);
+        for (content, extra) in [("short", "{}"), (big.as_str(), "{\"k\":1}")] {
+            let (c, e, flag) = encode_message(content, extra, true).unwrap();
+            assert_eq!(flag, content.len() >= COMPRESS_MIN_BYTES);
+            conn.execute(
+                "INSERT INTO m VALUES (?1, ?2, ?3)",
+                rusqlite::params![c, e, flag],
+            )
+            .unwrap();
+        }
+
+        let stored: i64 = conn
+            .query_row(
+                "SELECT LENGTH(content) FROM m WHERE compressed = 1",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!((stored as usize) < big.len() / 10);
+        let texts: Vec<(String, String)> = conn
+            .prepare("SELECT cass_text(content), cass_text(extra_json) FROM m")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(texts[0], ("short".to_string(), "{}".to_string()));
+        assert_eq!(texts[1], (big.clone(), "{\"k\":1}".to_string()));
+
+        let (c, _, flag) = encode_message(&big, "{}", false).unwrap();
+        assert!(!flag);
+        assert_eq!(c, Value::Text(big));
+    }
+}
@@ -0,0 +1,302 @@
+//! Optional encryption at rest via SQLCipher.
+//!
+//! Build with `--features sqlcipher` and choose where the key comes from:
+//!
+//! ```toml
+//! [storage]
+//! encryption = "keychain"   # or "env" to read CASS_DB_KEY
+//! ```
+//!
+//! With `keychain`, a random key is generated on first use and kept in the OS
+//! keychain (macOS Keychain, Windows Credential Manager, Secret Service on
+//! Linux). Every connection to the database applies the key before running
+//! anything else. A missing or wrong key is reported as such, never mistaken
+//! for corruption, so the indexer does not rebuild over an encrypted database.
+//! A database created before encryption was turned on is encrypted in place
+//! the next time it is opened for writing, keeping notes, tags and bookmarks.
+//!
+//! Only the SQLite database is encrypted. The Tantivy search index and the
+//! vector index in the data dir still hold message text in plaintext, so keep
+//! the data dir on an encrypted volume when that matters.
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+
+use crate::indexer::budget::StorageConfig;
+
+/// Environment variable holding the database key for `encryption = "env"`
+pub const DB_KEY_ENV: &str = "CASS_DB_KEY";
+
+/// Keychain entry used for `encryption = "keychain"`
+pub const KEYCHAIN_SERVICE: &str = "cass";
+pub const KEYCHAIN_ACCOUNT: &str = "agent_search.db";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Keychain,
+    Env,
+}
+
+/// The configured key source, read once per process.
+pub fn configured_key_source() -> Option<KeySource> {
+    static SOURCE: OnceLock<Option<KeySource>> = OnceLock::new();
+    *SOURCE.get_or_init(|| StorageConfig::load().and_then(|c| c.encryption))
+}
+
+/// Whether this build can open encrypted databases
+pub const fn supported() -> bool {
+    cfg!(feature = "sqlcipher")
+}
+
+/// Apply the database key to a freshly opened connection (no-op when encryption is off).
+pub fn apply_key(conn: &Connection) -> Result<()> {
+    let Some(source) = configured_key_source() else {
+        return Ok(());
+    };
+    if !supported() {
+        bail!(
+            "[storage] encryption is set but this cass build lacks SQLCipher; rebuild with `--features sqlcipher`"
+        );
+    }
+    conn.pragma_update(None, "key", database_key(source)?)?;
+    Ok(())
+}
+
+/// Check that the configured key opens an existing database.
+pub fn verify_key(path: &Path) -> Result<()> {
+    if configured_key_source().is_none() {
+        return Ok(());
+    }
+    let conn = Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    apply_key(&conn)?;
+    if let Err(e) = conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| {
+        r.get::<_, i64>(0)
+    }) {
+        if is_plaintext(path) {
+            bail!(
+                "{} is not encrypted yet; run 'cass index' to encrypt it in place \
+                 (notes, tags and bookmarks are kept)",
+                path.display()
+            );
+        }
+        bail!(
+            "cannot decrypt {} ({e}): the key is wrong. Restore the original key \
+             (OS keychain entry '{KEYCHAIN_SERVICE}' or {DB_KEY_ENV}); the notes, tags and \
+             bookmarks in this database can't be rebuilt from session files",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Whether `path` is a database readable without any key.
+pub fn is_plaintext(path: &Path) -> bool {
+    Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .and_then(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| {
+            r.get::<_, i64>(0)
+        })
+    })
+    .is_ok()
+}
+
+/// Encrypt an existing plaintext database with the configured key, if
+/// encryption is on. Returns whether it was encrypted.
+pub fn encrypt_if_plaintext(path: &Path) -> Result<bool> {
+    let Some(source) = configured_key_source() else {
+        return Ok(false);
+    };
+    if !path.is_file() || !is_plaintext(path) {
+        return Ok(false);
+    }
+    if !supported() {
+        bail!(
+            "[storage] encryption is set but this cass build lacks SQLCipher; rebuild with `--features sqlcipher`"
+        );
+    }
+    encrypt_in_place(path, &database_key(source)?)?;
+    tracing::info!(path = %path.display(), "encrypted the existing database");
+    Ok(true)
+}
+
+/// Copy the plaintext database at `path` into an encrypted one and swap it in.
+/// The original stays untouched until the copy is known to open with `key`.
+fn encrypt_in_place(path: &Path, key: &str) -> Result<()> {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".encrypting");
+    let encrypted = std::path::PathBuf::from(name);
+    let _ = fs::remove_file(&encrypted);
+    {
+        let conn = Connection::open(path)?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![encrypted.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+            .context("copying into the encrypted database")?;
+        conn.execute_batch("DETACH DATABASE encrypted")?;
+    }
+    {
+        let check = Connection::open(&encrypted)?;
+        check.pragma_update(None, "key", key)?;
+        check
+            .query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| {
+                r.get::<_, i64>(0)
+            })
+            .context("reopening the encrypted copy")?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut side = path.as_os_str().to_os_string();
+        side.push(suffix);
+        let _ = fs::remove_file(side);
+    }
+    fs::rename(&encrypted, path)
+        .with_context(|| format!("replacing {} with its encrypted copy", path.display()))?;
+    Ok(())
+}
+
+/// Key for the staged files of sources with `encrypt = true`, derived from
+/// the database key so one secret protects both.
+pub fn staging_key() -> Result<[u8; 32]> {
+    let Some(source) = configured_key_source() else {
+        bail!(
+            "sources with `encrypt = true` need [storage] encryption = \"keychain\" or \"env\" in config.toml"
+        );
+    };
+    Ok(derive_staging_key(&database_key(source)?))
+}
+
+fn derive_staging_key(db_key: &str) -> [u8; 32] {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA256,
+        format!("cass staging v1\0{db_key}").as_bytes(),
+    );
+    let mut out = [0u8; 32];
+    out.copy_from_slice(digest.as_ref());
+    out
+}
+
+/// The key for `source`; cached so the keychain is consulted once per process.
+fn database_key(source: KeySource) -> Result<String> {
+    static KEY: OnceLock<String> = OnceLock::new();
+    if let Some(key) = KEY.get() {
+        return Ok(key.clone());
+    }
+    let key = match source {
+        KeySource::Env => match std::env::var(DB_KEY_ENV) {
+            Ok(key) if !key.is_empty() => key,
+            _ => bail!("[storage] encryption = \"env\" but {DB_KEY_ENV} is not set"),
+        },
+        KeySource::Keychain => keychain_key()?,
+    };
+    Ok(KEY.get_or_init(|| key).clone())
+}
+
+#[cfg(feature = "sqlcipher")]
+fn keychain_key() -> Result<String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)?;
+    match entry.get_password() {
+        Ok(key) => Ok(key),
+        Err(keyring::Error::NoEntry) => {
+            let key = generate_key()?;
+            entry.set_password(&key)?;
+            tracing::info!(
+                service = KEYCHAIN_SERVICE,
+                "generated database key and stored it in the OS keychain"
+            );
+            Ok(key)
+        }
+        Err(e) => bail!("reading the database key from the OS keychain: {e}"),
+    }
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+fn keychain_key() -> Result<String> {
+    bail!("keychain support requires the `sqlcipher` feature")
+}
+
+/// 256 random bits as hex
+#[cfg_attr(not(feature = "sqlcipher"), allow(dead_code))]
+fn generate_key() -> Result<String> {
+    use ring::rand::SecureRandom;
+    let mut bytes = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow::anyhow!("system random number generator unavailable"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_are_random_hex() {
+        let a = generate_key().unwrap();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, generate_key().unwrap());
+    }
+
+    #[test]
+    fn staging_key_is_derived_but_distinct_from_the_database_key() {
+        let key = derive_staging_key("secret");
+        assert_eq!(key, derive_staging_key("secret"));
+        assert_ne!(key, derive_staging_key("other"));
+        assert_ne!(&key[..], b"secret");
+    }
+
+    #[test]
+    fn plaintext_databases_are_recognized() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE t(x); INSERT INTO t VALUES (1);")
+            .unwrap();
+        assert!(is_plaintext(&path));
+        fs::write(dir.path().join("noise.db"), [0x5au8; 4096]).unwrap();
+        assert!(!is_plaintext(&dir.path().join("noise.db")));
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn encrypting_in_place_keeps_the_data_and_needs_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent_search.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE notes(body); INSERT INTO notes VALUES ('keep me');")
+            .unwrap();
+
+        encrypt_in_place(&path, "k3y").unwrap();
+        assert!(!is_plaintext(&path));
+
+        let wrong = Connection::open(&path).unwrap();
+        wrong.pragma_update(None, "key", "nope").unwrap();
+        assert!(
+            wrong
+                .query_row("SELECT body FROM notes", [], |r| r.get::<_, String>(0))
+                .is_err()
+        );
+        let right = Connection::open(&path).unwrap();
+        right.pragma_update(None, "key", "k3y").unwrap();
+        let body: String = right
+            .query_row("SELECT body FROM notes", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(body, "keep me");
+    }
+}
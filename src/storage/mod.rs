@@ -1,2 +1,5 @@
 //! Persistent storage interfaces.
+pub mod archive;
+pub mod compression;
+pub mod encryption;
 pub mod sqlite;
@@ -1,12 +1,17 @@
 //! `SQLite` backend: schema, pragmas, and migrations.
 
-use crate::model::types::{Agent, AgentKind, Conversation, Message, MessageRole, Snippet};
-use crate::sources::provenance::{LOCAL_SOURCE_ID, Source, SourceKind};
+use crate::model::types::{
+    Agent, AgentKind, Annotation, Conversation, ConversationRating, MergedDuplicate, Message,
+    MessageRole, Snippet, WorkspaceProject,
+};
+use crate::sources::provenance::{LOCAL_SOURCE_ID, Source, SourceFilter, SourceKind};
+use crate::storage::{compression, encryption};
 use anyhow::{Context, Result, anyhow};
 use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 // -------------------------------------------------------------------------
@@ -120,7 +125,17 @@ pub fn cleanup_old_backups(db_path: &Path, keep_count: usize) -> Result<(), std:
 }
 
 /// Public schema version constant for external checks.
-pub const CURRENT_SCHEMA_VERSION: i64 = 5;
+pub const CURRENT_SCHEMA_VERSION: i64 = 21;
+
+/// Logged result clicks older than this are dropped as new ones come in.
+pub const CLICK_RETENTION_DAYS: i64 = 180;
+
+/// Most result clicks kept; the oldest beyond this are dropped as new ones come in.
+pub const MAX_RESULT_CLICKS: i64 = 10_000;
+
+/// How long a connection waits for another writer's lock before `database is locked`.
+/// Covers an indexer commit; the indexer itself is serialized by `indexer::lock`.
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Result of checking schema compatibility.
 #[derive(Debug, Clone)]
@@ -136,11 +151,13 @@ pub enum SchemaCheck {
 /// Check schema compatibility without modifying the database.
 ///
 /// Opens the database read-only and checks the schema version.
-fn check_schema_compatibility(path: &Path) -> std::result::Result<SchemaCheck, rusqlite::Error> {
+pub fn check_schema_compatibility(path: &Path) -> Result<SchemaCheck> {
     let conn = Connection::open_with_flags(
         path,
         rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
     )?;
+    encryption::apply_key(&conn)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
 
     // Check if meta table exists
     let meta_exists: i32 = conn.query_row(
@@ -195,7 +212,7 @@ fn check_schema_compatibility(path: &Path) -> std::result::Result<SchemaCheck, r
     }
 }
 
-const SCHEMA_VERSION: i64 = 5;
+const SCHEMA_VERSION: i64 = 21;
 
 const MIGRATION_V1: &str = r"
 PRAGMA foreign_keys = ON;
@@ -392,13 +409,447 @@ CREATE INDEX IF NOT EXISTS idx_conversations_source_id ON conversations(source_i
 PRAGMA foreign_keys = ON;
 ";
 
+const MIGRATION_V6: &str = r"
+-- Stable hash of the normalized conversation; reindex skips unchanged conversations
+ALTER TABLE conversations ADD COLUMN content_hash TEXT;
+CREATE INDEX IF NOT EXISTS idx_conversations_content_hash ON conversations(source_id, agent_id, content_hash);
+";
+
+const MIGRATION_V7: &str = r"
+-- Conversations locked against pruning. Keyed by source file rather than conversation id
+-- so locks survive index rebuilds (which recreate conversation rows).
+CREATE TABLE IF NOT EXISTS retained_conversations (
+    source_id TEXT NOT NULL DEFAULT 'local',
+    source_path TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (source_id, source_path)
+);
+";
+
+const MIGRATION_V8: &str = r"
+-- Per-message BPE token counts; conversations.approx_tokens holds their sum
+ALTER TABLE messages ADD COLUMN approx_tokens INTEGER;
+";
+
+const MIGRATION_V9: &str = r"
+-- Workspace -> logical project mapping (grouped by git remote, or set by hand).
+-- Keyed by workspace path rather than workspace id so manual edits survive index rebuilds.
+CREATE TABLE IF NOT EXISTS workspace_projects (
+    workspace TEXT PRIMARY KEY,
+    project TEXT NOT NULL,
+    git_remote TEXT,
+    manual INTEGER NOT NULL DEFAULT 0,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_workspace_projects_project ON workspace_projects(project);
+";
+
+const MIGRATION_V10: &str = r"
+-- Per-session access tracking for the `least-accessed` storage budget policy.
+-- Keyed like retained_conversations so it survives index rebuilds.
+CREATE TABLE IF NOT EXISTS conversation_access (
+    source_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    access_count INTEGER NOT NULL DEFAULT 0,
+    last_accessed_at INTEGER NOT NULL,
+    PRIMARY KEY (source_id, source_path)
+);
+";
+
+const MIGRATION_V11: &str = r"
+-- Thumbs up/down (+1/-1) with an optional note, used as a `rating:` filter and ranking signal.
+-- Keyed like retained_conversations so it survives index rebuilds.
+CREATE TABLE IF NOT EXISTS conversation_ratings (
+    source_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    rating INTEGER NOT NULL CHECK (rating IN (-1, 1)),
+    note TEXT,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (source_id, source_path)
+);
+";
+
+const MIGRATION_V12: &str = r"
+-- Results opened from the TUI result list, aggregated into click-through priors for ranking.
+CREATE TABLE IF NOT EXISTS result_clicks (
+    id INTEGER PRIMARY KEY,
+    source_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    agent TEXT NOT NULL,
+    workspace TEXT,
+    query TEXT,
+    clicked_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_result_clicks_session ON result_clicks(source_id, source_path);
+";
+
+const MIGRATION_V13: &str = r"
+-- Personal notes on individual messages; `note:` in a query searches them.
+CREATE TABLE IF NOT EXISTS annotations (
+    id INTEGER PRIMARY KEY,
+    message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    note TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_annotations_message ON annotations(message_id);
+-- rowid = annotations.id, kept in sync by the annotation CRUD methods
+CREATE VIRTUAL TABLE IF NOT EXISTS fts_annotations USING fts5(note, tokenize='porter');
+";
+
+const MIGRATION_V14: &str = r"
+-- One row per UTC day (last sample of the day wins) for `cass stats storage` growth history.
+-- Not keyed by conversation, so it survives index rebuilds.
+CREATE TABLE IF NOT EXISTS storage_samples (
+    day TEXT PRIMARY KEY,
+    sampled_at INTEGER NOT NULL,
+    conversations INTEGER NOT NULL,
+    messages INTEGER NOT NULL,
+    db_bytes INTEGER NOT NULL
+);
+";
+
+const MIGRATION_V15: &str = r"
+-- 1 when content and extra_json are zstd blobs (`[storage] compression`); SQL reads them via cass_text()
+ALTER TABLE messages ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0;
+";
+
+const MIGRATION_V16: &str = r"
+-- Origin kind ('local', 'ssh') from the conversation's provenance, so source filters
+-- don't depend on the sources table still listing a removed remote
+ALTER TABLE conversations ADD COLUMN origin_kind TEXT NOT NULL DEFAULT 'local';
+UPDATE conversations SET origin_kind = COALESCE(
+    (SELECT kind FROM sources WHERE sources.id = conversations.source_id),
+    CASE WHEN source_id = 'local' THEN 'local' ELSE 'ssh' END
+);
+CREATE INDEX IF NOT EXISTS idx_conversations_origin_kind ON conversations(origin_kind);
+";
+
+const MIGRATION_V17: &str = r"
+-- Per-message provenance: the originating file when it differs from the conversation's
+-- source_path (sessions assembled from several files) and the 1-based line in that file
+ALTER TABLE messages ADD COLUMN source_path TEXT;
+ALTER TABLE messages ADD COLUMN source_line INTEGER;
+";
+
+const MIGRATION_V18: &str = r"
+-- Copies of a session (same agent and external id) dropped at index time in favour of a
+-- richer copy from another source. Keyed like retained_conversations plus the session, so
+-- rescanning the dropped copy's file skips it while its content_hash is unchanged.
+CREATE TABLE IF NOT EXISTS merged_duplicates (
+    source_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    agent TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    kept_source_id TEXT NOT NULL,
+    kept_source_path TEXT NOT NULL,
+    messages INTEGER NOT NULL,
+    merged_at INTEGER NOT NULL,
+    PRIMARY KEY (source_id, source_path, agent, external_id)
+);
+CREATE INDEX IF NOT EXISTS idx_conversations_agent_external ON conversations(agent_id, external_id);
+";
+
+const MIGRATION_V19: &str = r"
+-- Conversations deleted on purpose (`cass dedupe`, deleting from the TUI). Keyed like merged_duplicates, with an
+-- empty external_id for connectors without one, so rescanning the file (even with
+-- `index --full`) skips the conversation while its content_hash is unchanged.
+CREATE TABLE IF NOT EXISTS deleted_conversations (
+    source_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    agent TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    deleted_at INTEGER NOT NULL,
+    PRIMARY KEY (source_id, source_path, agent, external_id)
+);
+";
+
+const MIGRATION_V20: &str = r"
+-- When this database last stored new or changed messages for a conversation (unix millis).
+-- `cass serve --sync` hands the largest value to its peer as a cursor, so the next fetch
+-- only streams conversations written since, whatever the peer's clock says.
+ALTER TABLE conversations ADD COLUMN indexed_at INTEGER;
+CREATE INDEX IF NOT EXISTS idx_conversations_indexed_at ON conversations(indexed_at);
+";
+
+const MIGRATION_V21: &str = r"
+-- Backfill per-message provenance (V17) for messages stored before connectors recorded it:
+-- forgetting the incremental scan watermark makes the next `cass index` rescan every
+-- session file, and storing an unchanged message fills in its source_path/source_line.
+DELETE FROM meta WHERE key = 'last_scan_ts';
+";
+
 pub struct SqliteStorage {
     conn: Connection,
+    /// zstd-compress large messages on write (`[storage] compression`)
+    compress: bool,
+}
+
+/// Ratings keyed by (source_id, source_path), for search-time filtering and boosting.
+pub fn load_ratings(conn: &Connection) -> Result<HashMap<(String, String), i8>> {
+    let mut stmt =
+        conn.prepare("SELECT source_id, source_path, rating FROM conversation_ratings")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(((row.get(0)?, row.get(1)?), row.get::<_, i8>(2)?))
+    })?;
+    let mut out = HashMap::new();
+    for r in rows {
+        let (key, rating) = r?;
+        out.insert(key, rating);
+    }
+    Ok(out)
+}
+
+/// Reserved tag behind the starred flag (`--starred`, `*` in the TUI)
+pub const STARRED_TAG: &str = "starred";
+
+/// Canonical form of a tag name: trimmed and lowercased. Tags must be a single
+/// word so they can be written as `tag:<name>` in a query.
+pub fn normalize_tag(name: &str) -> Option<String> {
+    let tag = name.trim().to_lowercase();
+    (!tag.is_empty() && !tag.contains(char::is_whitespace)).then_some(tag)
+}
+
+/// Sessions, as (source_id, source_path), with a conversation carrying every one of `tags`.
+pub fn load_tagged_sessions(
+    conn: &Connection,
+    tags: &[String],
+) -> Result<HashSet<(String, String)>> {
+    let mut out = HashSet::new();
+    if tags.is_empty() {
+        return Ok(out);
+    }
+    let placeholders = vec!["?"; tags.len()].join(",");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT c.source_id, c.source_path
+         FROM conversations c
+         JOIN conversation_tags ct ON ct.conversation_id = c.id
+         JOIN tags t ON t.id = ct.tag_id
+         WHERE t.name IN ({placeholders})
+         GROUP BY c.id
+         HAVING COUNT(DISTINCT t.name) = {}",
+        tags.len()
+    ))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(tags), |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+    for r in rows {
+        out.insert(r?);
+    }
+    Ok(out)
+}
+
+/// Which conversations a bulk edit (`cass update --filter`) applies to.
+/// Every field that is set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversationFilter {
+    /// This workspace or any directory below it
+    pub workspace: Option<String>,
+    pub agent: Option<String>,
+    pub source_id: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl ConversationFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// FTS5 expression requiring every `note:` term (as a prefix) in the same note.
+pub fn note_match_expr(terms: &[String]) -> Option<String> {
+    let parts: Vec<String> = terms
+        .iter()
+        .map(|t| t.replace('"', ""))
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| format!("\"{}\"*", t.trim()))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" AND "))
+}
+
+/// Messages, as (source_id, source_path, idx), with a note matching every one of `terms`.
+pub fn load_note_matches(
+    conn: &Connection,
+    terms: &[String],
+) -> Result<HashSet<(String, String, i64)>> {
+    let mut out = HashSet::new();
+    let Some(expr) = note_match_expr(terms) else {
+        return Ok(out);
+    };
+    let mut stmt = conn.prepare(
+        "SELECT c.source_id, c.source_path, m.idx
+         FROM fts_annotations f
+         JOIN annotations a ON a.id = f.rowid
+         JOIN messages m ON m.id = a.message_id
+         JOIN conversations c ON c.id = m.conversation_id
+         WHERE fts_annotations MATCH ?",
+    )?;
+    let rows = stmt.query_map(params![expr], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    })?;
+    for r in rows {
+        out.insert(r?);
+    }
+    Ok(out)
+}
+
+fn annotation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Annotation> {
+    Ok(Annotation {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        note: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
 }
 
 pub struct InsertOutcome {
     pub conversation_id: i64,
     pub inserted_indices: Vec<i64>,
+    /// Existing messages whose content changed and were rewritten in place
+    pub replaced_indices: Vec<i64>,
+    /// True if a new conversation row was created (vs. upserting into an existing one)
+    pub created: bool,
+}
+
+/// A slice of a conversation's messages, see `SqliteStorage::fetch_messages_page`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MessagePage {
+    pub conversation_id: i64,
+    /// Messages in the whole conversation
+    pub total: i64,
+    /// Lowest `idx` requested
+    pub from_idx: i64,
+    /// `from_idx` of the following page; `None` on the last page
+    pub next_idx: Option<i64>,
+    pub messages: Vec<Message>,
+}
+
+/// A conversation considered by storage-budget pruning, with its estimated footprint.
+#[derive(Debug, Clone)]
+pub struct PruneCandidate {
+    pub conversation_id: i64,
+    pub source_id: String,
+    pub source_path: String,
+    pub agent: String,
+    pub title: Option<String>,
+    pub started_at: Option<i64>,
+    /// Bytes of message text and raw JSON stored for this conversation
+    pub content_bytes: u64,
+    pub access_count: i64,
+    /// Locked with `cass lock`; never pruned
+    pub retained: bool,
+}
+
+/// Sync times of one remote source, kept in `meta` so they travel with the
+/// database. The daemon schedules and backs off syncs from these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SourceSyncTimes {
+    /// Unix millis of the last sync attempt
+    pub last_sync: Option<i64>,
+    /// Unix millis of the last sync that wasn't a complete failure
+    pub last_success: Option<i64>,
+    /// Complete failures since `last_success`
+    pub consecutive_failures: u32,
+}
+
+/// Per-agent totals for `cass stats storage`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AgentStorage {
+    pub agent: String,
+    pub conversations: i64,
+    pub messages: i64,
+    /// Bytes of message text and raw JSON
+    pub content_bytes: u64,
+}
+
+/// Per-agent usage for the TUI dashboard, see `SqliteStorage::agent_usage`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AgentUsage {
+    pub agent: String,
+    pub conversations: i64,
+    pub messages: i64,
+    /// Sum of `approx_tokens`; conversations indexed before token counting add 0
+    pub tokens: i64,
+}
+
+/// A conversation's stored size, see `SqliteStorage::largest_conversations`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConversationSize {
+    pub conversation_id: i64,
+    pub agent: String,
+    pub title: Option<String>,
+    pub source_path: String,
+    pub messages: i64,
+    pub content_bytes: u64,
+}
+
+/// Daily database size sample for growth history.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StorageSample {
+    /// UTC day, `YYYY-MM-DD`
+    pub day: String,
+    pub sampled_at: i64,
+    pub conversations: i64,
+    pub messages: i64,
+    pub db_bytes: u64,
+}
+
+/// Activity on one local day: sessions started (`SqliteStorage::daily_activity`) or
+/// messages sent (`SqliteStorage::daily_messages`).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DayActivity {
+    /// Local day, `YYYY-MM-DD`
+    pub day: String,
+    pub conversations: i64,
+    pub messages: i64,
+}
+
+/// A conversation placed on the calendar, see `SqliteStorage::conversations_started_between`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CalendarEntry {
+    pub conversation_id: i64,
+    pub agent: String,
+    pub title: Option<String>,
+    pub workspace: Option<String>,
+    pub source_path: String,
+    pub started_at: i64,
+    pub messages: i64,
+}
+
+/// A conversation offered by `cass pick`, see `SqliteStorage::pick_candidates`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PickCandidate {
+    pub conversation_id: i64,
+    pub agent: String,
+    pub title: Option<String>,
+    pub workspace: Option<String>,
+    pub source_path: String,
+    pub started_at: Option<i64>,
+}
+
+/// A workspace listed by `cass workspaces`, see `SqliteStorage::workspace_activity`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct WorkspaceActivity {
+    pub path: String,
+    pub conversations: i64,
+    /// Agents with sessions in this workspace, alphabetical
+    pub agents: Vec<String>,
+    /// Latest session end (or start), epoch millis
+    pub last_activity: Option<i64>,
+}
+
+/// Start a read transaction on `conn`; all reads see one snapshot until it ends.
+pub fn begin_read_snapshot(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("BEGIN DEFERRED")?;
+    // A deferred transaction only takes its snapshot at the first read
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| {
+        r.get::<_, i64>(0)
+    })?;
+    Ok(())
 }
 
 impl SqliteStorage {
@@ -408,14 +859,20 @@ impl SqliteStorage {
                 .with_context(|| format!("creating db directory {}", parent.display()))?;
         }
 
+        // Turning encryption on encrypts a database created without it
+        encryption::encrypt_if_plaintext(path)?;
         let mut conn = Connection::open(path)
             .with_context(|| format!("opening sqlite db at {}", path.display()))?;
+        encryption::apply_key(&conn)?;
 
         apply_pragmas(&mut conn)?;
         init_meta(&mut conn)?;
         migrate(&mut conn)?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            compress: false,
+        })
     }
 
     pub fn open_readonly(path: &Path) -> Result<Self> {
@@ -424,10 +881,26 @@ impl SqliteStorage {
             rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
         )
         .with_context(|| format!("opening sqlite db readonly at {}", path.display()))?;
+        encryption::apply_key(&conn)?;
 
         apply_common_pragmas(&conn)?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            compress: false,
+        })
+    }
+
+    /// Open read-only with a pinned snapshot, for reports and exports that run many queries.
+    ///
+    /// Every query sees the database as of this call even if the indexer commits
+    /// meanwhile (WAL keeps the old pages readable). The snapshot is released when
+    /// the storage is dropped.
+    pub fn open_snapshot(path: &Path) -> Result<Self> {
+        let storage = Self::open_readonly(path)?;
+        begin_read_snapshot(&storage.conn)
+            .with_context(|| format!("starting read snapshot on {}", path.display()))?;
+        Ok(storage)
     }
 
     /// Open database with migration, backing up and signaling rebuild if schema is incompatible.
@@ -453,6 +926,8 @@ impl SqliteStorage {
 
         // Check if we need to handle an incompatible schema before opening
         if path.exists() {
+            // A missing or wrong key must not look like corruption, which would rebuild
+            encryption::verify_key(path).map_err(|e| MigrationError::Other(e.to_string()))?;
             let check_result = check_schema_compatibility(path);
             match check_result {
                 Ok(SchemaCheck::Compatible) => {
@@ -486,17 +961,26 @@ impl SqliteStorage {
 
         // Now open and migrate normally
         let mut conn = Connection::open(path)?;
+        encryption::apply_key(&conn).map_err(|e| MigrationError::Other(e.to_string()))?;
         apply_pragmas(&mut conn).map_err(|e| MigrationError::Other(e.to_string()))?;
         init_meta(&mut conn).map_err(|e| MigrationError::Other(e.to_string()))?;
         migrate(&mut conn).map_err(|e| MigrationError::Other(e.to_string()))?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            compress: false,
+        })
     }
 
     pub fn raw(&self) -> &Connection {
         &self.conn
     }
 
+    /// Compress large messages written from now on (see `storage::compression`).
+    pub fn set_compression(&mut self, enabled: bool) {
+        self.compress = enabled;
+    }
+
     pub fn schema_version(&self) -> Result<i64> {
         self.conn
             .query_row(
@@ -566,14 +1050,15 @@ impl SqliteStorage {
                 )
                 .optional()?
         {
-            return self.append_messages(existing, conv);
+            return self.upsert_messages(existing, workspace_id, conv);
         }
 
+        let compress = self.compress;
         let tx = self.conn.transaction()?;
 
         let conv_id = insert_conversation(&tx, agent_id, workspace_id, conv)?;
         for msg in &conv.messages {
-            let msg_id = insert_message(&tx, conv_id, msg)?;
+            let msg_id = insert_message(&tx, conv_id, msg, compress)?;
             insert_snippets(&tx, msg_id, &msg.snippets)?;
             insert_fts_message(&tx, msg_id, msg, conv)?;
         }
@@ -581,35 +1066,169 @@ impl SqliteStorage {
         Ok(InsertOutcome {
             conversation_id: conv_id,
             inserted_indices: conv.messages.iter().map(|m| m.idx).collect(),
+            replaced_indices: Vec::new(),
+            created: true,
         })
     }
 
-    fn append_messages(
+    /// Merge a re-scanned conversation into its stored row: new message indices are
+    /// appended, messages whose content hash changed are rewritten in place (with their
+    /// snippets and FTS rows), and title, ended_at and labels are refreshed. Stored
+    /// messages missing from `conv` are kept, since some sources only report new
+    /// messages, unless the stored row has since been split into workspace segments.
+    fn upsert_messages(
         &mut self,
         conversation_id: i64,
+        workspace_id: Option<i64>,
         conv: &Conversation,
     ) -> Result<InsertOutcome> {
+        let compress = self.compress;
         let tx = self.conn.transaction()?;
 
-        let max_idx: Option<i64> = tx.query_row(
-            "SELECT MAX(idx) FROM messages WHERE conversation_id = ?",
-            params![conversation_id],
-            |row| row.get::<_, Option<i64>>(0),
-        )?;
-        let cutoff = max_idx.unwrap_or(-1);
+        type Provenance = (Option<String>, Option<i64>);
+        let mut existing: HashMap<i64, (i64, [u8; 32], Provenance)> = HashMap::new();
+        {
+            let mut stmt = tx.prepare(
+                "SELECT id, idx, role, author, cass_text(content), source_path, source_line
+                 FROM messages WHERE conversation_id = ?",
+            )?;
+            let rows = stmt.query_map(params![conversation_id], |row| {
+                let role: String = row.get(2)?;
+                let author: Option<String> = row.get(3)?;
+                let content: String = row.get(4)?;
+                Ok((
+                    row.get::<_, i64>(1)?,
+                    (
+                        row.get::<_, i64>(0)?,
+                        message_hash(&role, author.as_deref(), &content),
+                        (row.get(5)?, row.get(6)?),
+                    ),
+                ))
+            })?;
+            for row in rows {
+                let (idx, entry) = row?;
+                existing.insert(idx, entry);
+            }
+        }
 
         let mut inserted_indices = Vec::new();
+        let mut replaced_indices = Vec::new();
         for msg in &conv.messages {
-            if msg.idx <= cutoff {
-                continue;
+            match existing.get(&msg.idx) {
+                None => {
+                    let msg_id = insert_message(&tx, conversation_id, msg, compress)?;
+                    insert_snippets(&tx, msg_id, &msg.snippets)?;
+                    insert_fts_message(&tx, msg_id, msg, conv)?;
+                    inserted_indices.push(msg.idx);
+                }
+                Some((msg_id, hash, _))
+                    if *hash
+                        != message_hash(
+                            &role_str(&msg.role),
+                            msg.author.as_deref(),
+                            &msg.content,
+                        ) =>
+                {
+                    replace_message(&tx, *msg_id, msg, compress)?;
+                    tx.execute("DELETE FROM snippets WHERE message_id = ?", params![msg_id])?;
+                    insert_snippets(&tx, *msg_id, &msg.snippets)?;
+                    // FTS mirror is best-effort, like insert_fts_message
+                    let _ = tx.execute(
+                        "DELETE FROM fts_messages WHERE message_id = ?",
+                        params![msg_id],
+                    );
+                    insert_fts_message(&tx, *msg_id, msg, conv)?;
+                    replaced_indices.push(msg.idx);
+                }
+                // Unchanged content, but provenance may be new (stored before connectors
+                // recorded it, see MIGRATION_V21) or the line may have moved
+                Some((msg_id, _, (path, line)))
+                    if msg.source_path != *path || msg.source_line != *line =>
+                {
+                    tx.execute(
+                        "UPDATE messages SET source_path = ?, source_line = ? WHERE id = ?",
+                        params![msg.source_path, msg.source_line, msg_id],
+                    )?;
+                }
+                Some(_) => {}
+            }
+        }
+
+        // The session was split by workspace (`connectors::split_by_workspace`), or split
+        // differently, since this row was stored: it now holds only the first segment's
+        // messages, so drop the ones that moved to later segments and take its workspace
+        // and segment metadata
+        let mut removed = 0;
+        if let Some(segment) = conv.metadata_json.get("segment") {
+            let stored: Option<String> = tx.query_row(
+                "SELECT metadata_json FROM conversations WHERE id = ?",
+                params![conversation_id],
+                |row| row.get(0),
+            )?;
+            let stored_segment = stored
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                .and_then(|m| m.get("segment").cloned());
+            if stored_segment.as_ref() != Some(segment) {
+                let kept = conv.messages.len() as i64;
+                tx.execute(
+                    "DELETE FROM fts_annotations WHERE rowid IN (
+                        SELECT a.id FROM annotations a JOIN messages m ON a.message_id = m.id
+                        WHERE m.conversation_id = ?1 AND m.idx >= ?2)",
+                    params![conversation_id, kept],
+                )?;
+                tx.execute(
+                    "DELETE FROM fts_messages WHERE message_id IN (
+                        SELECT id FROM messages WHERE conversation_id = ?1 AND idx >= ?2)",
+                    params![conversation_id, kept],
+                )?;
+                removed = tx.execute(
+                    "DELETE FROM messages WHERE conversation_id = ?1 AND idx >= ?2",
+                    params![conversation_id, kept],
+                )?;
+                tx.execute(
+                    "UPDATE conversations SET workspace_id = ?1, metadata_json = ?2, ended_at = ?3
+                     WHERE id = ?4",
+                    params![
+                        workspace_id,
+                        serde_json::to_string(&conv.metadata_json)?,
+                        conv.ended_at,
+                        conversation_id
+                    ],
+                )?;
+            }
+        }
+
+        // Classifier labels (`indexer::classify`) are recomputed on every scan; keep the
+        // stored copy current so rebuilding the search index from here uses the new ones
+        if let Some(labels) = conv.metadata_json.pointer("/cass/labels") {
+            let stored: Option<String> = tx.query_row(
+                "SELECT metadata_json FROM conversations WHERE id = ?",
+                params![conversation_id],
+                |row| row.get(0),
+            )?;
+            let mut metadata = stored
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                .filter(serde_json::Value::is_object)
+                .unwrap_or_else(|| serde_json::json!({}));
+            if metadata.pointer("/cass/labels") != Some(labels) {
+                if !metadata["cass"].is_object() {
+                    metadata["cass"] = serde_json::json!({});
+                }
+                metadata["cass"]["labels"] = labels.clone();
+                tx.execute(
+                    "UPDATE conversations SET metadata_json = ? WHERE id = ?",
+                    params![serde_json::to_string(&metadata)?, conversation_id],
+                )?;
             }
-            let msg_id = insert_message(&tx, conversation_id, msg)?;
-            insert_snippets(&tx, msg_id, &msg.snippets)?;
-            insert_fts_message(&tx, msg_id, msg, conv)?;
-            inserted_indices.push(msg.idx);
         }
 
-        if let Some(last_ts) = conv.messages.iter().filter_map(|m| m.created_at).max() {
+        let last_ts = conv
+            .messages
+            .iter()
+            .filter_map(|m| m.created_at)
+            .chain(conv.ended_at)
+            .max();
+        if let Some(last_ts) = last_ts {
             // Use IFNULL to handle NULL ended_at values correctly.
             // SQLite's scalar MAX(NULL, x) returns NULL, so we need to coalesce first.
             tx.execute(
@@ -617,11 +1236,29 @@ impl SqliteStorage {
                 params![last_ts, conversation_id],
             )?;
         }
+        if let Some(title) = &conv.title {
+            tx.execute(
+                "UPDATE conversations SET title = ?1 WHERE id = ?2 AND title IS NOT ?1",
+                params![title, conversation_id],
+            )?;
+        }
+
+        if !inserted_indices.is_empty() || !replaced_indices.is_empty() || removed > 0 {
+            tx.execute(
+                "UPDATE conversations SET approx_tokens =
+                    (SELECT SUM(approx_tokens) FROM messages WHERE conversation_id = ?1),
+                    indexed_at = ?2
+                 WHERE id = ?1",
+                params![conversation_id, Self::now_millis()],
+            )?;
+        }
 
         tx.commit()?;
         Ok(InsertOutcome {
             conversation_id,
             inserted_indices,
+            replaced_indices,
+            created: false,
         })
     }
 
@@ -630,17 +1267,1113 @@ impl SqliteStorage {
             .conn
             .prepare("SELECT id, slug, name, version, kind FROM agents ORDER BY slug")?;
         let rows = stmt.query_map([], |row| {
-            let kind: String = row.get(4)?;
-            Ok(Agent {
-                id: Some(row.get(0)?),
-                slug: row.get(1)?,
-                name: row.get(2)?,
-                version: row.get(3)?,
-                kind: match kind.as_str() {
-                    "cli" => AgentKind::Cli,
-                    "vscode" => AgentKind::VsCode,
-                    _ => AgentKind::Hybrid,
-                },
+            let kind: String = row.get(4)?;
+            Ok(Agent {
+                id: Some(row.get(0)?),
+                slug: row.get(1)?,
+                name: row.get(2)?,
+                version: row.get(3)?,
+                kind: match kind.as_str() {
+                    "cli" => AgentKind::Cli,
+                    "vscode" => AgentKind::VsCode,
+                    _ => AgentKind::Hybrid,
+                },
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    pub fn list_workspaces(&self) -> Result<Vec<crate::model::types::Workspace>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, path, display_name FROM workspaces ORDER BY path")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(crate::model::types::Workspace {
+                id: Some(row.get(0)?),
+                path: Path::new(&row.get::<_, String>(1)?).to_path_buf(),
+                display_name: row.get::<_, Option<String>>(2)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    pub fn list_conversations(&self, limit: i64, offset: i64) -> Result<Vec<Conversation>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{CONVERSATION_SELECT}
+                ORDER BY c.started_at IS NULL, c.started_at DESC, c.id DESC
+                LIMIT ? OFFSET ?"
+        ))?;
+
+        let rows = stmt.query_map(params![limit, offset], conversation_from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Ids of conversations recorded on this machine whose messages were stored at or
+    /// after `cursor` (an `indexed_at` value), oldest first; all of them without one.
+    pub fn local_conversations_indexed_since(&self, cursor: Option<i64>) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM conversations
+             WHERE source_id = ?1 AND (?2 IS NULL OR indexed_at >= ?2)
+             ORDER BY indexed_at, id",
+        )?;
+        let ids = stmt
+            .query_map(params![LOCAL_SOURCE_ID, cursor], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Largest `indexed_at` in the database, the cursor for the next `serve --sync`.
+    pub fn max_indexed_at(&self) -> Result<Option<i64>> {
+        Ok(self
+            .conn
+            .query_row("SELECT MAX(indexed_at) FROM conversations", [], |row| {
+                row.get(0)
+            })?)
+    }
+
+    /// Full conversation (with messages in order) by row id.
+    pub fn get_conversation(&self, id: i64) -> Result<Option<Conversation>> {
+        let conv = self.find_conversation(id)?;
+        self.with_messages(conv)
+    }
+
+    /// Full conversation by the agent's own session id, see `find_by_external_id`.
+    pub fn get_by_external_id(
+        &self,
+        external_id: &str,
+        agent_slug: Option<&str>,
+    ) -> Result<Option<Conversation>> {
+        let conv = self.find_by_external_id(external_id, agent_slug)?;
+        self.with_messages(conv)
+    }
+
+    /// Conversation header (no messages) by row id.
+    pub fn find_conversation(&self, id: i64) -> Result<Option<Conversation>> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!("{CONVERSATION_SELECT} WHERE c.id = ?"),
+                params![id],
+                conversation_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Conversation header (no messages) by the agent's own session id. The same id
+    /// can exist on several sources (or, rarely, agents); `agent_slug` narrows it,
+    /// local sessions are preferred, then the most recently started match.
+    pub fn find_by_external_id(
+        &self,
+        external_id: &str,
+        agent_slug: Option<&str>,
+    ) -> Result<Option<Conversation>> {
+        self.find_by_external_id_in(external_id, agent_slug, &SourceFilter::All)
+    }
+
+    /// Like [`Self::find_by_external_id`], restricted to sources matching `source`.
+    pub fn find_by_external_id_in(
+        &self,
+        external_id: &str,
+        agent_slug: Option<&str>,
+        source: &SourceFilter,
+    ) -> Result<Option<Conversation>> {
+        let (kind, source_id) = match source {
+            SourceFilter::All => (None, None),
+            SourceFilter::Local => (Some("local"), None),
+            SourceFilter::Remote => (Some("remote"), None),
+            SourceFilter::SourceId(id) => (None, Some(id.as_str())),
+            SourceFilter::Any(filters) => {
+                for filter in filters {
+                    if let Some(conv) =
+                        self.find_by_external_id_in(external_id, agent_slug, filter)?
+                    {
+                        return Ok(Some(conv));
+                    }
+                }
+                return Ok(None);
+            }
+        };
+        Ok(self
+            .conn
+            .query_row(
+                &format!(
+                    "{CONVERSATION_SELECT}
+                     WHERE c.external_id = ?1 AND (?2 IS NULL OR a.slug = ?2)
+                       AND (?3 IS NULL OR (?3 = 'local') = (c.origin_kind = 'local'))
+                       AND (?4 IS NULL OR c.source_id = ?4)
+                     ORDER BY c.source_id != 'local', c.started_at DESC, c.id DESC
+                     LIMIT 1"
+                ),
+                params![external_id, agent_slug, kind, source_id],
+                conversation_from_row,
+            )
+            .optional()?)
+    }
+
+    fn with_messages(&self, conv: Option<Conversation>) -> Result<Option<Conversation>> {
+        let Some(mut conv) = conv else {
+            return Ok(None);
+        };
+        if let Some(id) = conv.id {
+            conv.messages = self.fetch_messages(id)?;
+        }
+        Ok(Some(conv))
+    }
+
+    pub fn fetch_messages(&self, conversation_id: i64) -> Result<Vec<Message>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MESSAGE_SELECT} WHERE conversation_id = ? ORDER BY idx"
+        ))?;
+        let rows = stmt.query_map(params![conversation_id], message_from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Number of stored messages in a conversation.
+    pub fn message_count(&self, conversation_id: i64) -> Result<i64> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?",
+            params![conversation_id],
+            |r| r.get(0),
+        )?)
+    }
+
+    /// Up to `limit` messages with `idx >= from_idx`, in order, plus the total count
+    /// and where the next page starts. Lets callers walk huge threads page by page
+    /// instead of loading every message with `fetch_messages`.
+    pub fn fetch_messages_page(
+        &self,
+        conversation_id: i64,
+        from_idx: i64,
+        limit: usize,
+    ) -> Result<MessagePage> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{MESSAGE_SELECT} WHERE conversation_id = ? AND idx >= ? ORDER BY idx LIMIT ?"
+        ))?;
+        // One extra row tells whether another page follows
+        let rows = stmt.query_map(
+            params![conversation_id, from_idx, limit as i64 + 1],
+            message_from_row,
+        )?;
+        let mut messages = Vec::with_capacity(limit.min(1024) + 1);
+        for r in rows {
+            messages.push(r?);
+        }
+        let next_idx = if messages.len() > limit {
+            messages.pop().map(|m| m.idx)
+        } else {
+            None
+        };
+        Ok(MessagePage {
+            conversation_id,
+            total: self.message_count(conversation_id)?,
+            from_idx,
+            next_idx,
+            messages,
+        })
+    }
+
+    /// Returns true if a conversation with this content hash is already stored for the
+    /// given source and agent, i.e. re-persisting it would be a no-op.
+    pub fn has_content_hash(&self, source_id: &str, agent_id: i64, hash: &str) -> Result<bool> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM conversations WHERE source_id = ? AND agent_id = ? AND content_hash = ?)",
+            params![source_id, agent_id, hash],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    pub fn set_content_hash(&self, conversation_id: i64, hash: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE conversations SET content_hash = ? WHERE id = ?",
+            params![hash, conversation_id],
+        )?;
+        Ok(())
+    }
+
+    /// Lock (or unlock) the conversations of a source file so prune/retention policies skip them.
+    /// Returns true if the lock state changed.
+    pub fn set_retained(&self, source_id: &str, source_path: &str, retained: bool) -> Result<bool> {
+        let changed = if retained {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO retained_conversations(source_id, source_path, created_at) VALUES(?,?,?)",
+                params![source_id, source_path, Self::now_millis()],
+            )?
+        } else {
+            self.conn.execute(
+                "DELETE FROM retained_conversations WHERE source_id = ? AND source_path = ?",
+                params![source_id, source_path],
+            )?
+        };
+        Ok(changed > 0)
+    }
+
+    /// All locked (source_id, source_path, created_at) entries, newest first.
+    pub fn list_retained(&self) -> Result<Vec<(String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_id, source_path, created_at FROM retained_conversations ORDER BY created_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Rate the conversations of a source file (+1 or -1), replacing any earlier rating.
+    pub fn set_rating(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        rating: i8,
+        note: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO conversation_ratings(source_id, source_path, rating, note, updated_at)
+             VALUES(?,?,?,?,?)
+             ON CONFLICT(source_id, source_path) DO UPDATE SET
+                rating = excluded.rating, note = excluded.note, updated_at = excluded.updated_at",
+            params![
+                source_id,
+                source_path,
+                i64::from(rating.signum()),
+                note,
+                Self::now_millis()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a rating. Returns true if one existed.
+    pub fn clear_rating(&self, source_id: &str, source_path: &str) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM conversation_ratings WHERE source_id = ? AND source_path = ?",
+            params![source_id, source_path],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// All ratings, most recently updated first.
+    pub fn list_ratings(&self) -> Result<Vec<ConversationRating>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_id, source_path, rating, note, updated_at
+             FROM conversation_ratings ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ConversationRating {
+                source_id: row.get(0)?,
+                source_path: row.get(1)?,
+                rating: row.get(2)?,
+                note: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Stored copies of a session from sources other than `source_id`:
+    /// (conversation id, source_id, source_path, content_hash, locked).
+    #[allow(clippy::type_complexity)]
+    pub fn session_copies(
+        &self,
+        agent_id: i64,
+        external_id: &str,
+        source_id: &str,
+    ) -> Result<Vec<(i64, String, String, Option<String>, bool)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.source_id, c.source_path, c.content_hash,
+                    EXISTS(SELECT 1 FROM retained_conversations r
+                           WHERE r.source_id = c.source_id AND r.source_path = c.source_path)
+             FROM conversations c
+             WHERE c.agent_id = ? AND c.external_id = ? AND c.source_id != ?
+             ORDER BY c.id",
+        )?;
+        let rows = stmt.query_map(params![agent_id, external_id, source_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Whether this exact copy of a session was already dropped as a duplicate
+    /// of a copy that is still stored. Records whose kept copy is gone are removed.
+    pub fn is_merged_duplicate(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        content_hash: &str,
+    ) -> Result<bool> {
+        let kept: Option<bool> = self
+            .conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM conversations c JOIN agents a ON c.agent_id = a.id
+                               WHERE c.source_id = m.kept_source_id
+                                 AND c.source_path = m.kept_source_path
+                                 AND a.slug = m.agent AND c.external_id = m.external_id)
+                 FROM merged_duplicates m
+                 WHERE m.source_id = ? AND m.source_path = ? AND m.content_hash = ?",
+                params![source_id, source_path, content_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if kept == Some(false) {
+            self.conn.execute(
+                "DELETE FROM merged_duplicates
+                 WHERE source_id = ? AND source_path = ? AND content_hash = ?",
+                params![source_id, source_path, content_hash],
+            )?;
+        }
+        Ok(kept == Some(true))
+    }
+
+    /// Forget that a copy of a session was dropped, once it is the stored copy.
+    pub fn forget_merged_duplicate(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        agent: &str,
+        external_id: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM merged_duplicates
+             WHERE source_id = ? AND source_path = ? AND agent = ? AND external_id = ?",
+            params![source_id, source_path, agent, external_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record a dropped copy of a session, replacing an earlier record of it.
+    pub fn record_merged_duplicate(
+        &self,
+        duplicate: &MergedDuplicate,
+        content_hash: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO merged_duplicates(source_id, source_path, agent, external_id,
+                content_hash, kept_source_id, kept_source_path, messages, merged_at)
+             VALUES(?,?,?,?,?,?,?,?,?)",
+            params![
+                duplicate.source_id,
+                duplicate.source_path,
+                duplicate.agent,
+                duplicate.external_id,
+                content_hash,
+                duplicate.kept_source_id,
+                duplicate.kept_source_path,
+                duplicate.messages,
+                duplicate.merged_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Dropped session copies, most recent first.
+    pub fn list_merged_duplicates(&self) -> Result<Vec<MergedDuplicate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT source_id, source_path, agent, external_id, kept_source_id,
+                    kept_source_path, messages, merged_at
+             FROM merged_duplicates ORDER BY merged_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MergedDuplicate {
+                source_id: row.get(0)?,
+                source_path: row.get(1)?,
+                agent: row.get(2)?,
+                external_id: row.get(3)?,
+                kept_source_id: row.get(4)?,
+                kept_source_path: row.get(5)?,
+                messages: row.get(6)?,
+                merged_at: row.get(7)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Whether this exact copy of a conversation was deleted on purpose.
+    pub fn is_deleted_conversation(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        content_hash: &str,
+    ) -> Result<bool> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM deleted_conversations
+                           WHERE source_id = ? AND source_path = ? AND content_hash = ?)",
+            params![source_id, source_path, content_hash],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    /// Record conversations about to be deleted so rescans skip them until their
+    /// session file changes. Conversations stored without a content hash are skipped.
+    pub fn record_deleted_conversations(&self, ids: &[i64], reason: &str) -> Result<()> {
+        let now = Self::now_millis();
+        for id in ids {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO deleted_conversations(source_id, source_path, agent,
+                    external_id, content_hash, reason, deleted_at)
+                 SELECT c.source_id, c.source_path, a.slug, COALESCE(c.external_id, ''),
+                        c.content_hash, ?, ?
+                 FROM conversations c JOIN agents a ON c.agent_id = a.id
+                 WHERE c.id = ? AND c.content_hash IS NOT NULL",
+                params![reason, now, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Drop the deletion records of whole sessions so the next full index re-adds
+    /// them. Returns how many conversations were restored.
+    pub fn forget_deleted_sessions(&self, sessions: &[(String, String)]) -> Result<usize> {
+        let mut forgotten = 0;
+        for (source_id, source_path) in sessions {
+            forgotten += self.conn.execute(
+                "DELETE FROM deleted_conversations WHERE source_id = ? AND source_path = ?",
+                params![source_id, source_path],
+            )?;
+        }
+        Ok(forgotten)
+    }
+
+    /// `(source_id, agent slug, external_id, source_path)` of each conversation, which
+    /// is what its search index doc key is built from.
+    #[allow(clippy::type_complexity)]
+    pub fn conversation_identities(
+        &self,
+        ids: &[i64],
+    ) -> Result<Vec<(String, String, Option<String>, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.source_id, a.slug, c.external_id, c.source_path
+             FROM conversations c JOIN agents a ON c.agent_id = a.id
+             WHERE c.id = ?",
+        )?;
+        let mut out = Vec::new();
+        for id in ids {
+            if let Some(row) = stmt
+                .query_row([id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })
+                .optional()?
+            {
+                out.push(row);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Attach a tag to a conversation. Returns false if it was already tagged.
+    pub fn add_tag(&self, conversation_id: i64, name: &str) -> Result<bool> {
+        let tag = normalize_tag(name).ok_or_else(|| anyhow!("invalid tag name '{name}'"))?;
+        self.conn
+            .execute("INSERT OR IGNORE INTO tags(name) VALUES(?)", params![tag])?;
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO conversation_tags(conversation_id, tag_id)
+             SELECT ?, id FROM tags WHERE name = ?",
+            params![conversation_id, tag],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Detach a tag from a conversation, dropping the tag once nothing uses it.
+    /// Returns false if the conversation didn't have it.
+    pub fn remove_tag(&self, conversation_id: i64, name: &str) -> Result<bool> {
+        let Some(tag) = normalize_tag(name) else {
+            return Ok(false);
+        };
+        let removed = self.conn.execute(
+            "DELETE FROM conversation_tags
+             WHERE conversation_id = ? AND tag_id = (SELECT id FROM tags WHERE name = ?)",
+            params![conversation_id, tag],
+        )?;
+        self.conn.execute(
+            "DELETE FROM tags WHERE name = ?
+               AND NOT EXISTS(SELECT 1 FROM conversation_tags WHERE tag_id = tags.id)",
+            params![tag],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Star or unstar a conversation. Returns false if it was already in that state.
+    pub fn set_starred(&self, conversation_id: i64, starred: bool) -> Result<bool> {
+        if starred {
+            self.add_tag(conversation_id, STARRED_TAG)
+        } else {
+            self.remove_tag(conversation_id, STARRED_TAG)
+        }
+    }
+
+    /// Star or unstar every conversation indexed from a session file, as search
+    /// hits identify sessions rather than conversation rows. Returns how many changed.
+    pub fn set_session_starred(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        starred: bool,
+    ) -> Result<usize> {
+        let mut changed = 0;
+        for id in self.session_conversation_ids(source_id, source_path)? {
+            if self.set_starred(id, starred)? {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Ids of the conversations indexed from one session file.
+    pub fn session_conversation_ids(&self, source_id: &str, source_path: &str) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM conversations WHERE source_id = ? AND source_path = ?")?;
+        let ids = stmt
+            .query_map(params![source_id, source_path], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    /// The conversation a search hit belongs to. Files holding several
+    /// conversations of one agent (Cursor, Aider) are narrowed down by the
+    /// hit's message timestamp; `None` when the hit stays ambiguous.
+    pub fn hit_conversation_id(
+        &self,
+        source_id: &str,
+        agent_slug: &str,
+        source_path: &str,
+        created_at: Option<i64>,
+    ) -> Result<Option<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id FROM conversations c JOIN agents a ON a.id = c.agent_id
+             WHERE c.source_id = ?1 AND a.slug = ?2 AND c.source_path = ?3
+               AND (?4 IS NULL OR EXISTS (
+                   SELECT 1 FROM messages m WHERE m.conversation_id = c.id AND m.created_at = ?4))
+             LIMIT 2",
+        )?;
+        let ids: Vec<i64> = stmt
+            .query_map(
+                params![source_id, agent_slug, source_path, created_at],
+                |row| row.get(0),
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(match ids.as_slice() {
+            [id] => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Starred conversation headers, newest first.
+    pub fn starred_conversations(&self) -> Result<Vec<Conversation>> {
+        self.conversations_with_tag(STARRED_TAG)
+    }
+
+    /// Tags on one conversation, alphabetically.
+    pub fn conversation_tags(&self, conversation_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name FROM conversation_tags ct JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.conversation_id = ? ORDER BY t.name",
+        )?;
+        let rows = stmt.query_map(params![conversation_id], |row| row.get(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Every tag in use with its conversation count, alphabetically.
+    pub fn list_tags(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name, COUNT(ct.conversation_id) FROM tags t
+             JOIN conversation_tags ct ON ct.tag_id = t.id
+             GROUP BY t.id ORDER BY t.name",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Conversation headers (no messages) carrying a tag, newest first.
+    pub fn conversations_with_tag(&self, name: &str) -> Result<Vec<Conversation>> {
+        let Some(tag) = normalize_tag(name) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "{CONVERSATION_SELECT}
+             JOIN conversation_tags ct ON ct.conversation_id = c.id
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE t.name = ?
+             ORDER BY c.started_at IS NULL, c.started_at DESC, c.id DESC"
+        ))?;
+        let rows = stmt.query_map(params![tag], conversation_from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Conversation headers (no messages) matching `filter`, newest first.
+    pub fn filter_conversations(&self, filter: &ConversationFilter) -> Result<Vec<Conversation>> {
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        if let Some(ws) = &filter.workspace {
+            values.push(ws.trim_end_matches('/').to_string());
+            let n = values.len();
+            clauses.push(format!(
+                "(w.path = ?{n} OR substr(w.path, 1, length(?{n}) + 1) = ?{n} || '/')"
+            ));
+        }
+        if let Some(agent) = &filter.agent {
+            values.push(agent.clone());
+            clauses.push(format!("a.slug = ?{}", values.len()));
+        }
+        if let Some(source_id) = &filter.source_id {
+            values.push(source_id.clone());
+            clauses.push(format!("c.source_id = ?{}", values.len()));
+        }
+        if let Some(tag) = &filter.tag {
+            values.push(normalize_tag(tag).unwrap_or_default());
+            clauses.push(format!(
+                "c.id IN (SELECT ct.conversation_id FROM conversation_tags ct
+                          JOIN tags t ON t.id = ct.tag_id WHERE t.name = ?{})",
+                values.len()
+            ));
+        }
+        let where_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "{CONVERSATION_SELECT} {where_sql}
+             ORDER BY c.started_at IS NULL, c.started_at DESC, c.id DESC"
+        ))?;
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(values.iter()),
+            conversation_from_row,
+        )?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Move a conversation to another workspace (or none), keeping the FTS mirror in sync.
+    pub fn set_conversation_workspace(
+        &mut self,
+        conversation_id: i64,
+        workspace: Option<&Path>,
+    ) -> Result<()> {
+        let workspace_id = workspace
+            .map(|ws| self.ensure_workspace(ws, None))
+            .transpose()?;
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE conversations SET workspace_id = ? WHERE id = ?",
+            params![workspace_id, conversation_id],
+        )?;
+        // FTS mirror is best-effort, like insert_fts_message
+        let _ = tx.execute(
+            "UPDATE fts_messages SET workspace = ?
+             WHERE message_id IN (SELECT id FROM messages WHERE conversation_id = ?)",
+            params![
+                workspace.map(|ws| ws.to_string_lossy().into_owned()),
+                conversation_id
+            ],
+        );
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Attach a note to a message. Returns the new annotation id.
+    pub fn add_annotation(&mut self, message_id: i64, note: &str) -> Result<i64> {
+        let note = note.trim();
+        if note.is_empty() {
+            return Err(anyhow!("note is empty"));
+        }
+        let now = Self::now_millis();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO annotations(message_id, note, created_at, updated_at) VALUES(?,?,?,?)",
+            params![message_id, note, now, now],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO fts_annotations(rowid, note) VALUES(?,?)",
+            params![id, note],
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Add a note from another database with its own timestamps, unless the message
+    /// already has one with the same text. Returns whether it was added.
+    pub fn import_annotation(
+        &mut self,
+        message_id: i64,
+        note: &str,
+        created_at: i64,
+        updated_at: i64,
+    ) -> Result<bool> {
+        let note = note.trim();
+        if note.is_empty() {
+            return Ok(false);
+        }
+        let tx = self.conn.transaction()?;
+        let added = tx.execute(
+            "INSERT INTO annotations(message_id, note, created_at, updated_at)
+             SELECT ?1, ?2, ?3, ?4
+             WHERE NOT EXISTS (SELECT 1 FROM annotations WHERE message_id = ?1 AND note = ?2)",
+            params![message_id, note, created_at, updated_at],
+        )? > 0;
+        if added {
+            tx.execute(
+                "INSERT INTO fts_annotations(rowid, note) VALUES(?,?)",
+                params![tx.last_insert_rowid(), note],
+            )?;
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// Replace the text of a note. Returns false if no such annotation exists.
+    pub fn update_annotation(&mut self, id: i64, note: &str) -> Result<bool> {
+        let note = note.trim();
+        if note.is_empty() {
+            return Err(anyhow!("note is empty"));
+        }
+        let tx = self.conn.transaction()?;
+        let changed = tx.execute(
+            "UPDATE annotations SET note = ?, updated_at = ? WHERE id = ?",
+            params![note, Self::now_millis(), id],
+        )?;
+        if changed > 0 {
+            tx.execute("DELETE FROM fts_annotations WHERE rowid = ?", params![id])?;
+            tx.execute(
+                "INSERT INTO fts_annotations(rowid, note) VALUES(?,?)",
+                params![id, note],
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed > 0)
+    }
+
+    /// Delete a note. Returns false if no such annotation exists.
+    pub fn delete_annotation(&mut self, id: i64) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let removed = tx.execute("DELETE FROM annotations WHERE id = ?", params![id])?;
+        tx.execute("DELETE FROM fts_annotations WHERE rowid = ?", params![id])?;
+        tx.commit()?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_annotation(&self, id: i64) -> Result<Option<Annotation>> {
+        self.conn
+            .query_row(
+                "SELECT id, message_id, note, created_at, updated_at FROM annotations WHERE id = ?",
+                params![id],
+                annotation_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Notes on one message, oldest first.
+    pub fn annotations_for_message(&self, message_id: i64) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, message_id, note, created_at, updated_at FROM annotations
+             WHERE message_id = ? ORDER BY created_at, id",
+        )?;
+        let rows = stmt.query_map(params![message_id], annotation_from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Notes on every message of a conversation, keyed by message id, oldest first.
+    pub fn annotations_for_conversation(
+        &self,
+        conversation_id: i64,
+    ) -> Result<HashMap<i64, Vec<Annotation>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.id, a.message_id, a.note, a.created_at, a.updated_at
+             FROM annotations a JOIN messages m ON m.id = a.message_id
+             WHERE m.conversation_id = ? ORDER BY a.created_at, a.id",
+        )?;
+        let rows = stmt.query_map(params![conversation_id], annotation_from_row)?;
+        let mut out: HashMap<i64, Vec<Annotation>> = HashMap::new();
+        for r in rows {
+            let annotation = r?;
+            out.entry(annotation.message_id)
+                .or_default()
+                .push(annotation);
+        }
+        Ok(out)
+    }
+
+    /// A single message (without snippets) by id.
+    pub fn get_message(&self, message_id: i64) -> Result<Option<Message>> {
+        self.conn
+            .query_row(
+                &format!("{MESSAGE_SELECT} WHERE id = ?"),
+                params![message_id],
+                message_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Id of the message at `idx` in a conversation.
+    pub fn message_id_at(&self, conversation_id: i64, idx: i64) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT id FROM messages WHERE conversation_id = ? AND idx = ?",
+                params![conversation_id, idx],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Stored provenance (`source_path`, `source_line`) of the message at `idx` in the
+    /// conversation read from `source_path`, if that message exists.
+    pub fn message_provenance(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        idx: i64,
+    ) -> Result<Option<(Option<String>, Option<i64>)>> {
+        self.conn
+            .query_row(
+                "SELECT m.source_path, m.source_line FROM messages m
+                 JOIN conversations c ON m.conversation_id = c.id
+                 WHERE c.source_id = ? AND c.source_path = ? AND m.idx = ?
+                 LIMIT 1",
+                params![source_id, source_path, idx],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Project mapping for a workspace path, if one was detected or set.
+    pub fn workspace_project(&self, workspace: &str) -> Result<Option<WorkspaceProject>> {
+        self.conn
+            .query_row(
+                "SELECT workspace, project, git_remote, manual FROM workspace_projects WHERE workspace = ?",
+                params![workspace],
+                workspace_project_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Record an auto-detected project for a workspace. Manual mappings are left alone.
+    pub fn upsert_detected_project(
+        &self,
+        workspace: &str,
+        project: &str,
+        git_remote: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO workspace_projects(workspace, project, git_remote, manual, updated_at) VALUES(?,?,?,0,?)
+             ON CONFLICT(workspace) DO UPDATE SET project = excluded.project, git_remote = excluded.git_remote,
+                 updated_at = excluded.updated_at
+             WHERE workspace_projects.manual = 0",
+            params![workspace, project, git_remote, Self::now_millis()],
+        )?;
+        Ok(())
+    }
+
+    /// Manually assign a workspace to a project (`None` removes the mapping so it is re-detected).
+    /// Returns true if anything changed.
+    pub fn set_workspace_project(&self, workspace: &str, project: Option<&str>) -> Result<bool> {
+        let changed = match project {
+            Some(project) => self.conn.execute(
+                "INSERT INTO workspace_projects(workspace, project, manual, updated_at) VALUES(?,?,1,?)
+                 ON CONFLICT(workspace) DO UPDATE SET project = excluded.project, manual = 1,
+                     updated_at = excluded.updated_at",
+                params![workspace, project, Self::now_millis()],
+            )?,
+            None => self.conn.execute(
+                "DELETE FROM workspace_projects WHERE workspace = ?",
+                params![workspace],
+            )?,
+        };
+        Ok(changed > 0)
+    }
+
+    /// All workspace -> project mappings, grouped by project.
+    pub fn list_workspace_projects(&self) -> Result<Vec<WorkspaceProject>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT workspace, project, git_remote, manual FROM workspace_projects ORDER BY project, workspace",
+        )?;
+        let rows = stmt.query_map([], workspace_project_from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Workspace paths that belong to `project`.
+    pub fn project_workspaces(&self, project: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT workspace FROM workspace_projects WHERE project = ? ORDER BY workspace",
+        )?;
+        let rows = stmt.query_map(params![project], |row| row.get(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// List `(conversation_id, source_id, source_path)` for every stored conversation.
+    pub fn list_conversation_source_paths(&self) -> Result<Vec<(i64, String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, source_id, source_path FROM conversations ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// `(conversation id, workspace path)` for conversations that have a workspace.
+    pub fn list_conversation_workspaces(&self) -> Result<Vec<(i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, w.path FROM conversations c JOIN workspaces w ON c.workspace_id = w.id ORDER BY c.id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Record that a session was opened (view, export, TUI detail) for `least-accessed`
+    /// pruning. Returns false if no indexed conversation has this source path.
+    pub fn record_access(&self, source_path: &str) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT INTO conversation_access(source_id, source_path, access_count, last_accessed_at)
+             SELECT DISTINCT source_id, source_path, 1, ?2 FROM conversations WHERE source_path = ?1
+             ON CONFLICT(source_id, source_path)
+             DO UPDATE SET access_count = access_count + 1, last_accessed_at = excluded.last_accessed_at",
+            params![source_path, Self::now_millis()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Log a result opened from the result list; feeds click-through priors for ranking.
+    /// Clicks past [`CLICK_RETENTION_DAYS`] or beyond the newest [`MAX_RESULT_CLICKS`]
+    /// are dropped, so the log stays small and reflects recent habits.
+    pub fn record_click(
+        &self,
+        source_id: &str,
+        source_path: &str,
+        agent: &str,
+        workspace: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO result_clicks(source_id, source_path, agent, workspace, query, clicked_at)
+             VALUES(?,?,?,?,?,?)",
+            params![
+                source_id,
+                source_path,
+                agent,
+                workspace.filter(|w| !w.is_empty()),
+                query.filter(|q| !q.is_empty()),
+                Self::now_millis()
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM result_clicks WHERE clicked_at < ?1
+             OR id <= (SELECT id FROM result_clicks ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+            params![
+                Self::now_millis() - CLICK_RETENTION_DAYS * 86_400_000,
+                MAX_RESULT_CLICKS
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every conversation in pruning order: oldest first, or (with `least_accessed`)
+    /// fewest accesses first, ties broken by least recent access/start.
+    pub fn prune_candidates(&self, least_accessed: bool) -> Result<Vec<PruneCandidate>> {
+        let order = if least_accessed {
+            "COALESCE(acc.access_count, 0), COALESCE(acc.last_accessed_at, c.started_at, 0), c.id"
+        } else {
+            "COALESCE(c.started_at, 0), c.id"
+        };
+        let sql = format!(
+            "SELECT c.id, c.source_id, c.source_path, a.slug, c.title, c.started_at,
+                    (SELECT COALESCE(SUM(LENGTH(m.content) + LENGTH(COALESCE(m.extra_json, ''))), 0)
+                     FROM messages m WHERE m.conversation_id = c.id),
+                    COALESCE(acc.access_count, 0),
+                    EXISTS(SELECT 1 FROM retained_conversations r
+                           WHERE r.source_id = c.source_id AND r.source_path = c.source_path)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN conversation_access acc
+                ON acc.source_id = c.source_id AND acc.source_path = c.source_path
+             ORDER BY {order}"
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok(PruneCandidate {
+                conversation_id: row.get(0)?,
+                source_id: row.get(1)?,
+                source_path: row.get(2)?,
+                agent: row.get(3)?,
+                title: row.get(4)?,
+                started_at: row.get(5)?,
+                content_bytes: row.get::<_, i64>(6)?.max(0) as u64,
+                access_count: row.get(7)?,
+                retained: row.get(8)?,
             })
         })?;
         let mut out = Vec::new();
@@ -650,105 +2383,428 @@ impl SqliteStorage {
         Ok(out)
     }
 
-    pub fn list_workspaces(&self) -> Result<Vec<crate::model::types::Workspace>> {
-        let mut stmt = self
+    /// Bytes of the database file actually in use (excludes free pages left by deletes).
+    pub fn used_bytes(&self) -> Result<u64> {
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+        let page_count: i64 = self.conn.query_row("PRAGMA page_count", [], |r| r.get(0))?;
+        let free: i64 = self
             .conn
-            .prepare("SELECT id, path, display_name FROM workspaces ORDER BY path")?;
+            .query_row("PRAGMA freelist_count", [], |r| r.get(0))?;
+        Ok(((page_count - free).max(0) * page_size) as u64)
+    }
+
+    /// Size of the database file in pages (including free pages), excluding the WAL.
+    pub fn db_file_bytes(&self) -> Result<u64> {
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+        let page_count: i64 = self.conn.query_row("PRAGMA page_count", [], |r| r.get(0))?;
+        Ok((page_count * page_size).max(0) as u64)
+    }
+
+    /// Conversations, messages and stored content per agent, largest first.
+    pub fn agent_storage(&self) -> Result<Vec<AgentStorage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.slug, COUNT(DISTINCT c.id), COUNT(m.id),
+                    COALESCE(SUM(LENGTH(m.content) + LENGTH(COALESCE(m.extra_json, ''))), 0) AS bytes
+             FROM agents a
+             JOIN conversations c ON c.agent_id = a.id
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             GROUP BY a.slug
+             ORDER BY bytes DESC, a.slug",
+        )?;
         let rows = stmt.query_map([], |row| {
-            Ok(crate::model::types::Workspace {
-                id: Some(row.get(0)?),
-                path: Path::new(&row.get::<_, String>(1)?).to_path_buf(),
-                display_name: row.get::<_, Option<String>>(2)?,
+            Ok(AgentStorage {
+                agent: row.get(0)?,
+                conversations: row.get(1)?,
+                messages: row.get(2)?,
+                content_bytes: row.get::<_, i64>(3)?.max(0) as u64,
             })
         })?;
-        let mut out = Vec::new();
-        for r in rows {
-            out.push(r?);
-        }
-        Ok(out)
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
     }
 
-    pub fn list_conversations(&self, limit: i64, offset: i64) -> Result<Vec<Conversation>> {
+    /// Conversations and messages per local day for sessions started in
+    /// `[from_ms, to_ms)`, oldest day first. Days without sessions are omitted.
+    pub fn daily_activity(&self, from_ms: i64, to_ms: i64) -> Result<Vec<DayActivity>> {
         let mut stmt = self.conn.prepare(
-            r"SELECT c.id, a.slug, w.path, c.external_id, c.title, c.source_path,
-                       c.started_at, c.ended_at, c.approx_tokens, c.metadata_json,
-                       c.source_id, c.origin_host
-                FROM conversations c
-                JOIN agents a ON c.agent_id = a.id
-                LEFT JOIN workspaces w ON c.workspace_id = w.id
-                ORDER BY c.started_at IS NULL, c.started_at DESC, c.id DESC
-                LIMIT ? OFFSET ?",
+            "SELECT date(c.started_at / 1000, 'unixepoch', 'localtime') AS day,
+                    COUNT(*),
+                    SUM((SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id))
+             FROM conversations c
+             WHERE c.started_at >= ?1 AND c.started_at < ?2
+             GROUP BY day
+             ORDER BY day",
         )?;
+        let rows = stmt.query_map(params![from_ms, to_ms], |row| {
+            Ok(DayActivity {
+                day: row.get(0)?,
+                conversations: row.get(1)?,
+                messages: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
 
-        let rows = stmt.query_map(params![limit, offset], |row| {
-            Ok(Conversation {
-                id: Some(row.get(0)?),
-                agent_slug: row.get(1)?,
-                workspace: row
-                    .get::<_, Option<String>>(2)?
-                    .map(|p| Path::new(&p).to_path_buf()),
-                external_id: row.get(3)?,
-                title: row.get(4)?,
-                source_path: Path::new(&row.get::<_, String>(5)?).to_path_buf(),
-                started_at: row.get(6)?,
-                ended_at: row.get(7)?,
-                approx_tokens: row.get(8)?,
-                metadata_json: row
-                    .get::<_, Option<String>>(9)?
-                    .and_then(|s| serde_json::from_str(&s).ok())
-                    .unwrap_or_default(),
-                messages: Vec::new(),
-                source_id: row
-                    .get::<_, String>(10)
-                    .unwrap_or_else(|_| "local".to_string()),
-                origin_host: row.get(11)?,
+    /// Messages sent per local day between `from_ms` and `to_ms`, with the number of
+    /// conversations they belong to. Long sessions count on every day they were active;
+    /// messages without a timestamp fall on their conversation's start.
+    pub fn daily_messages(&self, from_ms: i64, to_ms: i64) -> Result<Vec<DayActivity>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date(COALESCE(m.created_at, c.started_at) / 1000, 'unixepoch', 'localtime') AS day,
+                    COUNT(DISTINCT c.id),
+                    COUNT(*)
+             FROM messages m
+             JOIN conversations c ON m.conversation_id = c.id
+             WHERE COALESCE(m.created_at, c.started_at) >= ?1
+               AND COALESCE(m.created_at, c.started_at) < ?2
+             GROUP BY day
+             ORDER BY day",
+        )?;
+        let rows = stmt.query_map(params![from_ms, to_ms], |row| {
+            Ok(DayActivity {
+                day: row.get(0)?,
+                conversations: row.get(1)?,
+                messages: row.get(2)?,
             })
         })?;
-        let mut out = Vec::new();
-        for r in rows {
-            out.push(r?);
-        }
-        Ok(out)
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
     }
 
-    pub fn fetch_messages(&self, conversation_id: i64) -> Result<Vec<Message>> {
+    /// Conversations started in `[from_ms, to_ms)`, in start order.
+    pub fn conversations_started_between(
+        &self,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<CalendarEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, idx, role, author, created_at, content, extra_json FROM messages WHERE conversation_id = ? ORDER BY idx",
+            "SELECT c.id, a.slug, c.title, w.path, c.source_path, c.started_at,
+                    (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN workspaces w ON c.workspace_id = w.id
+             WHERE c.started_at >= ?1 AND c.started_at < ?2
+             ORDER BY c.started_at, c.id",
         )?;
-        let rows = stmt.query_map(params![conversation_id], |row| {
-            let role: String = row.get(2)?;
-            Ok(Message {
-                id: Some(row.get(0)?),
-                idx: row.get(1)?,
-                role: match role.as_str() {
-                    "user" => MessageRole::User,
-                    "agent" | "assistant" => MessageRole::Agent,
-                    "tool" => MessageRole::Tool,
-                    "system" => MessageRole::System,
-                    other => MessageRole::Other(other.to_string()),
-                },
-                author: row.get::<_, Option<String>>(3)?,
-                created_at: row.get::<_, Option<i64>>(4)?,
-                content: row.get(5)?,
-                extra_json: row
-                    .get::<_, Option<String>>(6)?
-                    .and_then(|s| serde_json::from_str(&s).ok())
-                    .unwrap_or_default(),
-                snippets: Vec::new(),
+        let rows = stmt.query_map(params![from_ms, to_ms], |row| {
+            Ok(CalendarEntry {
+                conversation_id: row.get(0)?,
+                agent: row.get(1)?,
+                title: row.get(2)?,
+                workspace: row.get(3)?,
+                source_path: row.get(4)?,
+                started_at: row.get(5)?,
+                messages: row.get(6)?,
             })
         })?;
-        let mut out = Vec::new();
-        for r in rows {
-            out.push(r?);
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Up to `limit` conversations, most recently started first, from any of
+    /// `agents` (all agents when empty).
+    pub fn pick_candidates(&self, agents: &[String], limit: usize) -> Result<Vec<PickCandidate>> {
+        let agent_clause = if agents.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE a.slug IN ({})", vec!["?"; agents.len()].join(","))
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT c.id, a.slug, c.title, w.path, c.source_path, c.started_at
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN workspaces w ON c.workspace_id = w.id
+             {agent_clause}
+             ORDER BY COALESCE(c.started_at, 0) DESC, c.id DESC
+             LIMIT {limit}"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(agents), |row| {
+            Ok(PickCandidate {
+                conversation_id: row.get(0)?,
+                agent: row.get(1)?,
+                title: row.get(2)?,
+                workspace: row.get(3)?,
+                source_path: row.get(4)?,
+                started_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Conversations, messages and tokens per agent, busiest agent first.
+    pub fn agent_usage(&self) -> Result<Vec<AgentUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.slug, COUNT(*),
+                    SUM((SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)),
+                    COALESCE(SUM(c.approx_tokens), 0)
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             GROUP BY a.slug
+             ORDER BY 2 DESC, a.slug",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(AgentUsage {
+                agent: row.get(0)?,
+                conversations: row.get(1)?,
+                messages: row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                tokens: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The `limit` workspaces with the most conversations, with their counts.
+    pub fn top_workspaces(&self, limit: usize) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT w.path, COUNT(*) FROM conversations c
+             JOIN workspaces w ON c.workspace_id = w.id
+             GROUP BY w.path
+             ORDER BY 2 DESC, w.path
+             LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Workspaces with their session counts, most recently active first; all
+    /// of them when `limit` is 0.
+    pub fn workspace_activity(&self, limit: usize) -> Result<Vec<WorkspaceActivity>> {
+        let limit_clause = if limit == 0 {
+            String::new()
+        } else {
+            format!("LIMIT {limit}")
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT w.path, COUNT(*), GROUP_CONCAT(DISTINCT a.slug),
+                    MAX(COALESCE(c.ended_at, c.started_at)) AS last
+             FROM conversations c
+             JOIN workspaces w ON c.workspace_id = w.id
+             JOIN agents a ON c.agent_id = a.id
+             GROUP BY w.path
+             ORDER BY COALESCE(last, 0) DESC, w.path
+             {limit_clause}"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            let mut agents: Vec<String> = row
+                .get::<_, Option<String>>(2)?
+                .unwrap_or_default()
+                .split(',')
+                .filter(|a| !a.is_empty())
+                .map(str::to_string)
+                .collect();
+            agents.sort();
+            Ok(WorkspaceActivity {
+                path: row.get(0)?,
+                conversations: row.get(1)?,
+                agents,
+                last_activity: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The `limit` conversations with the most stored content.
+    pub fn largest_conversations(&self, limit: usize) -> Result<Vec<ConversationSize>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, a.slug, c.title, c.source_path, COUNT(m.id),
+                    COALESCE(SUM(LENGTH(m.content) + LENGTH(COALESCE(m.extra_json, ''))), 0) AS bytes
+             FROM conversations c
+             JOIN agents a ON c.agent_id = a.id
+             LEFT JOIN messages m ON m.conversation_id = c.id
+             GROUP BY c.id
+             ORDER BY bytes DESC, c.id
+             LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(ConversationSize {
+                conversation_id: row.get(0)?,
+                agent: row.get(1)?,
+                title: row.get(2)?,
+                source_path: row.get(3)?,
+                messages: row.get(4)?,
+                content_bytes: row.get::<_, i64>(5)?.max(0) as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Today's size sample, measured now without storing it.
+    pub fn current_storage_sample(&self) -> Result<StorageSample> {
+        let sampled_at = Self::now_millis();
+        let conversations: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))?;
+        let messages: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))?;
+        let day: String = self.conn.query_row(
+            "SELECT date(?1 / 1000, 'unixepoch')",
+            params![sampled_at],
+            |r| r.get(0),
+        )?;
+        Ok(StorageSample {
+            day,
+            sampled_at,
+            conversations,
+            messages,
+            db_bytes: self.db_file_bytes()?,
+        })
+    }
+
+    /// Record today's size sample, replacing an earlier one from the same UTC day.
+    pub fn record_storage_sample(&self) -> Result<StorageSample> {
+        let sample = self.current_storage_sample()?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO storage_samples(day, sampled_at, conversations, messages, db_bytes)
+             VALUES(?1, ?2, ?3, ?4, ?5)",
+            params![
+                sample.day,
+                sample.sampled_at,
+                sample.conversations,
+                sample.messages,
+                sample.db_bytes as i64
+            ],
+        )?;
+        Ok(sample)
+    }
+
+    /// The most recent `limit` daily samples, oldest first.
+    pub fn storage_samples(&self, limit: usize) -> Result<Vec<StorageSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT day, sampled_at, conversations, messages, db_bytes FROM (
+                SELECT * FROM storage_samples ORDER BY day DESC LIMIT ?
+             ) ORDER BY day",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(StorageSample {
+                day: row.get(0)?,
+                sampled_at: row.get(1)?,
+                conversations: row.get(2)?,
+                messages: row.get(3)?,
+                db_bytes: row.get::<_, i64>(4)?.max(0) as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Rewrite stored messages to match the compression setting: large plain rows are
+    /// compressed when it is on, compressed rows are expanded when it is off.
+    /// Returns the number of messages rewritten.
+    pub fn recompress_messages(&mut self) -> Result<usize> {
+        let compress = self.compress;
+        let tx = self.conn.transaction()?;
+        let ids: Vec<i64> = tx
+            .prepare("SELECT id FROM messages WHERE compressed = ?")?
+            .query_map(params![!compress], |r| r.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        let mut rewritten = 0;
+        for id in ids {
+            let (content, extra_json) = tx.query_row(
+                "SELECT content, extra_json, compressed FROM messages WHERE id = ?",
+                params![id],
+                |row| {
+                    let compressed = row.get::<_, i64>(2)? != 0;
+                    Ok((
+                        compression::column_text(row, 0, compressed)?.unwrap_or_default(),
+                        compression::column_text(row, 1, compressed)?.unwrap_or_default(),
+                    ))
+                },
+            )?;
+            let (content, extra_json, compressed) =
+                compression::encode_message(&content, &extra_json, compress)?;
+            if compressed != compress {
+                continue; // below the size threshold, stays plain
+            }
+            tx.execute(
+                "UPDATE messages SET content = ?, extra_json = ?, compressed = ? WHERE id = ?",
+                params![content, extra_json, compressed, id],
+            )?;
+            rewritten += 1;
         }
-        Ok(out)
+        tx.commit()?;
+        Ok(rewritten)
+    }
+
+    /// Compact the database (`cass db optimize`): merge the FTS b-trees, refresh
+    /// planner statistics, rebuild the file without free pages and truncate the WAL.
+    /// VACUUM needs no other open transaction and temporarily up to twice the file size.
+    pub fn optimize(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "INSERT INTO fts_messages(fts_messages) VALUES('optimize');
+             INSERT INTO fts_annotations(fts_annotations) VALUES('optimize');
+             ANALYZE;",
+        )?;
+        // Fold the WAL back first so VACUUM rewrites everything in one pass
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        self.conn.execute_batch("VACUUM;")?;
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Delete conversations by id. Messages, snippets, tag links and annotations
+    /// cascade; the FTS mirrors are cleaned explicitly. Returns the number of conversations removed.
+    pub fn delete_conversations(&mut self, ids: &[i64]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut removed = 0;
+        for id in ids {
+            tx.execute(
+                "DELETE FROM fts_annotations WHERE rowid IN (
+                    SELECT a.id FROM annotations a
+                    JOIN messages m ON a.message_id = m.id
+                    WHERE m.conversation_id = ?)",
+                params![id],
+            )?;
+            tx.execute(
+                "DELETE FROM fts_messages WHERE message_id IN (SELECT id FROM messages WHERE conversation_id = ?)",
+                params![id],
+            )?;
+            removed += tx.execute("DELETE FROM conversations WHERE id = ?", params![id])?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Carry tags, the rating and message notes from `from_ids` over to `keep_id`
+    /// before the former are deleted as duplicates. Notes move to the kept message
+    /// at the same position; the kept conversation's own rating wins. Returns the
+    /// number of tags, ratings and notes carried over.
+    pub fn merge_conversation_metadata(&mut self, keep_id: i64, from_ids: &[i64]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut merged = 0;
+        for id in from_ids {
+            merged += tx.execute(
+                "INSERT OR IGNORE INTO conversation_tags(conversation_id, tag_id)
+                 SELECT ?1, tag_id FROM conversation_tags WHERE conversation_id = ?2",
+                params![keep_id, id],
+            )?;
+            merged += tx.execute(
+                "INSERT OR IGNORE INTO conversation_ratings(source_id, source_path, rating, note, updated_at)
+                 SELECT k.source_id, k.source_path, r.rating, r.note, r.updated_at
+                 FROM conversations k, conversations f
+                 JOIN conversation_ratings r
+                    ON r.source_id = f.source_id AND r.source_path = f.source_path
+                 WHERE k.id = ?1 AND f.id = ?2",
+                params![keep_id, id],
+            )?;
+            merged += tx.execute(
+                "UPDATE annotations SET message_id = (
+                    SELECT km.id FROM messages km JOIN messages fm ON km.idx = fm.idx
+                    WHERE fm.id = annotations.message_id AND km.conversation_id = ?1)
+                 WHERE message_id IN (SELECT id FROM messages WHERE conversation_id = ?2)
+                   AND EXISTS (
+                    SELECT 1 FROM messages km JOIN messages fm ON km.idx = fm.idx
+                    WHERE fm.id = annotations.message_id AND km.conversation_id = ?1)",
+                params![keep_id, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(merged)
     }
 
     pub fn rebuild_fts(&mut self) -> Result<()> {
         self.conn.execute("DELETE FROM fts_messages", [])?;
         self.conn.execute_batch(
             r"INSERT INTO fts_messages(content, title, agent, workspace, source_path, created_at, message_id)
-               SELECT m.content, c.title, a.slug, w.path, c.source_path, m.created_at, m.id
+               SELECT cass_text(m.content), c.title, a.slug, w.path, c.source_path, m.created_at, m.id
                FROM messages m
                JOIN conversations c ON m.conversation_id = c.id
                JOIN agents a ON c.agent_id = a.id
@@ -775,6 +2831,28 @@ impl SqliteStorage {
         Ok(ts)
     }
 
+    /// Whether remote conversations were re-added to Tantivy with their provenance.
+    /// Indexes built before then tagged every document as local.
+    pub fn provenance_indexed(&self) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM meta WHERE key = 'tantivy_provenance'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    pub fn set_provenance_indexed(&mut self) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta(key, value) VALUES('tantivy_provenance', '1')",
+            [],
+        )?;
+        Ok(())
+    }
+
     /// Set the timestamp of the last successful scan (milliseconds since epoch).
     pub fn set_last_scan_ts(&mut self, ts: i64) -> Result<()> {
         self.conn.execute(
@@ -784,6 +2862,96 @@ impl SqliteStorage {
         Ok(())
     }
 
+    /// Per-rule counts of secrets redacted by the most recent index run.
+    pub fn get_last_redactions(&self) -> Result<BTreeMap<String, usize>> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'last_index_redactions'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default())
+    }
+
+    /// Report from the last storage-budget prune, if any ran.
+    pub fn get_last_budget_prune(&self) -> Result<Option<serde_json::Value>> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'last_budget_prune'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+    }
+
+    pub fn set_last_budget_prune(&mut self, report: &serde_json::Value) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta(key, value) VALUES('last_budget_prune', ?)",
+            params![report.to_string()],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_last_redactions(&mut self, counts: &BTreeMap<String, usize>) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta(key, value) VALUES('last_index_redactions', ?)",
+            params![serde_json::to_string(counts)?],
+        )?;
+        Ok(())
+    }
+
+    /// Sync times recorded for a remote source; all empty if it never synced.
+    pub fn get_source_sync_times(&self, source: &str) -> Result<SourceSyncTimes> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?",
+                params![format!("source_sync:{source}")],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default())
+    }
+
+    /// Record a sync attempt of a remote source at `ts` (milliseconds since epoch).
+    pub fn record_source_sync(
+        &mut self,
+        source: &str,
+        ts: i64,
+        failed: bool,
+    ) -> Result<SourceSyncTimes> {
+        let previous = self.get_source_sync_times(source)?;
+        let times = SourceSyncTimes {
+            last_sync: Some(ts),
+            last_success: if failed {
+                previous.last_success
+            } else {
+                Some(ts)
+            },
+            consecutive_failures: if failed {
+                previous.consecutive_failures + 1
+            } else {
+                0
+            },
+        };
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta(key, value) VALUES(?, ?)",
+            params![
+                format!("source_sync:{source}"),
+                serde_json::to_string(&times)?
+            ],
+        )?;
+        Ok(times)
+    }
+
     /// Get current time as milliseconds since epoch.
     pub fn now_millis() -> i64 {
         SystemTime::now()
@@ -917,6 +3085,31 @@ impl SqliteStorage {
     }
 }
 
+/// Open the database for ad-hoc queries outside `SqliteStorage`, with the
+/// encryption key and `cass_text` applied. Does not create or migrate the schema.
+pub fn open_connection(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("opening sqlite db at {}", path.display()))?;
+    encryption::apply_key(&conn)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    compression::register_functions(&conn)?;
+    Ok(conn)
+}
+
+/// Like [`open_connection`] but read-only, for search and reporting paths that
+/// must never contend with the indexer for the write lock.
+pub fn open_readonly_connection(path: &Path) -> Result<Connection> {
+    let conn = Connection::open_with_flags(
+        path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("opening sqlite db readonly at {}", path.display()))?;
+    encryption::apply_key(&conn)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    compression::register_functions(&conn)?;
+    Ok(conn)
+}
+
 fn apply_pragmas(conn: &mut Connection) -> Result<()> {
     conn.execute_batch(
         r"
@@ -928,6 +3121,7 @@ fn apply_pragmas(conn: &mut Connection) -> Result<()> {
 }
 
 fn apply_common_pragmas(conn: &Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
     conn.execute_batch(
         r"
         PRAGMA temp_store = MEMORY;
@@ -936,6 +3130,7 @@ fn apply_common_pragmas(conn: &Connection) -> Result<()> {
         PRAGMA foreign_keys = ON;
         ",
     )?;
+    compression::register_functions(conn)?;
     Ok(())
 }
 
@@ -978,6 +3173,15 @@ fn migrate(conn: &mut Connection) -> Result<()> {
         return Ok(());
     }
 
+    // The v5 table rewrite drops `conversations`; with foreign keys on, that cascades
+    // into `messages`. The pragma is a no-op inside a transaction, so set it around it.
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = apply_migrations(conn, current);
+    conn.pragma_update(None, "foreign_keys", true)?;
+    result
+}
+
+fn apply_migrations(conn: &mut Connection, current: i64) -> Result<()> {
     let tx = conn.transaction()?;
 
     match current {
@@ -987,24 +3191,272 @@ fn migrate(conn: &mut Connection) -> Result<()> {
             tx.execute_batch(MIGRATION_V3)?;
             tx.execute_batch(MIGRATION_V4)?;
             tx.execute_batch(MIGRATION_V5)?;
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
         }
         1 => {
             tx.execute_batch(MIGRATION_V2)?;
             tx.execute_batch(MIGRATION_V3)?;
             tx.execute_batch(MIGRATION_V4)?;
             tx.execute_batch(MIGRATION_V5)?;
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
         }
         2 => {
             tx.execute_batch(MIGRATION_V3)?;
             tx.execute_batch(MIGRATION_V4)?;
             tx.execute_batch(MIGRATION_V5)?;
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
         }
         3 => {
             tx.execute_batch(MIGRATION_V4)?;
             tx.execute_batch(MIGRATION_V5)?;
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
         }
         4 => {
             tx.execute_batch(MIGRATION_V5)?;
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        5 => {
+            tx.execute_batch(MIGRATION_V6)?;
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        6 => {
+            tx.execute_batch(MIGRATION_V7)?;
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        7 => {
+            tx.execute_batch(MIGRATION_V8)?;
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        8 => {
+            tx.execute_batch(MIGRATION_V9)?;
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        9 => {
+            tx.execute_batch(MIGRATION_V10)?;
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        10 => {
+            tx.execute_batch(MIGRATION_V11)?;
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        11 => {
+            tx.execute_batch(MIGRATION_V12)?;
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        12 => {
+            tx.execute_batch(MIGRATION_V13)?;
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        13 => {
+            tx.execute_batch(MIGRATION_V14)?;
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        14 => {
+            tx.execute_batch(MIGRATION_V15)?;
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        15 => {
+            tx.execute_batch(MIGRATION_V16)?;
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        16 => {
+            tx.execute_batch(MIGRATION_V17)?;
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        17 => {
+            tx.execute_batch(MIGRATION_V18)?;
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        18 => {
+            tx.execute_batch(MIGRATION_V19)?;
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        19 => {
+            tx.execute_batch(MIGRATION_V20)?;
+            tx.execute_batch(MIGRATION_V21)?;
+        }
+        20 => {
+            tx.execute_batch(MIGRATION_V21)?;
         }
         v => return Err(anyhow!("unsupported schema version {v}")),
     }
@@ -1027,8 +3479,9 @@ fn insert_conversation(
     tx.execute(
         "INSERT INTO conversations(
             agent_id, workspace_id, source_id, external_id, title, source_path,
-            started_at, ended_at, approx_tokens, metadata_json, origin_host
-        ) VALUES(?,?,?,?,?,?,?,?,?,?,?)",
+            started_at, ended_at, approx_tokens, metadata_json, origin_host, origin_kind,
+            indexed_at
+        ) VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?)",
         params![
             agent_id,
             workspace_id,
@@ -1040,29 +3493,180 @@ fn insert_conversation(
             conv.ended_at,
             conv.approx_tokens,
             serde_json::to_string(&conv.metadata_json)?,
-            conv.origin_host
+            conv.origin_host,
+            origin_kind(conv),
+            SqliteStorage::now_millis()
         ],
     )?;
     Ok(tx.last_insert_rowid())
 }
 
-fn insert_message(tx: &Transaction<'_>, conversation_id: i64, msg: &Message) -> Result<i64> {
+/// `metadata.cass.origin.kind` as injected at scan time; "ssh" for remote sources without it.
+fn origin_kind(conv: &Conversation) -> String {
+    conv.metadata_json
+        .pointer("/cass/origin/kind")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            if conv.source_id == "local" {
+                "local".to_string()
+            } else {
+                "ssh".to_string()
+            }
+        })
+}
+
+fn insert_message(
+    tx: &Transaction<'_>,
+    conversation_id: i64,
+    msg: &Message,
+    compress: bool,
+) -> Result<i64> {
+    let (content, extra_json, compressed) = compression::encode_message(
+        &msg.content,
+        &serde_json::to_string(&msg.extra_json)?,
+        compress,
+    )?;
     tx.execute(
-        "INSERT INTO messages(conversation_id, idx, role, author, created_at, content, extra_json)
-         VALUES(?,?,?,?,?,?,?)",
+        "INSERT INTO messages(conversation_id, idx, role, author, created_at, content, extra_json, approx_tokens, compressed, source_path, source_line)
+         VALUES(?,?,?,?,?,?,?,?,?,?,?)",
         params![
             conversation_id,
             msg.idx,
             role_str(&msg.role),
             msg.author,
             msg.created_at,
-            msg.content,
-            serde_json::to_string(&msg.extra_json)?
+            content,
+            extra_json,
+            msg.approx_tokens,
+            compressed,
+            msg.source_path,
+            msg.source_line
         ],
     )?;
     Ok(tx.last_insert_rowid())
 }
 
+/// Columns read by `conversation_from_row`; callers append WHERE/ORDER BY.
+const CONVERSATION_SELECT: &str =
+    "SELECT c.id, a.slug, w.path, c.external_id, c.title, c.source_path,
+            c.started_at, c.ended_at, c.approx_tokens, c.metadata_json,
+            c.source_id, c.origin_host
+     FROM conversations c
+     JOIN agents a ON c.agent_id = a.id
+     LEFT JOIN workspaces w ON c.workspace_id = w.id";
+
+/// Conversation header (no messages) from a `CONVERSATION_SELECT` row.
+fn conversation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Conversation> {
+    Ok(Conversation {
+        id: Some(row.get(0)?),
+        agent_slug: row.get(1)?,
+        workspace: row
+            .get::<_, Option<String>>(2)?
+            .map(|p| Path::new(&p).to_path_buf()),
+        external_id: row.get(3)?,
+        title: row.get(4)?,
+        source_path: Path::new(&row.get::<_, String>(5)?).to_path_buf(),
+        started_at: row.get(6)?,
+        ended_at: row.get(7)?,
+        approx_tokens: row.get(8)?,
+        metadata_json: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        messages: Vec::new(),
+        source_id: row
+            .get::<_, String>(10)
+            .unwrap_or_else(|_| "local".to_string()),
+        origin_host: row.get(11)?,
+    })
+}
+
+/// Columns read by `message_from_row`; callers append WHERE/ORDER BY.
+const MESSAGE_SELECT: &str =
+    "SELECT id, idx, role, author, created_at, content, extra_json, approx_tokens, compressed,
+            source_path, source_line FROM messages";
+
+fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Message> {
+    let role: String = row.get(2)?;
+    let compressed = row.get::<_, i64>(8)? != 0;
+    Ok(Message {
+        id: Some(row.get(0)?),
+        idx: row.get(1)?,
+        role: match role.as_str() {
+            "user" => MessageRole::User,
+            "agent" | "assistant" => MessageRole::Agent,
+            "tool" => MessageRole::Tool,
+            "system" => MessageRole::System,
+            other => MessageRole::Other(other.to_string()),
+        },
+        author: row.get::<_, Option<String>>(3)?,
+        created_at: row.get::<_, Option<i64>>(4)?,
+        content: compression::column_text(row, 5, compressed)?.unwrap_or_default(),
+        extra_json: compression::column_text(row, 6, compressed)?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        snippets: Vec::new(),
+        approx_tokens: row.get::<_, Option<i64>>(7)?,
+        source_path: row.get::<_, Option<String>>(9)?,
+        source_line: row.get::<_, Option<i64>>(10)?,
+    })
+}
+
+/// Rewrite a stored message in place, keeping its row id.
+fn replace_message(
+    tx: &Transaction<'_>,
+    message_id: i64,
+    msg: &Message,
+    compress: bool,
+) -> Result<()> {
+    let (content, extra_json, compressed) = compression::encode_message(
+        &msg.content,
+        &serde_json::to_string(&msg.extra_json)?,
+        compress,
+    )?;
+    tx.execute(
+        "UPDATE messages SET role = ?, author = ?, created_at = ?, content = ?, extra_json = ?,
+                approx_tokens = ?, compressed = ?, source_path = ?, source_line = ?
+         WHERE id = ?",
+        params![
+            role_str(&msg.role),
+            msg.author,
+            msg.created_at,
+            content,
+            extra_json,
+            msg.approx_tokens,
+            compressed,
+            msg.source_path,
+            msg.source_line,
+            message_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Hash of the fields that identify a message's content, for upsert diffing.
+fn message_hash(role: &str, author: Option<&str>, content: &str) -> [u8; 32] {
+    let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
+    ctx.update(role.as_bytes());
+    ctx.update(&[0x1f]);
+    ctx.update(author.unwrap_or_default().as_bytes());
+    ctx.update(&[0x1f]);
+    ctx.update(content.as_bytes());
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(ctx.finish().as_ref());
+    hash
+}
+
+fn workspace_project_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<WorkspaceProject> {
+    Ok(WorkspaceProject {
+        workspace: row.get(0)?,
+        project: row.get(1)?,
+        git_remote: row.get(2)?,
+        manual: row.get::<_, i64>(3)? != 0,
+    })
+}
+
 fn insert_snippets(tx: &Transaction<'_>, message_id: i64, snippets: &[Snippet]) -> Result<()> {
     for snip in snippets {
         tx.execute(
@@ -1555,6 +4159,18 @@ mod tests {
         assert_eq!(actual_ts, Some(expected_ts));
     }
 
+    #[test]
+    fn set_and_get_last_redactions() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        let mut storage = SqliteStorage::open(&db_path).unwrap();
+        assert!(storage.get_last_redactions().unwrap().is_empty());
+
+        let counts = BTreeMap::from([("aws_access_key".to_string(), 2)]);
+        storage.set_last_redactions(&counts).unwrap();
+        assert_eq!(storage.get_last_redactions().unwrap(), counts);
+    }
+
     // =========================================================================
     // now_millis utility test (bead yln.4)
     // =========================================================================
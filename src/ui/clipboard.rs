@@ -0,0 +1,109 @@
+//! Clipboard access for the TUI.
+//!
+//! Locally the platform clipboard is used through `arboard`. Over SSH, or when
+//! no native clipboard is reachable (headless Linux), the text is sent to the
+//! terminal as an OSC 52 escape so it lands in the clipboard of the machine the
+//! user is sitting at. Inside tmux the escape is wrapped in a passthrough
+//! sequence; tmux still needs `set -g set-clipboard on` to forward it.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Terminals drop larger OSC 52 payloads (xterm and tmux cap around 100 KB)
+const MAX_OSC52_BYTES: usize = 74_000;
+
+/// The clipboard handle is kept alive because on X11 and Wayland the owning
+/// process serves the contents; dropping it right after `set_text` can lose them.
+static NATIVE: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// How text reached the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    Native,
+    Osc52,
+}
+
+impl CopyMethod {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Native => "clipboard",
+            Self::Osc52 => "terminal clipboard (OSC 52)",
+        }
+    }
+}
+
+/// Copy `text`, preferring the native clipboard unless running over SSH.
+pub fn copy(text: &str) -> Result<CopyMethod, String> {
+    if !over_ssh() {
+        match copy_native(text) {
+            Ok(()) => return Ok(CopyMethod::Native),
+            Err(e) => tracing::debug!("native clipboard unavailable, using OSC 52: {e}"),
+        }
+    }
+    copy_osc52(text).map(|()| CopyMethod::Osc52)
+}
+
+/// Status line text for the result of [`copy`]
+pub fn status(what: &str, result: Result<CopyMethod, String>) -> String {
+    match result {
+        Ok(method) => format!("✓ Copied {what} to {}", method.label()),
+        Err(e) => format!("✗ Clipboard copy failed: {e}"),
+    }
+}
+
+fn over_ssh() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION", "SSH_CLIENT"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some())
+}
+
+fn copy_native(text: &str) -> Result<(), arboard::Error> {
+    let mut guard = NATIVE.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        *guard = Some(arboard::Clipboard::new()?);
+    }
+    let clipboard = guard.as_mut().expect("clipboard initialized above");
+    clipboard.set_text(text.to_owned())
+}
+
+fn copy_osc52(text: &str) -> Result<(), String> {
+    if text.len() > MAX_OSC52_BYTES {
+        return Err(format!(
+            "{} KB is too large for OSC 52 (limit {} KB)",
+            text.len() / 1024,
+            MAX_OSC52_BYTES / 1024
+        ));
+    }
+    let seq = osc52_sequence(text, std::env::var_os("TMUX").is_some());
+    let mut out = std::io::stdout();
+    out.write_all(seq.as_bytes())
+        .and_then(|()| out.flush())
+        .map_err(|e| e.to_string())
+}
+
+/// The OSC 52 "set clipboard" escape for `text`, optionally wrapped for tmux
+pub fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let seq = format!("\x1b]52;c;{}\x07", BASE64.encode(text));
+    if tmux {
+        // tmux passthrough: DCS tmux; <seq with every ESC doubled> ST
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_text_and_wraps_for_tmux() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+}
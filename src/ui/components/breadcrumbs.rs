@@ -1,6 +1,8 @@
 //! Breadcrumb bar component for the TUI.
 //! Displays current context (Agent › Workspace › Date) and ranking.
 //! Interactive elements allow direct clearing/changing of filters.
+//! When the user has drilled in (search → conversation → similar → ...), the
+//! [`NavHistory`] trail leads the bar so the way back stays visible.
 
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Paragraph};
@@ -18,6 +20,58 @@ pub enum BreadcrumbKind {
     None,
 }
 
+/// Most steps kept; the oldest is dropped beyond this
+pub const MAX_NAV_DEPTH: usize = 50;
+
+/// Places to go back to, each with the label shown in the trail
+#[derive(Clone, Debug)]
+pub struct NavHistory<T> {
+    entries: Vec<(String, T)>,
+}
+
+impl<T> Default for NavHistory<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> NavHistory<T> {
+    pub fn push(&mut self, label: impl Into<String>, state: T) {
+        if self.entries.len() == MAX_NAV_DEPTH {
+            self.entries.remove(0);
+        }
+        self.entries.push((label.into(), state));
+    }
+
+    pub fn pop(&mut self) -> Option<(String, T)> {
+        self.entries.pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Trail labels, oldest first
+    pub fn labels(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+}
+
+/// `label` cut to `max` characters with an ellipsis
+fn shorten(label: &str, max: usize) -> String {
+    if label.chars().count() <= max {
+        label.to_string()
+    } else {
+        let cut: String = label.chars().take(max.saturating_sub(1)).collect();
+        format!("{cut}…")
+    }
+}
+
 fn ranking_label(r: tui::RankingMode) -> &'static str {
     match r {
         tui::RankingMode::RecentHeavy => "Recent",
@@ -34,6 +88,7 @@ pub fn render_breadcrumbs(
     area: Rect,
     filters: &SearchFilters,
     ranking: tui::RankingMode,
+    trail: &[&str],
     palette: ThemePalette,
 ) -> Vec<(Rect, BreadcrumbKind)> {
     let mut rects: Vec<(Rect, BreadcrumbKind)> = Vec::new();
@@ -44,6 +99,23 @@ pub fn render_breadcrumbs(
         spans.push(Span::styled(" › ", Style::default().fg(palette.hint)));
     };
 
+    // 0. Navigation trail: the last few places Backspace/Ctrl+O go back to
+    let mut trail_text = String::new();
+    if !trail.is_empty() {
+        trail_text.push_str("↩ ");
+        if trail.len() > 3 {
+            trail_text.push_str("… › ");
+        }
+        for label in &trail[trail.len().saturating_sub(3)..] {
+            trail_text.push_str(&shorten(label, 24));
+            trail_text.push_str(" › ");
+        }
+        spans.push(Span::styled(
+            trail_text.clone(),
+            Style::default().fg(palette.accent_alt),
+        ));
+    }
+
     // 1. Agent
     let agent_text = if filters.agents.is_empty() {
         "All agents".to_string()
@@ -72,8 +144,8 @@ pub fn render_breadcrumbs(
     // However, to support "crumb choosers", we really want distinct zones.
     // Let's approximate width based on char count.
 
-    let mut current_x = area.x;
     let measure_width = |s: &str| s.chars().count() as u16;
+    let mut current_x = area.x + measure_width(&trail_text);
 
     let agent_width = measure_width(&agent_text);
     rects.push((
@@ -159,3 +231,20 @@ pub fn render_breadcrumbs(
 
     rects
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nav_history_pops_newest_first_and_caps_depth() {
+        let mut nav = NavHistory::default();
+        for i in 0..MAX_NAV_DEPTH + 2 {
+            nav.push(format!("step {i}"), i);
+        }
+        assert_eq!(nav.labels().len(), MAX_NAV_DEPTH);
+        assert_eq!(nav.labels()[0], "step 2");
+        assert_eq!(nav.pop().map(|(_, i)| i), Some(MAX_NAV_DEPTH + 1));
+        assert_eq!(shorten("abcdef", 4), "abc…");
+    }
+}
@@ -0,0 +1,439 @@
+//! Calendar screen: sessions laid out by local day, with a density heatmap
+//! and the list of sessions started on the selected day.
+//! State and rendering only; `src/ui/tui.rs` loads the data through
+//! [`SqliteStorage::daily_activity`] and
+//! [`SqliteStorage::conversations_started_between`] whenever
+//! [`CalendarState::activity_range`] or [`CalendarState::day_range`] change.
+//!
+//! [`SqliteStorage::daily_activity`]: crate::storage::sqlite::SqliteStorage::daily_activity
+//! [`SqliteStorage::conversations_started_between`]: crate::storage::sqlite::SqliteStorage::conversations_started_between
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate, TimeZone};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+use crate::storage::sqlite::{CalendarEntry, DayActivity};
+use crate::ui::components::theme::ThemePalette;
+
+/// Heatmap cells from no sessions to the busiest visible day
+const HEAT: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarView {
+    /// Six weeks around the selected month
+    Month,
+    /// The selected day's Monday–Sunday week
+    Week,
+}
+
+#[derive(Clone, Debug)]
+pub struct CalendarState {
+    pub open: bool,
+    pub view: CalendarView,
+    pub selected: NaiveDate,
+    /// Keys move through the day's sessions instead of the days
+    pub list_focus: bool,
+    pub entry_idx: usize,
+    /// (conversations, messages) per day of the loaded range
+    activity: HashMap<NaiveDate, (i64, i64)>,
+    activity_loaded: Option<(NaiveDate, NaiveDate)>,
+    entries: Vec<CalendarEntry>,
+    entries_loaded: Option<NaiveDate>,
+}
+
+impl Default for CalendarState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            view: CalendarView::Month,
+            selected: Local::now().date_naive(),
+            list_focus: false,
+            entry_idx: 0,
+            activity: HashMap::new(),
+            activity_loaded: None,
+            entries: Vec::new(),
+            entries_loaded: None,
+        }
+    }
+}
+
+impl CalendarState {
+    /// Open on today; data is reloaded since the index may have changed.
+    pub fn open(&mut self) {
+        *self = Self {
+            open: true,
+            view: self.view,
+            ..Self::default()
+        };
+    }
+
+    /// First and last day shown by the current view
+    pub fn visible_days(&self) -> (NaiveDate, NaiveDate) {
+        match self.view {
+            CalendarView::Month => {
+                let first = self.selected.with_day(1).unwrap_or(self.selected);
+                let start = monday_of(first);
+                (start, start + Days::new(41))
+            }
+            CalendarView::Week => {
+                let start = monday_of(self.selected);
+                (start, start + Days::new(6))
+            }
+        }
+    }
+
+    /// Millisecond range whose daily activity must be (re)loaded, if any
+    pub fn activity_range(&self) -> Option<(i64, i64)> {
+        let visible = self.visible_days();
+        if self.activity_loaded == Some(visible) {
+            return None;
+        }
+        Some((
+            day_start_ms(visible.0)?,
+            day_start_ms(visible.1 + Days::new(1))?,
+        ))
+    }
+
+    pub fn set_activity(&mut self, days: Vec<DayActivity>) {
+        self.activity = days
+            .into_iter()
+            .filter_map(|d| {
+                let day = NaiveDate::parse_from_str(&d.day, "%Y-%m-%d").ok()?;
+                Some((day, (d.conversations, d.messages)))
+            })
+            .collect();
+        self.activity_loaded = Some(self.visible_days());
+    }
+
+    /// Millisecond range of the selected day when its sessions must be loaded
+    pub fn day_range(&self) -> Option<(i64, i64)> {
+        if self.entries_loaded == Some(self.selected) {
+            return None;
+        }
+        Some((
+            day_start_ms(self.selected)?,
+            day_start_ms(self.selected + Days::new(1))?,
+        ))
+    }
+
+    pub fn set_entries(&mut self, entries: Vec<CalendarEntry>) {
+        self.entries = entries;
+        self.entries_loaded = Some(self.selected);
+        self.entry_idx = 0;
+        if self.entries.is_empty() {
+            self.list_focus = false;
+        }
+    }
+
+    /// Selected day's range for the search date filter
+    pub fn selected_day_range(&self) -> Option<(i64, i64)> {
+        Some((
+            day_start_ms(self.selected)?,
+            day_start_ms(self.selected + Days::new(1))? - 1,
+        ))
+    }
+
+    pub fn selected_entry(&self) -> Option<&CalendarEntry> {
+        self.entries.get(self.entry_idx)
+    }
+
+    pub fn move_days(&mut self, delta: i64) {
+        let moved = if delta >= 0 {
+            self.selected.checked_add_days(Days::new(delta as u64))
+        } else {
+            self.selected
+                .checked_sub_days(Days::new(delta.unsigned_abs()))
+        };
+        self.selected = moved.unwrap_or(self.selected);
+    }
+
+    /// Month view steps a month, week view a week
+    pub fn move_page(&mut self, forward: bool) {
+        match (self.view, forward) {
+            (CalendarView::Week, true) => self.move_days(7),
+            (CalendarView::Week, false) => self.move_days(-7),
+            (CalendarView::Month, true) => {
+                self.selected = self.selected + Months::new(1);
+            }
+            (CalendarView::Month, false) => {
+                self.selected = self.selected - Months::new(1);
+            }
+        }
+    }
+
+    pub fn move_entry(&mut self, delta: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let max = self.entries.len() as isize - 1;
+        self.entry_idx = (self.entry_idx as isize + delta).clamp(0, max) as usize;
+    }
+
+    pub fn toggle_view(&mut self) {
+        self.view = match self.view {
+            CalendarView::Month => CalendarView::Week,
+            CalendarView::Week => CalendarView::Month,
+        };
+    }
+
+    pub fn go_today(&mut self) {
+        self.selected = Local::now().date_naive();
+    }
+
+    fn heat(&self, day: NaiveDate, busiest: i64) -> usize {
+        let count = self.activity.get(&day).map_or(0, |(c, _)| *c);
+        if count == 0 || busiest == 0 {
+            return 0;
+        }
+        // Scale 1..=4 so a single session is still visible
+        1 + ((count - 1) * 3 / (busiest - 1).max(1)).min(3) as usize
+    }
+}
+
+fn monday_of(day: NaiveDate) -> NaiveDate {
+    day - Days::new(u64::from(day.weekday().num_days_from_monday()))
+}
+
+/// Local midnight of `day` in epoch milliseconds
+fn day_start_ms(day: NaiveDate) -> Option<i64> {
+    Local
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
+
+pub fn draw_calendar(f: &mut Frame<'_>, area: Rect, state: &CalendarState, palette: ThemePalette) {
+    let (first, last) = state.visible_days();
+    let busiest = first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .filter_map(|d| state.activity.get(&d).map(|(c, _)| *c))
+        .max()
+        .unwrap_or(0);
+
+    let title = match state.view {
+        CalendarView::Month => format!(" Calendar · {} ", state.selected.format("%B %Y")),
+        CalendarView::Week => format!(
+            " Calendar · week of {} ",
+            monday_of(state.selected).format("%b %-d, %Y")
+        ),
+    };
+    let block = Block::default()
+        .title(Span::styled(
+            title,
+            Style::default()
+                .fg(palette.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(palette.accent))
+        .style(Style::default().bg(palette.surface));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(44), Constraint::Min(20)])
+        .split(rows[0]);
+
+    let cell_style = |day: NaiveDate| {
+        let mut style = Style::default().fg(palette.fg);
+        if state.view == CalendarView::Month && day.month() != state.selected.month() {
+            style = style.fg(palette.hint);
+        }
+        if day == state.selected {
+            style = style
+                .bg(palette.accent)
+                .fg(palette.bg)
+                .add_modifier(Modifier::BOLD);
+        } else if day == Local::now().date_naive() {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        style
+    };
+    let heat_style = |level: usize| {
+        if level == 0 {
+            Style::default().fg(palette.hint)
+        } else {
+            Style::default().fg(palette.user)
+        }
+    };
+
+    let mut grid: Vec<Line> = Vec::new();
+    match state.view {
+        CalendarView::Month => {
+            grid.push(Line::from(Span::styled(
+                " Mon   Tue   Wed   Thu   Fri   Sat   Sun",
+                Style::default().fg(palette.hint),
+            )));
+            for week in 0..6u64 {
+                let mut spans = Vec::new();
+                for dow in 0..7u64 {
+                    let day = first + Days::new(week * 7 + dow);
+                    let level = state.heat(day, busiest);
+                    spans.push(Span::styled(format!(" {:>2}", day.day()), cell_style(day)));
+                    spans.push(Span::styled(
+                        format!("{} ", HEAT[level].repeat(2)),
+                        heat_style(level),
+                    ));
+                }
+                grid.push(Line::from(spans));
+            }
+        }
+        CalendarView::Week => {
+            for dow in 0..7u64 {
+                let day = first + Days::new(dow);
+                let level = state.heat(day, busiest);
+                let (conversations, messages) = state.activity.get(&day).copied().unwrap_or((0, 0));
+                grid.push(Line::from(vec![
+                    Span::styled(format!(" {} ", day.format("%a %b %e")), cell_style(day)),
+                    Span::styled(format!(" {} ", HEAT[level].repeat(8)), heat_style(level)),
+                    Span::styled(
+                        format!("{conversations} sessions · {messages} msgs"),
+                        Style::default().fg(palette.hint),
+                    ),
+                ]));
+            }
+        }
+    }
+    let (conversations, messages) = state
+        .activity
+        .get(&state.selected)
+        .copied()
+        .unwrap_or((0, 0));
+    grid.push(Line::from(""));
+    grid.push(Line::from(Span::styled(
+        format!(
+            " {} · {conversations} sessions · {messages} messages",
+            state.selected.format("%A %b %-d")
+        ),
+        Style::default().fg(palette.fg).add_modifier(Modifier::BOLD),
+    )));
+    f.render_widget(Paragraph::new(grid), cols[0]);
+
+    let items: Vec<ListItem> = if state.entries.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "No sessions started this day",
+            Style::default().fg(palette.hint),
+        ))]
+    } else {
+        state
+            .entries
+            .iter()
+            .map(|e| {
+                let time = Local
+                    .timestamp_millis_opt(e.started_at)
+                    .single()
+                    .map(|t| t.format("%H:%M").to_string())
+                    .unwrap_or_default();
+                let title = e
+                    .title
+                    .as_deref()
+                    .filter(|t| !t.trim().is_empty())
+                    .unwrap_or(&e.source_path);
+                let workspace = e
+                    .workspace
+                    .as_deref()
+                    .and_then(|w| w.rsplit(['/', '\\']).find(|s| !s.is_empty()))
+                    .unwrap_or("");
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(format!("{time} "), Style::default().fg(palette.hint)),
+                        Span::styled(
+                            format!("{:<8} ", e.agent),
+                            Style::default().fg(palette.agent),
+                        ),
+                        Span::styled(title.to_string(), Style::default().fg(palette.fg)),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("      {} msgs  {workspace}", e.messages),
+                        Style::default().fg(palette.hint),
+                    )),
+                ])
+            })
+            .collect()
+    };
+    let list_border = if state.list_focus {
+        palette.accent
+    } else {
+        palette.border
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(" Sessions ")
+                .borders(Borders::LEFT)
+                .border_style(Style::default().fg(list_border)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(palette.accent)
+                .fg(palette.bg)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut list_state = ListState::default();
+    if state.list_focus {
+        list_state.select(Some(state.entry_idx));
+    }
+    f.render_stateful_widget(list, cols[1], &mut list_state);
+
+    let help = if state.list_focus {
+        "↑↓ session · Enter search this day · e open in editor · Tab days · Esc close"
+    } else {
+        "←→↑↓ day · PgUp/PgDn page · w week/month · t today · Tab sessions · Enter search this day · Esc close"
+    };
+    f.render_widget(
+        Paragraph::new(Span::styled(
+            help,
+            Style::default()
+                .fg(palette.hint)
+                .add_modifier(Modifier::ITALIC),
+        )),
+        rows[1],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_view_spans_six_weeks_from_monday() {
+        let mut state = CalendarState {
+            selected: NaiveDate::from_ymd_opt(2025, 3, 4).unwrap(),
+            ..CalendarState::default()
+        };
+        let (first, last) = state.visible_days();
+        assert_eq!(first, NaiveDate::from_ymd_opt(2025, 2, 24).unwrap());
+        assert_eq!(last, NaiveDate::from_ymd_opt(2025, 4, 6).unwrap());
+
+        state.toggle_view();
+        assert_eq!(
+            state.visible_days(),
+            (
+                NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 3, 9).unwrap()
+            )
+        );
+
+        // Loaded data is only requested again once the view moves
+        assert!(state.activity_range().is_some());
+        state.set_activity(vec![]);
+        assert!(state.activity_range().is_none());
+        state.move_page(true);
+        assert!(state.activity_range().is_some());
+    }
+}
@@ -0,0 +1,269 @@
+//! Usage dashboard: totals per agent, a messages-per-day sparkline, the
+//! busiest workspaces and token/cost aggregates for the whole index.
+//! Rendering only; `src/ui/tui.rs` fills [`DashboardData`] from
+//! [`SqliteStorage::agent_usage`], [`SqliteStorage::top_workspaces`] and
+//! [`SqliteStorage::daily_messages`] each time the dashboard opens.
+//!
+//! [`SqliteStorage::agent_usage`]: crate::storage::sqlite::SqliteStorage::agent_usage
+//! [`SqliteStorage::top_workspaces`]: crate::storage::sqlite::SqliteStorage::top_workspaces
+//! [`SqliteStorage::daily_messages`]: crate::storage::sqlite::SqliteStorage::daily_messages
+
+use chrono::{Days, NaiveDate};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Sparkline},
+};
+
+use crate::storage::sqlite::{AgentUsage, DayActivity};
+use crate::ui::components::theme::ThemePalette;
+
+/// Days covered by the sparkline, ending today
+pub const SPARKLINE_DAYS: u64 = 30;
+
+#[derive(Clone, Debug, Default)]
+pub struct DashboardData {
+    pub agents: Vec<AgentUsage>,
+    pub workspaces: Vec<(String, i64)>,
+    /// Messages per day for the last [`SPARKLINE_DAYS`] days, oldest first
+    pub daily_messages: Vec<u64>,
+    /// From `usd_per_million_tokens` under `[tui]`; no cost is shown without it
+    pub usd_per_million_tokens: Option<f64>,
+}
+
+impl DashboardData {
+    /// Spread `days` over the `SPARKLINE_DAYS` ending at `today`, filling gaps with 0.
+    pub fn fill_days(days: &[DayActivity], today: NaiveDate) -> Vec<u64> {
+        let first = today - Days::new(SPARKLINE_DAYS - 1);
+        first
+            .iter_days()
+            .take(SPARKLINE_DAYS as usize)
+            .map(|day| {
+                let key = day.format("%Y-%m-%d").to_string();
+                days.iter()
+                    .find(|d| d.day == key)
+                    .map_or(0, |d| d.messages.max(0) as u64)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DashboardState {
+    pub open: bool,
+    pub data: DashboardData,
+    /// Set when the queries failed; shown instead of the panels
+    pub error: Option<String>,
+}
+
+/// `1234567` → `1.2M`
+fn compact(n: i64) -> String {
+    match n {
+        n if n >= 1_000_000_000 => format!("{:.1}B", n as f64 / 1e9),
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1e6),
+        n if n >= 10_000 => format!("{:.1}k", n as f64 / 1e3),
+        n => n.to_string(),
+    }
+}
+
+fn cost(tokens: i64, rate: Option<f64>) -> String {
+    rate.map(|r| format!("${:.2}", tokens as f64 / 1e6 * r))
+        .unwrap_or_else(|| "—".to_string())
+}
+
+pub fn draw_dashboard(
+    f: &mut Frame<'_>,
+    area: Rect,
+    state: &DashboardState,
+    palette: ThemePalette,
+) {
+    let block = Block::default()
+        .title(Span::styled(
+            " Usage dashboard ",
+            Style::default()
+                .fg(palette.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(palette.accent))
+        .style(Style::default().bg(palette.surface));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let hint = Style::default().fg(palette.hint);
+    let bold = Style::default().fg(palette.fg).add_modifier(Modifier::BOLD);
+    if let Some(err) = &state.error {
+        f.render_widget(
+            Paragraph::new(vec![
+                Line::from(Span::styled(format!("Could not load usage: {err}"), bold)),
+                Line::from(Span::styled("Esc close", hint)),
+            ]),
+            inner,
+        );
+        return;
+    }
+
+    let data = &state.data;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Length(5),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let sessions: i64 = data.agents.iter().map(|a| a.conversations).sum();
+    let messages: i64 = data.agents.iter().map(|a| a.messages).sum();
+    let tokens: i64 = data.agents.iter().map(|a| a.tokens).sum();
+    let mut totals = vec![
+        Span::styled(" Sessions ", hint),
+        Span::styled(compact(sessions), bold),
+        Span::styled("   Messages ", hint),
+        Span::styled(compact(messages), bold),
+        Span::styled("   Tokens ", hint),
+        Span::styled(compact(tokens), bold),
+    ];
+    if data.usd_per_million_tokens.is_some() {
+        totals.push(Span::styled("   Est. cost ", hint));
+        totals.push(Span::styled(
+            cost(tokens, data.usd_per_million_tokens),
+            bold,
+        ));
+    }
+    f.render_widget(Paragraph::new(Line::from(totals)), rows[0]);
+
+    let peak = data.daily_messages.iter().copied().max().unwrap_or(0);
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    format!(" Messages per day · last {SPARKLINE_DAYS} days · peak {peak} "),
+                    hint,
+                ))
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(palette.border)),
+        )
+        .data(&data.daily_messages)
+        .style(Style::default().fg(palette.user));
+    f.render_widget(sparkline, rows[1]);
+
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[2]);
+
+    let mut agent_lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<14}{:>9}{:>10}{:>10}{:>10}",
+            "Agent", "Sessions", "Messages", "Tokens", "Cost"
+        ),
+        hint,
+    ))];
+    agent_lines.extend(data.agents.iter().map(|a| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<14}", a.agent),
+                Style::default().fg(palette.agent),
+            ),
+            Span::styled(
+                format!(
+                    "{:>9}{:>10}{:>10}{:>10}",
+                    compact(a.conversations),
+                    compact(a.messages),
+                    compact(a.tokens),
+                    cost(a.tokens, data.usd_per_million_tokens)
+                ),
+                Style::default().fg(palette.fg),
+            ),
+        ])
+    }));
+    f.render_widget(
+        Paragraph::new(agent_lines).block(
+            Block::default()
+                .title(Span::styled(" Per agent ", hint))
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(palette.border)),
+        ),
+        cols[0],
+    );
+
+    let busiest = data.workspaces.first().map_or(1, |(_, c)| (*c).max(1));
+    let bar_width = 12usize;
+    let ws_lines: Vec<Line> = data
+        .workspaces
+        .iter()
+        .map(|(path, count)| {
+            let filled = ((*count * bar_width as i64) / busiest).max(1) as usize;
+            let name = path
+                .rsplit(['/', '\\'])
+                .find(|s| !s.is_empty())
+                .unwrap_or(path);
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<bar_width$} ", "█".repeat(filled)),
+                    Style::default().fg(palette.accent_alt),
+                ),
+                Span::styled(format!("{count:>5} "), Style::default().fg(palette.fg)),
+                Span::styled(name.to_string(), Style::default().fg(palette.fg)),
+            ])
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(ws_lines).block(
+            Block::default()
+                .title(Span::styled(" Top workspaces ", hint))
+                .borders(Borders::TOP | Borders::LEFT)
+                .border_style(Style::default().fg(palette.border)),
+        ),
+        cols[1],
+    );
+
+    let footer = if data.usd_per_million_tokens.is_some() {
+        "Esc/Ctrl+G close · r refresh"
+    } else {
+        "Esc/Ctrl+G close · r refresh · set usd_per_million_tokens under [tui] for cost estimates"
+    };
+    f.render_widget(
+        Paragraph::new(Span::styled(footer, hint.add_modifier(Modifier::ITALIC))),
+        rows[3],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_days_covers_the_window_with_zero_gaps() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let days = vec![
+            DayActivity {
+                day: "2025-03-01".into(),
+                conversations: 1,
+                messages: 4,
+            },
+            DayActivity {
+                day: "2025-03-30".into(),
+                conversations: 2,
+                messages: 9,
+            },
+            // Outside the window
+            DayActivity {
+                day: "2025-02-01".into(),
+                conversations: 1,
+                messages: 7,
+            },
+        ];
+        let filled = DashboardData::fill_days(&days, today);
+        assert_eq!(filled.len(), SPARKLINE_DAYS as usize);
+        assert_eq!(filled[0], 4);
+        assert_eq!(filled[SPARKLINE_DAYS as usize - 1], 9);
+        assert_eq!(filled.iter().sum::<u64>(), 13);
+    }
+}
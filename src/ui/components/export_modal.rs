@@ -0,0 +1,230 @@
+//! Export modal: choose a format, which hits, and where they go.
+//! Rendering and field editing only; `src/ui/tui.rs` collects the hits and
+//! runs [`crate::export::export_results`] when the user confirms.
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+use crate::export::ExportFormat;
+use crate::ui::components::theme::ThemePalette;
+
+/// Which hits to export
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportScope {
+    /// The highlighted result
+    Hit,
+    /// Every multi-selected result
+    Selection,
+    /// All results of the current search
+    Results,
+}
+
+impl ExportScope {
+    fn next(self, has_selection: bool) -> Self {
+        match self {
+            Self::Hit if has_selection => Self::Selection,
+            Self::Hit | Self::Selection => Self::Results,
+            Self::Results => Self::Hit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportDestination {
+    File,
+    Clipboard,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportField {
+    Format,
+    Scope,
+    Destination,
+    Path,
+}
+
+const FIELDS: [ExportField; 4] = [
+    ExportField::Format,
+    ExportField::Scope,
+    ExportField::Destination,
+    ExportField::Path,
+];
+
+#[derive(Clone, Debug)]
+pub struct ExportModalState {
+    pub open: bool,
+    pub format: ExportFormat,
+    pub scope: ExportScope,
+    pub destination: ExportDestination,
+    /// Output file, relative to the working directory cass was started in
+    pub path: String,
+    pub field: ExportField,
+    /// Whether a multi-selection existed when the modal opened
+    pub has_selection: bool,
+}
+
+impl Default for ExportModalState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            format: ExportFormat::Markdown,
+            scope: ExportScope::Hit,
+            destination: ExportDestination::File,
+            path: default_path(ExportFormat::Markdown),
+            field: ExportField::Format,
+            has_selection: false,
+        }
+    }
+}
+
+impl ExportModalState {
+    /// Open with the selection as scope when there is one; format and
+    /// destination are remembered from the previous export.
+    pub fn open(&mut self, has_selection: bool) {
+        self.open = true;
+        self.has_selection = has_selection;
+        self.scope = if has_selection {
+            ExportScope::Selection
+        } else {
+            ExportScope::Hit
+        };
+        self.path = default_path(self.format);
+        self.field = ExportField::Format;
+    }
+
+    pub fn move_field(&mut self, delta: isize) {
+        let fields: Vec<ExportField> = FIELDS
+            .into_iter()
+            .filter(|f| *f != ExportField::Path || self.destination == ExportDestination::File)
+            .collect();
+        let idx = fields.iter().position(|f| *f == self.field).unwrap_or(0) as isize;
+        let len = fields.len() as isize;
+        self.field = fields[(idx + delta).rem_euclid(len) as usize];
+    }
+
+    /// Step the focused field to its next value (no-op on the path).
+    pub fn cycle(&mut self) {
+        match self.field {
+            ExportField::Format => {
+                let next = self.format.next();
+                // Keep a hand-typed name, only swapping the extension
+                if let Some(stem) = self.path.strip_suffix(self.format.extension()) {
+                    self.path = format!("{stem}{}", next.extension());
+                }
+                self.format = next;
+            }
+            ExportField::Scope => self.scope = self.scope.next(self.has_selection),
+            ExportField::Destination => {
+                self.destination = match self.destination {
+                    ExportDestination::File => ExportDestination::Clipboard,
+                    ExportDestination::Clipboard => ExportDestination::File,
+                };
+            }
+            ExportField::Path => {}
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if self.field == ExportField::Path {
+            self.path.push(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        if self.field == ExportField::Path {
+            self.path.pop();
+        }
+    }
+}
+
+/// `cass-export-<timestamp>.<ext>` in the working directory
+pub fn default_path(format: ExportFormat) -> String {
+    format!(
+        "cass-export-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    )
+}
+
+/// Render the modal; `counts` are the hit counts for (selection, all results).
+pub fn draw_export_modal(
+    f: &mut Frame<'_>,
+    area: Rect,
+    state: &ExportModalState,
+    counts: (usize, usize),
+    palette: ThemePalette,
+) {
+    let (selection, results) = counts;
+    let scope = match state.scope {
+        ExportScope::Hit => "Selected result".to_string(),
+        ExportScope::Selection => format!("Selected set ({selection})"),
+        ExportScope::Results => format!("All results ({results})"),
+    };
+    let destination = match state.destination {
+        ExportDestination::File => "File",
+        ExportDestination::Clipboard => "Clipboard",
+    };
+    let mut rows = vec![
+        (
+            ExportField::Format,
+            "Format",
+            state.format.name().to_string(),
+        ),
+        (ExportField::Scope, "Export", scope),
+        (ExportField::Destination, "To", destination.to_string()),
+    ];
+    if state.destination == ExportDestination::File {
+        rows.push((ExportField::Path, "Path", format!("{}▏", state.path)));
+    }
+
+    let mut lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(field, label, value)| {
+            let focused = field == state.field;
+            let value_style = if focused {
+                Style::default()
+                    .bg(palette.accent)
+                    .fg(palette.bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(palette.fg)
+            };
+            let value = if focused && field != ExportField::Path {
+                format!("‹ {value} ›")
+            } else {
+                value
+            };
+            Line::from(vec![
+                Span::styled(if focused { "→ " } else { "  " }, value_style),
+                Span::styled(format!("{label:<8}"), Style::default().fg(palette.hint)),
+                Span::styled(value, value_style),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑↓ field · ←→/Space change · Enter export · Esc cancel",
+        Style::default()
+            .fg(palette.hint)
+            .add_modifier(Modifier::ITALIC),
+    )));
+
+    let block = Block::default()
+        .title(Span::styled(
+            " Export ",
+            Style::default()
+                .fg(palette.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(palette.accent))
+        .style(Style::default().bg(palette.surface));
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
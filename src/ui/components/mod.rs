@@ -1,8 +1,13 @@
 //! UI components registry.
 pub mod breadcrumbs;
+pub mod calendar;
+pub mod dashboard;
+pub mod export_modal;
 pub mod help_strip;
 pub mod palette;
 pub mod pills;
+pub mod raw_view;
 pub mod theme;
 pub mod toast;
+pub mod tool_panels;
 pub mod widgets;
@@ -28,6 +28,15 @@ pub enum PaletteAction {
     LoadViewSlot(u8),
     OpenBulkActions,
     ReloadIndex,
+    CopySnippet,
+    CopyMessage,
+    CopyConversation,
+    ExportResults,
+    OpenCalendar,
+    OpenDashboard,
+    OpenQuickPick,
+    ResumeSession,
+    SwitchProfile,
 }
 
 /// Render-ready descriptor for an action.
@@ -208,6 +217,51 @@ pub fn default_actions() -> Vec<PaletteItem> {
             "Bulk actions",
             "Open bulk menu on selection",
         ),
+        item(
+            PaletteAction::CopySnippet,
+            "Copy: snippet",
+            "Selected result's snippet",
+        ),
+        item(
+            PaletteAction::CopyMessage,
+            "Copy: message",
+            "Matched message (y in detail)",
+        ),
+        item(
+            PaletteAction::CopyConversation,
+            "Copy: conversation",
+            "Whole transcript (Y in detail)",
+        ),
+        item(
+            PaletteAction::ExportResults,
+            "Export results",
+            "Markdown/JSON/text to file or clipboard (Ctrl+E)",
+        ),
+        item(
+            PaletteAction::OpenCalendar,
+            "Calendar",
+            "Sessions per day, heatmap (Ctrl+T)",
+        ),
+        item(
+            PaletteAction::OpenDashboard,
+            "Usage dashboard",
+            "Per-agent totals, activity, tokens (Ctrl+G)",
+        ),
+        item(
+            PaletteAction::OpenQuickPick,
+            "Quick pick",
+            "Fuzzy-find a session by title or workspace, open it in the editor (Ctrl+K)",
+        ),
+        item(
+            PaletteAction::ResumeSession,
+            "Resume session",
+            "Relaunch the agent on this conversation (Ctrl+X)",
+        ),
+        item(
+            PaletteAction::SwitchProfile,
+            "Switch profile",
+            "Reopen on another profile's data dir (Ctrl+L)",
+        ),
         item(
             PaletteAction::ReloadIndex,
             "Reload index/view",
@@ -0,0 +1,187 @@
+//! Raw source view: the stored connector entry behind one message.
+//!
+//! Connectors keep the original JSON/JSONL record in `extra_json`. Showing it
+//! pretty-printed next to the normalized fields makes it easy to spot what a
+//! connector dropped or mis-mapped.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+
+use crate::model::types::{Message, MessageRole};
+use crate::ui::components::theme::ThemePalette;
+
+/// Header with the normalized fields, then the highlighted raw entry.
+pub fn render_raw_message(
+    msg: &Message,
+    position: usize,
+    total: usize,
+    palette: ThemePalette,
+) -> Vec<Line<'static>> {
+    let label = Style::default().fg(palette.hint);
+    let value = Style::default().fg(palette.fg);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("Raw entry · message {}/{total}", position + 1),
+                Style::default()
+                    .fg(palette.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  [ / ] previous/next · r back", label),
+        ]),
+        Line::from(""),
+    ];
+    let mut field = |name: &str, text: String| {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{name:>10}: "), label),
+            Span::styled(text, value),
+        ]));
+    };
+    field("idx", msg.idx.to_string());
+    field(
+        "id",
+        msg.id.map_or_else(|| "-".to_string(), |id| id.to_string()),
+    );
+    field(
+        "role",
+        match &msg.role {
+            MessageRole::User => "user".to_string(),
+            MessageRole::Agent => "agent".to_string(),
+            MessageRole::Tool => "tool".to_string(),
+            MessageRole::System => "system".to_string(),
+            MessageRole::Other(r) => r.clone(),
+        },
+    );
+    field("author", msg.author.clone().unwrap_or_else(|| "-".into()));
+    field(
+        "created_at",
+        msg.created_at
+            .map_or_else(|| "-".to_string(), |ts| ts.to_string()),
+    );
+    if msg.source_path.is_some() || msg.source_line.is_some() {
+        let (path, line) = msg.location("(conversation file)");
+        field(
+            "source",
+            line.map_or_else(|| path.to_string(), |l| format!("{path}:{l}")),
+        );
+    }
+    field("content", format!("{} chars", msg.content.chars().count()));
+    lines.push(Line::from(""));
+
+    if msg.extra_json.is_null() {
+        lines.push(Line::from(Span::styled(
+            "No raw entry stored for this message",
+            label.add_modifier(Modifier::ITALIC),
+        )));
+    } else {
+        lines.extend(highlight_json(&msg.extra_json, palette));
+    }
+    lines
+}
+
+/// Pretty-print `value` with keys, strings, numbers and literals colored apart.
+pub fn highlight_json(value: &Value, palette: ThemePalette) -> Vec<Line<'static>> {
+    let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+    pretty
+        .lines()
+        .map(|line| highlight_line(line, palette))
+        .collect()
+}
+
+fn highlight_line(line: &str, palette: ThemePalette) -> Line<'static> {
+    let punct = Style::default().fg(palette.hint);
+    let mut spans = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap_or(' ');
+        let len = if c == '"' {
+            let len = string_len(rest);
+            // A string followed by ':' is an object key
+            let is_key = rest[len..].trim_start().starts_with(':');
+            let color = if is_key { palette.accent } else { palette.user };
+            spans.push(Span::styled(
+                rest[..len].to_string(),
+                Style::default().fg(color),
+            ));
+            len
+        } else if c == '-' || c.is_ascii_digit() {
+            let len = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | '+' | '.')))
+                .unwrap_or(rest.len());
+            spans.push(Span::styled(
+                rest[..len].to_string(),
+                Style::default().fg(palette.accent_alt),
+            ));
+            len
+        } else if c.is_ascii_alphabetic() {
+            let len = rest
+                .find(|ch: char| !ch.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            spans.push(Span::styled(
+                rest[..len].to_string(),
+                Style::default()
+                    .fg(palette.system)
+                    .add_modifier(Modifier::ITALIC),
+            ));
+            len
+        } else {
+            let len = rest
+                .find(|ch: char| ch == '"' || ch == '-' || ch.is_ascii_alphanumeric())
+                .unwrap_or(rest.len())
+                .max(c.len_utf8());
+            spans.push(Span::styled(rest[..len].to_string(), punct));
+            len
+        };
+        rest = &rest[len..];
+    }
+    Line::from(spans)
+}
+
+/// Byte length of the JSON string literal at the start of `s`, quotes included.
+fn string_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, ch) in s.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keys_strings_and_literals_are_separate_spans() {
+        let palette = ThemePalette::dark();
+        let value = json!({"type": "user", "n": -1.5, "ok": true, "q": "say \"hi\": now"});
+        let lines = highlight_json(&value, palette);
+        let rendered: Vec<String> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(
+            rendered.join("\n"),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+
+        let quoted = lines
+            .iter()
+            .find(|l| l.spans.iter().any(|s| s.content == "\"q\""))
+            .unwrap();
+        let key = quoted.spans.iter().find(|s| s.content == "\"q\"").unwrap();
+        let val = quoted
+            .spans
+            .iter()
+            .find(|s| s.content == "\"say \\\"hi\\\": now\"")
+            .unwrap();
+        assert_eq!(key.style.fg, Some(palette.accent));
+        assert_eq!(val.style.fg, Some(palette.user));
+    }
+}
@@ -0,0 +1,318 @@
+//! Agent-specific rendering of tool calls in the conversation viewer.
+//!
+//! Connectors flatten tool calls into `[Tool: name - detail]` markers for
+//! search and keep the raw entry in `extra_json`. The viewer reads it back
+//! ([`crate::model::tool_calls`]):
+//! Claude `tool_use` blocks become collapsible panels, Codex shell calls become
+//! terminal blocks, and Gemini function calls show their arguments and result.
+//! File edits and writes from either agent expand into a colored unified diff.
+//! Agents without a renderer keep the flattened text.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde_json::Value;
+
+use crate::diff::{DiffOp, diff_lines, hunks};
+use crate::model::tool_calls::ToolCall;
+use crate::ui::components::theme::{ThemePalette, colors};
+
+/// Longest argument summary shown on a collapsed header
+const SUMMARY_CHARS: usize = 72;
+
+/// Whether a content line is a flattened tool marker that a rendered call replaces
+pub fn is_tool_marker(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("[Tool:") && line.ends_with(']')
+}
+
+/// Render one call. Panels and function calls show only a header line unless expanded.
+pub fn render_tool_call(
+    call: &ToolCall,
+    expanded: bool,
+    palette: ThemePalette,
+) -> Vec<Line<'static>> {
+    let frame = Style::default().fg(palette.hint);
+    let mut lines = Vec::new();
+    match call {
+        ToolCall::Panel { name, input } => {
+            lines.push(header(
+                expanded,
+                "🔧",
+                name,
+                &summarize(input),
+                None,
+                palette,
+            ));
+            if expanded {
+                if let Some(fields) = input.as_object() {
+                    for (key, value) in fields {
+                        let text = match value {
+                            Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        };
+                        for (i, row) in text.lines().enumerate() {
+                            lines.push(Line::from(vec![
+                                Span::styled("  │ ", frame),
+                                Span::styled(
+                                    if i == 0 {
+                                        format!("{key}: ")
+                                    } else {
+                                        " ".repeat(key.chars().count() + 2)
+                                    },
+                                    Style::default().fg(palette.tool),
+                                ),
+                                Span::styled(row.to_string(), Style::default().fg(palette.fg)),
+                            ]));
+                        }
+                    }
+                }
+                lines.push(Line::from(Span::styled("  └──", frame)));
+            }
+        }
+        ToolCall::Shell { command, workdir } => {
+            let mut top = vec![Span::styled("  ┌─ terminal", frame)];
+            if let Some(dir) = workdir {
+                top.push(Span::styled(format!(" · {dir}"), frame));
+            }
+            lines.push(Line::from(top));
+            let term = Style::default().bg(palette.surface);
+            for (i, row) in command.lines().enumerate() {
+                lines.push(Line::from(vec![
+                    Span::styled("  │ ", frame),
+                    Span::styled(
+                        if i == 0 { "$ " } else { "> " },
+                        term.fg(palette.user).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(row.to_string(), term.fg(palette.fg)),
+                ]));
+            }
+            lines.push(Line::from(Span::styled("  └──", frame)));
+        }
+        ToolCall::Function {
+            name,
+            args,
+            status,
+            result,
+        } => {
+            lines.push(header(
+                expanded,
+                "ƒ",
+                name,
+                &format!("({})", summarize(args)),
+                status.as_deref(),
+                palette,
+            ));
+            if expanded {
+                let pretty = serde_json::to_string_pretty(args).unwrap_or_default();
+                for row in pretty.lines() {
+                    lines.push(Line::from(vec![
+                        Span::styled("  │ ", frame),
+                        Span::styled(row.to_string(), Style::default().fg(palette.accent_alt)),
+                    ]));
+                }
+                if let Some(result) = result.as_deref().filter(|r| !r.trim().is_empty()) {
+                    lines.push(Line::from(Span::styled("  ├── result", frame)));
+                    for row in result.lines() {
+                        lines.push(Line::from(vec![
+                            Span::styled("  │ ", frame),
+                            Span::styled(row.to_string(), Style::default().fg(palette.fg)),
+                        ]));
+                    }
+                }
+                lines.push(Line::from(Span::styled("  └──", frame)));
+            }
+        }
+        ToolCall::Edit {
+            name,
+            path,
+            changes,
+            status,
+        } => {
+            let ops: Vec<Vec<(DiffOp, &str)>> = changes
+                .iter()
+                .map(|(old, new)| diff_lines(old, new))
+                .collect();
+            let count = |op: DiffOp| ops.iter().flatten().filter(|(o, _)| *o == op).count();
+            lines.push(header(
+                expanded,
+                "✎",
+                name,
+                &format!("{path} +{} −{}", count(DiffOp::Add), count(DiffOp::Remove)),
+                status.as_deref(),
+                palette,
+            ));
+            if expanded {
+                for change in &ops {
+                    for hunk in hunks(change) {
+                        lines.push(Line::from(vec![
+                            Span::styled("  │ ", frame),
+                            Span::styled(hunk.header, Style::default().fg(palette.accent_alt)),
+                        ]));
+                        for (op, text) in hunk.lines {
+                            let (mark, style) = match op {
+                                DiffOp::Keep => (" ", Style::default().fg(palette.hint)),
+                                DiffOp::Remove => ("-", Style::default().fg(colors::STATUS_ERROR)),
+                                DiffOp::Add => ("+", Style::default().fg(palette.user)),
+                            };
+                            lines.push(Line::from(vec![
+                                Span::styled("  │ ", frame),
+                                Span::styled(format!("{mark} {text}"), style),
+                            ]));
+                        }
+                    }
+                }
+                lines.push(Line::from(Span::styled("  └──", frame)));
+            }
+        }
+    }
+    lines
+}
+
+fn header(
+    expanded: bool,
+    icon: &str,
+    name: &str,
+    summary: &str,
+    status: Option<&str>,
+    palette: ThemePalette,
+) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(
+            format!("  {} {icon} ", if expanded { "▾" } else { "▸" }),
+            Style::default().fg(palette.hint),
+        ),
+        Span::styled(
+            name.to_string(),
+            Style::default()
+                .fg(palette.tool)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if !summary.is_empty() {
+        spans.push(Span::styled(
+            format!(" {summary}"),
+            Style::default().fg(palette.hint),
+        ));
+    }
+    if let Some(status) = status {
+        let (mark, color) = match status {
+            "success" => ("✓", palette.user),
+            "error" | "cancelled" => ("✗", palette.tool),
+            _ => ("·", palette.hint),
+        };
+        spans.push(Span::styled(
+            format!(" {mark} {status}"),
+            Style::default().fg(color),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// `key: value, ...` on one line, cut to [`SUMMARY_CHARS`]
+fn summarize(value: &Value) -> String {
+    let text = match value {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(k, v)| match v {
+                Value::String(s) => format!("{k}: {}", s.lines().next().unwrap_or("")),
+                other => format!("{k}: {other}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    if text.chars().count() > SUMMARY_CHARS {
+        let cut: String = text.chars().take(SUMMARY_CHARS - 1).collect();
+        format!("{cut}…")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::tool_calls::tool_calls;
+    use serde_json::json;
+
+    fn text(lines: &[Line]) -> String {
+        lines
+            .iter()
+            .map(|l| {
+                l.spans
+                    .iter()
+                    .map(|s| s.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn only_whole_lines_are_tool_markers() {
+        assert!(is_tool_marker("[Tool: Read - /src/main.rs]"));
+        assert!(!is_tool_marker("see [Tool: Read] above"));
+    }
+
+    #[test]
+    fn panels_collapse_to_a_header_and_shell_calls_render_as_terminal() {
+        let palette = ThemePalette::dark();
+        let panel = ToolCall::Panel {
+            name: "Edit".into(),
+            input: json!({"file_path": "/a.rs", "new_string": "fn a() {}\nfn b() {}"}),
+        };
+        let collapsed = render_tool_call(&panel, false, palette);
+        assert_eq!(collapsed.len(), 1);
+        assert!(text(&collapsed).contains("▸ 🔧 Edit file_path: /a.rs, new_string: fn a() {}"));
+        let expanded = text(&render_tool_call(&panel, true, palette));
+        assert!(expanded.contains("│ new_string: fn a() {}"));
+        assert!(expanded.contains("fn b() {}"));
+
+        let shell = ToolCall::Shell {
+            command: "cd src\nls".into(),
+            workdir: Some("/repo".into()),
+        };
+        let rendered = text(&render_tool_call(&shell, false, palette));
+        assert!(rendered.contains("terminal · /repo"));
+        assert!(rendered.contains("│ $ cd src"));
+        assert!(rendered.contains("│ > ls"));
+    }
+
+    #[test]
+    fn edits_and_writes_render_as_unified_diffs() {
+        let palette = ThemePalette::dark();
+        let claude = json!({"message": {"content": [
+            {"type": "tool_use", "name": "Edit", "input": {
+                "file_path": "/src/lib.rs",
+                "old_string": "fn a() {}\nfn b() {\n    1\n}\nfn c() {}",
+                "new_string": "fn a() {}\nfn b() {\n    2\n}\nfn c() {}"
+            }},
+            {"type": "tool_use", "name": "Write", "input": {
+                "file_path": "/src/new.rs", "content": "one\ntwo"
+            }}
+        ]}});
+        let calls = tool_calls("claude_code", &claude);
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(&calls[0], ToolCall::Edit { path, changes, .. }
+            if path == "/src/lib.rs" && changes.len() == 1));
+
+        let collapsed = text(&render_tool_call(&calls[0], false, palette));
+        assert!(collapsed.contains("✎ Edit /src/lib.rs +1 −1"));
+        let diff = text(&render_tool_call(&calls[0], true, palette));
+        assert!(diff.contains("│ @@ -1,5 +1,5 @@"));
+        assert!(diff.contains("│   fn b() {"));
+        assert!(diff.contains("│ -     1"));
+        assert!(diff.contains("│ +     2"));
+        assert!(!diff.contains("old_string"));
+
+        let write = text(&render_tool_call(&calls[1], true, palette));
+        assert!(write.contains("@@ -0,0 +1,2 @@"));
+        assert!(write.contains("│ + one"));
+
+        let gemini = json!({"toolCalls": [{"name": "replace", "status": "success",
+            "args": {"file_path": "/a.py", "old_string": "x = 1", "new_string": "x = 2"}}]});
+        assert!(matches!(&tool_calls("gemini", &gemini)[0],
+            ToolCall::Edit { status, .. } if status.as_deref() == Some("success")));
+    }
+}
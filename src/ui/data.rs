@@ -1,4 +1,6 @@
-use crate::model::types::{Conversation, Message, MessageRole, Workspace};
+use std::collections::HashMap;
+
+use crate::model::types::{Annotation, Conversation, Message, MessageRole, Workspace};
 use crate::storage::sqlite::SqliteStorage;
 use crate::ui::components::theme::ThemePalette;
 use anyhow::Result;
@@ -13,6 +15,8 @@ pub enum InputMode {
     PaneFilter,
     /// Inline find within the detail pane (local, non-indexed)
     DetailFind,
+    /// Tag name for the selected sessions (bulk actions)
+    Tag,
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +24,8 @@ pub struct ConversationView {
     pub convo: Conversation,
     pub messages: Vec<Message>,
     pub workspace: Option<Workspace>,
+    /// Personal notes (`cass note`) keyed by message id
+    pub notes: HashMap<i64, Vec<Annotation>>,
 }
 
 pub fn load_conversation(
@@ -66,10 +72,15 @@ pub fn load_conversation(
             display_name: row.get(4).ok().flatten(),
         });
         let messages = storage.fetch_messages(convo_id)?;
+        // Databases from before annotations existed have no table; show no notes
+        let notes = storage
+            .annotations_for_conversation(convo_id)
+            .unwrap_or_default();
         return Ok(Some(ConversationView {
             convo,
             messages,
             workspace,
+            notes,
         }));
     }
     Ok(None)
@@ -0,0 +1,203 @@
+//! Opening session files in the user's editor from the TUI.
+//!
+//! The command comes from `editor` under `[tui]` in `config.toml`, then
+//! `$VISUAL`, then `$EDITOR`, falling back to `vi`:
+//!
+//! ```toml
+//! [tui]
+//! editor = "code --wait --goto {path}:{line}"
+//! ```
+//!
+//! A command containing `{path}` is used as a template (`{line}` defaults to
+//! 1). Otherwise the line is passed the way the editor expects it: `--goto
+//! path:line` for VS Code and its forks, `path:line` for Sublime, Zed and
+//! Helix, and `+line path` for everything else (override the flag with
+//! `line_flag` or `$EDITOR_LINE_FLAG`).
+
+use std::io;
+use std::process::{Command, ExitStatus};
+
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::Terminal;
+use ratatui::backend::Backend;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TuiConfig {
+    editor: Option<String>,
+    line_flag: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    tui: TuiConfig,
+}
+
+impl TuiConfig {
+    /// The `[tui]` section; missing or invalid means "use the environment".
+    fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => config.tui,
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring [tui] editor: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// A resolved editor invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorCommand {
+    program: String,
+    args: Vec<String>,
+    line_flag: Option<String>,
+}
+
+impl EditorCommand {
+    /// Resolve from `config.toml`, `$VISUAL` and `$EDITOR`.
+    pub fn resolve() -> Self {
+        let config = TuiConfig::load();
+        let command = config
+            .editor
+            .filter(|e| !e.trim().is_empty())
+            .or_else(|| {
+                std::env::var("VISUAL")
+                    .ok()
+                    .filter(|e| !e.trim().is_empty())
+            })
+            .or_else(|| {
+                std::env::var("EDITOR")
+                    .ok()
+                    .filter(|e| !e.trim().is_empty())
+            })
+            .unwrap_or_else(|| "vi".to_string());
+        let line_flag = config
+            .line_flag
+            .or_else(|| std::env::var("EDITOR_LINE_FLAG").ok());
+        Self::parse(&command, line_flag)
+    }
+
+    pub fn parse(command: &str, line_flag: Option<String>) -> Self {
+        let trimmed = command.trim();
+        let mut parts = match shell_words::split(trimmed) {
+            Ok(parts) if !parts.is_empty() => parts,
+            _ if trimmed.is_empty() => vec!["vi".to_string()],
+            _ => vec![trimmed.to_string()],
+        };
+        let program = parts.remove(0);
+        Self {
+            program,
+            args: parts,
+            line_flag,
+        }
+    }
+
+    /// Executable name without its directory, for status messages
+    pub fn name(&self) -> &str {
+        std::path::Path::new(&self.program)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&self.program)
+    }
+
+    /// Arguments that open `path`, at `line` when known
+    pub fn args_for(&self, path: &str, line: Option<usize>) -> Vec<String> {
+        if self.args.iter().any(|a| a.contains("{path}")) {
+            let line = line.unwrap_or(1).to_string();
+            return self
+                .args
+                .iter()
+                .map(|a| a.replace("{path}", path).replace("{line}", &line))
+                .collect();
+        }
+        let mut args = self.args.clone();
+        match (line, self.line_flag.as_deref()) {
+            (None, _) => args.push(path.to_string()),
+            (Some(line), Some(flag)) => {
+                args.push(format!("{flag}{line}"));
+                args.push(path.to_string());
+            }
+            (Some(line), None) => match self.name() {
+                "code" | "code-insiders" | "codium" | "cursor" | "windsurf" => {
+                    args.push("--goto".to_string());
+                    args.push(format!("{path}:{line}"));
+                }
+                "subl" | "zed" | "hx" | "helix" => args.push(format!("{path}:{line}")),
+                _ => {
+                    args.push(format!("+{line}"));
+                    args.push(path.to_string());
+                }
+            },
+        }
+        args
+    }
+
+    pub fn command(&self, path: &str, line: Option<usize>) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(self.args_for(path, line));
+        cmd
+    }
+}
+
+/// Run `cmd` with the terminal handed back to it, then restore the TUI.
+///
+/// Raw mode, the alternate screen and mouse capture are released first so
+/// terminal editors draw normally; afterwards the screen is cleared so the
+/// next frame repaints everything the editor overwrote.
+pub fn run_suspended<B: Backend>(
+    terminal: &mut Terminal<B>,
+    cmd: &mut Command,
+) -> io::Result<ExitStatus> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor().ok();
+
+    let result = cmd.status();
+
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    enable_raw_mode()?;
+    terminal.clear().ok();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_is_passed_the_way_each_editor_expects() {
+        let vim = EditorCommand::parse("nvim", None);
+        assert_eq!(vim.args_for("/s.jsonl", Some(7)), ["+7", "/s.jsonl"]);
+        assert_eq!(vim.args_for("/s.jsonl", None), ["/s.jsonl"]);
+
+        let code = EditorCommand::parse("/usr/bin/code --wait", None);
+        assert_eq!(
+            code.args_for("/s.jsonl", Some(7)),
+            ["--wait", "--goto", "/s.jsonl:7"]
+        );
+
+        let flagged = EditorCommand::parse("kak", Some("+".into()));
+        assert_eq!(flagged.args_for("/s.jsonl", Some(3)), ["+3", "/s.jsonl"]);
+
+        let template = EditorCommand::parse("emacsclient -n +{line} '{path}'", None);
+        assert_eq!(
+            template.args_for("/a b.jsonl", None),
+            ["-n", "+1", "/a b.jsonl"]
+        );
+    }
+}
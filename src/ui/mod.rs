@@ -1,6 +1,10 @@
 //! TUI entrypoint and layout.
+pub mod clipboard;
 pub mod components;
 pub mod data;
+pub mod editor;
+pub mod pick;
+pub mod resume;
 pub mod shortcuts;
 pub mod time_parser;
 pub mod tui;
@@ -0,0 +1,334 @@
+//! `cass pick`: an fzf-style fuzzy picker over indexed conversations.
+//!
+//! The picker draws on stderr so that stdout carries only the selection,
+//! which is what makes `$EDITOR $(cass pick)` work. Candidates match on
+//! title, workspace and agent: every space-separated term must appear as a
+//! subsequence, and runs of consecutive characters and matches at word
+//! starts rank higher. Ties keep the most recent session first.
+//!
+//! The TUI shows the same [`Picker`] as an overlay (Ctrl+K) and opens the
+//! pick in the editor.
+
+use std::io;
+
+use chrono::{Local, TimeZone};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::prelude::*;
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+
+use crate::storage::sqlite::PickCandidate;
+use crate::ui::components::theme::ThemePalette;
+
+/// Score of `term` as a case-insensitive subsequence of `text`, `None` when
+/// it doesn't match. Each alignment starting at an occurrence of the first
+/// character is tried and the best one wins.
+pub fn fuzzy_score(term: &str, text: &str) -> Option<i64> {
+    let term: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let first = *term.first()?;
+    (0..text.len())
+        .filter(|&i| text[i] == first)
+        .filter_map(|start| score_from(&term, &text, start))
+        .max()
+}
+
+fn score_from(term: &[char], text: &[char], start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut prev: Option<usize> = None;
+    let mut pos = start;
+    for &q in term {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        match prev {
+            Some(p) if p + 1 == found => score += 5,
+            Some(p) => score -= (found - p - 1).min(3) as i64,
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        prev = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// What a candidate is matched against
+fn haystack(item: &PickCandidate) -> String {
+    format!(
+        "{} {} {}",
+        title(item),
+        item.workspace.as_deref().unwrap_or_default(),
+        item.agent
+    )
+}
+
+fn title(item: &PickCandidate) -> &str {
+    item.title
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(&item.source_path)
+}
+
+/// Indices of the items matching every term of `query`, best first. An
+/// empty query keeps all items in their original order.
+pub fn rank(query: &str, items: &[PickCandidate]) -> Vec<usize> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let text = haystack(item);
+            terms
+                .iter()
+                .map(|t| fuzzy_score(t, &text))
+                .sum::<Option<i64>>()
+                .map(|score| (score, i))
+        })
+        .collect();
+    // Stable, so equal scores stay newest first
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Query, matches and selection of one picker session; shared by `cass pick`
+/// and the TUI's quick-pick overlay (Ctrl+K).
+pub struct Picker {
+    items: Vec<PickCandidate>,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Picker {
+    pub fn new(items: Vec<PickCandidate>, query: &str) -> Self {
+        let mut picker = Self {
+            items,
+            query: query.to_string(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        picker.refilter();
+        picker
+    }
+
+    /// Candidate at `index`, as returned by [`Picker::handle_key`]
+    pub fn item(&self, index: usize) -> Option<&PickCandidate> {
+        self.items.get(index)
+    }
+
+    fn refilter(&mut self) {
+        self.matches = rank(&self.query, &self.items);
+        self.selected = 0;
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Apply a key; `Some(choice)` ends the picker
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Option<usize>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Some(None),
+            KeyCode::Char('c' | 'g') if ctrl => return Some(None),
+            KeyCode::Enter => {
+                return self
+                    .matches
+                    .get(self.selected)
+                    .map(|&i| Some(i))
+                    .or(Some(None));
+            }
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-10),
+            KeyCode::PageDown => self.move_by(10),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Char('w') if ctrl => {
+                let kept = self.query.trim_end().rfind(' ').map_or(0, |i| i + 1);
+                self.query.truncate(kept);
+                self.refilter();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Prompt, matches and key hints, filling `area`
+    pub fn draw(&self, f: &mut Frame, area: Rect, palette: ThemePalette) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(area);
+
+        let prompt = Line::from(vec![
+            Span::styled(
+                "> ",
+                Style::default()
+                    .fg(palette.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(self.query.clone(), Style::default().fg(palette.fg)),
+            Span::styled(
+                format!("  {}/{}", self.matches.len(), self.items.len()),
+                Style::default().fg(palette.hint),
+            ),
+        ]);
+        f.render_widget(Paragraph::new(prompt), rows[0]);
+        f.set_cursor_position(Position::new(
+            rows[0].x + 2 + self.query.chars().count() as u16,
+            rows[0].y,
+        ));
+
+        let list_items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&i| {
+                let item = &self.items[i];
+                let date = item
+                    .started_at
+                    .and_then(|ms| Local.timestamp_millis_opt(ms).single())
+                    .map(|t| t.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| " ".repeat(10));
+                let workspace = item
+                    .workspace
+                    .as_deref()
+                    .and_then(|w| w.rsplit(['/', '\\']).find(|s| !s.is_empty()))
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{date} "), Style::default().fg(palette.hint)),
+                    Span::styled(
+                        format!("{:<12} ", item.agent),
+                        Style::default().fg(palette.agent),
+                    ),
+                    Span::styled(title(item).to_string(), Style::default().fg(palette.fg)),
+                    Span::styled(format!("  {workspace}"), Style::default().fg(palette.hint)),
+                ]))
+            })
+            .collect();
+        let list = List::new(list_items).highlight_style(
+            Style::default()
+                .bg(palette.accent)
+                .fg(palette.bg)
+                .add_modifier(Modifier::BOLD),
+        );
+        let mut state = ListState::default();
+        state.select((!self.matches.is_empty()).then_some(self.selected));
+        f.render_stateful_widget(list, rows[1], &mut state);
+
+        f.render_widget(
+            Paragraph::new(Span::styled(
+                "type to filter · ↑↓ move · Enter pick · Ctrl+U clear · Esc cancel",
+                Style::default()
+                    .fg(palette.hint)
+                    .add_modifier(Modifier::ITALIC),
+            )),
+            rows[2],
+        );
+    }
+}
+
+/// Run the interactive picker on stderr; returns the chosen item, or `None`
+/// when cancelled.
+pub fn run_picker(items: Vec<PickCandidate>, query: &str) -> io::Result<Option<PickCandidate>> {
+    enable_raw_mode()?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stderr())).and_then(|mut terminal| {
+        let mut picker = Picker::new(items, query);
+        let palette = ThemePalette::dark();
+        loop {
+            terminal.draw(|f| picker.draw(f, f.area(), palette))?;
+            if let Event::Key(key) = event::read()?
+                && let Some(choice) = picker.handle_key(key)
+            {
+                return Ok(choice.and_then(|i| picker.items.get(i).cloned()));
+            }
+        }
+    });
+    disable_raw_mode().ok();
+    execute!(io::stderr(), LeaveAlternateScreen).ok();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(title: &str, workspace: &str) -> PickCandidate {
+        PickCandidate {
+            conversation_id: 1,
+            agent: "codex".into(),
+            title: Some(title.into()),
+            workspace: Some(workspace.into()),
+            source_path: format!("/logs/{title}.jsonl"),
+            started_at: None,
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        assert!(fuzzy_score("xyz", "fix the parser").is_none());
+        let run = fuzzy_score("pars", "fix the parser").unwrap();
+        let scattered = fuzzy_score("pars", "fix pending tax returns").unwrap();
+        assert!(run > scattered);
+        assert_eq!(fuzzy_score("FIX", "fix"), fuzzy_score("fix", "FIX"));
+    }
+
+    #[test]
+    fn rank_requires_every_term_and_keeps_recency_on_ties() {
+        let items = vec![
+            candidate("refactor parser", "/src/app"),
+            candidate("parser bug", "/src/api"),
+            candidate("write docs", "/src/app"),
+        ];
+        assert_eq!(rank("", &items), [0, 1, 2]);
+        assert_eq!(rank("parser", &items), [0, 1]);
+        assert_eq!(rank("parser api", &items), [1]);
+        assert!(rank("nothing", &items).is_empty());
+    }
+
+    #[test]
+    fn picker_filters_as_you_type_and_returns_the_pick() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut picker = Picker::new(
+            vec![
+                candidate("refactor parser", "/src/app"),
+                candidate("parser bug", "/src/api"),
+            ],
+            "",
+        );
+        for c in "api".chars() {
+            assert_eq!(picker.handle_key(key(KeyCode::Char(c))), None);
+        }
+        let choice = picker.handle_key(key(KeyCode::Enter)).unwrap();
+        assert_eq!(
+            choice
+                .and_then(|i| picker.item(i))
+                .map(|c| c.source_path.as_str()),
+            Some("/logs/parser bug.jsonl")
+        );
+        assert_eq!(picker.handle_key(key(KeyCode::Esc)), Some(None));
+    }
+}
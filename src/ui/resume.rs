@@ -0,0 +1,204 @@
+//! Relaunching an agent on a stored conversation from the TUI.
+//!
+//! The command per agent comes from `[tui.resume]` in `config.toml`, falling
+//! back to the built-in ones for agents whose CLI can resume a session:
+//!
+//! ```toml
+//! [tui.resume]
+//! claude_code = "claude --resume {session_id}"
+//! codex = "codex resume {session_id}"
+//! gemini = ""   # an empty command disables resuming for that agent
+//! ```
+//!
+//! `{session_id}`, `{path}` (the session file) and `{workspace}` are
+//! substituted per argument after splitting, so paths with spaces stay whole.
+//! The agent runs in the conversation's workspace when it still exists.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::hooks::HooksConfig;
+use crate::sources::provenance::LOCAL_SOURCE_ID;
+use crate::ui::data::ConversationView;
+
+#[derive(Debug, Default, Deserialize)]
+struct TuiConfig {
+    #[serde(default)]
+    resume: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    tui: TuiConfig,
+}
+
+/// Built-in resume commands, keyed by agent slug
+fn default_template(agent: &str) -> Option<&'static str> {
+    match agent {
+        "claude_code" => Some("claude --resume {session_id}"),
+        "codex" => Some("codex resume {session_id}"),
+        _ => None,
+    }
+}
+
+/// The agent's own id for a conversation: `sessionId` from the stored
+/// metadata, else a UUID at the end of the session file name
+/// (`<uuid>.jsonl`, `rollout-<timestamp>-<uuid>.jsonl`).
+pub fn session_id(metadata: &serde_json::Value, source_path: &str) -> Option<String> {
+    if let Some(id) = ["sessionId", "session_id"]
+        .iter()
+        .find_map(|k| metadata.get(k).and_then(|v| v.as_str()))
+        .filter(|id| !id.is_empty())
+    {
+        return Some(id.to_string());
+    }
+    let stem = Path::new(source_path).file_stem()?.to_str()?;
+    let tail = stem.get(stem.len().checked_sub(36)?..)?;
+    let is_uuid = tail.split('-').map(str::len).eq([8, 4, 4, 4, 12])
+        && tail.chars().all(|c| c == '-' || c.is_ascii_hexdigit());
+    is_uuid.then(|| tail.to_string())
+}
+
+/// Resolved `[tui.resume]` commands
+#[derive(Debug, Clone, Default)]
+pub struct ResumeCommands {
+    overrides: HashMap<String, String>,
+}
+
+impl ResumeCommands {
+    /// Load `[tui.resume]`; a missing or invalid config leaves the defaults.
+    pub fn load() -> Self {
+        let Some(path) = HooksConfig::config_path() else {
+            return Self::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str::<ConfigFile>(&content) {
+            Ok(config) => Self::new(config.tui.resume),
+            Err(e) => {
+                warn!(path = %path.display(), "ignoring [tui.resume]: invalid config.toml: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn new(overrides: HashMap<String, String>) -> Self {
+        Self { overrides }
+    }
+
+    /// The command template for `agent`, `None` when it can't be resumed
+    pub fn template(&self, agent: &str) -> Option<&str> {
+        self.overrides
+            .get(agent)
+            .map(String::as_str)
+            .or_else(|| default_template(agent))
+            .filter(|t| !t.trim().is_empty())
+    }
+
+    /// Program and arguments that resume `view`
+    pub fn argv(&self, view: &ConversationView) -> Result<Vec<String>, String> {
+        let convo = &view.convo;
+        let agent = convo.agent_slug.as_str();
+        if convo.source_id != LOCAL_SOURCE_ID {
+            let host = convo.origin_host.as_deref().unwrap_or(&convo.source_id);
+            return Err(format!("Session was recorded on {host}; resume it there"));
+        }
+        let template = self.template(agent).ok_or_else(|| {
+            format!("No resume command for {agent}; set one under [tui.resume] in config.toml")
+        })?;
+        let source_path = convo.source_path.to_string_lossy();
+        let session = session_id(&convo.metadata_json, &source_path);
+        if template.contains("{session_id}") && session.is_none() {
+            return Err(format!(
+                "No {agent} session id recorded for this conversation"
+            ));
+        }
+        let workspace = workspace_dir(view).unwrap_or_default();
+        let argv: Vec<String> = shell_words::split(template)
+            .map_err(|e| format!("Invalid resume command for {agent}: {e}"))?
+            .into_iter()
+            .map(|arg| {
+                arg.replace("{session_id}", session.as_deref().unwrap_or_default())
+                    .replace("{path}", &source_path)
+                    .replace("{workspace}", &workspace)
+            })
+            .collect();
+        if argv.is_empty() {
+            return Err(format!("Empty resume command for {agent}"));
+        }
+        Ok(argv)
+    }
+
+    /// The command to run, in the conversation's workspace when it exists
+    pub fn command(&self, view: &ConversationView) -> Result<Command, String> {
+        let argv = self.argv(view)?;
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        if let Some(dir) = workspace_dir(view).filter(|d| Path::new(d).is_dir()) {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd)
+    }
+}
+
+fn workspace_dir(view: &ConversationView) -> Option<String> {
+    view.workspace
+        .as_ref()
+        .map(|w| w.path.to_string_lossy().into_owned())
+        .or_else(|| {
+            view.convo
+                .workspace
+                .as_ref()
+                .map(|w| w.to_string_lossy().into_owned())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn session_id_prefers_metadata_then_file_name() {
+        assert_eq!(
+            session_id(&json!({"sessionId": "abc"}), "/x/whatever.jsonl").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            session_id(
+                &json!({}),
+                "/s/2025/11/20/rollout-2025-11-20T10-00-00-0199a2b3-c4d5-7e6f-8a9b-0c1d2e3f4a5b.jsonl"
+            )
+            .as_deref(),
+            Some("0199a2b3-c4d5-7e6f-8a9b-0c1d2e3f4a5b")
+        );
+        assert_eq!(session_id(&json!({}), "/s/rollout-1.jsonl"), None);
+    }
+
+    #[test]
+    fn templates_fall_back_to_defaults_and_can_be_disabled() {
+        let commands = ResumeCommands::new(HashMap::from([
+            ("codex".to_string(), String::new()),
+            (
+                "gemini".to_string(),
+                "gemini --resume {session_id}".to_string(),
+            ),
+        ]));
+        assert_eq!(
+            commands.template("claude_code"),
+            Some("claude --resume {session_id}")
+        );
+        assert_eq!(commands.template("codex"), None);
+        assert_eq!(
+            commands.template("gemini"),
+            Some("gemini --resume {session_id}")
+        );
+        assert_eq!(commands.template("aider"), None);
+    }
+}
@@ -38,3 +38,200 @@ pub const TAB_FOCUS: &str = "Tab";
 pub const VIM_NAV: &str = "Alt+h/j/k/l";
 pub const JUMP_TOP: &str = "Home";
 pub const JUMP_BOTTOM: &str = "End";
+
+/// Where a key binding applies; the help overlay lists the current one first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyContext {
+    /// Anywhere in the main view
+    Global,
+    /// Typing a query (before any results are showing)
+    Search,
+    /// The results list
+    Results,
+    /// The detail pane and the full-screen detail view
+    Viewer,
+}
+
+impl KeyContext {
+    pub const ALL: [KeyContext; 4] = [Self::Global, Self::Search, Self::Results, Self::Viewer];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Global => "Global",
+            Self::Search => "Search",
+            Self::Results => "Results",
+            Self::Viewer => "Viewer",
+        }
+    }
+}
+
+/// One row of the help overlay's keymap
+#[derive(Clone, Copy, Debug)]
+pub struct Binding {
+    pub context: KeyContext,
+    pub keys: &'static str,
+    pub action: &'static str,
+}
+
+const fn bind(context: KeyContext, keys: &'static str, action: &'static str) -> Binding {
+    Binding {
+        context,
+        keys,
+        action,
+    }
+}
+
+/// The keymap shown by the help overlay, grouped by [`KeyContext`].
+/// Keep in sync with the key handling in `tui.rs`.
+pub const BINDINGS: &[Binding] = &[
+    // Global
+    bind(KeyContext::Global, "F1 / ?", "Toggle this help"),
+    bind(KeyContext::Global, "Ctrl+P / Alt+P", "Command palette"),
+    bind(KeyContext::Global, QUIT, "Quit (or back from detail)"),
+    bind(KeyContext::Global, THEME, "Theme dark/light"),
+    bind(KeyContext::Global, "Ctrl+B", "Border style"),
+    bind(KeyContext::Global, FILTER_AGENT, "Filter by agent"),
+    bind(KeyContext::Global, FILTER_WORKSPACE, "Filter by workspace"),
+    bind(KeyContext::Global, "F5 / F6", "Filter from / to date"),
+    bind(KeyContext::Global, SCOPE_AGENT, "Scope to the active agent"),
+    bind(KeyContext::Global, SCOPE_WORKSPACE, "Clear scope"),
+    bind(
+        KeyContext::Global,
+        CYCLE_TIME_PRESETS,
+        "Cycle 24h/7d/30d/all",
+    ),
+    bind(KeyContext::Global, CLEAR_FILTERS, "Clear all filters"),
+    bind(KeyContext::Global, "F11", "Cycle source filter"),
+    bind(KeyContext::Global, "Shift+F11", "Source filter menu"),
+    bind(
+        KeyContext::Global,
+        SEARCH_MODE,
+        "Search mode lexical/semantic/hybrid",
+    ),
+    bind(KeyContext::Global, MATCH_MODE, "Match mode prefix/standard"),
+    bind(KeyContext::Global, RANKING, "Cycle ranking"),
+    bind(
+        KeyContext::Global,
+        CONTEXT_WINDOW,
+        "Context window S/M/L/XL",
+    ),
+    bind(
+        KeyContext::Global,
+        "Alt+. / Alt+,",
+        "More / less snippet context (remembered)",
+    ),
+    bind(
+        KeyContext::Global,
+        "Shift+= / Alt+-",
+        "More / fewer items per pane",
+    ),
+    bind(
+        KeyContext::Global,
+        "Ctrl+1…9",
+        "Save filters to a view slot",
+    ),
+    bind(KeyContext::Global, "Shift+1…9", "Load a view slot"),
+    bind(KeyContext::Global, "Ctrl+T", "Calendar"),
+    bind(KeyContext::Global, "Ctrl+G", "Usage dashboard"),
+    bind(KeyContext::Global, "Ctrl+K", "Quick pick"),
+    bind(KeyContext::Global, "Ctrl+L", "Switch profile"),
+    bind(KeyContext::Global, REFRESH, "Re-index in the background"),
+    bind(KeyContext::Global, RESET_STATE, "Reset UI state"),
+    // Search
+    bind(KeyContext::Search, "type", "Live search"),
+    bind(KeyContext::Search, FOCUS_QUERY, "Focus the query"),
+    bind(KeyContext::Search, HISTORY_CYCLE, "Cycle query history"),
+    bind(
+        KeyContext::Search,
+        "Backspace",
+        "Remove the last filter chip (empty query)",
+    ),
+    bind(
+        KeyContext::Search,
+        "Enter",
+        "Edit the last filter chip (empty query)",
+    ),
+    // Results
+    bind(KeyContext::Results, "↑↓ / PgUp PgDn", "Move / page"),
+    bind(KeyContext::Results, "← →", "Switch pane"),
+    bind(KeyContext::Results, VIM_NAV, "Vim-style navigation"),
+    bind(
+        KeyContext::Results,
+        "Home / End",
+        "First / last item (also Alt+g/G)",
+    ),
+    bind(KeyContext::Results, DETAIL_OPEN, "Open full-screen detail"),
+    bind(KeyContext::Results, TAB_FOCUS, "Focus the detail pane"),
+    bind(KeyContext::Results, "[ / ]", "Cycle detail tabs"),
+    bind(KeyContext::Results, TOGGLE_SELECT, "Toggle selection"),
+    bind(
+        KeyContext::Results,
+        "Space / *",
+        "Toggle / all (after arrowing)",
+    ),
+    bind(KeyContext::Results, "Ctrl+A", "Select all visible"),
+    bind(
+        KeyContext::Results,
+        BULK_MENU,
+        "Bulk actions on the selection",
+    ),
+    bind(
+        KeyContext::Results,
+        "Ctrl+Z",
+        "Undo the last delete or removal",
+    ),
+    bind(KeyContext::Results, "Ctrl+Enter", "Queue for opening"),
+    bind(
+        KeyContext::Results,
+        "Ctrl+O",
+        "Open all queued; with none queued, go back",
+    ),
+    bind(KeyContext::Results, EDITOR, "Open in editor at the match"),
+    bind(KeyContext::Results, "Ctrl+Y", "Copy the path"),
+    bind(KeyContext::Results, "Ctrl+E", "Export"),
+    bind(
+        KeyContext::Results,
+        "Ctrl+X",
+        "Resume the session in its agent",
+    ),
+    bind(KeyContext::Results, "Ctrl+Space", "Peek XL context"),
+    // Viewer
+    bind(KeyContext::Viewer, TAB_FOCUS, "Back to results"),
+    bind(KeyContext::Viewer, PANE_FILTER, "Find in the conversation"),
+    bind(KeyContext::Viewer, "n / N", "Next / previous match"),
+    bind(KeyContext::Viewer, COPY, "Copy snippet or message"),
+    bind(KeyContext::Viewer, "Y", "Copy the whole conversation"),
+    bind(KeyContext::Viewer, "e / o", "Open in editor"),
+    bind(
+        KeyContext::Viewer,
+        "c / p / s",
+        "Copy content / path / snippet",
+    ),
+    bind(KeyContext::Viewer, "b / *", "Bookmark / star the session"),
+    bind(KeyContext::Viewer, "+ / -", "Rate the session"),
+    bind(
+        KeyContext::Viewer,
+        "u",
+        "Undo the last bookmark/rating change",
+    ),
+    bind(KeyContext::Viewer, "t", "Expand / collapse tool panels"),
+    bind(KeyContext::Viewer, "M", "Markdown rendering on/off"),
+    bind(
+        KeyContext::Viewer,
+        "v",
+        "View read-only in nano (full-screen)",
+    ),
+    bind(KeyContext::Viewer, "x", "Resume the session (full-screen)"),
+    bind(
+        KeyContext::Viewer,
+        "S",
+        "Similar sessions (full-screen; Ctrl+O comes back)",
+    ),
+    bind(
+        KeyContext::Viewer,
+        "Backspace / Ctrl+O",
+        "Back to the previous results and conversation",
+    ),
+    bind(KeyContext::Viewer, "r", "Raw JSON of the matched message"),
+    bind(KeyContext::Viewer, DETAIL_CLOSE, "Close"),
+];
@@ -17,7 +17,7 @@ use ratatui::widgets::{
     Block, BorderType, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 use std::path::Path;
 use std::process::Command as StdCommand;
@@ -26,20 +26,34 @@ use syntect::easy::HighlightLines;
 use syntect::highlighting::{Theme, ThemeSet};
 use syntect::parsing::SyntaxSet;
 
+use crate::bookmarks::{Bookmark, BookmarkStore, UndoAction};
 use crate::default_data_dir;
-use crate::model::types::MessageRole;
+use crate::export::{ExportOptions, export_results, resolve_message_locations};
+use crate::model::types::{Message, MessageRole};
 use crate::search::model_manager::{SemanticAvailability, load_semantic_context};
 use crate::search::query::{
     CacheStats, QuerySuggestion, SearchClient, SearchFilters, SearchHit, SearchMode,
 };
 use crate::search::tantivy::index_dir;
+use crate::ui::clipboard;
+use crate::ui::components::calendar::{self, CalendarState};
+use crate::ui::components::dashboard::{self, DashboardData, DashboardState};
+use crate::ui::components::export_modal::{
+    self, ExportDestination, ExportField, ExportModalState, ExportScope,
+};
 use crate::ui::components::help_strip;
 use crate::ui::components::palette::{self, PaletteAction, PaletteState};
 use crate::ui::components::pills::{self, Pill};
-use crate::ui::components::theme::ThemePalette;
+use crate::ui::components::raw_view;
+use crate::ui::components::theme::{ThemePalette, kbd_style};
+use crate::ui::components::toast::{Toast, ToastManager, render_toasts};
+use crate::ui::components::tool_panels;
 use crate::ui::components::widgets::search_bar;
 use crate::ui::data::{ConversationView, InputMode, load_conversation, role_style};
-use crate::ui::shortcuts;
+use crate::ui::editor::{self, EditorCommand};
+use crate::ui::pick::Picker;
+use crate::ui::resume::ResumeCommands;
+use crate::ui::shortcuts::{self, KeyContext};
 use crate::update_check::{
     UpdateInfo, open_in_browser, run_self_update, skip_version, spawn_update_check,
 };
@@ -66,14 +80,167 @@ pub fn format_time_short(ms: i64) -> String {
         .unwrap_or_else(|| "?".to_string())
 }
 
-fn split_editor_command(editor: &str) -> (String, Vec<String>) {
-    let trimmed = editor.trim();
-    if trimmed.is_empty() {
-        return ("vi".to_string(), Vec::new());
+/// File and line to open for `hit`: the matched message's own provenance
+/// (merged source file, real line offset) when its conversation is loaded,
+/// otherwise the conversation file and message number.
+fn editor_target(
+    hit: &SearchHit,
+    detail: Option<&(String, ConversationView)>,
+) -> (String, Option<usize>) {
+    let message = detail
+        .filter(|(path, _)| *path == hit.source_path)
+        .zip(hit.line_number)
+        .and_then(|((_, view), ln)| view.messages.iter().find(|m| m.idx == ln as i64 - 1));
+    match message {
+        Some(m) if m.source_path.is_some() || m.source_line.is_some() => {
+            let (path, line) = m.location(&hit.source_path);
+            (path.to_string(), line.map(|l| l as usize))
+        }
+        _ => (hit.source_path.clone(), hit.line_number),
+    }
+}
+
+/// Load whatever the calendar's current view is missing.
+fn refresh_calendar(
+    calendar: &mut CalendarState,
+    db: Option<&crate::storage::sqlite::SqliteStorage>,
+) {
+    let Some(db) = db else {
+        return;
+    };
+    if let Some((from, to)) = calendar.activity_range() {
+        match db.daily_activity(from, to) {
+            Ok(days) => calendar.set_activity(days),
+            Err(e) => tracing::warn!("calendar activity query failed: {e}"),
+        }
+    }
+    if let Some((from, to)) = calendar.day_range() {
+        match db.conversations_started_between(from, to) {
+            Ok(entries) => calendar.set_entries(entries),
+            Err(e) => tracing::warn!("calendar sessions query failed: {e}"),
+        }
+    }
+}
+
+/// `usd_per_million_tokens` under `[tui]` in `config.toml`, for dashboard cost estimates.
+fn token_price() -> Option<f64> {
+    #[derive(Default, Deserialize)]
+    struct TuiPricing {
+        usd_per_million_tokens: Option<f64>,
+    }
+    #[derive(Default, Deserialize)]
+    struct ConfigFile {
+        #[serde(default)]
+        tui: TuiPricing,
+    }
+    let path = crate::hooks::HooksConfig::config_path()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    toml::from_str::<ConfigFile>(&content)
+        .ok()?
+        .tui
+        .usd_per_million_tokens
+}
+
+/// Re-run the dashboard queries; failures are shown in the dashboard itself.
+fn load_dashboard(
+    dashboard: &mut DashboardState,
+    db: Option<&crate::storage::sqlite::SqliteStorage>,
+) {
+    use chrono::TimeZone;
+
+    let Some(db) = db else {
+        dashboard.error = Some("no database; run `cass index --full` first".to_string());
+        return;
+    };
+    let today = chrono::Local::now().date_naive();
+    let since = today - chrono::Days::new(dashboard::SPARKLINE_DAYS - 1);
+    let since_ms = chrono::Local
+        .from_local_datetime(&since.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map_or(0, |t| t.timestamp_millis());
+    let loaded = db.agent_usage().and_then(|agents| {
+        Ok(DashboardData {
+            agents,
+            workspaces: db.top_workspaces(8)?,
+            daily_messages: DashboardData::fill_days(
+                &db.daily_messages(since_ms, i64::MAX)?,
+                today,
+            ),
+            usd_per_million_tokens: token_price(),
+        })
+    });
+    match loaded {
+        Ok(data) => {
+            dashboard.data = data;
+            dashboard.error = None;
+        }
+        Err(e) => dashboard.error = Some(e.to_string()),
+    }
+}
+
+/// Sessions offered by the quick-pick overlay, like `cass pick`'s default
+const QUICK_PICK_LIMIT: usize = 5000;
+
+/// Start a quick-pick over the most recent sessions, or say why it can't.
+fn open_quick_pick(
+    db: Option<&crate::storage::sqlite::SqliteStorage>,
+) -> std::result::Result<Picker, String> {
+    let db = db.ok_or("No database; run `cass index --full` first")?;
+    let items = db
+        .pick_candidates(&[], QUICK_PICK_LIMIT)
+        .map_err(|e| format!("Quick pick failed: {e}"))?;
+    if items.is_empty() {
+        return Err("No sessions to pick from".to_string());
+    }
+    Ok(Picker::new(items, ""))
+}
+
+/// Open `path` in the editor with the TUI suspended; returns the status line.
+fn open_in_editor<B: Backend>(
+    terminal: &mut Terminal<B>,
+    editor: &EditorCommand,
+    path: &str,
+    line: Option<usize>,
+) -> String {
+    match editor::run_suspended(terminal, &mut editor.command(path, line)) {
+        Ok(status) if status.success() => match line {
+            Some(line) => format!("Opened {path}:{line} in {}", editor.name()),
+            None => format!("Opened {path} in {}", editor.name()),
+        },
+        Ok(status) => format!("✗ {} exited with {status}", editor.name()),
+        Err(e) => format!("✗ Failed to launch {}: {e}", editor.name()),
     }
-    match shell_words::split(trimmed) {
-        Ok(parts) if !parts.is_empty() => (parts[0].clone(), parts[1..].to_vec()),
-        _ => (trimmed.to_string(), Vec::new()),
+}
+
+/// Relaunch the agent on `hit`'s conversation with the TUI suspended; returns
+/// the status line. Uses the loaded detail when it is this conversation.
+fn resume_session<B: Backend>(
+    terminal: &mut Terminal<B>,
+    commands: &ResumeCommands,
+    hit: &SearchHit,
+    detail: Option<&(String, ConversationView)>,
+    db: Option<&crate::storage::sqlite::SqliteStorage>,
+) -> String {
+    let loaded;
+    let view = match detail.filter(|(path, _)| *path == hit.source_path) {
+        Some((_, view)) => view,
+        None => match db.and_then(|db| load_conversation(db, &hit.source_path).ok().flatten()) {
+            Some(view) => {
+                loaded = view;
+                &loaded
+            }
+            None => return "✗ Conversation not found in the index".to_string(),
+        },
+    };
+    let mut cmd = match commands.command(view) {
+        Ok(cmd) => cmd,
+        Err(e) => return format!("✗ {e}"),
+    };
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    match editor::run_suspended(terminal, &mut cmd) {
+        Ok(status) if status.success() => format!("Returned from {program}"),
+        Ok(status) => format!("✗ {program} exited with {status}"),
+        Err(e) => format!("✗ Failed to launch {program}: {e}"),
     }
 }
 
@@ -125,6 +292,35 @@ impl ContextWindow {
         }
     }
 
+    /// One size up, stopping at XL (Alt+.)
+    fn grow(self) -> Self {
+        match self {
+            ContextWindow::Small => ContextWindow::Medium,
+            ContextWindow::Medium => ContextWindow::Large,
+            _ => ContextWindow::XLarge,
+        }
+    }
+
+    /// One size down, stopping at S (Alt+,)
+    fn shrink(self) -> Self {
+        match self {
+            ContextWindow::XLarge => ContextWindow::Large,
+            ContextWindow::Large => ContextWindow::Medium,
+            _ => ContextWindow::Small,
+        }
+    }
+
+    /// Snippet lines per result: the density's budget, widened so larger
+    /// windows are actually visible and narrowed for the smallest one.
+    fn snippet_lines(self, density_lines: usize) -> usize {
+        match self {
+            ContextWindow::Small => density_lines.min(3),
+            ContextWindow::Medium => density_lines,
+            ContextWindow::Large => density_lines.max(10),
+            ContextWindow::XLarge => density_lines.max(20),
+        }
+    }
+
     fn size(self) -> usize {
         match self {
             ContextWindow::Small => 200,
@@ -210,6 +406,8 @@ struct TuiStatePersisted {
     per_pane_limit: Option<usize>,
     /// Persisted ranking mode (bead 46t.1): "recent", "balanced", "relevance", etc.
     ranking_mode: Option<String>,
+    /// Render markdown in the detail pane and viewer; false shows plain text.
+    markdown: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -245,6 +443,56 @@ struct AgentPane {
     total_count: usize,
 }
 
+/// A place Backspace/Ctrl+O return to: the result set as it was, and the
+/// conversation that was open over it, if any.
+#[derive(Clone, Debug)]
+struct NavSnapshot {
+    query: String,
+    filters: SearchFilters,
+    results: Vec<SearchHit>,
+    panes: Vec<AgentPane>,
+    active_pane: usize,
+    pane_scroll_offset: usize,
+    page: usize,
+    /// Scroll of the full-screen conversation when it was open
+    modal_scroll: Option<u16>,
+}
+
+/// Trail label for the search a snapshot was taken from
+fn search_label(query: &str) -> String {
+    if query.trim().is_empty() {
+        "All sessions".to_string()
+    } else {
+        format!("“{}”", query.trim())
+    }
+}
+
+/// An OR-query of the distinctive words of `text` (a conversation's title or
+/// first prompt), for finding sessions about the same thing.
+fn similar_query(text: &str) -> Option<String> {
+    const STOPWORDS: &[&str] = &[
+        "that", "this", "with", "from", "have", "what", "when", "where", "which", "there", "their",
+        "would", "could", "should", "about", "into", "then", "than", "them", "they", "your",
+        "will", "just", "some", "make", "please", "need", "want",
+    ];
+    let mut words: Vec<String> = Vec::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .map(str::to_lowercase)
+    {
+        if word.chars().count() >= 4
+            && !STOPWORDS.contains(&word.as_str())
+            && !words.contains(&word)
+        {
+            words.push(word);
+        }
+        if words.len() == 6 {
+            break;
+        }
+    }
+    (!words.is_empty()).then(|| words.join(" OR "))
+}
+
 /// Returns style modifiers based on score magnitude.
 /// High scores (>8) get bold, medium scores (>5) normal, low scores dimmed.
 fn score_style(score: f32) -> Modifier {
@@ -782,6 +1030,30 @@ fn contextual_empty_state(
     lines
 }
 
+/// Footer segment for the background indexer: conversations added and the last
+/// commit, plus scan progress when the daemon owns the index (the TUI's own
+/// indexer already has a progress bar).
+fn index_status_segment(
+    snapshot: &crate::indexer::ProgressSnapshot,
+    via_daemon: bool,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    if via_daemon {
+        parts.push(if snapshot.phase == 0 {
+            "daemon ✓".to_string()
+        } else {
+            format!("daemon ⟳ {}/{} scanned", snapshot.current, snapshot.total)
+        });
+    }
+    if snapshot.added > 0 {
+        parts.push(format!("+{} new", snapshot.added));
+    }
+    if let Some(at) = snapshot.last_commit_at {
+        parts.push(format!("committed {}", format_relative_time(at)));
+    }
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
+
 /// Formats a timestamp as a relative time string ("2h ago", "3d ago", etc.)
 /// Falls back to absolute date for timestamps older than 30 days.
 fn format_relative_time(timestamp_ms: i64) -> String {
@@ -824,7 +1096,8 @@ fn format_absolute_time(timestamp_ms: i64) -> String {
     )
 }
 
-pub fn help_lines(palette: ThemePalette) -> Vec<Line<'static>> {
+/// Help overlay content; the keymap lists `context`'s bindings first.
+pub fn help_lines(palette: ThemePalette, context: KeyContext) -> Vec<Line<'static>> {
     let mut lines: Vec<Line<'static>> = Vec::new();
 
     let add_section = |title: &str, items: &[String]| -> Vec<Line<'static>> {
@@ -876,6 +1149,27 @@ pub fn help_lines(palette: ThemePalette) -> Vec<Line<'static>> {
     ));
     lines.push(Line::from(""));
 
+    // Keymap, the current context first
+    let order =
+        std::iter::once(context).chain(KeyContext::ALL.into_iter().filter(|c| *c != context));
+    for ctx in order {
+        let title = if ctx == context {
+            format!("Keys: {} (current)", ctx.title())
+        } else {
+            format!("Keys: {}", ctx.title())
+        };
+        lines.push(Line::from(Span::styled(title, palette.title())));
+        for binding in shortcuts::BINDINGS.iter().filter(|b| b.context == ctx) {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(format!("{:<18}", binding.keys), kbd_style(palette)),
+                Span::raw(" "),
+                Span::raw(binding.action),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+
     // Data Directories section
     lines.extend(add_section(
         "Data Locations",
@@ -903,93 +1197,25 @@ pub fn help_lines(palette: ThemePalette) -> Vec<Line<'static>> {
     ));
 
     lines.extend(add_section(
-        "Search",
+        "Search tips",
         &[
-            format!(
-                "type to live-search; {} focuses query; {} cycles history",
-                shortcuts::FOCUS_QUERY,
-                shortcuts::HISTORY_CYCLE
-            ),
             "Wildcards: foo* (prefix), *foo (suffix), *foo* (contains)".to_string(),
             "Auto-fuzzy: searches with few results try *term* fallback".to_string(),
-            format!("{} refresh search (re-query index)", shortcuts::REFRESH),
-            "/ detail-find in preview; n/N to jump matches".to_string(),
-        ],
-    ));
-    lines.extend(add_section(
-        "Filters",
-        &[
-            format!("{} agent | {} workspace | {} from | {} to | {} clear all",
-                shortcuts::FILTER_AGENT, shortcuts::FILTER_WORKSPACE, shortcuts::FILTER_DATE_FROM, shortcuts::FILTER_DATE_TO, shortcuts::CLEAR_FILTERS),
-            format!("{} scope to active agent | {} clear scope | {} cycle time presets (24h/7d/30d/all)",
-                shortcuts::SCOPE_AGENT, shortcuts::SCOPE_WORKSPACE, shortcuts::CYCLE_TIME_PRESETS),
-            "Chips in search bar; Backspace removes last; Enter (query empty) edits last chip".to_string(),
+            "In the detail pane: / detail-find, n/N next/previous match".to_string(),
+            "Filter chips sit in the search bar; the palette (Ctrl+P) lists every action"
+                .to_string(),
         ],
     ));
     lines.extend(add_section(
         "Sources (Multi-Machine)",
         &[
-            "F11 cycle source filter: all → local → remote → all".to_string(),
-            "Shift+F11 opens source filter menu (select specific sources)".to_string(),
             "Remote sessions show [source-name] in results list".to_string(),
+            "F11 cycle source filter (all → local → remote → all) · Shift+F11 source menu"
+                .to_string(),
             "CLI: cass sources add|list|doctor|sync|mappings".to_string(),
             "Config: ~/.config/cass/sources.toml".to_string(),
         ],
     ));
-    lines.extend(add_section(
-        "Modes",
-        &[
-            format!(
-                "{} search mode: Lexical → Semantic → Hybrid",
-                shortcuts::SEARCH_MODE
-            ),
-            format!(
-                "{} match mode: prefix (default) ⇄ standard",
-                shortcuts::MATCH_MODE
-            ),
-            format!(
-                "{} ranking: recent → balanced → relevance → match-quality",
-                shortcuts::RANKING
-            ),
-            format!(
-                "{} theme: dark/light | Ctrl+B toggle border style",
-                shortcuts::THEME
-            ),
-        ],
-    ));
-    lines.extend(add_section(
-        "Context",
-        &[
-            format!(
-                "{} cycles S/M/L/XL context window",
-                shortcuts::CONTEXT_WINDOW
-            ),
-            "Ctrl+Space: peek XL for current hit, tap again to restore".to_string(),
-        ],
-    ));
-    lines.extend(add_section(
-        "Density",
-        &["Shift+=/+ increase pane items; Alt+- decrease (min 4, max 50)".to_string()],
-    ));
-    lines.extend(add_section(
-        "Navigation",
-        &[
-            "Arrows move; Left/Right pane; PgUp/PgDn page".to_string(),
-            format!(
-                "{} vim-style nav (when results showing)",
-                shortcuts::VIM_NAV
-            ),
-            format!("{} or Alt+g/G jump to first/last item", shortcuts::JUMP_TOP),
-            format!(
-                "{} toggle select; {} bulk actions; Esc clears selection",
-                shortcuts::TOGGLE_SELECT,
-                shortcuts::BULK_MENU
-            ),
-            "Ctrl+Enter queue item; Ctrl+O open all queued".to_string(),
-            format!("{} toggles focus (Results ⇄ Detail)", shortcuts::TAB_FOCUS),
-            "[ / ] cycle detail tabs (when results showing)".to_string(),
-        ],
-    ));
     lines.extend(add_section(
         "Mouse",
         &[
@@ -997,29 +1223,6 @@ pub fn help_lines(palette: ThemePalette) -> Vec<Line<'static>> {
             "Scroll wheel: navigate results or scroll detail".to_string(),
         ],
     ));
-    lines.extend(add_section(
-        "Actions",
-        &[
-            format!(
-                "{} opens detail modal (o=open, c=copy, p=path, s=snip, n=nano, Esc=close)",
-                shortcuts::DETAIL_OPEN
-            ),
-            format!(
-                "{} open hit in $EDITOR; {} copy path/content",
-                shortcuts::EDITOR,
-                shortcuts::COPY
-            ),
-            format!(
-                "{} detail-find within messages; n/N cycle matches",
-                shortcuts::PANE_FILTER
-            ),
-            format!(
-                "{}/? toggle this help; {} quit (or back from detail)",
-                shortcuts::HELP,
-                shortcuts::QUIT
-            ),
-        ],
-    ));
     lines.extend(add_section(
         "States",
         &[
@@ -1041,10 +1244,10 @@ pub fn help_lines(palette: ThemePalette) -> Vec<Line<'static>> {
     lines
 }
 
-fn render_help_overlay(frame: &mut Frame, palette: ThemePalette, scroll: u16) {
+fn render_help_overlay(frame: &mut Frame, palette: ThemePalette, scroll: u16, context: KeyContext) {
     let area = frame.area();
     let popup_area = centered_rect(70, 70, area);
-    let lines = help_lines(palette);
+    let lines = help_lines(palette, context);
     let block = Block::default()
         .title(Span::styled(
             "Quick Start & Shortcuts (F1 or ? to reopen)",
@@ -1094,12 +1297,55 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 /// Render parsed content lines from a conversation for the detail modal.
 /// Parses tool use, code blocks, and formats beautifully for human reading.
+/// Tool calls the agent recorded are drawn as panels; `tools_expanded` opens them.
 fn render_parsed_content(
     detail: &ConversationView,
     query: &str,
     palette: ThemePalette,
+    tools_expanded: bool,
+    markdown: bool,
 ) -> Vec<Line<'static>> {
+    render_parsed_content_indexed(detail, query, palette, tools_expanded, markdown).0
+}
+
+/// First wrapped row of message `idx` in the parsed detail view, `width` columns wide,
+/// so opening a hit can scroll straight to the matched message.
+fn message_scroll_offset(
+    detail: &ConversationView,
+    tools_expanded: bool,
+    markdown: bool,
+    idx: i64,
+    width: u16,
+) -> Option<u16> {
+    // Colors don't affect layout
+    let (lines, starts) =
+        render_parsed_content_indexed(detail, "", ThemePalette::dark(), tools_expanded, markdown);
+    let start = starts.iter().find(|(i, _)| *i == idx)?.1;
+    let width = usize::from(width.max(1));
+    let rows: usize = lines[..start]
+        .iter()
+        .map(|l| l.width().div_ceil(width).max(1))
+        .sum();
+    Some(rows.min(usize::from(u16::MAX)) as u16)
+}
+
+/// Parsed detail lines plus the line each message's header starts on, by message idx.
+fn render_parsed_content_indexed(
+    detail: &ConversationView,
+    query: &str,
+    palette: ThemePalette,
+    tools_expanded: bool,
+    markdown: bool,
+) -> (Vec<Line<'static>>, Vec<(i64, usize)>) {
+    let body = |text: &str| {
+        if markdown {
+            parse_message_content(text, query, palette)
+        } else {
+            plain_message_content(text, query, palette)
+        }
+    };
     let mut lines: Vec<Line<'static>> = Vec::new();
+    let mut starts = Vec::with_capacity(detail.messages.len());
 
     // Header with conversation info
     if let Some(title) = &detail.convo.title {
@@ -1158,6 +1404,7 @@ fn render_parsed_content(
         };
 
         // Role header with timestamp
+        starts.push((msg.idx, lines.len()));
         let ts_text = msg
             .created_at
             .map(|t| format!(" · {}", format_absolute_time(t)))
@@ -1172,10 +1419,43 @@ fn render_parsed_content(
         ]));
         lines.push(Line::from(""));
 
-        // Parse and render content
-        let content = &msg.content;
-        let parsed_lines = parse_message_content(content, query, palette);
-        lines.extend(parsed_lines);
+        // Parse and render content; rendered tool calls replace their flattened markers
+        let calls = crate::model::tool_calls::tool_calls(&detail.convo.agent_slug, &msg.extra_json);
+        if calls.is_empty() {
+            lines.extend(body(&msg.content));
+        } else {
+            let text = msg
+                .content
+                .lines()
+                .filter(|l| !tool_panels::is_tool_marker(l))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !text.trim().is_empty() {
+                lines.extend(body(&text));
+            }
+            for call in &calls {
+                lines.extend(tool_panels::render_tool_call(call, tools_expanded, palette));
+            }
+        }
+        for note in msg
+            .id
+            .and_then(|id| detail.notes.get(&id))
+            .into_iter()
+            .flatten()
+        {
+            lines.push(Line::from(""));
+            for (i, text) in note.note.lines().enumerate() {
+                lines.push(Line::from(vec![
+                    Span::raw(if i == 0 { "📝 " } else { "   " }),
+                    Span::styled(
+                        text.to_string(),
+                        Style::default()
+                            .fg(palette.accent)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                ]));
+            }
+        }
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
             "─".repeat(60),
@@ -1186,7 +1466,7 @@ fn render_parsed_content(
         lines.push(Line::from(""));
     }
 
-    lines
+    (lines, starts)
 }
 
 /// Parse message content and render with beautiful formatting.
@@ -1344,7 +1624,29 @@ fn parse_message_content(content: &str, query: &str, palette: ThemePalette) -> V
     lines
 }
 
+/// Message content as typed, line by line (markdown rendering toggled off);
+/// search terms are still highlighted.
+fn plain_message_content(content: &str, query: &str, palette: ThemePalette) -> Vec<Line<'static>> {
+    content
+        .lines()
+        .map(|line| {
+            let mut spans = vec![Span::raw("  ")];
+            spans.extend(highlight_spans_owned(
+                line,
+                query,
+                palette,
+                Style::default(),
+            ));
+            Line::from(spans)
+        })
+        .collect()
+}
+
 /// Render the full-screen detail modal for viewing parsed conversation content.
+///
+/// Returns the wrapped row of every line matching `query`, for n/N navigation;
+/// `current_match` indexes into them for the "3/17" counter.
+#[allow(clippy::too_many_arguments)]
 fn render_detail_modal(
     frame: &mut Frame,
     detail: &ConversationView,
@@ -1352,20 +1654,33 @@ fn render_detail_modal(
     query: &str,
     palette: ThemePalette,
     scroll: u16,
-) {
+    tools_expanded: bool,
+    markdown: bool,
+    raw_message: Option<usize>,
+    current_match: Option<usize>,
+) -> Vec<u16> {
     let area = frame.area();
     // Use near-full-screen for maximum readability
     let popup_area = centered_rect(90, 90, area);
 
-    let lines = render_parsed_content(detail, query, palette);
+    let lines = match raw_message.and_then(|i| detail.messages.get(i).map(|m| (i, m))) {
+        Some((i, msg)) => raw_view::render_raw_message(msg, i, detail.messages.len(), palette),
+        None => render_parsed_content(detail, query, palette, tools_expanded, markdown),
+    };
     let total_lines = lines.len();
     // Clamp scroll for display (actual scroll handled by Paragraph)
     let display_line = (scroll as usize).min(total_lines.saturating_sub(1)) + 1;
+    let matches = match_rows(&lines, query, popup_area.width.saturating_sub(2));
+    let match_badge = match (current_match, matches.len()) {
+        (_, 0) => String::new(),
+        (Some(i), n) if i < n => format!(" · match {}/{n}", i + 1),
+        (_, n) => format!(" · {n} matches (n/N)"),
+    };
 
     // Build title with scroll position and hints
     let title_text = format!(
-        " {} · line {}/{} · Esc · o open · c copy · p path · s snip · n nano ",
-        hit.title, display_line, total_lines
+        " {} · line {}/{}{} · Esc · o open · c copy · y yank · p path · s snip · S similar · v view · x resume · t tools · M md · r raw ",
+        hit.title, display_line, total_lines, match_badge
     );
 
     let block = Block::default()
@@ -1387,6 +1702,40 @@ fn render_detail_modal(
             .scroll((scroll, 0)),
         popup_area,
     );
+    matches
+}
+
+/// First wrapped row of each line containing `needle`, `width` columns wide.
+fn match_rows(lines: &[Line], needle: &str, width: u16) -> Vec<u16> {
+    if needle.trim().is_empty() {
+        return Vec::new();
+    }
+    let needle_lc = needle.to_lowercase();
+    let width = usize::from(width.max(1));
+    let mut row = 0usize;
+    let mut rows = Vec::new();
+    for line in lines {
+        if line_plain_text(line).to_lowercase().contains(&needle_lc) {
+            rows.push(row.min(usize::from(u16::MAX)) as u16);
+        }
+        row += line.width().div_ceil(width).max(1);
+    }
+    rows
+}
+
+/// The match after (or before) row `from`, wrapping around the conversation.
+fn step_match(matches: &[u16], from: u16, forward: bool) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    Some(if forward {
+        matches.iter().position(|&r| r > from).unwrap_or(0)
+    } else {
+        matches
+            .iter()
+            .rposition(|&r| r < from)
+            .unwrap_or(matches.len() - 1)
+    })
 }
 
 /// Calculate optimal items per pane based on terminal height.
@@ -1440,8 +1789,6 @@ fn apply_pane_filter(results: &[SearchHit], pane_filter: Option<&str>) -> Vec<Se
 }
 
 fn build_agent_panes(results: &[SearchHit], per_pane_limit: usize) -> Vec<AgentPane> {
-    use std::collections::HashMap;
-
     // First pass: count total hits per agent
     let mut counts: HashMap<String, usize> = HashMap::new();
     for hit in results {
@@ -1516,6 +1863,83 @@ fn active_hit(panes: &[AgentPane], active_idx: usize) -> Option<&SearchHit> {
         .and_then(|pane| pane.hits.get(pane.selected))
 }
 
+/// Plain-text transcript of a conversation for copying or opening in an editor
+fn conversation_text(messages: &[Message]) -> String {
+    let mut text = String::new();
+    for msg in messages {
+        let role_label = match &msg.role {
+            MessageRole::User => "YOU",
+            MessageRole::Agent => "ASSISTANT",
+            MessageRole::Tool => "TOOL",
+            MessageRole::System => "SYSTEM",
+            MessageRole::Other(r) => r,
+        };
+        text.push_str(&format!("=== {role_label} ===\n"));
+        text.push_str(&msg.content);
+        text.push_str("\n\n");
+    }
+    text
+}
+
+/// Entries of the bulk actions menu (`A` with a selection)
+const BULK_ACTIONS: [&str; 7] = [
+    "Open all in editor",
+    "Copy all paths",
+    "Export…",
+    "Add tag…",
+    "Star all",
+    "Delete from index…",
+    "Clear selection",
+];
+
+/// Distinct sessions, as `(source_id, source_path)`, behind a set of hits
+fn hit_sessions<'a>(hits: impl IntoIterator<Item = &'a SearchHit>) -> Vec<(String, String)> {
+    let mut sessions: Vec<(String, String)> = Vec::new();
+    for hit in hits {
+        let key = (hit.source_id.clone(), hit.source_path.clone());
+        if !sessions.contains(&key) {
+            sessions.push(key);
+        }
+    }
+    sessions
+}
+
+/// Undo the newest journaled action. A restored bulk delete still needs its
+/// sessions re-indexed, by the daemon or our own indexer when one is running.
+fn undo_last_action(
+    store: &BookmarkStore,
+    db_path: &Path,
+    data_dir: &Path,
+    daemon: bool,
+    reindex_tx: Option<&crossbeam_channel::Sender<crate::indexer::IndexerEvent>>,
+) -> Toast {
+    let entry = match store.undo_last(db_path) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return Toast::info("Nothing to undo"),
+        Err(e) => return Toast::error(format!("Undo failed: {e}")),
+    };
+    if entry.action != UndoAction::DeleteSessions {
+        return Toast::success(format!("Undone: {}", entry.summary));
+    }
+    if daemon {
+        match crate::daemon::request_reindex(data_dir) {
+            Ok(()) => {
+                Toast::success("Restoring deleted sessions; the daemon re-indexes within 30s")
+            }
+            Err(e) => Toast::warning(format!(
+                "Restored deleted sessions, but could not reach the daemon: {e}"
+            )),
+        }
+    } else if let Some(tx) = reindex_tx {
+        let _ = tx.send(crate::indexer::IndexerEvent::Command(
+            crate::indexer::ReindexCommand::Full,
+        ));
+        Toast::success("Restoring deleted sessions (re-indexing)...")
+    } else {
+        Toast::success("Restored deleted sessions; run `cass index --full` to re-add them")
+    }
+}
+
 /// Known agent slugs for autocomplete suggestions
 const KNOWN_AGENTS: &[&str] = &[
     "claude_code",
@@ -1948,7 +2372,7 @@ fn semantic_unavailable_message(availability: &SemanticAvailability) -> String {
     }
 }
 
-use crate::ui::components::breadcrumbs::{self, BreadcrumbKind};
+use crate::ui::components::breadcrumbs::{self, BreadcrumbKind, NavHistory};
 
 fn chips_for_filters(filters: &SearchFilters, palette: ThemePalette) -> Vec<Span<'static>> {
     use crate::sources::provenance::SourceFilter;
@@ -2000,9 +2424,9 @@ fn chips_for_filters(filters: &SearchFilters, palette: ThemePalette) -> Vec<Span
             ));
             spans.push(Span::raw(" ".to_string()));
         }
-        SourceFilter::SourceId(id) => {
+        SourceFilter::SourceId(_) | SourceFilter::Any(_) => {
             spans.push(Span::styled(
-                format!("[src:{}]", id),
+                format!("[src:{}]", filters.source_filter),
                 Style::default()
                     .fg(palette.accent_alt)
                     .add_modifier(Modifier::ITALIC),
@@ -2042,6 +2466,12 @@ fn contextual_shortcuts(
             ("j/k".into(), "Scroll".into()),
             ("Home/End".into(), "Top/Bottom".into()),
             ("c".into(), "Copy".into()),
+            ("y".into(), "Yank message".into()),
+            ("t".into(), "Tools".into()),
+            ("M".into(), "Markdown".into()),
+            ("r".into(), "Raw JSON".into()),
+            ("+/-".into(), "Rate".into()),
+            ("*".into(), "Star".into()),
         ];
     }
     match input_mode {
@@ -2070,6 +2500,11 @@ fn contextual_shortcuts(
             (shortcuts::DETAIL_OPEN.into(), "Apply".into()),
             (shortcuts::DETAIL_CLOSE.into(), "Cancel".into()),
         ],
+        InputMode::Tag => vec![
+            ("type".into(), "Tag name".into()),
+            (shortcuts::DETAIL_OPEN.into(), "Tag selected".into()),
+            (shortcuts::DETAIL_CLOSE.into(), "Cancel".into()),
+        ],
         InputMode::Query => match focus_region {
             FocusRegion::Results => vec![
                 ("Ctrl+P".into(), "Palette".into()),
@@ -2094,8 +2529,8 @@ fn contextual_shortcuts(
                 ("←/→".into(), "Tabs".into()),
                 (shortcuts::PANE_FILTER.into(), "Find in detail".into()),
                 ("n/N".into(), "Next/prev match".into()),
-                ("c".into(), "Copy".into()),
-                ("o".into(), "Open file".into()),
+                ("y/Y".into(), "Copy message/conversation".into()),
+                ("e".into(), "Edit source".into()),
                 (shortcuts::DETAIL_CLOSE.into(), "Close detail".into()),
             ],
         },
@@ -2439,6 +2874,101 @@ pub fn footer_legend(show_help: bool) -> &'static str {
     }
 }
 
+/// Per-session badges read from the database
+#[derive(Default)]
+struct SessionMarks {
+    /// Sessions locked against pruning (`cass lock`), shown with a 🔒 badge.
+    retained_sessions: HashSet<(String, String)>,
+    /// Session ratings (`cass rate`, or +/- in the detail view), shown as 👍/👎 badges.
+    session_ratings: HashMap<(String, String), i8>,
+    /// Starred sessions (`*` in the detail view, `cass tag add <id> starred`), shown with a ★ badge.
+    starred_sessions: HashSet<(String, String)>,
+}
+
+impl SessionMarks {
+    fn load(db: Option<&crate::storage::sqlite::SqliteStorage>) -> Self {
+        let Some(db) = db else {
+            return Self::default();
+        };
+        Self {
+            retained_sessions: db
+                .list_retained()
+                .map(|rows| rows.into_iter().map(|(sid, path, _)| (sid, path)).collect())
+                .unwrap_or_default(),
+            session_ratings: crate::storage::sqlite::load_ratings(db.raw()).unwrap_or_default(),
+            starred_sessions: crate::storage::sqlite::load_tagged_sessions(
+                db.raw(),
+                &[crate::storage::sqlite::STARRED_TAG.to_string()],
+            )
+            .unwrap_or_default(),
+        }
+    }
+}
+
+/// Label of the switcher entry for the plain data dir; not a valid profile name
+const DEFAULT_PROFILE: &str = "(default)";
+
+/// Entries of the profile switcher: the plain data dir, the configured
+/// profiles, then the active profile when it isn't configured explicitly.
+fn profile_choices(
+    configured: impl IntoIterator<Item = String>,
+    active: Option<&str>,
+) -> Vec<String> {
+    let mut choices = vec![DEFAULT_PROFILE.to_string()];
+    choices.extend(configured);
+    if let Some(active) = active
+        && !choices.iter().any(|c| c == active)
+    {
+        choices.push(active.to_string());
+    }
+    choices
+}
+
+/// Open a profile picked in the switcher: its data dir, database, index dir
+/// and search client.
+fn open_profile(
+    name: &str,
+) -> Result<(
+    std::path::PathBuf,
+    std::path::PathBuf,
+    std::path::PathBuf,
+    Option<SearchClient>,
+)> {
+    let (data_dir, db_path) = if name == DEFAULT_PROFILE {
+        let dir = crate::base_data_dir();
+        let db = default_db_path_for(&dir);
+        (dir, db)
+    } else {
+        let profile =
+            crate::profiles::resolve(name, &crate::profiles::configured(), crate::base_data_dir())?;
+        let db = profile
+            .db
+            .unwrap_or_else(|| default_db_path_for(&profile.data_dir));
+        (profile.data_dir, db)
+    };
+    let index_path = index_dir(&data_dir)?;
+    let client = SearchClient::open(&index_path, Some(&db_path))?;
+    Ok((data_dir, db_path, index_path, client))
+}
+
+/// Fill the profile switcher, selecting the current profile; leaves `items`
+/// empty when no profiles are configured. Returns the status line.
+fn open_profile_menu(
+    items: &mut Vec<String>,
+    selection: &mut usize,
+    active: Option<&str>,
+) -> String {
+    let configured = crate::profiles::configured();
+    if configured.is_empty() {
+        items.clear();
+        return "No profiles configured; add [profiles.<name>] to config.toml".to_string();
+    }
+    *items = profile_choices(configured.into_keys(), active);
+    let current = active.unwrap_or(DEFAULT_PROFILE);
+    *selection = items.iter().position(|p| p == current).unwrap_or(0);
+    "Switch profile (↑/↓ select, Enter open, Esc close)".to_string()
+}
+
 pub fn run_tui(
     data_dir_override: Option<std::path::PathBuf>,
     once: bool,
@@ -2447,7 +2977,7 @@ pub fn run_tui(
     reindex_tx: Option<crossbeam_channel::Sender<crate::indexer::IndexerEvent>>,
 ) -> Result<()> {
     // Resolve data dir early so we can honor reset-state in headless mode too.
-    let data_dir = data_dir_override.unwrap_or_else(default_data_dir);
+    let mut data_dir = data_dir_override.unwrap_or_else(default_data_dir);
     let state_path = state_path_for(&data_dir);
 
     // Optional: wipe persisted UI state before loading defaults.
@@ -2469,10 +2999,10 @@ pub fn run_tui(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let index_path = index_dir(&data_dir)?;
-    let db_path = default_db_path_for(&data_dir);
+    let mut index_path = index_dir(&data_dir)?;
+    let mut db_path = default_db_path_for(&data_dir);
     let persisted = load_state(&state_path);
-    let search_client = SearchClient::open(&index_path, Some(&db_path))?;
+    let mut search_client = SearchClient::open(&index_path, Some(&db_path))?;
     let mut semantic_availability = if let Some(client) = &search_client {
         initialize_semantic_context(client, &data_dir, &db_path)
     } else {
@@ -2489,7 +3019,17 @@ pub fn run_tui(
 
     // Open a read-only connection for the UI to fetch details efficiently.
     // If DB doesn't exist yet (first run), this will be None, which is fine as we can't view details anyway.
-    let db_reader = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path).ok();
+    let mut db_reader = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path).ok();
+    // Bookmarks live in their own DB; removals are journaled so `u` can undo them.
+    let mut bookmark_store = BookmarkStore::open(&data_dir.join("bookmarks.db")).ok();
+    let SessionMarks {
+        mut retained_sessions,
+        mut session_ratings,
+        mut starred_sessions,
+    } = SessionMarks::load(db_reader.as_ref());
+    let mut toasts = ToastManager::new();
+    let query_aliases = crate::search::aliases::QueryAliases::load();
+    let click_feedback = crate::search::feedback::RankingConfig::load().click_feedback;
 
     let index_ready = search_client.is_some();
     let mut status = if index_ready {
@@ -2504,6 +3044,11 @@ pub fn run_tui(
         )
     };
 
+    if let Some(err) = search_client.as_ref().and_then(|c| c.index_error()) {
+        tracing::warn!(kind = "index-corrupt", error = %err, "tui: search index unreadable");
+        status = "Search index corrupt - searching via SQLite FTS. The indexer rebuilds it from the database; reopen afterwards (or run `cass index --rebuild`).".to_string();
+    }
+
     if reset_state {
         status = format!("State reset (tui_state.json cleared). {status}");
     }
@@ -2562,6 +3107,9 @@ pub fn run_tui(
     let mut pane_scroll_offset: usize = 0; // First visible pane index
     // Multi-select state: (pane_index, hit_index) tuples of selected items
     let mut selected: HashSet<(usize, usize)> = HashSet::new();
+    // Where Backspace/Ctrl+O go back to (search → conversation → similar → ...)
+    let mut nav: NavHistory<NavSnapshot> = NavHistory::default();
+    let mut nav_back = false;
     // Require double-confirm before opening a large queue of files
     const OPEN_CONFIRM_THRESHOLD: usize = 12;
     let mut open_confirm_armed = false;
@@ -2602,9 +3150,28 @@ pub fn run_tui(
     // Full-screen modal for viewing parsed content
     let mut show_detail_modal = false;
     let mut modal_scroll: u16 = 0;
+    // Query matches in the full-screen detail view, refreshed on every draw
+    let mut modal_matches: Vec<u16> = Vec::new();
+    let mut modal_match: Option<usize> = None;
+    // Whether tool panels in the detail modal show their full input
+    let mut tools_expanded = false;
+    // Message whose stored source entry the detail modal shows instead of the conversation
+    let mut raw_message: Option<usize> = None;
     // Bulk action modal state
     let mut show_bulk_modal = false;
     let mut bulk_action_idx: usize = 0;
+    // Delete in the bulk menu needs a second Enter
+    let mut delete_armed = false;
+    // Set after arrowing through results: Space toggles and * selects all
+    // instead of typing into the query
+    let mut list_keys = false;
+    let mut export_modal = ExportModalState::default();
+    let mut calendar = CalendarState::default();
+    let mut dashboard = DashboardState::default();
+    let mut quick_pick: Option<Picker> = None;
+    // Progress of a `cass daemon` that owns the index, re-read from daemon.json
+    let mut daemon_progress: Option<crate::indexer::ProgressSnapshot> = None;
+    let mut daemon_checked_at: Option<Instant> = None;
     let mut cached_detail: Option<(String, ConversationView)> = None;
     let mut detail_find: Option<DetailFindState> = None;
     let mut last_query = String::new();
@@ -2632,6 +3199,7 @@ pub fn run_tui(
         status = format!("Semantic unavailable: {reason}. Using lexical search.");
         search_mode = SearchMode::Lexical;
     }
+    let mut render_markdown = persisted.markdown.unwrap_or(true);
     let mut ranking_mode = persisted
         .ranking_mode
         .as_deref()
@@ -2679,9 +3247,8 @@ pub fn run_tui(
     let mut peek_window_saved: Option<ContextWindow> = None;
     let mut peek_badge_until: Option<Instant> = None;
     let mut help_scroll: u16 = 0;
-    let editor_cmd = std::env::var("EDITOR").unwrap_or_else(|_| "vi".into());
-    let (editor_bin, editor_args) = split_editor_command(&editor_cmd);
-    let editor_line_flag = std::env::var("EDITOR_LINE_FLAG").unwrap_or_else(|_| "+".into());
+    let editor = EditorCommand::resolve();
+    let resume_commands = ResumeCommands::load();
     let mut time_preset_idx: usize = 0;
 
     // Mouse support: track layout regions for click/scroll handling
@@ -2699,6 +3266,15 @@ pub fn run_tui(
     // Available source IDs discovered from index (populated on menu open)
     let mut available_source_ids: Vec<String> = Vec::new();
 
+    // Profile switcher state: the background indexer keeps serving the
+    // profile the TUI was launched with
+    let launch_data_dir = data_dir.clone();
+    let mut active_profile: Option<String> = crate::profiles::active().map(|p| p.name.clone());
+    let mut profile_menu_open = false;
+    let mut profile_menu_selection: usize = 0;
+    let mut profile_menu_items: Vec<String> = Vec::new();
+    let mut pending_profile: Option<String> = None;
+
     // Keep a short history of indexer percentages for sparkline rendering
     let mut progress_history: std::collections::VecDeque<u8> =
         std::collections::VecDeque::with_capacity(24);
@@ -2864,6 +3440,88 @@ pub fn run_tui(
     };
 
     loop {
+        // Backspace/Ctrl+O: return to the previous result set and conversation
+        if std::mem::take(&mut nav_back) {
+            match nav.pop() {
+                Some((label, snap)) => {
+                    query = snap.query;
+                    filters = snap.filters;
+                    results = snap.results;
+                    panes = snap.panes;
+                    active_pane = snap.active_pane;
+                    pane_scroll_offset = snap.pane_scroll_offset;
+                    page = snap.page;
+                    selected.clear();
+                    dirty_since = None;
+                    show_detail_modal = snap.modal_scroll.is_some();
+                    modal_scroll = snap.modal_scroll.unwrap_or(0);
+                    modal_match = None;
+                    raw_message = None;
+                    status = format!("Back to {label}");
+                }
+                None => status = "Nothing to go back to".to_string(),
+            }
+            needs_draw = true;
+        }
+
+        // Profile switcher: reopen everything on the picked profile's data dir
+        if let Some(name) = pending_profile.take() {
+            match open_profile(&name) {
+                Ok((dir, db, idx, client)) => {
+                    // Replacing the client closes the previous profile's index
+                    search_client = client;
+                    data_dir = dir;
+                    db_path = db;
+                    index_path = idx;
+                    db_reader = crate::storage::sqlite::SqliteStorage::open_readonly(&db_path).ok();
+                    bookmark_store = BookmarkStore::open(&data_dir.join("bookmarks.db")).ok();
+                    SessionMarks {
+                        retained_sessions,
+                        session_ratings,
+                        starred_sessions,
+                    } = SessionMarks::load(db_reader.as_ref());
+                    semantic_availability = match &search_client {
+                        Some(client) => initialize_semantic_context(client, &data_dir, &db_path),
+                        None => SemanticAvailability::LoadFailed {
+                            context: "index/db not ready".to_string(),
+                        },
+                    };
+                    if !matches!(search_mode, SearchMode::Lexical)
+                        && !semantic_availability.is_ready()
+                    {
+                        search_mode = SearchMode::Lexical;
+                    }
+                    results.clear();
+                    panes.clear();
+                    selected.clear();
+                    nav = NavHistory::default();
+                    cached_detail = None;
+                    show_detail_modal = false;
+                    active_pane = 0;
+                    pane_scroll_offset = 0;
+                    page = 0;
+                    detail_scroll = 0;
+                    dirty_since = Some(Instant::now());
+                    active_profile = (name != DEFAULT_PROFILE).then_some(name);
+                    let label = active_profile.as_deref().unwrap_or("default");
+                    status = if search_client.is_some() {
+                        format!("Profile {label}: {}", data_dir.display())
+                    } else {
+                        format!(
+                            "Profile {label}: no index at {}. Run `cass{} index --full`.",
+                            index_path.display(),
+                            active_profile
+                                .as_deref()
+                                .map(|p| format!(" --profile {p}"))
+                                .unwrap_or_default()
+                        )
+                    };
+                }
+                Err(e) => status = format!("✗ Could not open profile {name}: {e}"),
+            }
+            needs_draw = true;
+        }
+
         // Check for terminal resize and recalculate pane limit if needed
         if let Ok(size) = terminal.size()
             && size.height != last_terminal_height
@@ -2919,11 +3577,16 @@ pub fn run_tui(
                     InputMode::CreatedTo => format!("[to] {input_buffer}"),
                     InputMode::PaneFilter => format!("[pane] {input_buffer}"),
                     InputMode::DetailFind => format!("[detail find] {input_buffer}"),
+                    InputMode::Tag => format!("[tag] {input_buffer}"),
                 };
                 let mode_label = match match_mode {
                     MatchMode::Standard => "standard",
                     MatchMode::Prefix => "prefix",
                 };
+                let mode_label = match &active_profile {
+                    Some(profile) => format!("{mode_label} · profile {profile}"),
+                    None => mode_label.to_string(),
+                };
                 let search_split = Layout::default()
                     .direction(Direction::Vertical)
                     .constraints(
@@ -2937,7 +3600,7 @@ pub fn run_tui(
                     .split(chunks[0]);
 
                 let chips = chips_for_filters(&filters, palette);
-                let sb = search_bar(&bar_text, palette, input_mode, mode_label, chips);
+                let sb = search_bar(&bar_text, palette, input_mode, &mode_label, chips);
                 f.render_widget(sb, search_split[0]);
 
                 let mut pill_vec: Vec<Pill> = Vec::new();
@@ -2991,6 +3654,7 @@ pub fn run_tui(
                     search_split[2],
                     &filters,
                     ranking_mode,
+                    &nav.labels(),
                     palette,
                 );
                 last_breadcrumb_rects = bc_rects;
@@ -3283,6 +3947,29 @@ pub fn run_tui(
                                             .add_modifier(Modifier::ITALIC),
                                     ));
                                 }
+                                if retained_sessions
+                                    .contains(&(hit.source_id.clone(), hit.source_path.clone()))
+                                {
+                                    location_spans.push(Span::styled(
+                                        " 🔒",
+                                        Style::default().fg(palette.hint),
+                                    ));
+                                }
+                                if starred_sessions
+                                    .contains(&(hit.source_id.clone(), hit.source_path.clone()))
+                                {
+                                    location_spans.push(Span::styled(
+                                        " ★",
+                                        Style::default().fg(Color::Yellow),
+                                    ));
+                                }
+                                match session_ratings
+                                    .get(&(hit.source_id.clone(), hit.source_path.clone()))
+                                {
+                                    Some(1) => location_spans.push(Span::raw(" 👍")),
+                                    Some(-1) => location_spans.push(Span::raw(" 👎")),
+                                    _ => {}
+                                }
                                 let location_line = Line::from(location_spans);
 
                                 // Snippet with enhanced highlighting (multiple lines if long)
@@ -3296,7 +3983,8 @@ pub fn run_tui(
                                 // Wrap at word boundaries with continuation indent
                                 // Limit to 2 lines for compact display (sux.6.1)
                                 let wrapped_lines = smart_word_wrap(&raw_snippet, 80);
-                                let snippet_budget = density_mode.snippet_lines();
+                                let snippet_budget =
+                                    context_window.snippet_lines(density_mode.snippet_lines());
                                 let snippet_lines: Vec<Line> =
                                     wrapped_lines
                                         .into_iter()
@@ -3638,7 +4326,13 @@ pub fn run_tui(
                     let content_lines: Vec<Line> = match detail_tab {
                         DetailTab::Messages => {
                             if let Some(full) = detail {
-                                let lines = render_parsed_content(&full, highlight_term, palette);
+                                let lines = render_parsed_content(
+                                    &full,
+                                    highlight_term,
+                                    palette,
+                                    false,
+                                    render_markdown,
+                                );
                                 detail_match_lines = match_line_indices(&lines, highlight_term);
                                 if lines.is_empty() {
                                     vec![Line::from(Span::styled(
@@ -3649,11 +4343,14 @@ pub fn run_tui(
                                     lines
                                 }
                             } else {
-                                let lines: Vec<Line> = hit
-                                    .content
-                                    .lines()
-                                    .map(|l| Line::from(l.to_string()))
-                                    .collect();
+                                let lines: Vec<Line> = if render_markdown {
+                                    parse_message_content(&hit.content, highlight_term, palette)
+                                } else {
+                                    hit.content
+                                        .lines()
+                                        .map(|l| Line::from(l.to_string()))
+                                        .collect()
+                                };
                                 detail_match_lines = match_line_indices(&lines, highlight_term);
                                 if lines.is_empty() {
                                     vec![Line::from(Span::styled(
@@ -3891,6 +4588,14 @@ pub fn run_tui(
                         footer_parts.push(p_str);
                     }
                 }
+                let index_segment = match (&daemon_progress, &progress) {
+                    (Some(d), _) => index_status_segment(d, true),
+                    (None, Some(p)) => index_status_segment(&p.snapshot(), false),
+                    (None, None) => None,
+                };
+                if let Some(segment) = index_segment {
+                    footer_parts.push(segment);
+                }
 
                 if let Some(ms) = last_search_ms {
                     footer_parts.push(format!("⚡ {ms}ms"));
@@ -3940,7 +4645,7 @@ pub fn run_tui(
                 }
                 // Show queued count when items are selected (bead lsv.1)
                 if !selected.is_empty() {
-                    footer_parts.push(format!("queued:{}", selected.len()));
+                    footer_parts.push(format!("✓ {} selected", selected.len()));
                 }
                 if !matches!(context_window, ContextWindow::Medium) {
                     footer_parts.push(
@@ -4112,7 +4817,14 @@ pub fn run_tui(
                 }
 
                 if show_help {
-                    render_help_overlay(f, palette, help_scroll);
+                    let context = if show_detail_modal || focus_region == FocusRegion::Detail {
+                        KeyContext::Viewer
+                    } else if results.is_empty() {
+                        KeyContext::Search
+                    } else {
+                        KeyContext::Results
+                    };
+                    render_help_overlay(f, palette, help_scroll, context);
                 }
 
                 // Detail modal takes priority over help
@@ -4129,7 +4841,18 @@ pub fn run_tui(
                     } else {
                         last_query.as_str()
                     };
-                    render_detail_modal(f, detail, hit, modal_highlight, palette, modal_scroll);
+                    modal_matches = render_detail_modal(
+                        f,
+                        detail,
+                        hit,
+                        modal_highlight,
+                        palette,
+                        modal_scroll,
+                        tools_expanded,
+                        render_markdown,
+                        raw_message,
+                        modal_match,
+                    );
                 }
 
                 // Bulk action modal
@@ -4147,17 +4870,16 @@ pub fn run_tui(
                         .border_style(Style::default().fg(palette.accent))
                         .style(Style::default().bg(palette.surface));
 
-                    const BULK_ACTIONS: [&str; 4] = [
-                        "Open all in editor",
-                        "Copy all paths",
-                        "Export as JSON",
-                        "Clear selection",
-                    ];
                     let items: Vec<ListItem> = BULK_ACTIONS
                         .iter()
                         .enumerate()
                         .map(|(i, label)| {
-                            let style = if i == bulk_action_idx {
+                            let style = if i == bulk_action_idx && delete_armed {
+                                Style::default()
+                                    .bg(Color::Red)
+                                    .fg(palette.bg)
+                                    .add_modifier(Modifier::BOLD)
+                            } else if i == bulk_action_idx {
                                 Style::default()
                                     .bg(palette.accent)
                                     .fg(palette.bg)
@@ -4252,10 +4974,95 @@ pub fn run_tui(
                     f.render_widget(list, area);
                 }
 
+                if profile_menu_open {
+                    let area = centered_rect(40, 40, f.area());
+                    let block = Block::default()
+                        .title(Span::styled(
+                            " Profiles ",
+                            Style::default()
+                                .fg(palette.accent)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(palette.accent))
+                        .style(Style::default().bg(palette.surface));
+                    let current = active_profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+                    let items: Vec<ListItem> = profile_menu_items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| {
+                            let style = if i == profile_menu_selection {
+                                Style::default()
+                                    .bg(palette.accent)
+                                    .fg(palette.bg)
+                                    .add_modifier(Modifier::BOLD)
+                            } else {
+                                Style::default().fg(palette.fg)
+                            };
+                            let marker = if i == profile_menu_selection {
+                                "→ "
+                            } else {
+                                "  "
+                            };
+                            let suffix = if name == current { "  (current)" } else { "" };
+                            ListItem::new(Line::from(vec![
+                                Span::styled(marker, style),
+                                Span::styled(format!("{name}{suffix}"), style),
+                            ]))
+                        })
+                        .collect();
+                    let list = List::new(items).block(block);
+                    f.render_widget(ratatui::widgets::Clear, area);
+                    f.render_widget(list, area);
+                }
+
+                if export_modal.open {
+                    let area = centered_rect(50, 30, f.area());
+                    export_modal::draw_export_modal(
+                        f,
+                        area,
+                        &export_modal,
+                        (selected.len(), results.len()),
+                        palette,
+                    );
+                }
+
+                if calendar.open {
+                    let area = centered_rect(90, 85, f.area());
+                    calendar::draw_calendar(f, area, &calendar, palette);
+                }
+
+                if dashboard.open {
+                    let area = centered_rect(90, 85, f.area());
+                    dashboard::draw_dashboard(f, area, &dashboard, palette);
+                }
+
+                if let Some(picker) = &quick_pick {
+                    let area = centered_rect(80, 70, f.area());
+                    let block = Block::default()
+                        .title(Span::styled(
+                            " Quick pick ",
+                            Style::default()
+                                .fg(palette.accent)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(palette.accent))
+                        .style(Style::default().bg(palette.bg));
+                    let inner = block.inner(area);
+                    f.render_widget(ratatui::widgets::Clear, area);
+                    f.render_widget(block, area);
+                    picker.draw(f, inner, palette);
+                }
+
                 if palette_state.open {
                     let area = centered_rect(70, 60, f.area());
                     palette::draw_palette(f, area, &palette_state, palette);
                 }
+
+                render_toasts(f, &toasts, &palette);
             })?;
             needs_draw = false;
         }
@@ -4275,7 +5082,16 @@ pub fn run_tui(
             // Handle mouse events (skip when modal is open)
             if let Event::Mouse(mouse) = event {
                 // Ignore mouse events when help, detail, bulk, or source filter modal is open
-                if show_help || show_detail_modal || show_bulk_modal || source_filter_menu_open {
+                if show_help
+                    || show_detail_modal
+                    || show_bulk_modal
+                    || export_modal.open
+                    || calendar.open
+                    || dashboard.open
+                    || quick_pick.is_some()
+                    || source_filter_menu_open
+                    || profile_menu_open
+                {
                     continue;
                 }
                 needs_draw = true;
@@ -4435,6 +5251,10 @@ pub fn run_tui(
                 && !show_help
                 && !show_detail_modal
                 && !show_bulk_modal
+                && !export_modal.open
+                && !calendar.open
+                && !dashboard.open
+                && quick_pick.is_none()
                 && !palette_state.open
             {
                 match key.code {
@@ -4569,6 +5389,88 @@ pub fn run_tui(
                                 PaletteAction::ReloadIndex => {
                                     dirty_since = Some(Instant::now());
                                 }
+                                PaletteAction::CopySnippet | PaletteAction::CopyMessage => {
+                                    if let Some(hit) = active_hit(&panes, active_pane) {
+                                        status =
+                                            if matches!(item.action, PaletteAction::CopySnippet) {
+                                                clipboard::status(
+                                                    "snippet",
+                                                    clipboard::copy(&hit.snippet),
+                                                )
+                                            } else {
+                                                clipboard::status(
+                                                    "message",
+                                                    clipboard::copy(&hit.content),
+                                                )
+                                            };
+                                    }
+                                }
+                                PaletteAction::OpenDashboard => {
+                                    dashboard.open = true;
+                                    load_dashboard(&mut dashboard, db_reader.as_ref());
+                                }
+                                PaletteAction::OpenQuickPick => {
+                                    match open_quick_pick(db_reader.as_ref()) {
+                                        Ok(picker) => quick_pick = Some(picker),
+                                        Err(msg) => status = msg,
+                                    }
+                                }
+                                PaletteAction::OpenCalendar => {
+                                    calendar.open();
+                                    refresh_calendar(&mut calendar, db_reader.as_ref());
+                                }
+                                PaletteAction::SwitchProfile => {
+                                    status = open_profile_menu(
+                                        &mut profile_menu_items,
+                                        &mut profile_menu_selection,
+                                        active_profile.as_deref(),
+                                    );
+                                    profile_menu_open = !profile_menu_items.is_empty();
+                                }
+                                PaletteAction::ResumeSession => {
+                                    status = match active_hit(&panes, active_pane) {
+                                        Some(hit) => resume_session(
+                                            &mut terminal,
+                                            &resume_commands,
+                                            hit,
+                                            cached_detail.as_ref(),
+                                            db_reader.as_ref(),
+                                        ),
+                                        None => "No result selected".to_string(),
+                                    };
+                                }
+                                PaletteAction::ExportResults => {
+                                    if results.is_empty() {
+                                        status = "No results to export".to_string();
+                                    } else {
+                                        export_modal.open(!selected.is_empty());
+                                    }
+                                }
+                                PaletteAction::CopyConversation => {
+                                    if let Some(hit) = active_hit(&panes, active_pane) {
+                                        let loaded = match cached_detail.as_ref() {
+                                            Some((path, detail)) if *path == hit.source_path => {
+                                                Some(detail.clone())
+                                            }
+                                            _ => db_reader.as_ref().and_then(|storage| {
+                                                load_conversation(storage, &hit.source_path)
+                                                    .ok()
+                                                    .flatten()
+                                            }),
+                                        };
+                                        status = match loaded {
+                                            Some(detail) => clipboard::status(
+                                                "conversation",
+                                                clipboard::copy(&conversation_text(
+                                                    &detail.messages,
+                                                )),
+                                            ),
+                                            None => {
+                                                "Conversation not found in the index".to_string()
+                                            }
+                                        };
+                                    }
+                                }
                                 PaletteAction::OpenSavedViews => {
                                     status =
                                         "Saved views: Ctrl+<n> save, Shift+<n> load".to_string();
@@ -4611,24 +5513,167 @@ pub fn run_tui(
                 continue;
             }
 
+            // Export modal: handle keys when open
+            if export_modal.open {
+                match key.code {
+                    KeyCode::Esc => export_modal.open = false,
+                    KeyCode::Up | KeyCode::BackTab => export_modal.move_field(-1),
+                    KeyCode::Down | KeyCode::Tab => export_modal.move_field(1),
+                    KeyCode::Left | KeyCode::Right => export_modal.cycle(),
+                    KeyCode::Char(' ') if export_modal.field != ExportField::Path => {
+                        export_modal.cycle();
+                    }
+                    KeyCode::Backspace => export_modal.pop_char(),
+                    KeyCode::Char(c) => export_modal.push_char(c),
+                    KeyCode::Enter => {
+                        let mut hits: Vec<SearchHit> = match export_modal.scope {
+                            ExportScope::Hit => active_hit(&panes, active_pane)
+                                .cloned()
+                                .into_iter()
+                                .collect(),
+                            ExportScope::Selection => {
+                                let mut keys: Vec<&(usize, usize)> = selected.iter().collect();
+                                keys.sort();
+                                keys.into_iter()
+                                    .filter_map(|(pane_idx, hit_idx)| {
+                                        panes.get(*pane_idx).and_then(|p| p.hits.get(*hit_idx))
+                                    })
+                                    .cloned()
+                                    .collect()
+                            }
+                            ExportScope::Results => results.clone(),
+                        };
+                        if hits.is_empty() {
+                            status = "Nothing to export".to_string();
+                            continue;
+                        }
+                        if let Some(db) = db_reader.as_ref() {
+                            resolve_message_locations(&mut hits, db);
+                        }
+                        let options = ExportOptions {
+                            include_content: true,
+                            query: (!query.trim().is_empty()).then(|| query.clone()),
+                            ..ExportOptions::default()
+                        };
+                        let text = export_results(&hits, export_modal.format, &options);
+                        let what = format!(
+                            "{} result{} as {}",
+                            hits.len(),
+                            if hits.len() == 1 { "" } else { "s" },
+                            export_modal.format.name()
+                        );
+                        status = match export_modal.destination {
+                            ExportDestination::Clipboard => {
+                                clipboard::status(&what, clipboard::copy(&text))
+                            }
+                            ExportDestination::File => {
+                                let path = export_modal.path.trim();
+                                if path.is_empty() {
+                                    status = "Enter a file path".to_string();
+                                    continue;
+                                }
+                                match std::fs::write(path, &text) {
+                                    Ok(()) => format!("✓ Exported {what} to {path}"),
+                                    Err(e) => format!("✗ Export to {path} failed: {e}"),
+                                }
+                            }
+                        };
+                        export_modal.open = false;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Quick pick: type to filter, Enter opens the session in the editor
+            if let Some(picker) = quick_pick.as_mut() {
+                if let Some(choice) = picker.handle_key(key) {
+                    let path = choice
+                        .and_then(|i| picker.item(i))
+                        .map(|c| c.source_path.clone());
+                    quick_pick = None;
+                    if let Some(path) = path {
+                        status = open_in_editor(&mut terminal, &editor, &path, None);
+                    }
+                }
+                continue;
+            }
+
+            // Usage dashboard: read-only, so only close and refresh
+            if dashboard.open {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => dashboard.open = false,
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        dashboard.open = false
+                    }
+                    KeyCode::Char('r') => load_dashboard(&mut dashboard, db_reader.as_ref()),
+                    _ => {}
+                }
+                continue;
+            }
+
+            // Calendar screen: handle keys when open
+            if calendar.open {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => calendar.open = false,
+                    KeyCode::Tab | KeyCode::BackTab => {
+                        calendar.list_focus =
+                            !calendar.list_focus && calendar.selected_entry().is_some();
+                    }
+                    KeyCode::Up | KeyCode::Char('k') if calendar.list_focus => {
+                        calendar.move_entry(-1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if calendar.list_focus => {
+                        calendar.move_entry(1)
+                    }
+                    KeyCode::Char('e') | KeyCode::Char('o') if calendar.list_focus => {
+                        if let Some(entry) = calendar.selected_entry() {
+                            let path = entry.source_path.clone();
+                            status = open_in_editor(&mut terminal, &editor, &path, None);
+                        }
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => calendar.move_days(-1),
+                    KeyCode::Right | KeyCode::Char('l') => calendar.move_days(1),
+                    KeyCode::Up | KeyCode::Char('k') => calendar.move_days(-7),
+                    KeyCode::Down | KeyCode::Char('j') => calendar.move_days(7),
+                    KeyCode::PageUp | KeyCode::Char('[') => calendar.move_page(false),
+                    KeyCode::PageDown | KeyCode::Char(']') => calendar.move_page(true),
+                    KeyCode::Char('w') => calendar.toggle_view(),
+                    KeyCode::Char('t') => calendar.go_today(),
+                    KeyCode::Enter => {
+                        if let Some((from, to)) = calendar.selected_day_range() {
+                            filters.created_from = Some(from);
+                            filters.created_to = Some(to);
+                            page = 0;
+                            dirty_since = Some(Instant::now());
+                            status = format!(
+                                "Showing sessions from {}",
+                                calendar.selected.format("%a %b %-d, %Y")
+                            );
+                        }
+                        calendar.open = false;
+                    }
+                    _ => {}
+                }
+                refresh_calendar(&mut calendar, db_reader.as_ref());
+                continue;
+            }
+
             // Bulk action modal: handle keys when open
             if show_bulk_modal {
-                const BULK_ACTIONS: [&str; 4] = [
-                    "Open all in editor",
-                    "Copy all paths",
-                    "Export as JSON",
-                    "Clear selection",
-                ];
                 match key.code {
                     KeyCode::Esc => {
                         show_bulk_modal = false;
+                        delete_armed = false;
                         status = format!("{} items still selected", selected.len());
                     }
                     KeyCode::Up | KeyCode::Char('k') => {
                         bulk_action_idx = bulk_action_idx.saturating_sub(1);
+                        delete_armed = false;
                     }
                     KeyCode::Down | KeyCode::Char('j') => {
                         bulk_action_idx = (bulk_action_idx + 1).min(BULK_ACTIONS.len() - 1);
+                        delete_armed = false;
                     }
                     KeyCode::Enter => {
                         show_bulk_modal = false;
@@ -4642,36 +5687,16 @@ pub fn run_tui(
                         match bulk_action_idx {
                             0 => {
                                 // Open all in editor
-                                let editor = std::env::var("EDITOR")
-                                    .or_else(|_| std::env::var("VISUAL"))
-                                    .unwrap_or_else(|_| "code".to_string());
-                                let (editor_bin, editor_args) = split_editor_command(&editor);
-                                // Exit raw mode
-                                disable_raw_mode().ok();
-                                execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
-                                    .ok();
                                 for hit in &selected_hits {
-                                    let mut cmd = StdCommand::new(&editor_bin);
-                                    cmd.args(&editor_args);
-                                    if editor_bin == "code" {
-                                        if let Some(ln) = hit.line_number {
-                                            cmd.arg("--goto")
-                                                .arg(format!("{}:{}", hit.source_path, ln));
-                                        } else {
-                                            cmd.arg(&hit.source_path);
-                                        }
-                                    } else {
-                                        cmd.arg(&hit.source_path);
-                                    }
-                                    let _ = cmd.status();
+                                    let _ = editor::run_suspended(
+                                        &mut terminal,
+                                        &mut editor.command(&hit.source_path, hit.line_number),
+                                    );
                                 }
-                                execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
-                                    .ok();
-                                enable_raw_mode().ok();
                                 status = format!(
                                     "Opened {} files in {}",
                                     selected_hits.len(),
-                                    editor_bin
+                                    editor.name()
                                 );
                                 selected.clear();
                                 open_confirm_armed = false;
@@ -4683,107 +5708,137 @@ pub fn run_tui(
                                     .map(|h| h.source_path.clone())
                                     .collect();
                                 let text = paths.join("\n");
-                                let clipboard_cmd = if cfg!(target_os = "macos") {
-                                    Some("pbcopy")
-                                } else if StdCommand::new("which")
-                                    .arg("xclip")
-                                    .output()
-                                    .map(|o| o.status.success())
-                                    .unwrap_or(false)
-                                {
-                                    Some("xclip -selection clipboard")
-                                } else if StdCommand::new("which")
-                                    .arg("xsel")
-                                    .output()
-                                    .map(|o| o.status.success())
-                                    .unwrap_or(false)
-                                {
-                                    Some("xsel --clipboard --input")
-                                } else {
-                                    None
-                                };
-                                status = if let Some(cmd) = clipboard_cmd {
-                                    let result = StdCommand::new("sh")
-                                        .arg("-c")
-                                        .arg(cmd)
-                                        .stdin(std::process::Stdio::piped())
-                                        .spawn()
-                                        .and_then(|mut child| {
-                                            use std::io::Write;
-                                            if let Some(stdin) = child.stdin.as_mut() {
-                                                stdin.write_all(text.as_bytes())?;
-                                            }
-                                            child.wait()
-                                        });
-                                    if result.map(|s| s.success()).unwrap_or(false) {
+                                let result = clipboard::copy(&text);
+                                if result.is_ok() {
+                                    selected.clear();
+                                    open_confirm_armed = false;
+                                }
+                                status =
+                                    clipboard::status(&format!("{} paths", paths.len()), result);
+                            }
+                            2 => export_modal.open(true),
+                            3 => {
+                                input_mode = InputMode::Tag;
+                                input_buffer.clear();
+                                status = format!(
+                                    "Tag {} selected sessions: type a tag, Enter to apply",
+                                    hit_sessions(selected_hits).len()
+                                );
+                            }
+                            4 => {
+                                // Star every selected session
+                                let sessions = hit_sessions(selected_hits);
+                                let result = crate::storage::sqlite::SqliteStorage::open(&db_path)
+                                    .and_then(|storage| {
+                                        for (source_id, source_path) in &sessions {
+                                            storage.set_session_starred(
+                                                source_id,
+                                                source_path,
+                                                true,
+                                            )?;
+                                        }
+                                        Ok(())
+                                    });
+                                match result {
+                                    Ok(()) => {
+                                        toasts.push(Toast::success(format!(
+                                            "★ Starred {} sessions",
+                                            sessions.len()
+                                        )));
+                                        starred_sessions.extend(sessions);
                                         selected.clear();
                                         open_confirm_armed = false;
-                                        format!("✓ Copied {} paths to clipboard", paths.len())
-                                    } else {
-                                        "✗ Clipboard copy failed".to_string()
+                                        if let Some(client) = &search_client {
+                                            client.invalidate_cache();
+                                        }
                                     }
-                                } else {
-                                    "✗ No clipboard tool found".to_string()
-                                };
+                                    Err(e) => {
+                                        toasts.push(Toast::error(format!("Failed to star: {e}")))
+                                    }
+                                }
                             }
-                            2 => {
-                                // Export as JSON
-                                let export: Vec<serde_json::Value> = selected_hits
-                                    .iter()
-                                    .map(|h| {
-                                        serde_json::json!({
-                                            "source_path": h.source_path,
-                                            "line_number": h.line_number,
-                                            "title": h.title,
-                                            "agent": h.agent,
-                                            "workspace": h.workspace,
-                                            "score": h.score,
-                                            "snippet": h.snippet,
-                                        })
+                            5 if !delete_armed => {
+                                // Ask once more before touching the index
+                                delete_armed = true;
+                                show_bulk_modal = true;
+                                status = format!(
+                                    "Delete {} sessions from the index? Enter again to confirm · Esc cancel",
+                                    hit_sessions(selected_hits).len()
+                                );
+                            }
+                            5 => {
+                                delete_armed = false;
+                                let sessions = hit_sessions(selected_hits);
+                                let result = if let Some(tx) =
+                                    reindex_tx.as_ref().filter(|_| data_dir == launch_data_dir)
+                                {
+                                    // Our own watcher holds the index writer; it deletes for us
+                                    let (reply, done) = crossbeam_channel::bounded(1);
+                                    tx.send(crate::indexer::IndexerEvent::Command(
+                                        crate::indexer::ReindexCommand::DeleteSessions {
+                                            sessions: sessions.clone(),
+                                            reply,
+                                        },
+                                    ))
+                                    .map_err(|_| anyhow::anyhow!("the indexer has stopped"))
+                                    .and_then(|()| {
+                                        done.recv_timeout(Duration::from_secs(60))
+                                            .map_err(|_| {
+                                                anyhow::anyhow!("the indexer did not answer")
+                                            })?
+                                            .map_err(anyhow::Error::msg)
                                     })
-                                    .collect();
-                                if let Ok(json) = serde_json::to_string_pretty(&export) {
-                                    let clipboard_cmd = if cfg!(target_os = "macos") {
-                                        Some("pbcopy")
-                                    } else if StdCommand::new("which")
-                                        .arg("xclip")
-                                        .output()
-                                        .map(|o| o.status.success())
-                                        .unwrap_or(false)
-                                    {
-                                        Some("xclip -selection clipboard")
-                                    } else {
-                                        None
-                                    };
-                                    status = if let Some(cmd) = clipboard_cmd {
-                                        let result = StdCommand::new("sh")
-                                            .arg("-c")
-                                            .arg(cmd)
-                                            .stdin(std::process::Stdio::piped())
-                                            .spawn()
-                                            .and_then(|mut child| {
-                                                use std::io::Write;
-                                                if let Some(stdin) = child.stdin.as_mut() {
-                                                    stdin.write_all(json.as_bytes())?;
-                                                }
-                                                child.wait()
-                                            });
-                                        if result.map(|s| s.success()).unwrap_or(false) {
-                                            selected.clear();
-                                            open_confirm_armed = false;
-                                            format!(
-                                                "✓ Exported {} items as JSON to clipboard",
-                                                export.len()
+                                } else {
+                                    crate::indexer::lock::IndexLock::acquire(&data_dir).and_then(
+                                        |_lock| {
+                                            let mut storage =
+                                                crate::storage::sqlite::SqliteStorage::open(
+                                                    &db_path,
+                                                )?;
+                                            let mut t_index =
+                                                crate::search::tantivy::TantivyIndex::open_or_create(
+                                                    &index_path,
+                                                )?;
+                                            crate::indexer::delete_sessions(
+                                                &mut storage,
+                                                &mut t_index,
+                                                &sessions,
                                             )
-                                        } else {
-                                            "✗ JSON export failed".to_string()
+                                        },
+                                    )
+                                };
+                                match result {
+                                    Ok((removed, locked)) => {
+                                        let mut msg = format!("Deleted {removed} conversations");
+                                        if locked > 0 {
+                                            msg.push_str(&format!(
+                                                " · kept {locked} locked sessions"
+                                            ));
                                         }
-                                    } else {
-                                        "✗ No clipboard tool found".to_string()
-                                    };
+                                        if let Some(store) = &bookmark_store {
+                                            match store.journal_deleted_sessions(&sessions, removed)
+                                            {
+                                                Ok(()) => msg.push_str(" · Ctrl+Z to undo"),
+                                                Err(e) => tracing::warn!(
+                                                    "journaling bulk delete failed: {e}"
+                                                ),
+                                            }
+                                        }
+                                        toasts.push(Toast::success(msg));
+                                        selected.clear();
+                                        open_confirm_armed = false;
+                                        cached_detail = None;
+                                        if let Some(client) = &search_client {
+                                            client.invalidate_cache();
+                                        }
+                                        dirty_since = Some(Instant::now());
+                                    }
+                                    Err(e) => toasts.push(Toast::error(format!(
+                                        "Delete failed (is an indexer running?): {e}"
+                                    ))),
                                 }
                             }
-                            3 => {
+                            6 => {
                                 // Clear selection
                                 let count = selected.len();
                                 selected.clear();
@@ -4839,6 +5894,9 @@ pub fn run_tui(
                                 SourceFilter::Local => "local only".to_string(),
                                 SourceFilter::Remote => "remote only".to_string(),
                                 SourceFilter::SourceId(id) => format!("source '{}'", id),
+                                SourceFilter::Any(_) => {
+                                    format!("sources {}", filters.source_filter)
+                                }
                             }
                         );
                         dirty_since = Some(Instant::now());
@@ -4849,6 +5907,31 @@ pub fn run_tui(
                 continue;
             }
 
+            // Profile switcher: handle keys when open
+            if profile_menu_open {
+                match key.code {
+                    KeyCode::Esc => profile_menu_open = false,
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        profile_menu_selection = profile_menu_selection.saturating_sub(1);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        profile_menu_selection = (profile_menu_selection + 1)
+                            .min(profile_menu_items.len().saturating_sub(1));
+                    }
+                    KeyCode::Enter => {
+                        profile_menu_open = false;
+                        let current = active_profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+                        match profile_menu_items.get(profile_menu_selection) {
+                            Some(name) if name != current => pending_profile = Some(name.clone()),
+                            _ => status = "Profile unchanged".to_string(),
+                        }
+                    }
+                    _ => {}
+                }
+                needs_draw = true;
+                continue;
+            }
+
             // While help is open, keys scroll the help modal and do not affect panes.
             if show_help {
                 match key.code {
@@ -4869,7 +5952,10 @@ pub fn run_tui(
                         help_scroll = help_scroll.saturating_add(5);
                     }
                     KeyCode::Home => help_scroll = 0,
-                    KeyCode::End => help_scroll = help_lines(ThemePalette::dark()).len() as u16,
+                    KeyCode::End => {
+                        help_scroll =
+                            help_lines(ThemePalette::dark(), KeyContext::Global).len() as u16
+                    }
                     _ => {}
                 }
                 continue;
@@ -4878,9 +5964,115 @@ pub fn run_tui(
             // While detail modal is open, handle its keyboard shortcuts
             if show_detail_modal {
                 match key.code {
+                    KeyCode::Esc if raw_message.is_some() => {
+                        raw_message = None;
+                        modal_scroll = 0;
+                        modal_match = None;
+                    }
                     KeyCode::Esc => {
                         show_detail_modal = false;
                         modal_scroll = 0;
+                        modal_match = None;
+                    }
+                    KeyCode::Backspace if !nav.is_empty() => nav_back = true,
+                    KeyCode::Char('o')
+                        if key.modifiers.contains(KeyModifiers::CONTROL) && !nav.is_empty() =>
+                    {
+                        nav_back = true
+                    }
+                    KeyCode::Backspace => {
+                        show_detail_modal = false;
+                        modal_scroll = 0;
+                        modal_match = None;
+                    }
+                    KeyCode::Char('S') => {
+                        // Sessions about the same thing; Backspace/Ctrl+O come back here
+                        let seed = cached_detail.as_ref().and_then(|(_, d)| {
+                            d.convo.title.clone().or_else(|| {
+                                d.messages
+                                    .iter()
+                                    .find(|m| matches!(m.role, MessageRole::User))
+                                    .map(|m| m.content.chars().take(300).collect())
+                            })
+                        });
+                        match seed.as_deref().and_then(similar_query) {
+                            Some(similar) => {
+                                let title = active_hit(&panes, active_pane)
+                                    .map(|h| h.title.clone())
+                                    .filter(|t| !t.trim().is_empty())
+                                    .unwrap_or_else(|| "conversation".to_string());
+                                nav.push(
+                                    format!("{} › {title}", search_label(&query)),
+                                    NavSnapshot {
+                                        query: query.clone(),
+                                        filters: filters.clone(),
+                                        results: results.clone(),
+                                        panes: panes.clone(),
+                                        active_pane,
+                                        pane_scroll_offset,
+                                        page,
+                                        modal_scroll: Some(modal_scroll),
+                                    },
+                                );
+                                query = similar;
+                                page = 0;
+                                selected.clear();
+                                show_detail_modal = false;
+                                modal_scroll = 0;
+                                modal_match = None;
+                                raw_message = None;
+                                dirty_since = Some(Instant::now());
+                                status = "Similar sessions · Ctrl+O back to the conversation"
+                                    .to_string();
+                            }
+                            None => {
+                                status = "Nothing distinctive to search for in this conversation"
+                                    .to_string()
+                            }
+                        }
+                    }
+                    KeyCode::Char(c @ ('n' | 'N')) => {
+                        match step_match(&modal_matches, modal_scroll, c == 'n') {
+                            Some(i) => {
+                                modal_match = Some(i);
+                                modal_scroll = modal_matches[i];
+                            }
+                            None => status = "No query matches in this conversation".to_string(),
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        raw_message = if raw_message.is_some() {
+                            None
+                        } else if let Some((_, ref detail)) = cached_detail {
+                            // Start at the matched message when the hit knows it
+                            let hit_idx = panes
+                                .get(active_pane)
+                                .and_then(|pane| pane.hits.get(pane.selected))
+                                .and_then(|hit| hit.line_number)
+                                .map(|ln| ln as i64 - 1);
+                            Some(
+                                hit_idx
+                                    .and_then(|idx| {
+                                        detail.messages.iter().position(|m| m.idx == idx)
+                                    })
+                                    .unwrap_or(0),
+                            )
+                        } else {
+                            None
+                        };
+                        modal_scroll = 0;
+                        modal_match = None;
+                    }
+                    KeyCode::Char('[') if raw_message.is_some() => {
+                        raw_message = raw_message.map(|i| i.saturating_sub(1));
+                        modal_scroll = 0;
+                    }
+                    KeyCode::Char(']') if raw_message.is_some() => {
+                        let last = cached_detail
+                            .as_ref()
+                            .map_or(0, |(_, d)| d.messages.len().saturating_sub(1));
+                        raw_message = raw_message.map(|i| (i + 1).min(last));
+                        modal_scroll = 0;
                     }
                     KeyCode::Up | KeyCode::Char('k') => {
                         modal_scroll = modal_scroll.saturating_sub(1);
@@ -4896,85 +6088,45 @@ pub fn run_tui(
                     }
                     KeyCode::Home | KeyCode::Char('g') => modal_scroll = 0,
                     KeyCode::End | KeyCode::Char('G') => modal_scroll = u16::MAX,
+                    KeyCode::Char('M') => {
+                        render_markdown = !render_markdown;
+                        modal_scroll = 0;
+                        status = if render_markdown {
+                            "Markdown rendering on".to_string()
+                        } else {
+                            "Markdown rendering off (plain text)".to_string()
+                        };
+                    }
+                    KeyCode::Char('t') => {
+                        tools_expanded = !tools_expanded;
+                        status = if tools_expanded {
+                            "Tool panels expanded".to_string()
+                        } else {
+                            "Tool panels collapsed".to_string()
+                        };
+                    }
                     KeyCode::Char('c') => {
-                        // Copy rendered content to clipboard using xclip/xsel/pbcopy
                         if let Some((_, ref detail)) = cached_detail {
-                            let mut text = String::new();
-                            for msg in &detail.messages {
-                                let role_label = match &msg.role {
-                                    MessageRole::User => "YOU",
-                                    MessageRole::Agent => "ASSISTANT",
-                                    MessageRole::Tool => "TOOL",
-                                    MessageRole::System => "SYSTEM",
-                                    MessageRole::Other(r) => r,
-                                };
-                                text.push_str(&format!("=== {role_label} ===\n"));
-                                text.push_str(&msg.content);
-                                text.push_str("\n\n");
-                            }
-                            // Try clipboard tools in order of preference
-                            let clipboard_cmd = if cfg!(target_os = "macos") {
-                                Some("pbcopy")
-                            } else {
-                                // Linux: prefer xclip, fallback to xsel
-                                if StdCommand::new("which")
-                                    .arg("xclip")
-                                    .output()
-                                    .map(|o| o.status.success())
-                                    .unwrap_or(false)
-                                {
-                                    Some("xclip -selection clipboard")
-                                } else if StdCommand::new("which")
-                                    .arg("xsel")
-                                    .output()
-                                    .map(|o| o.status.success())
-                                    .unwrap_or(false)
-                                {
-                                    Some("xsel --clipboard --input")
-                                } else {
-                                    None
-                                }
-                            };
-
-                            status = if let Some(cmd) = clipboard_cmd {
-                                let result = StdCommand::new("sh")
-                                    .arg("-c")
-                                    .arg(cmd)
-                                    .stdin(std::process::Stdio::piped())
-                                    .spawn()
-                                    .and_then(|mut child| {
-                                        use std::io::Write;
-                                        if let Some(stdin) = child.stdin.as_mut() {
-                                            stdin.write_all(text.as_bytes())?;
-                                        }
-                                        child.wait()
-                                    });
-                                if result.map(|s| s.success()).unwrap_or(false) {
-                                    "✓ Copied to clipboard".to_string()
-                                } else {
-                                    "✗ Clipboard copy failed".to_string()
-                                }
+                            let text = conversation_text(&detail.messages);
+                            status = clipboard::status("conversation", clipboard::copy(&text));
+                        }
+                    }
+                    KeyCode::Char('y') => {
+                        // Yank the message open in raw view, else the whole conversation
+                        if let Some((_, ref detail)) = cached_detail {
+                            if let Some(msg) = raw_message.and_then(|i| detail.messages.get(i)) {
+                                status =
+                                    clipboard::status("message", clipboard::copy(&msg.content));
                             } else {
-                                "✗ No clipboard tool found (xclip/xsel/pbcopy)".to_string()
-                            };
+                                let text = conversation_text(&detail.messages);
+                                status = clipboard::status("conversation", clipboard::copy(&text));
+                            }
                         }
                     }
-                    KeyCode::Char('n') => {
+                    KeyCode::Char('v') => {
                         // Open content in nano via temp file
                         if let Some((_, ref detail)) = cached_detail {
-                            let mut text = String::new();
-                            for msg in &detail.messages {
-                                let role_label = match &msg.role {
-                                    MessageRole::User => "YOU",
-                                    MessageRole::Agent => "ASSISTANT",
-                                    MessageRole::Tool => "TOOL",
-                                    MessageRole::System => "SYSTEM",
-                                    MessageRole::Other(r) => r,
-                                };
-                                text.push_str(&format!("=== {role_label} ===\n"));
-                                text.push_str(&msg.content);
-                                text.push_str("\n\n");
-                            }
+                            let text = conversation_text(&detail.messages);
                             // Create temp file
                             let tmp_path = std::env::temp_dir().join(format!(
                                 "cass_view_{}.md",
@@ -5003,182 +6155,157 @@ pub fn run_tui(
                                     "✗ Failed to launch nano".to_string()
                                 };
                                 show_detail_modal = false;
-                                modal_scroll = 0;
-                            } else {
-                                status = "✗ Failed to create temp file".to_string();
-                            }
-                        }
-                    }
-                    KeyCode::Char('o') => {
-                        // Open source file in $EDITOR or default editor
-                        if let Some(pane) = panes.get(active_pane)
-                            && let Some(hit) = pane.hits.get(pane.selected)
-                        {
-                            let path = &hit.source_path;
-                            // Determine editor: $EDITOR, $VISUAL, or fallback chain
-                            let editor = std::env::var("EDITOR")
-                                .or_else(|_| std::env::var("VISUAL"))
-                                .unwrap_or_else(|_| {
-                                    // Try common editors in order of preference
-                                    for candidate in ["code", "vim", "nano", "vi"] {
-                                        if StdCommand::new("which")
-                                            .arg(candidate)
-                                            .output()
-                                            .map(|o| o.status.success())
-                                            .unwrap_or(false)
-                                        {
-                                            return candidate.to_string();
-                                        }
-                                    }
-                                    "nano".to_string()
-                                });
-
-                            let (editor_bin, editor_args) = split_editor_command(&editor);
-                            // Exit raw mode for GUI editors (code) or TUI editors
-                            disable_raw_mode().ok();
-                            execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture).ok();
-
-                            // Build command with optional line number
-                            let mut cmd = StdCommand::new(&editor_bin);
-                            cmd.args(&editor_args);
-                            if editor_bin == "code" {
-                                // VS Code: code --goto file:line
-                                if let Some(ln) = hit.line_number {
-                                    cmd.arg("--goto").arg(format!("{path}:{ln}"));
-                                } else {
-                                    cmd.arg(path);
-                                }
-                            } else if editor_bin == "vim"
-                                || editor_bin == "vi"
-                                || editor_bin == "nvim"
-                            {
-                                // Vim: vim +line file
-                                if let Some(ln) = hit.line_number {
-                                    cmd.arg(format!("+{ln}"));
-                                }
-                                cmd.arg(path);
-                            } else if editor_bin == "nano" {
-                                // Nano: nano +line file
-                                if let Some(ln) = hit.line_number {
-                                    cmd.arg(format!("+{ln}"));
-                                }
-                                cmd.arg(path);
-                            } else {
-                                // Generic: just pass the path
-                                cmd.arg(path);
-                            }
-
-                            let result = cmd.status();
-
-                            // Re-enter raw mode
-                            execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture).ok();
-                            enable_raw_mode().ok();
-
-                            status = if result.map(|s| s.success()).unwrap_or(false) {
-                                format!("Opened {path} in {editor_bin}")
+                                modal_scroll = 0;
+                                raw_message = None;
                             } else {
-                                format!("✗ Failed to open in {editor_bin}")
-                            };
+                                status = "✗ Failed to create temp file".to_string();
+                            }
+                        }
+                    }
+                    KeyCode::Char('x') => {
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            status = resume_session(
+                                &mut terminal,
+                                &resume_commands,
+                                hit,
+                                cached_detail.as_ref(),
+                                db_reader.as_ref(),
+                            );
+                        }
+                    }
+                    KeyCode::Char('o') | KeyCode::Char('e') => {
+                        // Open source file in the configured editor at the matched line
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            let (path, line) = editor_target(hit, cached_detail.as_ref());
+                            status = open_in_editor(&mut terminal, &editor, &path, line);
                             show_detail_modal = false;
                             modal_scroll = 0;
+                            raw_message = None;
                         }
                     }
                     KeyCode::Char('p') => {
-                        // Copy source path to clipboard
-                        if let Some(pane) = panes.get(active_pane)
-                            && let Some(hit) = pane.hits.get(pane.selected)
-                        {
-                            let path = &hit.source_path;
-                            let clipboard_cmd = if cfg!(target_os = "macos") {
-                                Some("pbcopy")
-                            } else if StdCommand::new("which")
-                                .arg("xclip")
-                                .output()
-                                .map(|o| o.status.success())
-                                .unwrap_or(false)
-                            {
-                                Some("xclip -selection clipboard")
-                            } else if StdCommand::new("which")
-                                .arg("xsel")
-                                .output()
-                                .map(|o| o.status.success())
-                                .unwrap_or(false)
-                            {
-                                Some("xsel --clipboard --input")
-                            } else {
-                                None
-                            };
-
-                            status = if let Some(cmd) = clipboard_cmd {
-                                let result = StdCommand::new("sh")
-                                    .arg("-c")
-                                    .arg(cmd)
-                                    .stdin(std::process::Stdio::piped())
-                                    .spawn()
-                                    .and_then(|mut child| {
-                                        use std::io::Write;
-                                        if let Some(stdin) = child.stdin.as_mut() {
-                                            stdin.write_all(path.as_bytes())?;
-                                        }
-                                        child.wait()
-                                    });
-                                if result.map(|s| s.success()).unwrap_or(false) {
-                                    format!("✓ Path copied: {path}")
-                                } else {
-                                    "✗ Clipboard copy failed".to_string()
-                                }
-                            } else {
-                                "✗ No clipboard tool found".to_string()
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            status = match clipboard::copy(&hit.source_path) {
+                                Ok(_) => format!("✓ Path copied: {}", hit.source_path),
+                                Err(e) => format!("✗ Clipboard copy failed: {e}"),
                             };
                         }
                     }
                     KeyCode::Char('s') => {
-                        // Copy snippet to clipboard
-                        if let Some(pane) = panes.get(active_pane)
-                            && let Some(hit) = pane.hits.get(pane.selected)
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            status = clipboard::status("snippet", clipboard::copy(&hit.snippet));
+                        }
+                    }
+                    KeyCode::Char('b') => {
+                        // Toggle bookmark for the active hit; removals can be undone with `u`
+                        if let Some(store) = &bookmark_store
+                            && let Some(hit) = active_hit(&panes, active_pane)
                         {
-                            let snippet = &hit.snippet;
-                            let clipboard_cmd = if cfg!(target_os = "macos") {
-                                Some("pbcopy")
-                            } else if StdCommand::new("which")
-                                .arg("xclip")
-                                .output()
-                                .map(|o| o.status.success())
-                                .unwrap_or(false)
-                            {
-                                Some("xclip -selection clipboard")
-                            } else if StdCommand::new("which")
-                                .arg("xsel")
-                                .output()
-                                .map(|o| o.status.success())
-                                .unwrap_or(false)
-                            {
-                                Some("xsel --clipboard --input")
-                            } else {
-                                None
-                            };
-
-                            status = if let Some(cmd) = clipboard_cmd {
-                                let result = StdCommand::new("sh")
-                                    .arg("-c")
-                                    .arg(cmd)
-                                    .stdin(std::process::Stdio::piped())
-                                    .spawn()
-                                    .and_then(|mut child| {
-                                        use std::io::Write;
-                                        if let Some(stdin) = child.stdin.as_mut() {
-                                            stdin.write_all(snippet.as_bytes())?;
-                                        }
-                                        child.wait()
-                                    });
-                                if result.map(|s| s.success()).unwrap_or(false) {
-                                    "✓ Snippet copied to clipboard".to_string()
-                                } else {
-                                    "✗ Clipboard copy failed".to_string()
+                            match store.find_by_location(&hit.source_path, hit.line_number) {
+                                Ok(Some(id)) => match store.remove(id) {
+                                    Ok(_) => toasts.push(
+                                        Toast::warning("Bookmark removed · u to undo")
+                                            .with_id("bookmark-undo"),
+                                    ),
+                                    Err(e) => toasts.push(Toast::error(format!(
+                                        "Failed to remove bookmark: {e}"
+                                    ))),
+                                },
+                                Ok(None) => {
+                                    let mut bookmark = Bookmark::new(
+                                        hit.title.clone(),
+                                        hit.source_path.clone(),
+                                        hit.agent.clone(),
+                                        hit.workspace.clone(),
+                                    )
+                                    .with_snippet(hit.snippet.clone());
+                                    if let Some(line) = hit.line_number {
+                                        bookmark = bookmark.with_line(line);
+                                    }
+                                    match store.add(&bookmark) {
+                                        Ok(_) => toasts.push(Toast::success("Bookmarked")),
+                                        Err(e) => toasts
+                                            .push(Toast::error(format!("Failed to bookmark: {e}"))),
+                                    }
                                 }
-                            } else {
-                                "✗ No clipboard tool found".to_string()
-                            };
+                                Err(e) => toasts
+                                    .push(Toast::error(format!("Bookmark lookup failed: {e}"))),
+                            }
+                        }
+                    }
+                    KeyCode::Char(c @ ('+' | '-')) => {
+                        // Rate the session; pressing the same key again clears the rating
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            let key = (hit.source_id.clone(), hit.source_path.clone());
+                            let wanted: i8 = if c == '+' { 1 } else { -1 };
+                            let clear = session_ratings.get(&key) == Some(&wanted);
+                            let result = crate::storage::sqlite::SqliteStorage::open(&db_path)
+                                .and_then(|storage| {
+                                    if clear {
+                                        storage.clear_rating(&key.0, &key.1).map(|_| ())
+                                    } else {
+                                        storage.set_rating(&key.0, &key.1, wanted, None)
+                                    }
+                                });
+                            match result {
+                                Ok(()) => {
+                                    if clear {
+                                        session_ratings.remove(&key);
+                                        toasts.push(Toast::info("Rating cleared"));
+                                    } else {
+                                        session_ratings.insert(key, wanted);
+                                        toasts.push(Toast::success(if wanted > 0 {
+                                            "👍 Rated up"
+                                        } else {
+                                            "👎 Rated down"
+                                        }));
+                                    }
+                                    if let Some(client) = &search_client {
+                                        client.invalidate_cache();
+                                    }
+                                }
+                                Err(e) => toasts.push(Toast::error(format!("Failed to rate: {e}"))),
+                            }
+                        }
+                    }
+                    KeyCode::Char('*') => {
+                        // Star or unstar the session
+                        if let Some(hit) = active_hit(&panes, active_pane) {
+                            let key = (hit.source_id.clone(), hit.source_path.clone());
+                            let star = !starred_sessions.contains(&key);
+                            let result = crate::storage::sqlite::SqliteStorage::open(&db_path)
+                                .and_then(|storage| {
+                                    storage.set_session_starred(&key.0, &key.1, star)
+                                });
+                            match result {
+                                Ok(0) if star => {
+                                    toasts.push(Toast::warning("Session not in the database yet"))
+                                }
+                                Ok(_) => {
+                                    if star {
+                                        starred_sessions.insert(key);
+                                        toasts.push(Toast::success("★ Starred"));
+                                    } else {
+                                        starred_sessions.remove(&key);
+                                        toasts.push(Toast::info("Unstarred"));
+                                    }
+                                    if let Some(client) = &search_client {
+                                        client.invalidate_cache();
+                                    }
+                                }
+                                Err(e) => toasts.push(Toast::error(format!("Failed to star: {e}"))),
+                            }
+                        }
+                    }
+                    KeyCode::Char('u') => {
+                        if let Some(store) = &bookmark_store {
+                            toasts.push(undo_last_action(
+                                store,
+                                &db_path,
+                                &data_dir,
+                                daemon_progress.is_some(),
+                                reindex_tx.as_ref().filter(|_| data_dir == launch_data_dir),
+                            ));
                         }
                     }
                     _ => {}
@@ -5226,6 +6353,34 @@ pub fn run_tui(
                 continue;
             }
 
+            // Grow/shrink the snippet context around the match (Alt+. / Alt+,)
+            if matches!(key.code, KeyCode::Char('.' | ',' | '>' | '<'))
+                && key.modifiers.contains(KeyModifiers::ALT)
+            {
+                peek_window_saved = None;
+                peek_badge_until = None;
+                let before = context_window;
+                context_window = if matches!(key.code, KeyCode::Char('.' | '>')) {
+                    context_window.grow()
+                } else {
+                    context_window.shrink()
+                };
+                status = if context_window == before {
+                    format!(
+                        "Context already at {} ({} chars)",
+                        context_window.label(),
+                        context_window.size()
+                    )
+                } else {
+                    format!(
+                        "Context window: {} ({} chars)",
+                        context_window.label(),
+                        context_window.size()
+                    )
+                };
+                continue;
+            }
+
             match input_mode {
                 InputMode::Query => {
                     if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -5237,11 +6392,32 @@ pub fn run_tui(
                             status = save_view_slot(slot, &filters, ranking_mode, &mut saved_views);
                             continue;
                         }
+                        // Ctrl+Z = undo the last journaled action, such as a bulk delete
+                        if matches!(key.code, KeyCode::Char('z')) {
+                            if let Some(store) = &bookmark_store {
+                                toasts.push(undo_last_action(
+                                    store,
+                                    &db_path,
+                                    &data_dir,
+                                    daemon_progress.is_some(),
+                                    reindex_tx.as_ref().filter(|_| data_dir == launch_data_dir),
+                                ));
+                            }
+                            continue;
+                        }
                         // Handle both 'r' and 'R' since Shift modifier may change the char
                         if matches!(key.code, KeyCode::Char('r' | 'R')) {
                             // Ctrl+Shift+R = refresh search (re-query index)
                             if key.modifiers.contains(KeyModifiers::SHIFT) {
-                                if let Some(tx) = &reindex_tx {
+                                if daemon_progress.is_some() {
+                                    status = match crate::daemon::request_reindex(&data_dir) {
+                                        Ok(()) => "Asked the daemon to re-index (within 30s)..."
+                                            .to_string(),
+                                        Err(e) => format!("✗ Could not reach the daemon: {e}"),
+                                    };
+                                } else if let Some(tx) =
+                                    reindex_tx.as_ref().filter(|_| data_dir == launch_data_dir)
+                                {
                                     let _ = tx.send(crate::indexer::IndexerEvent::Command(
                                         crate::indexer::ReindexCommand::Full,
                                     ));
@@ -5338,6 +6514,7 @@ pub fn run_tui(
                                     } else if let Some(pane) = panes.get_mut(active_pane)
                                         && pane.selected + 1 < pane.hits.len()
                                     {
+                                        list_keys = true;
                                         pane.selected += 1;
                                         // Re-load details for new selection
                                         cached_detail = None;
@@ -5365,6 +6542,7 @@ pub fn run_tui(
                                     } else if let Some(pane) = panes.get_mut(active_pane)
                                         && pane.selected > 0
                                     {
+                                        list_keys = true;
                                         pane.selected -= 1;
                                         // Re-load details for new selection
                                         cached_detail = None;
@@ -5450,54 +6628,11 @@ pub fn run_tui(
                             if let Some(hit) = active_hit(&panes, active_pane) {
                                 // User committed to copying result - save query to history
                                 save_query_to_history(&query, &mut query_history, history_cap);
-                                let text_to_copy = if matches!(focus_region, FocusRegion::Detail) {
-                                    if let Some((_, _)) = &cached_detail {
-                                        hit.content.clone()
-                                    } else {
-                                        hit.content.clone()
-                                    }
+                                status = if matches!(focus_region, FocusRegion::Detail) {
+                                    clipboard::status("message", clipboard::copy(&hit.content))
                                 } else {
-                                    hit.source_path.clone()
+                                    clipboard::status("path", clipboard::copy(&hit.source_path))
                                 };
-
-                                #[cfg(any(target_os = "linux", target_os = "macos"))]
-                                {
-                                    use std::process::Stdio;
-                                    let child = std::process::Command::new("sh")
-                                        .arg("-c")
-                                        .arg("if command -v wl-copy >/dev/null; then wl-copy; elif command -v pbcopy >/dev/null; then pbcopy; elif command -v xclip >/dev/null; then xclip -selection clipboard; fi")
-                                        .stdin(Stdio::piped())
-                                        .spawn();
-                                    if let Ok(mut child) = child
-                                        && let Some(mut stdin) = child.stdin.take()
-                                    {
-                                        use std::io::Write;
-                                        let _ = stdin.write_all(text_to_copy.as_bytes());
-                                        drop(stdin); // Ensure EOF
-                                        let _ = child.wait();
-                                        status = "Copied to clipboard".to_string();
-                                    } else {
-                                        status =
-                                            "Clipboard copy failed (missing tool?)".to_string();
-                                    }
-                                }
-                                #[cfg(target_os = "windows")]
-                                {
-                                    let child = std::process::Command::new("powershell")
-                                        .arg("-command")
-                                        .arg("$Input | Set-Clipboard")
-                                        .stdin(std::process::Stdio::piped())
-                                        .spawn();
-                                    if let Ok(mut child) = child
-                                        && let Some(mut stdin) = child.stdin.take()
-                                    {
-                                        use std::io::Write;
-                                        let _ = stdin.write_all(text_to_copy.as_bytes());
-                                        drop(stdin);
-                                        let _ = child.wait();
-                                        status = "Copied to clipboard".to_string();
-                                    }
-                                }
                             }
                         }
                         // Multi-select: Ctrl+M toggles selection on current item
@@ -5562,9 +6697,58 @@ pub fn run_tui(
                                 );
                             }
                         }
+                        // Ctrl+E: export the hit, the selection or all results
+                        KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            if results.is_empty() {
+                                status = "No results to export".to_string();
+                            } else {
+                                export_modal.open(!selected.is_empty());
+                            }
+                        }
+                        // Ctrl+K: quick-pick a session by title or workspace
+                        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            match open_quick_pick(db_reader.as_ref()) {
+                                Ok(picker) => quick_pick = Some(picker),
+                                Err(msg) => status = msg,
+                            }
+                        }
+                        // Ctrl+G: usage dashboard
+                        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            dashboard.open = true;
+                            load_dashboard(&mut dashboard, db_reader.as_ref());
+                        }
+                        // Ctrl+X: relaunch the agent on the selected conversation
+                        KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            if let Some(hit) = active_hit(&panes, active_pane) {
+                                status = resume_session(
+                                    &mut terminal,
+                                    &resume_commands,
+                                    hit,
+                                    cached_detail.as_ref(),
+                                    db_reader.as_ref(),
+                                );
+                            }
+                        }
+                        // Ctrl+T: sessions laid out by day
+                        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            calendar.open();
+                            refresh_calendar(&mut calendar, db_reader.as_ref());
+                        }
+                        // Ctrl+L: switch to another profile's data dir
+                        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            status = open_profile_menu(
+                                &mut profile_menu_items,
+                                &mut profile_menu_selection,
+                                active_profile.as_deref(),
+                            );
+                            profile_menu_open = !profile_menu_items.is_empty();
+                        }
                         // Ctrl+O: Open all queued items in editor directly
                         KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            if selected.is_empty() {
+                            if selected.is_empty() && !nav.is_empty() {
+                                // Nothing queued: jump back instead
+                                nav_back = true;
+                            } else if selected.is_empty() {
                                 status = "No items queued. Ctrl+Enter to queue items.".to_string();
                                 open_confirm_armed = false;
                             } else if selected.len() >= OPEN_CONFIRM_THRESHOLD
@@ -5582,36 +6766,16 @@ pub fn run_tui(
                                         panes.get(*pane_idx).and_then(|p| p.hits.get(*hit_idx))
                                     })
                                     .collect();
-                                let editor = std::env::var("EDITOR")
-                                    .or_else(|_| std::env::var("VISUAL"))
-                                    .unwrap_or_else(|_| "code".to_string());
-                                let (editor_bin, editor_args) = split_editor_command(&editor);
-                                // Exit raw mode
-                                disable_raw_mode().ok();
-                                execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
-                                    .ok();
                                 for hit in &selected_hits {
-                                    let mut cmd = StdCommand::new(&editor_bin);
-                                    cmd.args(&editor_args);
-                                    if editor_bin == "code" {
-                                        if let Some(ln) = hit.line_number {
-                                            cmd.arg("--goto")
-                                                .arg(format!("{}:{}", hit.source_path, ln));
-                                        } else {
-                                            cmd.arg(&hit.source_path);
-                                        }
-                                    } else {
-                                        cmd.arg(&hit.source_path);
-                                    }
-                                    let _ = cmd.status();
+                                    let _ = editor::run_suspended(
+                                        &mut terminal,
+                                        &mut editor.command(&hit.source_path, hit.line_number),
+                                    );
                                 }
-                                execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
-                                    .ok();
-                                enable_raw_mode().ok();
                                 status = format!(
                                     "Opened {} files in {}",
                                     selected_hits.len(),
-                                    editor_bin
+                                    editor.name()
                                 );
                                 selected.clear();
                                 open_confirm_armed = false;
@@ -5625,6 +6789,7 @@ pub fn run_tui(
                             } else {
                                 show_bulk_modal = true;
                                 bulk_action_idx = 0;
+                                delete_armed = false;
                                 status = "Bulk actions: ↑↓ navigate · Enter execute · Esc cancel"
                                     .to_string();
                             }
@@ -5782,10 +6947,10 @@ pub fn run_tui(
                             status = format!(
                                 "Source: {}",
                                 match &filters.source_filter {
-                                    SourceFilter::All => "all sources",
-                                    SourceFilter::Local => "local only",
-                                    SourceFilter::Remote => "remote only",
-                                    SourceFilter::SourceId(id) => id.as_str(),
+                                    SourceFilter::All => "all sources".to_string(),
+                                    SourceFilter::Local => "local only".to_string(),
+                                    SourceFilter::Remote => "remote only".to_string(),
+                                    other => other.to_string(),
                                 }
                             );
                             dirty_since = Some(Instant::now());
@@ -5873,13 +7038,8 @@ pub fn run_tui(
                             if let Some(hit) = active_hit(&panes, active_pane) {
                                 // User committed to viewing result in editor - save query to history
                                 save_query_to_history(&query, &mut query_history, history_cap);
-                                let path = &hit.source_path;
-                                let mut cmd = StdCommand::new(&editor_bin);
-                                cmd.args(&editor_args);
-                                if let Some(line) = hit.line_number {
-                                    cmd.arg(format!("{editor_line_flag}{line}"));
-                                }
-                                let _ = cmd.arg(path).status();
+                                let (path, line) = editor_target(hit, cached_detail.as_ref());
+                                status = open_in_editor(&mut terminal, &editor, &path, line);
                             }
                         }
                         KeyCode::F(9) => {
@@ -5931,6 +7091,50 @@ pub fn run_tui(
                             }
                             // Detail pane local find/navigation
                             if matches!(focus_region, FocusRegion::Detail) {
+                                if c == 'e' {
+                                    if let Some(hit) = active_hit(&panes, active_pane) {
+                                        save_query_to_history(
+                                            &query,
+                                            &mut query_history,
+                                            history_cap,
+                                        );
+                                        let (path, line) =
+                                            editor_target(hit, cached_detail.as_ref());
+                                        status =
+                                            open_in_editor(&mut terminal, &editor, &path, line);
+                                    }
+                                    continue;
+                                }
+                                if c == 'y' || c == 'Y' {
+                                    // y: the snippet or matched message shown in the tab; Y: whole conversation
+                                    if let Some(hit) = active_hit(&panes, active_pane) {
+                                        status = if c == 'Y' {
+                                            match cached_detail.as_ref() {
+                                                Some((path, detail))
+                                                    if *path == hit.source_path =>
+                                                {
+                                                    let text = conversation_text(&detail.messages);
+                                                    clipboard::status(
+                                                        "conversation",
+                                                        clipboard::copy(&text),
+                                                    )
+                                                }
+                                                _ => "Conversation not loaded yet".to_string(),
+                                            }
+                                        } else if detail_tab == DetailTab::Snippets {
+                                            clipboard::status(
+                                                "snippet",
+                                                clipboard::copy(&hit.snippet),
+                                            )
+                                        } else {
+                                            clipboard::status(
+                                                "message",
+                                                clipboard::copy(&hit.content),
+                                            )
+                                        };
+                                    }
+                                    continue;
+                                }
                                 if c == '/' {
                                     input_mode = InputMode::DetailFind;
                                     input_buffer.clear();
@@ -6205,7 +7409,41 @@ pub fn run_tui(
                                     _ => {}
                                 }
                             }
+                            // After arrowing through results, Space and * select hits
+                            if list_keys
+                                && matches!(focus_region, FocusRegion::Results)
+                                && (c == ' ' || c == '*')
+                            {
+                                if c == ' ' {
+                                    if let Some(pane) = panes.get(active_pane) {
+                                        let key = (active_pane, pane.selected);
+                                        if !selected.remove(&key) {
+                                            selected.insert(key);
+                                        }
+                                    }
+                                } else {
+                                    let all: Vec<(usize, usize)> = panes
+                                        .iter()
+                                        .enumerate()
+                                        .flat_map(|(p, pane)| {
+                                            (0..pane.hits.len()).map(move |i| (p, i))
+                                        })
+                                        .collect();
+                                    if all.iter().all(|k| selected.contains(k)) {
+                                        selected.clear();
+                                    } else {
+                                        selected.extend(all);
+                                    }
+                                }
+                                open_confirm_armed = false;
+                                status = format!(
+                                    "{} selected · Space toggle · * all · A bulk actions · Esc clear",
+                                    selected.len()
+                                );
+                                continue;
+                            }
                             // All other characters pass through to query input
+                            list_keys = false;
                             query.push(c);
                             page = 0;
                             history_cursor = None;
@@ -6232,6 +7470,7 @@ pub fn run_tui(
                             } else {
                                 query.pop();
                             }
+                            list_keys = false;
                             page = 0;
                             history_cursor = None;
                             suggestion_idx = None;
@@ -6291,8 +7530,49 @@ pub fn run_tui(
                                 save_query_to_history(&query, &mut query_history, history_cap);
                                 // Open full-screen detail modal for parsed viewing
                                 show_detail_modal = true;
-                                modal_scroll = 0;
-                                status = "Detail view · Esc close · c copy · n nano".to_string();
+                                modal_match = None;
+                                raw_message = None;
+                                // Start at the matched message rather than the top
+                                let inner_width = terminal
+                                    .size()
+                                    .map(|r| {
+                                        centered_rect(90, 90, Rect::new(0, 0, r.width, r.height))
+                                            .width
+                                    })
+                                    .unwrap_or(80)
+                                    .saturating_sub(2);
+                                modal_scroll = active_hit(&panes, active_pane)
+                                    .and_then(|hit| hit.line_number)
+                                    .zip(cached_detail.as_ref())
+                                    .and_then(|(ln, (_, detail))| {
+                                        message_scroll_offset(
+                                            detail,
+                                            tools_expanded,
+                                            render_markdown,
+                                            ln as i64 - 1,
+                                            inner_width,
+                                        )
+                                    })
+                                    .unwrap_or(0);
+                                // Opened sessions are kept longest under `least-accessed` pruning,
+                                // and with `[ranking] click_feedback` the click feeds re-ranking
+                                if let Some(hit) = active_hit(&panes, active_pane)
+                                    && let Ok(storage) =
+                                        crate::storage::sqlite::SqliteStorage::open(&db_path)
+                                {
+                                    let _ = storage.record_access(&hit.source_path);
+                                    if click_feedback {
+                                        let _ = storage.record_click(
+                                            &hit.source_id,
+                                            &hit.source_path,
+                                            &hit.agent,
+                                            Some(&hit.workspace),
+                                            Some(&query),
+                                        );
+                                    }
+                                }
+                                status = "Detail view · Esc close · n/N matches · c copy · v view"
+                                    .to_string();
                             } else if active_hit(&panes, active_pane).is_some() {
                                 // User committed to viewing a result - save query to history
                                 save_query_to_history(&query, &mut query_history, history_cap);
@@ -6601,6 +7881,54 @@ pub fn run_tui(
                     }
                     _ => {}
                 },
+                InputMode::Tag => match key.code {
+                    KeyCode::Esc => {
+                        input_mode = InputMode::Query;
+                        input_buffer.clear();
+                        status = format!("Tagging cancelled; {} still selected", selected.len());
+                    }
+                    KeyCode::Enter => {
+                        let tag = input_buffer.trim().to_string();
+                        let sessions =
+                            hit_sessions(selected.iter().filter_map(|(pane_idx, hit_idx)| {
+                                panes.get(*pane_idx).and_then(|p| p.hits.get(*hit_idx))
+                            }));
+                        let result = crate::storage::sqlite::SqliteStorage::open(&db_path)
+                            .and_then(|storage| {
+                                let mut tagged = 0;
+                                for (source_id, source_path) in &sessions {
+                                    for id in
+                                        storage.session_conversation_ids(source_id, source_path)?
+                                    {
+                                        if storage.add_tag(id, &tag)? {
+                                            tagged += 1;
+                                        }
+                                    }
+                                }
+                                Ok(tagged)
+                            });
+                        match result {
+                            Ok(tagged) => {
+                                toasts.push(Toast::success(format!(
+                                    "Tagged {tagged} conversations with '{tag}'"
+                                )));
+                                selected.clear();
+                                open_confirm_armed = false;
+                                if let Some(client) = &search_client {
+                                    client.invalidate_cache();
+                                }
+                            }
+                            Err(e) => toasts.push(Toast::error(format!("Failed to tag: {e}"))),
+                        }
+                        input_mode = InputMode::Query;
+                        input_buffer.clear();
+                    }
+                    KeyCode::Backspace => {
+                        input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => input_buffer.push(c),
+                    _ => {}
+                },
                 InputMode::DetailFind => match key.code {
                     KeyCode::Esc => {
                         detail_find = None;
@@ -6642,6 +7970,17 @@ pub fn run_tui(
         }
 
         if last_tick.elapsed() >= tick_rate {
+            if daemon_checked_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(5)) {
+                daemon_checked_at = Some(Instant::now());
+                let now_ms = Utc::now().timestamp_millis();
+                let next = crate::daemon::read_status(&data_dir)
+                    .filter(|s| s.is_alive(now_ms) && s.pid != std::process::id())
+                    .map(|s| s.progress);
+                if next != daemon_progress {
+                    daemon_progress = next;
+                    needs_draw = true;
+                }
+            }
             if let Some(client) = &search_client {
                 let should_search = dirty_since.is_some_and(|t| t.elapsed() >= debounce);
 
@@ -6651,8 +7990,9 @@ pub fn run_tui(
                         .map(|h| h.agent.clone())
                         .or_else(|| panes.get(active_pane).map(|p| p.agent.clone()));
                     let prev_path = active_hit(&panes, active_pane).map(|h| h.source_path.clone());
-                    let lexical_query = apply_match_mode(&query, match_mode);
-                    let semantic_query = query.clone();
+                    let expanded_query = query_aliases.expand(&query);
+                    let lexical_query = apply_match_mode(&expanded_query, match_mode);
+                    let semantic_query = expanded_query.clone();
                     // Use search_with_fallback for implicit wildcard expansion on sparse results
                     const SPARSE_THRESHOLD: usize = 3;
                     let search_started = Instant::now();
@@ -6988,6 +8328,10 @@ pub fn run_tui(
                     needs_draw = true;
                 }
             }
+            if !toasts.is_empty() {
+                toasts.tick();
+                needs_draw = true;
+            }
             last_tick = Instant::now();
         }
     }
@@ -7050,6 +8394,7 @@ pub fn run_tui(
             RankingMode::DateOldest => "oldest".into(),
             RankingMode::Balanced => "balanced".into(),
         }),
+        markdown: Some(render_markdown),
     };
     save_state(&state_path, &persisted_out);
 
@@ -7086,6 +8431,35 @@ mod tests {
     use std::path::PathBuf;
     use tempfile::TempDir;
 
+    #[test]
+    fn index_status_segment_reports_additions_and_daemon_progress() {
+        use crate::indexer::ProgressSnapshot;
+
+        assert_eq!(
+            index_status_segment(&ProgressSnapshot::default(), false),
+            None
+        );
+        let idle = ProgressSnapshot {
+            added: 2,
+            last_commit_at: Some(Utc::now().timestamp_millis()),
+            ..Default::default()
+        };
+        assert_eq!(
+            index_status_segment(&idle, false).as_deref(),
+            Some("+2 new · committed just now")
+        );
+        let scanning = ProgressSnapshot {
+            phase: 1,
+            current: 3,
+            total: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            index_status_segment(&scanning, true).as_deref(),
+            Some("daemon ⟳ 3/10 scanned")
+        );
+    }
+
     #[test]
     fn state_roundtrip_persists_mode_and_context() {
         let dir = TempDir::new().unwrap();
@@ -7110,6 +8484,7 @@ mod tests {
             }]),
             per_pane_limit: Some(12),
             ranking_mode: Some("balanced".into()),
+            markdown: Some(false),
         };
         save_state(&path, &state);
 
@@ -7126,6 +8501,18 @@ mod tests {
         // Verify new fields (bead 46t.1)
         assert_eq!(loaded.per_pane_limit, Some(12));
         assert_eq!(loaded.ranking_mode.as_deref(), Some("balanced"));
+        assert_eq!(loaded.markdown, Some(false));
+    }
+
+    #[test]
+    fn context_window_grows_and_shrinks_without_wrapping() {
+        assert_eq!(ContextWindow::Small.shrink(), ContextWindow::Small);
+        assert_eq!(ContextWindow::Small.grow(), ContextWindow::Medium);
+        assert_eq!(ContextWindow::XLarge.grow(), ContextWindow::XLarge);
+        assert_eq!(ContextWindow::XLarge.shrink(), ContextWindow::Large);
+        assert_eq!(ContextWindow::Medium.snippet_lines(5), 5);
+        assert_eq!(ContextWindow::Small.snippet_lines(8), 3);
+        assert_eq!(ContextWindow::XLarge.snippet_lines(5), 20);
     }
 
     #[test]
@@ -7237,15 +8624,28 @@ mod tests {
             content: "hello world".into(),
             extra_json: json!({}),
             snippets: vec![],
+            approx_tokens: None,
+            source_path: None,
+            source_line: None,
         };
 
         let detail = ConversationView {
             convo,
             messages: vec![message],
             workspace: None,
+            notes: HashMap::from([(
+                1,
+                vec![crate::model::types::Annotation {
+                    id: 7,
+                    message_id: 1,
+                    note: "check the greeting".into(),
+                    created_at: msg_ts,
+                    updated_at: msg_ts,
+                }],
+            )]),
         };
 
-        let lines = render_parsed_content(&detail, "", palette);
+        let lines = render_parsed_content(&detail, "", palette, false, true);
         let joined = lines
             .iter()
             .map(line_to_string)
@@ -7253,6 +8653,7 @@ mod tests {
             .join("\n");
 
         assert!(joined.contains("2024-01-02 03:04:05 UTC"));
+        assert!(joined.contains("📝 check the greeting"));
         assert!(joined.contains("2024-01-02 04:05:06 UTC"));
         assert!(
             !joined.contains("ago"),
@@ -7260,6 +8661,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn plain_text_toggle_keeps_markdown_source() {
+        let palette = ThemePalette::dark();
+        let content = "## Plan\n- use `serde`\n```rust\nfn main() {}\n```";
+        let render =
+            |lines: Vec<Line<'static>>| lines.iter().map(line_to_string).collect::<Vec<_>>();
+
+        let markdown = render(parse_message_content(content, "", palette));
+        assert!(markdown.iter().any(|l| l.contains("• use")));
+        assert!(markdown.iter().any(|l| l.contains("┌── rust")));
+        assert!(!markdown.iter().any(|l| l.contains("```")));
+
+        let plain = render(plain_message_content(content, "serde", palette));
+        assert_eq!(plain.len(), 5);
+        assert_eq!(plain[0], "  ## Plan");
+        assert_eq!(plain[1], "  - use `serde`");
+        assert_eq!(plain[2], "  ```rust");
+    }
+
+    #[test]
+    fn opening_detail_scrolls_to_matched_message() {
+        let message = |idx: i64, content: &str| Message {
+            id: Some(idx + 1),
+            idx,
+            role: MessageRole::User,
+            author: None,
+            created_at: None,
+            content: content.into(),
+            extra_json: json!({}),
+            snippets: vec![],
+            approx_tokens: None,
+            source_path: None,
+            source_line: None,
+        };
+        let detail = ConversationView {
+            convo: Conversation {
+                id: Some(1),
+                agent_slug: "codex".into(),
+                workspace: None,
+                external_id: None,
+                title: None,
+                source_path: PathBuf::from("/tmp/test"),
+                started_at: None,
+                ended_at: None,
+                approx_tokens: None,
+                metadata_json: json!({}),
+                messages: Vec::new(),
+                source_id: "local".to_string(),
+                origin_host: None,
+            },
+            messages: vec![message(0, &"x".repeat(100)), message(1, "second")],
+            workspace: None,
+            notes: HashMap::new(),
+        };
+
+        // Separator and blank line precede the first message
+        assert_eq!(message_scroll_offset(&detail, false, true, 0, 200), Some(2));
+        // At 40 columns the 60-char separator takes two rows
+        assert_eq!(message_scroll_offset(&detail, false, true, 0, 40), Some(3));
+        let wide = message_scroll_offset(&detail, false, true, 1, 200).unwrap();
+        let narrow = message_scroll_offset(&detail, false, true, 1, 40).unwrap();
+        // The 100-char first message wraps too
+        assert!(narrow >= wide + 3);
+        let (lines, _) =
+            render_parsed_content_indexed(&detail, "", ThemePalette::dark(), false, true);
+        assert!(line_to_string(&lines[wide as usize]).contains("You"));
+        assert_eq!(message_scroll_offset(&detail, false, true, 9, 40), None);
+    }
+
     // ==========================================================================
     // Navigation State Tests (tst.ui.nav)
     // Tests for TUI navigation state machine behavior
@@ -7995,6 +9465,62 @@ mod tests {
         assert_eq!(matches[0], 0);
     }
 
+    #[test]
+    fn match_rows_counts_wrapped_rows_before_each_match() {
+        let lines = vec![
+            Line::from("x".repeat(25)),
+            Line::from("needle one"),
+            Line::from("filler"),
+            Line::from("NEEDLE two"),
+        ];
+        // First line wraps onto three 10-column rows
+        assert_eq!(match_rows(&lines, "needle", 10), vec![3, 5]);
+        assert!(match_rows(&lines, "  ", 10).is_empty());
+    }
+
+    #[test]
+    fn similar_query_ors_distinctive_words() {
+        assert_eq!(
+            similar_query("Fix the login redirect loop in the auth middleware").as_deref(),
+            Some("login OR redirect OR loop OR auth OR middleware")
+        );
+        assert_eq!(
+            similar_query("Please make this work, then this").as_deref(),
+            Some("work")
+        );
+        assert_eq!(similar_query("a b c"), None);
+        assert_eq!(search_label("  "), "All sessions");
+        assert_eq!(search_label("auth"), "“auth”");
+    }
+
+    #[test]
+    fn step_match_wraps_in_both_directions() {
+        let rows = [4, 10, 30];
+        assert_eq!(step_match(&rows, 0, true), Some(0));
+        assert_eq!(step_match(&rows, 4, true), Some(1));
+        assert_eq!(step_match(&rows, 30, true), Some(0));
+        assert_eq!(step_match(&rows, 10, false), Some(0));
+        assert_eq!(step_match(&rows, 4, false), Some(2));
+        assert_eq!(step_match(&[], 4, true), None);
+    }
+
+    #[test]
+    fn profile_choices_lead_with_default_and_keep_implicit_active() {
+        let configured = ["personal".to_string(), "work".to_string()];
+        assert_eq!(
+            profile_choices(configured.clone(), None),
+            [DEFAULT_PROFILE, "personal", "work"]
+        );
+        assert_eq!(
+            profile_choices(configured.clone(), Some("work")),
+            [DEFAULT_PROFILE, "personal", "work"]
+        );
+        assert_eq!(
+            profile_choices(configured, Some("scratch")),
+            [DEFAULT_PROFILE, "personal", "work", "scratch"]
+        );
+    }
+
     #[test]
     fn active_hit_returns_correct_selection() {
         let panes = vec![
@@ -8372,6 +9898,18 @@ mod tests {
         assert!(!show_bulk_modal);
     }
 
+    #[test]
+    fn bulk_actions_act_once_per_session() {
+        let hits = [
+            make_hit("codex", "/a", 3.0, "one"),
+            make_hit("codex", "/b", 2.0, "two"),
+            make_hit("codex", "/a", 1.0, "three"),
+        ];
+        let sessions = hit_sessions(&hits);
+        let paths: Vec<&str> = sessions.iter().map(|(_, p)| p.as_str()).collect();
+        assert_eq!(paths, ["/a", "/b"]);
+    }
+
     #[test]
     fn bulk_modal_opens_with_selection() {
         let mut selected: HashSet<(usize, usize)> = HashSet::new();
@@ -312,8 +312,12 @@ async fn fetch_latest_release() -> Result<GitHubRelease> {
         .context("parsing release JSON")
 }
 
-/// Get path to update state file
+/// Get path to update state file: in the active profile's data dir, else the
+/// platform data dir
 fn state_path() -> PathBuf {
+    if let Some(dir) = crate::profiles::active_data_dir() {
+        return dir.join("update_state.json");
+    }
     directories::ProjectDirs::from("com", "coding-agent-search", "coding-agent-search").map_or_else(
         || PathBuf::from("update_state.json"),
         |dirs| dirs.data_dir().join("update_state.json"),
@@ -109,6 +109,80 @@ fn index_force_rebuild_flag() {
     assert!(data_dir.join("agent_search.db").exists());
 }
 
+#[test]
+fn force_rebuild_keeps_tags_and_refills_the_index_from_the_db() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path();
+    let codex_home = home.join(".codex");
+    let data_dir = home.join("cass_data");
+    fs::create_dir_all(&data_dir).unwrap();
+    make_codex_session(&codex_home, "2025/11/20", "rollout-1.jsonl", "zeta_content");
+    let data = data_dir.to_str().unwrap();
+
+    let run = |args: &[&str]| {
+        let mut cmd = base_cmd(home);
+        cmd.env("CODEX_HOME", &codex_home);
+        cmd.args(args);
+        let output = cmd.output().expect("run cass");
+        assert!(
+            output.status.success(),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    run(&["index", "--full", "--data-dir", data, "--json"]);
+    run(&["tag", "add", "1", "keepme", "--data-dir", data, "--json"]);
+    run(&["index", "--force-rebuild", "--data-dir", data, "--json"]);
+
+    let tags = run(&["tag", "list", "1", "--data-dir", data, "--json"]);
+    assert!(tags.contains("keepme"), "tag survived rebuild: {tags}");
+    let hits = run(&["search", "zeta_content", "--robot", "--data-dir", data]);
+    assert!(
+        hits.contains("zeta_content"),
+        "rebuilt index finds it: {hits}"
+    );
+}
+
+#[test]
+fn tag_rm_can_be_undone() {
+    let tmp = TempDir::new().unwrap();
+    let home = tmp.path();
+    let codex_home = home.join(".codex");
+    let data_dir = home.join("cass_data");
+    fs::create_dir_all(&data_dir).unwrap();
+    make_codex_session(&codex_home, "2025/11/20", "rollout-1.jsonl", "undo_content");
+    let data = data_dir.to_str().unwrap();
+
+    let run = |args: &[&str]| {
+        let mut cmd = base_cmd(home);
+        cmd.env("CODEX_HOME", &codex_home);
+        cmd.args(args);
+        let output = cmd.output().expect("run cass");
+        assert!(
+            output.status.success(),
+            "{args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    run(&["index", "--full", "--data-dir", data, "--json"]);
+    run(&["tag", "add", "1", "keepme", "--data-dir", data, "--json"]);
+    run(&["tag", "rm", "1", "keepme", "--data-dir", data, "--json"]);
+    let tags = run(&["tag", "list", "1", "--data-dir", data, "--json"]);
+    assert!(!tags.contains("keepme"), "tag removed: {tags}");
+
+    let undone = run(&["undo", "--data-dir", data, "--json"]);
+    assert!(
+        undone.contains("remove_conversation_tag"),
+        "undid the removal: {undone}"
+    );
+    let tags = run(&["tag", "list", "1", "--data-dir", data, "--json"]);
+    assert!(tags.contains("keepme"), "tag restored: {tags}");
+}
+
 /// Creates a Codex session file with the modern envelope format.
 fn make_codex_session(root: &std::path::Path, date_path: &str, filename: &str, content: &str) {
     let sessions = root.join(format!("sessions/{date_path}"));
@@ -812,6 +812,111 @@ fn search_robot_meta_includes_fallback_and_cache_stats() {
     );
 }
 
+#[test]
+fn stats_storage_reads_without_writing() {
+    let tmp = TempDir::new().unwrap();
+    let db = tmp.path().join("agent_search.db");
+    std::fs::copy("tests/fixtures/search_demo_data/agent_search.db", &db).unwrap();
+    let before = std::fs::read(&db).unwrap();
+
+    let mut cmd = base_cmd();
+    cmd.args(["stats", "storage", "--json", "--data-dir"])
+        .arg(tmp.path());
+    let assert = cmd.assert().success();
+    let json: Value =
+        serde_json::from_str(String::from_utf8_lossy(&assert.get_output().stdout).trim())
+            .expect("valid JSON");
+
+    // Today's sample is reported but not stored
+    assert!(!json["growth"].as_array().unwrap().is_empty());
+    assert!(
+        std::fs::read(&db).unwrap() == before,
+        "stats storage modified the database"
+    );
+}
+
+#[test]
+fn writing_commands_refuse_while_the_index_is_locked() {
+    let tmp = TempDir::new().unwrap();
+    std::fs::copy(
+        "tests/fixtures/search_demo_data/agent_search.db",
+        tmp.path().join("agent_search.db"),
+    )
+    .unwrap();
+    let _lock = coding_agent_search::indexer::lock::IndexLock::acquire(tmp.path()).unwrap();
+
+    for args in [
+        &["db", "optimize", "--json"][..],
+        &["prune", "--older-than", "1d", "--json"],
+        &["compact", "--json"],
+        &["embed", "--embedder", "hash", "--json"],
+        &[
+            "update",
+            "--filter",
+            "agent:codex",
+            "--add-tag",
+            "x",
+            "--json",
+        ],
+    ] {
+        let mut cmd = base_cmd();
+        cmd.args(args).arg("--data-dir").arg(tmp.path());
+        let output = cmd.assert().failure().get_output().clone();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("index-locked"), "{args:?}: {stderr}");
+    }
+
+    // A dry run only reads
+    let mut cmd = base_cmd();
+    cmd.args([
+        "prune",
+        "--older-than",
+        "1d",
+        "--dry-run",
+        "--json",
+        "--data-dir",
+    ])
+    .arg(tmp.path());
+    cmd.assert().success();
+}
+
+#[test]
+fn db_import_refuses_a_database_from_a_newer_schema() {
+    let tmp = TempDir::new().unwrap();
+    let archive = tmp.path().join("demo.cassar");
+    let mut cmd = base_cmd();
+    cmd.args(["db", "export"]).arg(&archive).args([
+        "--json",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+    cmd.assert().success();
+
+    let target = tmp.path().join("target");
+    std::fs::create_dir_all(&target).unwrap();
+    std::fs::copy(
+        "tests/fixtures/search_demo_data/agent_search.db",
+        target.join("agent_search.db"),
+    )
+    .unwrap();
+    rusqlite::Connection::open(target.join("agent_search.db"))
+        .unwrap()
+        .execute(
+            "UPDATE meta SET value = '999' WHERE key = 'schema_version'",
+            [],
+        )
+        .unwrap();
+
+    let mut cmd = base_cmd();
+    cmd.args(["db", "import"])
+        .arg(&archive)
+        .args(["--on-conflict", "skip", "--json", "--data-dir"])
+        .arg(&target);
+    let output = cmd.assert().failure().get_output().clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("db-schema"), "{stderr}");
+}
+
 #[test]
 fn stats_json_reports_counts() {
     let mut cmd = base_cmd();
@@ -840,6 +945,282 @@ fn stats_json_reports_counts() {
     );
 }
 
+#[test]
+fn stats_json_carries_versioned_dashboard_fields() {
+    let mut cmd = base_cmd();
+    cmd.args([
+        "stats",
+        "--json",
+        "--days",
+        "0",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+
+    assert_eq!(json["schema_version"], 2);
+    assert!(json["by_agent"][0]["messages"].as_i64().unwrap_or(0) > 0);
+    assert!(json["daily"].as_array().is_some_and(Vec::is_empty));
+    assert!(json["size"]["db_bytes"].as_u64().unwrap_or(0) > 0);
+    assert!(json["index"]["stale"].is_boolean());
+}
+
+#[test]
+fn config_set_keeps_comments_and_get_applies_env_overrides() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("cass").join("config.toml");
+    fs::create_dir_all(config.parent().unwrap()).unwrap();
+    fs::write(
+        &config,
+        "# tuned for the laptop\n[storage]\nmax_size = \"1GB\" # hard cap\n",
+    )
+    .unwrap();
+
+    let mut cmd = base_cmd();
+    cmd.env("XDG_CONFIG_HOME", tmp.path()).args([
+        "config",
+        "set",
+        "storage.compression",
+        "true",
+        "--json",
+    ]);
+    let assert = cmd.assert().success();
+    let json: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+    assert_eq!(json["value"], true);
+    let content = fs::read_to_string(&config).unwrap();
+    assert!(content.contains("# tuned for the laptop"));
+    assert!(content.contains("max_size = \"1GB\" # hard cap"));
+    assert!(content.contains("compression = true"));
+
+    let mut cmd = base_cmd();
+    cmd.env("XDG_CONFIG_HOME", tmp.path())
+        .env("CASS_SEMANTIC_EMBEDDER", "hash")
+        .args(["config", "get", "--json"]);
+    let assert = cmd.assert().success();
+    let json: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+    assert_eq!(json["embedding"]["embedder"], "hash");
+    assert_eq!(json["storage"]["compression"], true);
+    assert_eq!(json["env"]["CASS_SEMANTIC_EMBEDDER"], "hash");
+}
+
+#[test]
+fn export_query_writes_search_results() {
+    let mut cmd = base_cmd();
+    cmd.args([
+        "export",
+        "--query",
+        "hello",
+        "--format",
+        "json",
+        "--limit",
+        "3",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+    assert_eq!(json["query"], "hello");
+    assert!(!json["hits"].as_array().expect("hits").is_empty());
+}
+
+#[test]
+fn export_query_applies_search_filters() {
+    let mut cmd = base_cmd();
+    cmd.args([
+        "export",
+        "--query",
+        "hello",
+        "--agent",
+        "no-such-agent",
+        "--format",
+        "json",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+    assert_eq!(json["count"], 0);
+}
+
+#[test]
+fn export_conversation_writes_its_messages() {
+    // Exporting records an access, so work on a copy of the fixture database
+    let tmp = TempDir::new().unwrap();
+    fs::copy(
+        "tests/fixtures/search_demo_data/agent_search.db",
+        tmp.path().join("agent_search.db"),
+    )
+    .unwrap();
+
+    let mut cmd = base_cmd();
+    cmd.args(["export", "--conversation", "4", "--format", "json"])
+        .arg("--data-dir")
+        .arg(tmp.path());
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+    assert_eq!(json["conversation"]["id"], 4);
+    let messages = json["messages"].as_array().expect("messages");
+    assert_eq!(json["count"], messages.len());
+    assert!(!messages.is_empty());
+}
+
+#[test]
+fn export_records_the_line_each_message_came_from() {
+    let tmp = TempDir::new().unwrap();
+    let session = tmp.path().join("session.jsonl");
+    std::fs::write(
+        &session,
+        concat!(
+            r#"{"role":"user","content":"first question"}"#,
+            "\n\n",
+            r#"{"role":"assistant","content":"an answer"}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let mut cmd = base_cmd();
+    cmd.arg("export").arg(&session).args(["--format", "json"]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+    let lines: Vec<_> = json
+        .as_array()
+        .expect("messages")
+        .iter()
+        .map(|m| m["source_line"].as_i64())
+        .collect();
+    assert_eq!(lines, [Some(1), Some(3)]);
+    assert_eq!(json[0]["source_path"], session.to_string_lossy().as_ref());
+
+    let mut cmd = base_cmd();
+    cmd.arg("export").arg(&session).args(["--format", "text"]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains(&format!("Source: {}:3", session.display())));
+}
+
+#[test]
+fn export_query_rejects_html() {
+    let mut cmd = base_cmd();
+    cmd.args([
+        "export",
+        "--query",
+        "hello",
+        "--format",
+        "html",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+    cmd.assert().failure().code(2);
+}
+
+#[test]
+fn stats_daily_counts_messages_on_the_day_they_were_sent() {
+    let tmp = TempDir::new().unwrap();
+    let db = tmp.path().join("agent_search.db");
+    fs::copy("tests/fixtures/search_demo_data/agent_search.db", &db).unwrap();
+
+    // One conversation started two days ago and continued today
+    let now = chrono::Utc::now().timestamp_millis();
+    let two_days_ago = now - 2 * 86_400_000;
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let conv_id: i64 = conn
+        .query_row("SELECT MIN(id) FROM conversations", [], |r| r.get(0))
+        .unwrap();
+    conn.execute(
+        "UPDATE conversations SET started_at = ?1 WHERE id = ?2",
+        rusqlite::params![two_days_ago, conv_id],
+    )
+    .unwrap();
+    conn.execute(
+        "UPDATE messages SET created_at = CASE WHEN idx = 0 THEN ?1 ELSE ?2 END
+         WHERE conversation_id = ?3",
+        rusqlite::params![two_days_ago, now, conv_id],
+    )
+    .unwrap();
+    let messages: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?",
+            [conv_id],
+            |r| r.get(0),
+        )
+        .unwrap();
+    drop(conn);
+
+    let mut cmd = base_cmd();
+    cmd.args(["stats", "--json", "--days", "7", "--data-dir"])
+        .arg(tmp.path());
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+
+    let local_day = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .unwrap()
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d")
+            .to_string()
+    };
+    let daily: Vec<(String, i64, i64)> = json["daily"]
+        .as_array()
+        .expect("daily")
+        .iter()
+        .map(|d| {
+            (
+                d["day"].as_str().unwrap().to_string(),
+                d["conversations"].as_i64().unwrap(),
+                d["messages"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        daily,
+        [
+            (local_day(two_days_ago), 1, 1),
+            (local_day(now), 1, messages - 1),
+        ]
+    );
+}
+
+#[test]
+fn graph_json_links_only_the_selected_conversations() {
+    let mut cmd = base_cmd();
+    cmd.args([
+        "graph",
+        "--format",
+        "json",
+        "--limit",
+        "2",
+        "--data-dir",
+        "tests/fixtures/search_demo_data",
+    ]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: Value = serde_json::from_str(stdout.trim()).expect("valid JSON");
+
+    let ids: HashSet<i64> = json["nodes"]
+        .as_array()
+        .expect("nodes")
+        .iter()
+        .filter_map(|n| n["id"].as_i64())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    for edge in json["edges"].as_array().expect("edges") {
+        assert!(ids.contains(&edge["source"].as_i64().unwrap()));
+        assert!(ids.contains(&edge["target"].as_i64().unwrap()));
+    }
+}
+
 #[test]
 fn diag_json_reports_database_state() {
     let mut cmd = base_cmd();
@@ -984,11 +1365,18 @@ fn find_arg<'a>(cmd: &'a Value, name: &str) -> &'a Value {
 fn introspect_commands_match_clap_subcommands() {
     let json = fetch_introspect_json();
 
-    let clap_cmd = Cli::command();
-    let clap_commands: HashSet<String> = clap_cmd
-        .get_subcommands()
-        .map(|c: &clap::Command| c.get_name().to_string())
-        .collect();
+    // Building the full clap tree needs more stack than a debug test thread has.
+    let clap_commands: HashSet<String> = std::thread::Builder::new()
+        .stack_size(16 * 1024 * 1024)
+        .spawn(|| {
+            Cli::command()
+                .get_subcommands()
+                .map(|c: &clap::Command| c.get_name().to_string())
+                .collect()
+        })
+        .expect("spawn clap thread")
+        .join()
+        .expect("build clap command");
 
     let introspect_commands: HashSet<String> = json["commands"]
         .as_array()
@@ -3500,3 +3888,22 @@ fn introspect_all_integer_options_documented() {
         "health --stale-threshold should be integer type"
     );
 }
+
+#[test]
+fn agents_leaves_out_the_cass_source_reader() {
+    let tmp = TempDir::new().unwrap();
+    let mut cmd = base_cmd();
+    cmd.args(["agents", "--json", "--data-dir"]).arg(tmp.path());
+    let assert = cmd.assert().success();
+    let json: Value =
+        serde_json::from_str(String::from_utf8_lossy(&assert.get_output().stdout).trim())
+            .expect("valid JSON");
+    let slugs: Vec<&str> = json["agents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|a| a["slug"].as_str())
+        .collect();
+    assert!(slugs.contains(&"codex"), "{slugs:?}");
+    assert!(!slugs.contains(&"cass"), "{slugs:?}");
+}
@@ -4,6 +4,8 @@
 //! - sources add (with --no-test to skip SSH)
 //! - sources list
 //! - sources remove
+//! - sources show
+//! - sources test (unreachable hosts only)
 //! - sources doctor (limited without actual SSH)
 //! - sources sync (dry-run only)
 //!
@@ -222,6 +224,50 @@ fn sources_add_no_test() {
     );
 }
 
+/// Test: sources add --json reports the added source.
+#[test]
+fn sources_add_json() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args([
+            "sources",
+            "add",
+            "user@myserver.local",
+            "--name",
+            "myserver",
+            "--path",
+            "~/.claude/projects",
+            "--no-test",
+            "--json",
+        ])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output()
+        .expect("sources add --json command");
+
+    assert!(
+        output.status.success(),
+        "sources add --json failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("valid JSON output");
+    assert_eq!(json["added"]["name"], "myserver");
+    assert_eq!(json["added"]["host"], "user@myserver.local");
+    assert_eq!(json["added"]["paths"][0], "~/.claude/projects");
+    assert!(
+        json["config_path"]
+            .as_str()
+            .is_some_and(|p| p.ends_with("sources.toml"))
+    );
+    assert!(read_sources_config(&config_dir).contains("myserver"));
+}
+
 /// Test: sources add with explicit paths.
 #[test]
 fn sources_add_explicit_paths() {
@@ -545,6 +591,320 @@ paths = ["~/.claude/projects"]
     );
 }
 
+/// Test: sources remove --json reports the removed source and purged data.
+#[test]
+fn sources_remove_json() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    let data_dir = tmp.path().join("data");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    let source_data = data_dir.join("cass").join("remotes").join("laptop");
+    fs::create_dir_all(&source_data).unwrap();
+    fs::write(source_data.join("session.jsonl"), "test data").unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "laptop"
+type = "ssh"
+host = "user@laptop.local"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+    let _guard_data = EnvGuard::set("XDG_DATA_HOME", data_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "remove", "laptop", "--purge", "-y", "--json"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .env("XDG_DATA_HOME", &data_dir)
+        .output()
+        .expect("sources remove --json command");
+
+    assert!(
+        output.status.success(),
+        "sources remove --json failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("valid JSON output");
+    assert_eq!(json["removed"], "laptop");
+    assert_eq!(
+        json["purged_dir"],
+        source_data.to_string_lossy().as_ref(),
+        "Expected the purged directory"
+    );
+    assert!(!source_data.exists(), "Synced data not purged");
+    assert!(!read_sources_config(&config_dir).contains("laptop"));
+}
+
+/// Test: sources remove --json needs --yes, since it cannot prompt.
+#[test]
+fn sources_remove_json_requires_yes() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "laptop"
+type = "ssh"
+host = "user@laptop.local"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "remove", "laptop", "--json"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output()
+        .expect("sources remove --json command");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(read_sources_config(&config_dir).contains("laptop"));
+}
+
+// =============================================================================
+// sources show tests
+// =============================================================================
+
+/// Test: sources show prints the source's configuration and last sync.
+#[test]
+fn sources_show_with_last_sync() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    let data_dir = tmp.path().join("data");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::create_dir_all(data_dir.join("cass")).unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "laptop"
+type = "ssh"
+host = "user@laptop.local"
+paths = ["~/.claude/projects"]
+
+[[sources.path_mappings]]
+from = "/home/user/projects"
+to = "/Users/me/projects"
+"#,
+    );
+    fs::write(
+        data_dir.join("cass").join("sync_status.json"),
+        r#"{"sources": {"laptop": {
+            "last_sync": 1700000000000,
+            "last_result": "success",
+            "files_synced": 12,
+            "bytes_transferred": 2048,
+            "duration_ms": 900
+        }}}"#,
+    )
+    .unwrap();
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+    let _guard_data = EnvGuard::set("XDG_DATA_HOME", data_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "show", "laptop"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .env("XDG_DATA_HOME", &data_dir)
+        .output()
+        .expect("sources show command");
+
+    assert!(
+        output.status.success(),
+        "sources show failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Host: user@laptop.local"), "got: {stdout}");
+    assert!(stdout.contains("- ~/.claude/projects"), "got: {stdout}");
+    assert!(
+        stdout.contains("/home/user/projects -> /Users/me/projects"),
+        "got: {stdout}"
+    );
+    assert!(
+        stdout.contains("Last sync: 2023-11-14 22:13 UTC, ok (12 files"),
+        "got: {stdout}"
+    );
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "show", "laptop", "--json"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .env("XDG_DATA_HOME", &data_dir)
+        .output()
+        .expect("sources show --json command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("valid JSON output");
+    assert_eq!(json["source"]["name"], "laptop");
+    assert_eq!(json["last_sync"]["files_synced"], 12);
+    assert_eq!(
+        json["mirror_dir"],
+        data_dir
+            .join("cass")
+            .join("remotes")
+            .join("laptop")
+            .join("mirror")
+            .to_string_lossy()
+            .as_ref()
+    );
+}
+
+/// Test: sources show for a source that was never synced.
+#[test]
+fn sources_show_never_synced() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    let data_dir = tmp.path().join("data");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::create_dir_all(&data_dir).unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "laptop"
+type = "ssh"
+host = "user@laptop.local"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+    let _guard_data = EnvGuard::set("XDG_DATA_HOME", data_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "show", "laptop", "--json"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .env("XDG_DATA_HOME", &data_dir)
+        .output()
+        .expect("sources show --json command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("valid JSON output");
+    assert!(json["last_sync"].is_null());
+}
+
+/// Test: sources show with an unknown name fails with not-found.
+#[test]
+fn sources_show_nonexistent() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "laptop"
+type = "ssh"
+host = "user@laptop.local"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "show", "desktop"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output()
+        .expect("sources show command");
+
+    assert_eq!(output.status.code(), Some(13));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("not found"),
+        "Expected not found error, got: {stderr}"
+    );
+}
+
+// =============================================================================
+// sources test tests
+// =============================================================================
+
+/// Test: sources test reports an unreachable host and exits 1.
+#[test]
+fn sources_test_unreachable_json() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    // Nothing accepts a batch-mode login as nobody on the loopback address
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "loopback"
+type = "ssh"
+host = "nobody@127.0.0.1"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "test", "loopback", "--json"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output()
+        .expect("sources test --json command");
+
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("valid JSON output");
+    assert_eq!(json["source"], "loopback");
+    assert_eq!(json["host"], "nobody@127.0.0.1");
+    assert_eq!(json["reachable"], false);
+    assert_eq!(json["check"]["status"], "fail");
+}
+
+/// Test: sources test refuses sources that have no host to reach.
+#[test]
+fn sources_test_local_source() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    create_sources_config(
+        &config_dir,
+        r#"
+[[sources]]
+name = "here"
+type = "local"
+paths = ["~/.claude/projects"]
+"#,
+    );
+
+    let _guard_config = EnvGuard::set("XDG_CONFIG_HOME", config_dir.to_string_lossy());
+
+    let output = cargo_bin_cmd!("cass")
+        .args(["sources", "test", "here"])
+        .env("XDG_CONFIG_HOME", &config_dir)
+        .output()
+        .expect("sources test command");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("not a remote source"),
+        "Expected usage error, got: {stderr}"
+    );
+}
+
 // =============================================================================
 // sources doctor tests
 // =============================================================================
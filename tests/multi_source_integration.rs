@@ -35,6 +35,9 @@ fn msg(idx: i64, created_at: i64, content: &str) -> Message {
         content: content.to_string(),
         extra_json: json!({}),
         snippets: vec![],
+        approx_tokens: None,
+        source_path: None,
+        source_line: None,
     }
 }
 
@@ -106,6 +109,7 @@ fn norm_msg(
         content: content.to_string(),
         extra: json!({}),
         snippets: vec![],
+        source: None,
     }
 }
 
@@ -1,5 +1,6 @@
 use coding_agent_search::search::query::{SearchClient, SearchFilters};
 use coding_agent_search::search::tantivy::TantivyIndex;
+use coding_agent_search::storage::sqlite::SqliteStorage;
 use tempfile::TempDir;
 
 mod util;
@@ -128,3 +129,301 @@ fn time_filter_respects_since_until() {
     assert_eq!(hits.len(), 1, "only middle conversation should match");
     assert!(hits[0].content.contains("two"));
 }
+
+/// Code-only search should match fenced code blocks, not surrounding prose.
+#[test]
+fn code_only_filter_matches_snippets() {
+    let dir = TempDir::new().unwrap();
+    let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+    let conv_code = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("code.jsonl"))
+        .with_content(0, "Try this:\n```rust\nfn tokenize_widget() {}\n```")
+        .build_normalized();
+    let conv_prose = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("prose.jsonl"))
+        .with_content(0, "we should tokenize_widget later")
+        .build_normalized();
+
+    index.add_conversation(&conv_code).unwrap();
+    index.add_conversation(&conv_prose).unwrap();
+    index.commit().unwrap();
+
+    let client = SearchClient::open(dir.path(), None)
+        .unwrap()
+        .expect("client");
+
+    let all = client
+        .search("tokenize_widget", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(all.len(), 2);
+
+    let filters = SearchFilters {
+        code_only: true,
+        ..SearchFilters::default()
+    };
+    let hits = client
+        .search("tokenize_widget", filters, 10, 0)
+        .expect("search");
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].source_path.ends_with("code.jsonl"));
+}
+
+/// `test:` matches messages where a test failed or was later fixed, not every green run.
+#[test]
+fn test_name_filter_matches_failures_and_fixes() {
+    let dir = TempDir::new().unwrap();
+    let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+    let conv_fixed = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("fixed.jsonl"))
+        .with_content(0, "test parser::rejects_tabs ... FAILED")
+        .with_content(1, "patched the lexer")
+        .with_content(2, "test parser::rejects_tabs ... ok")
+        .build_normalized();
+    let conv_green = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("green.jsonl"))
+        .with_content(0, "test parser::rejects_tabs ... ok")
+        .build_normalized();
+
+    index.add_conversation(&conv_fixed).unwrap();
+    index.add_conversation(&conv_green).unwrap();
+    index.commit().unwrap();
+
+    let client = SearchClient::open(dir.path(), None)
+        .unwrap()
+        .expect("client");
+
+    let hits = client
+        .search("test:parser::rejects_tabs", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(hits.len(), 2, "failure and fix messages");
+    assert!(hits.iter().all(|h| h.source_path.ends_with("fixed.jsonl")));
+
+    let by_suffix = client
+        .search("test:rejects_tabs", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(by_suffix.len(), 2);
+}
+
+/// `dep:` matches messages that install or edit a package, whatever the surrounding wording.
+#[test]
+fn dependency_filter_matches_installs_and_manifest_edits() {
+    let dir = TempDir::new().unwrap();
+    let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+    let conv = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("deps.jsonl"))
+        .with_content(0, "$ cargo add tokio --features full")
+        .with_content(1, "[dependencies]\n+Tokio_Util = \"0.7\"")
+        .with_content(2, "tokio is async, maybe we need it")
+        .build_normalized();
+    index.add_conversation(&conv).unwrap();
+    index.commit().unwrap();
+
+    let client = SearchClient::open(dir.path(), None)
+        .unwrap()
+        .expect("client");
+
+    let hits = client
+        .search("dep:tokio", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].content.contains("cargo add"));
+
+    let normalized = client
+        .search("dep:tokio_util", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(normalized.len(), 1);
+}
+
+#[test]
+fn rating_filter_and_boost_use_session_ratings() {
+    let dir = TempDir::new().unwrap();
+    let index_dir = dir.path().join("index");
+    let mut index = TantivyIndex::open_or_create(&index_dir).unwrap();
+
+    let paths: Vec<_> = ["first.jsonl", "second.jsonl", "third.jsonl"]
+        .iter()
+        .map(|name| dir.path().join(name))
+        .collect();
+    for (i, path) in paths.iter().enumerate() {
+        let conv = util::ConversationFixtureBuilder::new("tester")
+            .source_path(path)
+            .base_ts(1_700_000_000_000 + i as i64)
+            .messages(1)
+            .with_content(0, format!("retry backoff tuning session {i}"))
+            .build_normalized();
+        index.add_conversation(&conv).unwrap();
+    }
+    index.commit().unwrap();
+
+    let db_path = dir.path().join("agent_search.db");
+    let storage = SqliteStorage::open(&db_path).unwrap();
+    let path_str = |i: usize| paths[i].to_string_lossy().into_owned();
+    storage.set_rating("local", &path_str(0), -1, None).unwrap();
+    storage
+        .set_rating("local", &path_str(2), 1, Some("trusted fix"))
+        .unwrap();
+
+    let client = SearchClient::open(&index_dir, Some(&db_path))
+        .unwrap()
+        .expect("client");
+
+    let hits = client
+        .search("backoff", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].source_path, path_str(2));
+    assert_eq!(hits[2].source_path, path_str(0));
+
+    let down = client
+        .search("backoff rating:down", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(down.len(), 1);
+    assert_eq!(down[0].source_path, path_str(0));
+
+    let unrated = client
+        .search("backoff rating:none", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(unrated.len(), 1);
+    assert_eq!(unrated[0].source_path, path_str(1));
+}
+
+#[test]
+fn note_filter_searches_message_annotations() {
+    let dir = TempDir::new().unwrap();
+    let index_dir = dir.path().join("index");
+    let mut index = TantivyIndex::open_or_create(&index_dir).unwrap();
+    let db_path = dir.path().join("agent_search.db");
+    let mut storage = SqliteStorage::open(&db_path).unwrap();
+
+    let fixture = |name: &str| {
+        util::ConversationFixtureBuilder::new("tester")
+            .source_path(dir.path().join(name))
+            .base_ts(1_700_000_000_000)
+            .messages(2)
+            .with_content(0, format!("deploy pipeline question in {name}"))
+            .with_content(1, format!("deploy pipeline answer in {name}"))
+    };
+    let mut message_ids = Vec::new();
+    for name in ["noted.jsonl", "plain.jsonl"] {
+        index
+            .add_conversation(&fixture(name).build_normalized())
+            .unwrap();
+        let conv = fixture(name).build_conversation();
+        let agent_id = storage
+            .ensure_agent(&coding_agent_search::model::types::Agent {
+                id: None,
+                slug: "tester".into(),
+                name: "Tester".into(),
+                version: None,
+                kind: coding_agent_search::model::types::AgentKind::Cli,
+            })
+            .unwrap();
+        let conv_id = storage
+            .insert_conversation_tree(agent_id, None, &conv)
+            .unwrap()
+            .conversation_id;
+        message_ids.push(storage.message_id_at(conv_id, 1).unwrap().unwrap());
+    }
+    index.commit().unwrap();
+    storage
+        .add_annotation(message_ids[0], "Flaky rollback on staging")
+        .unwrap();
+
+    let client = SearchClient::open(&index_dir, Some(&db_path))
+        .unwrap()
+        .expect("client");
+
+    // Alone, `note:` searches the notes (prefix match, case-insensitive)
+    let notes = client
+        .search("note:rollb", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(notes.len(), 1);
+    assert!(notes[0].source_path.ends_with("noted.jsonl"));
+    assert_eq!(notes[0].line_number, Some(2));
+    assert_eq!(notes[0].snippet, "Flaky rollback on staging");
+
+    // With text, it narrows hits to annotated messages
+    let narrowed = client
+        .search("pipeline note:staging", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert_eq!(narrowed.len(), 1);
+    assert!(narrowed[0].content.contains("answer"));
+
+    let none = client
+        .search("note:nothing", SearchFilters::default(), 10, 0)
+        .expect("search");
+    assert!(none.is_empty());
+}
+
+/// Workspace filters match by prefix, so a parent directory covers its projects.
+#[test]
+fn workspace_filter_matches_path_prefix() {
+    let dir = TempDir::new().unwrap();
+    let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+    for (ws, word) in [
+        ("clients/acme/api", "one"),
+        ("clients/acme/web", "two"),
+        ("personal/blog", "three"),
+    ] {
+        let conv = util::ConversationFixtureBuilder::new("tester")
+            .workspace(dir.path().join(ws))
+            .source_path(dir.path().join(ws).join("session.jsonl"))
+            .with_content(0, format!("prefix_term {word}"))
+            .build_normalized();
+        index.add_conversation(&conv).unwrap();
+    }
+    index.commit().unwrap();
+
+    let client = SearchClient::open(dir.path(), None)
+        .unwrap()
+        .expect("client");
+    let mut filters = SearchFilters::default();
+    filters.workspaces.insert(
+        dir.path()
+            .join("clients/acme")
+            .to_string_lossy()
+            .to_string(),
+    );
+
+    let hits = client
+        .search("prefix_term", filters, 10, 0)
+        .expect("search");
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(|h| h.workspace.contains("clients/acme/")));
+}
+
+/// Role filters keep only messages from the requested roles.
+#[test]
+fn role_filter_limits_results() {
+    let dir = TempDir::new().unwrap();
+    let mut index = TantivyIndex::open_or_create(dir.path()).unwrap();
+
+    let conv = util::ConversationFixtureBuilder::new("tester")
+        .source_path(dir.path().join("roles.jsonl"))
+        .with_content(0, "role_term question")
+        .with_content(1, "role_term answer")
+        .build_normalized();
+    index.add_conversation(&conv).unwrap();
+    index.commit().unwrap();
+
+    let client = SearchClient::open(dir.path(), None)
+        .unwrap()
+        .expect("client");
+    let search = |roles: &[&str]| {
+        let filters = SearchFilters {
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            ..SearchFilters::default()
+        };
+        client.search("role_term", filters, 10, 0).expect("search")
+    };
+
+    let hits = search(&["assistant"]);
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].content.contains("answer"));
+    assert_eq!(search(&["user", "assistant"]).len(), 2);
+    assert!(search(&["tool"]).is_empty());
+}
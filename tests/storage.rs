@@ -42,6 +42,9 @@ fn msg(idx: i64, created_at: i64) -> Message {
         content: format!("msg-{idx}"),
         extra_json: serde_json::json!({}),
         snippets: vec![],
+        approx_tokens: None,
+        source_path: None,
+        source_line: None,
     }
 }
 
@@ -51,7 +54,7 @@ fn schema_version_created_on_open() {
     let db_path = tmp.path().join("store.db");
     let storage = SqliteStorage::open(&db_path).expect("open");
 
-    assert_eq!(storage.schema_version().unwrap(), 5);
+    assert_eq!(storage.schema_version().unwrap(), 21);
 
     // If meta row is removed, the getter surfaces an error.
     storage.raw().execute("DELETE FROM meta", []).unwrap();
@@ -173,6 +176,166 @@ fn append_only_updates_existing_conversation() {
     assert_eq!(ended_at, 300);
 }
 
+#[test]
+fn upsert_replaces_edited_messages_and_refreshes_metadata() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("upsert.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+
+    let first = sample_conv(Some("ext-up"), vec![msg(0, 100), msg(1, 200)]);
+    let outcome1 = storage
+        .insert_conversation_tree(agent_id, None, &first)
+        .unwrap();
+    assert!(outcome1.created);
+
+    let mut edited = msg(1, 200);
+    edited.content = "msg-1 (streamed to completion)".into();
+    let mut second = sample_conv(Some("ext-up"), vec![msg(0, 100), edited, msg(2, 300)]);
+    second.title = Some("Renamed session".into());
+    second.ended_at = Some(500);
+    let outcome2 = storage
+        .insert_conversation_tree(agent_id, None, &second)
+        .unwrap();
+    assert!(!outcome2.created);
+    assert_eq!(outcome2.conversation_id, outcome1.conversation_id);
+    assert_eq!(outcome2.inserted_indices, vec![2]);
+    assert_eq!(outcome2.replaced_indices, vec![1]);
+
+    let contents: Vec<String> = storage
+        .raw()
+        .prepare("SELECT content FROM messages ORDER BY idx")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(
+        contents,
+        vec!["msg-0", "msg-1 (streamed to completion)", "msg-2"]
+    );
+
+    let (title, ended_at): (String, i64) = storage
+        .raw()
+        .query_row(
+            "SELECT title, ended_at FROM conversations WHERE id = ?",
+            [outcome1.conversation_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(title, "Renamed session");
+    assert_eq!(ended_at, 500);
+
+    // Re-sending identical content is a no-op.
+    let outcome3 = storage
+        .insert_conversation_tree(agent_id, None, &second)
+        .unwrap();
+    assert!(outcome3.inserted_indices.is_empty());
+    assert!(outcome3.replaced_indices.is_empty());
+}
+
+#[test]
+fn get_conversation_by_id_and_external_id() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("get.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+
+    // Inserted out of order; retrieval must order by idx
+    let conv = sample_conv(Some("sess-42"), vec![msg(1, 200), msg(0, 100)]);
+    let outcome = storage
+        .insert_conversation_tree(agent_id, None, &conv)
+        .unwrap();
+
+    let by_id = storage
+        .get_conversation(outcome.conversation_id)
+        .unwrap()
+        .expect("conversation by id");
+    assert_eq!(by_id.external_id.as_deref(), Some("sess-42"));
+    assert_eq!(by_id.title.as_deref(), Some("Demo conversation"));
+    let order: Vec<i64> = by_id.messages.iter().map(|m| m.idx).collect();
+    assert_eq!(order, vec![0, 1]);
+
+    let by_ext = storage
+        .get_by_external_id("sess-42", Some("tester"))
+        .unwrap()
+        .expect("conversation by external id");
+    assert_eq!(by_ext.id, Some(outcome.conversation_id));
+    assert_eq!(by_ext.messages.len(), 2);
+
+    assert!(
+        storage
+            .get_by_external_id("sess-42", Some("codex"))
+            .unwrap()
+            .is_none()
+    );
+    assert!(storage.get_conversation(9999).unwrap().is_none());
+}
+
+#[test]
+fn hit_conversation_id_tells_apart_conversations_sharing_a_file() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("hits.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    // Like a Cursor or Aider store: one file, several conversations
+    let first = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("a"), vec![msg(0, 100)]))
+        .unwrap();
+    let second = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("b"), vec![msg(0, 200)]))
+        .unwrap();
+
+    let lookup = |created_at| {
+        storage
+            .hit_conversation_id("local", "tester", "/logs/demo.jsonl", created_at)
+            .unwrap()
+    };
+    assert_eq!(lookup(Some(100)), Some(first.conversation_id));
+    assert_eq!(lookup(Some(200)), Some(second.conversation_id));
+    // Without a timestamp to go by there is no single answer
+    assert_eq!(lookup(None), None);
+    assert_eq!(
+        storage
+            .hit_conversation_id("local", "codex", "/logs/demo.jsonl", Some(100))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn fetch_messages_page_walks_long_threads() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("pages.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+
+    let messages = (0..25).map(|i| msg(i, 100 + i)).collect();
+    let outcome = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("long"), messages))
+        .unwrap();
+    let id = outcome.conversation_id;
+    assert_eq!(storage.message_count(id).unwrap(), 25);
+
+    let mut seen = Vec::new();
+    let mut from = 0;
+    loop {
+        let page = storage.fetch_messages_page(id, from, 10).unwrap();
+        assert_eq!(page.total, 25);
+        assert!(page.messages.len() <= 10);
+        seen.extend(page.messages.iter().map(|m| m.idx));
+        match page.next_idx {
+            Some(next) => from = next,
+            None => break,
+        }
+    }
+    assert_eq!(seen, (0..25).collect::<Vec<_>>());
+
+    let tail = storage.fetch_messages_page(id, 20, 10).unwrap();
+    assert_eq!(tail.messages.len(), 5);
+    assert_eq!(tail.next_idx, None);
+}
+
 #[test]
 fn large_batch_insert_keeps_fts_in_sync() {
     let tmp = tempfile::TempDir::new().unwrap();
@@ -253,346 +416,761 @@ fn last_scan_ts_overwrite() {
 }
 
 #[test]
-fn unsupported_schema_version_errors() {
+fn source_sync_times_track_failures_until_a_success() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("schema.db");
+    let db_path = tmp.path().join("sync.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
 
-    // First open initializes schema to current version
-    let storage = SqliteStorage::open(&db_path).expect("initial open");
-    // Poison the schema_version to an unsupported future value
-    storage
-        .raw()
-        .execute(
-            "UPDATE meta SET value = '999' WHERE key = 'schema_version'",
-            [],
-        )
-        .unwrap();
-    drop(storage); // Close connection before reopening
+    let times = storage.get_source_sync_times("laptop").unwrap();
+    assert_eq!((times.last_sync, times.consecutive_failures), (None, 0));
 
-    let reopen = SqliteStorage::open(&db_path);
-    assert!(
-        reopen.is_err(),
-        "opening with unsupported schema_version should error"
+    storage.record_source_sync("laptop", 100, false).unwrap();
+    storage.record_source_sync("laptop", 200, true).unwrap();
+    storage.record_source_sync("laptop", 300, true).unwrap();
+    let times = storage.get_source_sync_times("laptop").unwrap();
+    assert_eq!(times.last_sync, Some(300));
+    assert_eq!(times.last_success, Some(100));
+    assert_eq!(times.consecutive_failures, 2);
+    assert_eq!(
+        storage.get_source_sync_times("desk").unwrap().last_sync,
+        None
     );
-}
 
-// =============================================================================
-// Schema Migration Tests (tst.sto.schema)
-// Tests for database schema creation and migrations
-// =============================================================================
+    storage.record_source_sync("laptop", 400, false).unwrap();
+    drop(storage);
+    let storage = SqliteStorage::open(&db_path).expect("reopen");
+    let times = storage.get_source_sync_times("laptop").unwrap();
+    assert_eq!(times.last_success, Some(400));
+    assert_eq!(times.consecutive_failures, 0);
+}
 
 #[test]
-fn fresh_db_creates_all_tables() {
+fn delete_conversations_removes_messages_and_fts_rows() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("fresh.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+    let db_path = tmp.path().join("gc.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
 
-    // Query sqlite_master for table names
-    let tables: Vec<String> = storage
-        .raw()
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' ORDER BY name")
-        .unwrap()
-        .query_map([], |r| r.get(0))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let gone = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("gone"), vec![msg(0, 1)]))
+        .unwrap();
+    let mut kept_conv = sample_conv(Some("kept"), vec![msg(0, 2)]);
+    kept_conv.source_path = PathBuf::from("/logs/kept.jsonl");
+    storage
+        .insert_conversation_tree(agent_id, None, &kept_conv)
+        .unwrap();
 
-    assert!(tables.contains(&"meta".to_string()), "meta table exists");
-    assert!(
-        tables.contains(&"agents".to_string()),
-        "agents table exists"
-    );
-    assert!(
-        tables.contains(&"workspaces".to_string()),
-        "workspaces table exists"
-    );
-    assert!(
-        tables.contains(&"conversations".to_string()),
-        "conversations table exists"
-    );
-    assert!(
-        tables.contains(&"messages".to_string()),
-        "messages table exists"
-    );
-    assert!(
-        tables.contains(&"snippets".to_string()),
-        "snippets table exists"
-    );
-    assert!(tables.contains(&"tags".to_string()), "tags table exists");
-    assert!(
-        tables.contains(&"conversation_tags".to_string()),
-        "conversation_tags table exists"
-    );
-    // FTS5 virtual table
-    assert!(
-        tables.contains(&"fts_messages".to_string()),
-        "fts_messages virtual table exists"
-    );
-    // Sources table (v4)
+    let paths = storage.list_conversation_source_paths().unwrap();
+    assert_eq!(paths.len(), 2);
     assert!(
-        tables.contains(&"sources".to_string()),
-        "sources table exists"
+        paths
+            .iter()
+            .any(|(_, sid, p)| sid == "local" && p == "/logs/demo.jsonl")
     );
+
+    let removed = storage
+        .delete_conversations(&[gone.conversation_id])
+        .unwrap();
+    assert_eq!(removed, 1);
+
+    let messages: i64 = storage
+        .raw()
+        .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+        .unwrap();
+    let fts: i64 = storage
+        .raw()
+        .query_row("SELECT COUNT(*) FROM fts_messages", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(messages, 1);
+    assert_eq!(fts, 1);
+    assert_eq!(storage.list_conversation_source_paths().unwrap().len(), 1);
 }
 
 #[test]
-fn fresh_db_creates_all_indexes() {
+fn daily_activity_groups_sessions_by_local_day() {
+    use chrono::{Local, TimeZone};
+
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("indexes.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+    let mut storage = SqliteStorage::open(&tmp.path().join("cal.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
 
-    let indexes: Vec<String> = storage
-        .raw()
-        .prepare("SELECT name FROM sqlite_master WHERE type='index' AND name NOT LIKE 'sqlite_%' ORDER BY name")
-        .unwrap()
-        .query_map([], |r| r.get(0))
-        .unwrap()
-        .map(|r| r.unwrap())
+    let day = |d: u32, h: u32| {
+        Local
+            .with_ymd_and_hms(2025, 3, d, h, 0, 0)
+            .single()
+            .unwrap()
+            .timestamp_millis()
+    };
+    for (id, started, messages) in [
+        ("a", day(4, 9), 2),
+        ("b", day(4, 23), 1),
+        ("c", day(6, 0), 3),
+    ] {
+        let mut conv = sample_conv(
+            Some(id),
+            (0..messages).map(|i| msg(i, started + i)).collect(),
+        );
+        conv.source_path = PathBuf::from(format!("/logs/{id}.jsonl"));
+        conv.started_at = Some(started);
+        storage
+            .insert_conversation_tree(agent_id, Some(ws_id), &conv)
+            .unwrap();
+    }
+
+    let days = storage.daily_activity(day(1, 0), day(8, 0)).unwrap();
+    let summary: Vec<(&str, i64, i64)> = days
+        .iter()
+        .map(|d| (d.day.as_str(), d.conversations, d.messages))
         .collect();
+    assert_eq!(summary, [("2025-03-04", 2, 3), ("2025-03-06", 1, 3)]);
 
-    assert!(
-        indexes.contains(&"idx_conversations_agent_started".to_string()),
-        "idx_conversations_agent_started index exists"
-    );
-    assert!(
-        indexes.contains(&"idx_messages_conv_idx".to_string()),
-        "idx_messages_conv_idx index exists"
-    );
-    assert!(
-        indexes.contains(&"idx_messages_created".to_string()),
-        "idx_messages_created index exists"
-    );
+    let tuesday = storage
+        .conversations_started_between(day(4, 0), day(5, 0))
+        .unwrap();
+    let paths: Vec<&str> = tuesday.iter().map(|c| c.source_path.as_str()).collect();
+    assert_eq!(paths, ["/logs/a.jsonl", "/logs/b.jsonl"]);
+    assert_eq!(tuesday[0].workspace.as_deref(), Some("/workspace/demo"));
 }
 
 #[test]
-fn agents_table_has_correct_columns() {
+fn daily_messages_spread_a_session_over_the_days_it_was_active() {
+    use chrono::{Local, TimeZone};
+
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("agents_cols.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+    let mut storage = SqliteStorage::open(&tmp.path().join("days.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
 
-    let columns: Vec<String> = storage
-        .raw()
-        .prepare("PRAGMA table_info(agents)")
-        .unwrap()
-        .query_map([], |r| r.get::<_, String>(1))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
+    let day = |d: u32, h: u32| {
+        Local
+            .with_ymd_and_hms(2025, 3, d, h, 0, 0)
+            .single()
+            .unwrap()
+            .timestamp_millis()
+    };
+    // Started late on the 4th, carried on through the 5th; one message has no timestamp
+    let mut untimed = msg(3, 0);
+    untimed.created_at = None;
+    let mut conv = sample_conv(
+        Some("long"),
+        vec![
+            msg(0, day(4, 23)),
+            msg(1, day(5, 9)),
+            msg(2, day(5, 10)),
+            untimed,
+        ],
+    );
+    conv.started_at = Some(day(4, 23));
+    storage
+        .insert_conversation_tree(agent_id, None, &conv)
+        .unwrap();
 
-    assert!(columns.contains(&"id".to_string()));
-    assert!(columns.contains(&"slug".to_string()));
-    assert!(columns.contains(&"name".to_string()));
-    assert!(columns.contains(&"version".to_string()));
-    assert!(columns.contains(&"kind".to_string()));
-    assert!(columns.contains(&"created_at".to_string()));
-    assert!(columns.contains(&"updated_at".to_string()));
+    let days = storage.daily_messages(day(1, 0), day(8, 0)).unwrap();
+    let summary: Vec<(&str, i64, i64)> = days
+        .iter()
+        .map(|d| (d.day.as_str(), d.conversations, d.messages))
+        .collect();
+    assert_eq!(summary, [("2025-03-04", 1, 2), ("2025-03-05", 1, 2)]);
 }
 
 #[test]
-fn conversations_table_has_correct_columns() {
+fn agent_usage_and_top_workspaces_aggregate_conversations() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("convs_cols.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+    let mut storage = SqliteStorage::open(&tmp.path().join("usage.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let demo = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), None)
+        .unwrap();
+    let other = storage
+        .ensure_workspace(PathBuf::from("/workspace/other").as_path(), None)
+        .unwrap();
 
-    let columns: Vec<String> = storage
-        .raw()
-        .prepare("PRAGMA table_info(conversations)")
-        .unwrap()
-        .query_map([], |r| r.get::<_, String>(1))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
+    for (id, ws, messages) in [("a", demo, 2), ("b", demo, 1), ("c", other, 3)] {
+        let mut conv = sample_conv(Some(id), (0..messages).map(|i| msg(i, 10 + i)).collect());
+        conv.source_path = PathBuf::from(format!("/logs/{id}.jsonl"));
+        storage
+            .insert_conversation_tree(agent_id, Some(ws), &conv)
+            .unwrap();
+    }
 
-    assert!(columns.contains(&"id".to_string()));
-    assert!(columns.contains(&"agent_id".to_string()));
-    assert!(columns.contains(&"workspace_id".to_string()));
-    assert!(columns.contains(&"external_id".to_string()));
-    assert!(columns.contains(&"title".to_string()));
-    assert!(columns.contains(&"source_path".to_string()));
-    assert!(columns.contains(&"started_at".to_string()));
-    assert!(columns.contains(&"ended_at".to_string()));
-    assert!(columns.contains(&"approx_tokens".to_string()));
-    assert!(columns.contains(&"metadata_json".to_string()));
+    let usage = storage.agent_usage().unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].agent, "tester");
+    assert_eq!(usage[0].conversations, 3);
+    assert_eq!(usage[0].messages, 6);
+    assert_eq!(usage[0].tokens, 3 * 42);
+
+    let top = storage.top_workspaces(1).unwrap();
+    assert_eq!(top, [("/workspace/demo".to_string(), 2)]);
 }
 
 #[test]
-fn messages_table_has_correct_columns() {
+fn pick_candidates_are_newest_first_and_filter_by_agent() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("msgs_cols.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+    let mut storage = SqliteStorage::open(&tmp.path().join("pick.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
 
-    let columns: Vec<String> = storage
-        .raw()
-        .prepare("PRAGMA table_info(messages)")
-        .unwrap()
-        .query_map([], |r| r.get::<_, String>(1))
-        .unwrap()
-        .map(|r| r.unwrap())
+    for (id, started) in [("a", Some(100)), ("b", Some(300)), ("c", None)] {
+        let mut conv = sample_conv(Some(id), vec![msg(0, 1)]);
+        conv.source_path = PathBuf::from(format!("/logs/{id}.jsonl"));
+        conv.started_at = started;
+        storage
+            .insert_conversation_tree(agent_id, None, &conv)
+            .unwrap();
+    }
+
+    let all = storage.pick_candidates(&[], 10).unwrap();
+    let paths: Vec<&str> = all.iter().map(|c| c.source_path.as_str()).collect();
+    assert_eq!(paths, ["/logs/b.jsonl", "/logs/a.jsonl", "/logs/c.jsonl"]);
+    assert_eq!(storage.pick_candidates(&[], 1).unwrap().len(), 1);
+    assert_eq!(
+        storage
+            .pick_candidates(&["tester".to_string()], 10)
+            .unwrap()
+            .len(),
+        3
+    );
+    assert!(
+        storage
+            .pick_candidates(&["codex".to_string()], 10)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn workspace_activity_orders_by_last_session() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("ws.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let demo = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), None)
+        .unwrap();
+    let other = storage
+        .ensure_workspace(PathBuf::from("/workspace/other").as_path(), None)
+        .unwrap();
+
+    for (id, ws, started, ended) in [
+        ("a", demo, 100, Some(150)),
+        ("b", demo, 200, None),
+        ("c", other, 120, Some(900)),
+    ] {
+        let mut conv = sample_conv(Some(id), vec![msg(0, started)]);
+        conv.source_path = PathBuf::from(format!("/logs/{id}.jsonl"));
+        conv.started_at = Some(started);
+        conv.ended_at = ended;
+        storage
+            .insert_conversation_tree(agent_id, Some(ws), &conv)
+            .unwrap();
+    }
+
+    let all = storage.workspace_activity(0).unwrap();
+    let summary: Vec<(&str, i64, Option<i64>)> = all
+        .iter()
+        .map(|w| (w.path.as_str(), w.conversations, w.last_activity))
         .collect();
+    assert_eq!(
+        summary,
+        [
+            ("/workspace/other", 1, Some(900)),
+            ("/workspace/demo", 2, Some(200))
+        ]
+    );
+    assert_eq!(all[1].agents, ["tester"]);
+    assert_eq!(storage.workspace_activity(1).unwrap().len(), 1);
+}
 
-    assert!(columns.contains(&"id".to_string()));
-    assert!(columns.contains(&"conversation_id".to_string()));
-    assert!(columns.contains(&"idx".to_string()));
-    assert!(columns.contains(&"role".to_string()));
-    assert!(columns.contains(&"author".to_string()));
-    assert!(columns.contains(&"created_at".to_string()));
-    assert!(columns.contains(&"content".to_string()));
-    assert!(columns.contains(&"extra_json".to_string()));
+#[test]
+fn read_snapshot_ignores_concurrent_commits() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("snapshot.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("a"), vec![msg(0, 1)]))
+        .unwrap();
+
+    let snapshot = SqliteStorage::open_snapshot(&db_path).expect("snapshot");
+    let count = |s: &SqliteStorage| -> i64 {
+        s.raw()
+            .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+            .unwrap()
+    };
+
+    let mut late = sample_conv(Some("b"), vec![msg(0, 2)]);
+    late.source_path = PathBuf::from("/logs/late.jsonl");
+    storage
+        .insert_conversation_tree(agent_id, None, &late)
+        .unwrap();
+
+    assert_eq!(count(&storage), 2);
+    assert_eq!(count(&snapshot), 1);
+    drop(snapshot);
+    assert_eq!(
+        count(&SqliteStorage::open_snapshot(&db_path).expect("snapshot")),
+        2
+    );
 }
 
 #[test]
-fn fts_messages_is_fts5_virtual_table() {
+fn retained_conversations_lock_and_unlock() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("fts5.db");
+    let db_path = tmp.path().join("retain.db");
     let storage = SqliteStorage::open(&db_path).expect("open");
 
-    // Check that fts_messages is an FTS5 virtual table
-    let sql: String = storage
-        .raw()
-        .query_row(
-            "SELECT sql FROM sqlite_master WHERE name='fts_messages' AND type='table'",
-            [],
-            |r| r.get(0),
-        )
-        .expect("fts_messages should exist");
-
     assert!(
-        sql.contains("fts5"),
-        "fts_messages should be FTS5 virtual table"
+        storage
+            .set_retained("local", "/logs/adr.jsonl", true)
+            .unwrap()
     );
-    assert!(sql.contains("content"), "fts_messages should have content");
-    assert!(sql.contains("title"), "fts_messages should have title");
-    assert!(sql.contains("agent"), "fts_messages should have agent");
+    // Locking twice is a no-op
     assert!(
-        sql.contains("workspace"),
-        "fts_messages should have workspace"
+        !storage
+            .set_retained("local", "/logs/adr.jsonl", true)
+            .unwrap()
     );
+
+    let retained = storage.list_retained().unwrap();
+    assert_eq!(retained.len(), 1);
+    assert_eq!(retained[0].0, "local");
+    assert_eq!(retained[0].1, "/logs/adr.jsonl");
+
     assert!(
-        sql.contains("porter"),
-        "fts_messages should use porter tokenizer"
+        storage
+            .set_retained("local", "/logs/adr.jsonl", false)
+            .unwrap()
     );
+    assert!(storage.list_retained().unwrap().is_empty());
 }
 
 #[test]
-fn migration_from_v1_applies_v2_and_v3() {
-    use rusqlite::Connection;
+fn conversation_ratings_replace_and_clear() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("ratings.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    storage
+        .set_rating("local", "/logs/a.jsonl", 1, Some("clean refactor"))
+        .unwrap();
+    // Re-rating replaces the earlier rating and note
+    storage
+        .set_rating("local", "/logs/a.jsonl", -1, None)
+        .unwrap();
+    let ratings = storage.list_ratings().unwrap();
+    assert_eq!(ratings.len(), 1);
+    assert_eq!(ratings[0].rating, -1);
+    assert_eq!(ratings[0].note, None);
+
+    assert!(storage.clear_rating("local", "/logs/a.jsonl").unwrap());
+    assert!(!storage.clear_rating("local", "/logs/a.jsonl").unwrap());
+    assert!(storage.list_ratings().unwrap().is_empty());
+}
+
+#[test]
+fn result_clicks_aggregate_into_priors() {
+    use coding_agent_search::search::feedback::ClickPriors;
 
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("migrate_v1.db");
+    let db_path = tmp.path().join("clicks.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
 
-    // Manually create a v1 database
-    {
-        let conn = Connection::open(&db_path).expect("create v1 db");
-        conn.execute_batch(
-            r"
-            PRAGMA foreign_keys = ON;
+    for _ in 0..2 {
+        storage
+            .record_click(
+                "local",
+                "/logs/a.jsonl",
+                "codex",
+                Some("/ws/app"),
+                Some("retry"),
+            )
+            .unwrap();
+    }
+    storage
+        .record_click("local", "/logs/b.jsonl", "claude_code", Some(""), None)
+        .unwrap();
 
-            CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
-            INSERT INTO meta(key, value) VALUES('schema_version', '1');
+    let priors = ClickPriors::load(storage.raw()).unwrap();
+    assert!(!priors.is_empty());
+    let clicked = priors.boost("local", "/logs/a.jsonl", "codex", "/ws/app");
+    let sibling = priors.boost("local", "/logs/c.jsonl", "codex", "/ws/app");
+    assert!(clicked > sibling && sibling > 1.0);
+    assert_eq!(
+        priors.boost("local", "/logs/z.jsonl", "gemini", "/other"),
+        1.0
+    );
+}
 
-            CREATE TABLE agents (
-                id INTEGER PRIMARY KEY,
-                slug TEXT NOT NULL UNIQUE,
-                name TEXT NOT NULL,
-                version TEXT,
-                kind TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            );
+#[test]
+fn result_clicks_age_out_and_stay_capped() {
+    use coding_agent_search::storage::sqlite::{CLICK_RETENTION_DAYS, MAX_RESULT_CLICKS};
 
-            CREATE TABLE workspaces (
-                id INTEGER PRIMARY KEY,
-                path TEXT NOT NULL UNIQUE,
-                display_name TEXT
-            );
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("clicks.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+    let conn = storage.raw();
+    let now = chrono::Utc::now().timestamp_millis();
+    let insert = |path: &str, clicked_at: i64| {
+        conn.execute(
+            "INSERT INTO result_clicks(source_id, source_path, agent, clicked_at)
+             VALUES('local', ?1, 'codex', ?2)",
+            rusqlite::params![path, clicked_at],
+        )
+        .unwrap();
+    };
+    let has = |path: &str| -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM result_clicks WHERE source_path = ?1",
+            [path],
+            |r| r.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    };
 
-            CREATE TABLE conversations (
-                id INTEGER PRIMARY KEY,
-                agent_id INTEGER NOT NULL REFERENCES agents(id),
-                workspace_id INTEGER REFERENCES workspaces(id),
-                external_id TEXT,
-                title TEXT,
-                source_path TEXT NOT NULL,
-                started_at INTEGER,
-                ended_at INTEGER,
-                approx_tokens INTEGER,
-                metadata_json TEXT,
-                UNIQUE(agent_id, external_id)
-            );
+    insert(
+        "/logs/stale.jsonl",
+        now - (CLICK_RETENTION_DAYS + 1) * 86_400_000,
+    );
+    insert("/logs/recent.jsonl", now - 86_400_000);
+    storage
+        .record_click("local", "/logs/new.jsonl", "codex", None, None)
+        .unwrap();
+    assert!(!has("/logs/stale.jsonl"));
+    assert!(has("/logs/recent.jsonl") && has("/logs/new.jsonl"));
 
-            CREATE TABLE messages (
-                id INTEGER PRIMARY KEY,
-                conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
-                idx INTEGER NOT NULL,
-                role TEXT NOT NULL,
-                author TEXT,
-                created_at INTEGER,
-                content TEXT NOT NULL,
-                extra_json TEXT,
-                UNIQUE(conversation_id, idx)
-            );
+    conn.execute_batch("BEGIN").unwrap();
+    for i in 0..MAX_RESULT_CLICKS {
+        insert(&format!("/logs/{i}.jsonl"), now);
+    }
+    conn.execute_batch("COMMIT").unwrap();
+    storage
+        .record_click("local", "/logs/newest.jsonl", "codex", None, None)
+        .unwrap();
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM result_clicks", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(total, MAX_RESULT_CLICKS);
+    assert!(!has("/logs/recent.jsonl") && !has("/logs/0.jsonl"));
+    assert!(has("/logs/newest.jsonl"));
+}
 
-            CREATE TABLE snippets (
-                id INTEGER PRIMARY KEY,
-                message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
-                file_path TEXT,
-                start_line INTEGER,
-                end_line INTEGER,
-                language TEXT,
-                snippet_text TEXT
-            );
+#[test]
+fn prune_candidates_order_by_policy_and_flag_locked_sessions() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("budget.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    for (id, started) in [("old", 1), ("mid", 2), ("new", 3)] {
+        let mut conv = sample_conv(Some(id), vec![msg(0, started)]);
+        conv.source_path = PathBuf::from(format!("/logs/{id}.jsonl"));
+        conv.started_at = Some(started);
+        storage
+            .insert_conversation_tree(agent_id, None, &conv)
+            .unwrap();
+    }
+    storage
+        .set_retained("local", "/logs/mid.jsonl", true)
+        .unwrap();
+    assert!(storage.record_access("/logs/old.jsonl").unwrap());
+    assert!(!storage.record_access("/logs/missing.jsonl").unwrap());
 
-            CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+    let paths = |least_accessed: bool| -> Vec<(String, bool)> {
+        storage
+            .prune_candidates(least_accessed)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.source_path, c.retained))
+            .collect()
+    };
+    assert_eq!(
+        paths(false),
+        vec![
+            ("/logs/old.jsonl".to_string(), false),
+            ("/logs/mid.jsonl".to_string(), true),
+            ("/logs/new.jsonl".to_string(), false),
+        ]
+    );
+    // The opened session moves to the back of the queue
+    assert_eq!(paths(true)[2].0, "/logs/old.jsonl");
+    assert!(storage.used_bytes().unwrap() > 0);
+}
 
-            CREATE TABLE conversation_tags (
-                conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
-                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
-                PRIMARY KEY (conversation_id, tag_id)
-            );
+#[test]
+fn workspace_projects_manual_mapping_wins_over_detection() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("projects.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
 
-            CREATE INDEX idx_conversations_agent_started ON conversations(agent_id, started_at DESC);
-            CREATE INDEX idx_messages_conv_idx ON messages(conversation_id, idx);
-            CREATE INDEX idx_messages_created ON messages(created_at);
-            ",
+    let remote = Some("git@github.com:acme/app.git");
+    storage
+        .upsert_detected_project("/work/app", "github.com/acme/app", remote)
+        .unwrap();
+    storage
+        .upsert_detected_project("/work/app-wt", "github.com/acme/app", remote)
+        .unwrap();
+    assert_eq!(
+        storage.project_workspaces("github.com/acme/app").unwrap(),
+        vec!["/work/app".to_string(), "/work/app-wt".to_string()]
+    );
+
+    // Manual mapping is not overwritten by later detection
+    assert!(
+        storage
+            .set_workspace_project("/work/app-wt", Some("acme-experiments"))
+            .unwrap()
+    );
+    storage
+        .upsert_detected_project("/work/app-wt", "github.com/acme/app", remote)
+        .unwrap();
+    let mapping = storage.workspace_project("/work/app-wt").unwrap().unwrap();
+    assert_eq!(mapping.project, "acme-experiments");
+    assert!(mapping.manual);
+
+    // Unset removes the mapping entirely
+    assert!(storage.set_workspace_project("/work/app-wt", None).unwrap());
+    assert!(storage.workspace_project("/work/app-wt").unwrap().is_none());
+    assert_eq!(storage.list_workspace_projects().unwrap().len(), 1);
+}
+
+#[test]
+fn unsupported_schema_version_errors() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("schema.db");
+
+    // First open initializes schema to current version
+    let storage = SqliteStorage::open(&db_path).expect("initial open");
+    // Poison the schema_version to an unsupported future value
+    storage
+        .raw()
+        .execute(
+            "UPDATE meta SET value = '999' WHERE key = 'schema_version'",
+            [],
         )
-        .expect("create v1 schema");
-    }
+        .unwrap();
+    drop(storage); // Close connection before reopening
 
-    // Open with SqliteStorage - should apply v2, v3, and v4 migrations
-    let storage = SqliteStorage::open(&db_path).expect("open v1 db");
+    let reopen = SqliteStorage::open(&db_path);
+    assert!(
+        reopen.is_err(),
+        "opening with unsupported schema_version should error"
+    );
+}
 
-    // Verify migration completed
-    assert_eq!(storage.schema_version().unwrap(), 5, "should migrate to v5");
+// =============================================================================
+// Schema Migration Tests (tst.sto.schema)
+// Tests for database schema creation and migrations
+// =============================================================================
 
-    // Verify FTS5 table was created
+#[test]
+fn fresh_db_creates_all_tables() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("fresh.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    // Query sqlite_master for table names
     let tables: Vec<String> = storage
         .raw()
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='fts_messages'")
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' ORDER BY name")
         .unwrap()
         .query_map([], |r| r.get(0))
         .unwrap()
         .map(|r| r.unwrap())
         .collect();
 
-    assert_eq!(tables.len(), 1, "fts_messages should exist after migration");
-}
-
-#[test]
-fn migration_from_v2_applies_v3() {
-    use rusqlite::Connection;
-
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("migrate_v2.db");
+    assert!(tables.contains(&"meta".to_string()), "meta table exists");
+    assert!(
+        tables.contains(&"agents".to_string()),
+        "agents table exists"
+    );
+    assert!(
+        tables.contains(&"workspaces".to_string()),
+        "workspaces table exists"
+    );
+    assert!(
+        tables.contains(&"conversations".to_string()),
+        "conversations table exists"
+    );
+    assert!(
+        tables.contains(&"messages".to_string()),
+        "messages table exists"
+    );
+    assert!(
+        tables.contains(&"snippets".to_string()),
+        "snippets table exists"
+    );
+    assert!(tables.contains(&"tags".to_string()), "tags table exists");
+    assert!(
+        tables.contains(&"conversation_tags".to_string()),
+        "conversation_tags table exists"
+    );
+    // FTS5 virtual table
+    assert!(
+        tables.contains(&"fts_messages".to_string()),
+        "fts_messages virtual table exists"
+    );
+    // Sources table (v4)
+    assert!(
+        tables.contains(&"sources".to_string()),
+        "sources table exists"
+    );
+}
 
-    // Manually create a v2 database with FTS5 table
+#[test]
+fn fresh_db_creates_all_indexes() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("indexes.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    let indexes: Vec<String> = storage
+        .raw()
+        .prepare("SELECT name FROM sqlite_master WHERE type='index' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(
+        indexes.contains(&"idx_conversations_agent_started".to_string()),
+        "idx_conversations_agent_started index exists"
+    );
+    assert!(
+        indexes.contains(&"idx_messages_conv_idx".to_string()),
+        "idx_messages_conv_idx index exists"
+    );
+    assert!(
+        indexes.contains(&"idx_messages_created".to_string()),
+        "idx_messages_created index exists"
+    );
+}
+
+#[test]
+fn agents_table_has_correct_columns() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("agents_cols.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    let columns: Vec<String> = storage
+        .raw()
+        .prepare("PRAGMA table_info(agents)")
+        .unwrap()
+        .query_map([], |r| r.get::<_, String>(1))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(columns.contains(&"id".to_string()));
+    assert!(columns.contains(&"slug".to_string()));
+    assert!(columns.contains(&"name".to_string()));
+    assert!(columns.contains(&"version".to_string()));
+    assert!(columns.contains(&"kind".to_string()));
+    assert!(columns.contains(&"created_at".to_string()));
+    assert!(columns.contains(&"updated_at".to_string()));
+}
+
+#[test]
+fn conversations_table_has_correct_columns() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("convs_cols.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    let columns: Vec<String> = storage
+        .raw()
+        .prepare("PRAGMA table_info(conversations)")
+        .unwrap()
+        .query_map([], |r| r.get::<_, String>(1))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(columns.contains(&"id".to_string()));
+    assert!(columns.contains(&"agent_id".to_string()));
+    assert!(columns.contains(&"workspace_id".to_string()));
+    assert!(columns.contains(&"external_id".to_string()));
+    assert!(columns.contains(&"title".to_string()));
+    assert!(columns.contains(&"source_path".to_string()));
+    assert!(columns.contains(&"started_at".to_string()));
+    assert!(columns.contains(&"ended_at".to_string()));
+    assert!(columns.contains(&"approx_tokens".to_string()));
+    assert!(columns.contains(&"metadata_json".to_string()));
+}
+
+#[test]
+fn messages_table_has_correct_columns() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("msgs_cols.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    let columns: Vec<String> = storage
+        .raw()
+        .prepare("PRAGMA table_info(messages)")
+        .unwrap()
+        .query_map([], |r| r.get::<_, String>(1))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(columns.contains(&"id".to_string()));
+    assert!(columns.contains(&"conversation_id".to_string()));
+    assert!(columns.contains(&"idx".to_string()));
+    assert!(columns.contains(&"role".to_string()));
+    assert!(columns.contains(&"author".to_string()));
+    assert!(columns.contains(&"created_at".to_string()));
+    assert!(columns.contains(&"content".to_string()));
+    assert!(columns.contains(&"extra_json".to_string()));
+}
+
+#[test]
+fn fts_messages_is_fts5_virtual_table() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("fts5.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    // Check that fts_messages is an FTS5 virtual table
+    let sql: String = storage
+        .raw()
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE name='fts_messages' AND type='table'",
+            [],
+            |r| r.get(0),
+        )
+        .expect("fts_messages should exist");
+
+    assert!(
+        sql.contains("fts5"),
+        "fts_messages should be FTS5 virtual table"
+    );
+    assert!(sql.contains("content"), "fts_messages should have content");
+    assert!(sql.contains("title"), "fts_messages should have title");
+    assert!(sql.contains("agent"), "fts_messages should have agent");
+    assert!(
+        sql.contains("workspace"),
+        "fts_messages should have workspace"
+    );
+    assert!(
+        sql.contains("porter"),
+        "fts_messages should use porter tokenizer"
+    );
+}
+
+#[test]
+fn migration_from_v1_applies_v2_and_v3() {
+    use rusqlite::Connection;
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("migrate_v1.db");
+
+    // Manually create a v1 database
     {
-        let conn = Connection::open(&db_path).expect("create v2 db");
+        let conn = Connection::open(&db_path).expect("create v1 db");
         conn.execute_batch(
             r"
             PRAGMA foreign_keys = ON;
 
             CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
-            INSERT INTO meta(key, value) VALUES('schema_version', '2');
+            INSERT INTO meta(key, value) VALUES('schema_version', '1');
 
             CREATE TABLE agents (
                 id INTEGER PRIMARY KEY,
@@ -657,66 +1235,178 @@ fn migration_from_v2_applies_v3() {
             CREATE INDEX idx_conversations_agent_started ON conversations(agent_id, started_at DESC);
             CREATE INDEX idx_messages_conv_idx ON messages(conversation_id, idx);
             CREATE INDEX idx_messages_created ON messages(created_at);
-
-            -- V2 FTS5 table
-            CREATE VIRTUAL TABLE fts_messages USING fts5(
-                content,
-                title,
-                agent,
-                workspace,
-                source_path,
-                created_at UNINDEXED,
-                message_id UNINDEXED,
-                tokenize='porter'
-            );
             ",
         )
-        .expect("create v2 schema");
+        .expect("create v1 schema");
     }
 
-    // Open with SqliteStorage - should apply v3 and v4 migrations
-    let storage = SqliteStorage::open(&db_path).expect("open v2 db");
+    // Open with SqliteStorage - should apply v2, v3, and v4 migrations
+    let storage = SqliteStorage::open(&db_path).expect("open v1 db");
 
     // Verify migration completed
-    assert_eq!(storage.schema_version().unwrap(), 5, "should migrate to v5");
-}
-
-#[test]
-fn foreign_keys_are_enforced() {
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("fk.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
-
-    // Try to insert a conversation with non-existent agent_id
-    let result = storage.raw().execute(
-        "INSERT INTO conversations(agent_id, source_path) VALUES(999, '/test')",
-        [],
+    assert_eq!(
+        storage.schema_version().unwrap(),
+        CURRENT_SCHEMA_VERSION,
+        "should migrate to the current schema"
     );
 
-    assert!(
-        result.is_err(),
-        "foreign key constraint should prevent invalid agent_id"
-    );
+    // Verify FTS5 table was created
+    let tables: Vec<String> = storage
+        .raw()
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='fts_messages'")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert_eq!(tables.len(), 1, "fts_messages should exist after migration");
 }
 
 #[test]
-fn unique_constraints_work() {
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("unique.db");
-    let storage = SqliteStorage::open(&db_path).expect("open");
+fn migration_from_v2_applies_v3() {
+    use rusqlite::Connection;
 
-    // Insert an agent
-    storage
-        .raw()
-        .execute(
-            "INSERT INTO agents(slug, name, kind, created_at, updated_at) VALUES('test', 'Test', 'cli', 0, 0)",
-            [],
-        )
-        .expect("first insert");
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("migrate_v2.db");
 
-    // Try to insert duplicate slug
-    let result = storage.raw().execute(
-        "INSERT INTO agents(slug, name, kind, created_at, updated_at) VALUES('test', 'Test2', 'cli', 0, 0)",
+    // Manually create a v2 database with FTS5 table
+    {
+        let conn = Connection::open(&db_path).expect("create v2 db");
+        conn.execute_batch(
+            r"
+            PRAGMA foreign_keys = ON;
+
+            CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO meta(key, value) VALUES('schema_version', '2');
+
+            CREATE TABLE agents (
+                id INTEGER PRIMARY KEY,
+                slug TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                version TEXT,
+                kind TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE workspaces (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                display_name TEXT
+            );
+
+            CREATE TABLE conversations (
+                id INTEGER PRIMARY KEY,
+                agent_id INTEGER NOT NULL REFERENCES agents(id),
+                workspace_id INTEGER REFERENCES workspaces(id),
+                external_id TEXT,
+                title TEXT,
+                source_path TEXT NOT NULL,
+                started_at INTEGER,
+                ended_at INTEGER,
+                approx_tokens INTEGER,
+                metadata_json TEXT,
+                UNIQUE(agent_id, external_id)
+            );
+
+            CREATE TABLE messages (
+                id INTEGER PRIMARY KEY,
+                conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                idx INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                author TEXT,
+                created_at INTEGER,
+                content TEXT NOT NULL,
+                extra_json TEXT,
+                UNIQUE(conversation_id, idx)
+            );
+
+            CREATE TABLE snippets (
+                id INTEGER PRIMARY KEY,
+                message_id INTEGER NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+                file_path TEXT,
+                start_line INTEGER,
+                end_line INTEGER,
+                language TEXT,
+                snippet_text TEXT
+            );
+
+            CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+
+            CREATE TABLE conversation_tags (
+                conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (conversation_id, tag_id)
+            );
+
+            CREATE INDEX idx_conversations_agent_started ON conversations(agent_id, started_at DESC);
+            CREATE INDEX idx_messages_conv_idx ON messages(conversation_id, idx);
+            CREATE INDEX idx_messages_created ON messages(created_at);
+
+            -- V2 FTS5 table
+            CREATE VIRTUAL TABLE fts_messages USING fts5(
+                content,
+                title,
+                agent,
+                workspace,
+                source_path,
+                created_at UNINDEXED,
+                message_id UNINDEXED,
+                tokenize='porter'
+            );
+            ",
+        )
+        .expect("create v2 schema");
+    }
+
+    // Open with SqliteStorage - should apply v3 and v4 migrations
+    let storage = SqliteStorage::open(&db_path).expect("open v2 db");
+
+    // Verify migration completed
+    assert_eq!(
+        storage.schema_version().unwrap(),
+        CURRENT_SCHEMA_VERSION,
+        "should migrate to the current schema"
+    );
+}
+
+#[test]
+fn foreign_keys_are_enforced() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("fk.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    // Try to insert a conversation with non-existent agent_id
+    let result = storage.raw().execute(
+        "INSERT INTO conversations(agent_id, source_path) VALUES(999, '/test')",
+        [],
+    );
+
+    assert!(
+        result.is_err(),
+        "foreign key constraint should prevent invalid agent_id"
+    );
+}
+
+#[test]
+fn unique_constraints_work() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("unique.db");
+    let storage = SqliteStorage::open(&db_path).expect("open");
+
+    // Insert an agent
+    storage
+        .raw()
+        .execute(
+            "INSERT INTO agents(slug, name, kind, created_at, updated_at) VALUES('test', 'Test', 'cli', 0, 0)",
+            [],
+        )
+        .expect("first insert");
+
+    // Try to insert duplicate slug
+    let result = storage.raw().execute(
+        "INSERT INTO agents(slug, name, kind, created_at, updated_at) VALUES('test', 'Test2', 'cli', 0, 0)",
         [],
     );
 
@@ -1030,6 +1720,10 @@ fn migration_from_v3_creates_sources_table() {
                 message_id UNINDEXED,
                 tokenize='porter'
             );
+
+            INSERT INTO agents VALUES (1, 'codex', 'Codex', NULL, 'cli', 0, 0);
+            INSERT INTO conversations (id, agent_id, source_path) VALUES (1, 1, '/logs/a.jsonl');
+            INSERT INTO messages (conversation_id, idx, role, content) VALUES (1, 0, 'user', 'hi');
             ",
         )
         .expect("create v3 schema");
@@ -1039,7 +1733,11 @@ fn migration_from_v3_creates_sources_table() {
     let storage = SqliteStorage::open(&db_path).expect("open v3 db");
 
     // Verify migration completed
-    assert_eq!(storage.schema_version().unwrap(), 5, "should migrate to v5");
+    assert_eq!(
+        storage.schema_version().unwrap(),
+        CURRENT_SCHEMA_VERSION,
+        "should migrate to the current schema"
+    );
 
     // Verify sources table was created with local source
     let sources = storage.list_sources().expect("list_sources");
@@ -1047,6 +1745,13 @@ fn migration_from_v3_creates_sources_table() {
         sources.iter().any(|s| s.id == LOCAL_SOURCE_ID),
         "local source should exist after migration"
     );
+
+    // Rewriting `conversations` in v5 must not cascade into its messages
+    let messages: i64 = storage
+        .raw()
+        .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(messages, 1, "messages survive the v5 table rewrite");
 }
 
 // -------------------------------------------------------------------------
@@ -1285,126 +1990,543 @@ fn open_or_rebuild_migrates_compatible_schema() {
 }
 
 #[test]
-fn open_or_rebuild_triggers_rebuild_for_future_version() {
-    use rusqlite::Connection;
-
+fn open_or_rebuild_triggers_rebuild_for_future_version() {
+    use rusqlite::Connection;
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("future.db");
+
+    // Create a database with a future schema version
+    {
+        let conn = Connection::open(&db_path).expect("create future db");
+        conn.execute_batch(
+            r"
+            CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO meta(key, value) VALUES('schema_version', '999');
+            ",
+        )
+        .expect("create future schema");
+    }
+
+    // Open with open_or_rebuild - should trigger rebuild
+    let result = SqliteStorage::open_or_rebuild(&db_path);
+
+    match result {
+        Err(MigrationError::RebuildRequired {
+            reason,
+            backup_path,
+        }) => {
+            assert!(
+                reason.contains("999"),
+                "reason should mention future version: {}",
+                reason
+            );
+            assert!(backup_path.is_some(), "backup should be created");
+            let backup = backup_path.unwrap();
+            assert!(backup.exists(), "backup file should exist");
+        }
+        Ok(_) => panic!("should have triggered rebuild for future version"),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+
+    // Original database should be deleted
+    assert!(!db_path.exists(), "original db should be deleted");
+}
+
+#[test]
+fn open_or_rebuild_handles_corrupted_db() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("corrupt.db");
+
+    // Create a corrupted database file
+    std::fs::write(&db_path, b"this is not a valid sqlite database").unwrap();
+
+    // Open with open_or_rebuild - should trigger rebuild
+    let result = SqliteStorage::open_or_rebuild(&db_path);
+
+    match result {
+        Err(MigrationError::RebuildRequired { backup_path, .. }) => {
+            assert!(backup_path.is_some(), "backup should be created");
+        }
+        Err(_) => {
+            // Also acceptable - database error during check
+        }
+        Ok(_) => panic!("should have failed for corrupted db"),
+    }
+}
+
+// =============================================================================
+// Timeline Source Filtering Tests (P7.8)
+// Tests for --source filtering in timeline command
+// =============================================================================
+
+/// Create a conversation with a specific source_id for testing timeline filtering
+fn sample_conv_with_source(
+    external_id: &str,
+    source_id: &str,
+    started_at: i64,
+    messages: Vec<Message>,
+) -> Conversation {
+    Conversation {
+        id: None,
+        agent_slug: "tester".into(),
+        workspace: Some(PathBuf::from("/workspace/demo")),
+        external_id: Some(external_id.to_string()),
+        title: Some(format!("Conv from {}", source_id)),
+        source_path: PathBuf::from(format!("/logs/{}.jsonl", external_id)),
+        started_at: Some(started_at),
+        ended_at: Some(started_at + 100),
+        approx_tokens: Some(42),
+        metadata_json: serde_json::json!({}),
+        messages,
+        source_id: source_id.to_string(),
+        origin_host: if source_id != "local" {
+            Some(format!("{}.local", source_id))
+        } else {
+            None
+        },
+    }
+}
+
+#[test]
+fn timeline_source_filter_local_only() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("timeline.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    // Setup: Create agent
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
+
+    // Ensure sources exist
+    storage
+        .upsert_source(&Source::local())
+        .expect("ensure local source");
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
+        .expect("ensure remote source");
+
+    // Insert conversations: 2 local, 1 remote
+    let now = 1700000000i64;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-2", "local", now + 1000, vec![msg(0, now + 1000)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("remote-1", "laptop", now + 2000, vec![msg(0, now + 2000)]),
+        )
+        .unwrap();
+
+    // Query with source_id = 'local' filter
+    let local_only: Vec<String> = storage
+        .raw()
+        .prepare(
+            "SELECT c.external_id FROM conversations c
+             WHERE c.source_id = 'local'
+             ORDER BY c.started_at DESC",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert_eq!(local_only.len(), 2, "should return 2 local conversations");
+    assert!(local_only.contains(&"local-1".to_string()));
+    assert!(local_only.contains(&"local-2".to_string()));
+    assert!(
+        !local_only.contains(&"remote-1".to_string()),
+        "should not include remote"
+    );
+}
+
+#[test]
+fn timeline_source_filter_remote_only() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("timeline_remote.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
+
+    storage
+        .upsert_source(&Source::local())
+        .expect("ensure local source");
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
+        .expect("ensure remote source");
+    storage
+        .upsert_source(&Source::remote("server", "server.example.com"))
+        .expect("ensure second remote source");
+
+    let now = 1700000000i64;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("laptop-1", "laptop", now + 1000, vec![msg(0, now + 1000)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("server-1", "server", now + 2000, vec![msg(0, now + 2000)]),
+        )
+        .unwrap();
+
+    // Query with source_id != 'local' (remote filter)
+    let remote_only: Vec<String> = storage
+        .raw()
+        .prepare(
+            "SELECT c.external_id FROM conversations c
+             WHERE c.source_id != 'local'
+             ORDER BY c.started_at DESC",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert_eq!(remote_only.len(), 2, "should return 2 remote conversations");
+    assert!(remote_only.contains(&"laptop-1".to_string()));
+    assert!(remote_only.contains(&"server-1".to_string()));
+    assert!(
+        !remote_only.contains(&"local-1".to_string()),
+        "should not include local"
+    );
+}
+
+#[test]
+fn timeline_source_filter_specific_source() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("timeline_specific.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
+
+    storage
+        .upsert_source(&Source::local())
+        .expect("ensure local source");
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
+        .expect("ensure remote source");
+    storage
+        .upsert_source(&Source::remote("server", "server.example.com"))
+        .expect("ensure second remote source");
+
+    let now = 1700000000i64;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("laptop-1", "laptop", now + 1000, vec![msg(0, now + 1000)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("laptop-2", "laptop", now + 2000, vec![msg(0, now + 2000)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("server-1", "server", now + 3000, vec![msg(0, now + 3000)]),
+        )
+        .unwrap();
+
+    // Query with source_id = 'laptop' (specific source)
+    let laptop_only: Vec<String> = storage
+        .raw()
+        .prepare(
+            "SELECT c.external_id FROM conversations c
+             WHERE c.source_id = 'laptop'
+             ORDER BY c.started_at DESC",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert_eq!(laptop_only.len(), 2, "should return 2 laptop conversations");
+    assert!(laptop_only.contains(&"laptop-1".to_string()));
+    assert!(laptop_only.contains(&"laptop-2".to_string()));
+    assert!(
+        !laptop_only.contains(&"local-1".to_string()),
+        "should not include local"
+    );
+    assert!(
+        !laptop_only.contains(&"server-1".to_string()),
+        "should not include server"
+    );
+}
+
+// =============================================================================
+// Timeline JSON Provenance Fields Tests (P7.10)
+// Tests for provenance fields (source_id, origin_kind, origin_host) in timeline output
+// =============================================================================
+
+#[test]
+fn timeline_json_includes_source_id_field() {
+    // P7.10: Verify timeline SQL returns source_id field
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("timeline_json.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
+
+    storage
+        .upsert_source(&Source::local())
+        .expect("upsert local source");
+
+    let now = 1700000000i64;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("test-1", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
+
+    // Query with source_id field selection (simulates timeline JSON output)
+    let result: Vec<(i64, String)> = storage
+        .raw()
+        .prepare(
+            "SELECT c.id, c.source_id FROM conversations c
+             WHERE c.source_id IS NOT NULL",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert!(!result.is_empty(), "should have at least one conversation");
+    let (_, source_id) = &result[0];
+    assert_eq!(source_id, "local", "source_id should be 'local'");
+}
+
+#[test]
+fn timeline_json_includes_origin_kind_field() {
+    // P7.10: Verify timeline SQL returns origin_kind from sources table
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("timeline_kind.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
+
+    // Create both local and remote sources
+    storage
+        .upsert_source(&Source::local())
+        .expect("upsert local source");
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
+        .expect("upsert remote source");
+
+    let now = 1700000000i64;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-conv", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source(
+                "remote-conv",
+                "laptop",
+                now + 1000,
+                vec![msg(0, now + 1000)],
+            ),
+        )
+        .unwrap();
+
+    // Query with origin_kind from sources table (matches timeline SQL)
+    let results: Vec<(String, String, String)> = storage
+        .raw()
+        .prepare(
+            "SELECT c.source_id, c.origin_host, s.kind as origin_kind
+             FROM conversations c
+             LEFT JOIN sources s ON c.source_id = s.id
+             ORDER BY c.source_id",
+        )
+        .unwrap()
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(2)?
+                    .unwrap_or_else(|| "local".into()),
+            ))
+        })
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    assert_eq!(results.len(), 2, "should have 2 conversations");
+
+    // Find local and remote results
+    let local = results
+        .iter()
+        .find(|(id, _, _)| id == "local")
+        .expect("local conv");
+    let remote = results
+        .iter()
+        .find(|(id, _, _)| id == "laptop")
+        .expect("remote conv");
+
+    // Verify origin_kind is correct
+    assert_eq!(local.2, "local", "local source should have kind 'local'");
+    assert_eq!(remote.2, "ssh", "remote source should have kind 'ssh'");
+}
+
+#[test]
+fn timeline_json_includes_origin_host_field() {
+    // P7.10: Verify timeline SQL returns origin_host for remote sessions
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("future.db");
+    let db_path = tmp.path().join("timeline_host.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
 
-    // Create a database with a future schema version
-    {
-        let conn = Connection::open(&db_path).expect("create future db");
-        conn.execute_batch(
-            r"
-            CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
-            INSERT INTO meta(key, value) VALUES('schema_version', '999');
-            ",
-        )
-        .expect("create future schema");
-    }
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let ws_id = storage
+        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+        .unwrap();
 
-    // Open with open_or_rebuild - should trigger rebuild
-    let result = SqliteStorage::open_or_rebuild(&db_path);
+    storage
+        .upsert_source(&Source::local())
+        .expect("upsert local source");
+    storage
+        .upsert_source(&Source::remote("work", "user@work.example.com"))
+        .expect("upsert remote source");
 
-    match result {
-        Err(MigrationError::RebuildRequired {
-            reason,
-            backup_path,
-        }) => {
-            assert!(
-                reason.contains("999"),
-                "reason should mention future version: {}",
-                reason
-            );
-            assert!(backup_path.is_some(), "backup should be created");
-            let backup = backup_path.unwrap();
-            assert!(backup.exists(), "backup file should exist");
-        }
-        Ok(_) => panic!("should have triggered rebuild for future version"),
-        Err(e) => panic!("unexpected error: {}", e),
-    }
+    let now = 1700000000i64;
 
-    // Original database should be deleted
-    assert!(!db_path.exists(), "original db should be deleted");
-}
+    // Local conversation - origin_host should be null
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("local-conv", "local", now, vec![msg(0, now)]),
+        )
+        .unwrap();
 
-#[test]
-fn open_or_rebuild_handles_corrupted_db() {
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("corrupt.db");
+    // Remote conversation - origin_host set via sample_conv_with_source
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            Some(ws_id),
+            &sample_conv_with_source("remote-conv", "work", now + 1000, vec![msg(0, now + 1000)]),
+        )
+        .unwrap();
 
-    // Create a corrupted database file
-    std::fs::write(&db_path, b"this is not a valid sqlite database").unwrap();
+    // Query origin_host field
+    let results: Vec<(String, Option<String>)> = storage
+        .raw()
+        .prepare("SELECT c.source_id, c.origin_host FROM conversations c ORDER BY c.source_id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
 
-    // Open with open_or_rebuild - should trigger rebuild
-    let result = SqliteStorage::open_or_rebuild(&db_path);
+    assert_eq!(results.len(), 2, "should have 2 conversations");
 
-    match result {
-        Err(MigrationError::RebuildRequired { backup_path, .. }) => {
-            assert!(backup_path.is_some(), "backup should be created");
-        }
-        Err(_) => {
-            // Also acceptable - database error during check
-        }
-        Ok(_) => panic!("should have failed for corrupted db"),
-    }
-}
+    let local = results
+        .iter()
+        .find(|(id, _)| id == "local")
+        .expect("local conv");
+    let remote = results
+        .iter()
+        .find(|(id, _)| id == "work")
+        .expect("remote conv");
 
-// =============================================================================
-// Timeline Source Filtering Tests (P7.8)
-// Tests for --source filtering in timeline command
-// =============================================================================
+    // Local should have null origin_host
+    assert!(
+        local.1.is_none(),
+        "local source should have null origin_host"
+    );
 
-/// Create a conversation with a specific source_id for testing timeline filtering
-fn sample_conv_with_source(
-    external_id: &str,
-    source_id: &str,
-    started_at: i64,
-    messages: Vec<Message>,
-) -> Conversation {
-    Conversation {
-        id: None,
-        agent_slug: "tester".into(),
-        workspace: Some(PathBuf::from("/workspace/demo")),
-        external_id: Some(external_id.to_string()),
-        title: Some(format!("Conv from {}", source_id)),
-        source_path: PathBuf::from(format!("/logs/{}.jsonl", external_id)),
-        started_at: Some(started_at),
-        ended_at: Some(started_at + 100),
-        approx_tokens: Some(42),
-        metadata_json: serde_json::json!({}),
-        messages,
-        source_id: source_id.to_string(),
-        origin_host: if source_id != "local" {
-            Some(format!("{}.local", source_id))
-        } else {
-            None
-        },
-    }
+    // Remote should have origin_host set
+    assert!(
+        remote.1.is_some(),
+        "remote source should have origin_host set"
+    );
+    assert_eq!(
+        remote.1.as_deref(),
+        Some("work.local"),
+        "origin_host should match the pattern from sample_conv_with_source"
+    );
 }
 
 #[test]
-fn timeline_source_filter_local_only() {
+fn timeline_json_grouped_output_includes_provenance() {
+    // P7.10: Verify provenance fields are present when timeline is grouped
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline.db");
+    let db_path = tmp.path().join("timeline_grouped.db");
     let mut storage = SqliteStorage::open(&db_path).expect("open");
 
-    // Setup: Create agent
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
     let ws_id = storage
         .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
         .unwrap();
 
-    // Ensure sources exist
     storage
         .upsert_source(&Source::local())
-        .expect("ensure local source");
+        .expect("upsert local");
     storage
-        .upsert_source(&Source::remote("laptop", "laptop.local"))
-        .expect("ensure remote source");
+        .upsert_source(&Source::remote("server", "server.example.com"))
+        .expect("upsert remote");
 
-    // Insert conversations: 2 local, 1 remote
     let now = 1700000000i64;
+    // Same day, different sources
     storage
         .insert_conversation_tree(
             agent_id,
@@ -1416,465 +2538,1018 @@ fn timeline_source_filter_local_only() {
         .insert_conversation_tree(
             agent_id,
             Some(ws_id),
-            &sample_conv_with_source("local-2", "local", now + 1000, vec![msg(0, now + 1000)]),
-        )
-        .unwrap();
-    storage
-        .insert_conversation_tree(
-            agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("remote-1", "laptop", now + 2000, vec![msg(0, now + 2000)]),
+            &sample_conv_with_source("server-1", "server", now + 100, vec![msg(0, now + 100)]),
         )
         .unwrap();
 
-    // Query with source_id = 'local' filter
-    let local_only: Vec<String> = storage
+    // Query all provenance fields as timeline JSON would
+    let results: Vec<(i64, String, Option<String>, Option<String>)> = storage
         .raw()
         .prepare(
-            "SELECT c.external_id FROM conversations c
-             WHERE c.source_id = 'local'
-             ORDER BY c.started_at DESC",
+            "SELECT c.id, c.source_id, c.origin_host, s.kind as origin_kind
+             FROM conversations c
+             LEFT JOIN sources s ON c.source_id = s.id",
         )
         .unwrap()
-        .query_map([], |row| row.get(0))
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
         .unwrap()
         .map(|r| r.unwrap())
         .collect();
 
-    assert_eq!(local_only.len(), 2, "should return 2 local conversations");
-    assert!(local_only.contains(&"local-1".to_string()));
-    assert!(local_only.contains(&"local-2".to_string()));
+    // All entries should have source_id
+    for (id, source_id, _, _) in &results {
+        assert!(
+            !source_id.is_empty(),
+            "Entry {} should have non-empty source_id",
+            id
+        );
+    }
+
+    // Verify we have both local and remote entries with correct kinds
+    let has_local = results
+        .iter()
+        .any(|(_, sid, _, kind)| sid == "local" && kind.as_deref() == Some("local"));
+    let has_remote = results
+        .iter()
+        .any(|(_, sid, _, kind)| sid == "server" && kind.as_deref() == Some("ssh"));
+
+    assert!(has_local, "should have local entry with kind='local'");
+    assert!(has_remote, "should have remote entry with kind='ssh'");
+}
+
+#[test]
+fn tags_attach_list_and_filter_sessions() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("tags.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+
+    let first = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("a"), vec![msg(0, 1)]))
+        .unwrap()
+        .conversation_id;
+    let mut other = sample_conv(Some("b"), vec![msg(0, 1)]);
+    other.source_path = PathBuf::from("/logs/other.jsonl");
+    let second = storage
+        .insert_conversation_tree(agent_id, None, &other)
+        .unwrap()
+        .conversation_id;
+
+    assert!(storage.add_tag(first, " Flaky ").unwrap());
     assert!(
-        !local_only.contains(&"remote-1".to_string()),
-        "should not include remote"
+        !storage.add_tag(first, "flaky").unwrap(),
+        "tags are case-insensitive"
+    );
+    assert!(storage.add_tag(first, "release").unwrap());
+    assert!(storage.add_tag(second, "flaky").unwrap());
+    assert!(storage.add_tag(first, "two words").is_err());
+
+    assert_eq!(
+        storage.conversation_tags(first).unwrap(),
+        ["flaky", "release"]
+    );
+    assert_eq!(
+        storage.list_tags().unwrap(),
+        vec![("flaky".to_string(), 2), ("release".to_string(), 1)]
+    );
+    let flaky: Vec<Option<i64>> = storage
+        .conversations_with_tag("FLAKY")
+        .unwrap()
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    assert_eq!(flaky.len(), 2);
+    assert!(flaky.contains(&Some(first)) && flaky.contains(&Some(second)));
+
+    // `tag:` filters require every tag
+    let both = coding_agent_search::storage::sqlite::load_tagged_sessions(
+        storage.raw(),
+        &["flaky".to_string(), "release".to_string()],
+    )
+    .unwrap();
+    assert_eq!(both.len(), 1);
+    assert!(both.contains(&("local".to_string(), "/logs/demo.jsonl".to_string())));
+
+    assert!(storage.remove_tag(first, "release").unwrap());
+    assert!(!storage.remove_tag(first, "release").unwrap());
+    assert_eq!(
+        storage.list_tags().unwrap(),
+        vec![("flaky".to_string(), 2)],
+        "unused tags are dropped"
+    );
+}
+
+#[test]
+fn starring_a_session_uses_the_reserved_tag() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("star.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let id = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("s"), vec![msg(0, 1)]))
+        .unwrap()
+        .conversation_id;
+
+    assert_eq!(
+        storage
+            .set_session_starred("local", "/logs/demo.jsonl", true)
+            .unwrap(),
+        1
+    );
+    assert!(!storage.set_starred(id, true).unwrap(), "already starred");
+    assert_eq!(storage.conversation_tags(id).unwrap(), ["starred"]);
+    assert_eq!(storage.starred_conversations().unwrap()[0].id, Some(id));
+    assert_eq!(
+        storage
+            .set_session_starred("local", "/logs/missing.jsonl", true)
+            .unwrap(),
+        0
     );
+
+    assert!(storage.set_starred(id, false).unwrap());
+    assert!(storage.starred_conversations().unwrap().is_empty());
 }
 
 #[test]
-fn timeline_source_filter_remote_only() {
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_remote.db");
-    let mut storage = SqliteStorage::open(&db_path).expect("open");
+fn filter_conversations_and_move_workspace() {
+    use coding_agent_search::storage::sqlite::ConversationFilter;
 
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("update.db")).expect("open");
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
-        .unwrap();
-
-    storage
-        .upsert_source(&Source::local())
-        .expect("ensure local source");
-    storage
-        .upsert_source(&Source::remote("laptop", "laptop.local"))
-        .expect("ensure remote source");
-    storage
-        .upsert_source(&Source::remote("server", "server.example.com"))
-        .expect("ensure second remote source");
+    let mut insert = |ext: &str, ws: &str| {
+        let mut conv = sample_conv(Some(ext), vec![msg(0, 1)]);
+        conv.source_path = PathBuf::from(format!("/logs/{ext}.jsonl"));
+        let ws_id = storage.ensure_workspace(&PathBuf::from(ws), None).unwrap();
+        storage
+            .insert_conversation_tree(agent_id, Some(ws_id), &conv)
+            .unwrap()
+            .conversation_id
+    };
+    let root = insert("root", "/home/me/old");
+    let nested = insert("nested", "/home/me/old/sub");
+    let sibling = insert("sibling", "/home/me/older");
+    storage.add_tag(nested, "keep").unwrap();
+
+    let ids = |storage: &SqliteStorage, filter: &ConversationFilter| -> Vec<i64> {
+        let mut ids: Vec<i64> = storage
+            .filter_conversations(filter)
+            .unwrap()
+            .iter()
+            .filter_map(|c| c.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+    let under_old = ConversationFilter {
+        workspace: Some("/home/me/old/".into()),
+        ..Default::default()
+    };
+    assert_eq!(ids(&storage, &under_old), vec![root, nested]);
+    let tagged = ConversationFilter {
+        tag: Some("KEEP".into()),
+        ..under_old.clone()
+    };
+    assert_eq!(ids(&storage, &tagged), vec![nested]);
+    let other_agent = ConversationFilter {
+        agent: Some("codex".into()),
+        ..Default::default()
+    };
+    assert!(ids(&storage, &other_agent).is_empty());
+    assert_eq!(ids(&storage, &ConversationFilter::default()).len(), 3);
 
-    let now = 1700000000i64;
     storage
-        .insert_conversation_tree(
-            agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
-        )
+        .set_conversation_workspace(nested, Some(&PathBuf::from("/home/me/new/sub")))
         .unwrap();
-    storage
-        .insert_conversation_tree(
-            agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("laptop-1", "laptop", now + 1000, vec![msg(0, now + 1000)]),
+    let moved = storage.find_conversation(nested).unwrap().unwrap();
+    assert_eq!(moved.workspace, Some(PathBuf::from("/home/me/new/sub")));
+    let fts_workspace: String = storage
+        .raw()
+        .query_row(
+            "SELECT workspace FROM fts_messages WHERE message_id IN
+             (SELECT id FROM messages WHERE conversation_id = ?)",
+            [nested],
+            |row| row.get(0),
         )
         .unwrap();
-    storage
+    assert_eq!(fts_workspace, "/home/me/new/sub");
+    assert_eq!(ids(&storage, &under_old), vec![root]);
+    assert_eq!(
+        storage
+            .find_conversation(sibling)
+            .unwrap()
+            .unwrap()
+            .workspace,
+        Some(PathBuf::from("/home/me/older"))
+    );
+}
+
+#[test]
+fn annotations_crud_and_note_matches() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let db_path = tmp.path().join("notes.db");
+    let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let conv_id = storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("server-1", "server", now + 2000, vec![msg(0, now + 2000)]),
-        )
-        .unwrap();
-
-    // Query with source_id != 'local' (remote filter)
-    let remote_only: Vec<String> = storage
-        .raw()
-        .prepare(
-            "SELECT c.external_id FROM conversations c
-             WHERE c.source_id != 'local'
-             ORDER BY c.started_at DESC",
+            None,
+            &sample_conv(Some("n"), vec![msg(0, 1), msg(1, 2)]),
         )
         .unwrap()
-        .query_map([], |row| row.get(0))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
+        .conversation_id;
+    let second = storage.message_id_at(conv_id, 1).unwrap().unwrap();
 
-    assert_eq!(remote_only.len(), 2, "should return 2 remote conversations");
-    assert!(remote_only.contains(&"laptop-1".to_string()));
-    assert!(remote_only.contains(&"server-1".to_string()));
+    let id = storage
+        .add_annotation(second, "  Root cause: stale lockfile ")
+        .unwrap();
+    assert!(storage.add_annotation(second, "   ").is_err());
+    let note = storage.get_annotation(id).unwrap().unwrap();
+    assert_eq!(note.note, "Root cause: stale lockfile");
+    assert_eq!(note.message_id, second);
+
+    let matches = |storage: &SqliteStorage, terms: &[&str]| {
+        let terms: Vec<String> = terms.iter().map(|t| t.to_string()).collect();
+        coding_agent_search::storage::sqlite::load_note_matches(storage.raw(), &terms).unwrap()
+    };
+    let hit = ("local".to_string(), "/logs/demo.jsonl".to_string(), 1);
+    assert!(matches(&storage, &["lockfile"]).contains(&hit));
     assert!(
-        !remote_only.contains(&"local-1".to_string()),
-        "should not include local"
+        matches(&storage, &["stale", "lock"]).contains(&hit),
+        "prefix terms AND together"
+    );
+    assert!(matches(&storage, &["lockfile", "deadlock"]).is_empty());
+
+    assert!(
+        storage
+            .update_annotation(id, "Root cause: clock skew")
+            .unwrap()
     );
+    assert!(matches(&storage, &["lockfile"]).is_empty());
+    assert!(matches(&storage, &["skew"]).contains(&hit));
+    let by_message = storage.annotations_for_conversation(conv_id).unwrap();
+    assert_eq!(by_message[&second].len(), 1);
+    assert_eq!(storage.annotations_for_message(second).unwrap()[0].id, id);
+
+    assert!(storage.delete_annotation(id).unwrap());
+    assert!(!storage.delete_annotation(id).unwrap());
+    assert!(storage.get_annotation(id).unwrap().is_none());
+    assert!(matches(&storage, &["skew"]).is_empty());
 }
 
 #[test]
-fn timeline_source_filter_specific_source() {
+fn optimize_reclaims_free_pages_and_truncates_wal() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_specific.db");
+    let db_path = tmp.path().join("optimize.db");
     let mut storage = SqliteStorage::open(&db_path).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let messages = (0..200)
+        .map(|i| Message {
+            content: format!("msg-{i} {}", "padding ".repeat(256)),
+            ..msg(i, i)
+        })
+        .collect();
+    let conv_id = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("big"), messages))
+        .unwrap()
+        .conversation_id;
+    storage.delete_conversations(&[conv_id]).unwrap();
+    let free_pages = |storage: &SqliteStorage| -> i64 {
+        storage
+            .raw()
+            .query_row("PRAGMA freelist_count", [], |r| r.get(0))
+            .unwrap()
+    };
+    assert!(free_pages(&storage) > 0);
+
+    storage.optimize().unwrap();
+    assert_eq!(free_pages(&storage), 0);
+    let wal = std::fs::metadata(tmp.path().join("optimize.db-wal")).map_or(0, |m| m.len());
+    assert_eq!(wal, 0);
+}
 
+#[test]
+fn storage_stats_rank_agents_and_conversations_and_sample_daily() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("stats.db")).expect("open");
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+    let small = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("small"), vec![msg(0, 1)]))
+        .unwrap()
+        .conversation_id;
+    let big_messages = (0..3)
+        .map(|i| Message {
+            content: "x".repeat(100),
+            ..msg(i, i)
+        })
+        .collect();
+    let mut big_conv = sample_conv(Some("big"), big_messages);
+    big_conv.source_path = "/tmp/big.jsonl".into();
+    let big = storage
+        .insert_conversation_tree(agent_id, None, &big_conv)
+        .unwrap()
+        .conversation_id;
+
+    let agents = storage.agent_storage().unwrap();
+    assert_eq!(agents.len(), 1);
+    assert_eq!((agents[0].conversations, agents[0].messages), (2, 4));
+    assert!(agents[0].content_bytes >= 300);
+
+    let largest = storage.largest_conversations(1).unwrap();
+    assert_eq!(largest.len(), 1);
+    assert_eq!(largest[0].conversation_id, big);
+    assert_ne!(largest[0].conversation_id, small);
+    assert_eq!(largest[0].messages, 3);
+
+    let first = storage.record_storage_sample().unwrap();
+    let second = storage.record_storage_sample().unwrap();
+    assert_eq!(first.day, second.day);
+    let samples = storage.storage_samples(30).unwrap();
+    assert_eq!(samples.len(), 1, "one sample per day");
+    assert_eq!((samples[0].conversations, samples[0].messages), (2, 4));
+    assert!(samples[0].db_bytes > 0);
+}
+
+#[test]
+fn compression_is_transparent_and_recompress_follows_the_setting() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("zstd.db")).expect("open");
+    storage.set_compression(true);
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let dump = "pub fn handler() -> Result<()> { Ok(()) }\n".repeat(200);
+    let messages = vec![
+        msg(0, 1),
+        Message {
+            content: dump.clone(),
+            ..msg(1, 2)
+        },
+    ];
+    let conv_id = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("zstd"), messages))
+        .unwrap()
+        .conversation_id;
+    let stored = |storage: &SqliteStorage| -> Vec<(String, i64)> {
+        storage
+            .raw()
+            .prepare("SELECT typeof(content), compressed FROM messages ORDER BY idx")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+    assert_eq!(
+        stored(&storage),
+        vec![("text".to_string(), 0), ("blob".to_string(), 1)]
+    );
+
+    let fetched = storage.fetch_messages(conv_id).unwrap();
+    assert_eq!(fetched[1].content, dump);
+    let via_sql: String = storage
+        .raw()
+        .query_row(
+            "SELECT cass_text(content) FROM messages WHERE idx = 1",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(via_sql, dump);
+    let fts_hits: i64 = storage
+        .raw()
+        .query_row(
+            "SELECT COUNT(*) FROM fts_messages WHERE fts_messages MATCH 'handler'",
+            [],
+            |r| r.get(0),
+        )
         .unwrap();
+    assert_eq!(fts_hits, 1);
 
-    storage
-        .upsert_source(&Source::local())
-        .expect("ensure local source");
-    storage
-        .upsert_source(&Source::remote("laptop", "laptop.local"))
-        .expect("ensure remote source");
-    storage
-        .upsert_source(&Source::remote("server", "server.example.com"))
-        .expect("ensure second remote source");
+    storage.set_compression(false);
+    assert_eq!(storage.recompress_messages().unwrap(), 1);
+    assert_eq!(
+        stored(&storage),
+        vec![("text".to_string(), 0), ("text".to_string(), 0)]
+    );
+    assert_eq!(storage.fetch_messages(conv_id).unwrap()[1].content, dump);
+}
 
-    let now = 1700000000i64;
+#[test]
+fn dedupe_clusters_copies_and_retries_and_merges_metadata() {
+    use coding_agent_search::indexer::dedupe::{
+        ClusterKind, DEFAULT_THRESHOLD, DedupeOptions, find_duplicates,
+    };
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("dedupe.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let reply = |idx: i64, text: &str| Message {
+        role: MessageRole::Agent,
+        content: text.to_string(),
+        ..msg(idx, idx)
+    };
+    let mut insert = |ext: &str, path: &str, messages: Vec<Message>| {
+        let mut conv = sample_conv(Some(ext), messages);
+        conv.source_path = PathBuf::from(path);
+        storage
+            .insert_conversation_tree(agent_id, None, &conv)
+            .unwrap()
+            .conversation_id
+    };
+    let original = insert(
+        "orig",
+        "/logs/orig.jsonl",
+        vec![msg(0, 1), reply(1, "done")],
+    );
+    let copy = insert(
+        "copy",
+        "/backup/orig.jsonl",
+        vec![msg(0, 1), reply(1, "done")],
+    );
+    let retry = insert(
+        "retry",
+        "/logs/retry.jsonl",
+        vec![msg(0, 1), reply(1, "a different   answer")],
+    );
+    let unrelated = insert("other", "/logs/other.jsonl", vec![msg(5, 1)]);
+
+    let report = find_duplicates(
+        &storage,
+        &DedupeOptions {
+            threshold: DEFAULT_THRESHOLD,
+            agents: Vec::new(),
+        },
+    )
+    .unwrap();
+    assert_eq!(report.conversations_scanned, 4);
+    assert_eq!(report.clusters.len(), 1);
+    let cluster = &report.clusters[0];
+    assert_eq!(cluster.kind, ClusterKind::Retry);
+    let ids: Vec<i64> = cluster.members.iter().map(|m| m.conversation_id).collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.contains(&original) && ids.contains(&copy) && ids.contains(&retry));
+    assert!(!ids.contains(&unrelated));
+    assert_eq!(report.duplicates, 2);
+    assert!(report.reclaimable_bytes > 0);
+
+    // A different workspace turns the retry into an unrelated session
+    let elsewhere = storage
+        .ensure_workspace(&PathBuf::from("/workspace/elsewhere"), None)
+        .unwrap();
     storage
-        .insert_conversation_tree(
-            agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
+        .raw()
+        .execute(
+            "UPDATE conversations SET workspace_id = ? WHERE id = ?",
+            [elsewhere, retry],
         )
         .unwrap();
-    storage
+    let report = find_duplicates(
+        &storage,
+        &DedupeOptions {
+            threshold: 1.0,
+            agents: vec!["TESTER".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(report.clusters.len(), 1);
+    assert_eq!(report.clusters[0].kind, ClusterKind::Identical);
+    assert_eq!(report.clusters[0].members.len(), 2);
+
+    assert!(storage.add_tag(copy, "flaky").unwrap());
+    let copy_reply = storage.message_id_at(copy, 1).unwrap().unwrap();
+    storage.add_annotation(copy_reply, "worth keeping").unwrap();
+    assert_eq!(
+        storage
+            .merge_conversation_metadata(original, &[copy])
+            .unwrap(),
+        2
+    );
+    storage.delete_conversations(&[copy]).unwrap();
+    assert_eq!(storage.conversation_tags(original).unwrap(), ["flaky"]);
+    let original_reply = storage.message_id_at(original, 1).unwrap().unwrap();
+    assert_eq!(
+        storage.annotations_for_message(original_reply).unwrap()[0].note,
+        "worth keeping"
+    );
+}
+
+#[test]
+fn archive_round_trips_user_data_and_reimport_is_a_no_op() {
+    use coding_agent_search::storage::archive::{
+        ConflictResolution, export_archive, import_archive, read_header,
+    };
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut source = SqliteStorage::open(&tmp.path().join("source.db")).expect("open");
+    let agent_id = source.ensure_agent(&sample_agent()).unwrap();
+    let workspace_id = source
+        .ensure_workspace(&PathBuf::from("/workspace/demo"), None)
+        .unwrap();
+    let conv_id = source
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("laptop-1", "laptop", now + 1000, vec![msg(0, now + 1000)]),
+            Some(workspace_id),
+            &sample_conv(Some("keep"), vec![msg(0, 1), msg(1, 2)]),
         )
+        .unwrap()
+        .conversation_id;
+    let mut untitled = sample_conv(None, vec![msg(0, 3)]);
+    untitled.source_path = PathBuf::from("/logs/no-id.jsonl");
+    source
+        .insert_conversation_tree(agent_id, None, &untitled)
         .unwrap();
-    storage
+    source.add_tag(conv_id, "release").unwrap();
+    let second = source.message_id_at(conv_id, 1).unwrap().unwrap();
+    source.add_annotation(second, "the fix").unwrap();
+    source
+        .set_retained("local", "/logs/demo.jsonl", true)
+        .unwrap();
+    source
+        .set_rating("local", "/logs/demo.jsonl", 1, Some("solid"))
+        .unwrap();
+
+    let archive = tmp.path().join("backup.cassar");
+    let exported = export_archive(&source, &archive).unwrap();
+    assert_eq!((exported.conversations, exported.messages), (2, 3));
+    assert_eq!((exported.tags, exported.notes), (1, 1));
+    let header = read_header(&archive).unwrap();
+    assert_eq!(header.format, "cassar");
+    assert_eq!(header.conversations, 2);
+
+    let mut target = SqliteStorage::open(&tmp.path().join("target.db")).expect("open");
+    let imported = import_archive(&mut target, &archive, &mut |_| {
+        ConflictResolution::KeepLocal
+    })
+    .unwrap();
+    assert_eq!((imported.conversations, imported.messages), (2, 3));
+    assert_eq!(imported.created_ids.len(), 2);
+    assert_eq!((imported.locks, imported.ratings), (1, 1));
+
+    let restored = target
+        .get_by_external_id("keep", Some("tester"))
+        .unwrap()
+        .unwrap();
+    let restored_id = restored.id.unwrap();
+    assert_eq!(restored.workspace, Some(PathBuf::from("/workspace/demo")));
+    assert_eq!(restored.messages.len(), 2);
+    assert_eq!(target.conversation_tags(restored_id).unwrap(), ["release"]);
+    let note_target = target.message_id_at(restored_id, 1).unwrap().unwrap();
+    assert_eq!(
+        target.annotations_for_message(note_target).unwrap()[0].note,
+        "the fix"
+    );
+    assert_eq!(target.list_retained().unwrap().len(), 1);
+    assert_eq!(
+        target.list_ratings().unwrap()[0].note.as_deref(),
+        Some("solid")
+    );
+
+    let again = import_archive(&mut target, &archive, &mut |_| {
+        ConflictResolution::KeepLocal
+    })
+    .unwrap();
+    assert_eq!((again.conversations, again.skipped, again.notes), (0, 2, 0));
+    let count: i64 = target
+        .raw()
+        .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(count, 2);
+
+    // Notes added since the last export merge into the imported conversation
+    let first = source.message_id_at(conv_id, 0).unwrap().unwrap();
+    source.add_annotation(first, "context").unwrap();
+    let written = source.annotations_for_message(first).unwrap()[0].created_at;
+    export_archive(&source, &archive).unwrap();
+    let merged = import_archive(&mut target, &archive, &mut |_| {
+        ConflictResolution::KeepLocal
+    })
+    .unwrap();
+    assert_eq!((merged.conversations, merged.notes), (0, 1));
+    let first_target = target.message_id_at(restored_id, 0).unwrap().unwrap();
+    let notes = target.annotations_for_message(first_target).unwrap();
+    assert_eq!(
+        (notes[0].note.as_str(), notes[0].created_at),
+        ("context", written)
+    );
+    assert_eq!(
+        target.annotations_for_message(note_target).unwrap().len(),
+        1
+    );
+
+    std::fs::write(tmp.path().join("junk.cassar"), b"not an archive").unwrap();
+    assert!(read_header(&tmp.path().join("junk.cassar")).is_err());
+}
+
+#[test]
+fn archive_import_settles_conflicting_conversations() {
+    use coding_agent_search::storage::archive::{
+        ConflictPolicy, ConflictResolution, export_archive, import_archive,
+    };
+
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut laptop = SqliteStorage::open(&tmp.path().join("laptop.db")).expect("open");
+    let agent_id = laptop.ensure_agent(&sample_agent()).unwrap();
+    let mut edited = msg(1, 50);
+    edited.content = "msg-1 (edited on the laptop)".into();
+    let shared = laptop
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("laptop-2", "laptop", now + 2000, vec![msg(0, now + 2000)]),
+            None,
+            &sample_conv(Some("shared"), vec![msg(0, 1), edited]),
         )
-        .unwrap();
-    storage
+        .unwrap()
+        .conversation_id;
+    let edited_id = laptop.message_id_at(shared, 1).unwrap().unwrap();
+    laptop.add_annotation(edited_id, "why it changed").unwrap();
+    laptop
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("server-1", "server", now + 3000, vec![msg(0, now + 3000)]),
+            None,
+            &sample_conv(Some("extended"), vec![msg(0, 1), msg(1, 2), msg(2, 3)]),
         )
         .unwrap();
+    let archive = tmp.path().join("laptop.cassar");
+    export_archive(&laptop, &archive).unwrap();
+
+    let open_desktop = |name: &str| {
+        let mut desktop = SqliteStorage::open(&tmp.path().join(name)).expect("open");
+        let agent_id = desktop.ensure_agent(&sample_agent()).unwrap();
+        for ext in ["shared", "extended"] {
+            desktop
+                .insert_conversation_tree(
+                    agent_id,
+                    None,
+                    &sample_conv(Some(ext), vec![msg(0, 1), msg(1, 2)]),
+                )
+                .unwrap();
+        }
+        desktop
+    };
+    let content_of = |storage: &SqliteStorage, ext: &str| {
+        storage
+            .get_by_external_id(ext, Some("tester"))
+            .unwrap()
+            .unwrap()
+            .messages[1]
+            .content
+            .clone()
+    };
 
-    // Query with source_id = 'laptop' (specific source)
-    let laptop_only: Vec<String> = storage
-        .raw()
-        .prepare(
-            "SELECT c.external_id FROM conversations c
-             WHERE c.source_id = 'laptop'
-             ORDER BY c.started_at DESC",
-        )
-        .unwrap()
-        .query_map([], |row| row.get(0))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
+    // Appended messages aren't a conflict; the edited message is
+    let mut desktop = open_desktop("skip.db");
+    let mut seen = Vec::new();
+    let summary = import_archive(&mut desktop, &archive, &mut |c| {
+        seen.push((c.external_id.clone(), c.differing.clone()));
+        ConflictPolicy::Skip.resolve(c).unwrap()
+    })
+    .unwrap();
+    assert_eq!(seen, [("shared".to_string(), vec![1])]);
+    assert_eq!(
+        summary.conflicts[0].resolution,
+        ConflictResolution::KeepLocal
+    );
+    assert_eq!(content_of(&desktop, "shared"), "msg-1");
+    assert_eq!(
+        desktop
+            .get_by_external_id("extended", Some("tester"))
+            .unwrap()
+            .unwrap()
+            .messages
+            .len(),
+        3
+    );
 
-    assert_eq!(laptop_only.len(), 2, "should return 2 laptop conversations");
-    assert!(laptop_only.contains(&"laptop-1".to_string()));
-    assert!(laptop_only.contains(&"laptop-2".to_string()));
-    assert!(
-        !laptop_only.contains(&"local-1".to_string()),
-        "should not include local"
+    // The laptop's edit is newer than the desktop copy
+    let mut desktop = open_desktop("newest.db");
+    let summary = import_archive(&mut desktop, &archive, &mut |c| {
+        ConflictPolicy::KeepNewest.resolve(c).unwrap()
+    })
+    .unwrap();
+    assert_eq!(
+        summary.conflicts[0].resolution,
+        ConflictResolution::KeepArchive
     );
-    assert!(
-        !laptop_only.contains(&"server-1".to_string()),
-        "should not include server"
+    assert_eq!(summary.replaced_ids.len(), 1);
+    assert_eq!(
+        content_of(&desktop, "shared"),
+        "msg-1 (edited on the laptop)"
+    );
+    let replaced = desktop.message_id_at(summary.replaced_ids[0], 1).unwrap();
+    assert_eq!(
+        desktop.annotations_for_message(replaced.unwrap()).unwrap()[0].note,
+        "why it changed"
     );
-}
 
-// =============================================================================
-// Timeline JSON Provenance Fields Tests (P7.10)
-// Tests for provenance fields (source_id, origin_kind, origin_host) in timeline output
-// =============================================================================
+    let mut desktop = open_desktop("both.db");
+    let summary = import_archive(&mut desktop, &archive, &mut |c| {
+        ConflictPolicy::KeepBoth.resolve(c).unwrap()
+    })
+    .unwrap();
+    assert_eq!(
+        summary.conflicts[0].imported_as.as_deref(),
+        Some("shared.imported")
+    );
+    assert_eq!(content_of(&desktop, "shared"), "msg-1");
+    assert_eq!(
+        content_of(&desktop, "shared.imported"),
+        "msg-1 (edited on the laptop)"
+    );
+    assert_eq!(summary.created_ids.len(), 1);
+}
 
 #[test]
-fn timeline_json_includes_source_id_field() {
-    // P7.10: Verify timeline SQL returns source_id field
-    let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_json.db");
-    let mut storage = SqliteStorage::open(&db_path).expect("open");
+fn origin_kind_and_source_scoped_session_lookup() {
+    use coding_agent_search::sources::provenance::SourceFilter;
 
-    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("origin.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
         .unwrap();
 
+    // The same session id synced from a remote and present locally
+    let mut remote = sample_conv_with_source("shared", "laptop", 2_000, vec![msg(0, 2_000)]);
+    remote.metadata_json = serde_json::json!({
+        "cass": {"origin": {"source_id": "laptop", "kind": "ssh", "host": "laptop.local"}}
+    });
     storage
-        .upsert_source(&Source::local())
-        .expect("upsert local source");
-
-    let now = 1700000000i64;
+        .insert_conversation_tree(agent_id, None, &remote)
+        .unwrap();
     storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("test-1", "local", now, vec![msg(0, now)]),
+            None,
+            &sample_conv_with_source("shared", "local", 1_000, vec![msg(0, 1_000)]),
         )
         .unwrap();
 
-    // Query with source_id field selection (simulates timeline JSON output)
-    let result: Vec<(i64, String)> = storage
+    let kinds: Vec<(String, String)> = storage
         .raw()
-        .prepare(
-            "SELECT c.id, c.source_id FROM conversations c
-             WHERE c.source_id IS NOT NULL",
-        )
+        .prepare("SELECT source_id, origin_kind FROM conversations ORDER BY source_id")
         .unwrap()
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
         .unwrap()
-        .map(|r| r.unwrap())
+        .map(Result::unwrap)
         .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("laptop".to_string(), "ssh".to_string()),
+            ("local".to_string(), "local".to_string())
+        ]
+    );
 
-    assert!(!result.is_empty(), "should have at least one conversation");
-    let (_, source_id) = &result[0];
-    assert_eq!(source_id, "local", "source_id should be 'local'");
+    let find = |filter: SourceFilter| {
+        storage
+            .find_by_external_id_in("shared", None, &filter)
+            .unwrap()
+            .map(|c| c.source_id)
+    };
+    assert_eq!(find(SourceFilter::All).as_deref(), Some("local"));
+    assert_eq!(find(SourceFilter::Remote).as_deref(), Some("laptop"));
+    assert_eq!(
+        find(SourceFilter::SourceId("laptop".into())).as_deref(),
+        Some("laptop")
+    );
+    assert_eq!(find(SourceFilter::SourceId("desktop".into())), None);
 }
 
 #[test]
-fn timeline_json_includes_origin_kind_field() {
-    // P7.10: Verify timeline SQL returns origin_kind from sources table
+fn message_provenance_round_trips_and_falls_back_to_conversation_file() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_kind.db");
-    let mut storage = SqliteStorage::open(&db_path).expect("open");
-
+    let mut storage = SqliteStorage::open(&tmp.path().join("provenance.db")).expect("open");
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
-        .unwrap();
-
-    // Create both local and remote sources
-    storage
-        .upsert_source(&Source::local())
-        .expect("upsert local source");
-    storage
-        .upsert_source(&Source::remote("laptop", "laptop.local"))
-        .expect("upsert remote source");
 
-    let now = 1700000000i64;
-    storage
-        .insert_conversation_tree(
-            agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("local-conv", "local", now, vec![msg(0, now)]),
-        )
-        .unwrap();
-    storage
+    let mut merged = msg(1, 20);
+    merged.source_path = Some("/logs/demo-continued.jsonl".into());
+    merged.source_line = Some(7);
+    let mut first = msg(0, 10);
+    first.source_line = Some(3);
+    let outcome = storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source(
-                "remote-conv",
-                "laptop",
-                now + 1000,
-                vec![msg(0, now + 1000)],
-            ),
+            None,
+            &sample_conv(Some("prov"), vec![first, merged, msg(2, 30)]),
         )
         .unwrap();
 
-    // Query with origin_kind from sources table (matches timeline SQL)
-    let results: Vec<(String, String, String)> = storage
-        .raw()
-        .prepare(
-            "SELECT c.source_id, c.origin_host, s.kind as origin_kind
-             FROM conversations c
-             LEFT JOIN sources s ON c.source_id = s.id
-             ORDER BY c.source_id",
-        )
-        .unwrap()
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
-                row.get::<_, Option<String>>(2)?
-                    .unwrap_or_else(|| "local".into()),
-            ))
+    let stored = storage.fetch_messages(outcome.conversation_id).unwrap();
+    let locations: Vec<_> = stored
+        .iter()
+        .map(|m| {
+            let (path, line) = m.location("/logs/demo.jsonl");
+            (path.to_string(), line)
         })
-        .unwrap()
-        .map(|r| r.unwrap())
         .collect();
-
-    assert_eq!(results.len(), 2, "should have 2 conversations");
-
-    // Find local and remote results
-    let local = results
-        .iter()
-        .find(|(id, _, _)| id == "local")
-        .expect("local conv");
-    let remote = results
-        .iter()
-        .find(|(id, _, _)| id == "laptop")
-        .expect("remote conv");
-
-    // Verify origin_kind is correct
-    assert_eq!(local.2, "local", "local source should have kind 'local'");
-    assert_eq!(remote.2, "ssh", "remote source should have kind 'ssh'");
+    assert_eq!(
+        locations,
+        [
+            ("/logs/demo.jsonl".to_string(), Some(3)),
+            ("/logs/demo-continued.jsonl".to_string(), Some(7)),
+            ("/logs/demo.jsonl".to_string(), None),
+        ]
+    );
 }
 
 #[test]
-fn timeline_json_includes_origin_host_field() {
-    // P7.10: Verify timeline SQL returns origin_host for remote sessions
+fn rescanning_unchanged_messages_backfills_their_provenance() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_host.db");
-    let mut storage = SqliteStorage::open(&db_path).expect("open");
-
+    let mut storage = SqliteStorage::open(&tmp.path().join("backfill.db")).expect("open");
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
-        .unwrap();
-
-    storage
-        .upsert_source(&Source::local())
-        .expect("upsert local source");
-    storage
-        .upsert_source(&Source::remote("work", "user@work.example.com"))
-        .expect("upsert remote source");
-
-    let now = 1700000000i64;
 
-    // Local conversation - origin_host should be null
-    storage
+    // Stored before the connector recorded provenance
+    let outcome = storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("local-conv", "local", now, vec![msg(0, now)]),
+            None,
+            &sample_conv(Some("old"), vec![msg(0, 10), msg(1, 20)]),
         )
         .unwrap();
+    assert_eq!(storage.get_last_scan_ts().unwrap(), None);
 
-    // Remote conversation - origin_host set via sample_conv_with_source
-    storage
+    let mut first = msg(0, 10);
+    first.source_line = Some(2);
+    let mut second = msg(1, 20);
+    second.source_path = Some("/logs/demo-continued.jsonl".into());
+    second.source_line = Some(1);
+    let rescan = storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("remote-conv", "work", now + 1000, vec![msg(0, now + 1000)]),
+            None,
+            &sample_conv(Some("old"), vec![first, second]),
         )
         .unwrap();
+    assert_eq!(rescan.conversation_id, outcome.conversation_id);
+    assert!(rescan.inserted_indices.is_empty());
 
-    // Query origin_host field
-    let results: Vec<(String, Option<String>)> = storage
-        .raw()
-        .prepare("SELECT c.source_id, c.origin_host FROM conversations c ORDER BY c.source_id")
-        .unwrap()
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
-
-    assert_eq!(results.len(), 2, "should have 2 conversations");
-
-    let local = results
-        .iter()
-        .find(|(id, _)| id == "local")
-        .expect("local conv");
-    let remote = results
+    let stored = storage.fetch_messages(outcome.conversation_id).unwrap();
+    let provenance: Vec<_> = stored
         .iter()
-        .find(|(id, _)| id == "work")
-        .expect("remote conv");
-
-    // Local should have null origin_host
-    assert!(
-        local.1.is_none(),
-        "local source should have null origin_host"
-    );
-
-    // Remote should have origin_host set
-    assert!(
-        remote.1.is_some(),
-        "remote source should have origin_host set"
+        .map(|m| (m.source_path.as_deref(), m.source_line))
+        .collect();
+    assert_eq!(
+        provenance,
+        [
+            (None, Some(2)),
+            (Some("/logs/demo-continued.jsonl"), Some(1))
+        ]
     );
     assert_eq!(
-        remote.1.as_deref(),
-        Some("work.local"),
-        "origin_host should match the pattern from sample_conv_with_source"
+        storage
+            .message_provenance("local", "/logs/demo.jsonl", 1)
+            .unwrap(),
+        Some((Some("/logs/demo-continued.jsonl".to_string()), Some(1)))
     );
 }
 
 #[test]
-fn timeline_json_grouped_output_includes_provenance() {
-    // P7.10: Verify provenance fields are present when timeline is grouped
+fn indexed_at_cursor_finds_local_conversations_changed_since() {
     let tmp = tempfile::TempDir::new().unwrap();
-    let db_path = tmp.path().join("timeline_grouped.db");
-    let mut storage = SqliteStorage::open(&db_path).expect("open");
-
+    let mut storage = SqliteStorage::open(&tmp.path().join("cursor.db")).expect("open");
+    storage
+        .upsert_source(&Source::remote("laptop", "laptop.local"))
+        .unwrap();
     let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
-    let ws_id = storage
-        .ensure_workspace(PathBuf::from("/workspace/demo").as_path(), Some("Demo"))
+
+    let a = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("a"), vec![msg(0, 10)]))
+        .unwrap()
+        .conversation_id;
+    let b = storage
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("b"), vec![msg(0, 10)]))
+        .unwrap()
+        .conversation_id;
+    let mut remote = sample_conv(Some("r"), vec![msg(0, 10)]);
+    remote.source_id = "laptop".into();
+    storage
+        .insert_conversation_tree(agent_id, None, &remote)
         .unwrap();
+    assert_eq!(
+        storage.local_conversations_indexed_since(None).unwrap(),
+        [a, b]
+    );
 
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let cursor = SqliteStorage::now_millis();
+    // Rescanning unchanged messages leaves the conversation where it was
     storage
-        .upsert_source(&Source::local())
-        .expect("upsert local");
+        .insert_conversation_tree(agent_id, None, &sample_conv(Some("b"), vec![msg(0, 10)]))
+        .unwrap();
     storage
-        .upsert_source(&Source::remote("server", "server.example.com"))
-        .expect("upsert remote");
+        .insert_conversation_tree(
+            agent_id,
+            None,
+            &sample_conv(Some("a"), vec![msg(0, 10), msg(1, 20)]),
+        )
+        .unwrap();
+    assert_eq!(
+        storage
+            .local_conversations_indexed_since(Some(cursor))
+            .unwrap(),
+        [a]
+    );
+    assert!(storage.max_indexed_at().unwrap().unwrap() >= cursor);
+}
 
-    let now = 1700000000i64;
-    // Same day, different sources
+#[test]
+fn splitting_a_stored_session_by_workspace_moves_messages_out_of_it() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("split.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let before = storage
+        .ensure_workspace(&PathBuf::from("/workspace/demo"), None)
+        .unwrap();
+    let whole = sample_conv(Some("s"), (0..5).map(|i| msg(i, 10 + i)).collect());
+    let id = storage
+        .insert_conversation_tree(agent_id, Some(before), &whole)
+        .unwrap()
+        .conversation_id;
+
+    let segment = |index: i64, first_idx: i64, ws: &str, range: std::ops::Range<i64>| {
+        let mut conv = sample_conv(
+            Some(&if index == 0 {
+                "s".to_string()
+            } else {
+                format!("s#{index}")
+            }),
+            range.clone().map(|i| msg(i - first_idx, 10 + i)).collect(),
+        );
+        conv.workspace = Some(PathBuf::from(ws));
+        conv.ended_at = Some(10 + range.end - 1);
+        conv.metadata_json = serde_json::json!({
+            "segment": {"parent": "s", "index": index, "count": 2, "first_idx": first_idx}
+        });
+        conv
+    };
+    let first = storage
+        .ensure_workspace(&PathBuf::from("/workspace/app"), None)
+        .unwrap();
     storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("local-1", "local", now, vec![msg(0, now)]),
+            Some(first),
+            &segment(0, 0, "/workspace/app", 0..3),
         )
         .unwrap();
+    let second = storage
+        .ensure_workspace(&PathBuf::from("/workspace/lib"), None)
+        .unwrap();
     storage
         .insert_conversation_tree(
             agent_id,
-            Some(ws_id),
-            &sample_conv_with_source("server-1", "server", now + 100, vec![msg(0, now + 100)]),
+            Some(second),
+            &segment(1, 3, "/workspace/lib", 3..5),
         )
         .unwrap();
 
-    // Query all provenance fields as timeline JSON would
-    let results: Vec<(i64, String, Option<String>, Option<String>)> = storage
+    let stored = storage.get_conversation(id).unwrap().unwrap();
+    assert_eq!(stored.messages.len(), 3);
+    assert_eq!(stored.workspace, Some(PathBuf::from("/workspace/app")));
+    assert_eq!(stored.metadata_json["segment"]["count"], 2);
+    assert_eq!(stored.ended_at, Some(12));
+    let total: i64 = storage
         .raw()
-        .prepare(
-            "SELECT c.id, c.source_id, c.origin_host, s.kind as origin_kind
-             FROM conversations c
-             LEFT JOIN sources s ON c.source_id = s.id",
-        )
-        .unwrap()
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, Option<String>>(2)?,
-                row.get::<_, Option<String>>(3)?,
-            ))
-        })
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect();
-
-    // All entries should have source_id
-    for (id, source_id, _, _) in &results {
-        assert!(
-            !source_id.is_empty(),
-            "Entry {} should have non-empty source_id",
-            id
-        );
-    }
+        .query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(total, 5);
+}
 
-    // Verify we have both local and remote entries with correct kinds
-    let has_local = results
-        .iter()
-        .any(|(_, sid, _, kind)| sid == "local" && kind.as_deref() == Some("local"));
-    let has_remote = results
-        .iter()
-        .any(|(_, sid, _, kind)| sid == "server" && kind.as_deref() == Some("ssh"));
+#[test]
+fn rescanned_conversations_keep_their_labels_current() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let mut storage = SqliteStorage::open(&tmp.path().join("labels.db")).expect("open");
+    let agent_id = storage.ensure_agent(&sample_agent()).unwrap();
+    let labeled = |outcome: &str, messages| {
+        let mut conv = sample_conv(Some("l"), messages);
+        conv.metadata_json = serde_json::json!({
+            "k": "v",
+            "cass": {"labels": [{"key": "outcome", "value": outcome}]}
+        });
+        conv
+    };
+    let id = storage
+        .insert_conversation_tree(agent_id, None, &labeled("unknown", vec![msg(0, 1)]))
+        .unwrap()
+        .conversation_id;
+    storage
+        .insert_conversation_tree(
+            agent_id,
+            None,
+            &labeled("success", vec![msg(0, 1), msg(1, 2)]),
+        )
+        .unwrap();
 
-    assert!(has_local, "should have local entry with kind='local'");
-    assert!(has_remote, "should have remote entry with kind='ssh'");
+    let stored = storage.get_conversation(id).unwrap().unwrap();
+    assert_eq!(
+        stored.metadata_json["cass"]["labels"][0]["value"],
+        "success"
+    );
+    assert_eq!(stored.metadata_json["k"], "v");
 }
@@ -1,4 +1,5 @@
 use coding_agent_search::ui::components::theme::ThemePalette;
+use coding_agent_search::ui::shortcuts::KeyContext;
 use coding_agent_search::ui::tui::{footer_legend, help_lines};
 
 #[test]
@@ -28,7 +29,7 @@ fn help_legend_has_hotkeys() {
 
 /// Convert help lines to a single string for easy searching
 fn help_lines_to_string(palette: ThemePalette) -> String {
-    help_lines(palette)
+    help_lines(palette, KeyContext::Results)
         .iter()
         .map(|line| {
             line.spans
@@ -105,7 +106,7 @@ fn help_modal_mentions_all_agents() {
 
 #[test]
 fn help_modal_line_count_reasonable() {
-    let lines = help_lines(ThemePalette::dark());
+    let lines = help_lines(ThemePalette::dark(), KeyContext::Results);
     let line_count = lines.len();
 
     // Help should be comprehensive but not overwhelming
@@ -128,42 +129,68 @@ fn help_modal_sections_order() {
 
     // Find positions of key sections to verify logical ordering
     let welcome_pos = content.find("Welcome to CASS");
+    let keymap_pos = content.find("Keys: Results (current)");
     let data_locations_pos = content.find("Data Locations");
-    let search_pos = content.find("\nSearch\n").or(content.find("Search\n"));
-    let filters_pos = content.find("\nFilters\n").or(content.find("Filters\n"));
+    let search_tips_pos = content.find("Search tips");
     let sources_pos = content.find("Sources (Multi-Machine)");
-    let navigation_pos = content
-        .find("\nNavigation\n")
-        .or(content.find("Navigation\n"));
-    let actions_pos = content.find("\nActions\n").or(content.find("Actions\n"));
 
-    // Welcome should come first
     assert!(welcome_pos.is_some(), "Help should have Welcome section");
+    assert!(
+        keymap_pos.is_some(),
+        "Help should list the current context's keys"
+    );
     assert!(
         data_locations_pos.is_some(),
         "Help should have Data Locations section"
     );
-    assert!(search_pos.is_some(), "Help should have Search section");
-    assert!(filters_pos.is_some(), "Help should have Filters section");
-    assert!(sources_pos.is_some(), "Help should have Sources section");
     assert!(
-        navigation_pos.is_some(),
-        "Help should have Navigation section"
+        search_tips_pos.is_some(),
+        "Help should have Search tips section"
     );
-    assert!(actions_pos.is_some(), "Help should have Actions section");
-
-    // Verify logical ordering: Welcome → Data Locations → Search → Filters → Sources
-    if let (Some(w), Some(d)) = (welcome_pos, data_locations_pos) {
-        assert!(w < d, "Welcome should come before Data Locations");
+    assert!(sources_pos.is_some(), "Help should have Sources section");
+    for ctx in ["Global", "Search", "Results", "Viewer"] {
+        assert!(
+            content.contains(&format!("Keys: {ctx}")),
+            "Help should have a {ctx} keymap section"
+        );
     }
 
-    if let (Some(s), Some(f)) = (search_pos, filters_pos) {
-        assert!(s < f, "Search should come before Filters");
-    }
+    // Verify logical ordering: Welcome → Keys → Data Locations → Search tips → Sources
+    let order = [
+        welcome_pos,
+        keymap_pos,
+        data_locations_pos,
+        search_tips_pos,
+        sources_pos,
+    ];
+    assert!(
+        order.windows(2).all(|w| w[0] < w[1]),
+        "Help sections out of order: {order:?}"
+    );
+}
 
-    if let (Some(f), Some(src)) = (filters_pos, sources_pos) {
-        assert!(f < src, "Filters should come before Sources");
-    }
+#[test]
+fn help_modal_lists_current_context_first() {
+    let content = help_lines_to_string(ThemePalette::dark());
+    let viewer = help_lines(ThemePalette::dark(), KeyContext::Viewer)
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let first_keys = |text: &str| {
+        text.lines()
+            .find(|l| l.starts_with("Keys: "))
+            .map(str::to_string)
+    };
+    assert_eq!(
+        first_keys(&content).as_deref(),
+        Some("Keys: Results (current)")
+    );
+    assert_eq!(
+        first_keys(&viewer).as_deref(),
+        Some("Keys: Viewer (current)")
+    );
 }
 
 #[test]
@@ -232,8 +259,8 @@ fn help_modal_consistent_across_themes() {
     let light_content = help_lines_to_string(ThemePalette::light());
 
     // Line counts should be identical
-    let dark_lines = help_lines(ThemePalette::dark()).len();
-    let light_lines = help_lines(ThemePalette::light()).len();
+    let dark_lines = help_lines(ThemePalette::dark(), KeyContext::Results).len();
+    let light_lines = help_lines(ThemePalette::light(), KeyContext::Results).len();
     assert_eq!(
         dark_lines, light_lines,
         "Help modal should have same line count in dark and light themes"
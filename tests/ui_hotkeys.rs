@@ -1,4 +1,5 @@
 use coding_agent_search::sources::provenance::SourceFilter;
+use coding_agent_search::ui::shortcuts::KeyContext;
 use coding_agent_search::ui::tui::footer_legend;
 
 #[test]
@@ -23,6 +24,7 @@ fn footer_mentions_editor_and_clear_keys() {
 fn help_includes_detail_find_hotkeys() {
     let lines = coding_agent_search::ui::tui::help_lines(
         coding_agent_search::ui::components::theme::ThemePalette::dark(),
+        KeyContext::Results,
     );
     let text: String = lines.iter().map(|l| l.to_string()).collect();
     assert!(
@@ -43,6 +45,7 @@ fn help_includes_detail_find_hotkeys() {
 fn f11_hotkey_documented_in_help() {
     let lines = coding_agent_search::ui::tui::help_lines(
         coding_agent_search::ui::components::theme::ThemePalette::dark(),
+        KeyContext::Results,
     );
     let text: String = lines.iter().map(|l| l.to_string()).collect();
 
@@ -63,6 +66,7 @@ fn f11_hotkey_documented_in_footer_or_help() {
     let footer = footer_legend(true);
     let help_lines = coding_agent_search::ui::tui::help_lines(
         coding_agent_search::ui::components::theme::ThemePalette::dark(),
+        KeyContext::Results,
     );
     let help_text: String = help_lines.iter().map(|l| l.to_string()).collect();
 
@@ -91,6 +95,7 @@ fn source_filter_cycle_matches_documented_behavior() {
     let content = {
         let lines = coding_agent_search::ui::tui::help_lines(
             coding_agent_search::ui::components::theme::ThemePalette::dark(),
+            KeyContext::Results,
         );
         lines.iter().map(|l| l.to_string()).collect::<String>()
     };
@@ -108,6 +113,7 @@ fn source_filter_cycle_matches_documented_behavior() {
 fn shift_f11_hotkey_documented() {
     let lines = coding_agent_search::ui::tui::help_lines(
         coding_agent_search::ui::components::theme::ThemePalette::dark(),
+        KeyContext::Results,
     );
     let text: String = lines.iter().map(|l| l.to_string()).collect();
 
@@ -270,6 +270,7 @@ impl ConversationFixtureBuilder {
                     content,
                     extra: json!({"seed": i}),
                     snippets,
+                    source: None,
                 }
             })
             .collect();
@@ -331,6 +332,9 @@ impl ConversationFixtureBuilder {
                     content,
                     extra_json: json!({"seed": i}),
                     snippets,
+                    approx_tokens: None,
+                    source_path: None,
+                    source_line: None,
                 }
             })
             .collect();